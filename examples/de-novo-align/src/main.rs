@@ -1,4 +1,4 @@
-use std::{collections::HashMap, fs::File, io::BufWriter};
+use std::{fs::File, io::BufWriter, sync::mpsc::sync_channel};
 
 use align::AlignScoring;
 use clap::Parser;
@@ -6,9 +6,9 @@ use identification::SpectrumIds;
 use itertools::{Itertools, MinMaxResult};
 use rayon::prelude::*;
 use rustyms::{
-    align::{align, AlignType},
-    csv::write_csv,
-    identification::{open_identified_peptides_file, FastaData},
+    align::{align_peptide_to_database, DatabaseAlignmentSettings, DatabaseHit},
+    identification::{open_identified_peptides_file, FastaData, IdentifiedPeptide},
+    row_sink::{CsvWriter, OrderedSink, RowSink},
     *,
 };
 
@@ -23,6 +23,45 @@ struct Cli {
     /// Where to store the results
     #[arg(long)]
     out_path: String,
+    /// Write the results in the same order as the input peptides, otherwise the results are
+    /// written as soon as they are ready
+    #[arg(long)]
+    ordered: bool,
+}
+
+/// The columns of the output file
+const COLUMNS: [&str; 13] = [
+    "Peptide",
+    "Spectra ref",
+    "De novo score",
+    "Protein",
+    "Alignment score",
+    "Unique",
+    "Start",
+    "End",
+    "Path",
+    "Mass",
+    "Z",
+    "Peptide length",
+    "Retention time",
+];
+
+/// A single row of the output file
+type Row = [(&'static str, String); 13];
+
+/// Write all rows for a single peptide at once
+struct Batch<S>(S);
+
+impl<S: RowSink<Row>> RowSink<Vec<Row>> for Batch<S> {
+    type Error = S::Error;
+
+    fn write_row(&mut self, rows: Vec<Row>) -> Result<(), Self::Error> {
+        rows.into_iter().try_for_each(|row| self.0.write_row(row))
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        self.0.finish()
+    }
 }
 
 fn main() {
@@ -40,110 +79,116 @@ fn main() {
         .collect_vec();
     let database = FastaData::parse_file(args.database).unwrap();
 
-    let alignments: Vec<_> = peptides
-        .par_iter()
-        .flat_map(|(peptide, linear_peptide)| {
-            let alignments = database
-                .iter()
-                .map(|db| {
-                    (
-                        db,
-                        peptide,
-                        align::<4, SemiAmbiguous, SemiAmbiguous>(
-                            db.peptide(),
-                            linear_peptide,
-                            AlignScoring::default(),
-                            AlignType::EITHER_GLOBAL,
-                        ),
-                    )
-                })
-                .collect_vec();
-            let max = alignments
-                .iter()
-                .max_by(|a, b| a.2.normalised_score().total_cmp(&b.2.normalised_score()))
-                .unwrap()
-                .2
-                .normalised_score();
-            let mut alignments = alignments
-                .into_iter()
-                .filter(|a| a.2.normalised_score() == max)
-                .collect_vec();
-            if alignments.len() == 1 {
-                let (d, p, a) = alignments.pop().unwrap();
-                vec![(d, p, a, true)]
+    // The rows are streamed to a separate writer thread so only a bounded number of results is
+    // kept in memory at any time
+    let (sender, receiver) = sync_channel::<(usize, Vec<Row>)>(1024);
+    std::thread::scope(|scope| {
+        let writer = scope.spawn(move || {
+            let csv = CsvWriter::new(out_file, COLUMNS).unwrap();
+            if args.ordered {
+                let mut sink = OrderedSink::new(Batch(csv));
+                receiver
+                    .into_iter()
+                    .try_for_each(|rows| sink.write_row(rows))
+                    .and_then(|()| sink.finish())
             } else {
-                alignments
+                let mut sink = Batch(csv);
+                receiver
                     .into_iter()
-                    .map(|(d, p, a)| (d, p, a, false))
-                    .collect_vec()
+                    .try_for_each(|(_, rows)| sink.write_row(rows))
+                    .and_then(|()| sink.finish())
             }
-        })
-        .map(|(db, peptide, alignment, unique)| {
-            HashMap::from([
-                ("Peptide".to_string(), alignment.seq_b().to_string()),
-                (
-                    "Spectra ref".to_string(),
-                    match peptide.scans() {
-                        SpectrumIds::None => String::new(),
-                        SpectrumIds::FileNotKnown(scans) => scans.iter().join(";"),
-                        SpectrumIds::FileKnown(scans) => scans
-                            .iter()
-                            .map(|(file, scans)| {
-                                format!("{}:{}", file.to_string_lossy(), scans.iter().join(";"))
-                            })
-                            .join("|"),
-                    },
-                ),
-                (
-                    "De novo score".to_string(),
-                    peptide.score.map_or(String::new(), |s| s.to_string()),
-                ),
-                ("Protein".to_string(), db.identifier().to_string()),
-                (
-                    "Alignment score".to_string(),
-                    alignment.normalised_score().to_string(),
-                ),
-                ("Unique".to_string(), unique.to_string()),
-                ("Start".to_string(), alignment.start_a().to_string()),
-                (
-                    "End".to_string(),
-                    (alignment.start_a() + alignment.len_a()).to_string(),
-                ),
-                ("Path".to_string(), alignment.short()),
-                (
-                    "Mass".to_string(),
-                    match alignment.seq_b().formulas().mass_bounds() {
-                        MinMaxResult::NoElements => "-".to_string(),
-                        MinMaxResult::OneElement(m) => m.monoisotopic_mass().value.to_string(),
-                        MinMaxResult::MinMax(min, max) => format!(
-                            "{} - {}",
-                            min.monoisotopic_mass().value,
-                            max.monoisotopic_mass().value
-                        ),
-                    },
-                ),
-                (
-                    "Z".to_string(),
-                    peptide.charge().map_or(0, |c| c.value).to_string(),
-                ),
-                (
-                    "Peptide length".to_string(),
-                    peptide
-                        .peptide()
-                        .and_then(|p| p.peptide())
-                        .map_or(0, |p| p.len())
-                        .to_string(),
-                ),
-                (
-                    "Retention time".to_string(),
-                    peptide
-                        .retention_time()
-                        .map_or(f64::NAN, |t| t.value)
-                        .to_string(),
-                ),
-            ])
-        })
-        .collect();
+        });
+
+        peptides.par_iter().enumerate().for_each_with(
+            sender,
+            |sender, (index, (peptide, linear_peptide))| {
+                let rows = align_peptide(&database, peptide, linear_peptide);
+                // If the writer stopped the error is reported when joining the writer
+                let _ = sender.send((index, rows));
+            },
+        );
+
+        writer.join().unwrap().unwrap();
+    });
+}
 
-    write_csv(out_file, alignments).unwrap();
+/// Align a single peptide to all proteins in the database and create the rows for all best
+/// scoring alignments
+fn align_peptide(
+    database: &[FastaData],
+    peptide: &IdentifiedPeptide,
+    linear_peptide: &Peptidoform<SemiAmbiguous>,
+) -> Vec<Row> {
+    let hits = align_peptide_to_database::<4, SemiAmbiguous>(
+        linear_peptide,
+        database,
+        AlignScoring::default(),
+        &DatabaseAlignmentSettings::default(),
+    );
+    let unique = hits.len() == 1;
+    hits.into_iter()
+        .map(
+            |DatabaseHit {
+                 protein: db,
+                 alignment,
+                 ..
+             }| {
+                [
+                    ("Peptide", alignment.seq_b().to_string()),
+                    (
+                        "Spectra ref",
+                        match peptide.scans() {
+                            SpectrumIds::None => String::new(),
+                            SpectrumIds::FileNotKnown(scans) => scans.iter().join(";"),
+                            SpectrumIds::FileKnown(scans) => scans
+                                .iter()
+                                .map(|(file, scans)| {
+                                    format!("{}:{}", file.to_string_lossy(), scans.iter().join(";"))
+                                })
+                                .join("|"),
+                        },
+                    ),
+                    (
+                        "De novo score",
+                        peptide.score.map_or(String::new(), |s| s.to_string()),
+                    ),
+                    ("Protein", db.identifier().to_string()),
+                    ("Alignment score", alignment.normalised_score().to_string()),
+                    ("Unique", unique.to_string()),
+                    ("Start", alignment.start_a().to_string()),
+                    ("End", (alignment.start_a() + alignment.len_a()).to_string()),
+                    ("Path", alignment.short()),
+                    (
+                        "Mass",
+                        match alignment.seq_b().formulas().mass_bounds() {
+                            MinMaxResult::NoElements => "-".to_string(),
+                            MinMaxResult::OneElement(m) => m.monoisotopic_mass().value.to_string(),
+                            MinMaxResult::MinMax(min, max) => format!(
+                                "{} - {}",
+                                min.monoisotopic_mass().value,
+                                max.monoisotopic_mass().value
+                            ),
+                        },
+                    ),
+                    ("Z", peptide.charge().map_or(0, |c| c.value).to_string()),
+                    (
+                        "Peptide length",
+                        peptide
+                            .peptide()
+                            .and_then(|p| p.peptide())
+                            .map_or(0, |p| p.len())
+                            .to_string(),
+                    ),
+                    (
+                        "Retention time",
+                        peptide
+                            .retention_time()
+                            .map_or(f64::NAN, |t| t.value)
+                            .to_string(),
+                    ),
+                ]
+            },
+        )
+        .collect()
 }
@@ -1,9 +1,5 @@
 #![allow(non_snake_case)] // charge_independent_Y needs the capital as it means the glycan fragmentation
-use std::{
-    collections::BTreeMap,
-    fs::File,
-    io::{BufReader, BufWriter},
-};
+use std::{collections::BTreeMap, fs::File, io::BufWriter};
 
 use clap::Parser;
 use directories::ProjectDirs;
@@ -19,7 +15,7 @@ use spectrum::{AnnotatedPeak, PeakSpectrum};
 
 #[derive(Parser)]
 struct Cli {
-    /// The input csv file, should have the following columns: 'mgf_path', 'scan_number', 'z', 'sequence', and can have 'fragmentation' (etd/td_etd/ethcd/etcad/hot eacid/eacid/ead/hcd/cid/all/none, defaults to the global model)
+    /// The input csv file, should have the following columns: 'mgf_path', 'scan_number', 'z', 'sequence', and can have 'fragmentation' (etd/td_etd/ethcd/etcad/hot eacid/eacid/ead/ecd/hcd/cid/cid_hcd/uvpd/negative/all/none, defaults to the global model)
     #[arg(short, long)]
     in_path: String,
     /// The output path to output the resulting csv file
@@ -28,7 +24,7 @@ struct Cli {
     /// The tolerance for matching fragments, use `<x>ppm` or `<x>da` to control the unit, e.g. `10.0ppm` or `2.3da`
     #[arg(short, long, default_value_t = Tolerance::new_ppm(20.0), value_parser=mass_tolerance_parse)]
     pub tolerance: Tolerance<Mass>,
-    /// Global model, will be overruled by line specific models (etd/td_etd/ethcd/etcad/hot eacid/eacid/ead/hcd/cid/all/none)
+    /// Global model, will be overruled by line specific models (etd/td_etd/ethcd/etcad/hot eacid/eacid/ead/ecd/hcd/cid/cid_hcd/uvpd/negative/all/none)
     #[arg(long, default_value_t = String::from("all"))]
     model: String,
     /// Turns on reporting of glycan Y-ions in a charge independent manner
@@ -56,7 +52,12 @@ fn select_model(text: &str, default: &Model) -> Model {
         "ethcd" | "etcad" => Model::ethcd(),
         "hot eacid" | "eacid" => Model::hot_eacid(),
         "ead" => Model::ead(),
-        "hcd" | "cid" => Model::cid_hcd(),
+        "ecd" => Model::ecd(),
+        "hcd" => Model::hcd(),
+        "cid" => Model::cid(),
+        "cid_hcd" => Model::cid_hcd(),
+        "uvpd" => Model::uvpd(),
+        "negative" => Model::negative_mode(),
         "all" => Model::all(),
         "none" => Model::none(),
         _ => default.clone(),
@@ -73,7 +74,7 @@ fn main() {
     let custom_database = if args.no_custom_mods || !path.exists() {
         None
     } else {
-        Some(serde_json::from_reader(BufReader::new(File::open(path).unwrap())).unwrap())
+        Some(rustyms::ontologies::load_custom_database(path).unwrap())
     };
     let files = rustyms::csv::parse_csv(args.in_path, b',', None)
         .unwrap()
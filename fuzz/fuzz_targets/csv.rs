@@ -0,0 +1,15 @@
+use afl::*;
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        if let Ok(csv) = rustyms::csv::parse_csv_raw(data, b',', None) {
+            for line in csv.flatten() {
+                for column in 0..line.number_of_columns() {
+                    let _ = &line[column];
+                    let _ = line.column_context(column).to_string();
+                }
+                let _ = line.to_string();
+            }
+        }
+    });
+}
@@ -0,0 +1,11 @@
+use afl::*;
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        if let Ok(s) = std::str::from_utf8(data) {
+            if let Err(error) = rustyms::MolecularFormula::from_pro_forma(s, .., true, true, true) {
+                let _ = error.to_string();
+            }
+        }
+    });
+}
@@ -0,0 +1,11 @@
+use afl::*;
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        for spectrum in rustyms::rawfile::mgf::MgfReader::new(data) {
+            if let Err(error) = spectrum {
+                let _ = error.to_string();
+            }
+        }
+    });
+}
@@ -1,4 +1,4 @@
-use std::{collections::HashMap, io::Write, path::Path, sync::Arc};
+use std::{collections::BTreeMap, io::Write, path::Path, sync::Arc};
 
 use crate::{csv::parse_csv, glycan::*, SimpleModification};
 
@@ -61,7 +61,7 @@ pub fn build_gnome_ontology(out_dir: &Path) {
         .unwrap();
 }
 
-fn find_mass(mods: &HashMap<String, GNOmeModification>, mut name: String) -> Option<f64> {
+fn find_mass(mods: &BTreeMap<String, GNOmeModification>, mut name: String) -> Option<f64> {
     let mut mass = None;
     while mass.is_none() {
         mass = mods.get(&name)?.weight;
@@ -116,10 +116,10 @@ impl std::str::FromStr for GnoSubsumption {
     }
 }
 
-fn parse_gnome() -> HashMap<String, GNOmeModification> {
+fn parse_gnome() -> BTreeMap<String, GNOmeModification> {
     let obo = OboOntology::from_file("rustyms-generate-databases/data/GNOme.obo.gz")
         .expect("Not a valid obo file");
-    let mut mods = HashMap::new();
+    let mut mods = BTreeMap::new();
 
     for obj in obo.objects {
         if obj.name != "Term" || !obj.lines.contains_key("is_a") {
@@ -217,8 +217,8 @@ struct GlycosmosList {
     glycomeatlas: Vec<(String, Vec<(String, String)>)>,
 }
 
-fn parse_gnome_structures() -> HashMap<String, GlycosmosList> {
-    let mut glycans = HashMap::new();
+fn parse_gnome_structures() -> BTreeMap<String, GlycosmosList> {
+    let mut glycans = BTreeMap::new();
     let mut errors = 0;
     for line in parse_csv(
         "rustyms-generate-databases/data/glycosmos_glycans_list.csv.gz",
@@ -26,6 +26,7 @@ mod csv;
 pub mod error;
 pub mod glycan;
 mod gnome;
+#[path = "../../rustyms/src/shared/obo.rs"]
 mod obo;
 mod ontology_modification;
 mod psi_mod;
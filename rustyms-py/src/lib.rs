@@ -3,7 +3,6 @@
 use std::fmt::Debug;
 use std::num::NonZeroU16;
 
-use ordered_float::OrderedFloat;
 use pyo3::{exceptions::PyValueError, prelude::*, types::PyType};
 
 use rustyms::{AnnotatableSpectrum, Chemical, Linked, MultiChemical};
@@ -763,6 +762,11 @@ enum FragmentationModel {
     CidHcd,
     Etd,
     Ethcd,
+    Hcd,
+    Cid,
+    Ecd,
+    Uvpd,
+    NegativeMode,
 }
 
 /// Helper function to match a [`FragmentationModel`] to a rustyms Model.
@@ -772,6 +776,11 @@ fn match_model(model: &FragmentationModel) -> PyResult<rustyms::Model> {
         FragmentationModel::CidHcd => Ok(rustyms::Model::cid_hcd()),
         FragmentationModel::Etd => Ok(rustyms::Model::etd()),
         FragmentationModel::Ethcd => Ok(rustyms::Model::ethcd()),
+        FragmentationModel::Hcd => Ok(rustyms::Model::hcd()),
+        FragmentationModel::Cid => Ok(rustyms::Model::cid()),
+        FragmentationModel::Ecd => Ok(rustyms::Model::ecd()),
+        FragmentationModel::Uvpd => Ok(rustyms::Model::uvpd()),
+        FragmentationModel::NegativeMode => Ok(rustyms::Model::negative_mode()),
     }
 }
 
@@ -1238,6 +1247,17 @@ impl RawPeak {
     fn intensity(&self) -> f64 {
         self.0.intensity.into_inner()
     }
+
+    /// The ion mobility of the peak, for timsTOF data this is the inverse reduced mobility (1/K0).
+    ///
+    /// Returns
+    /// -------
+    /// float | None
+    ///
+    #[getter]
+    fn ion_mobility(&self) -> Option<f64> {
+        self.0.ion_mobility
+    }
 }
 
 impl std::fmt::Display for RawPeak {
@@ -1388,9 +1408,11 @@ impl RawSpectrum {
         let peaks = mz_array
             .into_iter()
             .zip(intensity_array)
-            .map(|(mz, i)| rustyms::spectrum::RawPeak {
-                mz: rustyms::system::MassOverCharge::new::<rustyms::system::mz>(mz),
-                intensity: OrderedFloat(i),
+            .map(|(mz, i)| {
+                rustyms::spectrum::RawPeak::new(
+                    rustyms::system::MassOverCharge::new::<rustyms::system::mz>(mz),
+                    i,
+                )
             })
             .collect::<Vec<_>>();
 
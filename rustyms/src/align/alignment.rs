@@ -13,6 +13,7 @@ use super::scoring::*;
 
 use crate::align::mass_alignment::determine_final_score;
 use crate::align::mass_alignment::score_pair;
+use crate::error::{Context, CustomError};
 use crate::helper_functions::next_num;
 use crate::peptidoform::AtMax;
 use crate::peptidoform::Linear;
@@ -21,6 +22,7 @@ use crate::system::Ratio;
 use crate::MolecularFormula;
 use crate::Multi;
 use crate::Peptidoform;
+use crate::SequenceElement;
 use crate::SequencePosition;
 use crate::SimpleLinear;
 
@@ -126,38 +128,7 @@ impl<'lifetime, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>> Alignment<'lifet
         align_type: AlignType,
         maximal_step: u16,
     ) -> Option<Self> {
-        let mut index = 0;
-        let mut steps = Vec::new();
-        while index < path.len() {
-            if let Some((offset, num)) = next_num(path.as_bytes(), index, false) {
-                let num = u16::try_from(num).unwrap();
-                index += offset + 1;
-                match path.as_bytes()[index - 1] {
-                    b'I' => steps.push((MatchType::Gap, 0, num)),
-                    b'D' => steps.push((MatchType::Gap, num, 0)),
-                    b'X' => steps.push((MatchType::Mismatch, num, num)),
-                    b'm' => steps.push((MatchType::IdentityMassMismatch, num, num)),
-                    b'=' => steps.push((MatchType::FullIdentity, num, num)),
-                    b'r' => steps.push((MatchType::Rotation, num, num)),
-                    b'i' => steps.push((MatchType::Isobaric, num, num)),
-                    b':' => {
-                        if let Some((offset, num2)) = next_num(path.as_bytes(), index, false) {
-                            let num2 = u16::try_from(num2).unwrap();
-                            index += offset + 1;
-                            match path.as_bytes()[index - 1] {
-                                b'i' => steps.push((MatchType::Isobaric, num, num2)),
-                                _ => return None,
-                            }
-                        } else {
-                            return None;
-                        }
-                    }
-                    _ => return None,
-                }
-            } else {
-                return None;
-            }
-        }
+        let steps = parse_short(path)?;
 
         let mut index_a = start_a;
         let mut index_b = start_b;
@@ -231,6 +202,7 @@ impl<'lifetime, A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>> Alignment<'lifet
                             let piece = score_pair(
                                 (&seq_a[index_a], &mass_a),
                                 (&seq_b[index_b], &mass_b),
+                                index_a,
                                 scoring,
                                 score,
                             );
@@ -346,6 +318,36 @@ impl<A, B> Alignment<'_, A, B> {
         self.path().iter().map(|p| p.step_b as usize).sum()
     }
 
+    /// Iterate over all steps in this alignment, with for every step the aligned residues from
+    /// both sequences and the type of the step. This gives the same information as
+    /// [`Self::short`] but per position, which makes it easier to display an alignment.
+    /// ```rust
+    /// # use rustyms::{*, align::*};
+    /// let a = Peptidoform::pro_forma("ANGARS", None).unwrap().into_simple_linear().unwrap();
+    /// let b = Peptidoform::pro_forma("AGGQRS", None).unwrap().into_simple_linear().unwrap();
+    /// let alignment = align::<4, _, _>(&a, &b, AlignScoring::default(), AlignType::GLOBAL);
+    /// let columns = alignment.columns().collect::<Vec<_>>();
+    /// assert_eq!(columns[1].piece.match_type, MatchType::Isobaric);
+    /// assert_eq!((columns[1].a.len(), columns[1].b.len()), (1, 2));
+    /// assert!(columns[1].mass_difference().value.abs() < 1e-6);
+    /// ```
+    pub fn columns(&self) -> impl Iterator<Item = AlignmentColumn<'_, A, B>> + '_ {
+        self.path
+            .iter()
+            .scan((self.start_a, self.start_b), |(index_a, index_b), piece| {
+                let column = AlignmentColumn {
+                    index_a: *index_a,
+                    index_b: *index_b,
+                    a: &self.seq_a.sequence()[*index_a..*index_a + piece.step_a as usize],
+                    b: &self.seq_b.sequence()[*index_b..*index_b + piece.step_b as usize],
+                    piece,
+                };
+                *index_a += piece.step_a as usize;
+                *index_b += piece.step_b as usize;
+                Some(column)
+            })
+    }
+
     /// Returns statistics for this match.
     pub fn stats(&self) -> Stats {
         let (identical, mass_similar, similar, gaps, length) =
@@ -526,6 +528,243 @@ impl<A: AtMax<Linear>, B: AtMax<Linear>> Alignment<'_, A, B> {
     }
 }
 
+impl<A: AtMax<Linear>, B: AtMax<Linear>> Alignment<'_, A, B> {
+    /// Get a compact representation of this alignment that does not contain the sequences. This
+    /// can be stored and later restored with [`CompactAlignment::restore`] without having to
+    /// recompute the alignment.
+    pub fn to_compact(&self) -> CompactAlignment {
+        CompactAlignment {
+            start_a: self.start_a,
+            start_b: self.start_b,
+            path: self.short(),
+            local_scores: self.path.iter().map(|p| p.local_score).collect(),
+            score: self.score,
+            align_type: self.align_type,
+            maximal_step: self.maximal_step,
+        }
+    }
+}
+
+/// A compact representation of an [`Alignment`], without the sequences. The path is stored in
+/// the CIGAR like format of [`Alignment::short`] together with the local score for every step.
+/// Together with the original sequences this gives back the exact same alignment, see
+/// [`Self::restore`]. This can be used to cache alignment results on disk, either with any serde
+/// format or with the binary format from [`Self::to_bytes`].
+/// ```rust
+/// # use rustyms::{*, align::*};
+/// let a = Peptidoform::pro_forma("ANGARS", None).unwrap().into_simple_linear().unwrap();
+/// let b = Peptidoform::pro_forma("AGGQRS", None).unwrap().into_simple_linear().unwrap();
+/// let alignment = align::<4, _, _>(&a, &b, AlignScoring::default(), AlignType::GLOBAL);
+/// let bytes = alignment.to_compact().to_bytes();
+/// let restored = CompactAlignment::from_bytes(&bytes).unwrap().restore(&a, &b).unwrap();
+/// assert_eq!(alignment, restored);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct CompactAlignment {
+    /// The position in the first sequence where the alignment starts
+    pub start_a: usize,
+    /// The position in the second sequence where the alignment starts
+    pub start_b: usize,
+    /// The path in the format of [`Alignment::short`]
+    pub path: String,
+    /// The local score of every step in the path
+    pub local_scores: Vec<isize>,
+    /// The scores of the alignment
+    pub score: Score,
+    /// The alignment type
+    pub align_type: AlignType,
+    /// The maximal step size (the const generic STEPS)
+    pub maximal_step: u16,
+}
+
+impl CompactAlignment {
+    /// Restore the full alignment by combining this representation with the sequences that were
+    /// aligned. No alignment or scoring is done, the scores are taken as stored.
+    /// # Errors
+    /// If the path is not valid, or if the path does not fit on the given sequences.
+    pub fn restore<'lifetime, A, B>(
+        &self,
+        seq_a: &'lifetime Peptidoform<A>,
+        seq_b: &'lifetime Peptidoform<B>,
+    ) -> Result<Alignment<'lifetime, A, B>, CustomError> {
+        let steps = parse_short(&self.path).ok_or_else(|| {
+            CustomError::error(
+                "Invalid alignment path",
+                "The path is not in the correct format",
+                Context::show(&self.path),
+            )
+        })?;
+        let mut local_scores = self.local_scores.iter();
+        let mut score = 0;
+        let mut path = Vec::with_capacity(self.local_scores.len());
+        let mut piece = |match_type, step_a, step_b| {
+            local_scores.next().map(|local_score| {
+                score += local_score;
+                path.push(Piece::new(score, *local_score, match_type, step_a, step_b));
+            })
+        };
+        let mut missing = false;
+        for (match_type, a, b) in steps {
+            let fits = match match_type {
+                MatchType::Gap => (0..a.max(b))
+                    .all(|_| piece(match_type, u16::from(a != 0), u16::from(b != 0)).is_some()),
+                MatchType::FullIdentity | MatchType::IdentityMassMismatch | MatchType::Mismatch => {
+                    (0..a).all(|_| piece(match_type, 1, 1).is_some())
+                }
+                MatchType::Rotation | MatchType::Isobaric => piece(match_type, a, b).is_some(),
+            };
+            missing |= !fits;
+        }
+        if missing || local_scores.next().is_some() {
+            return Err(CustomError::error(
+                "Invalid compact alignment",
+                format!(
+                    "The number of local scores ({}) does not match the number of steps in the path",
+                    self.local_scores.len()
+                ),
+                Context::show(&self.path),
+            ));
+        }
+        let len_a: usize = path.iter().map(|p| p.step_a as usize).sum();
+        let len_b: usize = path.iter().map(|p| p.step_b as usize).sum();
+        if self.start_a + len_a > seq_a.len() || self.start_b + len_b > seq_b.len() {
+            return Err(CustomError::error(
+                "Invalid compact alignment",
+                format!(
+                    "The alignment covers {}..{} and {}..{} but the sequences have lengths {} and {}",
+                    self.start_a,
+                    self.start_a + len_a,
+                    self.start_b,
+                    self.start_b + len_b,
+                    seq_a.len(),
+                    seq_b.len()
+                ),
+                Context::show(&self.path),
+            ));
+        }
+        Ok(Alignment {
+            seq_a: Cow::Borrowed(seq_a),
+            seq_b: Cow::Borrowed(seq_b),
+            score: self.score,
+            path,
+            start_a: self.start_a,
+            start_b: self.start_b,
+            align_type: self.align_type,
+            maximal_step: self.maximal_step,
+        })
+    }
+
+    /// Store this alignment in a compact binary format.
+    #[allow(clippy::missing_panics_doc)] // Serialising into a Vec cannot fail
+    pub fn to_bytes(&self) -> Vec<u8> {
+        bincode::serialize(self).unwrap()
+    }
+
+    /// Read an alignment from the binary format from [`Self::to_bytes`].
+    /// # Errors
+    /// If the bytes are not a valid compact alignment.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, CustomError> {
+        bincode::deserialize(bytes).map_err(|err| {
+            CustomError::error(
+                "Invalid compact alignment",
+                format!("The binary data could not be read: {err}"),
+                Context::none(),
+            )
+        })
+    }
+}
+
+/// Parse a path in the format of [`Alignment::short`] into the steps, as the match type and the
+/// step sizes on both sequences.
+fn parse_short(path: &str) -> Option<Vec<(MatchType, u16, u16)>> {
+    let mut index = 0;
+    let mut steps = Vec::new();
+    while index < path.len() {
+        let (offset, num) = next_num(path.as_bytes(), index, false)?;
+        let num = u16::try_from(num).ok()?;
+        index += offset + 1;
+        match path.as_bytes().get(index - 1)? {
+            b'I' => steps.push((MatchType::Gap, 0, num)),
+            b'D' => steps.push((MatchType::Gap, num, 0)),
+            b'X' => steps.push((MatchType::Mismatch, num, num)),
+            b'm' => steps.push((MatchType::IdentityMassMismatch, num, num)),
+            b'=' => steps.push((MatchType::FullIdentity, num, num)),
+            b'r' => steps.push((MatchType::Rotation, num, num)),
+            b'i' => steps.push((MatchType::Isobaric, num, num)),
+            b':' => {
+                let (offset, num2) = next_num(path.as_bytes(), index, false)?;
+                let num2 = u16::try_from(num2).ok()?;
+                index += offset + 1;
+                match path.as_bytes().get(index - 1)? {
+                    b'i' => steps.push((MatchType::Isobaric, num, num2)),
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        }
+    }
+    Some(steps)
+}
+
+/// A single step in an alignment with the aligned residues from both sequences, see
+/// [`Alignment::columns`]. A gap has no residues for one of the sequences, while isobaric and
+/// rotated steps can have multiple residues for both sequences.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlignmentColumn<'a, A, B> {
+    /// The index in the first sequence of the first residue of this step
+    pub index_a: usize,
+    /// The index in the second sequence of the first residue of this step
+    pub index_b: usize,
+    /// The residues, including modifications, from the first sequence
+    pub a: &'a [SequenceElement<A>],
+    /// The residues, including modifications, from the second sequence
+    pub b: &'a [SequenceElement<B>],
+    /// The step in the path, with the match type and score
+    pub piece: &'a Piece,
+}
+
+impl<A, B> AlignmentColumn<'_, A, B> {
+    /// The mass(es) for the residues of the first sequence in this step
+    pub fn mass_a(&self) -> Multi<MolecularFormula> {
+        Self::mass(self.a, self.index_a)
+    }
+
+    /// The mass(es) for the residues of the second sequence in this step
+    pub fn mass_b(&self) -> Multi<MolecularFormula> {
+        Self::mass(self.b, self.index_b)
+    }
+
+    /// Get the mass delta for this step (a - b). If there are multiple possible masses for any of
+    /// the sides it returns the smallest difference.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn mass_difference(&self) -> Mass {
+        self.mass_a()
+            .iter()
+            .cartesian_product(self.mass_b().iter())
+            .map(|(a, b)| a.monoisotopic_mass() - b.monoisotopic_mass())
+            .min_by(|a, b| a.abs().value.total_cmp(&b.abs().value))
+            .expect("An empty Multi<MolecularFormula>  was detected")
+    }
+
+    fn mass<T>(residues: &[SequenceElement<T>], start: usize) -> Multi<MolecularFormula> {
+        residues
+            .iter()
+            .enumerate()
+            .fold(Multi::default(), |acc, (index, s)| {
+                acc * s
+                    .formulas_all(
+                        &[],
+                        &[],
+                        &mut Vec::new(),
+                        false,
+                        SequencePosition::Index(start + index),
+                        0,
+                    )
+                    .0
+            })
+    }
+}
+
 /// Statistics for an alignment with some helper functions to easily retrieve the number of interest.
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub struct Stats {
@@ -595,7 +834,7 @@ pub struct Score {
 #[allow(clippy::missing_panics_doc)]
 mod tests {
     use crate::{
-        align::{align, AlignScoring, AlignType},
+        align::{align, AlignScoring, AlignType, Alignment, CompactAlignment, MatchType},
         peptidoform::SimpleLinear,
         AminoAcid, MultiChemical, Peptidoform,
     };
@@ -675,4 +914,115 @@ mod tests {
             "{mass_diff_bc} (peptides) should be equal to {mass_diff_nd} (ND)"
         );
     }
+
+    #[test]
+    fn compact_round_trip() {
+        let a = Peptidoform::pro_forma("MAGGWHKTLNASRDEK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("WHKTGGQSRK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let alignment = align::<4, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring::default(),
+            AlignType::LOCAL,
+        );
+        let compact = alignment.to_compact();
+        assert_eq!(compact.path, alignment.short());
+        assert_eq!(compact.restore(&a, &b).unwrap(), alignment);
+        let bytes = compact.to_bytes();
+        assert_eq!(CompactAlignment::from_bytes(&bytes).unwrap(), compact);
+        let json = serde_json::to_string(&compact).unwrap();
+        assert_eq!(
+            serde_json::from_str::<CompactAlignment>(&json).unwrap(),
+            compact
+        );
+        // The full alignment including the sequences
+        let json = serde_json::to_string(&alignment).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Alignment<SimpleLinear, SimpleLinear>>(&json).unwrap(),
+            alignment
+        );
+    }
+
+    #[test]
+    fn compact_invalid() {
+        let a = Peptidoform::pro_forma("ANGARS", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("AGGQRS", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let alignment = align::<4, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring::default(),
+            AlignType::GLOBAL,
+        );
+        let compact = alignment.to_compact();
+        let short = Peptidoform::pro_forma("ANG", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        assert!(compact.restore(&short, &b).is_err());
+        let mut missing = compact.clone();
+        missing.local_scores.pop();
+        assert!(missing.restore(&a, &b).is_err());
+        let mut invalid = compact;
+        invalid.path = String::from("3Q");
+        assert!(invalid.restore(&a, &b).is_err());
+        assert!(CompactAlignment::from_bytes(&[1, 2]).is_err());
+    }
+
+    #[test]
+    fn columns() {
+        let a = Peptidoform::pro_forma("MAGGWHKTLNASRDEK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("WHKTGGQSR[Oxidation]K", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let alignment = align::<4, SimpleLinear, SimpleLinear>(
+            &a,
+            &b,
+            AlignScoring::default(),
+            AlignType::LOCAL,
+        );
+        let columns = alignment.columns().collect::<Vec<_>>();
+        assert_eq!(columns.len(), alignment.path().len());
+        assert_eq!(columns[0].index_a, alignment.start_a());
+        assert_eq!(columns[0].index_b, alignment.start_b());
+        let last = columns.last().unwrap();
+        assert_eq!(
+            last.index_a + last.a.len(),
+            alignment.start_a() + alignment.len_a()
+        );
+        assert_eq!(
+            last.index_b + last.b.len(),
+            alignment.start_b() + alignment.len_b()
+        );
+        for column in &columns {
+            match column.piece.match_type {
+                MatchType::Gap => assert!(column.a.is_empty() || column.b.is_empty()),
+                MatchType::FullIdentity | MatchType::Isobaric | MatchType::Rotation => {
+                    assert!(column.mass_difference().value.abs() < 1e-6);
+                }
+                MatchType::IdentityMassMismatch => {
+                    assert!((column.mass_difference().value + 15.994_915).abs() < 1e-3);
+                }
+                MatchType::Mismatch => (),
+            }
+        }
+        assert!(columns
+            .iter()
+            .any(|c| c.piece.match_type == MatchType::IdentityMassMismatch));
+    }
 }
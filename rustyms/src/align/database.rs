@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identification::FastaData,
+    peptidoform::{AtMax, SemiAmbiguous, SimpleLinear},
+    Peptidoform,
+};
+
+use super::{align, AlignScoring, AlignType, Alignment};
+
+/// The settings for aligning peptides to a database, see [`align_to_database`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DatabaseAlignmentSettings {
+    /// The alignment type, with the protein from the database as the first sequence and the
+    /// peptide as the second sequence.
+    ///
+    /// Default: [`AlignType::EITHER_GLOBAL`].
+    pub align_type: AlignType,
+    /// The number of best scoring alignments that are kept for every peptide.
+    ///
+    /// Default: 1.
+    pub top: usize,
+    /// If alignments that have the same score as the last kept alignment are kept as well, which
+    /// means that more than `top` alignments can be returned for a peptide.
+    ///
+    /// Default: true.
+    pub ties: bool,
+}
+
+impl Default for DatabaseAlignmentSettings {
+    fn default() -> Self {
+        Self {
+            align_type: AlignType::EITHER_GLOBAL,
+            top: 1,
+            ties: true,
+        }
+    }
+}
+
+impl DatabaseAlignmentSettings {
+    /// Set the alignment type
+    #[must_use]
+    pub const fn align_type(self, align_type: AlignType) -> Self {
+        Self { align_type, ..self }
+    }
+
+    /// Set the number of alignments to keep for every peptide
+    #[must_use]
+    pub const fn top(self, top: usize) -> Self {
+        Self { top, ..self }
+    }
+
+    /// Set if ties for the last place are kept
+    #[must_use]
+    pub const fn ties(self, ties: bool) -> Self {
+        Self { ties, ..self }
+    }
+}
+
+/// A single alignment of a peptide to a protein from the database
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DatabaseHit<'lifetime, A> {
+    /// The protein
+    pub protein: &'lifetime FastaData,
+    /// The alignment, with the protein as the first sequence and the peptide as the second
+    pub alignment: Alignment<'lifetime, SemiAmbiguous, A>,
+    /// The rank of this hit, 1 for the best scoring hit(s), hits with the same score have the
+    /// same rank
+    pub rank: usize,
+}
+
+/// Align all peptides to all proteins in the database and keep the best scoring alignments for
+/// every peptide, see [`DatabaseAlignmentSettings`]. The alignments are sorted on descending
+/// normalised score. The results are in the same order as the given peptides. If the crate
+/// feature `rayon` is turned on (the default) the peptides are aligned in parallel.
+///
+/// Only available with crate feature `identification`.
+/// ```rust
+/// # use rustyms::{*, align::*, identification::FastaData};
+/// let database = FastaData::parse_reader(
+///     std::io::BufReader::new(">P1\nMKWVTFISLLLLFSSAYSRGV\n>P2\nMPEPTIDEKAAGRL".as_bytes()),
+///     None,
+/// )
+/// .unwrap();
+/// let peptides = ["PEPTLDEK", "FLSLLLLF"].map(|p| {
+///     Peptidoform::pro_forma(p, None).unwrap().into_simple_linear().unwrap()
+/// });
+/// let results = align_to_database::<4, _>(
+///     &peptides,
+///     &database,
+///     AlignScoring::default(),
+///     &DatabaseAlignmentSettings::default(),
+/// );
+/// assert_eq!(results[0][0].protein.identifier().accession(), "P2");
+/// assert_eq!(results[1][0].protein.identifier().accession(), "P1");
+/// ```
+pub fn align_to_database<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear> + Send + Sync>(
+    peptides: &'lifetime [Peptidoform<A>],
+    database: &'lifetime [FastaData],
+    scoring: AlignScoring<'lifetime>,
+    settings: &DatabaseAlignmentSettings,
+) -> Vec<Vec<DatabaseHit<'lifetime, A>>> {
+    let align =
+        |peptide| align_peptide_to_database::<STEPS, A>(peptide, database, scoring, settings);
+    #[cfg(feature = "rayon")]
+    {
+        use rayon::prelude::*;
+        peptides.par_iter().map(align).collect()
+    }
+    #[cfg(not(feature = "rayon"))]
+    {
+        peptides.iter().map(align).collect()
+    }
+}
+
+/// Align a single peptide to all proteins in the database and keep the best scoring alignments,
+/// see [`align_to_database`].
+///
+/// Only available with crate feature `identification`.
+pub fn align_peptide_to_database<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear>>(
+    peptide: &'lifetime Peptidoform<A>,
+    database: &'lifetime [FastaData],
+    scoring: AlignScoring<'lifetime>,
+    settings: &DatabaseAlignmentSettings,
+) -> Vec<DatabaseHit<'lifetime, A>> {
+    top_hits::<STEPS, A>(database, peptide, scoring, settings)
+}
+
+/// Align the peptide to all given proteins and keep the best scoring alignments
+pub(super) fn top_hits<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear>>(
+    proteins: impl IntoIterator<Item = &'lifetime FastaData>,
+    peptide: &'lifetime Peptidoform<A>,
+    scoring: AlignScoring<'lifetime>,
+    settings: &DatabaseAlignmentSettings,
+) -> Vec<DatabaseHit<'lifetime, A>> {
+    let top = settings.top.max(1);
+    let mut hits: Vec<DatabaseHit<'lifetime, A>> = Vec::with_capacity(top + 1);
+    for protein in proteins {
+        let alignment = align::<STEPS, SemiAmbiguous, A>(
+            protein.peptide(),
+            peptide,
+            scoring,
+            settings.align_type,
+        );
+        if hits.len() >= top && hits[top - 1].alignment > alignment {
+            continue;
+        }
+        // Insert after all hits with the same score to keep the database order for ties
+        let index = hits.partition_point(|h| h.alignment >= alignment);
+        hits.insert(
+            index,
+            DatabaseHit {
+                protein,
+                alignment,
+                rank: 0,
+            },
+        );
+        if hits.len() > top {
+            if settings.ties {
+                let threshold = hits[top - 1].alignment.score().normalised;
+                let keep = hits.partition_point(|h| h.alignment.score().normalised >= threshold);
+                hits.truncate(keep);
+            } else {
+                hits.truncate(top);
+            }
+        }
+    }
+    for index in 0..hits.len() {
+        hits[index].rank = if index > 0
+            && hits[index - 1].alignment.score().normalised
+                == hits[index].alignment.score().normalised
+        {
+            hits[index - 1].rank
+        } else {
+            index + 1
+        };
+    }
+    hits
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+
+    fn database() -> Vec<FastaData> {
+        FastaData::parse_reader(
+            BufReader::new(
+                ">A\nMAGGWHKTLRS\n>B\nKKPEPTIDEKMS\n>C\nMAGGWHKTLRS\n>D\nPPPPPPPPPP".as_bytes(),
+            ),
+            None,
+        )
+        .unwrap()
+    }
+
+    fn linear(aa: &str) -> Peptidoform<SimpleLinear> {
+        Peptidoform::pro_forma(aa, None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn top_hits() {
+        let database = database();
+        let peptides = [linear("AGGWHKT"), linear("PEPTIDEK")];
+        let results = align_to_database::<4, SimpleLinear>(
+            &peptides,
+            &database,
+            AlignScoring::default(),
+            &DatabaseAlignmentSettings::default(),
+        );
+        assert_eq!(results.len(), 2);
+        // A and C are identical so both are kept as a tie
+        assert_eq!(
+            results[0]
+                .iter()
+                .map(|h| (h.protein.identifier().accession(), h.rank))
+                .collect::<Vec<_>>(),
+            [("A", 1), ("C", 1)]
+        );
+        assert_eq!(results[1].len(), 1);
+        assert_eq!(results[1][0].protein.identifier().accession(), "B");
+        assert_eq!(results[1][0].alignment.start_a(), 2);
+
+        let results = align_to_database::<4, SimpleLinear>(
+            &peptides,
+            &database,
+            AlignScoring::default(),
+            &DatabaseAlignmentSettings::default().ties(false),
+        );
+        assert_eq!(results[0].len(), 1);
+        assert_eq!(results[0][0].protein.identifier().accession(), "A");
+    }
+
+    #[test]
+    fn top_n() {
+        let database = database();
+        let peptide = linear("PEPTIDEK");
+        let hits = align_peptide_to_database::<4, SimpleLinear>(
+            &peptide,
+            &database,
+            AlignScoring::default(),
+            &DatabaseAlignmentSettings::default().top(2).ties(false),
+        );
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].rank, 1);
+        assert_eq!(hits[1].rank, 2);
+        assert!(hits[0].alignment > hits[1].alignment);
+        let hits = align_peptide_to_database::<4, SimpleLinear>(
+            &peptide,
+            &database,
+            AlignScoring::default(),
+            &DatabaseAlignmentSettings::default().top(10),
+        );
+        assert_eq!(hits.len(), 4);
+        assert!(hits.windows(2).all(|w| w[0].alignment >= w[1].alignment));
+    }
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+
+use crate::{
+    identification::FastaData,
+    peptidoform::{AtMax, SimpleLinear},
+    AminoAcid, Peptidoform, SequenceElement,
+};
+
+use super::{
+    database::{top_hits, DatabaseAlignmentSettings, DatabaseHit},
+    AlignScoring,
+};
+
+/// An index of all k-mers in a protein database that can be used to quickly find the proteins
+/// that share at least some k-mers with a peptide. Only these candidate proteins are then aligned
+/// with the (much more expensive) mass based alignment. For this the amino acids I, L, and J are
+/// seen as the same amino acid, as these cannot be distinguished based on mass.
+///
+/// The length of the k-mers determines the sensitivity, short k-mers find more candidates, also
+/// for peptides with more errors, while long k-mers give a faster search. Peptides that are
+/// shorter than the k-mer length cannot be prefiltered, for these all proteins are candidates.
+///
+/// Only available with crate feature `identification`.
+/// ```rust
+/// # use rustyms::{*, align::*, identification::FastaData};
+/// let database = FastaData::parse_reader(
+///     std::io::BufReader::new(">P1\nMKWVTFISLLLLFSSAYSRGV\n>P2\nMPEPTIDEKAAGRL".as_bytes()),
+///     None,
+/// )
+/// .unwrap();
+/// let index = KmerIndex::new(&database, 4);
+/// let peptide = Peptidoform::pro_forma("PEPTLDEK", None).unwrap().into_simple_linear().unwrap();
+/// assert_eq!(index.candidates(&peptide), [(1, 5)]);
+/// let hits = index.align::<4, _>(&peptide, AlignScoring::default(), &DatabaseAlignmentSettings::default());
+/// assert_eq!(hits[0].protein.identifier().accession(), "P2");
+/// ```
+#[derive(Clone, Debug)]
+pub struct KmerIndex<'lifetime> {
+    /// The database
+    database: &'lifetime [FastaData],
+    /// The k-mer length
+    k: usize,
+    /// The minimal number of shared k-mers for a protein to be a candidate
+    min_shared: usize,
+    /// For every k-mer all proteins (sorted index in the database) that contain it
+    index: HashMap<u64, Vec<usize>>,
+}
+
+impl<'lifetime> KmerIndex<'lifetime> {
+    /// The longest supported k-mer length
+    pub const MAX_K: usize = 12;
+
+    /// Build the index for the given database with the given k-mer length. The k-mer length is
+    /// clamped to `1..=MAX_K`.
+    pub fn new(database: &'lifetime [FastaData], k: usize) -> Self {
+        let k = k.clamp(1, Self::MAX_K);
+        let mut index: HashMap<u64, Vec<usize>> = HashMap::new();
+        for (protein_index, protein) in database.iter().enumerate() {
+            for kmer in kmers(protein.peptide().sequence(), k) {
+                let proteins = index.entry(kmer).or_default();
+                if proteins.last() != Some(&protein_index) {
+                    proteins.push(protein_index);
+                }
+            }
+        }
+        Self {
+            database,
+            k,
+            min_shared: 1,
+            index,
+        }
+    }
+
+    /// Set the minimal number of different k-mers a protein has to share with a peptide to be
+    /// a candidate.
+    ///
+    /// Default: 1.
+    #[must_use]
+    pub fn min_shared(self, min_shared: usize) -> Self {
+        Self {
+            min_shared: min_shared.max(1),
+            ..self
+        }
+    }
+
+    /// The k-mer length
+    pub const fn k(&self) -> usize {
+        self.k
+    }
+
+    /// The indexed database
+    pub const fn database(&self) -> &'lifetime [FastaData] {
+        self.database
+    }
+
+    /// The number of different k-mers in the index
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check if there are no k-mers in the index
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Get all candidate proteins for this peptide, as the index in the database and the number
+    /// of different k-mers shared with the peptide. The candidates are sorted on the index in the
+    /// database. If the peptide is shorter than the k-mer length all proteins are returned, with 0
+    /// shared k-mers.
+    pub fn candidates<Complexity>(&self, peptide: &Peptidoform<Complexity>) -> Vec<(usize, usize)> {
+        if peptide.len() < self.k {
+            return (0..self.database.len()).map(|i| (i, 0)).collect();
+        }
+        let mut peptide_kmers = kmers(peptide.sequence(), self.k).collect::<Vec<_>>();
+        peptide_kmers.sort_unstable();
+        peptide_kmers.dedup();
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for kmer in peptide_kmers {
+            if let Some(proteins) = self.index.get(&kmer) {
+                for protein in proteins {
+                    *shared.entry(*protein).or_default() += 1;
+                }
+            }
+        }
+        let mut candidates = shared
+            .into_iter()
+            .filter(|(_, count)| *count >= self.min_shared)
+            .collect::<Vec<_>>();
+        candidates.sort_unstable();
+        candidates
+    }
+
+    /// Align a peptide to all candidate proteins and keep the best scoring alignments, see
+    /// [`align_peptide_to_database`](super::align_peptide_to_database).
+    pub fn align<const STEPS: u16, A: AtMax<SimpleLinear>>(
+        &self,
+        peptide: &'lifetime Peptidoform<A>,
+        scoring: AlignScoring<'lifetime>,
+        settings: &DatabaseAlignmentSettings,
+    ) -> Vec<DatabaseHit<'lifetime, A>> {
+        top_hits::<STEPS, A>(
+            self.candidates(peptide)
+                .into_iter()
+                .map(|(index, _)| &self.database[index]),
+            peptide,
+            scoring,
+            settings,
+        )
+    }
+
+    /// Align all peptides to their candidate proteins and keep the best scoring alignments for
+    /// every peptide, see [`align_to_database`](super::align_to_database). If the crate feature
+    /// `rayon` is turned on (the default) the peptides are aligned in parallel.
+    pub fn align_all<const STEPS: u16, A: AtMax<SimpleLinear> + Send + Sync>(
+        &self,
+        peptides: &'lifetime [Peptidoform<A>],
+        scoring: AlignScoring<'lifetime>,
+        settings: &DatabaseAlignmentSettings,
+    ) -> Vec<Vec<DatabaseHit<'lifetime, A>>> {
+        let align = |peptide| self.align::<STEPS, A>(peptide, scoring, settings);
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            peptides.par_iter().map(align).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            peptides.iter().map(align).collect()
+        }
+    }
+}
+
+/// Iterate over all k-mers in the sequence, encoded with five bits per amino acid with I, L, and
+/// J folded into L
+fn kmers<Complexity>(
+    sequence: &[SequenceElement<Complexity>],
+    k: usize,
+) -> impl Iterator<Item = u64> + '_ {
+    sequence.windows(k).map(|window| {
+        window.iter().fold(0, |kmer, element| {
+            let aa = match element.aminoacid.aminoacid() {
+                AminoAcid::Isoleucine | AminoAcid::AmbiguousLeucine => AminoAcid::Leucine,
+                aa => aa,
+            };
+            kmer << 5 | aa as u64
+        })
+    })
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::align::align_to_database;
+
+    fn linear(aa: &str) -> Peptidoform<SimpleLinear> {
+        Peptidoform::pro_forma(aa, None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn candidates() {
+        let database = FastaData::parse_reader(
+            BufReader::new(
+                ">A\nMAGGWHKTLRS\n>B\nKKPEPTIDEKMS\n>C\nMAGGWHKTIRS\n>D\nPPPPPPPPPP".as_bytes(),
+            ),
+            None,
+        )
+        .unwrap();
+        let index = KmerIndex::new(&database, 4);
+        assert_eq!(index.k(), 4);
+        // I/L are folded so A and C are both found
+        assert_eq!(index.candidates(&linear("WHKTLR")), [(0, 3), (2, 3)]);
+        assert_eq!(index.candidates(&linear("PEPTLDE")), [(1, 4)]);
+        assert!(index.candidates(&linear("YYYYYY")).is_empty());
+        // Too short to prefilter
+        assert_eq!(index.candidates(&linear("PEP")).len(), 4);
+        let strict = index.clone().min_shared(4);
+        assert_eq!(strict.candidates(&linear("WHKTLRS")), [(0, 4), (2, 4)]);
+        assert_eq!(strict.candidates(&linear("WHKTLR")), []);
+    }
+
+    #[test]
+    fn same_as_full_search() {
+        let database = FastaData::parse_reader(
+            BufReader::new(
+                ">A\nMAGGWHKTLRS\n>B\nKKPEPTIDEKMS\n>C\nMAGGWHKTIRS\n>D\nPPPPPPPPPP".as_bytes(),
+            ),
+            None,
+        )
+        .unwrap();
+        let peptides = [linear("AGGWHKT"), linear("PEPTLDEK"), linear("KTLRS")];
+        let index = KmerIndex::new(&database, 3);
+        let settings = DatabaseAlignmentSettings::default();
+        let indexed =
+            index.align_all::<4, SimpleLinear>(&peptides, AlignScoring::default(), &settings);
+        let full = align_to_database::<4, SimpleLinear>(
+            &peptides,
+            &database,
+            AlignScoring::default(),
+            &settings,
+        );
+        assert_eq!(indexed, full);
+    }
+}
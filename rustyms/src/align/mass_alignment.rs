@@ -86,6 +86,7 @@ pub fn align<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear>, B: AtMax<Simpl
                                     masses_b.get_unchecked([index_b - 1, 0]),
                                 )
                             },
+                            index_a - 1,
                             scoring,
                             base_score,
                         ))
@@ -144,6 +145,7 @@ pub fn align<'lifetime, const STEPS: u16, A: AtMax<SimpleLinear>, B: AtMax<Simpl
                             seq_b.sequence().get_unchecked(index_b - 1),
                             masses_b.get_unchecked([index_b - 1, 0]),
                         ),
+                        index_a - 1,
                         scoring,
                         matrix.get_unchecked([index_a - 1, index_b - 1]).score,
                     );
@@ -176,9 +178,18 @@ pub(super) fn determine_final_score<A, B>(
     let maximal_score = (seq_a.sequence()
         [start_a..start_a + path.iter().map(|p| p.step_a as usize).sum::<usize>()]
         .iter()
-        .map(|a| {
-            scoring.matrix[a.aminoacid.aminoacid() as usize][a.aminoacid.aminoacid() as usize]
-                as isize
+        .enumerate()
+        .map(|(index, a)| {
+            scoring
+                .position_specific
+                .and_then(|m| m.get(start_a + index))
+                .map_or_else(
+                    || {
+                        scoring.matrix[a.aminoacid.aminoacid() as usize]
+                            [a.aminoacid.aminoacid() as usize]
+                    },
+                    |row| row[a.aminoacid.aminoacid() as usize],
+                ) as isize
         })
         .sum::<isize>()
         + seq_b.sequence()
@@ -202,20 +213,27 @@ pub(super) fn determine_final_score<A, B>(
     }
 }
 
-/// Score a pair of sequence elements (AA + mods)
+/// Score a pair of sequence elements (AA + mods), the position is the index of a in its sequence
 pub(super) fn score_pair<A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>>(
     a: (&SequenceElement<A>, &Multi<Mass>),
     b: (&SequenceElement<B>, &Multi<Mass>),
+    position: usize,
     scoring: AlignScoring<'_>,
     score: isize,
 ) -> Piece {
+    let position_specific = scoring
+        .position_specific
+        .and_then(|m| m.get(position))
+        .map(|row| row[b.0.aminoacid.aminoacid() as usize] as isize);
     match (
         a.0.aminoacid.aminoacid() == b.0.aminoacid.aminoacid(),
         scoring.tolerance.within(a.1, b.1),
     ) {
         (true, true) => {
-            let local = scoring.matrix[a.0.aminoacid.aminoacid() as usize]
-                [b.0.aminoacid.aminoacid() as usize] as isize;
+            let local = position_specific.unwrap_or_else(|| {
+                scoring.matrix[a.0.aminoacid.aminoacid() as usize]
+                    [b.0.aminoacid.aminoacid() as usize] as isize
+            });
             Piece::new(score + local, local, MatchType::FullIdentity, 1, 1)
         }
         (true, false) => {
@@ -229,13 +247,10 @@ pub(super) fn score_pair<A: AtMax<SimpleLinear>, B: AtMax<SimpleLinear>>(
             1,
             1,
         ),
-        (false, false) => Piece::new(
-            score + scoring.mismatch as isize,
-            scoring.mismatch as isize,
-            MatchType::Mismatch,
-            1,
-            1,
-        ),
+        (false, false) => {
+            let local = position_specific.unwrap_or(scoring.mismatch as isize);
+            Piece::new(score + local, local, MatchType::Mismatch, 1, 1)
+        }
     }
 }
 
@@ -0,0 +1,175 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    system::{dalton, Mass},
+    AminoAcid, MassMode, MultiChemical,
+};
+
+/// Generate a scoring matrix based on the mass difference and chemical similarity of the amino
+/// acids instead of evolutionary substitution rates (like BLOSUM and PAM). This is a better fit
+/// for aligning de novo sequences, where the errors are driven by mass coincidences and not by
+/// evolution.
+///
+/// The score for two different amino acids is linearly interpolated between `isobaric` (no mass
+/// difference) and `mismatch` (a mass difference of `max_difference` or more). If both amino
+/// acids are in the same chemical class (e.g. both acidic) the `chemical_similarity` bonus is
+/// added. The score for different amino acids is capped at `identity - 1` so identical amino acids
+/// are always preferred. For ambiguous amino acids (B/J/Z) the smallest mass difference is used,
+/// unknown amino acids (X) score 0 against everything.
+///
+/// ```rust
+/// # use rustyms::{*, align::{*, matrix::MassSimilarityMatrix}};
+/// let matrix = MassSimilarityMatrix::default().generate();
+/// assert_eq!(matrix[AminoAcid::Lysine as usize][AminoAcid::Glutamine as usize], 6);
+/// let scoring = AlignScoring {
+///     matrix: &matrix,
+///     ..AlignScoring::default()
+/// };
+/// let a = Peptidoform::pro_forma("PEKTIDE", None).unwrap().into_simple_linear().unwrap();
+/// let b = Peptidoform::pro_forma("PEQTIDE", None).unwrap().into_simple_linear().unwrap();
+/// let alignment = align::<4, SimpleLinear, SimpleLinear>(&a, &b, scoring, AlignType::GLOBAL);
+/// assert_eq!(alignment.short(), "2=1X4=");
+/// ```
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MassSimilarityMatrix {
+    /// The score for identical amino acids.
+    ///
+    /// Default: 8.
+    pub identity: i8,
+    /// The score for different amino acids with the exact same mass.
+    ///
+    /// Default: 6.
+    pub isobaric: i8,
+    /// The score for different amino acids that differ by `max_difference` or more.
+    ///
+    /// Default: -4.
+    pub mismatch: i8,
+    /// The mass difference at which the score reaches `mismatch`.
+    ///
+    /// Default: 30 Da.
+    pub max_difference: Mass,
+    /// The bonus for different amino acids in the same chemical class.
+    ///
+    /// Default: 2.
+    pub chemical_similarity: i8,
+    /// The mass mode used to calculate the mass differences.
+    ///
+    /// Default: Monoisotopic.
+    pub mass_mode: MassMode,
+}
+
+impl Default for MassSimilarityMatrix {
+    fn default() -> Self {
+        Self {
+            identity: 8,
+            isobaric: 6,
+            mismatch: -4,
+            max_difference: Mass::new::<dalton>(30.0),
+            chemical_similarity: 2,
+            mass_mode: MassMode::Monoisotopic,
+        }
+    }
+}
+
+impl MassSimilarityMatrix {
+    /// Generate the scoring matrix, it is indexed by the amino acid (`AminoAcid as usize`) in the
+    /// same way as the predefined matrices.
+    pub fn generate(&self) -> [[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER] {
+        let masses = AminoAcid::ALL_AMINO_ACIDS.map(|aa| {
+            aa.formulas()
+                .iter()
+                .map(|f| f.mass(self.mass_mode))
+                .collect::<Vec<_>>()
+        });
+        let mut matrix = [[0; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER];
+        for a in *AminoAcid::ALL_AMINO_ACIDS {
+            for b in *AminoAcid::ALL_AMINO_ACIDS {
+                matrix[a as usize][b as usize] = if a == AminoAcid::Unknown
+                    || b == AminoAcid::Unknown
+                {
+                    0
+                } else if a == b {
+                    self.identity
+                } else {
+                    let difference = masses[a as usize]
+                        .iter()
+                        .flat_map(|ma| masses[b as usize].iter().map(move |mb| (*ma - *mb).abs()))
+                        .fold(f64::INFINITY, |acc, d| acc.min(d.value));
+                    let fraction = (difference / self.max_difference.value).min(1.0);
+                    let bonus =
+                        if chemical_class(a).is_some() && chemical_class(a) == chemical_class(b) {
+                            self.chemical_similarity
+                        } else {
+                            0
+                        };
+                    let score = (f64::from(self.isobaric) - f64::from(self.mismatch))
+                        .mul_add(-fraction, f64::from(self.isobaric) + f64::from(bonus));
+                    (score.round() as i8).min(self.identity.saturating_sub(1))
+                };
+            }
+        }
+        matrix
+    }
+}
+
+/// The chemical class of the side chain, if any
+const fn chemical_class(aa: AminoAcid) -> Option<u8> {
+    match aa {
+        // Aliphatic
+        AminoAcid::Alanine
+        | AminoAcid::Valine
+        | AminoAcid::Isoleucine
+        | AminoAcid::Leucine
+        | AminoAcid::AmbiguousLeucine
+        | AminoAcid::Methionine => Some(0),
+        // Aromatic
+        AminoAcid::Phenylalanine | AminoAcid::Tryptophan | AminoAcid::Tyrosine => Some(1),
+        // Basic
+        AminoAcid::Arginine | AminoAcid::Histidine | AminoAcid::Lysine | AminoAcid::Pyrrolysine => {
+            Some(2)
+        }
+        // Acidic
+        AminoAcid::AsparticAcid | AminoAcid::GlutamicAcid => Some(3),
+        // Polar
+        AminoAcid::Asparagine | AminoAcid::Glutamine | AminoAcid::Serine | AminoAcid::Threonine => {
+            Some(4)
+        }
+        // Thiol/selenol
+        AminoAcid::Cysteine | AminoAcid::Selenocysteine => Some(5),
+        AminoAcid::Glycine
+        | AminoAcid::Proline
+        | AminoAcid::AmbiguousAsparagine
+        | AminoAcid::AmbiguousGlutamine
+        | AminoAcid::Unknown => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mass_similarity() {
+        let matrix = MassSimilarityMatrix::default().generate();
+        let score = |a: AminoAcid, b: AminoAcid| matrix[a as usize][b as usize];
+        for a in *AminoAcid::ALL_AMINO_ACIDS {
+            for b in *AminoAcid::ALL_AMINO_ACIDS {
+                assert_eq!(score(a, b), score(b, a), "{a:?} {b:?}");
+            }
+        }
+        assert_eq!(score(AminoAcid::Alanine, AminoAcid::Alanine), 8);
+        assert_eq!(score(AminoAcid::Isoleucine, AminoAcid::Leucine), 7);
+        assert_eq!(
+            score(AminoAcid::AmbiguousAsparagine, AminoAcid::AsparticAcid),
+            6
+        );
+        assert_eq!(score(AminoAcid::Lysine, AminoAcid::Glutamine), 6);
+        assert_eq!(score(AminoAcid::Glycine, AminoAcid::Tryptophan), -4);
+        assert_eq!(score(AminoAcid::Unknown, AminoAcid::Alanine), 0);
+        assert!(
+            score(AminoAcid::AsparticAcid, AminoAcid::GlutamicAcid)
+                > score(AminoAcid::AsparticAcid, AminoAcid::Glutamine)
+        );
+    }
+}
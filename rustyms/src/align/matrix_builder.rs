@@ -0,0 +1,417 @@
+use std::{io::BufRead, path::Path};
+
+use itertools::Itertools;
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::open_reader,
+    AminoAcid, Peptidoform,
+};
+
+use super::scoring::matrices;
+
+/// The type of a full substitution matrix, indexed by the amino acid (`AminoAcid as usize`)
+type Matrix = [[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER];
+
+/// Build custom substitution matrices, either starting from one of the predefined matrices or by
+/// reading an NCBI formatted matrix file (as used by BLAST). The result can be used as the
+/// `matrix` in [`AlignScoring`](crate::align::AlignScoring). Additionally position specific
+/// scoring matrices can be created for use as `position_specific` in the scoring.
+///
+/// ```rust
+/// # use rustyms::{*, align::{*, matrix::*}};
+/// let matrix = MatrixBuilder::new(BLOSUM62)
+///     .score(AminoAcid::Lysine, AminoAcid::Glutamine, 3)
+///     .build();
+/// assert_eq!(matrix[AminoAcid::Glutamine as usize][AminoAcid::Lysine as usize], 3);
+/// let scoring = AlignScoring {
+///     matrix: &matrix,
+///     ..AlignScoring::default()
+/// };
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MatrixBuilder {
+    matrix: Matrix,
+}
+
+impl MatrixBuilder {
+    /// Start from the given matrix
+    pub const fn new(matrix: &Matrix) -> Self {
+        Self { matrix: *matrix }
+    }
+
+    /// Set the score for substituting `a` with `b` and `b` with `a`
+    #[must_use]
+    pub const fn score(mut self, a: AminoAcid, b: AminoAcid, score: i8) -> Self {
+        self.matrix[a as usize][b as usize] = score;
+        self.matrix[b as usize][a as usize] = score;
+        self
+    }
+
+    /// Set the score for substituting `a` with `b` only, resulting in an asymmetric matrix
+    #[must_use]
+    pub const fn asymmetric_score(mut self, a: AminoAcid, b: AminoAcid, score: i8) -> Self {
+        self.matrix[a as usize][b as usize] = score;
+        self
+    }
+
+    /// Set the scores of the ambiguous amino acids B, J, and Z to the average (rounded towards
+    /// zero) of the corresponding amino acids (N/D, I/L, and Q/E), in the same way as for the
+    /// predefined matrices.
+    #[must_use]
+    pub fn ambiguous_averages(mut self) -> Self {
+        for (ambiguous, a, b) in AMBIGUOUS {
+            fill_ambiguous(&mut self.matrix, ambiguous, a, b);
+        }
+        self
+    }
+
+    /// Get the matrix
+    pub const fn build(self) -> Matrix {
+        self.matrix
+    }
+
+    /// Create a position specific scoring matrix for the given sequence, where every row is the
+    /// row of this matrix for the amino acid at that position.
+    pub fn position_specific<Complexity>(
+        &self,
+        sequence: &Peptidoform<Complexity>,
+    ) -> Vec<[i8; AminoAcid::TOTAL_NUMBER]> {
+        sequence
+            .sequence()
+            .iter()
+            .map(|s| self.matrix[s.aminoacid.aminoacid() as usize])
+            .collect()
+    }
+
+    /// Create a position specific scoring matrix for the given sequence, where every row is the
+    /// row of this matrix for the amino acid at that position multiplied by the weight for that
+    /// position (rounded and saturated to fit). This can for example be used to put more weight
+    /// on positions with a high local confidence. Positions without a weight get a weight of 1.
+    pub fn weighted_position_specific<Complexity>(
+        &self,
+        sequence: &Peptidoform<Complexity>,
+        weights: &[f64],
+    ) -> Vec<[i8; AminoAcid::TOTAL_NUMBER]> {
+        self.position_specific(sequence)
+            .into_iter()
+            .enumerate()
+            .map(|(index, row)| {
+                let weight = weights.get(index).copied().unwrap_or(1.0);
+                row.map(|score| {
+                    (f64::from(score) * weight)
+                        .round()
+                        .clamp(f64::from(i8::MIN), f64::from(i8::MAX)) as i8
+                })
+            })
+            .collect()
+    }
+
+    /// Read an NCBI formatted matrix file, see [`Self::parse_ncbi`]. Gzipped files are
+    /// uncompressed automatically.
+    /// # Errors
+    /// If the file could not be opened or is not a valid matrix file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        Self::parse_ncbi(open_reader(path)?)
+    }
+
+    /// Parse an NCBI formatted matrix. Lines starting with `#` are comments, the first other line
+    /// contains the amino acids of the columns, all following lines start with the amino acid of
+    /// the row followed by the scores for all columns. The stop (`*`) row and column are ignored.
+    /// Any amino acids not in the matrix get the same scores as in the predefined matrices: B, J,
+    /// and Z the average (rounded towards zero) of the corresponding amino acids, U and O the
+    /// scores from the predefined matrices, and X the average (rounded towards zero) of the scores
+    /// for all 20 standard amino acids.
+    /// # Errors
+    /// If the matrix is not valid, eg a row has the wrong number of scores, a score does not fit in
+    /// an `i8`, a row or column is repeated, or not all 20 standard amino acids are defined.
+    pub fn parse_ncbi(reader: impl BufRead) -> Result<Self, CustomError> {
+        let mut columns: Option<Vec<Option<AminoAcid>>> = None;
+        let mut matrix = *matrices::BLOSUM62;
+        let mut seen_rows = Vec::new();
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line.map_err(|e| {
+                CustomError::error(
+                    "Could not read matrix",
+                    e,
+                    Context::full_line(line_index, "-"),
+                )
+            })?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+            let parse_amino_acid = |text: &str| {
+                if text == "*" {
+                    Ok(None)
+                } else {
+                    AminoAcid::try_from(text).map(Some).map_err(|()| {
+                        CustomError::error(
+                            "Invalid matrix",
+                            format!("'{text}' is not a valid amino acid"),
+                            Context::full_line(line_index, &line),
+                        )
+                    })
+                }
+            };
+            if let Some(columns) = &columns {
+                let mut cells = trimmed.split_whitespace();
+                let row = parse_amino_acid(cells.next().unwrap_or_default())?;
+                let scores = cells
+                    .map(|cell| {
+                        cell.parse::<i8>().map_err(|_| {
+                            CustomError::error(
+                                "Invalid matrix",
+                                format!("'{cell}' is not a valid score, a score should be a whole number between -128 and 127"),
+                                Context::full_line(line_index, &line),
+                            )
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                if scores.len() != columns.len() {
+                    return Err(CustomError::error(
+                        "Invalid matrix",
+                        format!(
+                            "This row has {} scores while {} columns are defined",
+                            scores.len(),
+                            columns.len()
+                        ),
+                        Context::full_line(line_index, &line),
+                    ));
+                }
+                if let Some(row) = row {
+                    if seen_rows.contains(&row) {
+                        return Err(CustomError::error(
+                            "Invalid matrix",
+                            "This row is defined multiple times",
+                            Context::full_line(line_index, &line),
+                        ));
+                    }
+                    seen_rows.push(row);
+                    for (column, score) in columns.iter().zip(scores) {
+                        if let Some(column) = column {
+                            matrix[row as usize][*column as usize] = score;
+                        }
+                    }
+                }
+            } else {
+                let header = trimmed
+                    .split_whitespace()
+                    .map(parse_amino_acid)
+                    .collect::<Result<Vec<_>, _>>()?;
+                if header.iter().flatten().duplicates().next().is_some() {
+                    return Err(CustomError::error(
+                        "Invalid matrix",
+                        "A column is defined multiple times",
+                        Context::full_line(line_index, &line),
+                    ));
+                }
+                columns = Some(header);
+            }
+        }
+        let columns = columns.unwrap_or_default();
+        let defined =
+            |aa: AminoAcid| seen_rows.contains(&aa) && columns.iter().flatten().any(|c| *c == aa);
+        if let Some(missing) = AminoAcid::CANONICAL_AMINO_ACIDS
+            .iter()
+            .find(|aa| !defined(**aa))
+        {
+            return Err(CustomError::error(
+                "Invalid matrix",
+                format!(
+                    "Not all standard amino acids are defined, {} is missing",
+                    missing.char()
+                ),
+                Context::none(),
+            ));
+        }
+        // Fill in all amino acids that are not defined
+        for (ambiguous, a, b) in AMBIGUOUS {
+            if !defined(ambiguous) {
+                fill_ambiguous(&mut matrix, ambiguous, a, b);
+            }
+        }
+        if !defined(AminoAcid::Unknown) {
+            let unknown = AminoAcid::Unknown as usize;
+            // Divided by the number of standard amino acids
+            for row in &mut matrix {
+                let average = AminoAcid::CANONICAL_AMINO_ACIDS
+                    .iter()
+                    .map(|aa| i32::from(row[*aa as usize]))
+                    .sum::<i32>()
+                    / 20;
+                row[unknown] = i8::try_from(average).unwrap_or_default();
+            }
+            matrix[unknown] = std::array::from_fn(|other| {
+                let average = AminoAcid::CANONICAL_AMINO_ACIDS
+                    .iter()
+                    .map(|aa| i32::from(matrix[*aa as usize][other]))
+                    .sum::<i32>()
+                    / 20;
+                i8::try_from(average).unwrap_or_default()
+            });
+        }
+        Ok(Self { matrix })
+    }
+}
+
+/// The ambiguous amino acids with the two amino acids they represent
+const AMBIGUOUS: [(AminoAcid, AminoAcid, AminoAcid); 3] = [
+    (
+        AminoAcid::AmbiguousAsparagine,
+        AminoAcid::Asparagine,
+        AminoAcid::AsparticAcid,
+    ),
+    (
+        AminoAcid::AmbiguousLeucine,
+        AminoAcid::Isoleucine,
+        AminoAcid::Leucine,
+    ),
+    (
+        AminoAcid::AmbiguousGlutamine,
+        AminoAcid::Glutamine,
+        AminoAcid::GlutamicAcid,
+    ),
+];
+
+/// Set the scores of an ambiguous amino acid to the average of the two amino acids it represents
+fn fill_ambiguous(matrix: &mut Matrix, ambiguous: AminoAcid, a: AminoAcid, b: AminoAcid) {
+    matrix[ambiguous as usize] =
+        std::array::from_fn(|other| average(matrix[a as usize][other], matrix[b as usize][other]));
+    for row in matrix {
+        row[ambiguous as usize] = average(row[a as usize], row[b as usize]);
+    }
+}
+
+/// The average (rounded towards zero) of two scores
+fn average(a: i8, b: i8) -> i8 {
+    // The average of two i8 values always fits in an i8
+    i8::try_from((i16::from(a) + i16::from(b)) / 2).unwrap_or_default()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_blosum62() {
+        // The BLOSUM62 matrix as distributed by NCBI
+        let file = "#  Matrix made by matblas from blosum62.iij
+#  * column uses minimum score
+   A  R  N  D  C  Q  E  G  H  I  L  K  M  F  P  S  T  W  Y  V  B  Z  X  *
+A  4 -1 -2 -2  0 -1 -1  0 -2 -1 -1 -1 -1 -2 -1  1  0 -3 -2  0 -2 -1 -1 -4
+R -1  5  0 -2 -3  1  0 -2  0 -3 -2  2 -1 -3 -2 -1 -1 -3 -2 -3 -1  0 -1 -4
+N -2  0  6  1 -3  0  0  0  1 -3 -3  0 -2 -3 -2  1  0 -4 -2 -3  3  0 -1 -4
+D -2 -2  1  6 -3  0  2 -1 -1 -3 -4 -1 -3 -3 -1  0 -1 -4 -3 -3  4  1 -1 -4
+C  0 -3 -3 -3  9 -3 -4 -3 -3 -1 -1 -3 -1 -2 -3 -1 -1 -2 -2 -1 -3 -3 -1 -4
+Q -1  1  0  0 -3  5  2 -2  0 -3 -2  1  0 -3 -1  0 -1 -2 -1 -2  0  3 -1 -4
+E -1  0  0  2 -4  2  5 -2  0 -3 -3  1 -2 -3 -1  0 -1 -3 -2 -2  1  4 -1 -4
+G  0 -2  0 -1 -3 -2 -2  6 -2 -4 -4 -2 -3 -3 -2  0 -2 -2 -3 -3 -1 -2 -1 -4
+H -2  0  1 -1 -3  0  0 -2  8 -3 -3 -1 -2 -1 -2 -1 -2 -2  2 -3  0  0 -1 -4
+I -1 -3 -3 -3 -1 -3 -3 -4 -3  4  2 -3  1  0 -3 -2 -1 -3 -1  3 -3 -3 -1 -4
+L -1 -2 -3 -4 -1 -2 -3 -4 -3  2  4 -2  2  0 -3 -2 -1 -2 -1  1 -4 -3 -1 -4
+K -1  2  0 -1 -3  1  1 -2 -1 -3 -2  5 -1 -3 -1  0 -1 -3 -2 -2  0  1 -1 -4
+M -1 -1 -2 -3 -1  0 -2 -3 -2  1  2 -1  5  0 -2 -1 -1 -1 -1  1 -3 -1 -1 -4
+F -2 -3 -3 -3 -2 -3 -3 -3 -1  0  0 -3  0  6 -4 -2 -2  1  3 -1 -3 -3 -1 -4
+P -1 -2 -2 -1 -3 -1 -1 -2 -2 -3 -3 -1 -2 -4  7 -1 -1 -4 -3 -2 -2 -1 -2 -4
+S  1 -1  1  0 -1  0  0  0 -1 -2 -2  0 -1 -2 -1  4  1 -3 -2 -2  0  0  0 -4
+T  0 -1  0 -1 -1 -1 -1 -2 -2 -1 -1 -1 -1 -2 -1  1  5 -2 -2  0 -1 -1  0 -4
+W -3 -3 -4 -4 -2 -2 -3 -2 -2 -3 -2 -3 -1  1 -4 -3 -2 11  2 -3 -4 -3 -2 -4
+Y -2 -2 -2 -3 -2 -1 -2 -3  2 -1 -1 -2 -1  3 -3 -2 -2  2  7 -1 -3 -2 -1 -4
+V  0 -3 -3 -3 -1 -2 -2 -3 -3  3  1 -2  1 -1 -2 -2  0 -3 -1  4 -3 -2 -1 -4
+B -2 -1  3  4 -3  0  1 -1  0 -3 -4  0 -3 -3 -2  0 -1 -4 -3 -3  4  1 -1 -4
+Z -1  0  0  1 -3  3  4 -2  0 -3 -3  1 -1 -3 -1  0 -1 -3 -2 -2  1  4 -1 -4
+X -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -1 -2  0  0 -2 -1 -1 -1 -1 -1 -4
+* -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4 -4  1
+";
+        let matrix = MatrixBuilder::parse_ncbi(file.as_bytes()).unwrap().build();
+        for aa in AminoAcid::CANONICAL_AMINO_ACIDS.iter().copied() {
+            assert_eq!(matrix[aa as usize], {
+                let mut row = matrices::BLOSUM62[aa as usize];
+                // The ambiguous columns in the predefined matrix are computed as averages
+                for (ambiguous, _, _) in AMBIGUOUS {
+                    row[ambiguous as usize] = matrix[aa as usize][ambiguous as usize];
+                }
+                row[AminoAcid::Unknown as usize] = matrix[aa as usize][AminoAcid::Unknown as usize];
+                row
+            });
+        }
+        assert_eq!(
+            matrix[AminoAcid::Selenocysteine as usize],
+            matrices::BLOSUM62[AminoAcid::Selenocysteine as usize]
+        );
+        // J is not in the file so is the average of I and L
+        assert_eq!(
+            matrix[AminoAcid::AmbiguousLeucine as usize][AminoAcid::Valine as usize],
+            2
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!(MatrixBuilder::parse_ncbi(b"   A R\nA 1 2\n".as_slice()).is_err());
+        assert!(MatrixBuilder::parse_ncbi(b"   A  R\nA  1  2 3\n".as_slice()).is_err());
+        assert!(MatrixBuilder::parse_ncbi(b"   A  !\nA  1  2\n".as_slice()).is_err());
+        assert!(MatrixBuilder::parse_ncbi(b"   A  R\nA  1  200\n".as_slice()).is_err());
+        assert!(MatrixBuilder::parse_ncbi(b"".as_slice()).is_err());
+    }
+
+    #[test]
+    fn builder() {
+        let matrix = MatrixBuilder::new(matrices::IDENTITY)
+            .score(AminoAcid::Asparagine, AminoAcid::AsparticAcid, 4)
+            .asymmetric_score(AminoAcid::Lysine, AminoAcid::Glutamine, 2)
+            .ambiguous_averages()
+            .build();
+        assert_eq!(
+            matrix[AminoAcid::AsparticAcid as usize][AminoAcid::Asparagine as usize],
+            4
+        );
+        assert_eq!(
+            matrix[AminoAcid::Glutamine as usize][AminoAcid::Lysine as usize],
+            matrices::IDENTITY[AminoAcid::Glutamine as usize][AminoAcid::Lysine as usize]
+        );
+        assert_eq!(
+            matrix[AminoAcid::AmbiguousAsparagine as usize][AminoAcid::Asparagine as usize],
+            6
+        );
+
+        let peptide = Peptidoform::pro_forma("AKE", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let pssm = MatrixBuilder::new(matrices::BLOSUM62)
+            .weighted_position_specific(&peptide, &[1.0, 2.0]);
+        assert_eq!(pssm.len(), 3);
+        assert_eq!(pssm[1][AminoAcid::Lysine as usize], 10);
+        assert_eq!(pssm[2][AminoAcid::GlutamicAcid as usize], 5);
+    }
+
+    #[test]
+    fn position_specific_alignment() {
+        use crate::align::{align, AlignScoring, AlignType};
+        let a = Peptidoform::pro_forma("AKEW", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let b = Peptidoform::pro_forma("ARDW", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let pssm = MatrixBuilder::new(matrices::BLOSUM62).position_specific(&a);
+        let plain = align::<1, _, _>(&a, &b, AlignScoring::default(), AlignType::GLOBAL);
+        let specific = align::<1, _, _>(
+            &a,
+            &b,
+            AlignScoring {
+                position_specific: Some(&pssm),
+                ..AlignScoring::default()
+            },
+            AlignType::GLOBAL,
+        );
+        assert_eq!(plain.short(), specific.short());
+        // K->R (2) and E->D (2) instead of twice the mismatch score (-1)
+        assert_eq!(specific.score().absolute, plain.score().absolute + 6);
+    }
+}
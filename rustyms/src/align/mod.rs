@@ -31,20 +31,34 @@ mod alignment;
 mod bad_alignments;
 mod diagonal_array;
 mod mass_alignment;
+mod mass_similarity;
+mod matrix_builder;
 mod multi_alignment;
 mod piece;
 mod scoring;
 #[cfg(test)]
 mod test_alignments;
 
+#[cfg(feature = "identification")]
+mod database;
+#[cfg(feature = "identification")]
+mod kmer_index;
+#[cfg(feature = "identification")]
+pub use database::*;
+#[cfg(feature = "identification")]
+pub use kmer_index::KmerIndex;
+
 #[cfg(feature = "imgt")]
 mod consecutive;
 #[cfg(feature = "imgt")]
 pub use consecutive::*;
 
 pub use align_type::{AlignType, Side};
-pub use alignment::{Alignment, Score, Stats};
+pub use alignment::{Alignment, AlignmentColumn, CompactAlignment, Score, Stats};
 pub use mass_alignment::align;
+pub use multi_alignment::{
+    multi_align, MultiAlignment, MultiAlignmentLine, MultiPiece, ProfileColumn,
+};
 pub use piece::Piece;
 pub use scoring::{AlignScoring, MatchType};
 
@@ -52,7 +66,12 @@ pub use scoring::{AlignScoring, MatchType};
 /// Matrices from: <https://www.ncbi.nlm.nih.gov/IEB/ToolBox/CPP_DOC/lxr/source/src/util/tables/> and <https://www.ncbi.nlm.nih.gov/IEB/ToolBox/C_DOC/lxr/source/data/>
 ///
 /// The UO columns are added, for these the B/J/Z score is the rounded down average of the corresponding non ambiguous AAs. All UO scores are exactly the same for all matrices (except identity).
+///
+/// Additionally matrices based on mass similarity can be generated with [`MassSimilarityMatrix`](matrix::MassSimilarityMatrix),
+/// and custom or position specific matrices can be made or read from NCBI formatted files with [`MatrixBuilder`](matrix::MatrixBuilder).
 pub mod matrix {
+    pub use super::mass_similarity::MassSimilarityMatrix;
+    pub use super::matrix_builder::MatrixBuilder;
     use super::scoring;
     pub use scoring::matrices::*;
 }
@@ -1,44 +1,622 @@
+//! Progressive multiple sequence alignment of peptides, built on the pairwise mass based alignment.
+
 use std::borrow::Cow;
 
-use crate::Peptidoform;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-use super::{AlignType, MatchType, Score};
+use crate::{
+    peptidoform::{AtMax, SimpleLinear},
+    Peptidoform, SequenceElement,
+};
 
-use serde::{Deserialize, Serialize};
+use super::{align, AlignScoring, AlignType, MatchType};
 
-type MultiAlignment<'lifetime, Complexity> = Vec<MultiAlignmentLine<'lifetime, Complexity>>;
+/// Create a multiple sequence alignment of a set of peptides. This is a progressive alignment
+/// built on the pairwise mass based alignment ([`align`]). First all pairs of sequences are
+/// aligned, the sequence with the highest summed (absolute) score against all other sequences
+/// is used as the starting point. Then, repeatedly, the sequence with the highest score against any sequence already in the alignment is aligned to that sequence and merged
+/// into the alignment. If a step in the new pairwise alignment spans multiple columns of the
+/// multiple alignment (for example an isobaric `N` on a sequence that already has `GG` in two
+/// columns) these columns are merged into a single column.
+///
+/// The lines in the resulting alignment are in the same order as the given sequences. The
+/// alignment type is used for all pairwise alignments, for a set of overlapping de novo peptides
+/// use [`AlignType::EITHER_GLOBAL`]. Any residues not covered by a (local) pairwise alignment are
+/// still placed in the multiple alignment, but opposite of gaps.
+/// ```rust
+/// use rustyms::{*, align::*};
+/// let peptides = ["AGGWHK", "ANWHKL", "GGWHKL"].map(|p| {
+///     Peptidoform::pro_forma(p, None).unwrap().into_simple_linear().unwrap()
+/// });
+/// let alignment = multi_align::<4, SimpleLinear>(
+///     &peptides,
+///     AlignScoring::default(),
+///     AlignType::EITHER_GLOBAL,
+/// );
+/// assert_eq!(alignment.to_string(), "AGGWHK-\nAN·WHKL\n-GGWHKL\n");
+/// ```
+/// # Panics
+/// It panics when the length of any sequence is bigger than [`isize::MAX`].
+pub fn multi_align<'lifetime, const STEPS: u16, Complexity: AtMax<SimpleLinear>>(
+    sequences: &'lifetime [Peptidoform<Complexity>],
+    scoring: AlignScoring<'lifetime>,
+    align_type: AlignType,
+) -> MultiAlignment<'lifetime, Complexity> {
+    let mut result = MultiAlignment {
+        lines: Vec::with_capacity(sequences.len()),
+        align_type,
+        maximal_step: STEPS,
+    };
+    if sequences.is_empty() {
+        return result;
+    }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
-struct MultiAlignmentLine<'lifetime, Complexity> {
-    sequence: Cow<'lifetime, Peptidoform<Complexity>>,
-    path: Vec<MultiPiece>,
-    score: Score,
-    start: usize,
+    // The guide: the absolute scores of all pairwise alignments
+    let mut scores = vec![vec![0; sequences.len()]; sequences.len()];
+    for (a, b) in (0..sequences.len()).tuple_combinations() {
+        let score = align::<STEPS, Complexity, Complexity>(
+            &sequences[a],
+            &sequences[b],
+            scoring,
+            align_type,
+        )
+        .score()
+        .absolute;
+        scores[a][b] = score;
+        scores[b][a] = score;
+    }
+    let start = (0..sequences.len())
+        .max_by(|a, b| {
+            scores[*a]
+                .iter()
+                .sum::<isize>()
+                .cmp(&scores[*b].iter().sum::<isize>())
+                .then(b.cmp(a))
+        })
+        .unwrap_or_default();
+
+    // The order of the lines in the alignment while building, mapped back at the end
+    let mut order = vec![start];
+    result.lines.push(MultiAlignmentLine {
+        sequence: Cow::Borrowed(&sequences[start]),
+        path: sequences[start]
+            .sequence()
+            .iter()
+            .map(|_| MultiPiece {
+                score: 0,
+                local_score: 0,
+                match_type: MatchType::FullIdentity,
+                step: 1,
+            })
+            .collect(),
+    });
+    while order.len() < sequences.len() {
+        let Some((placed, new)) = order
+            .iter()
+            .enumerate()
+            .flat_map(|(line, placed)| {
+                (0..sequences.len())
+                    .filter(|new| !order.contains(new))
+                    .map(move |new| (line, *placed, new))
+            })
+            .max_by(|a, b| {
+                scores[a.1][a.2]
+                    .cmp(&scores[b.1][b.2])
+                    .then(b.2.cmp(&a.2))
+                    .then(b.0.cmp(&a.0))
+            })
+            .map(|(line, _, new)| (line, new))
+        else {
+            break;
+        };
+        let alignment = align::<STEPS, Complexity, Complexity>(
+            &sequences[order[placed]],
+            &sequences[new],
+            scoring,
+            align_type,
+        );
+        let segments = segments(
+            alignment
+                .path()
+                .iter()
+                .map(|p| (p.step_a, p.step_b, p.match_type, p.local_score)),
+            alignment.start(),
+            (alignment.len_a(), alignment.len_b()),
+            (sequences[order[placed]].len(), sequences[new].len()),
+        );
+        result.merge(placed, &sequences[new], &segments);
+        order.push(new);
+    }
+
+    for line in &mut result.lines {
+        let mut score = 0;
+        for piece in &mut line.path {
+            score += piece.local_score;
+            piece.score = score;
+        }
+    }
+    let mut lines = order.into_iter().zip(result.lines).collect_vec();
+    lines.sort_unstable_by_key(|(index, _)| *index);
+    result.lines = lines.into_iter().map(|(_, line)| line).collect();
+    result
+}
+
+/// Turn a pairwise alignment path into segments (step a, step b, match type, local score) that
+/// cover both full sequences, with all gaps split into single residue steps.
+fn segments(
+    path: impl Iterator<Item = (u16, u16, MatchType, isize)>,
+    (start_a, start_b): (usize, usize),
+    (len_a, len_b): (usize, usize),
+    (total_a, total_b): (usize, usize),
+) -> Vec<(u16, u16, MatchType, isize)> {
+    let gaps = |a: usize, b: usize| {
+        std::iter::repeat((1, 0, MatchType::Gap, 0))
+            .take(a)
+            .chain(std::iter::repeat((0, 1, MatchType::Gap, 0)).take(b))
+    };
+    gaps(start_a, start_b)
+        .chain(path)
+        .chain(gaps(
+            total_a.saturating_sub(start_a + len_a),
+            total_b.saturating_sub(start_b + len_b),
+        ))
+        .collect()
+}
+
+/// A multiple sequence alignment of peptides, see [`multi_align`]. This consists of a number of
+/// lines, one for every sequence, that all have the same number of columns. A column can contain
+/// multiple residues for a line if an isobaric set of residues is aligned, or none if the line
+/// has a gap in that column.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultiAlignment<'lifetime, Complexity> {
+    /// The lines, one for each sequence
+    lines: Vec<MultiAlignmentLine<'lifetime, Complexity>>,
+    /// The alignment type used for the pairwise alignments
     align_type: AlignType,
+    /// The maximal step size (the const generic STEPS)
     maximal_step: u16,
 }
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
-struct MultiPiece {
-    score: isize,
-    local_score: isize,
-    match_type: MatchType,
-    step: u16,
+impl<Complexity> Clone for MultiAlignment<'_, Complexity> {
+    fn clone(&self) -> Self {
+        Self {
+            lines: self.lines.clone(),
+            align_type: self.align_type,
+            maximal_step: self.maximal_step,
+        }
+    }
+}
+
+impl<Complexity> PartialEq for MultiAlignment<'_, Complexity> {
+    fn eq(&self, other: &Self) -> bool {
+        self.lines == other.lines
+            && self.align_type == other.align_type
+            && self.maximal_step == other.maximal_step
+    }
+}
+
+impl<Complexity> Eq for MultiAlignment<'_, Complexity> {}
+
+/// A single line in a [`MultiAlignment`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultiAlignmentLine<'lifetime, Complexity> {
+    /// The sequence
+    sequence: Cow<'lifetime, Peptidoform<Complexity>>,
+    /// The steps taken for this sequence, one for each column
+    path: Vec<MultiPiece>,
+}
+
+impl<Complexity> Clone for MultiAlignmentLine<'_, Complexity> {
+    fn clone(&self) -> Self {
+        Self {
+            sequence: self.sequence.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+impl<Complexity> PartialEq for MultiAlignmentLine<'_, Complexity> {
+    fn eq(&self, other: &Self) -> bool {
+        self.sequence == other.sequence && self.path == other.path
+    }
+}
+
+impl<Complexity> Eq for MultiAlignmentLine<'_, Complexity> {}
+
+/// A piece in a line of a multiple alignment, the step taken for this line in a single column
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub struct MultiPiece {
+    /// The total score of the path of this line up till now
+    pub score: isize,
+    /// The local contribution to the score of this piece, as determined by the pairwise alignment
+    /// that added this line (or merged this column)
+    pub local_score: isize,
+    /// The type of the match, as determined by the pairwise alignment that added this line
+    pub match_type: MatchType,
+    /// The number of residues of this line in this column, 0 for a gap
+    pub step: u16,
+}
+
+impl MultiPiece {
+    /// A gap in a column
+    const fn gap() -> Self {
+        Self {
+            score: 0,
+            local_score: 0,
+            match_type: MatchType::Gap,
+            step: 0,
+        }
+    }
+}
+
+/// A column in the profile of a [`MultiAlignment`], see [`MultiAlignment::profile`]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ProfileColumn<Complexity> {
+    /// All different sets of residues found in this column, with the number of lines that have
+    /// this exact set, sorted on descending number of lines
+    pub options: Vec<(Vec<SequenceElement<Complexity>>, usize)>,
+    /// The number of lines with a gap in this column
+    pub gaps: usize,
+}
+
+impl<Complexity> Clone for ProfileColumn<Complexity> {
+    fn clone(&self) -> Self {
+        Self {
+            options: self.options.clone(),
+            gaps: self.gaps,
+        }
+    }
+}
+
+impl<Complexity> PartialEq for ProfileColumn<Complexity> {
+    fn eq(&self, other: &Self) -> bool {
+        self.options == other.options && self.gaps == other.gaps
+    }
+}
+
+impl<Complexity> ProfileColumn<Complexity> {
+    /// The total number of lines in this column
+    pub fn lines(&self) -> usize {
+        self.options.iter().map(|(_, n)| n).sum::<usize>() + self.gaps
+    }
+
+    /// The most common set of residues in this column, or None if all lines have a gap
+    pub fn consensus(&self) -> Option<&[SequenceElement<Complexity>]> {
+        self.options.first().map(|(o, _)| o.as_slice())
+    }
+
+    /// The support for the consensus, the fraction of all lines that have the consensus residues
+    pub fn support(&self) -> f64 {
+        self.options
+            .first()
+            .map_or(0.0, |(_, n)| *n as f64 / self.lines() as f64)
+    }
+
+    /// The coverage of this column, the fraction of all lines that do not have a gap
+    pub fn coverage(&self) -> f64 {
+        let lines = self.lines();
+        if lines == 0 {
+            0.0
+        } else {
+            (lines - self.gaps) as f64 / lines as f64
+        }
+    }
+}
+
+impl<'lifetime, Complexity> MultiAlignment<'lifetime, Complexity> {
+    /// The lines of this alignment, in the same order as the sequences given to [`multi_align`]
+    pub fn lines(&self) -> &[MultiAlignmentLine<'lifetime, Complexity>] {
+        &self.lines
+    }
+
+    /// The number of columns in this alignment
+    pub fn len(&self) -> usize {
+        self.lines.first().map_or(0, |l| l.path.len())
+    }
+
+    /// Check if this alignment is empty (has no columns)
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The alignment type used for all pairwise alignments
+    pub const fn align_type(&self) -> AlignType {
+        self.align_type
+    }
+
+    /// The maximal step size (the const generic STEPS)
+    pub const fn max_step(&self) -> u16 {
+        self.maximal_step
+    }
+
+    /// Clone the referenced sequences to make an alignment that owns the sequences.
+    #[must_use]
+    pub fn to_owned(&self) -> MultiAlignment<'static, Complexity> {
+        MultiAlignment {
+            lines: self
+                .lines
+                .iter()
+                .map(|line| MultiAlignmentLine {
+                    sequence: Cow::Owned(line.sequence.clone().into_owned()),
+                    path: line.path.clone(),
+                })
+                .collect(),
+            align_type: self.align_type,
+            maximal_step: self.maximal_step,
+        }
+    }
+
+    /// Get the profile of this alignment, for every column all sets of residues found in that
+    /// column and the number of lines that support each set.
+    pub fn profile(&self) -> Vec<ProfileColumn<Complexity>> {
+        let mut profile = (0..self.len())
+            .map(|_| ProfileColumn {
+                options: Vec::new(),
+                gaps: 0,
+            })
+            .collect_vec();
+        for line in &self.lines {
+            for (column, residues) in profile.iter_mut().zip(line.columns()) {
+                if residues.is_empty() {
+                    column.gaps += 1;
+                } else if let Some(option) = column.options.iter_mut().find(|(o, _)| o == residues)
+                {
+                    option.1 += 1;
+                } else {
+                    column.options.push((residues.to_vec(), 1));
+                }
+            }
+        }
+        for column in &mut profile {
+            column.options.sort_by_key(|o| std::cmp::Reverse(o.1));
+        }
+        profile
+    }
+
+    /// Merge a new sequence into this alignment, using the segments of the pairwise alignment of
+    /// the sequence on the line with the given index with the new sequence.
+    fn merge(
+        &mut self,
+        line: usize,
+        sequence: &'lifetime Peptidoform<Complexity>,
+        segments: &[(u16, u16, MatchType, isize)],
+    ) {
+        let columns = self.len();
+        let mut lines = vec![Vec::with_capacity(columns); self.lines.len() + 1];
+        let mut column = 0;
+        let mut segment = 0;
+        while column < columns || segment < segments.len() {
+            if segment < segments.len() && segments[segment].0 == 0 {
+                // Insertion in the new sequence, if the aligned line has a gap here as well the
+                // insertion is placed in that column, otherwise a new column is created
+                if column < columns && self.lines[line].path[column].step == 0 {
+                    for (path, existing) in lines.iter_mut().zip(&self.lines) {
+                        path.push(existing.path[column]);
+                    }
+                    column += 1;
+                } else {
+                    for path in &mut lines[..self.lines.len()] {
+                        path.push(MultiPiece::gap());
+                    }
+                }
+                lines[self.lines.len()].push(MultiPiece {
+                    step: segments[segment].1,
+                    match_type: segments[segment].2,
+                    local_score: segments[segment].3,
+                    score: 0,
+                });
+                segment += 1;
+            } else if column < columns
+                && (self.lines[line].path[column].step == 0 || segment >= segments.len())
+            {
+                // Gap in the aligned line
+                for (path, existing) in lines.iter_mut().zip(&self.lines) {
+                    path.push(existing.path[column]);
+                }
+                lines[self.lines.len()].push(MultiPiece::gap());
+                column += 1;
+            } else if column < columns {
+                // Find the smallest block of columns and segments that cover the same residues
+                let start_column = column;
+                let start_segment = segment;
+                let mut column_residues = self.lines[line].path[column].step;
+                let mut segment_residues = segments[segment].0;
+                column += 1;
+                segment += 1;
+                while column_residues != segment_residues {
+                    if column_residues < segment_residues && column < columns {
+                        column_residues += self.lines[line].path[column].step;
+                        column += 1;
+                    } else if segment < segments.len() {
+                        segment_residues += segments[segment].0;
+                        segment += 1;
+                    } else {
+                        break;
+                    }
+                }
+                for (path, existing) in lines.iter_mut().zip(&self.lines) {
+                    path.push(merge_pieces(&existing.path[start_column..column]));
+                }
+                let new = &segments[start_segment..segment];
+                lines[self.lines.len()].push(MultiPiece {
+                    score: 0,
+                    local_score: new.iter().map(|s| s.3).sum(),
+                    match_type: if new.len() == 1 {
+                        new[0].2
+                    } else {
+                        MatchType::Isobaric
+                    },
+                    step: new.iter().map(|s| s.1).sum(),
+                });
+            } else {
+                // Segments left that are not covered by the line (cannot happen for valid paths)
+                break;
+            }
+        }
+        let new = lines.pop().unwrap_or_default();
+        for (existing, path) in self.lines.iter_mut().zip(lines) {
+            existing.path = path;
+        }
+        self.lines.push(MultiAlignmentLine {
+            sequence: Cow::Borrowed(sequence),
+            path: new,
+        });
+    }
+}
+
+/// Merge multiple pieces of a single line into one piece
+fn merge_pieces(pieces: &[MultiPiece]) -> MultiPiece {
+    if let [piece] = pieces {
+        *piece
+    } else {
+        let step = pieces.iter().map(|p| p.step).sum();
+        MultiPiece {
+            score: 0,
+            local_score: pieces.iter().map(|p| p.local_score).sum(),
+            match_type: if step == 0 {
+                MatchType::Gap
+            } else {
+                MatchType::Isobaric
+            },
+            step,
+        }
+    }
 }
 
 impl<Complexity> MultiAlignmentLine<'_, Complexity> {
-    fn debug_display(&self) {
-        for piece in self
-            .path
+    /// The sequence
+    pub fn sequence(&self) -> &Peptidoform<Complexity> {
+        &self.sequence
+    }
+
+    /// The steps taken for this line, one for every column
+    pub fn path(&self) -> &[MultiPiece] {
+        &self.path
+    }
+
+    /// The residues of this line in every column, empty for a gap
+    pub fn columns(&self) -> impl Iterator<Item = &[SequenceElement<Complexity>]> + '_ {
+        let mut index = 0;
+        self.path.iter().map(move |piece| {
+            let residues = &self.sequence.sequence()[index..index + piece.step as usize];
+            index += piece.step as usize;
+            residues
+        })
+    }
+}
+
+impl<Complexity> std::fmt::Display for MultiAlignment<'_, Complexity> {
+    /// Show the alignment, one line per sequence. Columns where at least one of the lines has
+    /// multiple residues are padded with `·`, gaps are shown as `-`.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let widths = (0..self.len())
+            .map(|column| {
+                self.lines
+                    .iter()
+                    .map(|l| l.path[column].step as usize)
+                    .max()
+                    .unwrap_or_default()
+                    .max(1)
+            })
+            .collect_vec();
+        for line in &self.lines {
+            for (residues, width) in line.columns().zip(&widths) {
+                if residues.is_empty() {
+                    write!(f, "{}", "-".repeat(*width))?;
+                } else {
+                    for residue in residues {
+                        write!(f, "{}", residue.aminoacid.char())?;
+                    }
+                    write!(f, "{}", "·".repeat(width - residues.len()))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc, clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn linear(aa: &str) -> Peptidoform<SimpleLinear> {
+        Peptidoform::pro_forma(aa, None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+    }
+
+    #[test]
+    fn identical() {
+        let peptides = [linear("PEPTIDE"), linear("PEPTIDE"), linear("PEPTIDE")];
+        let alignment =
+            multi_align::<4, SimpleLinear>(&peptides, AlignScoring::default(), AlignType::GLOBAL);
+        assert_eq!(alignment.len(), 7);
+        assert_eq!(alignment.to_string(), "PEPTIDE\n".repeat(3));
+        assert!(alignment
+            .profile()
             .iter()
-            .zip(self.sequence.sequence().iter().skip(self.start))
-        {
-            print!(
-                "{}{}",
-                piece.1.aminoacid.char(),
-                "·".repeat(piece.0.step as usize - 1)
+            .all(|c| c.support() == 1.0 && c.coverage() == 1.0));
+    }
+
+    #[test]
+    fn isobaric_merge() {
+        let peptides = [linear("AGGWK"), linear("AGGWK"), linear("ANWK")];
+        let alignment =
+            multi_align::<4, SimpleLinear>(&peptides, AlignScoring::default(), AlignType::GLOBAL);
+        assert_eq!(alignment.to_string(), "AGGWK\nAGGWK\nAN·WK\n");
+        let profile = alignment.profile();
+        assert_eq!(profile.len(), 4);
+        assert_eq!(profile[1].options.len(), 2);
+        assert!((profile[1].support() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(profile[1].consensus().unwrap(), linear("GG").sequence());
+        let last = alignment.lines()[2].path().last().unwrap();
+        assert_eq!(
+            last.score,
+            alignment.lines()[2]
+                .path()
+                .iter()
+                .map(|p| p.local_score)
+                .sum::<isize>()
+        );
+    }
+
+    #[test]
+    fn overlapping() {
+        let peptides = [linear("WGKTHRK"), linear("KTHRKLM"), linear("GKTHR")];
+        let alignment = multi_align::<4, SimpleLinear>(
+            &peptides,
+            AlignScoring::default(),
+            AlignType::EITHER_GLOBAL,
+        );
+        assert_eq!(alignment.to_string(), "WGKTHRK--\n--KTHRKLM\n-GKTHR---\n");
+        let profile = alignment.profile();
+        assert_eq!(profile[0].gaps, 2);
+        assert!((profile[2].coverage() - 1.0).abs() < 1e-9);
+        assert!((profile[7].coverage() - 1.0 / 3.0).abs() < 1e-9);
+        for (line, peptide) in alignment.lines().iter().zip(&peptides) {
+            assert_eq!(line.sequence(), peptide);
+            assert_eq!(
+                line.path().iter().map(|p| p.step as usize).sum::<usize>(),
+                peptide.len()
             );
         }
-        println!();
+    }
+
+    #[test]
+    fn empty() {
+        let alignment =
+            multi_align::<4, SimpleLinear>(&[], AlignScoring::default(), AlignType::GLOBAL);
+        assert!(alignment.is_empty());
+        assert!(alignment.profile().is_empty());
+        let single = [linear("AAA")];
+        let alignment =
+            multi_align::<4, SimpleLinear>(&single, AlignScoring::default(), AlignType::GLOBAL);
+        assert_eq!(alignment.to_string(), "AAA\n");
     }
 }
@@ -70,9 +70,18 @@ pub struct AlignScoring<'a> {
     ///
     /// Default: BLOSUM62.
     pub matrix: &'a [[i8; AminoAcid::TOTAL_NUMBER]; AminoAcid::TOTAL_NUMBER],
+    /// A position specific scoring matrix for the first sequence, with one row for every position
+    /// in the first sequence that is indexed by the amino acid of the second sequence. If set it
+    /// is used instead of `matrix` and `mismatch` for all one to one steps (identities and
+    /// mismatches) on the positions it covers. See [`MatrixBuilder`](crate::align::matrix::MatrixBuilder)
+    /// to create these.
+    ///
+    /// Default: None.
+    pub position_specific: Option<&'a [[i8; AminoAcid::TOTAL_NUMBER]]>,
     /// The tolerance of mass equality.
     ///
-    /// Default: 10ppm.
+    /// Default: [`Precision::comparison_ppm`](crate::Precision::comparison_ppm) of the global
+    /// precision policy (10ppm).
     pub tolerance: Tolerance<OrderedMass>,
     /// The mass mode for the alignment.
     ///
@@ -91,7 +100,8 @@ impl Default for AlignScoring<'static> {
             gap_start: -4,
             gap_extend: -1,
             matrix: matrices::BLOSUM62,
-            tolerance: crate::Tolerance::new_ppm(10.0),
+            position_specific: None,
+            tolerance: crate::Precision::global().tolerance(),
             mass_mode: MassMode::Monoisotopic,
         }
     }
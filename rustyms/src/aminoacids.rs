@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     formula::MolecularFormula,
-    fragment::{Fragment, FragmentType, PeptidePosition},
+    fragment::{BackboneCFragment, BackboneNFragment, Fragment, FragmentType, PeptidePosition},
     model::*,
     molecular_charge::CachedCharge,
     Multi, MultiChemical, NeutralLoss, SequencePosition,
@@ -36,6 +36,36 @@ impl AminoAcid {
         Self::Pyrrolysine,
     ];
 
+    /// All amino acids, in the order of their discriminant so `ALL_AMINO_ACIDS[aa as usize] == aa`
+    pub const ALL_AMINO_ACIDS: &'static [Self; Self::TOTAL_NUMBER] = &[
+        Self::Alanine,
+        Self::Arginine,
+        Self::Asparagine,
+        Self::AsparticAcid,
+        Self::Cysteine,
+        Self::Glutamine,
+        Self::GlutamicAcid,
+        Self::Glycine,
+        Self::Histidine,
+        Self::Isoleucine,
+        Self::Leucine,
+        Self::Lysine,
+        Self::Methionine,
+        Self::Phenylalanine,
+        Self::Proline,
+        Self::Serine,
+        Self::Threonine,
+        Self::Tryptophan,
+        Self::Tyrosine,
+        Self::Valine,
+        Self::AmbiguousAsparagine,
+        Self::AmbiguousLeucine,
+        Self::AmbiguousGlutamine,
+        Self::Selenocysteine,
+        Self::Pyrrolysine,
+        Self::Unknown,
+    ];
+
     /// All 20 canonical amino acids
     pub const CANONICAL_AMINO_ACIDS: &'static [Self] = &[
         Self::Glycine,
@@ -61,6 +91,11 @@ impl AminoAcid {
     ];
 
     // TODO: Take side chain mutations into account (maybe define pyrrolysine as a mutation)
+    /// The side chain groups that can be lost by β-γ cleavage of the side chain, resulting in the
+    /// satellite d and w ions. Residues without a γ substituent (G and A), with an aromatic side
+    /// chain (F, H, W, and Y), or with a cyclic side chain (P) do not form satellite ions. For
+    /// residues with two γ substituents all options are given, this is what allows I (losing CH3
+    /// or C2H5) to be distinguished from L (losing C3H7).
     /// # Panics
     /// When the sequence index is terminal.
     pub(crate) fn satellite_ion_fragments(
@@ -79,8 +114,8 @@ impl AminoAcid {
             | Self::Proline
             | Self::Tryptophan
             | Self::Tyrosine
-            | Self::Unknown => Multi::default(),
-            Self::Arginine => molecular_formula!(H 9 C 2 N 2).into(),
+            | Self::Unknown => Multi::from(Vec::new()),
+            Self::Arginine => molecular_formula!(H 6 C 2 N 3).into(),
             Self::Asparagine => molecular_formula!(H 2 C 1 N 1 O 1).into(),
             Self::AsparticAcid => molecular_formula!(H 1 C 1 O 2).into(),
             Self::AmbiguousAsparagine => vec![
@@ -111,7 +146,7 @@ impl AminoAcid {
             Self::Lysine => molecular_formula!(H 8 C 3 N 1).into(),
             Self::Methionine => molecular_formula!(H 5 C 2 S 1).into(),
             Self::Pyrrolysine => molecular_formula!(H 15 C 9 N 2 O 1).into(),
-            Self::Selenocysteine => molecular_formula!(Se 1).into(),
+            Self::Selenocysteine => molecular_formula!(H 1 Se 1).into(),
             Self::Serine => molecular_formula!(H 1 O 1).into(),
             Self::Threonine => vec![
                 molecular_formula!(H 1 O 1),
@@ -198,7 +233,7 @@ impl AminoAcid {
     /// |       | 55                                                                                                                        |                             |                                                | 55                                       |                                                                       | 55                                      |                             |                              |                   |                                                                          | 55.0548                                                             |                         |                         |                   |                                                                          |                              | 4       |   55.0548 |              | 17.0263  |              | H3N1             |                         | H3N1       |
     /// |       | 44                                                                                                                        |                             |                                                |                                          |                                                                       |                                         |                             |                              |                   |                                                                          |                                                                     |                         |                         |                   |                                                                          |                              | 1       |        44 |              | 28.0811  |              | C1H2N1           |                         | C1H2N1     |
     /// |       |                                                                                                                           |                             |                                                | 41                                       |                                                                       | 41                                      |                             |                              |                   |                                                                          | 41.0391                                                             |                         |                         |                   |                                                                          |                              | 3       |   41.0391 |              | 31.0420  |              | C1H5N1           |                         | C1H5N1     |
-    fn immonium_losses(self) -> Vec<NeutralLoss> {
+    pub(crate) fn immonium_losses(self) -> Vec<NeutralLoss> {
         // TODO: For B/Z there are common immonium ions, but the mass is the same (meaning the loss is different), find a way of representing that
         match self {
             Self::Arginine => vec![
@@ -290,125 +325,140 @@ impl AminoAcid {
         let c_pos = PeptidePosition::c(sequence_index, sequence_length);
 
         if ions.a.0 && allow_terminal.0 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
-                    * (modifications - molecular_formula!(H 1 C 1 O 1))),
+                    * (modifications + BackboneNFragment::a.offset())),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::a(n_pos),
                 n_term,
                 ions.a.1,
+                ions.a.3,
                 charge_carriers,
                 ions.a.2,
             ));
         }
         if ions.b.0 && allow_terminal.0 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
-                    * (modifications - molecular_formula!(H 1))),
+                    * (modifications + BackboneNFragment::b.offset())),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::b(n_pos),
                 n_term,
                 ions.b.1,
+                ions.b.3,
                 charge_carriers,
                 ions.b.2,
             ));
         }
         if ions.c.0 && allow_terminal.0 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
-                    * (modifications + molecular_formula!(H 2 N 1))),
+                    * (modifications + BackboneNFragment::c.offset())),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::c(n_pos),
                 n_term,
                 ions.c.1,
+                ions.c.3,
                 charge_carriers,
                 ions.c.2,
             ));
         }
+        // The d ion is an a· ion that lost the γ substituent of the side chain of its last residue
         if ions.d.0 && allow_terminal.0 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(-self.satellite_ion_fragments(sequence_index, peptidoform_index)
                     * modifications
                     * self.formulas_inner(sequence_index, peptidoform_index)
-                    + molecular_formula!(H 1 C 1 O 1)),
+                    + molecular_formula!(C -1 O -1)),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::d(n_pos),
                 n_term,
                 ions.d.1,
+                ions.d.3,
                 charge_carriers,
                 ions.d.2,
             ));
         }
-        if ions.v.0 && allow_terminal.1 {
-            base_fragments.extend(Fragment::generate_all(
+        // Glycine has no side chain to lose and the side chain of proline is bound to the backbone
+        if ions.v.0
+            && allow_terminal.1
+            && !matches!(self, Self::Glycine | Self::Proline | Self::Unknown)
+        {
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &molecular_formula!(H 3 C 2 N 1 O 1).into(),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::v(c_pos),
                 c_term,
                 ions.v.1,
+                ions.v.3,
                 charge_carriers,
                 ions.v.2,
             ));
         }
+        // The w ion is a z· ion that lost the γ substituent of the side chain of its first residue
         if ions.w.0 && allow_terminal.1 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(-self.satellite_ion_fragments(sequence_index, peptidoform_index)
                     * modifications
                     * self.formulas_inner(sequence_index, peptidoform_index)
-                    + molecular_formula!(H 2 N 1)),
+                    + molecular_formula!(H -1 N -1)),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::w(c_pos),
                 c_term,
                 ions.w.1,
+                ions.w.3,
                 charge_carriers,
                 ions.w.2,
             ));
         }
         if ions.x.0 && allow_terminal.1 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
-                    * (modifications + molecular_formula!(C 1 O 1) - molecular_formula!(H 1))),
+                    * (modifications + BackboneCFragment::x.offset())),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::x(c_pos),
                 c_term,
                 ions.x.1,
+                ions.x.3,
                 charge_carriers,
                 ions.x.2,
             ));
         }
         if ions.y.0 && allow_terminal.1 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
-                    * (modifications + molecular_formula!(H 1))),
+                    * (modifications + BackboneCFragment::y.offset())),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::y(c_pos),
                 c_term,
                 ions.y.1,
+                ions.y.3,
                 charge_carriers,
                 ions.y.2,
             ));
         }
         if ions.z.0 && allow_terminal.1 {
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
-                    * (modifications - molecular_formula!(H 2 N 1))),
+                    * (modifications + BackboneCFragment::z.offset())),
                 peptidoform_ion_index,
                 peptidoform_index,
                 &FragmentType::z(c_pos),
                 c_term,
                 ions.z.1,
+                ions.z.3,
                 charge_carriers,
                 ions.z.2,
             ));
-            base_fragments.extend(Fragment::generate_all(
+            base_fragments.extend(Fragment::generate_all_combined_losses(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
                     * (modifications - molecular_formula!(H 1 N 1))),
                 peptidoform_ion_index,
@@ -416,12 +466,13 @@ impl AminoAcid {
                 &FragmentType::z·(c_pos),
                 c_term,
                 ions.z.1,
+                ions.z.3,
                 charge_carriers,
                 ions.z.2,
             ));
         }
 
-        if ions.immonium.0 && allow_terminal.0 && allow_terminal.1 {
+        if ions.immonium.allows(self) && allow_terminal.0 && allow_terminal.1 {
             base_fragments.extend(Fragment::generate_all(
                 &(self.formulas_inner(sequence_index, peptidoform_index)
                     * (modifications - molecular_formula!(C 1 O 1))),
@@ -429,9 +480,9 @@ impl AminoAcid {
                 peptidoform_index,
                 &FragmentType::Immonium(n_pos, self.into()), // TODO: get the actual sequenceelement here
                 &Multi::default(),
-                self.immonium_losses().as_slice(),
+                ions.immonium.related_ion_losses(self).as_slice(),
                 charge_carriers,
-                ions.immonium.1,
+                ions.immonium.charge_range,
             ));
         }
         base_fragments
@@ -574,6 +625,16 @@ mod tests {
         assert!((mass_ala.value - 71.037113783).abs() < 1e-5);
     }
 
+    #[test]
+    fn all_amino_acids_order() {
+        for (index, aa) in AminoAcid::ALL_AMINO_ACIDS.iter().enumerate() {
+            assert_eq!(*aa as usize, index);
+        }
+        assert!(AminoAcid::CANONICAL_AMINO_ACIDS
+            .iter()
+            .all(|aa| AminoAcid::ALL_AMINO_ACIDS.contains(aa)));
+    }
+
     #[test]
     fn mass_lysine() {
         let weight_lys = AminoAcid::Lysine.formulas()[0].average_weight();
@@ -35,9 +35,9 @@ pub enum Context {
         line_index: Option<usize>,
         /// The line to show the issue itself.
         line: String,
-        /// The offset of the special position to be annotated.
+        /// The offset of the special position to be annotated, in bytes.
         offset: usize,
-        /// The length of the special position to be annotated.
+        /// The length of the special position to be annotated, in bytes.
         length: usize,
     },
     /// To show multiple lines where an error occurred.
@@ -124,9 +124,9 @@ impl Context {
                     &line,
                     start,
                     match end {
-                        Bound::Excluded(n) => n - 1,
+                        Bound::Excluded(n) => n.saturating_sub(1),
                         Bound::Included(n) => *n,
-                        Bound::Unbounded => line.chars().count(),
+                        Bound::Unbounded => line.len(),
                     }
                     .saturating_sub(start),
                 )
@@ -293,11 +293,11 @@ impl Context {
                     &line[start..end],
                     if end == line.len() { "" } else { "…" },
                     "",
-                    " ".repeat(*offset - start + usize::from(start != 0)),
+                    " ".repeat(char_width(line, start, *offset) + usize::from(start != 0)),
                     if *length == 0 {
                         "└".to_string()
                     } else {
-                        "‾".repeat(*length)
+                        "‾".repeat(char_width(line, *offset, offset + length).max(1))
                     },
                     pad = margin
                 )?;
@@ -387,6 +387,16 @@ impl fmt::Display for Context {
     }
 }
 
+/// The number of characters in the given byte range of the line, so that annotations line up with
+/// the text even if it contains multi byte characters. Any part of the range outside of the line
+/// (or not on character boundaries) is counted as one character per byte.
+fn char_width(line: &str, start: usize, end: usize) -> usize {
+    let clamped = end.min(line.len());
+    line.get(start.min(clamped)..clamped)
+        .map_or_else(|| clamped.saturating_sub(start), |s| s.chars().count())
+        + (end - clamped)
+}
+
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
 /// A position in a file for use in parsing/lexing
 pub struct FilePosition<'a> {
@@ -184,6 +184,11 @@ impl CustomError {
     pub const fn context(&self) -> &Context {
         &self.content.context
     }
+
+    /// Gives the underlying errors for this error
+    pub fn underlying_errors(&self) -> &[Self] {
+        &self.content.underlying_errors
+    }
 }
 
 impl fmt::Debug for CustomError {
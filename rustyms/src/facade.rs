@@ -0,0 +1,727 @@
+//! High level functions for common end to end tasks
+
+use std::{
+    collections::HashMap,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::CustomError,
+    fragment::Fragment,
+    identification::{AnnotationScore, Experiment, FastaData, IdentifiedPeptide},
+    modification::{SimpleModification, SimpleModificationInner},
+    modification_search_mass,
+    placement_rule::{Position, ProteinTermini},
+    spectrum::{AnnotatableSpectrum, AnnotatedSpectrum},
+    system::{da, e, usize::Charge, Mass},
+    MassMode, Model, Modification, Peptidoform, Protease, RawSpectrum, SemiAmbiguous, Tolerance,
+    WithinTolerance,
+};
+
+/// An identified peptide annotated on its spectrum, see [`annotate_file`]
+#[derive(Clone, Debug)]
+pub struct AnnotatedPsm {
+    /// The identified peptide
+    pub peptide: IdentifiedPeptide,
+    /// The raw spectrum
+    pub spectrum: RawSpectrum,
+    /// The theoretical fragments that were used for the annotation
+    pub fragments: Vec<Fragment>,
+    /// The annotated spectrum
+    pub annotated: AnnotatedSpectrum,
+}
+
+/// Annotate all identified peptides in the given identification file on their spectra in the given
+/// raw file. This combines [`Experiment::link_spectra`], generating the theoretical fragments, and
+/// [`AnnotatableSpectrum::annotate`] (with monoisotopic masses). The theoretical fragments are
+/// generated up to the charge of the peptide, or the charge of the spectrum if the peptide charge
+/// is not known. Peptides without a sequence, without any known charge, or whose spectra are not
+/// found are left out. Every spectrum of a peptide results in a separate annotated PSM.
+///
+/// The peptides and their spectra are read when this function is called, the fragments are only
+/// generated and annotated when the returned iterator is advanced.
+///
+/// ```rust
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// # use rustyms::prelude::*;
+/// let model = Model::all();
+/// let psms = annotate_file(
+///     "data/experiment/20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mgf",
+///     "src/identification/test_files/sage_v0_14.tsv",
+///     &model,
+/// )?;
+/// for psm in psms {
+///     println!("{}: {:.3}", psm.annotated.peptide, psm.annotated.intensity_explained());
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Only available with feature `identification`.
+/// # Errors
+/// If the raw file or identification file could not be opened, or if any peptide or spectrum
+/// could not be read. See [`Experiment`] for the supported raw files.
+pub fn annotate_file(
+    raw_file: impl Into<PathBuf>,
+    identification_file: impl Into<PathBuf>,
+    model: &Model,
+) -> Result<impl Iterator<Item = AnnotatedPsm> + '_, CustomError> {
+    let experiment = Experiment::new()
+        .raw_file(raw_file)
+        .identification_file(identification_file);
+    let peptides = experiment.identified_peptides(None)?;
+    #[allow(clippy::needless_collect)] // The linked spectra borrow from the local peptides
+    let linked: Vec<_> = experiment
+        .link_spectra(&peptides)?
+        .into_iter()
+        .filter_map(|linked| {
+            let peptidoform = linked.peptide.peptide()?.compound_peptidoform().into_owned();
+            Some((linked.peptide.clone(), peptidoform, linked.spectra))
+        })
+        .collect();
+    Ok(linked
+        .into_iter()
+        .flat_map(move |(peptide, peptidoform, spectra)| {
+            spectra.into_iter().filter_map(move |spectrum| {
+                let charge = peptide.charge().or(spectrum.charge)?;
+                let fragments = peptidoform.generate_theoretical_fragments(charge, model);
+                let annotated = spectrum.annotate(
+                    peptidoform.clone(),
+                    &fragments,
+                    model,
+                    MassMode::Monoisotopic,
+                );
+                Some(AnnotatedPsm {
+                    peptide: peptide.clone(),
+                    spectrum,
+                    fragments,
+                    annotated,
+                })
+            })
+        }))
+}
+
+/// The settings for [`digest_fasta`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DigestionSettings {
+    /// The maximal number of missed cleavages.
+    ///
+    /// Default: 2.
+    pub missed_cleavages: usize,
+    /// The minimal length of a peptide (inclusive).
+    ///
+    /// Default: 7.
+    pub min_length: usize,
+    /// The maximal length of a peptide (inclusive).
+    ///
+    /// Default: 50.
+    pub max_length: usize,
+}
+
+impl Default for DigestionSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DigestionSettings {
+    /// Create the default settings, see the documentation on the fields for the defaults
+    pub const fn new() -> Self {
+        Self {
+            missed_cleavages: 2,
+            min_length: 7,
+            max_length: 50,
+        }
+    }
+
+    /// Set the maximal number of missed cleavages
+    #[must_use]
+    pub const fn missed_cleavages(self, missed_cleavages: usize) -> Self {
+        Self {
+            missed_cleavages,
+            ..self
+        }
+    }
+
+    /// Set the minimal and maximal length of a peptide (both inclusive)
+    #[must_use]
+    pub const fn length(self, min_length: usize, max_length: usize) -> Self {
+        Self {
+            min_length,
+            max_length,
+            ..self
+        }
+    }
+}
+
+/// A peptide resulting from [`digest_fasta`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DigestedPeptide {
+    /// The peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The accession of the protein this peptide originates from
+    pub protein: String,
+    /// Which termini of this peptide are protein termini
+    pub termini: ProteinTermini,
+    /// The index of the first residue of this peptide in the protein sequence
+    pub start: usize,
+    /// The index of the residue after the last residue of this peptide in the protein sequence
+    pub end: usize,
+    /// The number of missed cleavages in this peptide
+    pub missed_cleavages: usize,
+}
+
+/// Digest all proteins in the given FASTA file with the given protease, see
+/// [`FastaData::digest`]. Only peptides within the length limits of the settings are returned,
+/// in the order of the proteins in the file.
+///
+/// ```rust
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// # use rustyms::prelude::*;
+/// let peptides = digest_fasta(
+///     "data/experiment/proteins.fasta",
+///     &Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]),
+///     &DigestionSettings::default().missed_cleavages(1),
+/// )?;
+/// assert!(peptides.iter().all(|p| (7..=50).contains(&p.peptide.len())));
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Only available with feature `identification`.
+/// # Errors
+/// If the FASTA file could not be opened or parsed.
+pub fn digest_fasta(
+    path: impl AsRef<Path>,
+    protease: &Protease,
+    settings: &DigestionSettings,
+) -> Result<Vec<DigestedPeptide>, CustomError> {
+    Ok(FastaData::parse_file(path)?
+        .iter()
+        .flat_map(|protein| digest_protein(protein, protease, settings))
+        .collect())
+}
+
+/// Digest a single protein, only keep the peptides within the length limits of the settings
+fn digest_protein(
+    protein: &FastaData,
+    protease: &Protease,
+    settings: &DigestionSettings,
+) -> Vec<DigestedPeptide> {
+    let accession = protein.identifier().accession().to_string();
+    protein
+        .digest(protease, settings.missed_cleavages)
+        .into_iter()
+        .filter(|(digested, _)| {
+            (settings.min_length..=settings.max_length).contains(&digested.peptide.len())
+        })
+        .map(|(digested, termini)| DigestedPeptide {
+            peptide: digested.peptide,
+            protein: accession.clone(),
+            termini,
+            start: digested.start,
+            end: digested.end,
+            missed_cleavages: digested.missed_cleavages,
+        })
+        .collect()
+}
+
+/// A unique peptide with all proteins containing it
+type DigestedDatabaseEntry = (Peptidoform<SemiAmbiguous>, Vec<(String, ProteinTermini)>);
+
+/// A deduplicated map from peptide to all proteins that contain this peptide, see
+/// [`digest_database`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DigestedDatabase {
+    peptides: Vec<DigestedDatabaseEntry>,
+    index: HashMap<Peptidoform<SemiAmbiguous>, usize>,
+}
+
+impl DigestedDatabase {
+    /// The number of unique peptides
+    pub fn len(&self) -> usize {
+        self.peptides.len()
+    }
+
+    /// Check if there are no peptides
+    pub fn is_empty(&self) -> bool {
+        self.peptides.is_empty()
+    }
+
+    /// Iterate over all unique peptides, in order of first occurrence in the database, with the
+    /// accessions of all proteins containing the peptide and which of the peptide termini are
+    /// protein termini in that protein
+    pub fn iter(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (&Peptidoform<SemiAmbiguous>, &[(String, ProteinTermini)])>
+    {
+        self.peptides
+            .iter()
+            .map(|(p, proteins)| (p, proteins.as_slice()))
+    }
+
+    /// Get all proteins that contain the given peptide, returns None if the peptide is not
+    /// present in the digested database
+    pub fn proteins(
+        &self,
+        peptide: &Peptidoform<SemiAmbiguous>,
+    ) -> Option<&[(String, ProteinTermini)]> {
+        self.index
+            .get(peptide)
+            .map(|index| self.peptides[*index].1.as_slice())
+    }
+
+    /// Add a digested peptide
+    fn add(&mut self, peptide: DigestedPeptide) {
+        let location = (peptide.protein, peptide.termini);
+        if let Some(index) = self.index.get(&peptide.peptide) {
+            let proteins = &mut self.peptides[*index].1;
+            if !proteins.contains(&location) {
+                proteins.push(location);
+            }
+        } else {
+            self.index
+                .insert(peptide.peptide.clone(), self.peptides.len());
+            self.peptides.push((peptide.peptide, vec![location]));
+        }
+    }
+}
+
+/// Digest all given proteins with the given protease, see [`FastaData::digest`], into a
+/// deduplicated map from peptide to all proteins containing that peptide, as needed for database
+/// search. Only peptides within the length limits of the settings are kept. If the crate feature
+/// `rayon` is turned on (the default) the proteins are digested in parallel.
+///
+/// ```rust
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// # use rustyms::prelude::*;
+/// let database = FastaData::parse_file("data/experiment/proteins.fasta")?;
+/// let digested = digest_database(
+///     &database,
+///     &Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]),
+///     &DigestionSettings::default().missed_cleavages(1),
+/// );
+/// for (peptide, proteins) in digested.iter() {
+///     assert!(!proteins.is_empty());
+///     assert!((7..=50).contains(&peptide.len()));
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Only available with feature `identification`.
+pub fn digest_database(
+    proteins: &[FastaData],
+    protease: &Protease,
+    settings: &DigestionSettings,
+) -> DigestedDatabase {
+    let digest = |protein| digest_protein(protein, protease, settings);
+    #[cfg(feature = "rayon")]
+    let digested: Vec<Vec<DigestedPeptide>> = {
+        use rayon::prelude::*;
+        proteins.par_iter().map(digest).collect()
+    };
+    #[cfg(not(feature = "rayon"))]
+    let digested: Vec<Vec<DigestedPeptide>> = proteins.iter().map(digest).collect();
+
+    let mut database = DigestedDatabase::default();
+    for peptide in digested.into_iter().flatten() {
+        database.add(peptide);
+    }
+    database
+}
+
+/// The settings for [`open_search`]
+#[derive(Clone, Debug)]
+pub struct OpenSearchSettings {
+    /// The range of mass offsets (precursor mass minus peptide mass) that is allowed.
+    ///
+    /// Default: -150 Da to 500 Da.
+    pub offsets: RangeInclusive<Mass>,
+    /// The tolerance for the precursor mass, peptides within this tolerance are seen as having no
+    /// mass offset.
+    ///
+    /// Default: 10 ppm.
+    pub precursor_tolerance: Tolerance<Mass>,
+    /// The tolerance for matching the mass offset to modifications from the ontologies.
+    ///
+    /// Default: 0.02 Da.
+    pub modification_tolerance: Tolerance<Mass>,
+    /// The number of best scoring peptides (scored without offset) for which the offset is
+    /// localised.
+    ///
+    /// Default: 10.
+    pub candidates: usize,
+    /// The score used to rank the peptides.
+    ///
+    /// Default: [`AnnotationScore::Hyperscore`].
+    pub scoring: AnnotationScore,
+}
+
+impl Default for OpenSearchSettings {
+    fn default() -> Self {
+        Self {
+            offsets: da(-150.0)..=da(500.0),
+            precursor_tolerance: Tolerance::new_ppm(10.0),
+            modification_tolerance: Tolerance::new_absolute(da(0.02)),
+            candidates: 10,
+            scoring: AnnotationScore::Hyperscore,
+        }
+    }
+}
+
+impl OpenSearchSettings {
+    /// Set the range of allowed mass offsets
+    #[must_use]
+    pub fn offsets(self, offsets: RangeInclusive<Mass>) -> Self {
+        Self { offsets, ..self }
+    }
+
+    /// Set the precursor tolerance
+    #[must_use]
+    pub fn precursor_tolerance(self, precursor_tolerance: Tolerance<Mass>) -> Self {
+        Self {
+            precursor_tolerance,
+            ..self
+        }
+    }
+
+    /// Set the modification tolerance
+    #[must_use]
+    pub fn modification_tolerance(self, modification_tolerance: Tolerance<Mass>) -> Self {
+        Self {
+            modification_tolerance,
+            ..self
+        }
+    }
+
+    /// Set the number of peptides for which the offset is localised
+    #[must_use]
+    pub const fn candidates(self, candidates: usize) -> Self {
+        Self { candidates, ..self }
+    }
+
+    /// Set the score used to rank the peptides
+    #[must_use]
+    pub const fn scoring(self, scoring: AnnotationScore) -> Self {
+        Self { scoring, ..self }
+    }
+}
+
+/// A peptide matched to a spectrum in an open search, see [`open_search`]
+#[derive(Clone, Debug)]
+pub struct OpenSearchMatch {
+    /// The peptide from the database
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The accessions of all proteins containing this peptide and which of the peptide termini
+    /// are protein termini in that protein
+    pub proteins: Vec<(String, ProteinTermini)>,
+    /// The unexplained mass, the precursor mass minus the peptide mass
+    pub offset: Mass,
+    /// The residue the offset is localised to, `None` if the offset is within the precursor
+    /// tolerance
+    pub position: Option<usize>,
+    /// The peptide with the offset placed as a mass modification on the localised residue
+    pub localised: Peptidoform<SemiAmbiguous>,
+    /// All modifications from the ontologies that match the offset and can be placed on the
+    /// localised residue
+    pub modifications: Vec<SimpleModification>,
+    /// The score of the localised peptide
+    pub score: f64,
+}
+
+/// Search the given spectrum against all peptides in the database allowing one unexplained mass
+/// offset. All peptides with an offset in the allowed range are first scored without the offset,
+/// then for the best peptides the offset is placed as a mass modification on every residue in
+/// turn and the best scoring residue is taken as the localisation. Finally the offset is looked
+/// up in the ontologies (see [`modification_search_mass`]) to annotate it with all modifications
+/// that fit the mass and can be placed on the localised residue. The theoretical fragments are
+/// generated up to the precursor charge (or 1 if that is not known) with monoisotopic masses.
+/// The matches are returned sorted from best to worst score, if the spectrum has no precursor
+/// mass nothing is returned.
+///
+/// ```rust
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// # use rustyms::prelude::*;
+/// let database = FastaData::parse_file("data/experiment/proteins.fasta")?;
+/// let digested = digest_database(
+///     &database,
+///     &Protease::trypsin(),
+///     &DigestionSettings::default(),
+/// );
+/// let spectrum = &rustyms::rawfile::mgf::open("data/annotated_example.mgf")?[0];
+/// for found in open_search(spectrum, &digested, &Model::all(), &OpenSearchSettings::default()) {
+///     println!("{} {:.3} {:?}", found.localised, found.offset.value, found.modifications);
+/// }
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Only available with feature `identification`.
+pub fn open_search(
+    spectrum: &RawSpectrum,
+    database: &DigestedDatabase,
+    model: &Model,
+    settings: &OpenSearchSettings,
+) -> Vec<OpenSearchMatch> {
+    let Some(precursor) = spectrum.mass else {
+        return Vec::new();
+    };
+    let charge = spectrum.charge.unwrap_or_else(|| Charge::new::<e>(1));
+    let score = |peptide: &Peptidoform<SemiAmbiguous>| {
+        let fragments: Vec<Fragment> = peptide
+            .generate_theoretical_fragments(charge, model)
+            .into_iter()
+            .filter(|f| {
+                f.mz(MassMode::Monoisotopic)
+                    .is_some_and(|mz| model.mz_range.contains(&mz))
+            })
+            .collect();
+        let annotated = spectrum.annotate(
+            peptide.clone().into(),
+            &fragments,
+            model,
+            MassMode::Monoisotopic,
+        );
+        settings.scoring.score(&annotated, &fragments)
+    };
+
+    // Score all peptides within the offset range without the offset
+    let mut candidates: Vec<_> = database
+        .iter()
+        .filter_map(|(peptide, proteins)| {
+            let mass = peptide.formulas().first()?.monoisotopic_mass();
+            let offset = precursor - mass;
+            settings
+                .offsets
+                .contains(&offset)
+                .then(|| (peptide, proteins, mass, score(peptide)))
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.3.total_cmp(&a.3));
+    candidates.truncate(settings.candidates);
+
+    // Localise the offset for the best peptides
+    let mut matches: Vec<OpenSearchMatch> = candidates
+        .into_iter()
+        .map(|(peptide, proteins, mass, unmodified)| {
+            let offset = precursor - mass;
+            let (position, localised, score) =
+                if settings.precursor_tolerance.within(&precursor, &mass) {
+                    (None, peptide.clone(), unmodified)
+                } else {
+                    (0..peptide.len())
+                        .map(|index| {
+                            let mut localised = peptide.clone();
+                            localised.sequence_mut()[index].modifications.push(
+                                Modification::Simple(Arc::new(SimpleModificationInner::Mass(
+                                    offset.into(),
+                                ))),
+                            );
+                            let score = score(&localised);
+                            (Some(index), localised, score)
+                        })
+                        .max_by(|a, b| a.2.total_cmp(&b.2).then(b.0.cmp(&a.0)))
+                        .unwrap_or_else(|| (None, peptide.clone(), unmodified))
+                };
+            let modifications = position.map_or_else(Vec::new, |index| {
+                let aminoacid = vec![peptide.sequence()[index].aminoacid.aminoacid()];
+                let mut positions = vec![(aminoacid.clone(), Position::Anywhere)];
+                if index == 0 {
+                    positions.push((aminoacid.clone(), Position::AnyNTerm));
+                }
+                if index == peptide.len() - 1 {
+                    positions.push((aminoacid, Position::AnyCTerm));
+                }
+                modification_search_mass(
+                    offset,
+                    settings.modification_tolerance,
+                    Some(&positions),
+                    MassMode::Monoisotopic,
+                    None,
+                )
+                .map(|(_, _, _, modification)| modification)
+                .collect()
+            });
+            OpenSearchMatch {
+                peptide: peptide.clone(),
+                proteins: proteins.to_vec(),
+                offset,
+                position,
+                localised,
+                modifications,
+                score,
+            }
+        })
+        .collect();
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+    matches
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{spectrum::PeakSpectrum, AminoAcid};
+
+    #[test]
+    fn annotate() {
+        let psms = annotate_file(
+            "data/experiment/20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mgf",
+            "src/identification/test_files/sage_v0_14.tsv",
+            &Model::all(),
+        )
+        .unwrap()
+        .collect::<Vec<_>>();
+        assert_eq!(psms.len(), 2);
+        for psm in &psms {
+            assert!(!psm.fragments.is_empty());
+            assert_eq!(
+                psm.annotated.spectrum().len(),
+                psm.spectrum.spectrum().len()
+            );
+        }
+        assert!(annotate_file("missing.mgf", "missing.tsv", &Model::all()).is_err());
+    }
+
+    #[test]
+    fn digest() {
+        let trypsin = Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]);
+        let all = digest_fasta(
+            "data/experiment/proteins.fasta",
+            &trypsin,
+            &DigestionSettings::default().length(1, usize::MAX),
+        )
+        .unwrap();
+        let limited = digest_fasta(
+            "data/experiment/proteins.fasta",
+            &trypsin,
+            &DigestionSettings::default().missed_cleavages(0),
+        )
+        .unwrap();
+        assert!(!limited.is_empty());
+        assert!(limited.len() < all.len());
+        assert!(limited
+            .iter()
+            .all(|p| (7..=50).contains(&p.peptide.len()) && !p.protein.is_empty()));
+    }
+
+    #[test]
+    fn digest_database_shared() {
+        let database = FastaData::parse_reader(
+            std::io::BufReader::new(
+                ">sp|P1|A A\nMPEPTIDEKAAGGLLRAA\n>sp|P2|B B\nKAAGGLLRPEPTIDE\n".as_bytes(),
+            ),
+            None,
+        )
+        .unwrap();
+        let trypsin = Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]);
+        let digested = digest_database(
+            &database,
+            &trypsin,
+            &DigestionSettings::default()
+                .missed_cleavages(0)
+                .length(3, 20),
+        );
+        assert_eq!(
+            digested
+                .iter()
+                .map(|(p, _)| p.to_string())
+                .collect::<Vec<_>>(),
+            vec!["MPEPTIDEK", "AAGGLLR", "PEPTIDE"]
+        );
+        let shared = digested
+            .proteins(
+                &Peptidoform::pro_forma("AAGGLLR", None)
+                    .unwrap()
+                    .into_semi_ambiguous()
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(shared.len(), 2);
+        assert_eq!(shared[0].0, "P1");
+        assert!(!shared[0].1.c_term && !shared[0].1.n_term);
+        assert_eq!(shared[1].0, "P2");
+        assert!(!shared[1].1.n_term && !shared[1].1.c_term);
+
+        let all = digest_fasta(
+            "data/experiment/proteins.fasta",
+            &trypsin,
+            &DigestionSettings::default(),
+        )
+        .unwrap();
+        let digested = digest_database(
+            &FastaData::parse_file("data/experiment/proteins.fasta").unwrap(),
+            &trypsin,
+            &DigestionSettings::default(),
+        );
+        assert!(digested.len() <= all.len());
+        assert!(all.iter().all(|p| digested
+            .proteins(&p.peptide)
+            .is_some_and(|proteins| proteins.contains(&(p.protein.clone(), p.termini)))));
+    }
+
+    #[test]
+    fn open_search_phospho() {
+        let database = FastaData::parse_reader(
+            std::io::BufReader::new(">sp|P1|A A\nMKAAGSPEPTIDEKLLGGVVAWKEEDDR\n".as_bytes()),
+            None,
+        )
+        .unwrap();
+        let digested = digest_database(
+            &database,
+            &Protease::trypsin(),
+            &DigestionSettings::default().length(5, 30),
+        );
+        let model = Model::cid_hcd();
+        let modified = Peptidoform::pro_forma("AAGS[Phospho]PEPTIDEK", None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        let charge = Charge::new::<e>(1);
+        let mut spectrum = RawSpectrum::default();
+        spectrum.mass = Some(modified.formulas()[0].monoisotopic_mass());
+        spectrum.charge = Some(charge);
+        let mut mzs: Vec<f64> = modified
+            .generate_theoretical_fragments(charge, &model)
+            .iter()
+            .filter(|f| {
+                f.neutral_loss.is_empty()
+                    && matches!(
+                        f.ion.kind(),
+                        crate::fragment::FragmentKind::b | crate::fragment::FragmentKind::y
+                    )
+            })
+            .filter_map(|f| f.mz(MassMode::Monoisotopic).map(|mz| mz.value))
+            .collect();
+        mzs.sort_by(f64::total_cmp);
+        mzs.dedup();
+        spectrum.extend(mzs.into_iter().map(|mz| {
+            crate::spectrum::RawPeak::new(
+                crate::system::MassOverCharge::new::<crate::system::mz>(mz),
+                1.0,
+            )
+        }));
+
+        let matches = open_search(&spectrum, &digested, &model, &OpenSearchSettings::default());
+        let best = &matches[0];
+        assert_eq!(best.peptide.to_string(), "AAGSPEPTIDEK");
+        assert_eq!(best.proteins[0].0, "P1");
+        assert!((best.offset.value - 79.966).abs() < 0.001);
+        assert_eq!(best.position, Some(3));
+        assert!(best
+            .modifications
+            .iter()
+            .any(|m| m.to_string().eq_ignore_ascii_case("U:Phospho")));
+        assert!(matches.windows(2).all(|w| w[0].score >= w[1].score));
+
+        spectrum.mass = None;
+        assert!(
+            open_search(&spectrum, &digested, &model, &OpenSearchSettings::default()).is_empty()
+        );
+    }
+}
@@ -1,7 +1,9 @@
 use crate::{
+    error::{Context, CustomError},
     system::{da, fraction, Mass, OrderedMass, Ratio},
-    MassMode,
+    MassMode, Precision,
 };
+use serde::{Deserialize, Serialize};
 use std::fmt::Write;
 
 #[macro_use]
@@ -60,6 +62,41 @@ impl MolecularFormula {
         self.monoisotopic_mass() + da(max.map_or(0, |f| f.0) as f64)
     }
 
+    /// Get the averagine formula for the given monoisotopic mass. This is the formula of a
+    /// hypothetical peptide with the average amino acid composition
+    /// (C<sub>4.9384</sub>H<sub>7.7583</sub>N<sub>1.3577</sub>O<sub>1.4773</sub>S<sub>0.0417</sub>,
+    /// Senko et al. 1995) rounded to whole atoms, with the remaining mass difference made up with
+    /// hydrogen atoms. It is used to model the isotope distribution of unidentified peptides. For
+    /// negative masses an empty formula is returned.
+    /// ```rust
+    /// # use rustyms::{*, system::da};
+    /// let formula = MolecularFormula::averagine(da(1000.0));
+    /// assert!((formula.monoisotopic_mass().value - 1000.0).abs() < 1.0);
+    /// ```
+    #[allow(clippy::missing_panics_doc)] // All elements are valid
+    pub fn averagine(mass: Mass) -> Self {
+        let units = (mass.value / 111.054_3).max(0.0);
+        #[allow(clippy::cast_possible_truncation)]
+        let amount = |per_unit: f64| (units * per_unit).round() as i32;
+        let mut formula = Self::new(
+            &[
+                (crate::Element::C, None, amount(4.9384)),
+                (crate::Element::H, None, amount(7.7583)),
+                (crate::Element::N, None, amount(1.3577)),
+                (crate::Element::O, None, amount(1.4773)),
+                (crate::Element::S, None, amount(0.0417)),
+            ],
+            &[],
+        )
+        .unwrap();
+        #[allow(clippy::cast_possible_truncation)]
+        let hydrogen = ((mass.value.max(0.0) - formula.monoisotopic_mass().value)
+            / crate::Element::H.mass(None).unwrap().value)
+            .round() as i32;
+        let _ = formula.add((crate::Element::H, None, hydrogen));
+        formula
+    }
+
     /// Get the mass in the given mode
     pub fn mass(&self, mode: MassMode) -> Mass {
         match mode {
@@ -124,6 +161,161 @@ impl MolecularFormula {
     }
 }
 
+/// The markup used to render a molecular formula, see [`FormulaStyle`]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FormulaMarkup {
+    /// Plain text in ProForma notation, isotopes are written as `[13C2]` and the charge as `:z+1`
+    #[default]
+    ProForma,
+    /// Unicode subscript and superscript characters, e.g. `¹³C₂H₄²⁺`
+    Unicode,
+    /// HTML `<sub>` and `<sup>` tags, e.g. `<sup>13</sup>C<sub>2</sub>`
+    Html,
+    /// LaTeX math mode, e.g. `{}^{13}\mathrm{C}_{2}`
+    Latex,
+}
+
+/// The style to render a molecular formula for display in user interfaces or reports, see
+/// [`MolecularFormula::hill_notation_styled`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FormulaStyle {
+    /// The markup used for isotopes, counts, and the charge.
+    ///
+    /// Default: [`FormulaMarkup::ProForma`].
+    pub markup: FormulaMarkup,
+    /// Leave out counts of one, `CH4` instead of `C1H4`.
+    ///
+    /// Default: false.
+    pub hide_single_counts: bool,
+    /// Show the charge of the formula (based on the number of electrons).
+    ///
+    /// Default: true.
+    pub show_charge: bool,
+}
+
+impl Default for FormulaStyle {
+    fn default() -> Self {
+        Self {
+            markup: FormulaMarkup::ProForma,
+            hide_single_counts: false,
+            show_charge: true,
+        }
+    }
+}
+
+impl FormulaStyle {
+    /// Create a style with the given markup and the default settings
+    pub const fn new(markup: FormulaMarkup) -> Self {
+        Self {
+            markup,
+            hide_single_counts: false,
+            show_charge: true,
+        }
+    }
+
+    /// Set if counts of one are left out
+    #[must_use]
+    pub const fn hide_single_counts(self, hide_single_counts: bool) -> Self {
+        Self {
+            hide_single_counts,
+            ..self
+        }
+    }
+
+    /// Set if the charge is shown
+    #[must_use]
+    pub const fn show_charge(self, show_charge: bool) -> Self {
+        Self {
+            show_charge,
+            ..self
+        }
+    }
+}
+
+impl MolecularFormula {
+    /// Create a [Hill notation](https://en.wikipedia.org/wiki/Chemical_formula#Hill_system) from
+    /// this collections of elements rendered in the given style, including any specific isotopes,
+    /// additional mass, and charge.
+    ///
+    /// ```rust
+    /// # use rustyms::*;
+    /// let formula = molecular_formula!(C 2 [13 C 1] H 4 Electron -2);
+    /// assert_eq!(formula.hill_notation_styled(FormulaStyle::new(FormulaMarkup::Unicode)), "C₂H₄¹³C₁²⁺");
+    /// assert_eq!(
+    ///     formula.hill_notation_styled(FormulaStyle::new(FormulaMarkup::Latex).hide_single_counts(true)),
+    ///     "\\mathrm{C}_{2}\\mathrm{H}_{4}{}^{13}\\mathrm{C}^{2+}"
+    /// );
+    /// ```
+    pub fn hill_notation_styled(&self, style: FormulaStyle) -> String {
+        let count = |n: i32| {
+            if style.hide_single_counts && n == 1 {
+                String::new()
+            } else {
+                match style.markup {
+                    FormulaMarkup::ProForma => n.to_string(),
+                    FormulaMarkup::Unicode => to_subscript_num(n as isize),
+                    FormulaMarkup::Html => format!("<sub>{n}</sub>"),
+                    FormulaMarkup::Latex => format!("_{{{n}}}"),
+                }
+            }
+        };
+        let elements: String = self
+            .hill_ordered_elements()
+            .into_iter()
+            .map(|(element, isotope, n)| match (style.markup, isotope) {
+                (FormulaMarkup::ProForma, Some(isotope)) => {
+                    format!("[{isotope}{element}{}]", count(*n))
+                }
+                (FormulaMarkup::Unicode, Some(isotope)) => {
+                    format!(
+                        "{}{element}{}",
+                        to_superscript_num(isotope.get()),
+                        count(*n)
+                    )
+                }
+                (FormulaMarkup::Html, Some(isotope)) => {
+                    format!("<sup>{isotope}</sup>{element}{}", count(*n))
+                }
+                (FormulaMarkup::Latex, Some(isotope)) => {
+                    format!("{{}}^{{{isotope}}}\\mathrm{{{element}}}{}", count(*n))
+                }
+                (FormulaMarkup::Latex, None) => format!("\\mathrm{{{element}}}{}", count(*n)),
+                (_, None) => format!("{element}{}", count(*n)),
+            })
+            .collect();
+        let additional_mass = if *self.additional_mass() == 0.0 {
+            String::new()
+        } else {
+            Precision::global().format_signed_mass(self.additional_mass().into_inner())
+        };
+        let charge = self.charge().value;
+        let charge = if !style.show_charge || charge == 0 {
+            String::new()
+        } else {
+            let sign = if charge > 0 { '+' } else { '-' };
+            let magnitude = if charge.abs() == 1 {
+                String::new()
+            } else {
+                charge.abs().to_string()
+            };
+            match style.markup {
+                FormulaMarkup::ProForma => format!(":z{charge:+}"),
+                FormulaMarkup::Unicode => format!(
+                    "{}{}",
+                    u16::try_from(charge.unsigned_abs())
+                        .ok()
+                        .filter(|c| *c != 1)
+                        .map_or_else(String::new, to_superscript_num),
+                    if charge > 0 { '\u{207A}' } else { '\u{207B}' }
+                ),
+                FormulaMarkup::Html => format!("<sup>{magnitude}{sign}</sup>"),
+                FormulaMarkup::Latex => format!("^{{{magnitude}{sign}}}"),
+            }
+        };
+        format!("{elements}{additional_mass}{charge}")
+    }
+}
+
 impl std::fmt::Display for AmbiguousLabel {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -185,11 +377,100 @@ impl std::fmt::Display for MolecularFormula {
     }
 }
 
+impl std::str::FromStr for MolecularFormula {
+    type Err = CustomError;
+
+    /// Parse a molecular formula from plain text, for example `C6H12O6`, `C6 H12 O6`, or with
+    /// isotopes `[13C6]H12O6`. This uses the ProForma formula syntax (see [`Self::from_pro_forma`])
+    /// allowing electrons to define the charge, `(empty)` for an empty formula, and all
+    /// elements. This can parse the output of [`Self::hill_notation`] back into the same formula.
+    /// ```rust
+    /// # use rustyms::*;
+    /// let formula: MolecularFormula = "[13C6]H12O6".parse().unwrap();
+    /// assert_eq!(formula, molecular_formula!([13 C 6] H 12 O 6));
+    /// assert_eq!(formula.to_string().parse::<MolecularFormula>().unwrap(), formula);
+    /// ```
+    /// # Errors
+    /// If the text is not a valid formula.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+        let start = s.len() - s.trim_start().len();
+        Self::from_pro_forma(s, start..start + trimmed.len(), true, true, true)
+    }
+}
+
+impl MolecularFormula {
+    /// Parse the formula of a ProForma `Formula:` modification, for example `Formula:C12H20O2`
+    /// or `Formula:[13C2]C-2H2N`. The `Formula:` prefix is optional and case insensitive. As in
+    /// ProForma electrons can be used to define the charge and all elements are allowed, see
+    /// [`Self::from_pro_forma`] for the full specification.
+    /// ```rust
+    /// # use rustyms::*;
+    /// let formula = MolecularFormula::from_pro_forma_modification("Formula:[13C2]C-2H2N").unwrap();
+    /// assert_eq!(formula, molecular_formula!([13 C 2] C -2 H 2 N 1));
+    /// ```
+    /// # Errors
+    /// If the text is not a valid ProForma formula.
+    pub fn from_pro_forma_modification(value: &str) -> Result<Self, CustomError> {
+        let trimmed = value.trim();
+        let mut start = value.len() - value.trim_start().len();
+        let end = start + trimmed.len();
+        if trimmed
+            .get(..8)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("formula:"))
+        {
+            start += 8;
+        }
+        if value[start..end].trim().is_empty() {
+            return Err(CustomError::error(
+                "Invalid ProForma formula",
+                "A formula modification must have a formula after the 'Formula:' prefix",
+                Context::line(None, value, start, end - start),
+            ));
+        }
+        Self::from_pro_forma(value, start..end, true, false, true)
+    }
+
+    /// Parse an mzPAF formula ion, for example `f{C13H9N2O3}` or `f{[13C2]C11H9N2O3}`. The
+    /// surrounding `f{}` is optional. This is the same syntax as used by mzPAF for neutral
+    /// losses and adducts, so it can also be used to parse `H2O` from the loss `-H2O`. Charges
+    /// are not allowed in mzPAF formulas.
+    /// ```rust
+    /// # use rustyms::*;
+    /// let formula = MolecularFormula::from_mz_paf("f{C13H9N2O3}").unwrap();
+    /// assert_eq!(formula, molecular_formula!(C 13 H 9 N 2 O 3));
+    /// ```
+    /// # Errors
+    /// If the text is not a valid mzPAF formula.
+    pub fn from_mz_paf(value: &str) -> Result<Self, CustomError> {
+        let trimmed = value.trim();
+        let start = value.len() - value.trim_start().len();
+        if let Some(inner) = trimmed.strip_prefix("f{") {
+            let Some(inner) = inner.strip_suffix('}') else {
+                return Err(CustomError::error(
+                    "Invalid mzPAF formula",
+                    "A formula must have the formula defined with curly braces '{}' after the 'f'",
+                    Context::line(None, value, start, trimmed.len()),
+                ));
+            };
+            Self::from_pro_forma(
+                value,
+                start + 2..start + 2 + inner.len(),
+                false,
+                false,
+                true,
+            )
+        } else {
+            Self::from_pro_forma(value, start..start + trimmed.len(), false, false, true)
+        }
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::missing_panics_doc)]
 mod tests {
     use crate::{
-        model::ChargeRange, molecular_formula, AminoAcid, Fragment, MolecularCharge,
+        model::ChargeRange, AminoAcid, FormulaMarkup, FormulaStyle, Fragment, MolecularCharge,
         MolecularFormula, MultiChemical,
     };
 
@@ -267,6 +548,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn from_str() {
+        let glucose = molecular_formula!(C 6 H 12 O 6);
+        for text in ["C6H12O6", "C6 H12 O6", " H12 C6 O6 ", "C 6 H 12 O 6"] {
+            assert_eq!(
+                text.parse::<MolecularFormula>(),
+                Ok(glucose.clone()),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            "[13C6]H12O6".parse::<MolecularFormula>(),
+            Ok(molecular_formula!([13 C 6] H 12 O 6))
+        );
+        assert_eq!(
+            "C6[13C-6]H12O6".parse::<MolecularFormula>(),
+            Ok(molecular_formula!(C 6 [13 C -6] H 12 O 6))
+        );
+        assert_eq!(
+            "(empty)".parse::<MolecularFormula>(),
+            Ok(MolecularFormula::default())
+        );
+        let formula = molecular_formula!(C 2 [13 C 1] H 4 N 1 Electron -2);
+        assert_eq!(
+            formula.hill_notation().parse::<MolecularFormula>(),
+            Ok(formula)
+        );
+        assert!("C6H12O6x".parse::<MolecularFormula>().is_err());
+        assert!("Xx2".parse::<MolecularFormula>().is_err());
+    }
+
+    #[test]
+    fn public_parsers() {
+        let formula = molecular_formula!([13 C 2] C -2 H 2 N 1);
+        for text in [
+            "Formula:[13C2]C-2H2N",
+            "formula:[13C2]C-2H2N",
+            " [13C2]C-2 H2 N ",
+        ] {
+            assert_eq!(
+                MolecularFormula::from_pro_forma_modification(text),
+                Ok(formula.clone()),
+                "{text}"
+            );
+        }
+        assert_eq!(
+            MolecularFormula::from_pro_forma_modification("Formula:Zn1:z+2"),
+            Ok(molecular_formula!(Zn 1 Electron -2))
+        );
+        assert!(MolecularFormula::from_pro_forma_modification("Formula:(empty)").is_err());
+        for text in ["Formula:", "formula: ", "formula:formula:", "Formula:Formula:C2"] {
+            assert!(
+                MolecularFormula::from_pro_forma_modification(text).is_err(),
+                "{text}"
+            );
+        }
+        let formula = molecular_formula!(C 13 H 9 N 2 O 3);
+        for text in ["f{C13H9N2O3}", "C13H9N2O3", " f{C13 H9 N2 O3} "] {
+            assert_eq!(
+                MolecularFormula::from_mz_paf(text),
+                Ok(formula.clone()),
+                "{text}"
+            );
+        }
+        assert!(MolecularFormula::from_mz_paf("f{C13H9N2O3").is_err());
+        assert!(MolecularFormula::from_mz_paf("f{C13H9N2O3:z+1}").is_err());
+    }
+
     #[test]
     fn pro_forma_empty() {
         assert_eq!(
@@ -357,4 +706,40 @@ mod tests {
         assert!(!all_fragments_labelled(&fragment_u));
         assert!(all_fragments_labelled(&fragment_ul));
     }
+
+    #[test]
+    fn styled() {
+        let formulas = [
+            molecular_formula!(H 2 O 1),
+            molecular_formula!(C 6 H 12 [15 N 2] O 6 Electron -1),
+            molecular_formula!(Na 1 Cl 1 Electron 2),
+            MolecularFormula::with_additional_mass(12.5),
+        ];
+        for formula in &formulas {
+            assert_eq!(
+                formula.hill_notation_styled(FormulaStyle::default()),
+                formula.hill_notation()
+            );
+        }
+        let formula = &formulas[1];
+        assert_eq!(
+            formula.hill_notation_styled(FormulaStyle::new(FormulaMarkup::Html)),
+            "C<sub>6</sub>H<sub>12</sub><sup>15</sup>N<sub>2</sub>O<sub>6</sub><sup>+</sup>"
+        );
+        assert_eq!(
+            formula
+                .hill_notation_styled(FormulaStyle::new(FormulaMarkup::Unicode).show_charge(false)),
+            formula.hill_notation_fancy().replace(":z+1", "")
+        );
+        assert_eq!(
+            formulas[2].hill_notation_styled(
+                FormulaStyle::new(FormulaMarkup::Unicode).hide_single_counts(true)
+            ),
+            "NaCl²⁻"
+        );
+        assert_eq!(
+            formulas[2].hill_notation_styled(FormulaStyle::new(FormulaMarkup::Latex)),
+            "\\mathrm{Na}_{1}\\mathrm{Cl}_{1}^{2-}"
+        );
+    }
 }
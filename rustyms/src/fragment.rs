@@ -19,7 +19,7 @@ use crate::{
         OrderedMassOverCharge,
     },
     AmbiguousLabel, AminoAcid, Chemical, MassMode, Modification, MolecularFormula, Multi,
-    NeutralLoss, SemiAmbiguous, SequenceElement, SequencePosition, Tolerance,
+    NeutralLoss, Precision, SemiAmbiguous, SequenceElement, SequencePosition, Tolerance,
 };
 
 /// A theoretical fragment of a peptide
@@ -43,6 +43,8 @@ pub struct Fragment {
     pub confidence: Option<OrderedFloat<f64>>,
     /// If this is an auxiliary fragment (from mzPAF)
     pub auxiliary: bool,
+    /// The isotope peak of this fragment, 0 for the monoisotopic peak, see [`Self::with_isotopes`]
+    pub isotope: usize,
 }
 
 impl Fragment {
@@ -82,6 +84,7 @@ impl Fragment {
             deviation: None,
             confidence: None,
             auxiliary: false,
+            isotope: 0,
         }
     }
 
@@ -100,25 +103,66 @@ impl Fragment {
         charge_carriers: &mut CachedCharge,
         charge_range: ChargeRange,
     ) -> Vec<Self> {
+        Self::generate_all_combined_losses(
+            theoretical_mass,
+            peptidoform_ion_index,
+            peptidoform_index,
+            annotation,
+            termini,
+            neutral_losses,
+            1,
+            charge_carriers,
+            charge_range,
+        )
+    }
+
+    /// Generate a list of possible fragments from the list of possible preceding termini and neutral losses,
+    /// where up to `max_neutral_losses` neutral losses are combined on a single fragment. The same loss can
+    /// be applied multiple times, so with a water loss and a maximum of 3 fragments with one, two, and three
+    /// water losses are generated.
+    /// # Panics
+    /// When the charge range results in a negative charge
+    #[allow(clippy::too_many_arguments)]
+    #[must_use]
+    pub fn generate_all_combined_losses(
+        theoretical_mass: &Multi<MolecularFormula>,
+        peptidoform_ion_index: usize,
+        peptidoform_index: usize,
+        annotation: &FragmentType,
+        termini: &Multi<MolecularFormula>,
+        neutral_losses: &[NeutralLoss],
+        max_neutral_losses: usize,
+        charge_carriers: &mut CachedCharge,
+        charge_range: ChargeRange,
+    ) -> Vec<Self> {
+        let losses = (1..=max_neutral_losses)
+            .flat_map(|n| {
+                neutral_losses
+                    .iter()
+                    .cloned()
+                    .combinations_with_replacement(n)
+            })
+            .collect_vec();
         termini
             .iter()
             .cartesian_product(theoretical_mass.iter())
             .cartesian_product(charge_carriers.range(charge_range))
-            .cartesian_product(std::iter::once(None).chain(neutral_losses.iter().map(Some)))
-            .map(|(((term, mass), charge), loss)| Self {
-                formula: Some(
+            .cartesian_product(std::iter::once(&Vec::new()).chain(losses.iter()))
+            .map(|(((term, mass), charge), losses)| Self {
+                formula: Some(losses.iter().fold(
                     term + mass
-                        + charge.formula_inner(SequencePosition::default(), peptidoform_index)
-                        + loss.unwrap_or(&NeutralLoss::Gain(MolecularFormula::default())),
-                ),
+                        + charge.formula_inner(SequencePosition::default(), peptidoform_index),
+                    |formula, loss| formula + loss,
+                )),
                 charge: Charge::new::<crate::system::e>(charge.charge().value.try_into().unwrap()),
                 ion: annotation.clone(),
                 peptidoform_ion_index: Some(peptidoform_ion_index),
                 peptidoform_index: Some(peptidoform_index),
-                neutral_loss: loss.map(|l| vec![l.clone()]).unwrap_or_default(),
+                neutral_loss: losses.clone(),
                 deviation: None,
                 confidence: None,
                 auxiliary: false,
+                isotope: 0,
             })
             .collect()
     }
@@ -177,6 +221,148 @@ impl Fragment {
         );
         output
     }
+
+    /// Create copies of this fragment for its isotope peaks up to and including the given isotope
+    /// (and a copy of this fragment itself as the monoisotopic peak). Isotope peaks with a lower
+    /// probability than the threshold in the isotopic distribution of the formula of this
+    /// fragment are skipped. The isotope peaks are spaced 1.003355 Da (the mass difference between
+    /// <sup>13</sup>C and <sup>12</sup>C) apart.
+    ///
+    /// Only available with crate feature 'isotopes'.
+    #[cfg(feature = "isotopes")]
+    #[must_use]
+    pub fn with_isotopes(&self, max_isotope: usize, threshold: f64) -> Vec<Self> {
+        let mut output = vec![self.clone()];
+        if let Some(formula) = &self.formula {
+            let distribution = formula.isotopic_distribution(threshold);
+            output.extend(
+                (1..=max_isotope)
+                    .filter(|isotope| {
+                        distribution
+                            .get(*isotope)
+                            .is_some_and(|probability| *probability >= threshold)
+                    })
+                    .map(|isotope| Self {
+                        formula: Some(
+                            formula.clone()
+                                + MolecularFormula::with_additional_mass(
+                                    isotope as f64 * crate::spectrum::ISOTOPE_SPACING,
+                                ),
+                        ),
+                        isotope: self.isotope + isotope,
+                        ..self.clone()
+                    }),
+            );
+        }
+        output
+    }
+
+    /// Check if the given neutral loss (or gain) is applied to this fragment
+    pub fn has_loss(&self, neutral_loss: &NeutralLoss) -> bool {
+        self.neutral_loss.contains(neutral_loss)
+    }
+
+    /// Get the sum of all neutral losses applied to this fragment, gains are counted as negative
+    /// losses. So a fragment with a loss of water and a gain of ammonia results in `H-1O1N-1`.
+    pub fn loss_formula_sum(&self) -> MolecularFormula {
+        self.neutral_loss
+            .iter()
+            .fold(MolecularFormula::default(), |acc, loss| match loss {
+                NeutralLoss::Loss(formula) => acc + formula,
+                NeutralLoss::Gain(formula) => acc - formula,
+            })
+    }
+
+    /// Check if this is a plain backbone ion, meaning an a, b, c, x, y, z, or z· ion without any
+    /// neutral losses. Satellite ions (d, v, w) and all non backbone ions are not plain.
+    pub fn is_plain_backbone_ion(&self) -> bool {
+        self.neutral_loss.is_empty()
+            && matches!(
+                self.ion,
+                FragmentType::a(_)
+                    | FragmentType::b(_)
+                    | FragmentType::c(_)
+                    | FragmentType::x(_)
+                    | FragmentType::y(_)
+                    | FragmentType::z(_)
+                    | FragmentType::z·(_)
+            )
+    }
+
+    /// Write this fragment as a [mzPAF](https://www.psidev.info/mzPAF) peak annotation. The
+    /// analyte number is the 1 based peptidoform ion index, it is left out for the first analyte
+    /// as that is the default in mzPAF. Satellite ions, glycan
+    /// fragments, and diagnostic ions cannot be expressed in mzPAF and are written as unknown
+    /// ions ('?'). A z· ion is written as z ion with a hydrogen gain.
+    pub fn to_mzpaf(&self) -> String {
+        let ordinal = |position: &PeptidePosition| match position.sequence_index {
+            SequencePosition::NTerm => 1,
+            SequencePosition::Index(i) => i + 1,
+            SequencePosition::CTerm => position.sequence_length,
+        };
+        let precision = Precision::global();
+        let ion = match &self.ion {
+            FragmentType::a(position) => format!("a{}", position.series_number),
+            FragmentType::b(position) => format!("b{}", position.series_number),
+            FragmentType::c(position) => format!("c{}", position.series_number),
+            FragmentType::x(position) => format!("x{}", position.series_number),
+            FragmentType::y(position) => format!("y{}", position.series_number),
+            FragmentType::z(position) => format!("z{}", position.series_number),
+            FragmentType::z·(position) => format!("z{}+H", position.series_number),
+            FragmentType::Immonium(_, element) => match element.modifications.as_slice() {
+                [] => format!("I{}", element.aminoacid.aminoacid().char()),
+                [Modification::Simple(modification)] => match &**modification {
+                    crate::modification::SimpleModificationInner::Database { id, .. }
+                        if id.ontology == crate::modification::Ontology::Unimod =>
+                    {
+                        format!("I{}[{}]", element.aminoacid.aminoacid().char(), id.name)
+                    }
+                    _ => "?".to_string(),
+                },
+                _ => "?".to_string(),
+            },
+            FragmentType::Internal(_, start, end) => {
+                format!("m{}:{}", ordinal(start), ordinal(end))
+            }
+            FragmentType::Unknown(Some(series)) => format!("?{series}"),
+            FragmentType::Precursor => "p".to_string(),
+            FragmentType::PrecursorChargeReduced(_) => self.ion.label().to_string(),
+            _ => "?".to_string(),
+        };
+        format!(
+            "{}{}{ion}{}{}{}{}{}",
+            if self.auxiliary { "&" } else { "" },
+            self.peptidoform_ion_index
+                .filter(|i| *i > 0)
+                .map_or(String::new(), |i| format!("{}@", i + 1)),
+            self.neutral_loss
+                .iter()
+                .map(NeutralLoss::hill_notation)
+                .join(""),
+            match self.isotope {
+                0 => String::new(),
+                1 => "+i".to_string(),
+                n => format!("+{n}i"),
+            },
+            if self.charge.value == 1 {
+                String::new()
+            } else {
+                format!("^{}", self.charge.value)
+            },
+            match self.deviation {
+                Some(Tolerance::Relative(ppm)) => format!(
+                    "/{}ppm",
+                    precision.format_ppm(ppm.get::<crate::system::ratio::ppm>())
+                ),
+                Some(Tolerance::Absolute(deviation)) => {
+                    format!("/{}", precision.format_mz(deviation.value))
+                }
+                None => String::new(),
+            },
+            self.confidence
+                .map_or(String::new(), |confidence| format!("*{confidence}")),
+        )
+    }
 }
 
 impl Display for Fragment {
@@ -186,7 +372,7 @@ impl Display for Fragment {
             "{}@{}{:+}{}",
             self.ion,
             self.mz(MassMode::Monoisotopic)
-                .map_or(String::new(), |mz| mz.value.to_string()),
+                .map_or(String::new(), |mz| Precision::global().format_mz(mz.value)),
             self.charge.value,
             self.neutral_loss
                 .iter()
@@ -265,6 +451,75 @@ impl PeptidePosition {
     }
 }
 
+/// The two products of a single cleavage of the peptide backbone, without any ion type specific
+/// chemistry applied. This can be used to build custom ion types (for example for novel
+/// dissociation methods) on top of the backbone cleavages of a peptide. The standard ion types
+/// can be made using the offsets from [`BackboneNFragment::offset`] and
+/// [`BackboneCFragment::offset`]. See [`Peptidoform::backbone_cleavages`](crate::Peptidoform::backbone_cleavages).
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct BackboneCleavage {
+    /// The position of the last residue on the N terminal side of the cleaved bond
+    pub n_position: PeptidePosition,
+    /// The position of the first residue on the C terminal side of the cleaved bond
+    pub c_position: PeptidePosition,
+    /// All possible formulas for the N terminal product, consisting of the N terminus and all
+    /// residues up to the cleaved bond (including their modifications). A neutral b fragment is
+    /// this formula minus one hydrogen.
+    pub n_terminal: Multi<MolecularFormula>,
+    /// All possible formulas for the C terminal product, consisting of all residues after the
+    /// cleaved bond and the C terminus (including their modifications). A neutral y fragment is
+    /// this formula plus one hydrogen.
+    pub c_terminal: Multi<MolecularFormula>,
+}
+
+impl BackboneCleavage {
+    /// Generate fragments from the N terminal product, with the given offset applied to all
+    /// formulas. All given neutral losses and all charges in the charge range are generated.
+    #[must_use]
+    pub fn n_terminal_fragments(
+        &self,
+        ion: &FragmentType,
+        offset: &MolecularFormula,
+        neutral_losses: &[NeutralLoss],
+        charge_carriers: &MolecularCharge,
+        charge_range: ChargeRange,
+    ) -> Vec<Fragment> {
+        Fragment::generate_all(
+            &offset.clone().into(),
+            0,
+            0,
+            ion,
+            &self.n_terminal,
+            neutral_losses,
+            &mut charge_carriers.into(),
+            charge_range,
+        )
+    }
+
+    /// Generate fragments from the C terminal product, with the given offset applied to all
+    /// formulas. All given neutral losses and all charges in the charge range are generated.
+    #[must_use]
+    pub fn c_terminal_fragments(
+        &self,
+        ion: &FragmentType,
+        offset: &MolecularFormula,
+        neutral_losses: &[NeutralLoss],
+        charge_carriers: &MolecularCharge,
+        charge_range: ChargeRange,
+    ) -> Vec<Fragment> {
+        Fragment::generate_all(
+            &offset.clone().into(),
+            0,
+            0,
+            ion,
+            &self.c_terminal,
+            neutral_losses,
+            &mut charge_carriers.into(),
+            charge_range,
+        )
+    }
+}
+
 /// The definition of the position of an ion inside a glycan
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub struct GlycanPosition {
@@ -357,14 +612,16 @@ pub enum FragmentType {
     z(PeptidePosition),
     /// z·
     z·(PeptidePosition),
-    // glycan A fragment (Never generated)
-    //A(GlycanPosition),
+    /// glycan A fragment, a cross-ring cleavage with the two broken ring bonds, the part not
+    /// containing the anomeric carbon of the cleaved monosaccharide
+    A(GlycanPosition, (u8, u8)),
     /// glycan B fragment
     B(GlycanPosition),
     // glycan C fragment (Never generated)
     //C(GlycanPosition),
-    // glycan X fragment (Never generated)
-    //X(GlycanPosition),
+    /// glycan X fragment, a cross-ring cleavage with the two broken ring bonds, the part containing
+    /// the anomeric carbon of the cleaved monosaccharide (and so the peptide)
+    X(GlycanPosition, (u8, u8)),
     /// glycan Y fragment, generated by one or more branches broken
     Y(Vec<GlycanPosition>),
     // glycan Z fragment (Never generated)
@@ -379,6 +636,10 @@ pub enum FragmentType {
     Immonium(PeptidePosition, SequenceElement<SemiAmbiguous>),
     /// Precursor with amino acid side chain loss
     PrecursorSideChainLoss(PeptidePosition, AminoAcid),
+    /// Charge reduced precursor, the precursor after capturing the given number of electrons
+    /// without dissociating (ECnoD/ETnoD), so `(M+nH)^(n-k)+•` for `k` electrons. This is labelled
+    /// as the precursor with the `k` captured hydrogens, `p+H` or `p+H{k}`, as in mzPAF.
+    PrecursorChargeReduced(usize),
     /// Diagnostic ion for a given position
     Diagnostic(DiagnosticPosition),
     /// An internal fragment, potentially with the named bonds that resulted in this fragment
@@ -418,7 +679,10 @@ impl FragmentType {
     /// Get the glycan position of this ion (or None not applicable)
     pub const fn glycan_position(&self) -> Option<&GlycanPosition> {
         match self {
-            Self::B(n) | Self::Diagnostic(DiagnosticPosition::Glycan(n, _)) => Some(n),
+            Self::A(n, _)
+            | Self::B(n)
+            | Self::X(n, _)
+            | Self::Diagnostic(DiagnosticPosition::Glycan(n, _)) => Some(n),
             _ => None,
         }
     }
@@ -439,7 +703,10 @@ impl FragmentType {
             | Self::Diagnostic(DiagnosticPosition::Peptide(n, _))
             | Self::Immonium(n, _)
             | Self::PrecursorSideChainLoss(n, _) => Some(n.series_number.to_string()),
-            Self::B(n) | Self::Diagnostic(DiagnosticPosition::Glycan(n, _)) => Some(n.label()),
+            Self::A(n, _)
+            | Self::B(n)
+            | Self::X(n, _)
+            | Self::Diagnostic(DiagnosticPosition::Glycan(n, _)) => Some(n.label()),
             Self::Y(bonds) => Some(bonds.iter().map(GlycanPosition::label).join("")),
             Self::Oxonium(breakages) => Some(
                 breakages
@@ -457,6 +724,7 @@ impl FragmentType {
                 Some(format!("{}:{}", pos1.sequence_index, pos2.sequence_index,))
             }
             Self::Precursor
+            | Self::PrecursorChargeReduced(_)
             | Self::Unknown(_)
             | Self::Diagnostic(
                 DiagnosticPosition::Labile(_)
@@ -479,7 +747,9 @@ impl FragmentType {
             Self::y(_) => Cow::Borrowed("y"),
             Self::z(_) => Cow::Borrowed("z"),
             Self::z·(_) => Cow::Borrowed("z·"),
+            Self::A(_, (first, second)) => Cow::Owned(format!("{first},{second}A")),
             Self::B(_) => Cow::Borrowed("B"),
+            Self::X(_, (first, second)) => Cow::Owned(format!("{first},{second}X")),
             Self::Y(_) | Self::YComposition(_, _) => Cow::Borrowed("Y"),
             Self::Diagnostic(DiagnosticPosition::Peptide(_, aa)) => {
                 Cow::Owned(format!("d{}", aa.char()))
@@ -494,6 +764,8 @@ impl FragmentType {
             Self::Immonium(_, aa) => Cow::Owned(format!("i{}", aa.aminoacid.char())),
             Self::PrecursorSideChainLoss(_, aa) => Cow::Owned(format!("p-s{}", aa.char())),
             Self::Precursor => Cow::Borrowed("p"),
+            Self::PrecursorChargeReduced(1) => Cow::Borrowed("p+H"),
+            Self::PrecursorChargeReduced(electrons) => Cow::Owned(format!("p+H{electrons}")),
             Self::Internal(fragmentation, _, _) => Cow::Owned(format!(
                 "m{}",
                 fragmentation.map_or(String::new(), |(n, c)| format!("{n}:{c}")),
@@ -517,17 +789,18 @@ impl FragmentType {
             Self::x(_) => FragmentKind::x,
             Self::y(_) => FragmentKind::y,
             Self::z(_) | Self::z·(_) => FragmentKind::z,
-            Self::Y(_) | Self::YComposition(_, _) => FragmentKind::Y,
+            Self::Y(_) | Self::YComposition(_, _) | Self::X(_, _) => FragmentKind::Y,
             Self::Diagnostic(
                 DiagnosticPosition::Glycan(_, _) | DiagnosticPosition::GlycanCompositional(_, _),
             )
+            | Self::A(_, _)
             | Self::B(_)
             | Self::Oxonium(_)
             | Self::OxoniumComposition(_, _) => FragmentKind::Oxonium,
             Self::Diagnostic(_) => FragmentKind::diagnostic,
             Self::Immonium(_, _) => FragmentKind::immonium,
             Self::PrecursorSideChainLoss(_, _) => FragmentKind::precursor_side_chain_loss,
-            Self::Precursor => FragmentKind::precursor,
+            Self::Precursor | Self::PrecursorChargeReduced(_) => FragmentKind::precursor,
             Self::Internal(_, _, _) => FragmentKind::internal,
             Self::Unknown(_) => FragmentKind::unknown,
         }
@@ -557,6 +830,18 @@ pub enum BackboneNFragment {
     c,
 }
 
+impl BackboneNFragment {
+    /// The formula that has to be added to the N terminal product of a [`BackboneCleavage`] to
+    /// get this fragment
+    pub fn offset(self) -> MolecularFormula {
+        match self {
+            Self::a => molecular_formula!(H -1 C -1 O -1),
+            Self::b => molecular_formula!(H - 1),
+            Self::c => molecular_formula!(H 2 N 1),
+        }
+    }
+}
+
 impl Display for BackboneNFragment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -583,6 +868,18 @@ pub enum BackboneCFragment {
     z,
 }
 
+impl BackboneCFragment {
+    /// The formula that has to be added to the C terminal product of a [`BackboneCleavage`] to
+    /// get this fragment, for z this gives the z fragment (z· has one additional hydrogen)
+    pub fn offset(self) -> MolecularFormula {
+        match self {
+            Self::x => molecular_formula!(C 1 O 1 H -1),
+            Self::y => molecular_formula!(H 1),
+            Self::z => molecular_formula!(H -2 N -1),
+        }
+    }
+}
+
 impl Display for BackboneCFragment {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -619,9 +916,9 @@ pub enum FragmentKind {
     y,
     /// z and z·
     z,
-    /// glycan Y fragment, generated by one or more branches broken
+    /// glycan Y fragment, generated by one or more branches broken, or glycan X fragment
     Y,
-    /// B or glycan diagnostic ion or Internal glycan fragment, meaning both a B and Y breakages (and potentially multiple of both), resulting in a set of monosaccharides
+    /// B or A or glycan diagnostic ion or Internal glycan fragment, meaning both a B and Y breakages (and potentially multiple of both), resulting in a set of monosaccharides
     Oxonium,
     /// Immonium ion
     immonium,
@@ -738,4 +1035,51 @@ mod tests {
         assert_eq!(n1.flip_terminal(), c1);
         assert_eq!(n2.flip_terminal(), c2);
     }
+
+    #[test]
+    fn backbone_cleavages() {
+        let peptide = crate::Peptidoform::pro_forma("[Acetyl]-AM[Oxidation]CBK", None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let model = crate::Model::none()
+            .b(crate::model::PrimaryIonSeries::default())
+            .y(crate::model::PrimaryIonSeries::default());
+        let expected = peptide
+            .generate_theoretical_fragments(Charge::new::<crate::system::charge::e>(1), &model)
+            .into_iter()
+            .filter(|f| match f.ion {
+                FragmentType::b(p) | FragmentType::y(p) => p.series_number < peptide.len(),
+                _ => false,
+            })
+            .map(|f| (f.ion, f.formula))
+            .sorted()
+            .collect_vec();
+        let proton = MolecularCharge::proton(1);
+        let actual = peptide
+            .backbone_cleavages(false)
+            .iter()
+            .flat_map(|cleavage| {
+                let mut fragments = cleavage.n_terminal_fragments(
+                    &FragmentType::b(cleavage.n_position),
+                    &BackboneNFragment::b.offset(),
+                    &[],
+                    &proton,
+                    ChargeRange::ONE,
+                );
+                fragments.extend(cleavage.c_terminal_fragments(
+                    &FragmentType::y(cleavage.c_position),
+                    &BackboneCFragment::y.offset(),
+                    &[],
+                    &proton,
+                    ChargeRange::ONE,
+                ));
+                fragments
+            })
+            .map(|f| (f.ion, f.formula))
+            .sorted()
+            .collect_vec();
+        assert_eq!(actual.len(), 12); // B is counted twice for the fragments containing it
+        assert_eq!(expected, actual);
+    }
 }
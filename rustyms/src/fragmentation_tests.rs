@@ -509,7 +509,7 @@ fn glycan_composition_fragmentation() {
     );
 }
 
-fn custom_database() -> CustomDatabase {
+pub fn custom_database() -> CustomDatabase {
     vec![
         (
             Some(0),
@@ -696,6 +696,431 @@ fn ensure_no_double_xl_labels_small_non_breaking() {
     assert_eq!(doubly_annotated.len(), 0);
 }
 
+#[test]
+fn deterministic_order() {
+    let peptide = CompoundPeptidoformIon::pro_forma(
+        "[Phospho]?EC[C:Disulfide#XL1]AS[Phospho#1]C[#XL1]S[#1]C[C:Disulfide#XL3]D//DC[#XL3]",
+        Some(&custom_database()),
+    )
+    .unwrap();
+    let model = Model::all().allow_cross_link_cleavage(true);
+    let serialise = || {
+        bincode::serialize(
+            &peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(3), &model),
+        )
+        .unwrap()
+    };
+    let first = serialise();
+    for _ in 0..3 {
+        assert_eq!(first, serialise());
+    }
+}
+
+#[test]
+fn neutral_loss_cap() {
+    let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDE", None).unwrap();
+    let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+    let phospho = NeutralLoss::Loss(molecular_formula!(H 3 P 1 O 4));
+    let model = Model::none()
+        .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone(), phospho.clone()]));
+    let charge = Charge::new::<crate::system::e>(1);
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert!(fragments.iter().any(|f| f.has_loss(&phospho)));
+    let plain = fragments
+        .iter()
+        .filter(|f| f.is_plain_backbone_ion())
+        .count();
+    assert_eq!(plain, 6);
+    assert!(fragments
+        .iter()
+        .filter(|f| f.has_loss(&water))
+        .all(|f| f.loss_formula_sum() == molecular_formula!(H 2 O 1)));
+
+    let capped = peptide.generate_theoretical_fragments(
+        charge,
+        &model.max_neutral_loss_mass(Some(crate::system::Mass::new::<crate::system::dalton>(
+            20.0,
+        ))),
+    );
+    assert!(capped.iter().any(|f| f.has_loss(&water)));
+    assert!(!capped.iter().any(|f| f.has_loss(&phospho)));
+    assert_eq!(
+        capped.iter().filter(|f| f.is_plain_backbone_ion()).count(),
+        plain
+    );
+}
+
+#[test]
+fn combined_neutral_losses() {
+    let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDE", None).unwrap();
+    let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+    let ammonia = NeutralLoss::Loss(molecular_formula!(H 3 N 1));
+    let charge = Charge::new::<crate::system::e>(1);
+    let b = |fragments: &[Fragment], losses: &[&NeutralLoss]| {
+        fragments
+            .iter()
+            .filter(|f| {
+                matches!(f.ion, fragment::FragmentType::b(_))
+                    && f.neutral_loss.len() == losses.len()
+                    && losses.iter().all(|l| {
+                        f.neutral_loss.iter().filter(|n| n == l).count()
+                            == losses.iter().filter(|n| *n == l).count()
+                    })
+            })
+            .count()
+    };
+
+    let model = Model::none().b(PrimaryIonSeries::default()
+        .neutral_losses(vec![water.clone()])
+        .max_neutral_losses(3));
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert_eq!(b(&fragments, &[]), 6);
+    assert_eq!(b(&fragments, &[&water]), 6);
+    assert_eq!(b(&fragments, &[&water, &water]), 6);
+    assert_eq!(b(&fragments, &[&water, &water, &water]), 6);
+    // All b ions plus the precursor
+    assert_eq!(fragments.len(), 24 + 1);
+    assert!(fragments
+        .iter()
+        .filter(|f| f.neutral_loss.len() == 3)
+        .all(|f| f.loss_formula_sum() == molecular_formula!(H 6 O 3)));
+
+    let model = Model::none().b(PrimaryIonSeries::default()
+        .neutral_losses(vec![water.clone(), ammonia.clone()])
+        .max_neutral_losses(2));
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert_eq!(b(&fragments, &[&water, &ammonia]), 6);
+    assert_eq!(b(&fragments, &[&ammonia, &ammonia]), 6);
+    assert_eq!(fragments.len(), 36 + 1);
+
+    // With a budget of 40 Da at most two water losses are allowed
+    let model = Model::none()
+        .b(PrimaryIonSeries::default()
+            .neutral_losses(vec![water.clone()])
+            .max_neutral_losses(3))
+        .max_neutral_loss_mass(Some(crate::system::Mass::new::<crate::system::dalton>(
+            40.0,
+        )));
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert_eq!(b(&fragments, &[&water, &water]), 6);
+    assert_eq!(b(&fragments, &[&water, &water, &water]), 0);
+}
+
+#[test]
+fn charge_reduced_precursor() {
+    let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDEK", None).unwrap();
+    let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+    let model = Model::none()
+        .precursor(vec![water.clone()], ChargeRange::ONE_TO_PRECURSOR)
+        .charge_reduced_precursor((true, ChargeRange::CHARGE_REDUCED));
+    let fragments =
+        peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(3), &model);
+    let find = |ion: &fragment::FragmentType, charge: usize, loss: bool| {
+        fragments
+            .iter()
+            .find(|f| {
+                &f.ion == ion && f.charge.value == charge && f.neutral_loss.is_empty() != loss
+            })
+            .unwrap()
+            .mz(MassMode::Monoisotopic)
+            .unwrap()
+            .value
+    };
+    let hydrogen = molecular_formula!(H 1).monoisotopic_mass().value;
+    for (electrons, charge) in [(1, 2), (2, 1)] {
+        let reduced = fragment::FragmentType::PrecursorChargeReduced(electrons);
+        assert_eq!(reduced.label(), if electrons == 1 { "p+H" } else { "p+H2" });
+        // The charge reduced species retains all charge carriers
+        let difference =
+            find(&reduced, charge, false) - find(&fragment::FragmentType::Precursor, charge, false);
+        assert!((difference - electrons as f64 * hydrogen / charge as f64).abs() < 1e-6);
+        assert!(fragments
+            .iter()
+            .any(|f| f.ion == reduced && f.has_loss(&water)));
+    }
+    assert_eq!(
+        fragments
+            .iter()
+            .filter(|f| matches!(f.ion, fragment::FragmentType::PrecursorChargeReduced(_)))
+            .count(),
+        4
+    );
+    let fragments =
+        peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(1), &model);
+    assert!(!fragments
+        .iter()
+        .any(|f| matches!(f.ion, fragment::FragmentType::PrecursorChargeReduced(_))));
+}
+
+#[test]
+#[cfg(feature = "isotopes")]
+fn isotope_peaks() {
+    let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDE", None).unwrap();
+    let charge = Charge::new::<crate::system::e>(2);
+    let model = Model::none().b(PrimaryIonSeries::default());
+    let monoisotopic = peptide.generate_theoretical_fragments(charge, &model);
+    assert!(monoisotopic.iter().all(|f| f.isotope == 0));
+
+    let fragments =
+        peptide.generate_theoretical_fragments(charge, &model.clone().isotope_peaks((2, 0.001)));
+    assert_eq!(fragments.len(), monoisotopic.len() * 3);
+    for fragment in fragments.iter().filter(|f| f.isotope > 0) {
+        let base = fragments
+            .iter()
+            .find(|f| f.isotope == 0 && f.ion == fragment.ion && f.charge == fragment.charge)
+            .unwrap();
+        let difference = fragment.mz(MassMode::Monoisotopic).unwrap().value
+            - base.mz(MassMode::Monoisotopic).unwrap().value;
+        assert!(
+            (difference - fragment.isotope as f64 * 1.003_355 / fragment.charge.value as f64).abs()
+                < 1e-6
+        );
+    }
+    assert!(fragments.iter().any(|f| f.to_mzpaf().starts_with("b2+i")));
+    assert!(fragments.iter().any(|f| f.to_mzpaf().starts_with("b2+2i")));
+
+    // The isotope peaks of these small fragments are all below 50% probability
+    let fragments = peptide.generate_theoretical_fragments(charge, &model.isotope_peaks((2, 0.5)));
+    assert_eq!(fragments.len(), monoisotopic.len());
+}
+
+#[test]
+fn glycan_mass_only_fragmentation() {
+    // GNOme entry only defined by its mass (910.33 Da), which fits Hex3HexNAc2
+    let peptide = CompoundPeptidoformIon::pro_forma("AN[GNO:10004572]K", None).unwrap();
+    let charge = Charge::new::<crate::system::e>(1);
+    let model = Model::none().glycan(GlycanModel::ALLOW);
+    let is_oxonium = |f: &Fragment| matches!(f.ion, fragment::FragmentType::OxoniumComposition(..));
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert!(fragments.iter().any(is_oxonium));
+    assert!(fragments
+        .iter()
+        .any(|f| matches!(&f.ion, fragment::FragmentType::YComposition(composition, _) if composition.len() == 2)));
+
+    let fragments = peptide.generate_theoretical_fragments(
+        charge,
+        &model.glycan(GlycanModel::ALLOW.mass_only_compositions(0)),
+    );
+    assert!(!fragments.iter().any(is_oxonium));
+}
+
+#[test]
+fn glycan_cross_ring_fragmentation() {
+    use crate::glycan::{BaseSugar, GlycanSubstituent, MonoSaccharide};
+    let hex_nac = MonoSaccharide::new(BaseSugar::Hexose(None), &[GlycanSubstituent::NAcetyl]);
+    let (a, x) = hex_nac.cross_ring_parts((0, 2)).unwrap();
+    assert_eq!(x, molecular_formula!(C 4 H 5 N 1 O 1));
+    assert_eq!(a + x, hex_nac.formula());
+    let (a, x) = hex_nac.cross_ring_parts((2, 4)).unwrap();
+    assert_eq!(a, molecular_formula!(C 2 H 4 O 2));
+    assert_eq!(a + x, hex_nac.formula());
+    assert!(hex_nac.cross_ring_parts((2, 6)).is_none());
+    assert!(hex_nac
+        .clone()
+        .furanose()
+        .cross_ring_parts((0, 5))
+        .is_none());
+
+    let peptide = Peptidoform::pro_forma("MVSHHN[GNO:G43728NL]LTTGATLINEQWLLTTAK", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let charge = Charge::new::<crate::system::e>(1);
+    let is_cross_ring = |f: &Fragment| {
+        matches!(
+            f.ion,
+            fragment::FragmentType::A(..) | fragment::FragmentType::X(..)
+        )
+    };
+    let model = Model::none().glycan(GlycanModel::DISALLOW.allow_structural(true));
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert!(!fragments.iter().any(is_cross_ring));
+
+    // No cross-ring fragments for monosaccharides that are not in the glycan
+    let model = Model::none().glycan(
+        GlycanModel::DISALLOW
+            .allow_structural(true)
+            .cross_ring_cleavages(vec![(
+                MonoSaccharide::new(BaseSugar::Pentose(None), &[]),
+                (0, 2),
+            )]),
+    );
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    assert!(!fragments.iter().any(is_cross_ring));
+
+    let model = Model::none().glycan(
+        GlycanModel::DISALLOW
+            .allow_structural(true)
+            .cross_ring_cleavages(vec![(hex_nac.clone(), (0, 2)), (hex_nac.clone(), (2, 4))]),
+    );
+    let fragments = peptide.generate_theoretical_fragments(charge, &model);
+    let full = peptide.formulas()[0].clone();
+    // The branches of the core HexNAc can also be on the X side, leaving the bare ring part
+    let (ring_part, _) = hex_nac.cross_ring_parts((0, 2)).unwrap();
+    assert!(fragments.iter().any(|f| {
+        matches!(f.ion, fragment::FragmentType::A(_, (0, 2)))
+            && f.formula.as_ref().is_some_and(|f| {
+                (f.monoisotopic_mass().value - ring_part.monoisotopic_mass().value - 1.007_276_467)
+                    .abs()
+                    < 1e-6
+            })
+    }));
+    let a = fragments
+        .iter()
+        .filter(|f| matches!(f.ion, fragment::FragmentType::A(..)))
+        .collect_vec();
+    assert!(!a.is_empty());
+    // Every A fragment has a complementary X fragment
+    for a in a {
+        let fragment::FragmentType::A(position, bonds) = &a.ion else {
+            unreachable!()
+        };
+        assert!(fragments.iter().any(|x| {
+            x.ion == fragment::FragmentType::X(position.clone(), *bonds)
+                && a.formula
+                    .as_ref()
+                    .zip(x.formula.as_ref())
+                    .is_some_and(|(a, x)| {
+                        // Both fragments carry a single proton
+                        ((a.clone() + x.clone() - full.clone())
+                            .monoisotopic_mass()
+                            .value
+                            - 2.014_552_934)
+                            .abs()
+                            < 1e-6
+                    })
+        }));
+    }
+}
+
+#[test]
+fn satellite_ions() {
+    let peptide = Peptidoform::pro_forma("GLGIGTG", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let model = Model::none()
+        .a(PrimaryIonSeries::default())
+        .d(PrimaryIonSeries::default())
+        .v(PrimaryIonSeries::default())
+        .w(PrimaryIonSeries::default())
+        .z(PrimaryIonSeries::default());
+    let fragments =
+        peptide.generate_theoretical_fragments(Charge::new::<crate::system::e>(1), &model);
+    let masses = |kind: &str, index: usize| {
+        fragments
+            .iter()
+            .filter(|f| {
+                f.ion.label() == kind
+                    && f.ion
+                        .position()
+                        .is_some_and(|p| p.sequence_index == SequencePosition::Index(index))
+            })
+            .map(|f| f.formula.as_ref().unwrap().monoisotopic_mass().value)
+            .sorted_by(f64::total_cmp)
+            .collect_vec()
+    };
+    let assert_losses = |kind: &str, base: &str, index: usize, losses: &[MolecularFormula]| {
+        let base = masses(base, index);
+        let found = masses(kind, index);
+        assert_eq!(base.len(), 1);
+        assert_eq!(found.len(), losses.len(), "{kind} ions for index {index}");
+        for (found, loss) in found.iter().zip(losses) {
+            assert!(
+                (base[0] - found - loss.monoisotopic_mass().value).abs() < 1e-6,
+                "{kind} ion for index {index} expected loss of {loss}"
+            );
+        }
+    };
+    // Leucine loses an isopropyl (C3H7) and isoleucine either a methyl or an ethyl group
+    let d_losses = |formulas: &[MolecularFormula]| {
+        formulas
+            .iter()
+            .map(|f| f.clone() - molecular_formula!(H 1))
+            .collect_vec()
+    };
+    assert_losses("d", "a", 1, &d_losses(&[molecular_formula!(C 3 H 7)]));
+    assert_losses(
+        "d",
+        "a",
+        3,
+        &d_losses(&[molecular_formula!(C 2 H 5), molecular_formula!(C 1 H 3)]),
+    );
+    assert_losses("w", "z·", 1, &[molecular_formula!(C 3 H 7)]);
+    assert_losses(
+        "w",
+        "z·",
+        3,
+        &[molecular_formula!(C 2 H 5), molecular_formula!(C 1 H 3)],
+    );
+    assert_losses(
+        "w",
+        "z·",
+        5,
+        &[molecular_formula!(H 1 O 1), molecular_formula!(C 1 H 3)],
+    );
+    // Glycine does not form any satellite ions
+    for index in [0, 2, 4, 6] {
+        assert!(masses("d", index).is_empty());
+        assert!(masses("v", index).is_empty());
+        assert!(masses("w", index).is_empty());
+    }
+    assert_eq!(masses("v", 1).len(), 1);
+}
+
+#[test]
+fn immonium_ions() {
+    let peptide = Peptidoform::pro_forma("AY[Phospho]K", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let charge = Charge::new::<crate::system::e>(1);
+    let immonium = |model: &Model| {
+        peptide
+            .generate_theoretical_fragments(charge, model)
+            .into_iter()
+            .filter(|f| matches!(f.ion, fragment::FragmentType::Immonium(..)))
+            .map(|f| f.mz(MassMode::Monoisotopic).unwrap().value)
+            .sorted_by(f64::total_cmp)
+            .collect_vec()
+    };
+    let all = immonium(&Model::none().immonium(ImmoniumModel::ALLOW));
+    assert!(all.len() > 3);
+    assert!(immonium(&Model::none()).is_empty());
+
+    // Only the phospho tyrosine immonium ion
+    let only_tyrosine = immonium(
+        &Model::none().immonium(
+            ImmoniumModel::ALLOW
+                .amino_acids(Some(vec![AminoAcid::Tyrosine]))
+                .related_ions(false),
+        ),
+    );
+    assert_eq!(only_tyrosine.len(), 1);
+    assert!((only_tyrosine[0] - 216.042_05).abs() < 1e-3);
+
+    // With the phosphate loss as custom related ion
+    let with_loss = immonium(
+        &Model::none().immonium(
+            ImmoniumModel::ALLOW
+                .amino_acids(Some(vec![AminoAcid::Tyrosine]))
+                .related_ions(false)
+                .custom_related_ions(vec![
+                    (
+                        AminoAcid::Tyrosine,
+                        NeutralLoss::Loss(molecular_formula!(H 1 P 1 O 3)),
+                    ),
+                    (
+                        AminoAcid::Lysine,
+                        NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
+                    ),
+                ]),
+        ),
+    );
+    assert_eq!(with_loss.len(), 2);
+    assert!((with_loss[0] - 136.075_75).abs() < 1e-3);
+}
+
 fn test(
     theoretical_fragments: &[(f64, &str)],
     peptide: impl Into<CompoundPeptidoformIon>,
@@ -768,3 +1193,30 @@ fn test(
         );
     }
 }
+
+#[test]
+fn ambiguous_amino_acid_position() {
+    // The C terminal fragments start halfway the sequence, the ambiguous amino acid should still
+    // be labelled with its index in the full sequence
+    let peptide = Peptidoform::pro_forma("AAB", None)
+        .unwrap()
+        .into_linear()
+        .unwrap();
+    let fragments = peptide.generate_theoretical_fragments(
+        Charge::new::<crate::system::e>(1),
+        &Model::none().y(PrimaryIonSeries::default()),
+    );
+    let labels = fragments
+        .iter()
+        .filter(|f| matches!(f.ion, fragment::FragmentType::y(_)))
+        .flat_map(|f| f.formula.iter().flat_map(|f| f.labels().to_vec()))
+        .collect_vec();
+    assert!(!labels.is_empty());
+    assert!(labels.iter().all(|label| matches!(
+        label,
+        AmbiguousLabel::AminoAcid {
+            sequence_index: 2,
+            ..
+        }
+    )));
+}
@@ -0,0 +1,343 @@
+//! Enumerate glycan structures from a composition
+use std::collections::HashMap;
+
+use itertools::Itertools;
+
+use super::{BaseSugar, GlycanStructure, GlycanSubstituent, MonoSaccharide};
+
+/// The maximal number of branches on a single monosaccharide, a pyranose linked via C1 has four
+/// remaining hydroxyl groups (2, 3, 4, and 6) that can be used for further linkages.
+const MAX_BRANCHES: usize = 4;
+
+impl GlycanStructure {
+    /// Enumerate all topologies that can be built from the given composition. This ignores
+    /// linkage positions and anomericity, so structures only differ in how the monosaccharides are
+    /// connected. Every structure has at most four branches on any monosaccharide. The number of
+    /// topologies grows exponentially with the size of the composition, so restricting to N-glycan
+    /// rules is advised for any composition of more than ten monosaccharides.
+    ///
+    /// If `n_glycan` is set, only structures on top of the N-glycan core
+    /// `HexNAc(HexNAc(Hex(Hex,Hex)))` are generated. In this case the reducing end HexNAc can only
+    /// carry the second core HexNAc and up to two (core) fucoses, the β-mannose can additionally
+    /// only carry one bisecting HexNAc and one xylose, and fucoses and sialic acids are always
+    /// terminal and never directly attached to the core mannoses. If the composition does not contain the core no structures are returned.
+    ///
+    /// The returned structures are sorted and unique. Any monosaccharide that occurs a negative
+    /// number of times results in no structures.
+    /// ```rust
+    /// # use rustyms::glycan::*;
+    /// let composition = MonoSaccharide::from_composition("Hex3HexNAc4Fuc1").unwrap();
+    /// let structures = GlycanStructure::enumerate_structures(&composition, true);
+    /// // With and without core fucose, bisecting HexNAc, and antennae with or without fucose
+    /// assert_eq!(structures.len(), 9);
+    /// ```
+    pub fn enumerate_structures(
+        composition: &[(MonoSaccharide, isize)],
+        n_glycan: bool,
+    ) -> Vec<Self> {
+        let Some(composition) = MonoSaccharide::simplify_composition(composition.to_vec()) else {
+            return Vec::new();
+        };
+        let Ok(counts) = composition
+            .iter()
+            .map(|(_, n)| usize::try_from(*n))
+            .collect::<Result<Vec<_>, _>>()
+        else {
+            return Vec::new();
+        };
+        let sugars = composition.into_iter().map(|(s, _)| s).collect_vec();
+        let mut enumerator = Enumerator {
+            terminal: sugars
+                .iter()
+                .map(|s| n_glycan && (is_fucose(s) || is_sialic_acid(s)))
+                .collect(),
+            sugars,
+            cache: HashMap::new(),
+        };
+
+        let mut structures = if n_glycan {
+            enumerator.n_glycans(&counts)
+        } else {
+            enumerator.trees(&counts)
+        };
+        structures.sort_unstable();
+        structures.dedup();
+        structures
+    }
+
+    /// Sort all branches on decreasing order, so that identical topologies are identical
+    fn canonicalise(mut self) -> Self {
+        self.branches = self
+            .branches
+            .into_iter()
+            .map(Self::canonicalise)
+            .sorted_unstable_by(|a, b| b.cmp(a))
+            .collect();
+        self
+    }
+}
+
+/// Check if this monosaccharide is a HexNAc
+fn is_hexnac(sugar: &MonoSaccharide) -> bool {
+    matches!(sugar.base_sugar, BaseSugar::Hexose(_))
+        && sugar.substituents == [GlycanSubstituent::NAcetyl]
+}
+
+/// Check if this monosaccharide is a Hex
+fn is_hex(sugar: &MonoSaccharide) -> bool {
+    matches!(sugar.base_sugar, BaseSugar::Hexose(_)) && sugar.substituents.is_empty()
+}
+
+/// Check if this monosaccharide is a (deoxyhexose) fucose
+fn is_fucose(sugar: &MonoSaccharide) -> bool {
+    matches!(sugar.base_sugar, BaseSugar::Hexose(_))
+        && sugar.substituents == [GlycanSubstituent::Deoxy]
+}
+
+/// Check if this monosaccharide is a (pentose) xylose
+fn is_xylose(sugar: &MonoSaccharide) -> bool {
+    matches!(sugar.base_sugar, BaseSugar::Pentose(_)) && sugar.substituents.is_empty()
+}
+
+/// Check if this monosaccharide is a sialic acid
+fn is_sialic_acid(sugar: &MonoSaccharide) -> bool {
+    matches!(sugar.base_sugar, BaseSugar::Nonose)
+        && sugar.substituents.contains(&GlycanSubstituent::Acid)
+}
+
+/// Enumerate all trees for (parts of) a composition, where the composition is given as counts for
+/// each monosaccharide in `sugars`.
+struct Enumerator {
+    sugars: Vec<MonoSaccharide>,
+    /// If the monosaccharide at this index cannot have any branches
+    terminal: Vec<bool>,
+    /// All trees for a given composition
+    cache: HashMap<Vec<usize>, Vec<GlycanStructure>>,
+}
+
+impl Enumerator {
+    /// All trees that use exactly the given composition
+    fn trees(&mut self, counts: &[usize]) -> Vec<GlycanStructure> {
+        if let Some(trees) = self.cache.get(counts) {
+            return trees.clone();
+        }
+        let mut trees = Vec::new();
+        for (index, count) in counts.iter().enumerate() {
+            if *count == 0 {
+                continue;
+            }
+            let mut rest = counts.to_vec();
+            rest[index] -= 1;
+            if rest.iter().all(|c| *c == 0) {
+                trees.push(GlycanStructure::new(self.sugars[index].clone(), Vec::new()));
+            } else if !self.terminal[index] {
+                trees.extend(
+                    self.forests(&rest, MAX_BRANCHES, None)
+                        .into_iter()
+                        .map(|branches| GlycanStructure::new(self.sugars[index].clone(), branches)),
+                );
+            }
+        }
+        self.cache.insert(counts.to_vec(), trees.clone());
+        trees
+    }
+
+    /// All sets of at most `max_trees` trees that together use exactly the given composition. To
+    /// prevent duplicates the trees are in decreasing order, with all trees at most `max`.
+    fn forests(
+        &mut self,
+        counts: &[usize],
+        max_trees: usize,
+        max: Option<&GlycanStructure>,
+    ) -> Vec<Vec<GlycanStructure>> {
+        if counts.iter().all(|c| *c == 0) {
+            return vec![Vec::new()];
+        }
+        if max_trees == 0 {
+            return Vec::new();
+        }
+        let mut forests = Vec::new();
+        for sub in sub_compositions(counts) {
+            let rest = counts.iter().zip(&sub).map(|(c, s)| c - s).collect_vec();
+            for tree in self.trees(&sub) {
+                if max.is_some_and(|max| tree > *max) {
+                    continue;
+                }
+                for others in self.forests(&rest, max_trees - 1, Some(&tree)) {
+                    let mut forest = vec![tree.clone()];
+                    forest.extend(others);
+                    forests.push(forest);
+                }
+            }
+        }
+        forests
+    }
+
+    /// All antennae on a core α-mannose that use exactly the given composition, fucoses and sialic
+    /// acids cannot be attached to the core directly
+    fn antennae(&mut self, counts: &[usize]) -> Vec<Vec<GlycanStructure>> {
+        self.forests(counts, MAX_BRANCHES, None)
+            .into_iter()
+            .filter(|forest| {
+                forest
+                    .iter()
+                    .all(|tree| !is_fucose(&tree.sugar) && !is_sialic_acid(&tree.sugar))
+            })
+            .collect()
+    }
+
+    /// All N-glycans that use exactly the given composition
+    fn n_glycans(&mut self, counts: &[usize]) -> Vec<GlycanStructure> {
+        let (Some(hexnac), Some(hex)) = (
+            self.sugars.iter().position(is_hexnac),
+            self.sugars.iter().position(is_hex),
+        ) else {
+            return Vec::new();
+        };
+        if counts[hexnac] < 2 || counts[hex] < 3 {
+            return Vec::new();
+        }
+        let mut rest = counts.to_vec();
+        rest[hexnac] -= 2;
+        rest[hex] -= 3;
+
+        let sugars = self.sugars.clone();
+        let fucose = sugars.iter().position(is_fucose);
+        let xylose = sugars.iter().position(is_xylose);
+        let leaf = |index: usize| GlycanStructure::new(sugars[index].clone(), Vec::new());
+        let mut structures = Vec::new();
+
+        for core_fucoses in 0..=fucose.map_or(0, |f| rest[f].min(2)) {
+            for bisecting in 0..=rest[hexnac].min(1) {
+                for xyloses in 0..=xylose.map_or(0, |x| rest[x].min(1)) {
+                    let mut antennae = rest.clone();
+                    if let Some(f) = fucose {
+                        antennae[f] -= core_fucoses;
+                    }
+                    antennae[hexnac] -= bisecting;
+                    if let Some(x) = xylose {
+                        antennae[x] -= xyloses;
+                    }
+                    for arm in sub_compositions_with_empty(&antennae) {
+                        let other_arm = antennae.iter().zip(&arm).map(|(c, s)| c - s).collect_vec();
+                        let arms = self.antennae(&arm);
+                        let other_arms = self.antennae(&other_arm);
+                        for (arm, other_arm) in arms.iter().cartesian_product(&other_arms) {
+                            let mut beta_mannose = vec![
+                                GlycanStructure::new(sugars[hex].clone(), arm.clone()),
+                                GlycanStructure::new(sugars[hex].clone(), other_arm.clone()),
+                            ];
+                            beta_mannose.extend(std::iter::repeat(leaf(hexnac)).take(bisecting));
+                            beta_mannose.extend(
+                                xylose
+                                    .iter()
+                                    .flat_map(|x| std::iter::repeat(leaf(*x)).take(xyloses)),
+                            );
+                            let mut reducing_end = vec![GlycanStructure::new(
+                                sugars[hexnac].clone(),
+                                vec![GlycanStructure::new(sugars[hex].clone(), beta_mannose)],
+                            )];
+                            reducing_end.extend(
+                                fucose
+                                    .iter()
+                                    .flat_map(|f| std::iter::repeat(leaf(*f)).take(core_fucoses)),
+                            );
+                            structures.push(
+                                GlycanStructure::new(sugars[hexnac].clone(), reducing_end)
+                                    .canonicalise(),
+                            );
+                        }
+                    }
+                }
+            }
+        }
+        structures
+    }
+}
+
+/// All non empty sub compositions of the given composition
+fn sub_compositions(counts: &[usize]) -> impl Iterator<Item = Vec<usize>> {
+    sub_compositions_with_empty(counts).filter(|sub| sub.iter().any(|c| *c != 0))
+}
+
+/// All sub compositions of the given composition, including the empty composition
+fn sub_compositions_with_empty(counts: &[usize]) -> impl Iterator<Item = Vec<usize>> {
+    counts
+        .iter()
+        .map(|c| 0..=*c)
+        .multi_cartesian_product()
+        .chain(counts.is_empty().then(Vec::new))
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    fn structures(composition: &str, n_glycan: bool) -> Vec<String> {
+        GlycanStructure::enumerate_structures(
+            &MonoSaccharide::from_composition(composition).unwrap(),
+            n_glycan,
+        )
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+    }
+
+    #[test]
+    fn enumerate_small() {
+        assert_eq!(structures("Hex1", false), ["Hex"]);
+        assert_eq!(structures("Hex2", false), ["Hex(Hex)"]);
+        let mut found = structures("Hex1HexNAc2", false);
+        found.sort();
+        assert_eq!(
+            found,
+            [
+                "Hex(HexNAc(HexNAc))",
+                "Hex(HexNAc,HexNAc)",
+                "HexNAc(Hex(HexNAc))",
+                "HexNAc(HexNAc(Hex))",
+                "HexNAc(HexNAc,Hex)",
+            ]
+        );
+        // Unlabelled rooted trees with four nodes
+        assert_eq!(structures("Hex4", false).len(), 4);
+        let hex = MonoSaccharide::from_composition("Hex1").unwrap()[0]
+            .0
+            .clone();
+        assert!(GlycanStructure::enumerate_structures(&[(hex, -1)], false).is_empty());
+    }
+
+    #[test]
+    fn enumerate_n_glycans() {
+        assert_eq!(
+            structures("Hex3HexNAc2", true),
+            ["HexNAc(HexNAc(Hex(Hex,Hex)))"]
+        );
+        assert!(structures("Hex2HexNAc2", true).is_empty());
+        assert_eq!(structures("Hex3HexNAc2Fuc1", true).len(), 1);
+        // Mono-antennary or bisecting
+        assert_eq!(structures("Hex3HexNAc3", true).len(), 2);
+        let found = structures("Hex5HexNAc4Neu5Ac1", true);
+        assert!(found.contains(
+            &"HexNAc(HexNAc(Hex(Hex(HexNAc(Hex(Neu5Ac))),Hex(HexNAc(Hex)))))".to_string()
+        ));
+        // The sialic acid is always terminal
+        assert!(found.iter().all(|s| !s.contains("Neu5Ac(")));
+    }
+
+    #[test]
+    fn enumerated_structures_fragment() {
+        let structure = GlycanStructure::enumerate_structures(
+            &MonoSaccharide::from_composition("Hex3HexNAc2").unwrap(),
+            true,
+        )
+        .pop()
+        .unwrap();
+        assert_eq!(
+            structure.composition(),
+            MonoSaccharide::from_composition("Hex3HexNAc2").unwrap()
+        );
+        assert_eq!(structure.determine_positions().outer_depth, 3);
+    }
+}
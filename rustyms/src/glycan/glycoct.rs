@@ -0,0 +1,710 @@
+//! Read and write glycan structures in the GlycoCT condensed format
+
+use std::fmt::Write;
+
+use super::{
+    glycan_parse_list, BaseSugar, GlycanStructure, GlycanSubstituent, HeptoseIsomer, HexoseIsomer,
+    MonoSaccharide, PentoseIsomer, TetroseIsomer,
+};
+use crate::{
+    error::{Context, CustomError},
+    formula::Chemical,
+};
+
+/// The GlycoCT substituents and the substituents they are represented by
+const SUBSTITUENTS: &[(&str, &[GlycanSubstituent])] = &[
+    ("n-acetyl", &[GlycanSubstituent::NAcetyl]),
+    ("n-glycolyl", &[GlycanSubstituent::NGlycolyl]),
+    ("n-formyl", &[GlycanSubstituent::NFo]),
+    ("n-dimethyl", &[GlycanSubstituent::NDiMe]),
+    (
+        "n-sulfate",
+        &[GlycanSubstituent::Amino, GlycanSubstituent::Sulfate],
+    ),
+    ("amino", &[GlycanSubstituent::Amino]),
+    ("acetyl", &[GlycanSubstituent::Acetyl]),
+    ("glycolyl", &[GlycanSubstituent::Glycolyl]),
+    ("formyl", &[GlycanSubstituent::Formyl]),
+    ("methyl", &[GlycanSubstituent::Methyl]),
+    ("sulfate", &[GlycanSubstituent::Sulfate]),
+    ("phosphate", &[GlycanSubstituent::Phosphate]),
+    ("phospho-choline", &[GlycanSubstituent::PCholine]),
+    ("ethanolamine", &[GlycanSubstituent::Ethanolamine]),
+];
+
+/// The GlycoCT superclasses and their base sugars
+const SUPERCLASSES: &[(&str, BaseSugar)] = &[
+    ("TRI", BaseSugar::Triose),
+    ("TET", BaseSugar::Tetrose(None)),
+    ("PEN", BaseSugar::Pentose(None)),
+    ("HEX", BaseSugar::Hexose(None)),
+    ("HEP", BaseSugar::Heptose(None)),
+    ("OCT", BaseSugar::Octose),
+    ("NON", BaseSugar::Nonose),
+    ("DEC", BaseSugar::Decose),
+];
+
+/// A residue from the RES section of a GlycoCT file
+enum Residue {
+    /// A monosaccharide (`b`)
+    Base(MonoSaccharide),
+    /// A substituent (`s`)
+    Substituent(Vec<GlycanSubstituent>),
+}
+
+impl GlycanStructure {
+    /// Parse a glycan structure from the GlycoCT condensed format, as used by GlyTouCan and
+    /// GlycoWorkbench. The anomeric configuration and linkage positions are ignored as these are
+    /// not stored in a glycan structure. Substituents are added to the monosaccharide they are
+    /// linked to, if a substituent bridges two monosaccharides (like a phosphate) it is added to
+    /// the parent monosaccharide and the child is linked directly to the parent.
+    /// ```rust
+    /// # use rustyms::glycan::GlycanStructure;
+    /// let structure = GlycanStructure::from_glycoct(
+    ///     "RES\n1b:b-dglc-HEX-1:5\n2s:n-acetyl\n3b:a-lgal-HEX-1:5|6:d\nLIN\n1:1d(2+1)2n\n2:1o(6+1)3d",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(structure.to_string(), "HexNAc(Fuc)");
+    /// ```
+    /// # Errors
+    /// If the text is not valid GlycoCT, if it uses any section other than `RES` and `LIN` (like
+    /// repeating or underdetermined units), or if it contains modifications or substituents that
+    /// cannot be represented.
+    pub fn from_glycoct(text: &str) -> Result<Self, CustomError> {
+        let mut residues: Vec<(usize, Residue)> = Vec::new();
+        let mut links: Vec<(usize, usize)> = Vec::new();
+        let mut section = None;
+        for (line_index, line) in text.lines().enumerate() {
+            for (offset, word) in crate::helper_functions::split_ascii_whitespace(line) {
+                let context = || Context::line(Some(line_index), line, offset, word.len());
+                match (word, section) {
+                    ("RES" | "LIN", _) => section = Some(word),
+                    (_, Some("RES")) => residues.push(parse_residue(word).map_err(|e| {
+                        CustomError::error("Invalid GlycoCT residue", e, context())
+                    })?),
+                    (_, Some("LIN")) => links.push(parse_link(word).ok_or_else(|| {
+                        CustomError::error(
+                            "Invalid GlycoCT linkage",
+                            "A linkage should be formatted like '1:1d(2+1)2n'",
+                            context(),
+                        )
+                    })?),
+                    (_, None) => {
+                        return Err(CustomError::error(
+                            "Invalid GlycoCT",
+                            "A GlycoCT structure should start with the RES section",
+                            context(),
+                        ))
+                    }
+                    _ => {
+                        return Err(CustomError::error(
+                            "Unsupported GlycoCT section",
+                            "Only the RES and LIN sections are supported",
+                            context(),
+                        ))
+                    }
+                }
+            }
+        }
+
+        let residue = |id: usize| {
+            residues.iter().position(|(i, _)| *i == id).ok_or_else(|| {
+                CustomError::error(
+                    "Invalid GlycoCT linkage",
+                    format!("The residue {id} is not defined"),
+                    Context::show(text),
+                )
+            })
+        };
+        let mut children = vec![Vec::new(); residues.len()];
+        let mut has_parent = vec![false; residues.len()];
+        for (parent, child) in links {
+            let (parent, child) = (residue(parent)?, residue(child)?);
+            children[parent].push(child);
+            has_parent[child] = true;
+        }
+        let mut roots = (0..residues.len()).filter(|i| !has_parent[*i]);
+        match (roots.next(), roots.next()) {
+            (Some(root), None) => {
+                build_structure(root, &residues, &children, &mut vec![false; residues.len()])
+                    .ok_or_else(|| {
+                        CustomError::error(
+                            "Invalid GlycoCT",
+                            "The linkages of a GlycoCT structure should not contain a cycle",
+                            Context::show(text),
+                        )
+                    })
+            }
+            _ => Err(CustomError::error(
+                "Invalid GlycoCT",
+                "A GlycoCT structure should have exactly one residue without a parent",
+                Context::show(text),
+            )),
+        }
+    }
+
+    /// Write this glycan structure in the GlycoCT condensed format. As a glycan structure does not
+    /// store the anomeric configuration and linkage positions these are written as unknown
+    /// (`x` and `-1`), the positions of substituents are only given for the common cases (like
+    /// the N-acetyl on position 2 of a HexNAc).
+    /// # Errors
+    /// If any monosaccharide cannot be represented in GlycoCT, for example if it has no defined
+    /// number of carbon atoms (`Sug`) or if it has substituents that have no GlycoCT name.
+    pub fn to_glycoct(&self) -> Result<String, CustomError> {
+        let mut residues = String::from("RES\n");
+        let mut links = String::from("LIN\n");
+        let mut counters = (0, 0);
+        self.write_glycoct(None, &mut residues, &mut links, &mut counters)?;
+        if counters.1 == 0 {
+            Ok(residues)
+        } else {
+            Ok(residues + &links)
+        }
+    }
+
+    /// Write this glycan and all its branches as GlycoCT residues and linkages. The counters keep
+    /// track of the last used residue and linkage ids.
+    /// # Errors
+    /// If any monosaccharide cannot be represented in GlycoCT.
+    fn write_glycoct(
+        &self,
+        parent: Option<usize>,
+        residues: &mut String,
+        links: &mut String,
+        counters: &mut (usize, usize),
+    ) -> Result<(), CustomError> {
+        let (basetype, substituents, anomeric_position) = glycoct_residue(&self.sugar)?;
+        let id = if let Some(basetype) = &basetype {
+            counters.0 += 1;
+            writeln!(residues, "{}b:{basetype}", counters.0).unwrap();
+            counters.0
+        } else {
+            // A monosaccharide without a base sugar is written as a lone substituent
+            counters.0 + 1
+        };
+        if let Some(parent) = parent {
+            counters.1 += 1;
+            writeln!(
+                links,
+                "{}:{parent}o(-1+{anomeric_position}){id}{}",
+                counters.1,
+                if basetype.is_some() { 'd' } else { 'n' }
+            )
+            .unwrap();
+        }
+        for (position, name) in substituents {
+            counters.0 += 1;
+            writeln!(residues, "{}s:{name}", counters.0).unwrap();
+            if basetype.is_some() {
+                counters.1 += 1;
+                let link = if name.starts_with("n-") || name == "amino" {
+                    'd'
+                } else {
+                    'o'
+                };
+                writeln!(
+                    links,
+                    "{}:{id}{link}({position}+1){}n",
+                    counters.1, counters.0
+                )
+                .unwrap();
+            }
+        }
+        for branch in &self.branches {
+            branch.write_glycoct(Some(id), residues, links, counters)?;
+        }
+        Ok(())
+    }
+}
+
+/// Build the glycan structure rooted at the given residue, substituents are merged into their
+/// parent monosaccharide. Returns `None` if a residue is visited twice, which means that the
+/// linkages contain a cycle.
+fn build_structure(
+    index: usize,
+    residues: &[(usize, Residue)],
+    children: &[Vec<usize>],
+    visited: &mut [bool],
+) -> Option<GlycanStructure> {
+    if std::mem::replace(&mut visited[index], true) {
+        return None;
+    }
+    let mut sugar = match &residues[index].1 {
+        Residue::Base(sugar) => sugar.clone(),
+        Residue::Substituent(substituents) => MonoSaccharide::new(BaseSugar::None, substituents),
+    };
+    let mut branches = Vec::new();
+    let mut stack = children[index].iter().rev().copied().collect::<Vec<_>>();
+    while let Some(child) = stack.pop() {
+        match &residues[child].1 {
+            Residue::Substituent(substituents) if sugar.base_sugar != BaseSugar::None => {
+                if std::mem::replace(&mut visited[child], true) {
+                    return None;
+                }
+                sugar.substituents.extend_from_slice(substituents);
+                // A bridging substituent, link its children to this monosaccharide
+                stack.extend(children[child].iter().rev().copied());
+            }
+            _ => branches.push(build_structure(child, residues, children, visited)?),
+        }
+    }
+    Some(GlycanStructure {
+        sugar: named_monosaccharide(sugar),
+        branches,
+    })
+}
+
+/// Find the named monosaccharide with the same base sugar and the same substituents (or if none
+/// match, the same formula) to give monosaccharides the same representation as from other formats
+pub(super) fn named_monosaccharide(mut sugar: MonoSaccharide) -> MonoSaccharide {
+    sugar.substituents.sort();
+    let sorted = |s: &MonoSaccharide| {
+        let mut substituents = s.substituents.clone();
+        substituents.sort();
+        substituents
+    };
+    let named = glycan_parse_list()
+        .iter()
+        .find(|(_, named)| {
+            named.base_sugar == sugar.base_sugar && sorted(named) == sugar.substituents
+        })
+        .or_else(|| {
+            glycan_parse_list().iter().find(|(_, named)| {
+                named.base_sugar == sugar.base_sugar && named.formula() == sugar.formula()
+            })
+        });
+    match named {
+        Some((_, named)) if !sugar.furanose => named.clone(),
+        Some((_, named)) => MonoSaccharide {
+            furanose: true,
+            proforma_name: None,
+            ..named.clone()
+        },
+        None => sugar,
+    }
+}
+
+/// Parse a single residue (`1b:b-dglc-HEX-1:5` or `2s:n-acetyl`) into its id and residue
+/// # Errors
+/// If the residue is not valid or not supported, the error contains the explanation.
+fn parse_residue(word: &str) -> Result<(usize, Residue), String> {
+    let (id, content) = word
+        .split_once(':')
+        .ok_or("A residue should be formatted like '1b:b-dglc-HEX-1:5' or '2s:n-acetyl'")?;
+    let kind = id.chars().last().ok_or(
+        "A residue should start with its id and type, like '1b:b-dglc-HEX-1:5' or '2s:n-acetyl'",
+    )?;
+    let id = id[..id.len() - kind.len_utf8()]
+        .parse()
+        .map_err(|_| format!("The residue id '{id}' is not a valid number"))?;
+    match kind {
+        'b' => parse_basetype(content).map(|sugar| (id, Residue::Base(sugar))),
+        's' => SUBSTITUENTS
+            .iter()
+            .find(|(name, _)| *name == content)
+            .map(|(_, substituents)| (id, Residue::Substituent(substituents.to_vec())))
+            .ok_or_else(|| format!("The substituent '{content}' is not supported")),
+        _ => Err(format!(
+            "The residue type '{kind}' is not supported, only monosaccharides (b) and substituents (s) are supported"
+        )),
+    }
+}
+
+/// Parse a monosaccharide basetype (`b-dglc-HEX-1:5|6:d`)
+/// # Errors
+/// If the superclass is missing or a modification is not supported.
+fn parse_basetype(content: &str) -> Result<MonoSaccharide, String> {
+    let mut modifications = content.split('|');
+    let mut parts = modifications.next().unwrap_or_default().split('-');
+    parts.next(); // The anomeric configuration
+    let mut stereo = Vec::new();
+    let base_sugar = loop {
+        let part = parts
+            .next()
+            .ok_or_else(|| format!("The basetype '{content}' has no superclass"))?;
+        if let Some((_, base_sugar)) = SUPERCLASSES.iter().find(|(name, _)| *name == part) {
+            break base_sugar.clone();
+        }
+        // The stereo code without the D/L configuration (`dglc` -> `glc`)
+        stereo.push(part.get(1..).unwrap_or_default());
+    };
+    let ring = parts.next().unwrap_or("x:x");
+    let furanose = ring
+        .split_once(':')
+        .and_then(|(start, end)| Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?)))
+        .is_some_and(|(start, end)| end == start + 3);
+
+    let mut substituents = Vec::new();
+    let mut keto = false;
+    for modification in modifications {
+        let kind = modification
+            .rsplit_once(':')
+            .map_or(modification, |(_, kind)| kind);
+        match kind {
+            "d" => substituents.push(GlycanSubstituent::Deoxy),
+            "a" => substituents.push(GlycanSubstituent::Acid),
+            "en" | "enx" => substituents.push(GlycanSubstituent::Didehydro),
+            "aldi" => substituents.push(GlycanSubstituent::Alcohol),
+            "keto" => keto = true,
+            _ => {
+                return Err(format!(
+                    "The modification '{modification}' is not supported"
+                ))
+            }
+        }
+    }
+
+    let base_sugar = match (base_sugar, stereo.as_slice(), keto) {
+        (BaseSugar::Tetrose(_), ["ery"], false) => {
+            BaseSugar::Tetrose(Some(TetroseIsomer::Erythrose))
+        }
+        (BaseSugar::Tetrose(_), ["thr"], false) => BaseSugar::Tetrose(Some(TetroseIsomer::Threose)),
+        (BaseSugar::Pentose(_), ["rib"], false) => BaseSugar::Pentose(Some(PentoseIsomer::Ribose)),
+        (BaseSugar::Pentose(_), ["ara"], false) => {
+            BaseSugar::Pentose(Some(PentoseIsomer::Arabinose))
+        }
+        (BaseSugar::Pentose(_), ["xyl"], false) => BaseSugar::Pentose(Some(PentoseIsomer::Xylose)),
+        (BaseSugar::Pentose(_), ["lyx"], false) => BaseSugar::Pentose(Some(PentoseIsomer::Lyxose)),
+        (BaseSugar::Pentose(_), ["thr"], true) => BaseSugar::Pentose(Some(PentoseIsomer::Xylulose)),
+        (BaseSugar::Hexose(_), [isomer], false) => BaseSugar::Hexose(match *isomer {
+            "glc" => Some(HexoseIsomer::Glucose),
+            "gal" => Some(HexoseIsomer::Galactose),
+            "man" => Some(HexoseIsomer::Mannose),
+            "all" => Some(HexoseIsomer::Allose),
+            "alt" => Some(HexoseIsomer::Altrose),
+            "gul" => Some(HexoseIsomer::Gulose),
+            "ido" => Some(HexoseIsomer::Idose),
+            "tal" => Some(HexoseIsomer::Talose),
+            _ => None,
+        }),
+        (BaseSugar::Hexose(_), [isomer], true) => BaseSugar::Hexose(match *isomer {
+            "rib" => Some(HexoseIsomer::Psicose),
+            "ara" => Some(HexoseIsomer::Fructose),
+            "xyl" => Some(HexoseIsomer::Sorbose),
+            "lyx" => Some(HexoseIsomer::Tagatose),
+            _ => None,
+        }),
+        (BaseSugar::Heptose(_), ["gro", "man"], false) => {
+            BaseSugar::Heptose(Some(HeptoseIsomer::GlyceroMannoHeptopyranose))
+        }
+        (BaseSugar::Heptose(_), ["alt"], true) => {
+            BaseSugar::Heptose(Some(HeptoseIsomer::Sedoheptulose))
+        }
+        (base_sugar, _, _) => base_sugar,
+    };
+    let mut sugar = MonoSaccharide::new(base_sugar, &substituents);
+    sugar.furanose = furanose;
+    Ok(sugar)
+}
+
+/// Parse a single linkage (`1:1d(2+1)2n`) into the parent and child residue ids
+fn parse_link(word: &str) -> Option<(usize, usize)> {
+    let (_, link) = word.split_once(':')?;
+    let (parent, rest) = link.split_once('(')?;
+    let (_, child) = rest.split_once(')')?;
+    let id = |s: &str| {
+        s.trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .ok()
+    };
+    Some((id(parent)?, id(child)?))
+}
+
+/// The basetype (if any), the substituents with their positions, and the anomeric position in
+/// GlycoCT of a monosaccharide
+type GlycoCtResidue = (Option<String>, Vec<(&'static str, &'static str)>, usize);
+
+/// Get the GlycoCT representation of a monosaccharide
+/// # Errors
+/// If the base sugar or any of the substituents cannot be represented.
+fn glycoct_residue(sugar: &MonoSaccharide) -> Result<GlycoCtResidue, CustomError> {
+    let error = |explanation: String| {
+        CustomError::error(
+            "Invalid GlycoCT monosaccharide",
+            explanation,
+            Context::show(sugar),
+        )
+    };
+    let (substituents, n_sulfate) = normalised_substituents(sugar);
+
+    let count =
+        |substituent: GlycanSubstituent| substituents.iter().filter(|s| **s == substituent).count();
+    let ulosonic = matches!(sugar.base_sugar, BaseSugar::Octose | BaseSugar::Nonose)
+        && count(GlycanSubstituent::Acid) > 0;
+    let (superclass, stereo, keto) = match &sugar.base_sugar {
+        BaseSugar::None => {
+            return match (substituents.as_slice(), n_sulfate) {
+                ([substituent], false) => glycoct_substituent(substituent)
+                    .map(|name| (None, vec![("-1", name)], 1))
+                    .ok_or_else(|| {
+                        error(format!("The substituent {substituent} has no GlycoCT name"))
+                    }),
+                _ => Err(error(
+                    "A residue without a base sugar should consist of a single substituent"
+                        .to_string(),
+                )),
+            }
+        }
+        BaseSugar::Sugar => {
+            return Err(error(
+                "A sugar with an unknown number of carbon atoms cannot be represented".to_string(),
+            ))
+        }
+        BaseSugar::Triose => ("TRI", "", false),
+        BaseSugar::Tetrose(isomer) => (
+            "TET",
+            match isomer {
+                Some(TetroseIsomer::Erythrose) => "dery-",
+                Some(TetroseIsomer::Threose) => "dthr-",
+                None => "",
+            },
+            false,
+        ),
+        BaseSugar::Pentose(isomer) => match isomer {
+            Some(PentoseIsomer::Ribose) => ("PEN", "drib-", false),
+            Some(PentoseIsomer::Arabinose) => ("PEN", "lara-", false),
+            Some(PentoseIsomer::Xylose) => ("PEN", "dxyl-", false),
+            Some(PentoseIsomer::Lyxose) => ("PEN", "dlyx-", false),
+            Some(PentoseIsomer::Xylulose) => ("PEN", "dthr-", true),
+            None => ("PEN", "", false),
+        },
+        BaseSugar::Hexose(isomer) => {
+            let deoxy = count(GlycanSubstituent::Deoxy) > 0;
+            match isomer {
+                Some(HexoseIsomer::Glucose) => ("HEX", "dglc-", false),
+                // Fucose and rhamnose are L sugars
+                Some(HexoseIsomer::Galactose) if deoxy => ("HEX", "lgal-", false),
+                Some(HexoseIsomer::Galactose) => ("HEX", "dgal-", false),
+                Some(HexoseIsomer::Mannose) if deoxy => ("HEX", "lman-", false),
+                Some(HexoseIsomer::Mannose) => ("HEX", "dman-", false),
+                Some(HexoseIsomer::Allose) => ("HEX", "dall-", false),
+                Some(HexoseIsomer::Altrose) => ("HEX", "dalt-", false),
+                Some(HexoseIsomer::Gulose) => ("HEX", "dgul-", false),
+                Some(HexoseIsomer::Idose) => ("HEX", "lido-", false),
+                Some(HexoseIsomer::Talose) => ("HEX", "dtal-", false),
+                Some(HexoseIsomer::Psicose) => ("HEX", "drib-", true),
+                Some(HexoseIsomer::Fructose) => ("HEX", "dara-", true),
+                Some(HexoseIsomer::Sorbose) => ("HEX", "lxyl-", true),
+                Some(HexoseIsomer::Tagatose) => ("HEX", "dlyx-", true),
+                None => ("HEX", "", false),
+            }
+        }
+        BaseSugar::Heptose(isomer) => match isomer {
+            Some(HeptoseIsomer::GlyceroMannoHeptopyranose) => ("HEP", "lgro-dman-", false),
+            Some(HeptoseIsomer::Sedoheptulose) => ("HEP", "dalt-", true),
+            None => ("HEP", "", false),
+        },
+        BaseSugar::Octose if ulosonic => ("OCT", "dman-", true),
+        BaseSugar::Octose => ("OCT", "", false),
+        BaseSugar::Nonose if ulosonic => ("NON", "dgro-dgal-", true),
+        BaseSugar::Nonose => ("NON", "", false),
+        BaseSugar::Decose => ("DEC", "", false),
+    };
+    let alditol = count(GlycanSubstituent::Alcohol) > 0;
+    let anomeric_position = if keto { 2 } else { 1 };
+    let ring = if alditol {
+        "0:0".to_string()
+    } else {
+        format!(
+            "{anomeric_position}:{}",
+            anomeric_position + if sugar.furanose { 3 } else { 4 }
+        )
+    };
+
+    // The default positions of the modifications and substituents
+    let (acid, deoxy, nitrogen) = match sugar.base_sugar {
+        _ if ulosonic => ("1", "3", "5"),
+        BaseSugar::Hexose(_) => ("6", "6", "2"),
+        _ => ("-1", "-1", "-1"),
+    };
+    let mut modifications = Vec::new();
+    if alditol {
+        modifications.push("1:aldi".to_string());
+    }
+    if keto {
+        modifications.push(format!("{anomeric_position}:keto"));
+    }
+    let mut named = Vec::new();
+    let mut first_deoxy = true;
+    let mut first_acid = true;
+    for substituent in &substituents {
+        match substituent {
+            GlycanSubstituent::Deoxy => {
+                modifications.push(format!("{}:d", if first_deoxy { deoxy } else { "-1" }));
+                first_deoxy = false;
+            }
+            GlycanSubstituent::Acid => {
+                modifications.push(format!("{}:a", if first_acid { acid } else { "-1" }));
+                first_acid = false;
+            }
+            GlycanSubstituent::Didehydro => modifications.push("-1:en".to_string()),
+            GlycanSubstituent::Alcohol => (),
+            other => {
+                let name = glycoct_substituent(other)
+                    .ok_or_else(|| error(format!("The substituent {other} has no GlycoCT name")))?;
+                named.push((
+                    if name.starts_with("n-") || name == "amino" {
+                        nitrogen
+                    } else {
+                        "-1"
+                    },
+                    name,
+                ));
+            }
+        }
+    }
+    if n_sulfate {
+        named.push((nitrogen, "n-sulfate"));
+    }
+    modifications.sort();
+    Ok((
+        Some(format!(
+            "{}-{stereo}{superclass}-{ring}{}",
+            if alditol { 'o' } else { 'x' },
+            modifications
+                .iter()
+                .flat_map(|m| ["|", m.as_str()])
+                .collect::<String>()
+        )),
+        named,
+        anomeric_position,
+    ))
+}
+
+/// Get the substituents of a monosaccharide as GlycoCT and WURCS see them, an amino with an acyl
+/// group is combined into an N-acyl substituent and an amino with a sulfate into an N-sulfate
+/// (which is removed from the list, signalled by the returned boolean).
+pub(super) fn normalised_substituents(sugar: &MonoSaccharide) -> (Vec<GlycanSubstituent>, bool) {
+    let mut substituents = sugar.substituents.clone();
+    // GlycoCT and WURCS place the N on a deoxygenated position, so an amino with an acyl group on it is
+    // written as a deoxy with an N-acyl substituent
+    for (acyl, n_acyl) in [
+        (GlycanSubstituent::Acetyl, GlycanSubstituent::NAcetyl),
+        (GlycanSubstituent::Glycolyl, GlycanSubstituent::NGlycolyl),
+    ] {
+        while let (Some(amino), Some(index)) = (
+            substituents
+                .iter()
+                .position(|s| *s == GlycanSubstituent::Amino),
+            substituents.iter().position(|s| *s == acyl),
+        ) {
+            substituents[index] = n_acyl.clone();
+            substituents[amino] = GlycanSubstituent::Deoxy;
+        }
+    }
+    // An amino with a sulfate is a N-sulfate
+    let n_sulfate = if let (Some(amino), Some(sulfate)) = (
+        substituents
+            .iter()
+            .position(|s| *s == GlycanSubstituent::Amino),
+        substituents
+            .iter()
+            .position(|s| *s == GlycanSubstituent::Sulfate),
+    ) {
+        substituents.remove(amino.max(sulfate));
+        substituents.remove(amino.min(sulfate));
+        true
+    } else {
+        false
+    };
+    (substituents, n_sulfate)
+}
+
+/// Get the GlycoCT name for a substituent, if it has one
+fn glycoct_substituent(substituent: &GlycanSubstituent) -> Option<&'static str> {
+    SUBSTITUENTS
+        .iter()
+        .find(|(_, s)| s.len() == 1 && s[0] == *substituent)
+        .map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    /// The N-glycan core with a core fucose (G00028MO)
+    const CORE_FUCOSE: &str = "RES
+1b:b-dglc-HEX-1:5
+2s:n-acetyl
+3b:b-dglc-HEX-1:5
+4s:n-acetyl
+5b:b-dman-HEX-1:5
+6b:a-dman-HEX-1:5
+7b:a-dman-HEX-1:5
+8b:a-lgal-HEX-1:5|6:d
+LIN
+1:1d(2+1)2n
+2:1o(4+1)3d
+3:3d(2+1)4n
+4:3o(4+1)5d
+5:5o(3+1)6d
+6:5o(6+1)7d
+7:1o(6+1)8d";
+
+    #[test]
+    fn parse_glycoct() {
+        let structure = GlycanStructure::from_glycoct(CORE_FUCOSE).unwrap();
+        assert_eq!(structure.to_string(), "HexNAc(HexNAc(Hex(Hex,Hex)),Fuc)");
+        let iupac = GlycanStructure::from_short_iupac(
+            "Man(a1-3)[Man(a1-6)]Man(b1-4)GlcNAc(b1-4)[Fuc(a1-6)]GlcNAc",
+            0..57,
+            0,
+        )
+        .unwrap();
+        assert_eq!(structure.formula(), iupac.formula());
+    }
+
+    #[test]
+    fn parse_glycoct_sialic_acid() {
+        let structure = GlycanStructure::from_glycoct(
+            "RES 1b:b-dgal-HEX-1:5 2b:a-dgro-dgal-NON-2:6|1:a|2:keto|3:d 3s:n-acetyl LIN 1:1o(3+2)2d 2:2d(5+1)3n",
+        )
+        .unwrap();
+        assert_eq!(structure.to_string(), "Hex(Neu5Ac)");
+        assert_eq!(
+            structure.branches[0].sugar.formula(),
+            molecular_formula!(C 11 H 17 N 1 O 8)
+        );
+    }
+
+    #[test]
+    fn glycoct_round_trip() {
+        let structure = GlycanStructure::from_glycoct(CORE_FUCOSE).unwrap();
+        let written = structure.to_glycoct().unwrap();
+        assert_eq!(GlycanStructure::from_glycoct(&written).unwrap(), structure);
+
+        let sialylated = GlycanStructure::from_glycoct(
+            "RES 1b:b-dgal-HEX-1:5 2b:a-dgro-dgal-NON-2:6|1:a|2:keto|3:d 3s:n-acetyl 4s:sulfate LIN 1:1o(3+2)2d 2:2d(5+1)3n 3:1o(6+1)4n",
+        )
+        .unwrap();
+        let written = sialylated.to_glycoct().unwrap();
+        assert!(
+            written.contains("x-dgro-dgal-NON-2:6|1:a|2:keto|3:d"),
+            "{written}"
+        );
+        assert_eq!(
+            GlycanStructure::from_glycoct(&written).unwrap(),
+            sialylated,
+            "{written}"
+        );
+    }
+
+    #[test]
+    fn invalid_glycoct() {
+        assert!(GlycanStructure::from_glycoct("").is_err());
+        assert!(GlycanStructure::from_glycoct("RES 1b:b-dglc-XXX-1:5").is_err());
+        assert!(GlycanStructure::from_glycoct("RES 1b:b-dglc-HEX-1:5 2s:unknown").is_err());
+        assert!(GlycanStructure::from_glycoct("RES 1b:b-dglc-HEX-1:5 2b:b-dglc-HEX-1:5").is_err());
+        assert!(GlycanStructure::from_glycoct("RES 1b:b-dglc-HEX-1:5 LIN 1:1o(4+1)2d").is_err());
+        assert!(
+            GlycanStructure::from_glycoct("RES 1b:b-dglc-HEX-1:5 REP REP1:2o(4+1)2d=-1--1")
+                .is_err()
+        );
+        assert!(GlycanStructure::from_glycoct("RES :b-dglc-HEX-1:5").is_err());
+        assert!(GlycanStructure::from_glycoct(
+            "RES 1b:b-dglc-HEX-1:5 2b:b-dglc-HEX-1:5 3b:b-dglc-HEX-1:5 LIN 1:1o(4+1)2d 2:2o(4+1)3d 3:3o(4+1)2d"
+        )
+        .is_err());
+    }
+}
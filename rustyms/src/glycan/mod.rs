@@ -1,8 +1,11 @@
 //! Handle glycan related issues, access provided if you want to work with glycans on your own.
 
+mod enumerate;
 mod glycan_structure;
+mod glycoct;
 mod monosaccharide;
 mod positioned_structure;
+mod wurcs;
 
 pub use glycan_structure::*;
 pub use monosaccharide::*;
@@ -1,16 +1,88 @@
 //! Handle monosaccharides
 
+use std::ops::RangeInclusive;
+
 use crate::{
     fragment::{DiagnosticPosition, Fragment, FragmentType},
     molecular_charge::CachedCharge,
-    system::usize::Charge,
-    AminoAcid, Model, Multi, NeutralLoss,
+    system::{usize::Charge, Mass},
+    AminoAcid, Model, Multi, NeutralLoss, Tolerance, WithinTolerance,
 };
 
 include!("../shared/glycan.rs");
 include!("../shared/glycan_lists.rs");
 
+/// Find all glycan compositions with a monoisotopic mass within the tolerance of the given mass,
+/// the glycan analogue of [`crate::find_isobaric_sets`]. The mass is the mass of the
+/// monosaccharides as they are part of a glycan, so this is the delta mass of a glycan on a
+/// peptide, for free glycans the mass of water has to be subtracted first. Every given
+/// monosaccharide is used the number of times allowed by its range, and the total number of
+/// monosaccharides in a composition is restricted to the `total` range. Monosaccharides that
+/// are given multiple times are treated as separate building blocks.
+///
+/// The compositions are returned sorted on the absolute mass error, compositions with the same
+/// error are returned in the order of the given monosaccharides.
+/// ```rust
+/// # use rustyms::{*, glycan::*, system::{Mass, dalton}};
+/// let sugars = ["Hex", "HexNAc", "Fuc", "Neu5Ac"].map(|name| {
+///     (MonoSaccharide::from_composition(name).unwrap()[0].0.clone(), 0..=10)
+/// });
+/// let compositions = find_glycan_compositions(
+///     Mass::new::<dalton>(1768.639),
+///     Tolerance::new_absolute(Mass::new::<dalton>(0.01)),
+///     &sugars,
+///     1..=20,
+/// );
+/// assert_eq!(
+///     MonoSaccharide::display_composition(&compositions[0]).to_string(),
+///     "Hex5HexNAc4Fuc1"
+/// );
+/// ```
+#[allow(clippy::cast_precision_loss, clippy::cast_possible_wrap)]
+pub fn find_glycan_compositions(
+    mass: Mass,
+    tolerance: Tolerance<Mass>,
+    monosaccharides: &[(MonoSaccharide, RangeInclusive<usize>)],
+    total: RangeInclusive<usize>,
+) -> Vec<Vec<(MonoSaccharide, isize)>> {
+    let building_blocks = monosaccharides
+        .iter()
+        .map(|(sugar, range)| {
+            (
+                sugar.clone(),
+                sugar.formula().monoisotopic_mass(),
+                range.clone(),
+            )
+        })
+        .collect_vec();
+    let mut result = Vec::new();
+    MonoSaccharide::fill_compositions(
+        &building_blocks,
+        &mut Vec::with_capacity(building_blocks.len()),
+        Mass::default(),
+        mass,
+        tolerance,
+        &total,
+        &mut result,
+    );
+    result.sort_by(|a, b| {
+        (a.1 - mass)
+            .value
+            .abs()
+            .total_cmp(&(b.1 - mass).value.abs())
+    });
+    result
+        .into_iter()
+        .map(|(composition, _)| composition)
+        .collect()
+}
+
 impl MonoSaccharide {
+    /// Display a glycan composition in the canonical form, see [`CompositionDisplay`].
+    pub const fn display_composition(composition: &[(Self, isize)]) -> CompositionDisplay<'_> {
+        CompositionDisplay(composition)
+    }
+
     /// Generate the composition used for searching on glycans
     pub(crate) fn search_composition(
         composition: &[(Self, isize)],
@@ -111,6 +183,83 @@ impl MonoSaccharide {
         fragments
     }
 
+    /// Find all compositions of common monosaccharides (`Hex`, `HexNAc`, `Fuc`, `Neu5Ac`, and
+    /// `Neu5Gc`) with a monoisotopic mass within the tolerance of the given mass. This is used as fallback for
+    /// glycans where only the mass is known. At most `max_monosaccharides` are used in a single
+    /// composition, and if more than `max_compositions` compositions are found no compositions
+    /// are returned as the mass is too ambiguous to be useful.
+    pub(crate) fn compositions_for_mass(
+        mass: Mass,
+        tolerance: Tolerance<Mass>,
+        max_monosaccharides: usize,
+        max_compositions: usize,
+    ) -> Vec<Vec<(Self, isize)>> {
+        let building_blocks = ["hex", "hexnac", "fuc", "neu5ac", "neu5gc"].map(|name| {
+            let sugar = glycan_parse_list()
+                .iter()
+                .find(|(n, _)| n == name)
+                .map_or_else(
+                    || unreachable!("{name} is not a known monosaccharide"),
+                    |(_, s)| s.clone(),
+                );
+            (sugar, 0..=max_monosaccharides)
+        });
+        let result =
+            find_glycan_compositions(mass, tolerance, &building_blocks, 1..=max_monosaccharides);
+        if result.len() > max_compositions {
+            Vec::new()
+        } else {
+            result
+        }
+    }
+
+    /// Recursively try all allowed numbers of the next building block, stops as soon as the mass
+    /// is too high or the maximal number of monosaccharides is reached.
+    #[allow(clippy::cast_possible_wrap)]
+    fn fill_compositions(
+        building_blocks: &[(Self, Mass, RangeInclusive<usize>)],
+        counts: &mut Vec<usize>,
+        current: Mass,
+        target: Mass,
+        tolerance: Tolerance<Mass>,
+        total: &RangeInclusive<usize>,
+        result: &mut Vec<(Vec<(Self, isize)>, Mass)>,
+    ) {
+        let used: usize = counts.iter().sum();
+        let Some((_, block_mass, range)) = building_blocks.get(counts.len()) else {
+            if used > 0 && total.contains(&used) && tolerance.within(&target, &current) {
+                result.push((
+                    building_blocks
+                        .iter()
+                        .zip(counts.iter())
+                        .filter(|(_, n)| **n > 0)
+                        .map(|((sugar, _, _), n)| (sugar.clone(), *n as isize))
+                        .collect(),
+                    current,
+                ));
+            }
+            return;
+        };
+        let upper = tolerance.bounds(target).1;
+        for n in *range.start()..=(*range.end()).min(total.end().saturating_sub(used)) {
+            let mass = current + *block_mass * n as f64;
+            if block_mass.value > 0.0 && mass > upper {
+                break;
+            }
+            counts.push(n);
+            Self::fill_compositions(
+                building_blocks,
+                counts,
+                mass,
+                target,
+                tolerance,
+                total,
+                result,
+            );
+            counts.pop();
+        }
+    }
+
     /// Get all unique combinations of monosaccharides within the given range of number of monosaccharides used
     /// # Panics
     /// If any if the composition options has more then [`isize::MAX`] sugars.
@@ -158,6 +307,152 @@ impl MonoSaccharide {
         result
     }
 
+    /// Check if the other monosaccharide matches this monosaccharide, meaning it has the same
+    /// base sugar, substituents, and ring size, regardless of the order of the substituents and
+    /// the name. If this monosaccharide has no isomer set any isomer of the same base sugar
+    /// matches.
+    pub(crate) fn matches(&self, other: &Self) -> bool {
+        let base_sugar = match (&self.base_sugar, &other.base_sugar) {
+            (BaseSugar::Tetrose(None), BaseSugar::Tetrose(_))
+            | (BaseSugar::Pentose(None), BaseSugar::Pentose(_))
+            | (BaseSugar::Hexose(None), BaseSugar::Hexose(_))
+            | (BaseSugar::Heptose(None), BaseSugar::Heptose(_)) => true,
+            (a, b) => a == b,
+        };
+        base_sugar
+            && self.furanose == other.furanose
+            && self.substituents.len() == other.substituents.len()
+            && self
+                .substituents
+                .iter()
+                .sorted()
+                .eq(other.substituents.iter().sorted())
+    }
+
+    /// Split this monosaccharide into the two parts resulting from a cross-ring cleavage of the
+    /// given ring bonds. The bonds are numbered according to Domon and Costello, where bond 0 is
+    /// between the ring oxygen and the anomeric carbon, bond 1 between the anomeric carbon and the
+    /// next carbon, and so on. Returns the part on the non-reducing side (A) and the part
+    /// containing the anomeric carbon (X), or None if this monosaccharide has no ring or the bonds
+    /// are not both part of the ring. The two parts always add up to the formula of the full
+    /// monosaccharide, no hydrogen rearrangements are taken into account.
+    ///
+    /// The positions of substituents are not stored, so these are placed on their most common
+    /// positions: N-linked substituents on carbon 2 (carbon 5 for ulosonic acids), the first deoxy
+    /// on carbon 3 for ulosonic acids, the first acid on carbon 1 for ulosonic acids, and all
+    /// other substituents on the last carbon.
+    pub(crate) fn cross_ring_parts(
+        &self,
+        bonds: (u8, u8),
+    ) -> Option<(MolecularFormula, MolecularFormula)> {
+        let carbons = match self.base_sugar {
+            BaseSugar::Pentose(_) => 5,
+            BaseSugar::Hexose(_) => 6,
+            BaseSugar::Heptose(_) => 7,
+            BaseSugar::Octose => 8,
+            BaseSugar::Nonose => 9,
+            BaseSugar::Decose => 10,
+            _ => return None,
+        };
+        if self.substituents.contains(&GlycanSubstituent::Alcohol) {
+            return None; // A reduced end has no ring
+        }
+        let ulosonic = matches!(self.base_sugar, BaseSugar::Octose | BaseSugar::Nonose)
+            && self.substituents.contains(&GlycanSubstituent::Acid);
+        let keto = ulosonic
+            || matches!(
+                self.base_sugar,
+                BaseSugar::Pentose(Some(PentoseIsomer::Xylulose))
+                    | BaseSugar::Hexose(Some(
+                        HexoseIsomer::Psicose
+                            | HexoseIsomer::Fructose
+                            | HexoseIsomer::Sorbose
+                            | HexoseIsomer::Tagatose
+                    ))
+                    | BaseSugar::Heptose(Some(HeptoseIsomer::Sedoheptulose))
+            );
+        // Zero based index of the anomeric carbon and the number of carbons in the ring
+        let anomeric = usize::from(keto);
+        let ring = if self.furanose { 4 } else { 5 };
+        let (first, second) = (usize::from(bonds.0), usize::from(bonds.1));
+        if anomeric + ring > carbons || first >= second || second > ring {
+            return None;
+        }
+
+        // The formula for each carbon with its oxygen and hydrogens
+        let mut parts = vec![molecular_formula!(H 2 C 1 O 1); carbons];
+        if anomeric > 0 {
+            parts[0] = molecular_formula!(H 3 C 1 O 1);
+        }
+        parts[carbons - 1] = molecular_formula!(H 3 C 1 O 1);
+        parts[anomeric] = molecular_formula!(C 1);
+        // The last ring carbon is bound to the ring oxygen instead of a hydroxyl group
+        let ring_end = anomeric + ring - 1;
+        parts[ring_end] = if ring_end == carbons - 1 {
+            molecular_formula!(H 2 C 1)
+        } else {
+            molecular_formula!(H 1 C 1)
+        };
+        let amino = self.substituents.contains(&GlycanSubstituent::Amino);
+        let (mut first_acid, mut first_deoxy) = (true, true);
+        for substituent in &self.substituents {
+            let position = match substituent {
+                GlycanSubstituent::Acid if ulosonic && first_acid => {
+                    first_acid = false;
+                    0
+                }
+                GlycanSubstituent::Deoxy if ulosonic && first_deoxy => {
+                    first_deoxy = false;
+                    2
+                }
+                GlycanSubstituent::Amino
+                | GlycanSubstituent::NAcetyl
+                | GlycanSubstituent::NDiMe
+                | GlycanSubstituent::NFo
+                | GlycanSubstituent::NGlycolyl => {
+                    if ulosonic {
+                        4
+                    } else {
+                        1
+                    }
+                }
+                GlycanSubstituent::Acetyl | GlycanSubstituent::Glycolyl if amino => {
+                    if ulosonic {
+                        4
+                    } else {
+                        1
+                    }
+                }
+                _ => carbons - 1,
+            };
+            parts[position] += substituent.formula();
+        }
+        // Make sure the parts add up to the full formula
+        let ring_oxygen = molecular_formula!(O 1);
+        let rest = self.formula() - parts.iter().cloned().sum::<MolecularFormula>() - &ring_oxygen;
+        parts[anomeric] += rest;
+
+        // The ring position (1 is the anomeric carbon) for each carbon, exocyclic carbons are
+        // grouped with the ring carbon they are attached to
+        let ring_position = |index: usize| index.saturating_sub(anomeric).clamp(0, ring - 1) + 1;
+        let mut a = MolecularFormula::default();
+        let mut x = MolecularFormula::default();
+        for (index, part) in parts.iter().enumerate() {
+            let position = ring_position(index);
+            if (first < position && position <= second) == (first == 0) {
+                x += part;
+            } else {
+                a += part;
+            }
+        }
+        if first == 0 {
+            a += ring_oxygen;
+        } else {
+            x += ring_oxygen;
+        }
+        Some((a, x))
+    }
+
     /// Generate all uncharged diagnostic ions for this monosaccharide.
     /// According to: <https://doi.org/10.1016/j.trac.2018.09.007>.
     pub(crate) fn diagnostic_ions(
@@ -219,9 +514,28 @@ impl MonoSaccharide {
     }
 }
 
+/// Display a glycan composition in the canonical form: sorted, with every monosaccharide occurring
+/// only once, and the ProForma names followed by the counts, e.g. `Hex5HexNAc4Fuc1Neu5Ac2`. The
+/// result can be parsed again with [`MonoSaccharide::from_composition`].
+#[derive(Clone, Copy, Debug)]
+pub struct CompositionDisplay<'a>(&'a [(MonoSaccharide, isize)]);
+
+impl std::fmt::Display for CompositionDisplay<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let composition = MonoSaccharide::simplify_composition(self.0.to_vec())
+            .unwrap_or_else(|| self.0.to_vec());
+        for (sugar, amount) in &composition {
+            write!(f, "{sugar}{amount}")?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::missing_panics_doc)]
 mod tests {
+    use itertools::Itertools;
+
     use super::*;
 
     #[test]
@@ -414,4 +728,86 @@ mod tests {
         assert_eq!(human_readable(&options_2), "Hep2,Hex1&Hep1", "Options 2");
         assert_eq!(human_readable(&options_3), "Hex1&Hep2", "Options 3");
     }
+    #[test]
+    fn parse_composition() {
+        let proforma = MonoSaccharide::from_composition("Hex5HexNAc4NeuAc2Fuc1").unwrap();
+        let byonic = MonoSaccharide::from_composition("HexNAc(4)Hex(5)Fuc(1)NeuAc(2)").unwrap();
+        assert_eq!(proforma, byonic);
+        assert_eq!(
+            proforma.iter().map(|(_, n)| n).sum::<isize>(),
+            12,
+            "All monosaccharides should be counted"
+        );
+        let canonical = MonoSaccharide::display_composition(&proforma).to_string();
+        assert_eq!(canonical, "Hex5HexNAc4Fuc1Neu5Ac2");
+        assert_eq!(
+            MonoSaccharide::from_composition(&canonical).unwrap(),
+            proforma
+        );
+        assert_eq!(
+            MonoSaccharide::display_composition(&byonic).to_string(),
+            canonical
+        );
+        // Parentheses that are part of a name are kept
+        assert_eq!(
+            MonoSaccharide::from_composition("HexNAc(S)(2)").unwrap(),
+            MonoSaccharide::from_composition("HexNAc(S)2").unwrap()
+        );
+        assert!(MonoSaccharide::from_composition("Hex(5").is_err());
+        assert!(MonoSaccharide::from_composition("Hex(a)").is_err());
+    }
+
+    #[test]
+    fn compositions_for_mass() {
+        let mass = Mass::new::<crate::system::dalton>(910.33)
+            - molecular_formula!(H 2 O 1).monoisotopic_mass();
+        let tolerance = Tolerance::new_absolute(Mass::new::<crate::system::dalton>(0.01));
+        let options = MonoSaccharide::compositions_for_mass(mass, tolerance, 40, 3);
+        assert_eq!(options.len(), 1);
+        assert_eq!(
+            options[0]
+                .iter()
+                .format_with("", |(sugar, n), f| f(&format_args!("{sugar}{n}")))
+                .to_string(),
+            "Hex3HexNAc2"
+        );
+        assert!(MonoSaccharide::compositions_for_mass(mass, tolerance, 4, 3).is_empty());
+        assert!(MonoSaccharide::compositions_for_mass(mass, tolerance, 40, 0).is_empty());
+    }
+
+    #[test]
+    fn find_glycan_compositions() {
+        let sugar = |name: &str| MonoSaccharide::from_composition(name).unwrap()[0].0.clone();
+        let tolerance = Tolerance::new_absolute(Mass::new::<crate::system::dalton>(0.01));
+        let target = MonoSaccharide::from_composition("Hex5HexNAc4Fuc1Neu5Ac1").unwrap();
+        let mass = target
+            .iter()
+            .map(|(s, n)| s.formula().monoisotopic_mass() * *n as f64)
+            .sum::<Mass>();
+        let sugars = [
+            (sugar("Hex"), 0..=10),
+            (sugar("HexNAc"), 0..=10),
+            (sugar("Fuc"), 0..=3),
+            (sugar("Neu5Ac"), 0..=4),
+        ];
+        let compositions = super::find_glycan_compositions(mass, tolerance, &sugars, 1..=20);
+        assert!(!compositions.is_empty());
+        assert_eq!(
+            MonoSaccharide::display_composition(&compositions[0]).to_string(),
+            "Hex5HexNAc4Fuc1Neu5Ac1"
+        );
+        // The bounds are respected
+        let sugars = [
+            (sugar("Hex"), 0..=10),
+            (sugar("HexNAc"), 0..=10),
+            (sugar("Fuc"), 0..=0),
+            (sugar("Neu5Ac"), 0..=4),
+        ];
+        assert!(
+            super::find_glycan_compositions(mass, tolerance, &sugars, 1..=20)
+                .iter()
+                .all(|c| c.iter().all(|(s, _)| *s != sugar("Fuc")))
+        );
+        assert!(super::find_glycan_compositions(mass, tolerance, &sugars, 1..=10).is_empty());
+    }
 }
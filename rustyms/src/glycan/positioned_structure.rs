@@ -99,6 +99,26 @@ impl PositionedGlycanStructure {
                         })
                         .flat_map(|f| f.with_neutral_losses(&model.glycan.neutral_losses)),
                 );
+                // Generate all cross-ring A and X fragments
+                base_fragments.extend(
+                    self.cross_ring_fragments(
+                        &model.glycan.cross_ring_cleavages,
+                        peptidoform_ion_index,
+                        peptidoform_index,
+                        full_formula,
+                        attachment,
+                    )
+                    .into_iter()
+                    .flat_map(|f| {
+                        let charge_range = if matches!(f.ion, FragmentType::A(..)) {
+                            model.glycan.oxonium_charge_range
+                        } else {
+                            model.glycan.other_charge_range
+                        };
+                        f.with_charge_range(charge_carriers, charge_range)
+                    })
+                    .flat_map(|f| f.with_neutral_losses(&model.glycan.neutral_losses)),
+                );
                 // Generate all diagnostic ions
                 base_fragments.extend(
                     self.diagnostic_ions(peptidoform_ion_index, peptidoform_index, attachment)
@@ -137,6 +157,73 @@ impl PositionedGlycanStructure {
         output
     }
 
+    /// Generate the uncharged cross-ring A and X fragments for the given ring cleavages on this
+    /// monosaccharide and all its branches. As the linkage positions are not known every
+    /// distribution of the branches over the A and X fragment is generated.
+    fn cross_ring_fragments(
+        &self,
+        cleavages: &[(MonoSaccharide, (u8, u8))],
+        peptidoform_ion_index: usize,
+        peptidoform_index: usize,
+        full_formula: &Multi<MolecularFormula>,
+        attachment: Option<(AminoAcid, usize)>,
+    ) -> Vec<Fragment> {
+        let mut output = Vec::new();
+        if cleavages.is_empty() {
+            return output;
+        }
+        let structure = self.formula_inner(SequencePosition::default(), peptidoform_index);
+        let branches = self
+            .branches
+            .iter()
+            .map(|b| b.formula_inner(SequencePosition::default(), peptidoform_index))
+            .collect_vec();
+        let total = branches.iter().cloned().sum::<MolecularFormula>();
+        // The formula of the branches on the A side, for every distribution of the branches
+        let mut distributions: Vec<MolecularFormula> = Vec::new();
+        for side in branches.iter().powerset() {
+            let formula = side.into_iter().cloned().sum::<MolecularFormula>();
+            if !distributions.contains(&formula) {
+                distributions.push(formula);
+            }
+        }
+        for (_, bonds) in cleavages
+            .iter()
+            .filter(|(sugar, _)| sugar.matches(&self.sugar))
+        {
+            if let Some((a, x)) = self.sugar.cross_ring_parts(*bonds) {
+                for a_branches in &distributions {
+                    output.push(Fragment::new(
+                        &a + a_branches,
+                        Charge::zero(),
+                        peptidoform_ion_index,
+                        peptidoform_index,
+                        FragmentType::A(self.position(attachment), *bonds),
+                    ));
+                    output.extend(full_formula.iter().map(|full| {
+                        Fragment::new(
+                            full - &structure + &x + &total - a_branches,
+                            Charge::zero(),
+                            peptidoform_ion_index,
+                            peptidoform_index,
+                            FragmentType::X(self.position(attachment), *bonds),
+                        )
+                    }));
+                }
+            }
+        }
+        output.extend(self.branches.iter().flat_map(|b| {
+            b.cross_ring_fragments(
+                cleavages,
+                peptidoform_ion_index,
+                peptidoform_index,
+                full_formula,
+                attachment,
+            )
+        }));
+        output
+    }
+
     /// Generate all fragments without charge and neutral loss options
     fn oxonium_fragments(
         &self,
@@ -0,0 +1,746 @@
+//! Read and write glycan structures in the WURCS 2.0 format
+
+use std::fmt::Write;
+
+use itertools::Itertools;
+
+use super::{
+    glycoct::{named_monosaccharide, normalised_substituents},
+    BaseSugar, GlycanStructure, GlycanSubstituent, HeptoseIsomer, HexoseIsomer, MonoSaccharide,
+    PentoseIsomer, TetroseIsomer,
+};
+use crate::error::{Context, CustomError};
+
+/// The WURCS MAP codes for substituents and the substituents they are represented by
+const SUBSTITUENTS: &[(&str, &[GlycanSubstituent])] = &[
+    ("*NCC/3=O", &[GlycanSubstituent::NAcetyl]),
+    ("*NCCO/3=O", &[GlycanSubstituent::NGlycolyl]),
+    ("*NC=O", &[GlycanSubstituent::NFo]),
+    (
+        "*NSO/3=O/3=O",
+        &[GlycanSubstituent::Amino, GlycanSubstituent::Sulfate],
+    ),
+    ("*N", &[GlycanSubstituent::Amino]),
+    ("*OCC/3=O", &[GlycanSubstituent::Acetyl]),
+    ("*OCCO/3=O", &[GlycanSubstituent::Glycolyl]),
+    ("*OC=O", &[GlycanSubstituent::Formyl]),
+    ("*OC", &[GlycanSubstituent::Methyl]),
+    ("*OSO/3=O/3=O", &[GlycanSubstituent::Sulfate]),
+    ("*OPO/3O/3=O", &[GlycanSubstituent::Phosphate]),
+];
+
+/// The WURCS MAP codes for substituents that bridge two monosaccharides
+const BRIDGES: &[(&str, GlycanSubstituent)] = &[
+    ("*OPO*/3O/3=O", GlycanSubstituent::Phosphate),
+    ("*OSO*/3=O/3=O", GlycanSubstituent::Sulfate),
+];
+
+/// The stereo codes for the isomers, as written from the first carbon after the carbonyl, and if
+/// the isomer is a ketose. The codes are given in the configuration that is written (D except for
+/// the isomers that are commonly L), the mirrored code is recognised as well when reading.
+const ISOMERS: &[(BaseSugar, bool, &str)] = &[
+    (
+        BaseSugar::Tetrose(Some(TetroseIsomer::Erythrose)),
+        false,
+        "22",
+    ),
+    (
+        BaseSugar::Tetrose(Some(TetroseIsomer::Threose)),
+        false,
+        "12",
+    ),
+    (
+        BaseSugar::Pentose(Some(PentoseIsomer::Ribose)),
+        false,
+        "222",
+    ),
+    (
+        BaseSugar::Pentose(Some(PentoseIsomer::Arabinose)),
+        false,
+        "211",
+    ),
+    (
+        BaseSugar::Pentose(Some(PentoseIsomer::Xylose)),
+        false,
+        "212",
+    ),
+    (
+        BaseSugar::Pentose(Some(PentoseIsomer::Lyxose)),
+        false,
+        "112",
+    ),
+    (
+        BaseSugar::Pentose(Some(PentoseIsomer::Xylulose)),
+        true,
+        "12",
+    ),
+    (
+        BaseSugar::Hexose(Some(HexoseIsomer::Glucose)),
+        false,
+        "2122",
+    ),
+    (
+        BaseSugar::Hexose(Some(HexoseIsomer::Galactose)),
+        false,
+        "2112",
+    ),
+    (
+        BaseSugar::Hexose(Some(HexoseIsomer::Mannose)),
+        false,
+        "1122",
+    ),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Allose)), false, "2222"),
+    (
+        BaseSugar::Hexose(Some(HexoseIsomer::Altrose)),
+        false,
+        "1222",
+    ),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Gulose)), false, "2212"),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Idose)), false, "2121"),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Talose)), false, "1112"),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Psicose)), true, "222"),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Fructose)), true, "122"),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Sorbose)), true, "121"),
+    (BaseSugar::Hexose(Some(HexoseIsomer::Tagatose)), true, "112"),
+    (
+        BaseSugar::Heptose(Some(HeptoseIsomer::GlyceroMannoHeptopyranose)),
+        false,
+        "11221",
+    ),
+    (
+        BaseSugar::Heptose(Some(HeptoseIsomer::GlyceroMannoHeptopyranose)),
+        false,
+        "11222",
+    ),
+    (
+        BaseSugar::Heptose(Some(HeptoseIsomer::Sedoheptulose)),
+        true,
+        "1222",
+    ),
+];
+
+impl GlycanStructure {
+    /// Parse a glycan structure from the WURCS 2.0 format, as used by GlyTouCan and GNOme. The
+    /// anomeric configuration and linkage positions are ignored as these are not stored in a
+    /// glycan structure. The first residue is used as the root of the structure. Substituents that
+    /// bridge two monosaccharides (like a phosphate) are added to the parent monosaccharide.
+    /// ```rust
+    /// # use rustyms::glycan::GlycanStructure;
+    /// let structure = GlycanStructure::from_wurcs(
+    ///     "WURCS=2.0/2,2,1/[a2122h-1b_1-5_2*NCC/3=O][a1221m-1a_1-5]/1-2/a6-b1",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(structure.to_string(), "HexNAc(Fuc)");
+    /// ```
+    /// # Errors
+    /// If the text is not valid WURCS, if it uses repeating units or ambiguous linkages, or if it
+    /// contains carbon descriptors or substituents that cannot be represented.
+    pub fn from_wurcs(text: &str) -> Result<Self, CustomError> {
+        let text = text.trim();
+        let error = |title: &str, explanation: String, offset: usize, length: usize| {
+            CustomError::error(
+                title,
+                explanation,
+                Context::line(None, text, offset, length),
+            )
+        };
+        let mut offset = text.find('/').unwrap_or(text.len());
+        if &text[..offset] != "WURCS=2.0" {
+            return Err(error(
+                "Invalid WURCS",
+                "Only WURCS version 2.0 is supported, the text should start with 'WURCS=2.0/'"
+                    .to_string(),
+                0,
+                offset,
+            ));
+        }
+        // The number of unique residues, residues, and linkages
+        offset += 1;
+        let counts_end = text[offset..].find('/').map_or(text.len(), |i| i + offset);
+        let counts = text[offset..counts_end]
+            .split(',')
+            .map(str::parse::<usize>)
+            .collect::<Result<Vec<_>, _>>()
+            .ok()
+            .filter(|counts| counts.len() == 3)
+            .ok_or_else(|| {
+                error(
+                    "Invalid WURCS",
+                    "The counts should be three numbers separated by commas".to_string(),
+                    offset,
+                    counts_end - offset,
+                )
+            })?;
+        offset = counts_end + 1;
+
+        // The unique residues, each enclosed in square brackets
+        let mut unique = Vec::new();
+        while text[offset.min(text.len())..].starts_with('[') {
+            let end = text[offset..]
+                .find(']')
+                .map(|i| i + offset)
+                .ok_or_else(|| {
+                    error(
+                        "Invalid WURCS",
+                        "Unclosed residue".to_string(),
+                        offset,
+                        text.len() - offset,
+                    )
+                })?;
+            unique.push(
+                parse_unique_residue(&text[offset + 1..end]).map_err(|explanation| {
+                    error(
+                        "Invalid WURCS residue",
+                        explanation,
+                        offset,
+                        end + 1 - offset,
+                    )
+                })?,
+            );
+            offset = end + 1;
+        }
+        if !text[offset.min(text.len())..].starts_with('/') {
+            return Err(error(
+                "Invalid WURCS",
+                "The unique residues should be followed by the residue list".to_string(),
+                offset.min(text.len()),
+                0,
+            ));
+        }
+        offset += 1;
+
+        // The list of residues, referring to the unique residues
+        let list_end = text[offset..].find('/').map_or(text.len(), |i| i + offset);
+        let residues = text[offset..list_end]
+            .split('-')
+            .map(|id| {
+                id.parse::<usize>()
+                    .ok()
+                    .and_then(|id| id.checked_sub(1))
+                    .and_then(|id| unique.get(id))
+                    .cloned()
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| {
+                error(
+                    "Invalid WURCS",
+                    "The residue list should consist of the ids of unique residues separated by dashes"
+                        .to_string(),
+                    offset,
+                    list_end - offset,
+                )
+            })?;
+        offset = (list_end + 1).min(text.len());
+
+        // The linkages
+        let mut links = Vec::new();
+        for link in text[offset..].split('_').filter(|l| !l.is_empty()) {
+            links.push(parse_link(link).map_err(|explanation| {
+                error("Invalid WURCS linkage", explanation, offset, link.len())
+            })?);
+            offset += link.len() + 1;
+        }
+
+        if counts != [unique.len(), residues.len(), links.len()] {
+            return Err(CustomError::error(
+                "Invalid WURCS",
+                format!(
+                    "The counts ({}) do not match the number of unique residues ({}), residues ({}), and linkages ({})",
+                    counts.iter().join(","),
+                    unique.len(),
+                    residues.len(),
+                    links.len()
+                ),
+                Context::show(text),
+            ));
+        }
+
+        // Build the tree starting from the first residue
+        let mut neighbours = vec![Vec::new(); residues.len()];
+        for (a, b, bridge) in links {
+            if a.max(b) >= residues.len() || a == b {
+                return Err(CustomError::error(
+                    "Invalid WURCS linkage",
+                    "A linkage should connect two different residues that are defined",
+                    Context::show(text),
+                ));
+            }
+            neighbours[a].push((b, bridge.clone()));
+            neighbours[b].push((a, bridge));
+        }
+        let mut seen = vec![false; residues.len()];
+        let structure =
+            build_structure(0, None, &residues, &neighbours, &mut seen).ok_or_else(|| {
+                CustomError::error(
+                    "Invalid WURCS",
+                    "The linkages contain a cycle, which cannot be represented",
+                    Context::show(text),
+                )
+            })?;
+        if seen.iter().all(|s| *s) {
+            Ok(structure)
+        } else {
+            Err(CustomError::error(
+                "Invalid WURCS",
+                "Not all residues are connected to the first residue",
+                Context::show(text),
+            ))
+        }
+    }
+
+    /// Write this glycan structure in the WURCS 2.0 format. As a glycan structure does not store
+    /// the anomeric configuration and linkage positions these are written as unknown (`x` and
+    /// `?`), the positions of substituents are only given for the common cases (like the N-acetyl
+    /// on position 2 of a HexNAc). Note that the output is not the normalised WURCS as used by
+    /// GlyTouCan, so it cannot be compared textually to WURCS from other sources.
+    /// # Errors
+    /// If any monosaccharide cannot be represented in WURCS, for example if it has no defined
+    /// number of carbon atoms (`Sug`), if it has no base sugar, if it has substituents that have no
+    /// WURCS MAP code, or if there are more than 52 monosaccharides.
+    pub fn to_wurcs(&self) -> Result<String, CustomError> {
+        let mut residues = Vec::new();
+        let mut links = Vec::new();
+        self.write_wurcs(None, &mut residues, &mut links)?;
+        if residues.len() > 52 {
+            return Err(CustomError::error(
+                "Invalid WURCS glycan",
+                "At most 52 monosaccharides can be written",
+                Context::show(self),
+            ));
+        }
+        let mut unique: Vec<&String> = Vec::new();
+        let list = residues
+            .iter()
+            .map(|residue| {
+                unique.iter().position(|u| *u == residue).map_or_else(
+                    || {
+                        unique.push(residue);
+                        unique.len()
+                    },
+                    |index| index + 1,
+                )
+            })
+            .collect_vec();
+        Ok(format!(
+            "WURCS=2.0/{},{},{}/{}/{}/{}",
+            unique.len(),
+            residues.len(),
+            links.len(),
+            unique.iter().map(|r| format!("[{r}]")).join(""),
+            list.iter().join("-"),
+            links
+                .iter()
+                .map(|(parent, child, anomeric_position)| format!(
+                    "{}?-{}{anomeric_position}",
+                    residue_letter(*parent),
+                    residue_letter(*child)
+                ))
+                .join("_")
+        ))
+    }
+
+    /// Write this glycan and all its branches as WURCS unique residues and linkages (parent
+    /// index, child index, and anomeric position of the child).
+    /// # Errors
+    /// If any monosaccharide cannot be represented in WURCS.
+    fn write_wurcs(
+        &self,
+        parent: Option<usize>,
+        residues: &mut Vec<String>,
+        links: &mut Vec<(usize, usize, usize)>,
+    ) -> Result<(), CustomError> {
+        let (residue, anomeric_position) = wurcs_residue(&self.sugar)?;
+        let index = residues.len();
+        residues.push(residue);
+        if let Some(parent) = parent {
+            links.push((parent, index, anomeric_position));
+        }
+        for branch in &self.branches {
+            branch.write_wurcs(Some(index), residues, links)?;
+        }
+        Ok(())
+    }
+}
+
+/// Build the glycan structure rooted at the given residue, returns None if a cycle is found
+fn build_structure(
+    index: usize,
+    parent: Option<usize>,
+    residues: &[MonoSaccharide],
+    neighbours: &[Vec<(usize, Option<GlycanSubstituent>)>],
+    seen: &mut [bool],
+) -> Option<GlycanStructure> {
+    seen[index] = true;
+    let mut sugar = residues[index].clone();
+    let mut branches = Vec::new();
+    for (child, bridge) in &neighbours[index] {
+        if Some(*child) == parent {
+            continue;
+        }
+        if seen[*child] {
+            return None;
+        }
+        if let Some(bridge) = bridge {
+            sugar.substituents.push(bridge.clone());
+        }
+        branches.push(build_structure(
+            *child,
+            Some(index),
+            residues,
+            neighbours,
+            seen,
+        )?);
+    }
+    Some(GlycanStructure {
+        sugar: named_monosaccharide(sugar),
+        branches,
+    })
+}
+
+/// Get the index of a residue from its letter (`a-z` then `A-Z`)
+const fn residue_index(letter: char) -> Option<usize> {
+    match letter {
+        'a'..='z' => Some(letter as usize - 'a' as usize),
+        'A'..='Z' => Some(letter as usize - 'A' as usize + 26),
+        _ => None,
+    }
+}
+
+/// Get the letter of a residue from its index (`a-z` then `A-Z`)
+const fn residue_letter(index: usize) -> char {
+    (b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ")[index] as char
+}
+
+/// Parse a single linkage (`a4-b1` or `a4-b1*OPO*/3O/3=O`) into the indices of the two residues
+/// and the bridging substituent, if any
+/// # Errors
+/// If the linkage is not valid or not supported, the error contains the explanation.
+fn parse_link(link: &str) -> Result<(usize, usize, Option<GlycanSubstituent>), String> {
+    if link.contains(['~', '{', '}']) {
+        return Err("Repeating units and fuzzy linkages are not supported".to_string());
+    }
+    let (positions, bridge) = link
+        .find('*')
+        .map_or((link, None), |i| (&link[..i], Some(&link[i..])));
+    let bridge = bridge
+        .map(|bridge| {
+            BRIDGES
+                .iter()
+                .find(|(code, _)| *code == bridge)
+                .map(|(_, substituent)| substituent.clone())
+                .ok_or_else(|| format!("The bridging substituent '{bridge}' is not supported"))
+        })
+        .transpose()?;
+    let residue = |end: &str| {
+        let letters = end
+            .split('|')
+            .map(|alternative| alternative.chars().next().and_then(residue_index))
+            .collect::<Option<Vec<_>>>()?;
+        letters.iter().all_equal_value().ok().copied()
+    };
+    match positions.split('-').collect_vec().as_slice() {
+        [a, b] => Ok((
+            residue(a).ok_or("The first residue of the linkage is invalid or ambiguous")?,
+            residue(b).ok_or("The second residue of the linkage is invalid or ambiguous")?,
+            bridge,
+        )),
+        _ => Err("A linkage should be formatted like 'a4-b1', linkages between more than two residues are not supported".to_string()),
+    }
+}
+
+/// Parse a unique residue (`a2122h-1b_1-5_2*NCC/3=O`)
+/// # Errors
+/// If the residue is not valid or not supported, the error contains the explanation.
+fn parse_unique_residue(content: &str) -> Result<MonoSaccharide, String> {
+    let mut parts = content.split('_');
+    let skeleton = parts
+        .next()
+        .and_then(|p| p.split('-').next())
+        .unwrap_or_default();
+    let mut substituents = Vec::new();
+    let mut furanose = false;
+    for part in parts {
+        if let Some(index) = part.find('*') {
+            let code = &part[index..];
+            substituents.extend_from_slice(
+                SUBSTITUENTS
+                    .iter()
+                    .find(|(c, _)| *c == code)
+                    .map(|(_, s)| *s)
+                    .ok_or_else(|| format!("The substituent '{code}' is not supported"))?,
+            );
+        } else if let Some((start, end)) = part.split_once('-') {
+            furanose = matches!((start.parse::<usize>(), end.parse::<usize>()), (Ok(start), Ok(end)) if end == start + 3);
+        } else {
+            return Err(format!("The residue part '{part}' is not valid"));
+        }
+    }
+
+    let mut stereo = String::new();
+    let mut carbonyl = None;
+    let mut double_bonds = 0;
+    for (index, carbon) in skeleton.chars().enumerate() {
+        match carbon {
+            '1' | '2' | '3' | '4' | 'x' => stereo.push(carbon),
+            'a' | 'o' | 'O' | 'u' | 'U' => carbonyl = carbonyl.or(Some(index + 1)),
+            'h' => (),
+            'm' | 'd' => substituents.push(GlycanSubstituent::Deoxy),
+            'A' => substituents.push(GlycanSubstituent::Acid),
+            'e' | 'z' | 'f' | 'n' | 'E' | 'Z' | 'F' | 'N' => double_bonds += 1,
+            _ => {
+                return Err(format!(
+                    "The carbon descriptor '{carbon}' in '{skeleton}' is not supported"
+                ))
+            }
+        }
+    }
+    substituents.extend(std::iter::repeat(GlycanSubstituent::Didehydro).take(double_bonds / 2));
+    if carbonyl.is_none() {
+        substituents.push(GlycanSubstituent::Alcohol);
+    }
+    let carbons = skeleton.chars().count();
+    let keto = carbonyl.is_some_and(|c| c > 1);
+    let mirrored = stereo
+        .chars()
+        .map(|c| match c {
+            '1' => '2',
+            '2' => '1',
+            c => c,
+        })
+        .collect::<String>();
+    let base_sugar = ISOMERS
+        .iter()
+        .filter(|(base_sugar, _, _)| carbon_count(base_sugar) == Some(carbons))
+        .find(|(_, k, code)| *k == keto && (**code == stereo || **code == mirrored))
+        .map(|(base_sugar, _, _)| base_sugar.clone())
+        .or(match carbons {
+            3 => Some(BaseSugar::Triose),
+            4 => Some(BaseSugar::Tetrose(None)),
+            5 => Some(BaseSugar::Pentose(None)),
+            6 => Some(BaseSugar::Hexose(None)),
+            7 => Some(BaseSugar::Heptose(None)),
+            8 => Some(BaseSugar::Octose),
+            9 => Some(BaseSugar::Nonose),
+            10 => Some(BaseSugar::Decose),
+            _ => None,
+        })
+        .ok_or_else(|| format!("A skeleton with {carbons} carbon atoms is not supported"))?;
+    let mut sugar = MonoSaccharide::new(base_sugar, &substituents);
+    sugar.furanose = furanose;
+    Ok(sugar)
+}
+
+/// The number of carbon atoms in a base sugar, if defined
+const fn carbon_count(base_sugar: &BaseSugar) -> Option<usize> {
+    match base_sugar {
+        BaseSugar::None | BaseSugar::Sugar => None,
+        BaseSugar::Triose => Some(3),
+        BaseSugar::Tetrose(_) => Some(4),
+        BaseSugar::Pentose(_) => Some(5),
+        BaseSugar::Hexose(_) => Some(6),
+        BaseSugar::Heptose(_) => Some(7),
+        BaseSugar::Octose => Some(8),
+        BaseSugar::Nonose => Some(9),
+        BaseSugar::Decose => Some(10),
+    }
+}
+
+/// Get the WURCS unique residue for a monosaccharide and its anomeric position
+/// # Errors
+/// If the base sugar or any of the substituents cannot be represented.
+fn wurcs_residue(sugar: &MonoSaccharide) -> Result<(String, usize), CustomError> {
+    let error = |explanation: String| {
+        CustomError::error(
+            "Invalid WURCS monosaccharide",
+            explanation,
+            Context::show(sugar),
+        )
+    };
+    let carbons = carbon_count(&sugar.base_sugar).ok_or_else(|| {
+        error(
+            "Only monosaccharides with a defined number of carbon atoms can be represented"
+                .to_string(),
+        )
+    })?;
+    let (mut substituents, n_sulfate) = normalised_substituents(sugar);
+    let take = |substituents: &mut Vec<GlycanSubstituent>, substituent: &GlycanSubstituent| {
+        substituents
+            .iter()
+            .position(|s| s == substituent)
+            .map(|index| substituents.remove(index))
+            .is_some()
+    };
+    let alditol = take(&mut substituents, &GlycanSubstituent::Alcohol);
+    let ulosonic = matches!(sugar.base_sugar, BaseSugar::Octose | BaseSugar::Nonose)
+        && substituents.contains(&GlycanSubstituent::Acid);
+    let isomer = ISOMERS.iter().find(|(b, _, _)| *b == sugar.base_sugar);
+    let keto = ulosonic || isomer.is_some_and(|(_, keto, _)| *keto);
+    let anomeric_position = if keto { 2 } else { 1 };
+
+    let mut skeleton = vec!['x'; carbons];
+    skeleton[carbons - 1] = 'h';
+    if keto {
+        skeleton[0] = 'h';
+    }
+    skeleton[anomeric_position - 1] = match (alditol, keto) {
+        (false, _) => 'a',
+        (true, false) => 'h',
+        (true, true) => 'x',
+    };
+    let stereo_start = if ulosonic {
+        take(&mut substituents, &GlycanSubstituent::Acid);
+        take(&mut substituents, &GlycanSubstituent::Deoxy);
+        skeleton[0] = 'A';
+        skeleton[2] = 'd';
+        3
+    } else {
+        anomeric_position
+    };
+    // Fucose and rhamnose are L sugars
+    let deoxy = substituents.contains(&GlycanSubstituent::Deoxy);
+    let stereo = match (&sugar.base_sugar, isomer) {
+        (
+            BaseSugar::Hexose(Some(HexoseIsomer::Galactose | HexoseIsomer::Mannose)),
+            Some((_, _, code)),
+        ) if deoxy => code
+            .chars()
+            .map(|c| if c == '1' { '2' } else { '1' })
+            .collect(),
+        (BaseSugar::Nonose, _) if ulosonic => "21122".to_string(),
+        (BaseSugar::Octose, _) if ulosonic => "1122".to_string(),
+        (_, Some((_, _, code))) => (*code).to_string(),
+        _ => String::new(),
+    };
+    for (index, c) in stereo.chars().enumerate() {
+        skeleton[stereo_start + index] = c;
+    }
+
+    // Place the acids and deoxys on the last carbon, or for deoxys on the first free internal carbon
+    while take(&mut substituents, &GlycanSubstituent::Acid) {
+        if skeleton[carbons - 1] == 'h' {
+            skeleton[carbons - 1] = 'A';
+        } else {
+            return Err(error("Only one acid group can be placed".to_string()));
+        }
+    }
+    while take(&mut substituents, &GlycanSubstituent::Deoxy) {
+        if skeleton[carbons - 1] == 'h' {
+            skeleton[carbons - 1] = 'm';
+        } else if let Some(index) = (stereo_start..carbons - 1)
+            .find(|i| matches!(skeleton[*i], '1' | '2' | '3' | '4' | 'x'))
+        {
+            skeleton[index] = 'd';
+        } else {
+            return Err(error("Not all deoxy groups can be placed".to_string()));
+        }
+    }
+
+    let mut residue = skeleton.into_iter().collect::<String>();
+    if !alditol {
+        write!(
+            residue,
+            "-{anomeric_position}x_{anomeric_position}-{}",
+            anomeric_position + if sugar.furanose { 3 } else { 4 }
+        )
+        .unwrap();
+    }
+    let nitrogen = match sugar.base_sugar {
+        _ if ulosonic => "5",
+        BaseSugar::Hexose(_) => "2",
+        _ => "?",
+    };
+    if n_sulfate {
+        write!(residue, "_{nitrogen}*NSO/3=O/3=O").unwrap();
+    }
+    for substituent in substituents {
+        let code = SUBSTITUENTS
+            .iter()
+            .find(|(_, s)| s.len() == 1 && s[0] == substituent)
+            .map(|(code, _)| *code)
+            .ok_or_else(|| {
+                error(format!(
+                    "The substituent {substituent} has no WURCS MAP code"
+                ))
+            })?;
+        let position = if code.starts_with("*N") {
+            nitrogen
+        } else {
+            "?"
+        };
+        write!(residue, "_{position}{code}").unwrap();
+    }
+    Ok((residue, anomeric_position))
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::formula::Chemical;
+
+    /// The N-glycan core with a core fucose (G00028MO)
+    const CORE_FUCOSE: &str = "WURCS=2.0/4,6,5/[a2122h-1b_1-5_2*NCC/3=O][a1122h-1b_1-5][a1122h-1a_1-5][a1221m-1a_1-5]/1-1-2-3-3-4/a4-b1_a6-f1_b4-c1_c3-d1_c6-e1";
+
+    #[test]
+    fn parse_wurcs() {
+        let structure = GlycanStructure::from_wurcs(CORE_FUCOSE).unwrap();
+        assert_eq!(structure.to_string(), "HexNAc(HexNAc(Hex(Hex,Hex)),Fuc)");
+        let glycoct = GlycanStructure::from_glycoct(
+            "RES 1b:b-dglc-HEX-1:5 2s:n-acetyl 3b:b-dglc-HEX-1:5 4s:n-acetyl 5b:b-dman-HEX-1:5 6b:a-dman-HEX-1:5 7b:a-dman-HEX-1:5 8b:a-lgal-HEX-1:5|6:d LIN 1:1d(2+1)2n 2:1o(4+1)3d 3:3d(2+1)4n 4:3o(4+1)5d 5:5o(3+1)6d 6:5o(6+1)7d 7:1o(6+1)8d",
+        )
+        .unwrap();
+        assert_eq!(structure, glycoct);
+    }
+
+    #[test]
+    fn parse_wurcs_sialic_acid() {
+        let structure = GlycanStructure::from_wurcs(
+            "WURCS=2.0/2,2,1/[a2112h-1b_1-5][Aad21122h-2a_2-6_5*NCC/3=O]/1-2/a3-b2",
+        )
+        .unwrap();
+        assert_eq!(structure.to_string(), "Hex(Neu5Ac)");
+        assert_eq!(
+            structure.branches[0].sugar.formula(),
+            molecular_formula!(C 11 H 17 N 1 O 8)
+        );
+    }
+
+    #[test]
+    fn wurcs_round_trip() {
+        let structure = GlycanStructure::from_wurcs(CORE_FUCOSE).unwrap();
+        let written = structure.to_wurcs().unwrap();
+        assert_eq!(
+            written,
+            "WURCS=2.0/3,6,5/[a2122h-1x_1-5_2*NCC/3=O][a1122h-1x_1-5][a1221m-1x_1-5]/1-1-2-2-2-3/a?-b1_b?-c1_c?-d1_c?-e1_a?-f1"
+        );
+        assert_eq!(GlycanStructure::from_wurcs(&written).unwrap(), structure);
+
+        let sialylated = GlycanStructure::from_wurcs(
+            "WURCS=2.0/2,2,1/[a2112h-1b_1-5_6*OSO/3=O/3=O][Aad21122h-2a_2-6_5*NCC/3=O]/1-2/a3-b2",
+        )
+        .unwrap();
+        let written = sialylated.to_wurcs().unwrap();
+        assert!(
+            written.contains("[Aad21122h-2x_2-6_5*NCC/3=O]"),
+            "{written}"
+        );
+        assert_eq!(
+            GlycanStructure::from_wurcs(&written).unwrap(),
+            sialylated,
+            "{written}"
+        );
+    }
+
+    #[test]
+    fn invalid_wurcs() {
+        assert!(GlycanStructure::from_wurcs("").is_err());
+        assert!(GlycanStructure::from_wurcs("WURCS=1.0/1,1,0/[a2122h-1b_1-5]/1/").is_err());
+        assert!(GlycanStructure::from_wurcs("WURCS=2.0/1,1,0/[a2122h-1b_1-5]/1/").is_ok());
+        assert!(GlycanStructure::from_wurcs("WURCS=2.0/1,2,0/[a2122h-1b_1-5]/1/").is_err());
+        assert!(GlycanStructure::from_wurcs("WURCS=2.0/1,1,0/[a2122h-1b_1-5_2*NCCCC]/1/").is_err());
+        assert!(GlycanStructure::from_wurcs("WURCS=2.0/1,2,0/[a2122h-1b_1-5]/1-1/").is_err());
+        assert!(
+            GlycanStructure::from_wurcs("WURCS=2.0/1,2,1/[a2122h-1b_1-5]/1-1/a4-b1~n").is_err()
+        );
+    }
+}
@@ -0,0 +1,274 @@
+//! Golden file (snapshot) tests for the canonical outputs of rustyms.
+//!
+//! Canonical text representations are generated with the functions in this module (ProForma
+//! display, fragment tables, and spectrum exports) and compared to previously stored golden
+//! files with [`GoldenFiles::check`]. This allows downstream users that embed rustyms to verify
+//! that the behaviour on their own data is stable across upgrades. Each golden file starts with a
+//! header line with the version of the golden format and the rustyms version that generated it.
+//! If the golden format changes the files have to be regenerated, this is reported separately
+//! from changes in the content.
+//!
+//! Set the environment variable `RUSTYMS_UPDATE_GOLDEN=1` (or use [`GoldenFiles::update`]) to
+//! (re)generate the golden files instead of checking them.
+//!
+//! ```rust
+//! # fn main() -> Result<(), rustyms::error::CustomError> {
+//! # #[cfg(feature = "golden")]
+//! # {
+//! # use rustyms::{*, golden::*, system::{e, usize::Charge}};
+//! let peptide = CompoundPeptidoformIon::pro_forma("[Acetyl]-M[Oxidation]ASK", None)?;
+//! let golden = GoldenFiles::new("data/golden");
+//! golden.check(
+//!     "fragments_all",
+//!     &fragment_table(&peptide, Charge::new::<e>(2), &Model::all()),
+//! )?;
+//! # }
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use itertools::Itertools;
+
+use crate::{
+    error::{Context, CustomError},
+    rawfile::mgf,
+    system::usize::Charge,
+    CompoundPeptidoformIon, MassMode, Model, RawSpectrum,
+};
+
+/// The version of the golden file format, this is increased when the canonical outputs change
+/// on purpose, all golden files need to be regenerated when this changes.
+pub const GOLDEN_FORMAT_VERSION: usize = 1;
+
+/// The environment variable that turns on updating the golden files
+pub const UPDATE_VARIABLE: &str = "RUSTYMS_UPDATE_GOLDEN";
+
+/// A directory with golden files, see the [module documentation](self).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GoldenFiles {
+    directory: PathBuf,
+    update: bool,
+}
+
+impl GoldenFiles {
+    /// Use the golden files in the given directory. The golden files are updated if the
+    /// environment variable `RUSTYMS_UPDATE_GOLDEN` is set to `1` or `true`.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            update: std::env::var(UPDATE_VARIABLE)
+                .is_ok_and(|value| value == "1" || value.eq_ignore_ascii_case("true")),
+        }
+    }
+
+    /// Set if the golden files are updated instead of checked
+    #[must_use]
+    pub fn update(self, update: bool) -> Self {
+        Self { update, ..self }
+    }
+
+    /// The directory with the golden files
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The path of the golden file with the given name
+    pub fn path(&self, name: &str) -> PathBuf {
+        self.directory.join(format!("{name}.golden"))
+    }
+
+    /// Check the given output against the golden file with the given name, or write the golden
+    /// file if updating is turned on.
+    /// # Errors
+    /// If the golden file does not exist, was generated with a different golden format version,
+    /// or if the content differs (the error contains a diff of the differences). If updating is
+    /// turned on an error is only returned if the golden file could not be written.
+    pub fn check(&self, name: &str, actual: &str) -> Result<(), CustomError> {
+        let path = self.path(name);
+        if self.update {
+            return std::fs::create_dir_all(&self.directory)
+                .and_then(|()| std::fs::write(&path, format!("{}\n{actual}", header())))
+                .map_err(|err| {
+                    CustomError::error(
+                        "Could not write golden file",
+                        err,
+                        Context::show(path.display()),
+                    )
+                });
+        }
+        let stored = std::fs::read_to_string(&path).map_err(|err| {
+            CustomError::error(
+                "Could not read golden file",
+                format!("{err}, run with {UPDATE_VARIABLE}=1 to generate the golden file"),
+                Context::show(path.display()),
+            )
+        })?;
+        let (stored_header, expected) = stored.split_once('\n').unwrap_or((&stored, ""));
+        let version = stored_header
+            .strip_prefix("# rustyms golden v")
+            .and_then(|rest| rest.split_once(' '))
+            .and_then(|(version, _)| version.parse::<usize>().ok());
+        if version != Some(GOLDEN_FORMAT_VERSION) {
+            return Err(CustomError::error(
+                "Outdated golden file",
+                format!("This golden file was not generated with golden format version {GOLDEN_FORMAT_VERSION}, run with {UPDATE_VARIABLE}=1 to regenerate the golden file"),
+                Context::show(stored_header),
+            ));
+        }
+        // Git can convert line endings on checkout
+        let expected = expected.replace("\r\n", "\n");
+        if expected == actual {
+            Ok(())
+        } else {
+            Err(CustomError::error(
+                "Output differs from golden file",
+                format!(
+                    "{}\n{}",
+                    path.display(),
+                    similar::TextDiff::from_lines(expected.as_str(), actual)
+                        .unified_diff()
+                        .header("golden", "actual")
+                ),
+                Context::none(),
+            ))
+        }
+    }
+}
+
+/// The header line of a golden file
+fn header() -> String {
+    format!(
+        "# rustyms golden v{GOLDEN_FORMAT_VERSION} (generated by rustyms {})",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+/// The canonical table for ProForma parsing and display. For every peptidoform this gives a tab
+/// separated line with the ProForma display, the number of formulas, and all monoisotopic
+/// masses (6 decimals).
+pub fn pro_forma_table<'a>(
+    peptidoforms: impl IntoIterator<Item = &'a CompoundPeptidoformIon>,
+) -> String {
+    let mut output = String::new();
+    for peptidoform in peptidoforms {
+        let formulas = peptidoform.formulas();
+        writeln!(
+            output,
+            "{peptidoform}\t{}\t{}",
+            formulas.len(),
+            formulas
+                .iter()
+                .map(|f| format!("{:.6}", f.monoisotopic_mass().value))
+                .join(",")
+        )
+        .unwrap();
+    }
+    output
+}
+
+/// The canonical fragment table for the given peptidoform. For every theoretical fragment this
+/// gives a tab separated line with the fragment type, the charge, the neutral losses, the
+/// formula, the monoisotopic m/z (6 decimals), and the mzPAF representation. The lines are
+/// sorted to not depend on the order of fragment generation.
+pub fn fragment_table(
+    peptidoform: &CompoundPeptidoformIon,
+    charge: Charge,
+    model: &Model,
+) -> String {
+    peptidoform
+        .generate_theoretical_fragments(charge, model)
+        .iter()
+        .map(|fragment| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\t{}\n",
+                fragment.ion,
+                fragment.charge.value,
+                fragment.neutral_loss.iter().join(""),
+                fragment
+                    .formula
+                    .as_ref()
+                    .map_or_else(String::new, crate::MolecularFormula::hill_notation),
+                fragment
+                    .mz(MassMode::Monoisotopic)
+                    .map_or_else(String::new, |mz| format!("{:.6}", mz.value)),
+                fragment.to_mzpaf()
+            )
+        })
+        .sorted()
+        .collect()
+}
+
+/// The canonical MGF export for the given spectra, see [`mgf::write`].
+/// # Errors
+/// If the spectra could not be written.
+pub fn mgf_export<'a>(
+    spectra: impl IntoIterator<Item = &'a RawSpectrum>,
+) -> Result<String, CustomError> {
+    let mut buffer = Vec::new();
+    mgf::write(&mut buffer, spectra)?;
+    String::from_utf8(buffer)
+        .map_err(|err| CustomError::error("Invalid MGF export", err.to_string(), Context::none()))
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::system::e;
+
+    #[test]
+    fn golden() {
+        let golden = GoldenFiles::new("data/golden");
+        let peptides = [
+            "PEPTIDE",
+            "AC[Carbamidomethyl]DK/2",
+            "[Acetyl]-M[Oxidation]ASK",
+            "EM[Oxidation]EVEES[Phospho]PEK",
+            "PEPT[Formula:C6H10O5]IDE",
+            "(?DQ)NGTWEM[Oxidation]ESNENFEGYM[Oxidation]K",
+            "AJK",
+            "PEPTIDE/2+PEPTIDE/3",
+        ]
+        .map(|p| CompoundPeptidoformIon::pro_forma(p, None).unwrap());
+        golden
+            .check("pro_forma", &pro_forma_table(&peptides))
+            .unwrap();
+        golden
+            .check(
+                "fragments_all",
+                &fragment_table(&peptides[2], Charge::new::<e>(2), &Model::all()),
+            )
+            .unwrap();
+        golden
+            .check(
+                "fragments_ethcd",
+                &fragment_table(&peptides[3], Charge::new::<e>(3), &Model::ethcd()),
+            )
+            .unwrap();
+        golden
+            .check(
+                "mgf",
+                &mgf_export(&mgf::open("data/example.mgf").unwrap()).unwrap(),
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn mismatch() {
+        let directory = std::env::temp_dir().join(format!("rustyms_golden_{}", std::process::id()));
+        let golden = GoldenFiles::new(&directory).update(false);
+        assert!(golden.check("missing", "a").is_err());
+        golden.clone().update(true).check("test", "a\nb\n").unwrap();
+        assert!(golden.check("test", "a\nb\n").is_ok());
+        let error = golden.check("test", "a\nc\n").unwrap_err();
+        assert!(error.to_string().contains("+c"), "{error}");
+        std::fs::write(golden.path("test"), "# rustyms golden v0 (old)\na\nb\n").unwrap();
+        assert!(golden.check("test", "a\nb\n").is_err());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
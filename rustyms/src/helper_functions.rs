@@ -1,13 +1,19 @@
 #![allow(dead_code)]
 
 use std::{
+    io::{BufRead, BufReader},
     num::{IntErrorKind, ParseIntError},
     ops::{Bound, Range, RangeBounds},
     path::Path,
     str::FromStr,
 };
 
-use crate::SequencePosition;
+use flate2::bufread::GzDecoder;
+
+use crate::{
+    error::{Context, CustomError},
+    SequencePosition,
+};
 
 pub fn peptide_range_contains(
     range: &impl RangeBounds<usize>,
@@ -241,6 +247,104 @@ pub fn split_ascii_whitespace(input: &str) -> Vec<(usize, &str)> {
     chunks
 }
 
+/// Open a file for reading, on Windows long paths (more than 260 characters) and UNC paths are
+/// supported by opening the file with its extended length path (see [`extended_length_path`]).
+/// # Errors
+/// If the file could not be opened.
+pub fn open_file(path: impl AsRef<Path>) -> std::io::Result<std::fs::File> {
+    std::fs::File::open(extended_length_path(path.as_ref()))
+}
+
+/// Create a file for writing, with the same support for long paths as [`open_file`].
+/// # Errors
+/// If the file could not be created.
+pub fn create_file(path: impl AsRef<Path>) -> std::io::Result<std::fs::File> {
+    std::fs::File::create(extended_length_path(path.as_ref()))
+}
+
+/// Open a file (see [`open_file`]) for buffered reading, gzipped files (with the extension `gz`)
+/// are uncompressed automatically.
+/// # Errors
+/// If the file could not be opened, the error has the path as context.
+pub fn open_reader(path: impl AsRef<Path>) -> Result<Box<dyn BufRead>, CustomError> {
+    let path = path.as_ref();
+    let file = open_file(path).map_err(|error| {
+        CustomError::error(
+            "Could not open file",
+            error,
+            Context::show(path.to_string_lossy()),
+        )
+    })?;
+    if check_extension(path, "gz") {
+        Ok(Box::new(BufReader::new(GzDecoder::new(BufReader::new(
+            file,
+        )))))
+    } else {
+        Ok(Box::new(BufReader::new(file)))
+    }
+}
+
+/// Get the extended length form of the given path (`\\?\C:\...` or `\\?\UNC\server\share\...`)
+/// which is not subject to the 260 character path length limit on Windows. Relative paths are
+/// made absolute and `.` and `..` components are resolved, as these are not supported in
+/// extended length paths. On other platforms the path is returned as is.
+#[cfg(windows)]
+pub fn extended_length_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    use std::{
+        ffi::OsString,
+        path::{Component, Prefix},
+    };
+
+    let absolute = if path.is_absolute() {
+        path.to_path_buf()
+    } else if let Ok(current) = std::env::current_dir() {
+        current.join(path)
+    } else {
+        return std::borrow::Cow::Borrowed(path);
+    };
+    let mut prefix = OsString::new();
+    let mut parts: Vec<&std::ffi::OsStr> = Vec::new();
+    for component in absolute.components() {
+        match component {
+            Component::Prefix(p) => match p.kind() {
+                // Already an extended length path, these do not support any normalisation
+                Prefix::Verbatim(_) | Prefix::VerbatimUNC(_, _) | Prefix::VerbatimDisk(_) => {
+                    return std::borrow::Cow::Borrowed(path)
+                }
+                Prefix::UNC(server, share) => {
+                    prefix.push(r"\\?\UNC\");
+                    prefix.push(server);
+                    prefix.push(r"\");
+                    prefix.push(share);
+                }
+                Prefix::Disk(_) => {
+                    prefix.push(r"\\?\");
+                    prefix.push(p.as_os_str());
+                }
+                // Device paths (`\\.\`) cannot be converted
+                Prefix::DeviceNS(_) => return std::borrow::Cow::Borrowed(path),
+            },
+            Component::RootDir | Component::CurDir => (),
+            Component::ParentDir => {
+                parts.pop();
+            }
+            Component::Normal(part) => parts.push(part),
+        }
+    }
+    for part in parts {
+        prefix.push(r"\");
+        prefix.push(part);
+    }
+    std::borrow::Cow::Owned(prefix.into())
+}
+
+/// Get the extended length form of the given path, this is only relevant on Windows, so on
+/// other platforms the path is returned as is.
+#[cfg(not(windows))]
+pub const fn extended_length_path(path: &Path) -> std::borrow::Cow<'_, Path> {
+    std::borrow::Cow::Borrowed(path)
+}
+
 /// Helper function to check extensions in filenames
 pub fn check_extension(filename: impl AsRef<Path>, extension: impl AsRef<Path>) -> bool {
     filename
@@ -353,68 +457,48 @@ pub fn next_num(chars: &[u8], mut start: usize, allow_only_sign: bool) -> Option
     }
 }
 
-/// A number of characters, used as length or index
-pub type Characters = usize;
-
 #[allow(dead_code)]
-/// Get the next number starting at the character range given, returns length in characters and the number.
-/// # Errors
-/// Returns none if the number is too big to fit in a `isize`.
+/// Get the next number starting at the byte range given, returns length in bytes, the sign, and the number.
+/// Any leading sign is part of the parsed text so signed numbers are returned with their sign.
+/// Returns none if the range does not start with a number, if the range is not valid for this line
+/// the number is considered absent.
 pub fn next_number<const ALLOW_SIGN: bool, const FLOATING_POINT: bool, Number: FromStr>(
     line: &str,
-    range: impl RangeBounds<Characters>,
-) -> Option<(Characters, bool, Result<Number, Number::Err>)> {
-    let start = match range.start_bound() {
-        Bound::Unbounded => 0,
-        Bound::Excluded(n) => n + 1,
-        Bound::Included(n) => *n,
-    };
+    range: impl RangeBounds<usize>,
+) -> Option<(usize, bool, Result<Number, Number::Err>)> {
+    let bytes = line.as_bytes();
+    let start = range.start_index();
     let end = match range.end_bound() {
-        Bound::Unbounded => line.chars().count(),
-        Bound::Excluded(n) => n - 1,
-        Bound::Included(n) => *n,
-    };
+        Bound::Unbounded => line.len(),
+        Bound::Excluded(n) => *n,
+        Bound::Included(n) => n + 1,
+    }
+    .min(line.len());
+    let mut index = start;
     let mut positive = true;
-    let mut sign_set = false;
-    let mut start_index = 0;
-    let mut chars = line.char_indices().skip(start).peekable();
-    if ALLOW_SIGN {
-        match chars.peek() {
-            Some((_, '-')) => {
+    if ALLOW_SIGN && index < end {
+        match bytes[index] {
+            b'-' => {
                 positive = false;
-                sign_set = true;
-            }
-            Some((_, '+')) => {
-                sign_set = true;
+                index += 1;
             }
+            b'+' => index += 1,
             _ => (),
         }
-        if sign_set {
-            let _ = chars.next();
-        }
     }
-    if let Some(index) = chars.peek().map(|(index, _)| index) {
-        start_index = *index;
+    let number_start = index;
+    while index < end
+        && (bytes[index].is_ascii_digit() || (FLOATING_POINT && b".eE+-".contains(&bytes[index])))
+    {
+        index += 1;
     }
-
-    let mut consumed = usize::from(sign_set);
-    chars
-        .take_while(|(_, c)| {
-            if c.is_ascii_digit() || (FLOATING_POINT && ".eE+-".contains(*c)) {
-                consumed += 1;
-                consumed < end - start
-            } else {
-                false
-            }
-        })
-        .last()
-        .map(|(end_index, c)| {
-            (
-                consumed,
-                positive,
-                line[start_index..end_index + c.len_utf8()].parse::<Number>(),
-            )
-        })
+    (index > number_start).then(|| {
+        (
+            index - start,
+            positive,
+            line[start..index].parse::<Number>(),
+        )
+    })
 }
 
 /// Get a canonicalised u64 for f64 to be able to hash f64, based on the `ordered_float` crate (MIT license)
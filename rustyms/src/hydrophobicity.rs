@@ -0,0 +1,118 @@
+//! Hydrophobicity indices for peptidoforms
+
+use crate::{AminoAcid, Peptidoform};
+
+impl AminoAcid {
+    /// The hydropathy index of this amino acid according to Kyte and Doolittle (1982,
+    /// <https://doi.org/10.1016/0022-2836(82)90515-0>). Positive values are hydrophobic,
+    /// negative values hydrophilic. The ambiguous amino acids B, J, and Z get the average of
+    /// their options, selenocysteine the value of cysteine, pyrrolysine the value of lysine, and
+    /// unknown amino acids have no value.
+    pub const fn hydropathy(self) -> Option<f64> {
+        Some(match self {
+            Self::Isoleucine => 4.5,
+            Self::Valine => 4.2,
+            Self::Leucine => 3.8,
+            Self::AmbiguousLeucine => 4.15,
+            Self::Phenylalanine => 2.8,
+            Self::Cysteine | Self::Selenocysteine => 2.5,
+            Self::Methionine => 1.9,
+            Self::Alanine => 1.8,
+            Self::Glycine => -0.4,
+            Self::Threonine => -0.7,
+            Self::Serine => -0.8,
+            Self::Tryptophan => -0.9,
+            Self::Tyrosine => -1.3,
+            Self::Proline => -1.6,
+            Self::Histidine => -3.2,
+            Self::GlutamicAcid
+            | Self::Glutamine
+            | Self::AmbiguousGlutamine
+            | Self::AsparticAcid
+            | Self::Asparagine
+            | Self::AmbiguousAsparagine => -3.5,
+            Self::Lysine | Self::Pyrrolysine => -3.9,
+            Self::Arginine => -4.5,
+            Self::Unknown => return None,
+        })
+    }
+}
+
+impl<Complexity> Peptidoform<Complexity> {
+    /// The grand average of hydropathy (GRAVY, Kyte and Doolittle 1982), the average
+    /// [hydropathy](AminoAcid::hydropathy) of all amino acids in this peptidoform. Unknown
+    /// amino acids are ignored and modifications are not taken into account. Returns `None` if
+    /// there are no amino acids with a known hydropathy.
+    /// ```rust
+    /// # use rustyms::*;
+    /// let peptide = Peptidoform::pro_forma("AILV", None).unwrap();
+    /// assert_eq!(peptide.gravy(), Some(3.575));
+    /// ```
+    pub fn gravy(&self) -> Option<f64> {
+        average(
+            self.sequence()
+                .iter()
+                .filter_map(|s| s.aminoacid.aminoacid().hydropathy()),
+        )
+    }
+
+    /// The Kyte-Doolittle hydropathy profile of this peptidoform, the average
+    /// [hydropathy](AminoAcid::hydropathy) in a sliding window of the given size. The value for
+    /// every window is given in sequence order, so the result has `len - window + 1` values
+    /// (none if the window is larger than the peptidoform). Kyte and Doolittle advise a window
+    /// between 7 and 11 amino acids to find surface regions and 19 for transmembrane regions.
+    /// Unknown amino acids are ignored in the average, a window with only unknown amino acids
+    /// has a value of 0.
+    /// ```rust
+    /// # use rustyms::*;
+    /// let peptide = Peptidoform::pro_forma("KKKILVILKKK", None).unwrap();
+    /// let profile = peptide.hydropathy_profile(3);
+    /// assert_eq!(profile.len(), 9);
+    /// assert!(profile[4] > profile[0]);
+    /// ```
+    pub fn hydropathy_profile(&self, window: usize) -> Vec<f64> {
+        let window = window.max(1);
+        let values = self
+            .sequence()
+            .iter()
+            .map(|s| s.aminoacid.aminoacid().hydropathy())
+            .collect::<Vec<_>>();
+        values
+            .windows(window)
+            .map(|w| average(w.iter().copied().flatten()).unwrap_or_default())
+            .collect()
+    }
+}
+
+/// The average of the given values, or `None` if there are no values
+fn average(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, count) = values.fold((0.0, 0_usize), |(sum, count), v| (sum + v, count + 1));
+    (count > 0).then(|| sum / count as f64)
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use crate::Peptidoform;
+
+    #[test]
+    fn gravy() {
+        let gravy = |p: &str| Peptidoform::pro_forma(p, None).unwrap().gravy();
+        assert_eq!(gravy("R"), Some(-4.5));
+        assert_eq!(gravy("RX"), Some(-4.5));
+        assert_eq!(gravy("X"), None);
+        assert_eq!(gravy("M[Oxidation]K"), gravy("MK"));
+        assert!(gravy("LLFWLLK").unwrap() > gravy("SGDNSKR").unwrap());
+    }
+
+    #[test]
+    fn profile() {
+        let peptide = Peptidoform::pro_forma("ILVKX", None).unwrap();
+        let profile = peptide.hydropathy_profile(2);
+        assert_eq!(profile.len(), 4);
+        assert!((profile[0] - 4.15).abs() < 1e-9);
+        assert!((profile[3] + 3.9).abs() < 1e-9);
+        assert_eq!(peptide.hydropathy_profile(1).last(), Some(&0.0));
+        assert!(peptide.hydropathy_profile(6).is_empty());
+    }
+}
@@ -0,0 +1,402 @@
+//! Assemble overlapping (de novo) peptides into contigs
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    align::{align, multi_align, AlignScoring, AlignType, MultiAlignment, MultiAlignmentLine},
+    identification::IdentifiedPeptide,
+    peptidoform::SemiAmbiguous,
+    Peptidoform, SequenceElement,
+};
+
+/// The settings for assembling peptides into contigs, see [`assemble_contigs`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AssemblySettings {
+    /// The minimal number of residues that two peptides have to overlap to be placed in the same
+    /// contig.
+    ///
+    /// Default: 4.
+    pub min_overlap: usize,
+    /// The minimal normalised score of the alignment of two overlapping peptides for them to be
+    /// placed in the same contig.
+    ///
+    /// Default: 0.8.
+    pub score_threshold: f64,
+    /// The minimal number of peptides in a contig, contigs with fewer peptides are not reported.
+    ///
+    /// Default: 1.
+    pub min_reads: usize,
+}
+
+impl Default for AssemblySettings {
+    fn default() -> Self {
+        Self {
+            min_overlap: 4,
+            score_threshold: 0.8,
+            min_reads: 1,
+        }
+    }
+}
+
+impl AssemblySettings {
+    /// Set the minimal overlap
+    #[must_use]
+    pub const fn min_overlap(self, min_overlap: usize) -> Self {
+        Self {
+            min_overlap,
+            ..self
+        }
+    }
+
+    /// Set the score threshold
+    #[must_use]
+    pub const fn score_threshold(self, score_threshold: f64) -> Self {
+        Self {
+            score_threshold,
+            ..self
+        }
+    }
+
+    /// Set the minimal number of peptides per contig
+    #[must_use]
+    pub const fn min_reads(self, min_reads: usize) -> Self {
+        Self { min_reads, ..self }
+    }
+}
+
+/// A contig assembled from overlapping peptides, see [`assemble_contigs`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Contig {
+    /// The consensus sequence
+    pub consensus: Peptidoform<SemiAmbiguous>,
+    /// The confidence for every residue in the consensus sequence, the fraction of the total
+    /// confidence of all peptides covering this position that supports the consensus residue
+    pub confidence: Vec<f64>,
+    /// The number of peptides that cover every residue in the consensus sequence
+    pub depth: Vec<usize>,
+    /// The indices of the peptides that make up this contig, the order is the same as the lines
+    /// in the alignment
+    pub reads: Vec<usize>,
+    /// The multiple alignment of all peptides in this contig
+    pub alignment: MultiAlignment<'static, SemiAmbiguous>,
+}
+
+/// Assemble identified peptides into contigs. Only peptides that are simple linear peptides are
+/// used. The normalised local confidence of the peptides is used to weigh the peptides when
+/// building the consensus, if this is not available the normalised score (or 1.0 if no score is
+/// available) is used for all residues. Both are in the range -1.0..=1.0, the original local
+/// confidences given by the formats are not used as these are not on a common scale. The read
+/// indices in the contigs are the indices in the given peptides. See [`assemble_reads`] for the
+/// details.
+///
+/// Only available with crate feature `align`.
+pub fn assemble_contigs<const STEPS: u16>(
+    peptides: &[IdentifiedPeptide],
+    scoring: AlignScoring<'_>,
+    settings: &AssemblySettings,
+) -> Vec<Contig> {
+    let (indices, reads): (Vec<usize>, Vec<_>) = peptides
+        .iter()
+        .enumerate()
+        .filter_map(|(index, peptide)| {
+            let sequence = peptide
+                .peptide()?
+                .peptide()?
+                .into_owned()
+                .into_semi_ambiguous()?;
+            let confidence = peptide
+                .local_confidence
+                .as_deref()
+                .filter(|c| c.len() == sequence.len())
+                .map_or_else(
+                    || vec![peptide.score.unwrap_or(1.0); sequence.len()],
+                    <[f64]>::to_vec,
+                );
+            Some((index, (sequence, confidence)))
+        })
+        .unzip();
+    let mut contigs = assemble_reads::<STEPS>(&reads, scoring, settings);
+    for contig in &mut contigs {
+        for read in &mut contig.reads {
+            *read = indices[*read];
+        }
+    }
+    contigs
+}
+
+/// Assemble peptides, with a confidence for every residue, into contigs. All pairs of peptides
+/// are aligned, if the alignment covers at least the minimal overlap on both peptides and has at
+/// least the minimal normalised score the two peptides are placed in the same cluster. Every
+/// cluster of overlapping peptides is aligned with a multiple alignment ([`multi_align`]), and
+/// the consensus is determined for every column. Every peptide votes for the residues it has in
+/// the column, or for a gap if the column is in between its first and last residue, weighted by
+/// its (average) confidence for these residues. Negative confidences are counted as zero. The
+/// contigs are sorted on descending number of peptides.
+/// ```rust
+/// use rustyms::{*, align::*, identification::*};
+/// let reads = [("AGGWHKT", 0.9), ("GGWHKTL", 0.8), ("WHKTLR", 0.9)].map(|(p, c)| {
+///     let peptide = Peptidoform::pro_forma(p, None).unwrap().into_semi_ambiguous().unwrap();
+///     let confidence = vec![c; peptide.len()];
+///     (peptide, confidence)
+/// });
+/// let contigs = assemble_reads::<4>(&reads, AlignScoring::default(), &AssemblySettings::default());
+/// assert_eq!(contigs.len(), 1);
+/// assert_eq!(contigs[0].consensus.to_string(), "AGGWHKTLR");
+/// ```
+///
+/// Only available with crate feature `align`.
+pub fn assemble_reads<const STEPS: u16>(
+    reads: &[(Peptidoform<SemiAmbiguous>, Vec<f64>)],
+    scoring: AlignScoring<'_>,
+    settings: &AssemblySettings,
+) -> Vec<Contig> {
+    // Cluster the overlapping reads with a union find
+    fn root(parents: &mut [usize], mut index: usize) -> usize {
+        while parents[index] != index {
+            parents[index] = parents[parents[index]];
+            index = parents[index];
+        }
+        index
+    }
+    let mut parents = (0..reads.len()).collect_vec();
+    for (a, b) in (0..reads.len()).tuple_combinations() {
+        let (root_a, root_b) = (root(&mut parents, a), root(&mut parents, b));
+        if root_a == root_b {
+            continue;
+        }
+        let alignment = align::<STEPS, SemiAmbiguous, SemiAmbiguous>(
+            &reads[a].0,
+            &reads[b].0,
+            scoring,
+            AlignType::EITHER_GLOBAL,
+        );
+        if alignment.len_a().min(alignment.len_b()) >= settings.min_overlap
+            && alignment.normalised_score() >= settings.score_threshold
+        {
+            parents[root_b.max(root_a)] = root_a.min(root_b);
+        }
+    }
+    let clusters = (0..reads.len())
+        .map(|index| (root(&mut parents, index), index))
+        .into_group_map();
+
+    let mut contigs = clusters
+        .into_values()
+        .filter(|members| members.len() >= settings.min_reads.max(1))
+        .map(|members| {
+            let sequences = members.iter().map(|i| reads[*i].0.clone()).collect_vec();
+            let alignment =
+                multi_align::<STEPS, SemiAmbiguous>(&sequences, scoring, AlignType::EITHER_GLOBAL)
+                    .to_owned();
+            let (consensus, confidence, depth) = consensus(
+                &alignment,
+                &members.iter().map(|i| reads[*i].1.as_slice()).collect_vec(),
+            );
+            Contig {
+                consensus: consensus.into_iter().collect(),
+                confidence,
+                depth,
+                reads: members,
+                alignment,
+            }
+        })
+        .collect_vec();
+    contigs.sort_by(|a, b| {
+        b.reads
+            .len()
+            .cmp(&a.reads.len())
+            .then(b.consensus.len().cmp(&a.consensus.len()))
+            .then(a.reads.cmp(&b.reads))
+    });
+    contigs
+}
+
+/// The consensus of a multiple alignment weighted by the confidences of the residues of all
+/// lines, returns the consensus sequence and the confidence and depth for every residue.
+#[allow(clippy::type_complexity)]
+fn consensus(
+    alignment: &MultiAlignment<'_, SemiAmbiguous>,
+    confidences: &[&[f64]],
+) -> (Vec<SequenceElement<SemiAmbiguous>>, Vec<f64>, Vec<usize>) {
+    let spans = alignment
+        .lines()
+        .iter()
+        .map(|line| {
+            let first = line.path().iter().position(|p| p.step > 0).unwrap_or(0);
+            let last = line.path().iter().rposition(|p| p.step > 0).unwrap_or(0);
+            first..=last
+        })
+        .collect_vec();
+    let averages = confidences
+        .iter()
+        .map(|c| c.iter().map(|c| c.max(0.0)).sum::<f64>() / c.len().max(1) as f64)
+        .collect_vec();
+    let mut columns = alignment
+        .lines()
+        .iter()
+        .map(MultiAlignmentLine::columns)
+        .collect_vec();
+    let mut positions = vec![0; columns.len()];
+    let mut result = (Vec::new(), Vec::new(), Vec::new());
+    for column in 0..alignment.len() {
+        let mut options: Vec<(Option<&[SequenceElement<SemiAmbiguous>]>, f64)> = Vec::new();
+        let mut total = 0.0;
+        let mut depth = 0;
+        for (line, residues) in columns.iter_mut().enumerate() {
+            let residues = residues.next().unwrap_or_default();
+            let (option, weight) = if residues.is_empty() {
+                if !spans[line].contains(&column) {
+                    continue;
+                }
+                (None, averages[line])
+            } else {
+                let position = positions[line];
+                positions[line] += residues.len();
+                depth += 1;
+                (
+                    Some(residues),
+                    confidences[line]
+                        .get(position..position + residues.len())
+                        .map_or(averages[line], |c| {
+                            c.iter().map(|c| c.max(0.0)).sum::<f64>() / residues.len() as f64
+                        }),
+                )
+            };
+            total += weight;
+            if let Some(existing) = options.iter_mut().find(|(o, _)| *o == option) {
+                existing.1 += weight;
+            } else {
+                options.push((option, weight));
+            }
+        }
+        // Prefer residues over gaps on equal support
+        if let Some((Some(residues), weight)) = options
+            .into_iter()
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(a.0.is_some().cmp(&b.0.is_some())))
+        {
+            let confidence = if total > 0.0 { weight / total } else { 0.0 };
+            result.0.extend(residues.iter().cloned());
+            result
+                .1
+                .extend(std::iter::repeat(confidence).take(residues.len()));
+            result
+                .2
+                .extend(std::iter::repeat(depth).take(residues.len()));
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use std::io::BufReader;
+
+    use super::*;
+    use crate::identification::FastaData;
+
+    fn reads(reads: &[(&str, f64)]) -> Vec<(Peptidoform<SemiAmbiguous>, Vec<f64>)> {
+        reads
+            .iter()
+            .map(|(p, c)| {
+                let peptide = Peptidoform::pro_forma(p, None)
+                    .unwrap()
+                    .into_semi_ambiguous()
+                    .unwrap();
+                let confidence = vec![*c; peptide.len()];
+                (peptide, confidence)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn separate_contigs() {
+        let reads = reads(&[
+            ("AGGWHKT", 0.9),
+            ("PEPTIDEK", 0.9),
+            ("GGWHKTL", 0.8),
+            ("PTIDEKMS", 0.7),
+            ("WHKTLR", 0.9),
+        ]);
+        let contigs = assemble_reads::<4>(
+            &reads,
+            AlignScoring::default(),
+            &AssemblySettings::default(),
+        );
+        assert_eq!(contigs.len(), 2);
+        assert_eq!(contigs[0].consensus.to_string(), "AGGWHKTLR");
+        assert_eq!(contigs[0].reads, [0, 2, 4]);
+        assert_eq!(contigs[0].depth, [1, 2, 2, 3, 3, 3, 3, 2, 1]);
+        assert_eq!(contigs[1].consensus.to_string(), "PEPTIDEKMS");
+        assert_eq!(contigs[1].reads, [1, 3]);
+        assert!(contigs[1]
+            .confidence
+            .iter()
+            .all(|c| (*c - 1.0).abs() < 1e-9));
+
+        let contigs = assemble_reads::<4>(
+            &reads,
+            AlignScoring::default(),
+            &AssemblySettings::default().min_reads(3),
+        );
+        assert_eq!(contigs.len(), 1);
+    }
+
+    #[test]
+    fn weighted_consensus() {
+        // The confident reads agree on N where the uncertain read has GG
+        let reads = reads(&[("WHKANWKR", 0.9), ("HKAGGWKR", 0.2), ("WHKANWK", 0.8)]);
+        let contigs = assemble_reads::<4>(
+            &reads,
+            AlignScoring::default(),
+            &AssemblySettings::default(),
+        );
+        assert_eq!(contigs.len(), 1);
+        assert_eq!(contigs[0].consensus.to_string(), "WHKANWKR");
+        let n = contigs[0].consensus.len() - 4;
+        assert!((contigs[0].confidence[n] - 1.7 / 1.9).abs() < 1e-9);
+    }
+
+    #[test]
+    fn identified_peptides() {
+        let fasta = ">A\nAGGWHKT\n>B\nGGWHKTL\n>C\nPEPTIDEK";
+        let peptides = FastaData::parse_reader(BufReader::new(fasta.as_bytes()), None)
+            .unwrap()
+            .into_iter()
+            .map(IdentifiedPeptide::from)
+            .collect_vec();
+        let contigs = assemble_contigs::<4>(
+            &peptides,
+            AlignScoring::default(),
+            &AssemblySettings::default(),
+        );
+        assert_eq!(contigs.len(), 2);
+        assert_eq!(contigs[0].consensus.to_string(), "AGGWHKTL");
+        assert_eq!(contigs[0].reads, [0, 1]);
+        assert_eq!(contigs[1].reads, [2]);
+    }
+
+    #[test]
+    fn normalised_weights() {
+        // The same reads as the weighted consensus, with both local confidence and scores
+        let fasta = ">A\nWHKANWKR\n>B\nHKAGGWKR\n>C\nWHKANWK";
+        let mut peptides = FastaData::parse_reader(BufReader::new(fasta.as_bytes()), None)
+            .unwrap()
+            .into_iter()
+            .map(IdentifiedPeptide::from)
+            .collect_vec();
+        peptides[0].local_confidence = Some(vec![0.9; 8]);
+        peptides[1].score = Some(0.2);
+        peptides[2].score = Some(0.8);
+        let contigs = assemble_contigs::<4>(
+            &peptides,
+            AlignScoring::default(),
+            &AssemblySettings::default(),
+        );
+        assert_eq!(contigs.len(), 1);
+        assert_eq!(contigs[0].consensus.to_string(), "WHKANWKR");
+        let n = contigs[0].consensus.len() - 4;
+        assert!((contigs[0].confidence[n] - 1.7 / 1.9).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,201 @@
+use std::{
+    io::{BufRead, Cursor, Read},
+    str::FromStr,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::open_reader,
+    identification::{CVTerm, IdentifiedPeptide, MZTabData, MetaData, SpectrumIds},
+    ontologies::CustomDatabase,
+    system::{usize::Charge, MassOverCharge, Time},
+    Peptidoform, SemiAmbiguous,
+};
+
+/// Peptide data from a Casanovo mzTab file
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct CasanovoData {
+    /// The Casanovo version, as detected from the metadata
+    pub version: CasanovoVersion,
+    /// The full software version as reported in the metadata (eg `4.2.1`)
+    pub software_version: String,
+    /// The predicted peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The PSM ID
+    pub id: usize,
+    /// The peptide score as reported by Casanovo, lowered by one if the precursor mass did not match
+    pub score: f64,
+    /// The confidence per amino acid, without the entries for terminal modifications
+    pub local_confidence: Vec<f64>,
+    /// The precursor charge
+    pub z: Charge,
+    /// The experimental mz
+    pub mz: Option<MassOverCharge>,
+    /// The retention time, if reported
+    pub rt: Option<Time>,
+    /// The spectra references grouped by raw file
+    pub spectra_ref: SpectrumIds,
+}
+
+impl CasanovoData {
+    /// Parse a Casanovo mzTab file, the Casanovo version is detected from the metadata.
+    /// # Errors
+    /// If the file could not be opened or the file was not written by Casanovo.
+    pub fn parse_file(
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
+        Ok(Box::new(Self::parse_reader(
+            open_reader(path)?,
+            custom_database,
+        )?))
+    }
+
+    /// Parse a Casanovo mzTab file directly from a buffered reader. The metadata is read
+    /// eagerly to detect the Casanovo version, the PSMs are read lazily.
+    /// # Errors
+    /// If the metadata could not be read or does not list Casanovo as the software.
+    pub fn parse_reader<'a, T: BufRead + 'a>(
+        mut reader: T,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<impl Iterator<Item = Result<Self, CustomError>> + 'a, CustomError> {
+        let mut metadata = Vec::new();
+        let mut software = None;
+        let mut line = String::new();
+        for line_index in 0.. {
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(|err| {
+                CustomError::error(
+                    "Could not read line",
+                    err,
+                    Context::full_line(line_index, "(failed)"),
+                )
+            })?;
+            metadata.extend_from_slice(line.as_bytes());
+            if read == 0 || line.starts_with("PSH") || line.starts_with("PSM") {
+                break;
+            }
+            if let Some(version) = software_version(&line) {
+                software = Some(version);
+            }
+        }
+        let software_version = software.ok_or_else(|| {
+            CustomError::error(
+                "Not a Casanovo file",
+                "The mzTab metadata does not list Casanovo as the software",
+                Context::None,
+            )
+        })?;
+        let version = CasanovoVersion::from_str(&software_version)?;
+
+        Ok(
+            MZTabData::parse_reader(Cursor::new(metadata).chain(reader), custom_database).map(
+                move |psm| psm.and_then(|psm| Self::from_mztab(psm, version, &software_version)),
+            ),
+        )
+    }
+
+    /// Convert a general mzTab PSM into a Casanovo PSM
+    /// # Errors
+    /// If the PSM does not contain a peptide
+    fn from_mztab(
+        psm: MZTabData,
+        version: CasanovoVersion,
+        software_version: &str,
+    ) -> Result<Self, CustomError> {
+        Ok(Self {
+            version,
+            software_version: software_version.to_string(),
+            peptide: psm.peptide.ok_or_else(|| {
+                CustomError::error(
+                    "Invalid Casanovo PSM",
+                    format!("The PSM with ID {} does not contain a peptide", psm.id),
+                    Context::None,
+                )
+            })?,
+            id: psm.id,
+            score: psm
+                .search_engine
+                .first()
+                .and_then(|(_, score, _)| *score)
+                .unwrap_or(f64::NAN),
+            local_confidence: psm.local_confidence.unwrap_or_default(),
+            z: psm.z,
+            mz: psm.mz,
+            rt: psm.rt,
+            spectra_ref: psm.spectra_ref,
+        })
+    }
+}
+
+/// Get the version from a `software[n]` metadata line if this lists Casanovo
+fn software_version(line: &str) -> Option<String> {
+    let mut fields = line.trim_end().splitn(3, '\t');
+    (fields.next() == Some("MTD")).then_some(())?;
+    let key = fields.next()?.to_ascii_lowercase();
+    (key.starts_with("software[") && key.ends_with(']')).then_some(())?;
+    let term = CVTerm::from_str(fields.next()?).ok()?;
+    term.term
+        .eq_ignore_ascii_case("casanovo")
+        .then_some(term.comment)
+}
+
+impl From<CasanovoData> for IdentifiedPeptide {
+    fn from(value: CasanovoData) -> Self {
+        Self {
+            score: (!value.score.is_nan()).then(|| value.score.clamp(-1.0, 1.0)),
+            original_score: None,
+            local_confidence: Some(value.local_confidence.clone()),
+            metadata: MetaData::Casanovo(value),
+        }
+    }
+}
+
+/// All supported Casanovo versions
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum CasanovoVersion {
+    /// Casanovo version 3 and older
+    V3,
+    /// Casanovo version 4, also used for newer versions
+    #[default]
+    V4,
+}
+
+impl FromStr for CasanovoVersion {
+    type Err = CustomError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s
+            .trim()
+            .trim_start_matches(['v', 'V'])
+            .split('.')
+            .next()
+            .and_then(|major| major.parse::<usize>().ok())
+        {
+            Some(0..=3) => Ok(Self::V3),
+            // Newer versions are assumed to write the latest known format
+            Some(_) => Ok(Self::V4),
+            None => Err(CustomError::error(
+                "Invalid Casanovo version",
+                format!("The Casanovo version '{s}' could not be parsed, it should start with the major version number"),
+                Context::None,
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for CasanovoVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::V3 => "v3",
+                Self::V4 => "v4",
+            }
+        )
+    }
+}
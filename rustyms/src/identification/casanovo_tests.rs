@@ -0,0 +1,99 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::{BufRead, BufReader};
+
+use super::mztab_test::{
+    CASANOVO_V3_2_0_A, CASANOVO_V3_2_0_B, CASANOVO_V4_2_1, PRIDE_EXP_EXCERPT_AC_1643,
+};
+use crate::{
+    error::CustomError,
+    identification::{test_identified_peptide, CasanovoData, CasanovoVersion, IdentifiedPeptide},
+};
+
+#[test]
+fn casanovo_v3_2_0() {
+    assert_eq!(
+        open_file(
+            BufReader::new(CASANOVO_V3_2_0_A.as_bytes()),
+            CasanovoVersion::V3
+        )
+        .unwrap(),
+        41
+    );
+    assert_eq!(
+        open_file(
+            BufReader::new(CASANOVO_V3_2_0_B.as_bytes()),
+            CasanovoVersion::V3
+        )
+        .unwrap(),
+        41
+    );
+}
+
+#[test]
+fn casanovo_v4_2_1() {
+    assert_eq!(
+        open_file(
+            BufReader::new(CASANOVO_V4_2_1.as_bytes()),
+            CasanovoVersion::V4
+        )
+        .unwrap(),
+        39
+    );
+}
+
+#[test]
+fn casanovo_metadata() {
+    let first = CasanovoData::parse_reader(BufReader::new(CASANOVO_V4_2_1.as_bytes()), None)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(first.software_version, "4.2.1");
+    assert_eq!(first.id, 1);
+    assert_eq!(first.peptide.to_string(), "KGWASDEEAEK");
+    assert_eq!(first.local_confidence.len(), 11);
+    assert!((first.local_confidence[0] - 0.71591).abs() < f64::EPSILON);
+    assert!((first.score + 0.466_827_164_093_653_3).abs() < f64::EPSILON);
+    assert_eq!(first.z.value, 3);
+}
+
+#[test]
+fn not_casanovo() {
+    assert!(
+        CasanovoData::parse_reader(BufReader::new(PRIDE_EXP_EXCERPT_AC_1643.as_bytes()), None)
+            .is_err()
+    );
+}
+
+#[test]
+fn casanovo_versions() {
+    assert_eq!(
+        "3.2.0".parse::<CasanovoVersion>().unwrap(),
+        CasanovoVersion::V3
+    );
+    assert_eq!(
+        "v4.2.1".parse::<CasanovoVersion>().unwrap(),
+        CasanovoVersion::V4
+    );
+    assert_eq!(
+        "5.0.0".parse::<CasanovoVersion>().unwrap(),
+        CasanovoVersion::V4
+    );
+    assert!("dev".parse::<CasanovoVersion>().is_err());
+}
+
+/// Read all PSMs and check that they are of the given version
+/// # Errors
+/// If any PSM could not be parsed.
+fn open_file(reader: impl BufRead, version: CasanovoVersion) -> Result<usize, CustomError> {
+    let mut peptides = 0;
+    for read in CasanovoData::parse_reader(reader, None)? {
+        let read = read?;
+        assert_eq!(read.version, version);
+        let peptide: IdentifiedPeptide = read.into();
+        peptides += 1;
+
+        test_identified_peptide(&peptide, false, true).unwrap();
+    }
+    Ok(peptides)
+}
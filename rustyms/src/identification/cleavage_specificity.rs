@@ -0,0 +1,198 @@
+//! Cleavage site specificity statistics to verify the performance of a protease
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identification::{FastaData, IdentifiedPeptide, ReturnedPeptide},
+    AminoAcid, PositionFrequencyMatrix, Protease,
+};
+
+/// The number of residues on either side of a cleavage site that are recorded (P4 to P4')
+const WINDOW_SIDE: usize = 4;
+
+/// The observed cleavage specificity for a set of identified peptides mapped to their proteins,
+/// see [`CleavageSpecificity::new`].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleavageSpecificity {
+    /// The residue frequencies around all observed cleavage sites, from P4 to P4' (the site is
+    /// between position 3 and 4). Protein termini are not cleavage sites and are not included.
+    pub sites: PositionFrequencyMatrix,
+    /// The number of observed cleavage sites that match the protease
+    pub specific_sites: usize,
+    /// The number of observed cleavage sites that do not match the protease
+    pub non_specific_sites: usize,
+    /// The number of peptides with both termini matching the protease (or a protein terminus)
+    pub fully_specific: usize,
+    /// The number of peptides with only one terminus matching the protease (or a protein terminus)
+    pub semi_specific: usize,
+    /// The number of peptides with no terminus matching the protease
+    pub non_specific: usize,
+    /// The number of peptides with the given number of missed cleavages (missed cleavages, count)
+    pub missed_cleavages: Vec<(usize, usize)>,
+}
+
+impl CleavageSpecificity {
+    /// Determine the cleavage specificity for the given peptides mapped to their proteins, as
+    /// given by [`crate::identification::Experiment::map_proteins`]. Each peptide is only
+    /// counted once, at the first location in the first protein where its sequence occurs.
+    /// Peptides are matched on their amino acid sequence, ignoring modifications, and
+    /// peptides that cannot be represented as a linear peptide are ignored.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, identification::*};
+    /// # fn main() -> Result<(), rustyms::error::CustomError> {
+    /// let experiment = Experiment::new()
+    ///     .identification_file("src/identification/test_files/sage_v0_14.tsv")
+    ///     .fasta("data/experiment/proteins.fasta");
+    /// let peptides = experiment.identified_peptides(None)?;
+    /// let proteins = experiment.map_proteins(&peptides)?;
+    /// // GluC cleaves C terminal of glutamic acid
+    /// let protease = Protease::new(&[AminoAcid::GlutamicAcid], &[]);
+    /// let specificity = CleavageSpecificity::new(&proteins, &protease);
+    /// assert!(specificity.specific_site_rate().unwrap() > 0.5);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(proteins: &[(FastaData, Vec<&IdentifiedPeptide>)], protease: &Protease) -> Self {
+        let mut seen: HashSet<*const IdentifiedPeptide> = HashSet::new();
+        let mut sites = PositionFrequencyMatrix::default();
+        let mut missed_cleavages: BTreeMap<usize, usize> = BTreeMap::new();
+        let (mut specific_sites, mut non_specific_sites) = (0, 0);
+        let (mut fully_specific, mut semi_specific, mut non_specific) = (0, 0, 0);
+
+        for (protein, peptides) in proteins {
+            let sequence: Vec<AminoAcid> = protein
+                .peptide()
+                .sequence()
+                .iter()
+                .map(|s| s.aminoacid.aminoacid())
+                .collect();
+            let text: String = sequence.iter().map(|aa| aa.char()).collect();
+            let cut_sites: HashSet<usize> =
+                if protein.peptide().len() > protease.n_term.len() + protease.c_term.len() {
+                    protease
+                        .match_locations(protein.peptide().sequence())
+                        .into_iter()
+                        .collect()
+                } else {
+                    HashSet::new()
+                };
+
+            for peptide in peptides {
+                let Some(linear) = peptide.peptide().and_then(ReturnedPeptide::peptide) else {
+                    continue;
+                };
+                let peptide_text: String = linear
+                    .sequence()
+                    .iter()
+                    .map(|s| s.aminoacid.char())
+                    .collect();
+                if peptide_text.is_empty() || seen.contains(&std::ptr::addr_of!(**peptide)) {
+                    continue;
+                }
+                let Some(start) = text.find(&peptide_text) else {
+                    continue;
+                };
+                seen.insert(std::ptr::addr_of!(**peptide));
+                let end = start + peptide_text.len();
+
+                let mut specific_termini = 0;
+                for site in [start, end] {
+                    if site == 0 || site == sequence.len() {
+                        specific_termini += 1;
+                        continue;
+                    }
+                    if cut_sites.contains(&site) {
+                        specific_termini += 1;
+                        specific_sites += 1;
+                    } else {
+                        non_specific_sites += 1;
+                    }
+                    sites.add_window((site..site + 2 * WINDOW_SIDE).map(|index| {
+                        index
+                            .checked_sub(WINDOW_SIDE)
+                            .and_then(|index| sequence.get(index).copied())
+                    }));
+                }
+                match specific_termini {
+                    2 => fully_specific += 1,
+                    1 => semi_specific += 1,
+                    _ => non_specific += 1,
+                }
+                *missed_cleavages
+                    .entry(
+                        cut_sites
+                            .iter()
+                            .filter(|site| (start + 1..end).contains(site))
+                            .count(),
+                    )
+                    .or_default() += 1;
+            }
+        }
+
+        Self {
+            sites,
+            specific_sites,
+            non_specific_sites,
+            fully_specific,
+            semi_specific,
+            non_specific,
+            missed_cleavages: missed_cleavages.into_iter().collect(),
+        }
+    }
+
+    /// The fraction of the observed cleavage sites that match the protease, `None` if no
+    /// cleavage sites were observed
+    pub fn specific_site_rate(&self) -> Option<f64> {
+        let total = self.specific_sites + self.non_specific_sites;
+        (total > 0).then(|| self.specific_sites as f64 / total as f64)
+    }
+
+    /// The fraction of peptides that has at least one missed cleavage, `None` if there are no
+    /// peptides
+    pub fn missed_cleavage_rate(&self) -> Option<f64> {
+        let (with, total) = self
+            .missed_cleavages
+            .iter()
+            .fold((0, 0), |(with, total), (n, count)| {
+                (if *n > 0 { with + count } else { with }, total + count)
+            });
+        (total > 0).then(|| with as f64 / total as f64)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::Experiment;
+
+    #[test]
+    fn gluc() {
+        let experiment = Experiment::new()
+            .identification_file("src/identification/test_files/sage_v0_14.tsv")
+            .fasta("data/experiment/proteins.fasta");
+        let peptides = experiment.identified_peptides(None).unwrap();
+        let proteins = experiment.map_proteins(&peptides).unwrap();
+        let gluc =
+            CleavageSpecificity::new(&proteins, &Protease::new(&[AminoAcid::GlutamicAcid], &[]));
+        let trypsin =
+            CleavageSpecificity::new(&proteins, &Protease::new(&[AminoAcid::Lysine], &[]));
+        assert_eq!(gluc.sites.len(), 8);
+        assert_eq!(gluc.sites, trypsin.sites);
+        assert_eq!(
+            gluc.fully_specific + gluc.semi_specific + gluc.non_specific,
+            gluc.missed_cleavages.iter().map(|(_, n)| n).sum::<usize>()
+        );
+        assert_eq!(
+            gluc.sites.sequences(),
+            gluc.specific_sites + gluc.non_specific_sites
+        );
+        assert!(gluc.specific_site_rate() > trypsin.specific_site_rate());
+        // The residue before the cleavage site (P1) is mostly glutamic acid
+        assert!(gluc.sites.frequency(3, AminoAcid::GlutamicAcid) > 0.5);
+        assert!(gluc.missed_cleavage_rate().is_some());
+    }
+}
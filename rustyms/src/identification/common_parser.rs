@@ -10,6 +10,7 @@ macro_rules! format_family {
      #[doc = $data_doc:expr]
      $data:ident,
      $version:ident, $versions:expr, $separator:expr, $header:expr;
+     $(csv_options { $($csv_item:item)* })?
      required { $($(#[doc = $rdoc:expr])? $rname:ident: $rtyp:ty, $rf:expr;)* }
      optional { $($(#[doc = $odoc:expr])? $oname:ident: $otyp:ty, $of:expr;)*}
      $($post_process:item)?) => {
@@ -57,7 +58,7 @@ macro_rules! format_family {
                 path: impl AsRef<std::path::Path>,
                 custom_database: Option<&crate::ontologies::CustomDatabase>,
             ) -> Result<BoxedIdentifiedPeptideIter<Self>, CustomError> {
-                parse_csv(path, $separator, $header).and_then(|lines| {
+                <Self as crate::identification::CsvIdentifiedPeptideSource>::csv_lines(crate::helper_functions::open_reader(path)?).and_then(|lines| {
                     let mut i = Self::parse_many::<Box<dyn Iterator<Item = Result<Self::Source, CustomError>>>>(
                         Box::new(lines), custom_database);
                     if let Some(Err(e)) = i.peek() {
@@ -71,7 +72,7 @@ macro_rules! format_family {
                 reader: impl std::io::Read + 'a,
                 custom_database: Option<&'a crate::ontologies::CustomDatabase>,
             ) -> Result<BoxedIdentifiedPeptideIter<'a, Self>, CustomError> {
-                crate::csv::parse_csv_raw(reader, $separator, $header).and_then(move |lines| {
+                <Self as crate::identification::CsvIdentifiedPeptideSource>::csv_lines(reader).and_then(move |lines| {
                     let mut i = Self::parse_many::<Box<dyn Iterator<Item = Result<Self::Source, CustomError>>>>(
                         Box::new(lines), custom_database);
                     if let Some(Err(e)) = i.peek() {
@@ -81,6 +82,24 @@ macro_rules! format_family {
                     }
                 })
             }
+            fn parse_file_with_report(
+                path: impl AsRef<std::path::Path>,
+                custom_database: Option<&crate::ontologies::CustomDatabase>,
+                error_budget: Option<usize>,
+            ) -> Result<crate::identification::ParseReport<Self>, CustomError> {
+                <Self as crate::identification::CsvIdentifiedPeptideSource>::csv_lines(crate::helper_functions::open_reader(path)?).map(|lines| {
+                    crate::identification::ParseReport::from_csv(lines, custom_database, error_budget)
+                })
+            }
+            fn parse_reader_with_report(
+                reader: impl std::io::Read,
+                custom_database: Option<&crate::ontologies::CustomDatabase>,
+                error_budget: Option<usize>,
+            ) -> Result<crate::identification::ParseReport<Self>, CustomError> {
+                <Self as crate::identification::CsvIdentifiedPeptideSource>::csv_lines(reader).map(|lines| {
+                    crate::identification::ParseReport::from_csv(lines, custom_database, error_budget)
+                })
+            }
             #[allow(clippy::redundant_closure_call)] // Macro magic
             fn parse_specific(source: &Self::Source, format: &$format, custom_database: Option<&crate::ontologies::CustomDatabase>) -> Result<Self, CustomError> {
                 #[allow(unused_imports)]
@@ -95,6 +114,14 @@ macro_rules! format_family {
             }
             $($post_process)?
         }
+
+        impl crate::identification::CsvIdentifiedPeptideSource for $data {
+            const SEPARATOR: u8 = $separator;
+            fn header() -> Option<Vec<String>> {
+                $header
+            }
+            $($($csv_item)*)?
+        }
     };
 }
 
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::{Location, OptionalColumn, OptionalLocation},
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     modification::Ontology,
     AminoAcid, BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
 };
@@ -80,6 +80,7 @@ impl From<DeepNovoFamilyData> for IdentifiedPeptide {
     fn from(value: DeepNovoFamilyData) -> Self {
         Self {
             score: value.score.map(|score| (2.0 / (1.0 + (-score).exp()))),
+            original_score: None,
             local_confidence: value
                 .local_confidence
                 .as_ref()
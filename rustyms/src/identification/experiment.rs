@@ -0,0 +1,414 @@
+//! Group all files that make up a single experiment
+
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    identification::{
+        open_identified_peptides_file, CleavageSpecificity, FastaData, IdentifiedPeptide,
+        RunQualityCollector, RunQualityReport, SpectrumId, SpectrumIds,
+    },
+    ontologies::CustomDatabase,
+    rawfile::mgf::{self, IndexedMgf},
+    Protease, RawSpectrum,
+};
+
+/// An experiment consisting of multiple raw files, identification files, and optionally a FASTA
+/// database. This groups all paths so that multi-run analyses can link identified peptides back
+/// to their spectra and proteins without keeping track of all files separately.
+///
+/// Raw files are linked to the raw file names reported by the identification files based on the
+/// file stem, so `run_01.mgf` is linked to `run_01.raw` and `run_01.mzML`. Currently only MGF
+/// raw files can be read.
+///
+/// ```rust
+/// # use rustyms::identification::*;
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// let experiment = Experiment::new()
+///     .raw_file("data/experiment/20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mgf")
+///     .identification_file("src/identification/test_files/sage_v0_14.tsv")
+///     .fasta("data/experiment/proteins.fasta");
+/// let peptides = experiment.identified_peptides(None)?;
+/// assert_eq!(peptides.len(), 19);
+/// assert_eq!(experiment.link_spectra(&peptides)?.len(), 2);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Experiment {
+    raw_files: Vec<PathBuf>,
+    identification_files: Vec<PathBuf>,
+    fasta: Option<PathBuf>,
+}
+
+/// An identified peptide linked to its spectra
+#[derive(Clone, Debug, PartialEq)]
+pub struct LinkedSpectra<'a> {
+    /// The identified peptide
+    pub peptide: &'a IdentifiedPeptide,
+    /// The raw file the spectra were read from
+    pub raw_file: PathBuf,
+    /// The spectra, in the order of the spectrum references of the peptide
+    pub spectra: Vec<RawSpectrum>,
+}
+
+impl Experiment {
+    /// Create a new empty experiment
+    pub const fn new() -> Self {
+        Self {
+            raw_files: Vec::new(),
+            identification_files: Vec::new(),
+            fasta: None,
+        }
+    }
+
+    /// Add a raw file
+    #[must_use]
+    pub fn raw_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.raw_files.push(path.into());
+        self
+    }
+
+    /// Add an identification file, any format supported by [`open_identified_peptides_file`] can
+    /// be used
+    #[must_use]
+    pub fn identification_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.identification_files.push(path.into());
+        self
+    }
+
+    /// Set the FASTA database
+    #[must_use]
+    pub fn fasta(self, path: impl Into<PathBuf>) -> Self {
+        Self {
+            fasta: Some(path.into()),
+            ..self
+        }
+    }
+
+    /// Get all raw files
+    pub fn raw_files(&self) -> &[PathBuf] {
+        &self.raw_files
+    }
+
+    /// Get all identification files
+    pub fn identification_files(&self) -> &[PathBuf] {
+        &self.identification_files
+    }
+
+    /// Get the FASTA database, if set
+    pub fn fasta_file(&self) -> Option<&Path> {
+        self.fasta.as_deref()
+    }
+
+    /// Find the raw file in this experiment with the same file stem as the given (raw file) name.
+    /// If no name is given and there is only a single raw file that one is returned.
+    pub fn find_raw_file(&self, name: Option<&Path>) -> Option<&Path> {
+        match name {
+            Some(name) => {
+                let stem = name.file_stem()?;
+                self.raw_files
+                    .iter()
+                    .find(|file| file.file_stem() == Some(stem))
+                    .map(PathBuf::as_path)
+            }
+            None if self.raw_files.len() == 1 => Some(&self.raw_files[0]),
+            None => None,
+        }
+    }
+
+    /// Read all identified peptides from all identification files, in the order the files were
+    /// added.
+    ///
+    /// # Errors
+    /// If any of the files could not be opened or any peptide could not be parsed.
+    pub fn identified_peptides(
+        &self,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Vec<IdentifiedPeptide>, CustomError> {
+        let mut peptides = Vec::new();
+        for path in &self.identification_files {
+            for peptide in open_identified_peptides_file(path, custom_database)? {
+                peptides.push(peptide?);
+            }
+        }
+        Ok(peptides)
+    }
+
+    /// Link all given peptides to their spectra. Peptides for which the raw file or the spectra
+    /// are not found are left out. Spectrum indices are used as index in the raw file, native ids
+    /// are matched on the title of the spectrum, or if the native id contains a `scan=` part on
+    /// the scan number. Retention time references are not supported.
+    ///
+    /// # Errors
+    /// If any raw file could not be opened or any spectrum could not be read.
+    pub fn link_spectra<'a>(
+        &self,
+        peptides: &'a [IdentifiedPeptide],
+    ) -> Result<Vec<LinkedSpectra<'a>>, CustomError> {
+        let mut files: Vec<(&Path, IndexedMgf<_>)> = Vec::new();
+        let mut linked = Vec::new();
+        for peptide in peptides {
+            let references = match peptide.scans() {
+                SpectrumIds::None => Vec::new(),
+                SpectrumIds::FileNotKnown(ids) => vec![(None, ids)],
+                SpectrumIds::FileKnown(files) => files
+                    .into_iter()
+                    .map(|(file, ids)| (Some(file), ids))
+                    .collect(),
+            };
+            for (name, ids) in references {
+                let Some(raw_file) = self.find_raw_file(name.as_deref()) else {
+                    continue;
+                };
+                let index = if let Some(index) = files.iter().position(|(f, _)| *f == raw_file) {
+                    index
+                } else {
+                    files.push((raw_file, open_raw_file(raw_file)?));
+                    files.len() - 1
+                };
+                let reader = &mut files[index].1;
+                let mut spectra = Vec::new();
+                for id in ids {
+                    let spectrum = match &id {
+                        SpectrumId::Index(index) => reader.get_by_index(*index),
+                        SpectrumId::Native(native) => reader.get_by_title(native).or_else(|| {
+                            native
+                                .split(' ')
+                                .find_map(|part| part.strip_prefix("scan="))
+                                .and_then(|scan| scan.parse().ok())
+                                .and_then(|scan| reader.get_by_scan(scan))
+                        }),
+                        SpectrumId::RetentionTime(_) => None,
+                    };
+                    if let Some(spectrum) = spectrum {
+                        spectra.push(spectrum?);
+                    }
+                }
+                if !spectra.is_empty() {
+                    linked.push(LinkedSpectra {
+                        peptide,
+                        raw_file: raw_file.to_path_buf(),
+                        spectra,
+                    });
+                }
+            }
+        }
+        Ok(linked)
+    }
+
+    /// Write only the spectra referenced by the given peptides as a single MGF file, to share
+    /// minimal examples or evidence spectra without the full raw files. The spectra are found as
+    /// described in [`Self::link_spectra`] and every spectrum is written only once, even if it
+    /// is referenced by multiple peptides. If a spectrum does not have a sequence yet the
+    /// sequence of the first peptide referencing it is added. Returns the number of written
+    /// spectra.
+    ///
+    /// # Errors
+    /// If any raw file could not be opened, any spectrum could not be read, or the writer could
+    /// not be written to.
+    pub fn write_spectra_subset(
+        &self,
+        peptides: &[IdentifiedPeptide],
+        writer: impl Write,
+    ) -> Result<usize, CustomError> {
+        let mut seen = HashSet::new();
+        let mut spectra = Vec::new();
+        for linked in self.link_spectra(peptides)? {
+            for mut spectrum in linked.spectra {
+                let key = (
+                    linked.raw_file.clone(),
+                    spectrum.title.clone(),
+                    spectrum.raw_scan_number,
+                    spectrum.rt.map(|rt| rt.value.to_bits()),
+                );
+                if seen.insert(key) {
+                    if spectrum.sequence.is_none() {
+                        spectrum.sequence = linked.peptide.peptide().map(|p| p.to_string());
+                    }
+                    spectra.push(spectrum);
+                }
+            }
+        }
+        mgf::write(writer, &spectra)?;
+        Ok(spectra.len())
+    }
+
+    /// Determine the cleavage specificity of the given protease for the given peptides, see
+    /// [`CleavageSpecificity::new`].
+    ///
+    /// # Errors
+    /// If no FASTA database is set or it could not be parsed.
+    pub fn cleavage_specificity(
+        &self,
+        peptides: &[IdentifiedPeptide],
+        protease: &Protease,
+    ) -> Result<CleavageSpecificity, CustomError> {
+        Ok(CleavageSpecificity::new(
+            &self.map_proteins(peptides)?,
+            protease,
+        ))
+    }
+
+    /// Map all given peptides to the proteins in the FASTA database that contain their sequence.
+    /// Only proteins with at least one peptide are returned, in the order of the database. The
+    /// peptides are matched on their amino acid sequence, ignoring any modifications.
+    ///
+    /// # Errors
+    /// If no FASTA database is set or it could not be parsed.
+    pub fn map_proteins<'a>(
+        &self,
+        peptides: &'a [IdentifiedPeptide],
+    ) -> Result<Vec<(FastaData, Vec<&'a IdentifiedPeptide>)>, CustomError> {
+        let path = self.fasta.as_ref().ok_or_else(|| {
+            CustomError::error(
+                "Could not map proteins",
+                "No FASTA database is set for this experiment",
+                Context::none(),
+            )
+        })?;
+        let sequences = peptides
+            .iter()
+            .map(|peptide| {
+                peptide.peptide().map_or_else(Vec::new, |returned| {
+                    returned
+                        .compound_peptidoform()
+                        .peptidoforms()
+                        .map(|peptidoform| {
+                            peptidoform
+                                .sequence()
+                                .iter()
+                                .map(|s| s.aminoacid.char())
+                                .collect::<String>()
+                        })
+                        .collect()
+                })
+            })
+            .collect::<Vec<Vec<String>>>();
+        Ok(FastaData::parse_file(path)?
+            .into_iter()
+            .filter_map(|protein| {
+                let sequence = protein
+                    .peptide()
+                    .sequence()
+                    .iter()
+                    .map(|s| s.aminoacid.char())
+                    .collect::<String>();
+                let matched = peptides
+                    .iter()
+                    .zip(&sequences)
+                    .filter(|(_, peptide_sequences)| {
+                        peptide_sequences
+                            .iter()
+                            .any(|s| !s.is_empty() && sequence.contains(s.as_str()))
+                    })
+                    .map(|(peptide, _)| peptide)
+                    .collect::<Vec<_>>();
+                (!matched.is_empty()).then_some((protein, matched))
+            })
+            .collect())
+    }
+
+    /// Get the quality control reports for all runs in all identification files, see
+    /// [`RunQualityCollector`]. The peptides are streamed so the files do not have to fit in
+    /// memory at once.
+    ///
+    /// # Errors
+    /// If any of the files could not be opened or any peptide could not be parsed.
+    pub fn quality_control(
+        &self,
+        custom_database: Option<&CustomDatabase>,
+        protease: Option<&Protease>,
+    ) -> Result<Vec<RunQualityReport>, CustomError> {
+        let mut collector = RunQualityCollector::new(protease);
+        for path in &self.identification_files {
+            for peptide in open_identified_peptides_file(path, custom_database)? {
+                collector.add(&peptide?);
+            }
+        }
+        Ok(collector.reports())
+    }
+}
+
+/// Open a raw file for random access
+/// # Errors
+/// If the file is not a supported raw file or could not be opened.
+fn open_raw_file(
+    path: &Path,
+) -> Result<IndexedMgf<std::io::BufReader<std::fs::File>>, CustomError> {
+    if path
+        .extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("mgf"))
+    {
+        IndexedMgf::open(path)
+    } else {
+        Err(CustomError::error(
+            "Unsupported raw file",
+            "Only MGF files can be used as raw files in an experiment",
+            Context::show(path.display()),
+        ))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::spectrum::PeakSpectrum;
+
+    #[test]
+    fn experiment() {
+        let experiment = Experiment::new()
+            .raw_file("data/experiment/20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mgf")
+            .identification_file("src/identification/test_files/sage_v0_14.tsv")
+            .fasta("data/experiment/proteins.fasta");
+        let peptides = experiment.identified_peptides(None).unwrap();
+        assert_eq!(peptides.len(), 19);
+
+        let linked = experiment.link_spectra(&peptides).unwrap();
+        assert_eq!(linked.len(), 2);
+        assert_eq!(linked[0].spectra.len(), 1);
+        assert_eq!(linked[0].spectra[0].raw_scan_number, Some(13947));
+        assert_eq!(linked[1].spectra[0].raw_scan_number, Some(26712));
+
+        let proteins = experiment.map_proteins(&peptides).unwrap();
+        assert_eq!(proteins.len(), 2);
+        assert_eq!(proteins[0].0.identifier().accession(), "anti-FLAG-M2_HC");
+        assert_eq!(proteins[1].1.len(), 9);
+
+        let reports = experiment.quality_control(None, None).unwrap();
+        assert_eq!(reports.len(), 3);
+        assert!(Experiment::new().map_proteins(&peptides).is_err());
+    }
+
+    #[test]
+    fn spectra_subset() {
+        let experiment = Experiment::new()
+            .raw_file("data/experiment/20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mgf")
+            .identification_file("src/identification/test_files/sage_v0_14.tsv");
+        let peptides = experiment.identified_peptides(None).unwrap();
+        let mut buffer = Vec::new();
+        let written = experiment
+            .write_spectra_subset(&[peptides.clone(), peptides.clone()].concat(), &mut buffer)
+            .unwrap();
+        assert_eq!(written, 2);
+        let subset = mgf::open_raw(buffer.as_slice()).unwrap();
+        assert_eq!(subset.len(), 2);
+        let linked = experiment.link_spectra(&peptides).unwrap();
+        for (spectrum, linked) in subset.iter().zip(&linked) {
+            assert_eq!(spectrum.raw_scan_number, linked.spectra[0].raw_scan_number);
+            assert_eq!(
+                spectrum.spectrum().len(),
+                linked.spectra[0].spectrum().len()
+            );
+            assert_eq!(spectrum.mass, linked.spectra[0].mass);
+            assert!(spectrum.sequence.is_some());
+        }
+    }
+}
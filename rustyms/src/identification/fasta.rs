@@ -1,17 +1,22 @@
 use crate::{
     error::{Context, CustomError},
-    helper_functions::explain_number_error,
+    helper_functions::{check_extension, explain_number_error, open_file},
     identification::{IdentifiedPeptide, MetaData},
+    modification::{Ontology, SimpleModification},
     peptidoform::{AnnotatedPeptide, Annotation, Region, SemiAmbiguous},
-    AminoAcid, Peptidoform, SequenceElement,
+    placement_rule::ProteinTermini,
+    AminoAcid, DigestedPeptidoform, Peptidoform, Protease, SequenceElement,
 };
+use flate2::bufread::GzDecoder;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use std::{
-    io::{BufRead, BufReader},
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom, Write},
     num::ParseIntError,
     ops::Range,
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 
@@ -27,6 +32,88 @@ pub struct FastaData {
     peptide: Peptidoform<SemiAmbiguous>,
     regions: Vec<(Region, usize)>,
     annotations: Vec<(Annotation, usize)>,
+    variants: Vec<PeffVariant>,
+    modifications: Vec<PeffModification>,
+    processed: Vec<PeffProcessed>,
+}
+
+/// A sequence variant as defined in a PEFF header (`\VariantSimple` or `\VariantComplex`)
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub struct PeffVariant {
+    /// The replaced region of the protein, zero based
+    pub location: Range<usize>,
+    /// The replacement sequence, empty for a deletion
+    pub replacement: Vec<AminoAcid>,
+}
+
+/// A known modification as defined in a PEFF header (`\ModResUnimod`, `\ModResPsi`, or `\ModRes`)
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub struct PeffModification {
+    /// The modified position in the protein, zero based
+    pub position: usize,
+    /// The accession as written in the header, e.g. `UNIMOD:35`, empty if not given
+    pub accession: String,
+    /// The name as written in the header
+    pub name: String,
+    /// The modification, if it could be found in the referenced ontology
+    pub modification: Option<SimpleModification>,
+}
+
+/// A processed region as defined in a PEFF header (`\Processed`), e.g. a signal peptide or mature
+/// chain
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize, Hash)]
+pub struct PeffProcessed {
+    /// The region of the protein, zero based
+    pub location: Range<usize>,
+    /// The accession as written in the header, e.g. `PEFF:0001021`, empty if not given
+    pub accession: String,
+    /// The name as written in the header, e.g. `signal peptide`
+    pub name: String,
+}
+
+/// The UniProt protein existence level (`PE` in UniProt FASTA headers)
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize, Hash)]
+pub enum ProteinExistence {
+    /// 1: Experimental evidence at protein level
+    Protein,
+    /// 2: Experimental evidence at transcript level
+    Transcript,
+    /// 3: Protein inferred from homology
+    Homology,
+    /// 4: Protein predicted
+    Predicted,
+    /// 5: Protein uncertain
+    Uncertain,
+}
+
+impl FromStr for ProteinExistence {
+    type Err = ();
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "1" => Ok(Self::Protein),
+            "2" => Ok(Self::Transcript),
+            "3" => Ok(Self::Homology),
+            "4" => Ok(Self::Predicted),
+            "5" => Ok(Self::Uncertain),
+            _ => Err(()),
+        }
+    }
+}
+
+impl std::fmt::Display for ProteinExistence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Protein => "Experimental evidence at protein level",
+                Self::Transcript => "Experimental evidence at transcript level",
+                Self::Homology => "Protein inferred from homology",
+                Self::Predicted => "Protein predicted",
+                Self::Uncertain => "Protein uncertain",
+            }
+        )
+    }
 }
 
 impl AnnotatedPeptide for FastaData {
@@ -429,6 +516,38 @@ impl FastaData {
             .map(|(k, v)| (&self.full_header[k.clone()], &self.full_header[v.clone()]))
     }
 
+    /// Get the value for the given tag, the first matching key is used
+    fn tag(&self, keys: &[&str]) -> Option<&str> {
+        keys.iter()
+            .find_map(|key| self.tags().find(|(k, _)| k == key).map(|(_, v)| v))
+    }
+
+    /// Get the organism name (`OS` in UniProt headers, `TaxName` in PEFF headers)
+    pub fn organism(&self) -> Option<&str> {
+        self.tag(&["OS", "TaxName"])
+    }
+
+    /// Get the NCBI taxonomy identifier of the organism (`OX` in UniProt headers, `NcbiTaxId` in
+    /// PEFF headers)
+    pub fn organism_id(&self) -> Option<usize> {
+        self.tag(&["OX", "NcbiTaxId"]).and_then(|v| v.parse().ok())
+    }
+
+    /// Get the gene name (`GN` in UniProt headers, `GName` in PEFF headers)
+    pub fn gene(&self) -> Option<&str> {
+        self.tag(&["GN", "GName"])
+    }
+
+    /// Get the protein existence level (`PE`)
+    pub fn protein_existence(&self) -> Option<ProteinExistence> {
+        self.tag(&["PE"]).and_then(|v| v.parse().ok())
+    }
+
+    /// Get the sequence version (`SV`)
+    pub fn sequence_version(&self) -> Option<usize> {
+        self.tag(&["SV"]).and_then(|v| v.parse().ok())
+    }
+
     /// Get the full header line
     pub fn header(&self) -> &str {
         &self.full_header
@@ -439,21 +558,121 @@ impl FastaData {
         &self.peptide
     }
 
-    /// Parse a single fasta file
+    /// Get the PEFF sequence variants (`\VariantSimple` and `\VariantComplex`)
+    pub fn variants(&self) -> &[PeffVariant] {
+        &self.variants
+    }
+
+    /// Get the PEFF known modifications (`\ModResUnimod`, `\ModResPsi`, and `\ModRes`)
+    pub fn known_modifications(&self) -> &[PeffModification] {
+        &self.modifications
+    }
+
+    /// Get the PEFF processed regions (`\Processed`)
+    pub fn processed(&self) -> &[PeffProcessed] {
+        &self.processed
+    }
+
+    /// Get the length of the signal peptide, if the first region is annotated as signal peptide
+    /// (`SP` in the `REGIONS` tag) or if a PEFF processed region named `signal peptide` starts at
+    /// the N terminus
+    pub fn signal_peptide(&self) -> Option<usize> {
+        self.regions
+            .first()
+            .filter(|(region, _)| *region == Region::SignalPeptide)
+            .map(|(_, length)| *length)
+            .or_else(|| {
+                self.processed
+                    .iter()
+                    .find(|p| {
+                        p.location.start == 0 && p.name.eq_ignore_ascii_case("signal peptide")
+                    })
+                    .map(|p| p.location.end)
+            })
+    }
+
+    /// Get the protein sequence with each of the PEFF sequence variants applied, one variant at a
+    /// time, in the order of [`Self::variants`]. These can be digested or aligned to generate
+    /// candidate peptidoforms for variant peptides.
+    pub fn variant_peptides(&self) -> impl Iterator<Item = Peptidoform<SemiAmbiguous>> + '_ {
+        self.variants.iter().map(|variant| {
+            let sequence = self.peptide.sequence();
+            sequence[..variant.location.start]
+                .iter()
+                .cloned()
+                .chain(
+                    variant
+                        .replacement
+                        .iter()
+                        .map(|aa| SequenceElement::new((*aa).into(), None)),
+                )
+                .chain(sequence[variant.location.end..].iter().cloned())
+                .collect::<Vec<_>>()
+                .into()
+        })
+    }
+
+    /// Get all PEFF known modifications that could be resolved and are located in the given
+    /// region of the protein, with their position relative to the start of the region. These can
+    /// be used to place the known modifications on a peptide from this protein.
+    pub fn known_modifications_in(
+        &self,
+        range: Range<usize>,
+    ) -> impl Iterator<Item = (usize, &SimpleModification)> + '_ {
+        self.modifications.iter().filter_map(move |m| {
+            range
+                .contains(&m.position)
+                .then_some(m.modification.as_ref())
+                .flatten()
+                .map(|modification| (m.position - range.start, modification))
+        })
+    }
+
+    /// Digest this protein with the given protease and the given maximal number of missed
+    /// cleavages. If the protein has a [signal peptide](Self::signal_peptide) this is removed
+    /// before digestion, so the N terminus of the mature protein is seen as the protein N
+    /// terminus. The location of every peptide is given in the full protein sequence, including
+    /// the signal peptide. For every peptide it is returned which termini are protein termini,
+    /// which can be used to place protein terminal modifications, see
+    /// [`Peptidoform::add_fixed_modification`].
+    pub fn digest(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+    ) -> Vec<(DigestedPeptidoform<SemiAmbiguous>, ProteinTermini)> {
+        let offset = self.signal_peptide().unwrap_or_default();
+        if offset >= self.peptide.len() {
+            return Vec::new();
+        }
+        self.peptide
+            .sub_peptide(offset..)
+            .digest(protease, max_missed_cleavages)
+            .into_iter()
+            .map(|peptide| {
+                let termini = ProteinTermini {
+                    n_term: peptide.start == 0,
+                    c_term: peptide.end == self.peptide.len() - offset,
+                };
+                (
+                    DigestedPeptidoform {
+                        start: peptide.start + offset,
+                        end: peptide.end + offset,
+                        ..peptide
+                    },
+                    termini,
+                )
+            })
+            .collect()
+    }
+
+    /// Parse a single fasta file, see [`Self::stream_file`] to read the proteins one at a time
+    /// for large databases.
     /// # Errors
     /// A custom error when it is not a valid fasta file
     pub fn parse_file(path: impl AsRef<Path>) -> Result<Vec<Self>, CustomError> {
-        let path = path.as_ref();
-        let file = std::fs::File::open(path).map_err(|_| {
-            CustomError::error(
-                "Failed reading fasta file",
-                "Error occurred while opening the file",
-                Context::show(path.to_string_lossy()),
-            )
-        })?;
-        let reader = BufReader::new(file);
-        Self::parse_reader(reader, Some(path))
+        Self::stream_file(path)?.collect()
     }
+
     /// Parse a single fasta file from a reader
     /// # Errors
     /// A custom error when it is not a valid fasta file
@@ -461,65 +680,90 @@ impl FastaData {
         reader: impl BufRead,
         path: Option<&Path>,
     ) -> Result<Vec<Self>, CustomError> {
-        let mut sequences = Vec::new();
-        let mut last_header = None;
-        let mut last_sequence: Vec<SequenceElement<SemiAmbiguous>> = Vec::new();
+        FastaReader::new(reader, path).collect()
+    }
 
-        for (line_index, line) in reader.lines().enumerate() {
-            let line = line.map_err(|_| {
-                CustomError::error(
-                    "Failed reading fasta file",
-                    format!("Error occurred while reading line {}", line_index + 1),
-                    path.map_or(Context::None, |p| Context::show(p.to_string_lossy())),
+    /// Open a fasta file and return an iterator that lazily reads one protein at a time. This
+    /// keeps the memory usage constant regardless of the size of the database. It will
+    /// uncompress gzipped files automatically. See [`IndexedFasta`] for random access.
+    /// # Errors
+    /// If the file could not be opened. Any error in reading a protein is returned by the
+    /// iterator, see [`FastaReader`].
+    pub fn stream_file(
+        path: impl AsRef<Path>,
+    ) -> Result<FastaReader<Box<dyn BufRead>>, CustomError> {
+        let path = path.as_ref();
+        let file = open_file(path).map_err(|_| {
+            CustomError::error(
+                "Failed reading fasta file",
+                "Error occurred while opening the file",
+                Context::show(path.to_string_lossy()),
+            )
+        })?;
+        let reader: Box<dyn BufRead> = if check_extension(path, "gz") {
+            Box::new(BufReader::new(GzDecoder::new(BufReader::new(file))))
+        } else {
+            Box::new(BufReader::new(file))
+        };
+        Ok(FastaReader::new(reader, Some(path)))
+    }
+
+    /// Write the given proteins as a FASTA file. The header is written as it was read, so all
+    /// tags (including `REGIONS` and `ANNOTATIONS`) are retained and the written file can be read
+    /// back with [`Self::parse_reader`]. The sequence is wrapped at 60 residues per line.
+    /// # Errors
+    /// If the writer could not be written to.
+    pub fn write<'a>(
+        mut writer: impl Write,
+        proteins: impl IntoIterator<Item = &'a Self>,
+    ) -> Result<(), CustomError> {
+        let error =
+            |error| CustomError::error("Could not write fasta file", error, Context::none());
+        for protein in proteins {
+            let header = protein.full_header.trim_end();
+            let header = header.strip_prefix('>').unwrap_or(header);
+            writeln!(writer, ">{header}").map_err(error)?;
+            for line in &protein.peptide.sequence().iter().chunks(60) {
+                writeln!(
+                    writer,
+                    "{}",
+                    line.map(|s| s.aminoacid.char()).collect::<String>()
                 )
-            })?;
-            #[allow(clippy::manual_strip)]
-            if line.starts_with('>') {
-                if let Some(last_header) = last_header {
-                    sequences.push(
-                        Self {
-                            peptide: last_sequence.into(),
-                            ..last_header
-                        }
-                        .validate()?,
-                    );
-                }
-                last_header = Some(Self::parse_header(line_index, line)?);
-                last_sequence = Vec::new();
-            } else {
-                last_sequence.extend(
-                    line.char_indices()
-                        .filter(|(_, c)| !c.is_ascii_whitespace())
-                        .map(|(i, c)| {
-                            c.try_into()
-                                .map(|aa: AminoAcid| SequenceElement::new(aa.into(), None))
-                                .map_err(|()| {
-                                    CustomError::error(
-                                        "Failed reading fasta file",
-                                        "Character is not an amino acid",
-                                        Context::line(Some(line_index), &line, i, 1),
-                                    )
-                                })
-                        })
-                        .collect::<Result<Vec<SequenceElement<_>>, _>>()?,
-                );
+                .map_err(error)?;
             }
         }
-        if let Some(last_header) = last_header {
-            sequences.push(
-                Self {
-                    peptide: last_sequence.into(),
-                    ..last_header
-                }
-                .validate()?,
-            );
-        }
+        writer.flush().map_err(error)
+    }
+
+    /// Get the subset of the database containing only the given proteins, for example all
+    /// proteins hit by a database alignment (see [`crate::align::align_to_database`]). The
+    /// proteins are returned in the order of the database and every protein is returned only
+    /// once, even if it is selected multiple times. Proteins are matched on their full header.
+    pub fn subset<'a>(
+        database: &'a [Self],
+        proteins: impl IntoIterator<Item = &'a Self>,
+    ) -> Vec<&'a Self> {
+        let selected: HashSet<&str> = proteins.into_iter().map(Self::header).collect();
+        database
+            .iter()
+            .filter(|protein| selected.contains(protein.header()))
+            .collect()
+    }
 
-        Ok(sequences)
+    /// Merge multiple databases into a single database, for example to add a contaminant
+    /// database to a proteome. Proteins with an accession that is already present in an earlier
+    /// database (or earlier in the same database) are skipped, so the first definition is kept.
+    pub fn merge(databases: impl IntoIterator<Item = Vec<Self>>) -> Vec<Self> {
+        let mut seen = HashSet::new();
+        databases
+            .into_iter()
+            .flatten()
+            .filter(|protein| seen.insert(protein.identifier().accession().to_string()))
+            .collect()
     }
 
     /// # Errors
-    /// If the total length of the regions is not identical to the length of the peptide, or if any of the annotations is outside of the peptide
+    /// If the total length of the regions is not identical to the length of the peptide, or if any of the annotations or PEFF annotations is outside of the peptide
     fn validate(self) -> Result<Self, CustomError> {
         let total_regions_len: usize = self.regions.iter().map(|(_, l)| *l).sum();
         if total_regions_len > 0 && total_regions_len != self.peptide.len() {
@@ -536,6 +780,21 @@ impl FastaData {
                 "Invalid annotations definition", 
                 format!("The 'ANNOTATIONS' definition is invalid, on of the annotations is out of range of the peptide (length {})", self.peptide.len()),
                  Context::full_line(self.line_index, &self.full_header)))
+        } else if self
+            .variants
+            .iter()
+            .map(|v| &v.location)
+            .chain(self.processed.iter().map(|p| &p.location))
+            .any(|r| r.start > r.end || r.end > self.peptide.len())
+            || self
+                .modifications
+                .iter()
+                .any(|m| m.position >= self.peptide.len())
+        {
+            Err(CustomError::error(
+                "Invalid PEFF annotation",
+                format!("One of the PEFF variants, modifications, or processed regions is out of range of the peptide (length {})", self.peptide.len()),
+                Context::full_line(self.line_index, &self.full_header)))
         } else if total_regions_len > 0 {
             Ok(self)
         } else {
@@ -562,37 +821,46 @@ impl FastaData {
         let mut tags = Vec::new();
         let mut last_tag = None;
 
-        loop {
-            let start = last_equals.unwrap_or(first_space);
-            let slice = &full_header[start..];
-            if let Some(equals_position) = slice.find('=') {
-                let tag_end = slice[..equals_position]
-                    .char_indices()
-                    .rev()
-                    .take_while(|(_, c)| c.is_ascii_uppercase())
-                    .last()
-                    .map(|(i, _)| i)
-                    .unwrap_or_default();
-                if let Some(last_tag) = last_tag.take() {
-                    tags.push((
-                        last_tag,
-                        trim_whitespace(&full_header, start..start + tag_end),
-                    ));
-                } else {
-                    description = trim_whitespace(&full_header, start..start + tag_end);
-                }
-                last_tag = Some(start + tag_end..start + equals_position);
-                last_equals = Some(start + equals_position + 1);
-            } else {
-                if let Some(last_tag) = last_tag.take() {
-                    tags.push((
-                        last_tag,
-                        trim_whitespace(&full_header, start..full_header.len()),
-                    ));
+        let peff = full_header[first_space..].trim_start().starts_with('\\');
+        if peff {
+            tags = peff_tags(&full_header, first_space);
+            description = tags
+                .iter()
+                .find(|(key, _)| &full_header[key.clone()] == "PName")
+                .map_or(0..0, |(_, value)| value.clone());
+        } else {
+            loop {
+                let start = last_equals.unwrap_or(first_space);
+                let slice = &full_header[start..];
+                if let Some(equals_position) = slice.find('=') {
+                    let tag_end = slice[..equals_position]
+                        .char_indices()
+                        .rev()
+                        .take_while(|(_, c)| c.is_ascii_uppercase())
+                        .last()
+                        .map(|(i, _)| i)
+                        .unwrap_or_default();
+                    if let Some(last_tag) = last_tag.take() {
+                        tags.push((
+                            last_tag,
+                            trim_whitespace(&full_header, start..start + tag_end),
+                        ));
+                    } else {
+                        description = trim_whitespace(&full_header, start..start + tag_end);
+                    }
+                    last_tag = Some(start + tag_end..start + equals_position);
+                    last_equals = Some(start + equals_position + 1);
                 } else {
-                    description = trim_whitespace(&full_header, start..full_header.len());
+                    if let Some(last_tag) = last_tag.take() {
+                        tags.push((
+                            last_tag,
+                            trim_whitespace(&full_header, start..full_header.len()),
+                        ));
+                    } else {
+                        description = trim_whitespace(&full_header, start..full_header.len());
+                    }
+                    break;
                 }
-                break;
             }
         }
 
@@ -645,24 +913,40 @@ impl FastaData {
                 _ => (),
             }
         }
+        let (variants, modifications, processed) = if peff {
+            parse_peff(line_index, &full_header, &tags)?
+        } else {
+            (Vec::new(), Vec::new(), Vec::new())
+        };
+
+        let mut identifier = full_header[0..first_space]
+            .parse::<FastaIdentifier<Range<usize>>>()
+            .map_err(|err| {
+                CustomError::error(
+                    "Failed reading fasta file",
+                    format!(
+                        "Error occurred parsing NCBI identifier: number {}",
+                        explain_number_error(&err)
+                    ),
+                    Context::line(Some(line_index), &full_header, 1, first_space - 1),
+                )
+            })?;
+        // PEFF identifiers are defined as 'prefix:accession', e.g. 'nxp:NX_P07766-1'
+        if let FastaIdentifier::Undefined(range) = &mut identifier {
+            if let Some(colon) = full_header[range.clone()].find(':').filter(|_| peff) {
+                range.start += colon + 1;
+            }
+        }
 
         Ok(Self {
-            identifier: full_header[0..first_space]
-                .parse::<FastaIdentifier<Range<usize>>>()
-                .map_err(|err| {
-                    CustomError::error(
-                        "Failed reading fasta file",
-                        format!(
-                            "Error occurred parsing NCBI identifier: number {}",
-                            explain_number_error(&err)
-                        ),
-                        Context::line(Some(line_index), &full_header, 1, first_space - 1),
-                    )
-                })?,
+            identifier,
             description,
             tags,
             regions,
             annotations,
+            variants,
+            modifications,
+            processed,
             full_header,
             line_index,
             peptide: Peptidoform::default(),
@@ -670,6 +954,530 @@ impl FastaData {
     }
 }
 
+/// An iterator over the proteins in a fasta file, reading one protein at a time.
+///
+/// If a protein could not be parsed the error is returned and the reader continues with the
+/// next protein.
+pub struct FastaReader<R: BufRead> {
+    lines: std::iter::Enumerate<std::io::Lines<R>>,
+    line_offset: usize,
+    path: Option<PathBuf>,
+    header: Option<FastaData>,
+    sequence: Vec<SequenceElement<SemiAmbiguous>>,
+    skip_sequence: bool,
+    pending_error: Option<CustomError>,
+}
+
+impl<R: BufRead> FastaReader<R> {
+    /// Create a new reader for the given buffered reader, the path is only used for error
+    /// messages
+    pub fn new(reader: R, path: Option<&Path>) -> Self {
+        Self::with_line_offset(reader, path, 0)
+    }
+
+    /// Create a new reader with the line numbers in errors offset by the given amount
+    fn with_line_offset(reader: R, path: Option<&Path>, line_offset: usize) -> Self {
+        Self {
+            lines: reader.lines().enumerate(),
+            line_offset,
+            path: path.map(Path::to_path_buf),
+            header: None,
+            sequence: Vec::new(),
+            skip_sequence: false,
+            pending_error: None,
+        }
+    }
+
+    /// Finish the current protein, if there is any
+    /// # Errors
+    /// If the protein is not valid, see [`FastaData::validate`].
+    fn finish(&mut self) -> Option<Result<FastaData, CustomError>> {
+        self.header.take().map(|header| {
+            FastaData {
+                peptide: std::mem::take(&mut self.sequence).into(),
+                ..header
+            }
+            .validate()
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = Result<FastaData, CustomError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.pending_error.take() {
+            return Some(Err(err));
+        }
+        while let Some((line_index, line)) = self.lines.next() {
+            let line_index = line_index + self.line_offset;
+            let Ok(line) = line else {
+                return Some(Err(CustomError::error(
+                    "Failed reading fasta file",
+                    format!("Error occurred while reading line {}", line_index + 1),
+                    self.path
+                        .as_ref()
+                        .map_or(Context::None, |p| Context::show(p.to_string_lossy())),
+                )));
+            };
+            if line.starts_with('#') {
+                // PEFF file header
+                continue;
+            }
+            if line.starts_with('>') {
+                let finished = self.finish();
+                self.sequence.clear();
+                match FastaData::parse_header(line_index, line) {
+                    Ok(header) => {
+                        self.header = Some(header);
+                        self.skip_sequence = false;
+                    }
+                    Err(err) => {
+                        self.skip_sequence = true;
+                        if finished.is_none() {
+                            return Some(Err(err));
+                        }
+                        // Report the error after the finished protein
+                        self.pending_error = Some(err);
+                    }
+                }
+                if finished.is_some() {
+                    return finished;
+                }
+            } else if !self.skip_sequence {
+                let sequence = line
+                    .char_indices()
+                    .filter(|(_, c)| !c.is_ascii_whitespace())
+                    .map(|(i, c)| {
+                        c.try_into()
+                            .map(|aa: AminoAcid| SequenceElement::new(aa.into(), None))
+                            .map_err(|()| {
+                                CustomError::error(
+                                    "Failed reading fasta file",
+                                    "Character is not an amino acid",
+                                    Context::line(Some(line_index), &line, i, 1),
+                                )
+                            })
+                    })
+                    .collect::<Result<Vec<SequenceElement<_>>, _>>();
+                match sequence {
+                    Ok(sequence) => self.sequence.extend(sequence),
+                    Err(err) => {
+                        self.header = None;
+                        self.sequence.clear();
+                        self.skip_sequence = true;
+                        return Some(Err(err));
+                    }
+                }
+            }
+        }
+        self.finish()
+    }
+}
+
+impl<R: BufRead> std::iter::FusedIterator for FastaReader<R> {}
+
+/// The location and accession of a single protein in a fasta file
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct FastaIndexEntry {
+    /// The byte offset of the header line
+    pub offset: u64,
+    /// The line index of the header line
+    pub line_index: usize,
+    /// The accession of the protein, see [`FastaIdentifier::accession`]
+    pub accession: String,
+}
+
+/// An index into a fasta file, allowing random access to the proteins by index or accession
+/// without keeping all proteins in memory. Only the location and accession of each protein are
+/// kept in memory. The index can be stored (see [`Self::write_index`]) to open the same file
+/// again later without having to rebuild the index. Gzipped files are not supported as these do
+/// not allow random access.
+pub struct IndexedFasta<R: BufRead + Seek> {
+    reader: R,
+    index: Vec<FastaIndexEntry>,
+    accessions: HashMap<String, usize>,
+}
+
+impl IndexedFasta<BufReader<File>> {
+    /// Open and index the fasta file at the given path, this reads through the whole file once.
+    /// # Errors
+    /// If the file could not be opened, if it is gzipped, or if any header could not be read.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        Self::new(open_indexable(path.as_ref())?)
+    }
+
+    /// Open the fasta file at the given path with a previously built index. The index is not
+    /// validated, so if the file changed since the index was built any protein could give an
+    /// error or the wrong data.
+    /// # Errors
+    /// If the file could not be opened or if it is gzipped.
+    pub fn open_with_index(
+        path: impl AsRef<Path>,
+        index: Vec<FastaIndexEntry>,
+    ) -> Result<Self, CustomError> {
+        Ok(Self::with_index(open_indexable(path.as_ref())?, index))
+    }
+}
+
+impl<R: BufRead + Seek> IndexedFasta<R> {
+    /// Index the given reader, this reads through the whole file once.
+    /// # Errors
+    /// If any line could not be read or any header could not be parsed.
+    pub fn new(mut reader: R) -> Result<Self, CustomError> {
+        let read_error = |line_index: usize| {
+            CustomError::error(
+                "Could not index fasta file",
+                format!("Error occurred while reading line {}", line_index + 1),
+                Context::none(),
+            )
+        };
+        let mut index = Vec::new();
+        let mut offset = reader.stream_position().map_err(|_| read_error(0))?;
+        let mut line = String::new();
+        let mut line_index = 0;
+        loop {
+            line.clear();
+            let read = reader
+                .read_line(&mut line)
+                .map_err(|_| read_error(line_index))?;
+            if read == 0 {
+                break;
+            }
+            if line.starts_with('>') {
+                let header = FastaData::parse_header(
+                    line_index,
+                    line.trim_end_matches(['\n', '\r']).to_string(),
+                )?;
+                index.push(FastaIndexEntry {
+                    offset,
+                    line_index,
+                    accession: header.identifier().accession().to_string(),
+                });
+            }
+            offset += read as u64;
+            line_index += 1;
+        }
+        Ok(Self::with_index(reader, index))
+    }
+
+    /// Use the given reader with a previously built index, see [`IndexedFasta::open_with_index`].
+    pub fn with_index(reader: R, index: Vec<FastaIndexEntry>) -> Self {
+        let mut accessions = HashMap::new();
+        for (i, entry) in index.iter().enumerate() {
+            accessions.entry(entry.accession.clone()).or_insert(i);
+        }
+        Self {
+            reader,
+            index,
+            accessions,
+        }
+    }
+
+    /// Get the index with the location and accession for all proteins
+    pub fn index(&self) -> &[FastaIndexEntry] {
+        &self.index
+    }
+
+    /// The number of proteins in this file
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check if this file has no proteins
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Read the protein at the given index (0 based), returns None if the index is out of range.
+    /// # Errors
+    /// If the protein could not be read or parsed.
+    pub fn get_by_index(&mut self, index: usize) -> Option<Result<FastaData, CustomError>> {
+        let entry = self.index.get(index)?.clone();
+        Some(self.read_entry(&entry))
+    }
+
+    /// Read the first protein with the given accession, returns None if no protein has this
+    /// accession.
+    /// # Errors
+    /// If the protein could not be read or parsed.
+    pub fn get_by_accession(&mut self, accession: &str) -> Option<Result<FastaData, CustomError>> {
+        let index = *self.accessions.get(accession)?;
+        self.get_by_index(index)
+    }
+
+    /// Write the index as a tab separated file with the accession, byte offset, and line index
+    /// of every protein, to be read back with [`Self::read_index`].
+    /// # Errors
+    /// If the writer could not be written to.
+    pub fn write_index(&self, mut writer: impl Write) -> Result<(), CustomError> {
+        let error =
+            |error| CustomError::error("Could not write fasta index", error, Context::none());
+        for entry in &self.index {
+            writeln!(
+                writer,
+                "{}\t{}\t{}",
+                entry.accession, entry.offset, entry.line_index
+            )
+            .map_err(error)?;
+        }
+        writer.flush().map_err(error)
+    }
+
+    /// Read an index as written by [`Self::write_index`].
+    /// # Errors
+    /// If any line could not be read or is not a valid index line.
+    pub fn read_index(reader: impl BufRead) -> Result<Vec<FastaIndexEntry>, CustomError> {
+        reader
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| line.as_ref().map_or(true, |l| !l.is_empty()))
+            .map(|(line_index, line)| {
+                let line = line.map_err(|_| {
+                    CustomError::error(
+                        "Could not read fasta index",
+                        format!("Error occurred while reading line {}", line_index + 1),
+                        Context::none(),
+                    )
+                })?;
+                let mut fields = line.rsplitn(3, '\t');
+                let (Some(index), Some(offset), Some(accession)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(CustomError::error(
+                        "Could not read fasta index",
+                        "An index line should contain the accession, offset, and line index separated by tabs",
+                        Context::full_line(line_index, &line),
+                    ));
+                };
+                match (offset.parse(), index.parse()) {
+                    (Ok(offset), Ok(index)) => Ok(FastaIndexEntry {
+                        offset,
+                        line_index: index,
+                        accession: accession.to_string(),
+                    }),
+                    _ => Err(CustomError::error(
+                        "Could not read fasta index",
+                        "The offset and line index should be numbers",
+                        Context::full_line(line_index, &line),
+                    )),
+                }
+            })
+            .collect()
+    }
+
+    /// Read the protein at the given location
+    /// # Errors
+    /// If the protein could not be read or parsed.
+    fn read_entry(&mut self, entry: &FastaIndexEntry) -> Result<FastaData, CustomError> {
+        self.reader
+            .seek(SeekFrom::Start(entry.offset))
+            .map_err(|_| {
+                CustomError::error(
+                    "Could not read fasta file",
+                    format!(
+                        "Error occurred while seeking to line {}",
+                        entry.line_index + 1
+                    ),
+                    Context::none(),
+                )
+            })?;
+        FastaReader::with_line_offset(&mut self.reader, None, entry.line_index)
+            .next()
+            .unwrap_or_else(|| {
+                Err(CustomError::error(
+                    "Could not read fasta file",
+                    format!(
+                        "The protein on line {} ended unexpectedly",
+                        entry.line_index + 1
+                    ),
+                    Context::none(),
+                ))
+            })
+    }
+}
+
+/// Open a fasta file for random access
+/// # Errors
+/// If the file could not be opened or is gzipped.
+fn open_indexable(path: &Path) -> Result<BufReader<File>, CustomError> {
+    if check_extension(path, "gz") {
+        return Err(CustomError::error(
+            "Could not index fasta file",
+            "Gzipped files do not allow random access, decompress the file first or use the streaming reader",
+            Context::show(path.display()),
+        ));
+    }
+    open_file(path).map(BufReader::new).map_err(|_| {
+        CustomError::error(
+            "Failed reading fasta file",
+            "Error occurred while opening the file",
+            Context::show(path.to_string_lossy()),
+        )
+    })
+}
+
+/// Get the PEFF tags (`\\Key=Value`) following the identifier as key and value ranges into the
+/// header
+fn peff_tags(header: &str, first_space: usize) -> Vec<(Range<usize>, Range<usize>)> {
+    let starts = header[first_space..]
+        .match_indices(" \\")
+        .map(|(i, _)| first_space + i + 2)
+        .collect_vec();
+    starts
+        .iter()
+        .enumerate()
+        .filter_map(|(index, start)| {
+            let end = starts.get(index + 1).map_or(header.len(), |next| next - 2);
+            header[*start..end].find('=').map(|equals| {
+                (
+                    *start..start + equals,
+                    trim_whitespace(header, start + equals + 1..end),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Split a PEFF tag value of the form `(a|b)(c|d)` in its groups, every group is returned as the
+/// range of the full group (including parentheses) and the ranges of all fields
+fn peff_groups(header: &str, value: Range<usize>) -> Vec<(Range<usize>, Vec<Range<usize>>)> {
+    let mut groups = Vec::new();
+    let mut fields = Vec::new();
+    let mut group_start = None;
+    let mut field_start = None;
+    for (i, c) in header[value.clone()].char_indices() {
+        let i = value.start + i;
+        match c {
+            '(' => {
+                fields.clear();
+                group_start = Some(i);
+                field_start = Some(i + 1);
+            }
+            '|' => {
+                if let Some(start) = field_start {
+                    fields.push(start..i);
+                    field_start = Some(i + 1);
+                }
+            }
+            ')' => {
+                if let (Some(group), Some(start)) = (group_start.take(), field_start.take()) {
+                    fields.push(start..i);
+                    groups.push((group..i + 1, std::mem::take(&mut fields)));
+                }
+            }
+            _ => (),
+        }
+    }
+    groups
+}
+
+/// The structured PEFF annotations from a header
+type PeffAnnotations = (Vec<PeffVariant>, Vec<PeffModification>, Vec<PeffProcessed>);
+
+/// Parse the PEFF variants, modifications, and processed regions from the given tags
+/// # Errors
+/// If any of these tags contains an invalid group.
+fn parse_peff(
+    line_index: usize,
+    header: &str,
+    tags: &[(Range<usize>, Range<usize>)],
+) -> Result<PeffAnnotations, CustomError> {
+    let mut variants = Vec::new();
+    let mut modifications = Vec::new();
+    let mut processed = Vec::new();
+
+    for (key, value) in tags {
+        let key = &header[key.clone()];
+        for (group, fields) in peff_groups(header, value.clone()) {
+            let error = |explanation: &str| {
+                CustomError::error(
+                    "Invalid PEFF annotation",
+                    format!("The PEFF '{key}' value is invalid, {explanation}"),
+                    Context::line(Some(line_index), header, group.start, group.len()),
+                )
+            };
+            let field = |index: usize| fields.get(index).map_or("", |f| header[f.clone()].trim());
+            let position = |text: &str| {
+                text.parse::<usize>()
+                    .ok()
+                    .filter(|p| *p > 0)
+                    .map(|p| p - 1)
+                    .ok_or_else(|| error("the position should be a one based number"))
+            };
+            let sequence = |text: &str| {
+                text.chars()
+                    .map(AminoAcid::try_from)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|()| error("the sequence contains an invalid amino acid"))
+            };
+            match key {
+                "VariantSimple" if fields.len() >= 2 => {
+                    let position = position(field(0))?;
+                    variants.push(PeffVariant {
+                        location: position..position + 1,
+                        replacement: sequence(field(1))?,
+                    });
+                }
+                "VariantComplex" if fields.len() >= 3 => variants.push(PeffVariant {
+                    location: position(field(0))?..position(field(1))? + 1,
+                    replacement: sequence(field(2))?,
+                }),
+                "ModResUnimod" | "ModResPsi" | "ModRes" if fields.len() >= 2 => {
+                    let (accession, name) = if fields.len() == 2 {
+                        ("", field(1))
+                    } else {
+                        (field(1), field(2))
+                    };
+                    let modification = resolve_peff_modification(accession, name);
+                    for p in field(0).split(',') {
+                        modifications.push(PeffModification {
+                            position: position(p.trim())?,
+                            accession: accession.to_string(),
+                            name: name.to_string(),
+                            modification: modification.clone(),
+                        });
+                    }
+                }
+                "Processed" if fields.len() >= 3 => {
+                    let (accession, name) = if fields.len() == 3 {
+                        ("", field(2))
+                    } else {
+                        (field(2), field(3))
+                    };
+                    processed.push(PeffProcessed {
+                        location: position(field(0))?..position(field(1))? + 1,
+                        accession: accession.to_string(),
+                        name: name.to_string(),
+                    });
+                }
+                "VariantSimple" | "VariantComplex" | "ModResUnimod" | "ModResPsi" | "ModRes"
+                | "Processed" => return Err(error("this group does not have enough fields")),
+                _ => (),
+            }
+        }
+    }
+    Ok((variants, modifications, processed))
+}
+
+/// Find a PEFF modification, first on accession (`UNIMOD:35`, `MOD:00046`, or `RESID:AA0037`)
+/// and otherwise on name in Unimod and PSI-MOD
+fn resolve_peff_modification(accession: &str, name: &str) -> Option<SimpleModification> {
+    accession
+        .split_once(':')
+        .and_then(|(ontology, id)| {
+            let ontology = match ontology.to_ascii_uppercase().as_str() {
+                "UNIMOD" => Ontology::Unimod,
+                "MOD" => Ontology::Psimod,
+                "RESID" => Ontology::Resid,
+                _ => return None,
+            };
+            ontology.find_id(id.trim_start_matches("AA").parse().ok()?, None)
+        })
+        .or_else(|| Ontology::Unimod.find_name(name, None))
+        .or_else(|| Ontology::Psimod.find_name(name, None))
+}
+
 fn trim_whitespace(line: &str, range: Range<usize>) -> Range<usize> {
     let start = range.len() - line[range.clone()].trim_start().len();
     let end = range.len() - line[range.clone()].trim_end().len();
@@ -680,6 +1488,7 @@ impl From<FastaData> for IdentifiedPeptide {
     fn from(value: FastaData) -> Self {
         Self {
             score: None,
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::Fasta(value),
         }
@@ -715,3 +1524,213 @@ fn parse_header() {
     assert_eq!(header.annotations().len(), 2);
     assert_eq!(header.annotations()[0], (Annotation::Conserved, 12));
 }
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn digest_signal_peptide() {
+    let file = ">sp|P1|PROT Protein REGIONS=SP:4;Mature:8\nMKKLAAKEEERR";
+    let fasta = FastaData::parse_reader(BufReader::new(file.as_bytes()), None).unwrap();
+    assert_eq!(fasta[0].signal_peptide(), Some(4));
+    let peptides = fasta[0].digest(
+        &Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]),
+        0,
+    );
+    assert_eq!(
+        peptides
+            .iter()
+            .map(|(p, t)| (p.peptide.to_string(), p.range(), t.n_term, t.c_term))
+            .collect_vec(),
+        vec![
+            ("AAK".to_string(), 4..7, true, false),
+            ("EEER".to_string(), 7..11, false, false),
+            ("R".to_string(), 11..12, false, true)
+        ]
+    );
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn write_round_trip() {
+    let file = ">sp|P1|PROT Protein OS=Homo sapiens REGIONS=SP:4;Mature:8 ANNOTATIONS=C:5\nMKKLAAKEEERR\n>tr|P2|LONG Long protein\nMAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAK\n";
+    let fasta = FastaData::parse_reader(BufReader::new(file.as_bytes()), None).unwrap();
+    let mut buffer = Vec::new();
+    FastaData::write(&mut buffer, &fasta).unwrap();
+    let written = String::from_utf8(buffer).unwrap();
+    assert!(written.starts_with(
+        ">sp|P1|PROT Protein OS=Homo sapiens REGIONS=SP:4;Mature:8 ANNOTATIONS=C:5\nMKKLAAKEEERR\n"
+    ));
+    assert!(written
+        .lines()
+        .all(|line| line.len() <= 60 || line.starts_with('>')));
+    let reread = FastaData::parse_reader(BufReader::new(written.as_bytes()), None).unwrap();
+    assert_eq!(reread.len(), 2);
+    for (original, reread) in fasta.iter().zip(&reread) {
+        assert_eq!(original.header(), reread.header());
+        assert_eq!(original.peptide(), reread.peptide());
+        assert_eq!(original.regions(), reread.regions());
+        assert_eq!(original.annotations(), reread.annotations());
+    }
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn subset_and_merge() {
+    let database = FastaData::parse_reader(
+        BufReader::new(">sp|P1|A A\nMKK\n>sp|P2|B B\nMRR\n>sp|P3|C C\nMLL".as_bytes()),
+        None,
+    )
+    .unwrap();
+    let subset = FastaData::subset(&database, [&database[2], &database[0], &database[2]]);
+    assert_eq!(
+        subset
+            .iter()
+            .map(|p| p.identifier().accession())
+            .collect_vec(),
+        vec!["P1", "P3"]
+    );
+    let contaminants = FastaData::parse_reader(
+        BufReader::new(">sp|P3|C_CONT Contaminant\nMAA\n>sp|P4|D D\nMEE".as_bytes()),
+        None,
+    )
+    .unwrap();
+    let merged = FastaData::merge([database, contaminants]);
+    assert_eq!(
+        merged
+            .iter()
+            .map(|p| p.identifier().accession())
+            .collect_vec(),
+        vec!["P1", "P2", "P3", "P4"]
+    );
+    assert_eq!(merged[2].identifier().name(), "C");
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn parse_peff_header() {
+    let file = r"# PEFF 1.0
+# //
+# DbName=neXtProt
+# Prefix=nxp
+# //
+>nxp:NX_P1-1 \DbUniqueId=NX_P1-1 \PName=Test protein \GName=TST \NcbiTaxId=9606 \Length=12 \ModResUnimod=(7|UNIMOD:35|Oxidation) \ModResPsi=(9,10|MOD:00046|O-phospho-L-serine) \ModRes=(11||Unknown modification) \VariantSimple=(6|R)(8|W) \VariantComplex=(9|10|) \Processed=(1|4|PEFF:0001021|signal peptide)(5|12|mature protein)
+MKKLAKMASSEK
+";
+    let fasta = FastaData::parse_reader(BufReader::new(file.as_bytes()), None).unwrap();
+    assert_eq!(fasta.len(), 1);
+    let protein = &fasta[0];
+    assert_eq!(protein.identifier().accession(), "NX_P1-1");
+    assert_eq!(protein.description(), "Test protein");
+    assert_eq!(protein.gene(), Some("TST"));
+    assert_eq!(protein.organism_id(), Some(9606));
+
+    assert_eq!(protein.known_modifications().len(), 4);
+    assert_eq!(protein.known_modifications()[0].position, 6);
+    assert_eq!(
+        protein.known_modifications()[0].modification,
+        Ontology::Unimod.find_id(35, None)
+    );
+    assert_eq!(protein.known_modifications()[2].position, 9);
+    assert!(protein.known_modifications()[2].modification.is_some());
+    assert_eq!(protein.known_modifications()[3].modification, None);
+    assert_eq!(protein.known_modifications_in(4..8).count(), 1);
+    assert_eq!(protein.known_modifications_in(4..8).next().unwrap().0, 2);
+
+    assert_eq!(protein.processed().len(), 2);
+    assert_eq!(protein.processed()[1].location, 4..12);
+    assert_eq!(protein.signal_peptide(), Some(4));
+
+    assert_eq!(
+        protein
+            .variant_peptides()
+            .map(|p| p.to_string())
+            .collect_vec(),
+        vec!["MKKLARMASSEK", "MKKLAKMWSSEK", "MKKLAKMAEK"]
+    );
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn invalid_peff() {
+    for header in [
+        r">nxp:NX_P1 \PName=Test \VariantSimple=(13|R)",
+        r">nxp:NX_P1 \PName=Test \VariantSimple=(0|R)",
+        r">nxp:NX_P1 \PName=Test \VariantSimple=(2|1)",
+        r">nxp:NX_P1 \PName=Test \ModResPsi=(13|MOD:00046|O-phospho-L-serine)",
+        r">nxp:NX_P1 \PName=Test \Processed=(1|4)",
+    ] {
+        let file = format!("{header}\nMKKLAKMASSEK");
+        assert!(
+            FastaData::parse_reader(BufReader::new(file.as_bytes()), None).is_err(),
+            "{header}"
+        );
+    }
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn uniprot_header() {
+    let header = ">sp|P02768|ALBU_HUMAN Albumin OS=Homo sapiens OX=9606 GN=ALB PE=1 SV=2";
+    let header = FastaData::parse_header(0, header.to_string()).unwrap();
+    assert_eq!(header.identifier().accession(), "P02768");
+    assert_eq!(header.identifier().name(), "ALBU_HUMAN");
+    assert_eq!(header.description(), "Albumin");
+    assert_eq!(header.organism(), Some("Homo sapiens"));
+    assert_eq!(header.organism_id(), Some(9606));
+    assert_eq!(header.gene(), Some("ALB"));
+    assert_eq!(header.protein_existence(), Some(ProteinExistence::Protein));
+    assert_eq!(header.sequence_version(), Some(2));
+
+    let header =
+        ">tr|A0A0B4J2F0|PIOS1_HUMAN Protein PIGBOS1 OS=Homo sapiens (Human) OX=9606 PE=4 SV=1";
+    let header = FastaData::parse_header(0, header.to_string()).unwrap();
+    assert_eq!(header.organism(), Some("Homo sapiens (Human)"));
+    assert_eq!(header.gene(), None);
+    assert_eq!(
+        header.protein_existence(),
+        Some(ProteinExistence::Predicted)
+    );
+
+    let header = ">custom_protein";
+    let header = FastaData::parse_header(0, header.to_string()).unwrap();
+    assert_eq!(header.organism(), None);
+    assert_eq!(header.organism_id(), None);
+    assert_eq!(header.protein_existence(), None);
+    assert_eq!(header.sequence_version(), None);
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn stream_continues_after_error() {
+    let file = ">sp|P1|A A\nMKK\n>sp|P2|B B\nMK1K\nMRR\n>sp|P3|C C\nMLL\n";
+    let proteins = FastaReader::new(BufReader::new(file.as_bytes()), None).collect_vec();
+    assert_eq!(proteins.len(), 3);
+    assert_eq!(proteins[0].as_ref().unwrap().identifier().accession(), "P1");
+    assert!(proteins[1].is_err());
+    assert_eq!(proteins[2].as_ref().unwrap().peptide().to_string(), "MLL");
+    assert!(FastaData::parse_reader(BufReader::new(file.as_bytes()), None).is_err());
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn indexed() {
+    let file =
+        "# PEFF 1.0\n>sp|P1|A A\nMKK\nAAA\n>sp|P2|B B OS=Homo sapiens\r\nMRR\r\n>sp|P3|C C\nMLL";
+    let all = FastaData::parse_reader(BufReader::new(file.as_bytes()), None).unwrap();
+    let mut indexed = IndexedFasta::new(std::io::Cursor::new(file.as_bytes())).unwrap();
+    assert_eq!(indexed.len(), 3);
+    assert_eq!(indexed.get_by_index(2).unwrap().unwrap(), all[2]);
+    assert_eq!(indexed.get_by_accession("P1").unwrap().unwrap(), all[0]);
+    let p2 = indexed.get_by_accession("P2").unwrap().unwrap();
+    assert_eq!(p2.peptide(), all[1].peptide());
+    assert_eq!(p2.organism(), Some("Homo sapiens"));
+    assert!(indexed.get_by_accession("P4").is_none());
+    assert!(indexed.get_by_index(3).is_none());
+
+    let mut buffer = Vec::new();
+    indexed.write_index(&mut buffer).unwrap();
+    let index = IndexedFasta::<std::io::Cursor<&[u8]>>::read_index(buffer.as_slice()).unwrap();
+    assert_eq!(index, indexed.index());
+    let mut reopened = IndexedFasta::with_index(std::io::Cursor::new(file.as_bytes()), index);
+    assert_eq!(reopened.get_by_accession("P3").unwrap().unwrap(), all[2]);
+    assert!(IndexedFasta::<std::io::Cursor<&[u8]>>::read_index(b"P1\tx\t1".as_slice()).is_err());
+}
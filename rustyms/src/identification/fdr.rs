@@ -0,0 +1,288 @@
+//! Target-decoy based false discovery rate estimation for identified peptides
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::identification::{IdentifiedPeptide, SpectrumIds};
+
+/// The settings for a target-decoy analysis, see [`q_values`].
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct FdrSettings {
+    /// If a higher score is better.
+    ///
+    /// Default: true.
+    pub higher_score_is_better: bool,
+    /// If target-decoy competition is applied, keeping only the best scoring PSM for every
+    /// spectrum. PSMs without a known spectrum are always kept.
+    ///
+    /// Default: true.
+    pub competition: bool,
+    /// The protein name prefixes that mark a decoy protein, compared case insensitively. These
+    /// are only used for formats that do not report if a PSM is a decoy.
+    ///
+    /// Default: `rev_`, `decoy_`, and `xxx_`.
+    pub decoy_prefixes: Vec<String>,
+}
+
+impl Default for FdrSettings {
+    fn default() -> Self {
+        Self {
+            higher_score_is_better: true,
+            competition: true,
+            decoy_prefixes: vec!["rev_".to_string(), "decoy_".to_string(), "xxx_".to_string()],
+        }
+    }
+}
+
+impl FdrSettings {
+    /// Set if a higher score is better
+    #[must_use]
+    pub fn higher_score_is_better(self, higher_score_is_better: bool) -> Self {
+        Self {
+            higher_score_is_better,
+            ..self
+        }
+    }
+
+    /// Set if target-decoy competition is applied
+    #[must_use]
+    pub fn competition(self, competition: bool) -> Self {
+        Self {
+            competition,
+            ..self
+        }
+    }
+
+    /// Set the decoy protein name prefixes
+    #[must_use]
+    pub fn decoy_prefixes(self, decoy_prefixes: Vec<String>) -> Self {
+        Self {
+            decoy_prefixes,
+            ..self
+        }
+    }
+
+    /// Check if the given protein name is a decoy, meaning that it or any of its parts (e.g. the
+    /// accession in `sp|rev_P12345|NAME`) starts with any of the decoy prefixes
+    pub fn is_decoy_protein(&self, name: &str) -> bool {
+        let name = name.to_ascii_lowercase();
+        self.decoy_prefixes.iter().any(|prefix| {
+            let prefix = prefix.to_ascii_lowercase();
+            name.starts_with(&prefix) || name.split('|').any(|p| p.starts_with(&prefix))
+        })
+    }
+}
+
+/// A peptide spectrum match that can be used in a target-decoy analysis. This is implemented for
+/// [`IdentifiedPeptide`] so it can be used for all identification formats, and for tuples of any
+/// PSM with its score and decoy status for PSMs from other sources.
+pub trait TargetDecoyPsm {
+    /// The score, PSMs without a score are ignored
+    fn score(&self) -> Option<f64>;
+    /// Check if this PSM is a decoy
+    fn is_decoy(&self, settings: &FdrSettings) -> bool;
+    /// The spectrum that was matched, used for target-decoy competition. `None` if not known.
+    fn spectrum(&self) -> Option<Vec<(Option<PathBuf>, String)>>;
+}
+
+impl TargetDecoyPsm for IdentifiedPeptide {
+    fn score(&self) -> Option<f64> {
+        self.score
+    }
+
+    /// Uses the decoy status as reported by the format, or if this is not reported checks if the
+    /// protein identifier is a decoy, see [`FdrSettings::is_decoy_protein`].
+    fn is_decoy(&self, settings: &FdrSettings) -> bool {
+        Self::is_decoy(self).unwrap_or_else(|| {
+            self.protein_name()
+                .is_some_and(|name| settings.is_decoy_protein(&name.to_string()))
+        })
+    }
+
+    fn spectrum(&self) -> Option<Vec<(Option<PathBuf>, String)>> {
+        let mut spectra: Vec<_> = match self.scans() {
+            SpectrumIds::None => return None,
+            SpectrumIds::FileNotKnown(ids) => ids.iter().map(|id| (None, id.to_string())).collect(),
+            SpectrumIds::FileKnown(files) => files
+                .into_iter()
+                .flat_map(|(file, ids)| {
+                    ids.into_iter()
+                        .map(move |id| (Some(file.clone()), id.to_string()))
+                })
+                .collect(),
+        };
+        spectra.sort();
+        (!spectra.is_empty()).then_some(spectra)
+    }
+}
+
+impl<T> TargetDecoyPsm for (T, f64, bool) {
+    fn score(&self) -> Option<f64> {
+        Some(self.1)
+    }
+
+    fn is_decoy(&self, _settings: &FdrSettings) -> bool {
+        self.2
+    }
+
+    fn spectrum(&self) -> Option<Vec<(Option<PathBuf>, String)>> {
+        None
+    }
+}
+
+/// A PSM with its q-value as determined in a target-decoy analysis
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ScoredPsm<P> {
+    /// The PSM
+    pub psm: P,
+    /// The score
+    pub score: f64,
+    /// If this PSM is a decoy
+    pub decoy: bool,
+    /// The q-value, the lowest FDR at which this PSM is accepted
+    pub q_value: f64,
+}
+
+/// Calculate the q-values for all given PSMs. If [`FdrSettings::competition`] is set only the best
+/// scoring PSM for each spectrum is kept (on equal scores targets win). The FDR at a score
+/// threshold is estimated as the number of decoys divided by the number of targets that score at
+/// least as good as that threshold, the q-value is the lowest FDR at which a PSM is accepted. The
+/// PSMs are returned sorted from best to worst score, PSMs without score are left out.
+///
+/// ```rust
+/// # use rustyms::identification::*;
+/// let psms = [("A", 10.0, false), ("B", 9.0, true), ("C", 8.0, false), ("D", 1.0, false)];
+/// let scored = q_values(psms, &FdrSettings::default());
+/// assert_eq!(scored[0].q_value, 0.0);
+/// assert_eq!(scored[3].q_value, 1.0 / 3.0);
+/// assert_eq!(filter_fdr(scored, 0.01).len(), 1);
+/// ```
+pub fn q_values<P: TargetDecoyPsm>(
+    psms: impl IntoIterator<Item = P>,
+    settings: &FdrSettings,
+) -> Vec<ScoredPsm<P>> {
+    let psms = psms.into_iter().filter_map(|psm| {
+        psm.score().filter(|s| !s.is_nan()).map(|score| ScoredPsm {
+            decoy: psm.is_decoy(settings),
+            psm,
+            score,
+            q_value: 1.0,
+        })
+    });
+    let better = |a: &ScoredPsm<P>, b: &ScoredPsm<P>| {
+        let order = if settings.higher_score_is_better {
+            b.score.total_cmp(&a.score)
+        } else {
+            a.score.total_cmp(&b.score)
+        };
+        order.then(a.decoy.cmp(&b.decoy))
+    };
+
+    let mut psms: Vec<ScoredPsm<P>> = if settings.competition {
+        let mut best: BTreeMap<Vec<(Option<PathBuf>, String)>, ScoredPsm<P>> = BTreeMap::new();
+        let mut unknown = Vec::new();
+        for psm in psms {
+            if let Some(spectrum) = psm.psm.spectrum() {
+                match best.get(&spectrum) {
+                    Some(existing) if better(existing, &psm).is_le() => (),
+                    _ => {
+                        best.insert(spectrum, psm);
+                    }
+                }
+            } else {
+                unknown.push(psm);
+            }
+        }
+        best.into_values().chain(unknown).collect()
+    } else {
+        psms.collect()
+    };
+    psms.sort_by(better);
+
+    // Determine the FDR at every score threshold, PSMs with equal scores share a threshold
+    let mut fdr = vec![1.0; psms.len()];
+    let (mut targets, mut decoys) = (0_usize, 0_usize);
+    let mut start = 0;
+    while start < psms.len() {
+        let end = psms[start..]
+            .iter()
+            .position(|p| p.score.total_cmp(&psms[start].score).is_ne())
+            .map_or(psms.len(), |i| start + i);
+        for psm in &psms[start..end] {
+            if psm.decoy {
+                decoys += 1;
+            } else {
+                targets += 1;
+            }
+        }
+        let value = if targets == 0 {
+            1.0
+        } else {
+            (decoys as f64 / targets as f64).min(1.0)
+        };
+        fdr[start..end].fill(value);
+        start = end;
+    }
+    // The q-value is the minimal FDR of this and all worse thresholds
+    let mut minimum = 1.0_f64;
+    for (psm, fdr) in psms.iter_mut().zip(fdr).rev() {
+        minimum = minimum.min(fdr);
+        psm.q_value = minimum;
+    }
+    psms
+}
+
+/// Keep only the target PSMs with a q-value at or below the given FDR (e.g. 0.01 for 1%)
+pub fn filter_fdr<P>(psms: Vec<ScoredPsm<P>>, fdr: f64) -> Vec<ScoredPsm<P>> {
+    psms.into_iter()
+        .filter(|psm| !psm.decoy && psm.q_value <= fdr)
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp, clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::{open_identified_peptides_file, MetaData};
+
+    #[test]
+    fn q_value_monotone() {
+        let psms = (0..100).map(|i| (i, f64::from(i), i % 7 == 0 && i < 50));
+        let scored = q_values(psms, &FdrSettings::default());
+        assert_eq!(scored.len(), 100);
+        assert_eq!(scored[0].psm.0, 99);
+        assert!(scored.windows(2).all(|w| w[0].q_value <= w[1].q_value));
+        assert_eq!(scored[0].q_value, 0.0);
+        let lower = q_values(
+            (0..100).map(|i| (i, f64::from(-i), i % 7 == 0 && i < 50)),
+            &FdrSettings::default().higher_score_is_better(false),
+        );
+        assert_eq!(
+            scored.iter().map(|p| p.q_value).collect::<Vec<_>>(),
+            lower.iter().map(|p| p.q_value).collect::<Vec<_>>()
+        );
+        assert_eq!(filter_fdr(scored, 0.0).len(), 50);
+    }
+
+    #[test]
+    fn competition() {
+        let peptides: Vec<IdentifiedPeptide> =
+            open_identified_peptides_file("src/identification/test_files/sage_v0_14.tsv", None)
+                .unwrap()
+                .collect::<Result<_, _>>()
+                .unwrap();
+        let with = q_values(peptides.clone(), &FdrSettings::default());
+        let without = q_values(peptides.clone(), &FdrSettings::default().competition(false));
+        assert_eq!(without.len(), peptides.len());
+        assert!(with.len() <= without.len());
+        for psm in &with {
+            assert_eq!(
+                Some(psm.decoy),
+                psm.psm.is_decoy(),
+                "Sage reports the decoy status"
+            );
+            assert!(matches!(psm.psm.metadata, MetaData::Sage(_)));
+        }
+    }
+}
@@ -3,10 +3,11 @@ use std::path::Path;
 use super::{
     error::{Context, CustomError},
     ontologies::CustomDatabase,
-    DeepNovoFamilyData, FastaData, IdentifiedPeptide, IdentifiedPeptideIter,
-    IdentifiedPeptideSource, InstaNovoData, MSFraggerData, MZTabData, MaxQuantData, NovoBData,
-    NovorData, OpairData, PLGSData, PLinkData, PeaksData, PepNetData, PowerNovoData, SageData,
-    SpectrumSequenceListData,
+    CasanovoData, DeepNovoFamilyData, FastaData, IdentifiedPeptide, IdentifiedPeptideIter,
+    IdentifiedPeptideSource, InstaNovoData, MSFraggerData, MZTabData, MascotData, MaxQuantData,
+    MetaMorpheusData, NovoBData, NovorData, OpairData, PLGSData, PLinkData, PNovoData, PeaksData,
+    PepNetData, PepNovoData, PercolatorData, PowerNovoData, SageData, SpectrumSequenceListData,
+    XTandemData,
 };
 
 // TODO:
@@ -58,13 +59,20 @@ pub fn open_identified_peptides_file<'a>(
                 PLGSData::parse_file(path, custom_database)
                     .map(IdentifiedPeptideIter::into_box)
                     .map_err(|ple| (pe, ne, ie, le, pne, ple))
-            }).map_err(|(pe, ne, ie, le, pne, ple)| {
+            }).or_else(|(pe, ne, ie, le, pne, ple)| {
+                MascotData::parse_file(path, custom_database)
+                    .map(|peptides| {
+                        Box::new(peptides.into_iter().map(|p| p.map(Into::into)))
+                            as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
+                    })
+                    .map_err(|mse| (pe, ne, ie, le, pne, ple, mse))
+            }).map_err(|(pe, ne, ie, le, pne, ple, mse)| {
                 CustomError::error(
                     "Unknown file format",
-                    "Could not be recognised as either a Peaks, Novor, InstaNovo, pLink, PowerNovo, or PLGS file",
+                    "Could not be recognised as either a Peaks, Novor, InstaNovo, pLink, PowerNovo, PLGS, or Mascot file",
                     Context::show(path.to_string_lossy()),
                 )
-                .with_underlying_errors(vec![pe, ne, ie, le, pne, ple])
+                .with_underlying_errors(vec![pe, ne, ie, le, pne, ple, mse])
             }),
         Some("tsv") => MSFraggerData::parse_file(path, custom_database)
             .map(IdentifiedPeptideIter::into_box)
@@ -86,10 +94,22 @@ pub fn open_identified_peptides_file<'a>(
                 )
                 .with_underlying_errors(vec![me, se, pe])
             }),
-        Some("psmtsv") => {
-            OpairData::parse_file(path, custom_database).map(IdentifiedPeptideIter::into_box)
-        }
-        Some("fasta") => FastaData::parse_file(path).map(|peptides| {
+        Some("psmtsv") => OpairData::parse_file(path, custom_database)
+            .map(IdentifiedPeptideIter::into_box)
+            .or_else(|oe| {
+                MetaMorpheusData::parse_file(path, custom_database)
+                    .map(IdentifiedPeptideIter::into_box)
+                    .map_err(|me| (oe, me))
+            })
+            .map_err(|(oe, me)| {
+                CustomError::error(
+                    "Unknown file format",
+                    "Could not be recognised as either an OPair or MetaMorpheus file",
+                    Context::show(path.to_string_lossy()),
+                )
+                .with_underlying_errors(vec![oe, me])
+            }),
+        Some("fasta" | "peff") => FastaData::parse_file(path).map(|peptides| {
             Box::new(peptides.into_iter().map(|p| Ok(p.into())))
                 as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
         }),
@@ -101,16 +121,53 @@ pub fn open_identified_peptides_file<'a>(
                     .map(IdentifiedPeptideIter::into_box)
                     .map_err(|ne| (me, ne))
             })
-            .map_err(|(me, ne)| {
+            .or_else(|(me, ne)| {
+                PercolatorData::parse_file(path, custom_database)
+                    .map(IdentifiedPeptideIter::into_box)
+                    .map_err(|pe| (me, ne, pe))
+            })
+            .or_else(|(me, ne, pe)| {
+                PepNovoData::parse_file(path, custom_database)
+                    .map(|peptides| {
+                        Box::new(peptides.into_iter().map(|p| p.map(Into::into)))
+                            as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
+                    })
+                    .map_err(|pne| (me, ne, pe, pne))
+            })
+            .map_err(|(me, ne, pe, pne)| {
                 CustomError::error(
                     "Unknown file format",
-                    "Could not be recognised as either a MaxQuant or NovoB file",
+                    "Could not be recognised as either a MaxQuant, NovoB, Percolator, or PepNovo file",
                     Context::show(path.to_string_lossy()),
                 )
-                .with_underlying_errors(vec![me, ne])
+                .with_underlying_errors(vec![me, ne, pe, pne])
             })
         }
-        Some("mztab") => MZTabData::parse_file(path, custom_database).map(|peptides| {
+        Some("pin" | "pout") => {
+            PercolatorData::parse_file(path, custom_database).map(IdentifiedPeptideIter::into_box)
+        }
+        Some("mztab") => CasanovoData::parse_file(path, custom_database)
+            .map(|peptides| {
+                Box::new(peptides.into_iter().map(|p| p.map(Into::into)))
+                    as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
+            })
+            .or_else(|ce| {
+                MZTabData::parse_file(path, custom_database)
+                    .map(|peptides| {
+                        Box::new(peptides.into_iter().map(|p| p.map(Into::into)))
+                            as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
+                    })
+                    .map_err(|me| (ce, me))
+            })
+            .map_err(|(ce, me)| {
+                CustomError::error(
+                    "Unknown file format",
+                    "Could not be recognised as either a Casanovo or mzTab file",
+                    Context::show(path.to_string_lossy()),
+                )
+                .with_underlying_errors(vec![ce, me])
+            }),
+        Some("res") => PNovoData::parse_file(path, custom_database).map(|peptides| {
             Box::new(peptides.into_iter().map(|p| p.map(Into::into)))
                 as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
         }),
@@ -120,9 +177,13 @@ pub fn open_identified_peptides_file<'a>(
         Some("ssl") => {
             SpectrumSequenceListData::parse_file(path, custom_database).map(IdentifiedPeptideIter::into_box)
         }
+        Some("xml") => XTandemData::parse_file(path, custom_database).map(|peptides| {
+            Box::new(peptides.into_iter().map(|p| p.map(Into::into)))
+                as Box<dyn Iterator<Item = Result<IdentifiedPeptide, CustomError>> + 'a>
+        }),
         _ => Err(CustomError::error(
             "Unknown extension",
-            "Use CSV, SSL, TSV, TXT, PSMTSV, deepnovo_denovo, or Fasta, or any of these as a gzipped file (eg csv.gz).",
+            "Use CSV, SSL, TSV, TXT, PSMTSV, PIN, POUT, mzTab, RES, XML, deepnovo_denovo, Fasta, or PEFF, or any of these as a gzipped file (eg csv.gz).",
             Context::show(path.to_string_lossy()),
         )),
     }
@@ -12,17 +12,19 @@ use crate::{
     error::CustomError,
     formula::MultiChemical,
     identification::{
-        deepnovofamily::DeepNovoFamilyData, fasta::FastaData, fasta::FastaIdentifier,
-        instanovo::InstaNovoData, novob::NovoBData, novor::NovorData, opair::OpairData,
-        peaks::PeaksData, pepnet::PepNetData, plink::PLinkData, powernovo::PowerNovoData,
-        system::MassOverCharge, MSFraggerData, MZTabData, MaxQuantData, PLGSData, SageData,
-        SpectrumSequenceListData,
+        casanovo::CasanovoData, deepnovofamily::DeepNovoFamilyData, fasta::FastaData,
+        fasta::FastaIdentifier, instanovo::InstaNovoData, mascot::MascotData,
+        metamorpheus::MetaMorpheusData, novob::NovoBData, novor::NovorData, opair::OpairData,
+        opair::OpairMatchKind, peaks::PeaksData, pepnet::PepNetData, pepnovo::PepNovoData,
+        plink::PLinkData, pnovo::PNovoData, powernovo::PowerNovoData, system::MassOverCharge,
+        xtandem::XTandemData, MSFraggerData, MZTabData, MaxQuantData, PLGSData, ParseReport,
+        PercolatorData, SageData, SpectrumSequenceListData,
     },
     ontologies::CustomDatabase,
     peptidoform::{SemiAmbiguous, SimpleLinear},
     system::usize::Charge,
     system::{OrderedTime, Time},
-    Peptidoform, PeptidoformIon,
+    Linear, Peptidoform, PeptidoformIon, RetentionTimeCalibration, RetentionTimePredictor,
 };
 
 use super::CompoundPeptidoformIon;
@@ -32,6 +34,12 @@ use super::CompoundPeptidoformIon;
 pub struct IdentifiedPeptide {
     /// The score -1.0..=1.0 if a score was available in the original format
     pub score: Option<f64>,
+    /// The score as originally given by the format, only set when the score was recomputed with
+    /// [`IdentifiedPeptide::rescore`]. This is `Some(None)` if this peptide was rescored but the
+    /// format did not give a score.
+    #[serde(default)]
+    #[allow(clippy::option_option)]
+    pub original_score: Option<Option<f64>>,
     /// The local confidence, if available, in range -1.0..=1.0
     pub local_confidence: Option<Vec<f64>>,
     /// The full metadata of this peptide
@@ -42,12 +50,20 @@ pub struct IdentifiedPeptide {
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 #[allow(clippy::large_enum_variant, clippy::upper_case_acronyms)]
 pub enum MetaData {
+    /// Casanovo metadata
+    Casanovo(CasanovoData),
     /// DeepNovo/PointNovo/PGPointNovo metadata
     DeepNovoFamily(DeepNovoFamilyData),
     /// Fasta metadata
     Fasta(FastaData),
+    /// Mascot metadata
+    Mascot(MascotData),
+    /// X!Tandem metadata
+    XTandem(XTandemData),
     /// MaxQuant metadata
     MaxQuant(MaxQuantData),
+    /// MetaMorpheus metadata
+    MetaMorpheus(MetaMorpheusData),
     /// InstaNovo metadata
     InstaNovo(InstaNovoData),
     /// MSFragger metadata
@@ -64,10 +80,16 @@ pub enum MetaData {
     Peaks(PeaksData),
     /// PepNet metadata
     PepNet(PepNetData),
+    /// PepNovo metadata
+    PepNovo(PepNovoData),
+    /// Percolator metadata
+    Percolator(PercolatorData),
     /// PLGS metadata
     PLGS(PLGSData),
     /// pLink metadata
     PLink(PLinkData),
+    /// pNovo metadata
+    PNovo(PNovoData),
     /// PowerNovo metadata
     PowerNovo(PowerNovoData),
     /// Sage metadata
@@ -172,14 +194,23 @@ impl IdentifiedPeptide {
             MetaData::Novor(NovorData { peptide, .. })
             | MetaData::InstaNovo(InstaNovoData { peptide, .. })
             | MetaData::Opair(OpairData { peptide, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { peptide, .. })
             | MetaData::PepNet(PepNetData { peptide, .. })
             | MetaData::PowerNovo(PowerNovoData { peptide, .. })
+            | MetaData::Casanovo(CasanovoData { peptide, .. })
+            | MetaData::Mascot(MascotData { peptide, .. })
+            | MetaData::XTandem(XTandemData { peptide, .. })
+            | MetaData::PepNovo(PepNovoData { peptide, .. })
+            | MetaData::PNovo(PNovoData { peptide, .. })
             | MetaData::Sage(SageData { peptide, .. }) => {
                 Some(ReturnedPeptide::LinearSemiAmbiguous(peptide))
             }
             MetaData::PLGS(PLGSData { peptide, .. }) => {
                 Some(ReturnedPeptide::LinearSimpleLinear(peptide))
             }
+            MetaData::Percolator(PercolatorData { peptide, .. }) => {
+                Some(ReturnedPeptide::LinearSemiAmbiguous(&peptide.1))
+            }
             MetaData::Peaks(PeaksData { peptide, .. }) => {
                 if peptide.1.len() == 1 {
                     Some(ReturnedPeptide::LinearSemiAmbiguous(&peptide.1[0]))
@@ -224,19 +255,26 @@ impl IdentifiedPeptide {
     pub const fn format_name(&self) -> &'static str {
         match &self.metadata {
             MetaData::SpectrumSequenceList(_) => "SpectrumSequenceList",
+            MetaData::Casanovo(_) => "Casanovo",
             MetaData::DeepNovoFamily(_) => "DeepNovo Family",
             MetaData::Fasta(_) => "Fasta",
             MetaData::InstaNovo(_) => "InstaNovo",
+            MetaData::Mascot(_) => "Mascot",
+            MetaData::XTandem(_) => "X!Tandem",
             MetaData::MaxQuant(_) => "MaxQuant",
             MetaData::MSFragger(_) => "MSFragger",
             MetaData::MZTab(_) => "mzTab",
             MetaData::NovoB(_) => "NovoB",
             MetaData::Novor(_) => "Novor",
             MetaData::Opair(_) => "OPair",
+            MetaData::MetaMorpheus(_) => "MetaMorpheus",
             MetaData::Peaks(_) => "PEAKS",
             MetaData::PepNet(_) => "PepNet",
+            MetaData::PepNovo(_) => "PepNovo",
+            MetaData::Percolator(_) => "Percolator",
             MetaData::PLGS(_) => "ProteinLynx Global Server",
             MetaData::PLink(_) => "pLink",
+            MetaData::PNovo(_) => "pNovo",
             MetaData::PowerNovo(_) => "PowerNovo",
             MetaData::Sage(_) => "Sage",
         }
@@ -248,19 +286,26 @@ impl IdentifiedPeptide {
             MetaData::SpectrumSequenceList(SpectrumSequenceListData { version, .. }) => {
                 version.to_string()
             }
+            MetaData::Casanovo(CasanovoData { version, .. }) => version.to_string(),
             MetaData::DeepNovoFamily(DeepNovoFamilyData { version, .. }) => version.to_string(),
             MetaData::Fasta(_) => "Fasta".to_string(),
             MetaData::InstaNovo(InstaNovoData { version, .. }) => version.to_string(),
+            MetaData::Mascot(MascotData { version, .. }) => version.to_string(),
+            MetaData::XTandem(XTandemData { version, .. }) => version.to_string(),
             MetaData::MaxQuant(MaxQuantData { version, .. }) => version.to_string(),
             MetaData::MSFragger(MSFraggerData { version, .. }) => version.to_string(),
             MetaData::MZTab(_) => "mzTab 1.0".to_string(),
             MetaData::NovoB(NovoBData { version, .. }) => version.to_string(),
             MetaData::Novor(NovorData { version, .. }) => version.to_string(),
             MetaData::Opair(OpairData { version, .. }) => version.to_string(),
+            MetaData::MetaMorpheus(MetaMorpheusData { version, .. }) => version.to_string(),
             MetaData::Peaks(PeaksData { version, .. }) => version.to_string(),
             MetaData::PepNet(PepNetData { version, .. }) => version.to_string(),
+            MetaData::PepNovo(PepNovoData { version, .. }) => version.to_string(),
+            MetaData::Percolator(PercolatorData { version, .. }) => version.to_string(),
             MetaData::PLGS(PLGSData { version, .. }) => version.to_string(),
             MetaData::PLink(PLinkData { version, .. }) => version.to_string(),
+            MetaData::PNovo(PNovoData { version, .. }) => version.to_string(),
             MetaData::PowerNovo(PowerNovoData { version, .. }) => version.to_string(),
             MetaData::Sage(SageData { version, .. }) => version.to_string(),
         }
@@ -283,12 +328,17 @@ impl IdentifiedPeptide {
             MetaData::DeepNovoFamily(DeepNovoFamilyData { scan, .. }) => scan.iter().join(";"),
             MetaData::Novor(NovorData { id, scan, .. }) => id.unwrap_or(*scan).to_string(),
             MetaData::Opair(OpairData { scan, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { scan, .. })
             | MetaData::NovoB(NovoBData { scan, .. })
             | MetaData::SpectrumSequenceList(SpectrumSequenceListData { scan, .. })
             | MetaData::InstaNovo(InstaNovoData { scan, .. }) => scan.to_string(),
-            MetaData::Sage(SageData { id, .. }) | MetaData::MZTab(MZTabData { id, .. }) => {
-                id.to_string()
-            }
+            MetaData::PepNovo(PepNovoData { spectrum_index, .. })
+            | MetaData::PNovo(PNovoData { spectrum_index, .. }) => spectrum_index.to_string(),
+            MetaData::Mascot(MascotData { query, .. }) => query.to_string(),
+            MetaData::Sage(SageData { id, .. })
+            | MetaData::MZTab(MZTabData { id, .. })
+            | MetaData::Casanovo(CasanovoData { id, .. })
+            | MetaData::XTandem(XTandemData { id, .. }) => id.to_string(),
             MetaData::Fasta(f) => f.identifier().accession().to_string(),
             MetaData::MSFragger(MSFraggerData { scan, .. }) => scan.to_string(),
             MetaData::PLink(PLinkData { order, .. }) => order.to_string(),
@@ -299,6 +349,7 @@ impl IdentifiedPeptide {
                 scan.as_ref().map_or("-".to_string(), ToString::to_string)
             }
             MetaData::PepNet(_) => "-".to_string(),
+            MetaData::Percolator(PercolatorData { id, .. }) => id.clone(),
             MetaData::PLGS(PLGSData {
                 peptide_component_id,
                 ..
@@ -317,6 +368,9 @@ impl IdentifiedPeptide {
             })
             | MetaData::PepNet(PepNetData {
                 local_confidence, ..
+            })
+            | MetaData::Casanovo(CasanovoData {
+                local_confidence, ..
             }) => Some(local_confidence),
 
             MetaData::Peaks(PeaksData {
@@ -340,6 +394,7 @@ impl IdentifiedPeptide {
         match &self.metadata {
             MetaData::Novor(NovorData { z, .. })
             | MetaData::Opair(OpairData { z, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { z, .. })
             | MetaData::Sage(SageData { z, .. })
             | MetaData::MSFragger(MSFraggerData { z, .. })
             | MetaData::MaxQuant(MaxQuantData { z, .. })
@@ -347,12 +402,18 @@ impl IdentifiedPeptide {
             | MetaData::PLGS(PLGSData { precursor_z: z, .. })
             | MetaData::PLink(PLinkData { z, .. })
             | MetaData::InstaNovo(InstaNovoData { z, .. })
-            | MetaData::MZTab(MZTabData { z, .. }) => Some(*z),
+            | MetaData::MZTab(MZTabData { z, .. })
+            | MetaData::Casanovo(CasanovoData { z, .. })
+            | MetaData::PepNovo(PepNovoData { z, .. })
+            | MetaData::Mascot(MascotData { z, .. })
+            | MetaData::XTandem(XTandemData { z, .. }) => Some(*z),
             MetaData::Peaks(PeaksData { z, .. })
-            | MetaData::DeepNovoFamily(DeepNovoFamilyData { z, .. }) => *z,
+            | MetaData::DeepNovoFamily(DeepNovoFamilyData { z, .. })
+            | MetaData::PNovo(PNovoData { z, .. }) => *z,
             MetaData::SpectrumSequenceList(SpectrumSequenceListData { z, .. }) => {
                 (z.value >= 0).then_some(Charge::new::<crate::system::charge::e>(z.value as usize))
             }
+            MetaData::Percolator(p) => p.charge(),
             MetaData::Fasta(_) | MetaData::PowerNovo(_) | MetaData::PepNet(_) => None,
         }
     }
@@ -366,11 +427,26 @@ impl IdentifiedPeptide {
         }
     }
 
+    /// If this peptide is a decoy, if the format reports this
+    pub const fn is_decoy(&self) -> Option<bool> {
+        match &self.metadata {
+            MetaData::Sage(SageData { decoy, .. }) => Some(*decoy),
+            MetaData::Percolator(PercolatorData { decoy, .. }) => *decoy,
+            MetaData::PLink(PLinkData { is_decoy, .. }) => Some(*is_decoy),
+            MetaData::Opair(OpairData { kind, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { kind, .. }) => {
+                Some(matches!(kind, OpairMatchKind::Decoy))
+            }
+            _ => None,
+        }
+    }
+
     /// The retention time, if known
     pub fn retention_time(&self) -> Option<Time> {
         match &self.metadata {
             MetaData::Peaks(PeaksData { rt, .. })
             | MetaData::Opair(OpairData { rt, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { rt, .. })
             | MetaData::Sage(SageData { rt, .. })
             | MetaData::PLGS(PLGSData {
                 precursor_rt: rt, ..
@@ -379,17 +455,40 @@ impl IdentifiedPeptide {
             MetaData::MaxQuant(MaxQuantData { rt, .. })
             | MetaData::Novor(NovorData { rt, .. })
             | MetaData::SpectrumSequenceList(SpectrumSequenceListData { rt, .. })
-            | MetaData::MZTab(MZTabData { rt, .. }) => *rt,
+            | MetaData::MZTab(MZTabData { rt, .. })
+            | MetaData::Casanovo(CasanovoData { rt, .. })
+            | MetaData::XTandem(XTandemData { rt, .. }) => *rt,
             MetaData::DeepNovoFamily(_)
             | MetaData::InstaNovo(_)
             | MetaData::Fasta(_)
             | MetaData::NovoB(_)
             | MetaData::PowerNovo(_)
             | MetaData::PepNet(_)
-            | MetaData::PLink(_) => None,
+            | MetaData::Percolator(_)
+            | MetaData::PLink(_)
+            | MetaData::PepNovo(_)
+            | MetaData::PNovo(_)
+            | MetaData::Mascot(_) => None,
         }
     }
 
+    /// The deviation of the observed retention time from the retention time predicted with the
+    /// given predictor and calibration (observed minus predicted). Returns `None` if the
+    /// retention time is not known, this peptide is not a linear peptide, or the predictor
+    /// cannot predict this peptide. This can be used to filter out peptides that deviate too
+    /// much from their expected retention time.
+    pub fn retention_time_deviation(
+        &self,
+        predictor: &(impl RetentionTimePredictor + ?Sized),
+        calibration: &RetentionTimeCalibration,
+    ) -> Option<Time> {
+        let rt = self.retention_time()?;
+        let peptide: Peptidoform<Linear> = self.peptide()?.peptide()?.into_owned().into();
+        calibration
+            .predict(predictor, &peptide)
+            .map(|predicted| rt - predicted)
+    }
+
     /// The scans per rawfile that are at the basis for this identified peptide, if the rawfile is unknown there will be one
     pub fn scans(&self) -> SpectrumIds {
         match &self.metadata {
@@ -427,6 +526,7 @@ impl IdentifiedPeptide {
             ),
 
             MetaData::Opair(OpairData { raw_file, scan, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { raw_file, scan, .. })
             | MetaData::SpectrumSequenceList(SpectrumSequenceListData { raw_file, scan, .. })
             | MetaData::InstaNovo(InstaNovoData { raw_file, scan, .. }) => {
                 SpectrumIds::FileKnown(vec![(raw_file.clone(), vec![SpectrumId::Index(*scan)])])
@@ -458,7 +558,8 @@ impl IdentifiedPeptide {
                     },
                 )
             }
-            MetaData::MZTab(MZTabData { spectra_ref, .. }) => spectra_ref.clone(),
+            MetaData::MZTab(MZTabData { spectra_ref, .. })
+            | MetaData::Casanovo(CasanovoData { spectra_ref, .. }) => spectra_ref.clone(),
             MetaData::MSFragger(MSFraggerData { raw_file, scan, .. }) => {
                 raw_file.clone().map_or_else(
                     || SpectrumIds::FileNotKnown(vec![scan.clone()]),
@@ -492,6 +593,16 @@ impl IdentifiedPeptide {
                 OrderedTime::from(*precursor_lift_off_rt)
                     ..=OrderedTime::from(*precursor_touch_down_rt),
             )]),
+            MetaData::Percolator(PercolatorData { scan, .. }) => scan
+                .map_or(SpectrumIds::None, |scan| {
+                    SpectrumIds::FileNotKnown(vec![SpectrumId::Index(scan)])
+                }),
+            MetaData::PepNovo(PepNovoData { spectrum_index, .. }) => {
+                SpectrumIds::FileNotKnown(vec![SpectrumId::Index(*spectrum_index)])
+            }
+            MetaData::PNovo(p) => p.scans(),
+            MetaData::Mascot(MascotData { scans, .. }) => scans.clone(),
+            MetaData::XTandem(x) => x.scans(),
             MetaData::Fasta(_) | MetaData::PepNet(_) => SpectrumIds::None,
         }
     }
@@ -502,14 +613,17 @@ impl IdentifiedPeptide {
             MetaData::Peaks(PeaksData { mz, .. })
             | MetaData::Novor(NovorData { mz, .. })
             | MetaData::Opair(OpairData { mz, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { mz, .. })
             | MetaData::InstaNovo(InstaNovoData { mz, .. })
             | MetaData::PLGS(PLGSData {
                 precursor_mz: mz, ..
             })
-            | MetaData::MSFragger(MSFraggerData { mz, .. }) => Some(*mz),
-            MetaData::MZTab(MZTabData { mz, .. }) | MetaData::MaxQuant(MaxQuantData { mz, .. }) => {
-                *mz
-            }
+            | MetaData::MSFragger(MSFraggerData { mz, .. })
+            | MetaData::Mascot(MascotData { mz, .. }) => Some(*mz),
+            MetaData::MZTab(MZTabData { mz, .. })
+            | MetaData::MaxQuant(MaxQuantData { mz, .. })
+            | MetaData::Casanovo(CasanovoData { mz, .. }) => *mz,
+            MetaData::XTandem(x) => Some(x.experimental_mz()),
             MetaData::Sage(SageData { mass, z, .. })
             | MetaData::NovoB(NovoBData { mass, z, .. })
             | MetaData::PLink(PLinkData { mass, z, .. }) => {
@@ -517,11 +631,20 @@ impl IdentifiedPeptide {
                     mass.value / (z.value as f64),
                 ))
             }
+            MetaData::Percolator(p) => {
+                p.mass
+                    .and_then(|mass| p.charge().map(|z| (mass, z)))
+                    .map(|(mass, z)| {
+                        MassOverCharge::new::<crate::system::mz>(mass.value / (z.value as f64))
+                    })
+            }
             MetaData::DeepNovoFamily(_)
             | MetaData::Fasta(_)
             | MetaData::SpectrumSequenceList(_)
             | MetaData::PowerNovo(_)
-            | MetaData::PepNet(_) => None,
+            | MetaData::PepNet(_)
+            | MetaData::PepNovo(_)
+            | MetaData::PNovo(_) => None,
         }
     }
 
@@ -533,6 +656,7 @@ impl IdentifiedPeptide {
             }
             MetaData::Novor(NovorData { mass, .. })
             | MetaData::Opair(OpairData { mass, .. })
+            | MetaData::MetaMorpheus(MetaMorpheusData { mass, .. })
             | MetaData::PLGS(PLGSData {
                 precursor_mass: mass,
                 ..
@@ -541,8 +665,14 @@ impl IdentifiedPeptide {
             | MetaData::MSFragger(MSFraggerData { mass, .. })
             | MetaData::PLink(PLinkData { mass, .. })
             | MetaData::Sage(SageData { mass, .. }) => Some(*mass),
-            MetaData::MaxQuant(MaxQuantData { mass, .. }) => *mass,
-            MetaData::MZTab(MZTabData { mz, z, .. }) => mz.map(|mz| mz * z.to_float()),
+            MetaData::MaxQuant(MaxQuantData { mass, .. })
+            | MetaData::Percolator(PercolatorData { mass, .. }) => *mass,
+            MetaData::Mascot(MascotData { mass, mz, z, .. }) => {
+                Some(mass.unwrap_or_else(|| *mz * z.to_float()))
+            }
+            MetaData::XTandem(x) => Some(x.experimental_mass()),
+            MetaData::MZTab(MZTabData { mz, z, .. })
+            | MetaData::Casanovo(CasanovoData { mz, z, .. }) => mz.map(|mz| mz * z.to_float()),
             MetaData::InstaNovo(InstaNovoData { mz, z, .. }) => Some(*mz * z.to_float()),
             MetaData::DeepNovoFamily(DeepNovoFamilyData { mz, z, .. }) => {
                 mz.and_then(|mz| z.map(|z| (mz, z)).map(|(mz, z)| mz * z.to_float()))
@@ -550,7 +680,9 @@ impl IdentifiedPeptide {
             MetaData::Fasta(_)
             | MetaData::PowerNovo(_)
             | MetaData::SpectrumSequenceList(_)
-            | MetaData::PepNet(_) => None,
+            | MetaData::PepNet(_)
+            | MetaData::PepNovo(_)
+            | MetaData::PNovo(_) => None,
         }
     }
 
@@ -593,6 +725,9 @@ impl IdentifiedPeptide {
                 protein_accession, ..
             }) => protein_accession.clone(),
             MetaData::Opair(OpairData { protein_name, .. }) => Some(protein_name.clone()),
+            MetaData::MetaMorpheus(MetaMorpheusData { accession, .. }) => accession
+                .first()
+                .map(|a| FastaIdentifier::Undefined(a.clone())),
             MetaData::PLGS(PLGSData {
                 protein_description,
                 ..
@@ -601,6 +736,17 @@ impl IdentifiedPeptide {
             MetaData::MZTab(MZTabData { accession, .. }) => accession
                 .as_ref()
                 .map(|a| FastaIdentifier::Undefined(a.clone())),
+            MetaData::Percolator(PercolatorData { proteins, .. }) => proteins
+                .first()
+                .map(|p| FastaIdentifier::Undefined(p.clone())),
+            MetaData::Mascot(MascotData {
+                protein_accession, ..
+            }) => protein_accession
+                .as_ref()
+                .map(|a| FastaIdentifier::Undefined(a.clone())),
+            MetaData::XTandem(XTandemData { domains, .. }) => {
+                domains.first().map(|d| d.protein.clone())
+            }
             MetaData::NovoB(_)
             | MetaData::MaxQuant(_)
             | MetaData::Sage(_)
@@ -611,7 +757,10 @@ impl IdentifiedPeptide {
             | MetaData::InstaNovo(_)
             | MetaData::PowerNovo(_)
             | MetaData::SpectrumSequenceList(_)
-            | MetaData::PepNet(_) => None,
+            | MetaData::PepNet(_)
+            | MetaData::Casanovo(_)
+            | MetaData::PepNovo(_)
+            | MetaData::PNovo(_) => None,
         }
     }
 
@@ -628,12 +777,19 @@ impl IdentifiedPeptide {
             | MetaData::PLink(_)
             | MetaData::NovoB(_)
             | MetaData::Opair(_)
+            | MetaData::MetaMorpheus(_)
             | MetaData::Fasta(_)
             | MetaData::PowerNovo(_)
             | MetaData::DeepNovoFamily(_)
             | MetaData::SpectrumSequenceList(_)
             | MetaData::InstaNovo(_)
-            | MetaData::PepNet(_) => None,
+            | MetaData::PepNet(_)
+            | MetaData::Percolator(_)
+            | MetaData::Casanovo(_)
+            | MetaData::PepNovo(_)
+            | MetaData::PNovo(_)
+            | MetaData::Mascot(_)
+            | MetaData::XTandem(_) => None,
         }
     }
 
@@ -649,6 +805,9 @@ impl IdentifiedPeptide {
             MetaData::Opair(OpairData {
                 protein_location, ..
             }) => Some(protein_location.clone()),
+            MetaData::MetaMorpheus(MetaMorpheusData {
+                protein_location, ..
+            }) => protein_location.first().cloned(),
             MetaData::PLGS(PLGSData {
                 peptide_start,
                 peptide,
@@ -660,6 +819,9 @@ impl IdentifiedPeptide {
                 ..
             }) => Some(*protein_start..*protein_end),
             MetaData::MZTab(MZTabData { start, end, .. }) => start.and_then(|s| end.map(|e| s..e)),
+            MetaData::XTandem(XTandemData { domains, .. }) => {
+                domains.first().map(|d| d.location.clone())
+            }
             MetaData::InstaNovo(_)
             | MetaData::DeepNovoFamily(_)
             | MetaData::MaxQuant(_)
@@ -669,7 +831,12 @@ impl IdentifiedPeptide {
             | MetaData::Fasta(_)
             | MetaData::PowerNovo(_)
             | MetaData::SpectrumSequenceList(_)
-            | MetaData::PepNet(_) => None,
+            | MetaData::PepNet(_)
+            | MetaData::Percolator(_)
+            | MetaData::Casanovo(_)
+            | MetaData::PepNovo(_)
+            | MetaData::PNovo(_)
+            | MetaData::Mascot(_) => None,
         }
     }
 
@@ -804,6 +971,41 @@ where
         custom_database: Option<&'a CustomDatabase>,
     ) -> Result<BoxedIdentifiedPeptideIter<'a, Self>, CustomError>;
 
+    /// Parse a file with identified peptides while collecting all row level errors into a single
+    /// report instead of stopping at the first error. Parsing stops as soon as more than
+    /// `error_budget` errors are found, if no budget is given the whole file is parsed. The
+    /// default implementation collects the results of [`Self::parse_file`], formats can override
+    /// this to report the offending columns as well.
+    /// # Errors
+    /// Returns Err when the file could not be opened or no headers are present.
+    fn parse_file_with_report(
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&CustomDatabase>,
+        error_budget: Option<usize>,
+    ) -> Result<ParseReport<Self>, CustomError>
+    where
+        Self::Format: 'static,
+    {
+        Self::parse_file(path, custom_database)
+            .map(|peptides| ParseReport::from_results(peptides, error_budget))
+    }
+
+    /// Parse a reader with identified peptides while collecting all row level errors, see
+    /// [`Self::parse_file_with_report`].
+    /// # Errors
+    /// When the file is empty or no headers are present.
+    fn parse_reader_with_report(
+        reader: impl std::io::Read,
+        custom_database: Option<&CustomDatabase>,
+        error_budget: Option<usize>,
+    ) -> Result<ParseReport<Self>, CustomError>
+    where
+        Self::Format: 'static,
+    {
+        Self::parse_reader(reader, custom_database)
+            .map(|peptides| ParseReport::from_results(peptides, error_budget))
+    }
+
     /// Allow post processing of the peptide
     /// # Errors
     /// On errors in the post processing, format specific
@@ -0,0 +1,364 @@
+//! Random access into (large) identified peptide files
+
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufRead, BufReader, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    csv::{csv_header, parse_csv_raw, CsvLine, CsvLineIter},
+    error::{Context, CustomError},
+    helper_functions::{check_extension, open_file},
+    identification::{IdentifiedPeptide, IdentifiedPeptideSource, SpectrumIds},
+    ontologies::CustomDatabase,
+};
+
+/// An identified peptide source that is read from CSV files, this is implemented for all CSV
+/// based formats.
+pub trait CsvIdentifiedPeptideSource: IdentifiedPeptideSource<Source = CsvLine> {
+    /// The column separator
+    const SEPARATOR: u8;
+    /// The column headers, if these are not present in the file itself
+    fn header() -> Option<Vec<String>>;
+    /// If the last column spans all remaining fields, for formats that separate the items of a
+    /// list in the last column with the same separator as the columns
+    const TRAILING_LIST: bool = false;
+    /// Check if this line contains metadata instead of a row, these lines are skipped
+    fn is_metadata_line(_line: &str) -> bool {
+        false
+    }
+    /// Read the lines of a file in this format
+    /// # Errors
+    /// If no header is provided and the first line could not be read as a header line.
+    fn csv_lines<R: std::io::Read>(reader: R) -> Result<CsvLineIter<R>, CustomError> {
+        parse_csv_raw(reader, Self::SEPARATOR, Self::header()).map(|lines| {
+            lines
+                .skip_lines(Self::is_metadata_line)
+                .trailing_list(Self::TRAILING_LIST)
+        })
+    }
+}
+
+/// The index of an identified peptides file, this stores the byte offset of every row and the
+/// rows for every raw file. This can be stored (it is serialisable) to prevent having to index
+/// the same file multiple times.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct IdentifiedPeptideFileIndex {
+    separator: u8,
+    header: Vec<String>,
+    /// The line index and byte offset of every row
+    rows: Vec<(usize, u64)>,
+    /// The rows for every raw file, `None` for rows where the raw file is not known
+    raw_files: BTreeMap<Option<PathBuf>, Vec<usize>>,
+}
+
+impl IdentifiedPeptideFileIndex {
+    /// Get the number of rows
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+
+    /// Check if there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Get all raw files, `None` is used for rows where the raw file is not known
+    pub fn raw_files(&self) -> impl Iterator<Item = Option<&Path>> {
+        self.raw_files.keys().map(Option::as_deref)
+    }
+
+    /// Get the indices of all rows for the given raw file, the raw file has to be identical to
+    /// the raw file as reported in the identified peptide file
+    pub fn rows(&self, raw_file: Option<&Path>) -> &[usize] {
+        self.raw_files
+            .get(&raw_file.map(Path::to_path_buf))
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// An identified peptides file with random access to all rows. When opened the whole file is read
+/// once to build an [index](IdentifiedPeptideFileIndex), after that single rows or all rows for a
+/// single raw file can be read without reading the full file again. Compressed (gz) files are not
+/// supported as these do not support random access.
+///
+/// ```rust
+/// # use rustyms::identification::*;
+/// # use std::path::Path;
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// let mut file = IndexedIdentifiedPeptideFile::<SageData>::open(
+///     "src/identification/test_files/sage_v0_14.tsv",
+///     None,
+/// )?;
+/// assert_eq!(file.len(), 19);
+/// let raw_file = Path::new("20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mzML");
+/// assert_eq!(file.raw_file(Some(raw_file)).count(), 8);
+/// # Ok(())
+/// # }
+/// ```
+pub struct IndexedIdentifiedPeptideFile<'a, T: CsvIdentifiedPeptideSource> {
+    path: PathBuf,
+    reader: BufReader<File>,
+    index: IdentifiedPeptideFileIndex,
+    format: Option<T::Format>,
+    custom_database: Option<&'a CustomDatabase>,
+}
+
+impl<'a, T> IndexedIdentifiedPeptideFile<'a, T>
+where
+    T: CsvIdentifiedPeptideSource + Into<IdentifiedPeptide> + Clone,
+    T::Format: 'static,
+{
+    /// Open a file and build the index, this parses all rows in the file once.
+    /// # Errors
+    /// If the file could not be opened, if it is compressed, or if any row could not be parsed.
+    pub fn open(
+        path: impl AsRef<Path>,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let path = path.as_ref();
+        let mut reader = open(path)?;
+        let mut separator = T::SEPARATOR;
+        let mut header = T::header();
+        let mut rows = Vec::new();
+        let mut raw_files: BTreeMap<Option<PathBuf>, Vec<usize>> = BTreeMap::new();
+        let mut format = None;
+        let mut offset = 0;
+        let mut line_index = 0;
+        let mut line = String::new();
+        loop {
+            let start = offset;
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(|err| {
+                CustomError::error(
+                    "Could not read line",
+                    err,
+                    Context::full_line(line_index, "(failed)"),
+                )
+            })?;
+            if read == 0 {
+                break;
+            }
+            offset += read as u64;
+            let text = trim_newline(&line);
+            if line_index == 0 && header.is_none() {
+                if let Some(sep) = text.strip_prefix("sep=") {
+                    separator = sep.bytes().next().unwrap_or(separator);
+                    line_index += 1;
+                    continue;
+                }
+            }
+            if header.is_some() && T::is_metadata_line(text) {
+                line_index += 1;
+                continue;
+            }
+            if let Some(header) = &header {
+                let source = CsvLine::new_with_trailing_list(
+                    line_index,
+                    text.to_string(),
+                    header,
+                    separator,
+                    T::TRAILING_LIST,
+                )?;
+                let peptide: IdentifiedPeptide = if let Some(format) = &format {
+                    T::parse_specific(&source, format, custom_database)?
+                } else {
+                    let (peptide, detected) = T::parse(&source, custom_database)?;
+                    format = Some(detected.clone());
+                    peptide
+                }
+                .into();
+                let row = rows.len();
+                match peptide.scans() {
+                    SpectrumIds::FileKnown(files) => {
+                        for (file, _) in files {
+                            let file_rows = raw_files.entry(Some(file)).or_default();
+                            if file_rows.last() != Some(&row) {
+                                file_rows.push(row);
+                            }
+                        }
+                    }
+                    SpectrumIds::FileNotKnown(_) | SpectrumIds::None => {
+                        raw_files.entry(None).or_default().push(row);
+                    }
+                }
+                rows.push((line_index, start));
+            } else {
+                header = Some(csv_header(text, separator)?);
+            }
+            line_index += 1;
+        }
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader,
+            index: IdentifiedPeptideFileIndex {
+                separator,
+                header: header.unwrap_or_default(),
+                rows,
+                raw_files,
+            },
+            format,
+            custom_database,
+        })
+    }
+
+    /// Open a file with a previously built index. The index is not validated, so if the file
+    /// changed since the index was built any row could give an error or the wrong data.
+    /// # Errors
+    /// If the file could not be opened or if it is compressed.
+    pub fn open_with_index(
+        path: impl AsRef<Path>,
+        index: IdentifiedPeptideFileIndex,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let path = path.as_ref();
+        Ok(Self {
+            path: path.to_path_buf(),
+            reader: open(path)?,
+            index,
+            format: None,
+            custom_database,
+        })
+    }
+
+    /// Get the path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Get the index, this can be stored to open this file again later without having to
+    /// rebuild the index, see [`Self::open_with_index`].
+    pub const fn index(&self) -> &IdentifiedPeptideFileIndex {
+        &self.index
+    }
+
+    /// Get the number of rows
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check if there are no rows
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Read the peptide at the given row, returns None if the row is out of bounds.
+    pub fn get(&mut self, row: usize) -> Option<Result<T, CustomError>> {
+        let (line_index, offset) = *self.index.rows.get(row)?;
+        Some(self.read(line_index, offset))
+    }
+
+    /// Stream all peptides for the given raw file, see [`IdentifiedPeptideFileIndex::rows`].
+    pub fn raw_file(
+        &mut self,
+        raw_file: Option<&Path>,
+    ) -> Box<dyn Iterator<Item = Result<T, CustomError>> + '_> {
+        let rows = self.index.rows(raw_file).to_vec();
+        Box::new(rows.into_iter().filter_map(move |row| self.get(row)))
+    }
+
+    /// Read a single row
+    /// # Errors
+    /// If the row could not be read or parsed.
+    fn read(&mut self, line_index: usize, offset: u64) -> Result<T, CustomError> {
+        let mut line = String::new();
+        self.reader
+            .seek(SeekFrom::Start(offset))
+            .and_then(|_| self.reader.read_line(&mut line))
+            .map_err(|err| {
+                CustomError::error(
+                    "Could not read line",
+                    err,
+                    Context::full_line(line_index, "(failed)"),
+                )
+            })?;
+        let source = CsvLine::new_with_trailing_list(
+            line_index,
+            trim_newline(&line).to_string(),
+            &self.index.header,
+            self.index.separator,
+            T::TRAILING_LIST,
+        )?;
+        if let Some(format) = &self.format {
+            T::parse_specific(&source, format, self.custom_database)
+        } else {
+            let (peptide, format) = T::parse(&source, self.custom_database)?;
+            self.format = Some(format.clone());
+            Ok(peptide)
+        }
+    }
+}
+
+/// Open a file for random access
+/// # Errors
+/// If the file could not be opened or is compressed.
+fn open(path: &Path) -> Result<BufReader<File>, CustomError> {
+    if check_extension(path, "gz") {
+        return Err(CustomError::error(
+            "Could not index file",
+            "Compressed files do not support random access",
+            Context::show(path.display()),
+        ));
+    }
+    open_file(path).map(BufReader::new).map_err(|err| {
+        CustomError::error("Could not open file", err, Context::show(path.display()))
+    })
+}
+
+/// Remove the line ending (`\n` or `\r\n`)
+fn trim_newline(line: &str) -> &str {
+    line.strip_suffix('\n')
+        .map_or(line, |l| l.strip_suffix('\r').unwrap_or(l))
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::SageData;
+
+    #[test]
+    fn sage() {
+        let path = "src/identification/test_files/sage_v0_14.tsv";
+        let mut file = IndexedIdentifiedPeptideFile::<SageData>::open(path, None).unwrap();
+        let all = SageData::parse_file(path, None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(file.len(), all.len());
+        assert_eq!(file.get(5).unwrap().unwrap(), all[5]);
+        assert_eq!(file.get(0).unwrap().unwrap(), all[0]);
+        assert!(file.get(all.len()).is_none());
+
+        let raw_files = file.index().raw_files().collect::<Vec<_>>();
+        assert!(raw_files.iter().all(Option::is_some));
+        let total: usize = raw_files
+            .iter()
+            .map(|raw_file| file.index().rows(*raw_file).len())
+            .sum();
+        assert_eq!(total, all.len());
+
+        let raw_file = Path::new("20240113_EX3_UM5_Peng0013_SA_EXT00_GluC_2h_standard.mzML");
+        let peptides = file
+            .raw_file(Some(raw_file))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            peptides,
+            all.iter()
+                .filter(|p| p.raw_file == raw_file)
+                .cloned()
+                .collect::<Vec<_>>()
+        );
+
+        let index = bincode::deserialize(&bincode::serialize(file.index()).unwrap()).unwrap();
+        let mut reopened =
+            IndexedIdentifiedPeptideFile::<SageData>::open_with_index(path, index, None).unwrap();
+        assert_eq!(reopened.get(18).unwrap().unwrap(), all[18]);
+    }
+}
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter,
 };
 
@@ -30,7 +30,7 @@ format_family!(
     InstaNovoFormat,
     /// The data from any InstaNovo file
     InstaNovoData,
-    InstaNovoVersion, [&INSTANOVO_V1_0_0], b',', None;
+    InstaNovoVersion, [&INSTANOVO_V1_0_0, &INSTANOVO_V1_1_0], b',', None;
     required {
         scan: usize, |location: Location, _| location.parse(NUMBER_ERROR);
         mz: MassOverCharge, |location: Location, _| location.parse::<f64>(NUMBER_ERROR).map(MassOverCharge::new::<crate::system::mz>);
@@ -62,6 +62,7 @@ impl From<InstaNovoData> for IdentifiedPeptide {
     fn from(value: InstaNovoData) -> Self {
         Self {
             score: Some(2.0 / (1.0 + 1.01_f64.powf(-value.score))),
+            original_score: None,
             local_confidence: Some(
                 value
                     .local_confidence
@@ -74,7 +75,7 @@ impl From<InstaNovoData> for IdentifiedPeptide {
     }
 }
 
-/// The only known version of InstaNovo
+/// InstaNovo version 1.0.0, with modifications as mass shifts (`S(+79.97)`)
 pub const INSTANOVO_V1_0_0: InstaNovoFormat = InstaNovoFormat {
     version: InstaNovoVersion::V1_0_0,
     scan: "scan_number",
@@ -86,6 +87,18 @@ pub const INSTANOVO_V1_0_0: InstaNovoFormat = InstaNovoFormat {
     local_confidence: "token_log_probs",
 };
 
+/// InstaNovo version 1.1.0, with modifications as Unimod accessions (`M[UNIMOD:35]`)
+pub const INSTANOVO_V1_1_0: InstaNovoFormat = InstaNovoFormat {
+    version: InstaNovoVersion::V1_1_0,
+    scan: "scan_number",
+    mz: "precursor_mz",
+    z: "precursor_charge",
+    raw_file: "experiment_name",
+    peptide: "predictions",
+    score: "log_probabilities",
+    local_confidence: "token_log_probabilities",
+};
+
 /// All possible InstaNovo versions
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
 #[allow(non_camel_case_types)]
@@ -93,6 +106,8 @@ pub enum InstaNovoVersion {
     #[default]
     /// InstaNovo version 1.0.0
     V1_0_0,
+    /// InstaNovo version 1.1.0
+    V1_1_0,
 }
 
 impl std::fmt::Display for InstaNovoVersion {
@@ -102,6 +117,7 @@ impl std::fmt::Display for InstaNovoVersion {
             "{}",
             match self {
                 Self::V1_0_0 => "v1.0.0",
+                Self::V1_1_0 => "v1.1.0",
             }
         )
     }
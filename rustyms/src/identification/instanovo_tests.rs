@@ -19,6 +19,23 @@ fn instanovo() {
     }
 }
 
+#[test]
+fn instanovo_v1_1_0() {
+    match test_format::<InstaNovoData>(
+        BufReader::new(INSTANOVO_V1_1_0.as_bytes()),
+        None,
+        false,
+        true,
+        Some(InstaNovoVersion::V1_1_0),
+    ) {
+        Ok(n) => assert_eq!(n, 3),
+        Err(e) => {
+            println!("{e}");
+            panic!("Failed identified peptides test");
+        }
+    }
+}
+
 const INSTANOVO_V1_0_0: &str = r#"scan_number,precursor_mz,precursor_charge,experiment_name,spectrum_id,preds,preds_tokenised,log_probs,token_log_probs
 0,1353.116333007813,4,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:0,LKVKVILEAEPS(+79.97)EEEEEEEEEEEEEEEEEEEEEEEEKEEK,"L, K, V, K, V, I, L, E, A, E, P, S(+79.97), E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, E, K, E, E, K",-47.14482498168945,"[-1.1898047924041748, -1.2532058954238892, -1.4706779718399048, -1.578391671180725, -1.910727858543396, -0.4288635551929474, -0.10262472927570343, -0.2335159033536911, -0.3816143870353699, -0.1399289071559906, -0.2679944634437561, -0.37487441301345825, -0.28003591299057007, -0.29957395792007446, -0.6062297224998474, -1.0798466205596924, -1.3055310249328613, -1.1969765424728394, -0.8466325402259827, -0.7559331655502319, -0.8520379066467285, -1.1635522842407227, -1.5230286121368408, -1.5223480463027954, -1.3874539136886597, -1.3555835485458374, -1.3308098316192627, -1.461938738822937, -1.292738437652588, -1.7667877674102783, -1.8383617401123047, -1.924727439880371, -1.5695301294326782, -1.4049240350723267, -1.2322568893432617, -1.1730256080627441, -0.09055394679307938, -3.6145036220550537, -1.8250231742858887, -3.1126556396484375]"
 1,1353.116333007813,4,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:1,SSSSTSGS(+79.97)DC(+57.02)DGVHVEPEEEDMES(+79.97)EDEDEDEDLVTSTTSK,"S, S, S, S, T, S, G, S(+79.97), D, C(+57.02), D, G, V, H, V, E, P, E, E, E, D, M, E, S(+79.97), E, D, E, D, E, D, E, D, L, V, T, S, T, T, S, K",-33.68059539794922,"[-0.07847003638744354, -0.5382011532783508, -0.38900521397590637, -0.3034709692001343, -0.14283983409404755, -0.004337664693593979, -0.005992896854877472, -0.443778932094574, -2.0242862701416016, -1.3575999736785889, -1.3120659589767456, -0.8160025477409363, -0.9171149730682373, -0.1492014229297638, -0.28191035985946655, -1.03749680519104, -0.7952876687049866, -0.11219097673892975, -0.6492378115653992, -0.0394880585372448, -0.35166993737220764, -0.031147046014666557, -0.1014118641614914, -0.8919384479522705, -0.5123100876808167, -1.5009464025497437, -0.7995803952217102, -0.8618601560592651, -0.74873948097229, -1.0185350179672241, -1.0649776458740234, -1.9072270393371582, -1.3915926218032837, -1.454406499862671, -0.8135812878608704, -0.7613984942436218, -1.0519371032714844, -2.232295274734497, -2.410634994506836, -2.3764281272888184]"
@@ -41,3 +58,9 @@ const INSTANOVO_V1_0_0: &str = r#"scan_number,precursor_mz,precursor_charge,expe
 18,1353.363037109375,4,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:18,GSGSEGGKPGWGWTHSRGYSFPDYDSNHSSSYVTVTSGST,"G, S, G, S, E, G, G, K, P, G, W, G, W, T, H, S, R, G, Y, S, F, P, D, Y, D, S, N, H, S, S, S, Y, V, T, V, T, S, G, S, T",-57.210357666015625,"[-0.08833134174346924, -0.010024912655353546, -0.000341476290486753, -0.0877658873796463, -0.018817594274878502, -1.3214493989944458, -0.3156588673591614, -0.9006086587905884, -1.6607422828674316, -1.2461398839950562, -1.5090794563293457, -0.9961817264556885, -1.3333173990249634, -1.5122567415237427, -0.4001505672931671, -1.5853387117385864, -1.7091730833053589, -0.7536590099334717, -1.4411301612854004, -1.387695550918579, -0.7693523168563843, -0.2747490704059601, -0.81729656457901, -0.8858135342597961, -1.272748589515686, -0.744476318359375, -2.2402219772338867, -1.3256865739822388, -1.1227340698242188, -1.442939043045044, -3.3821933269500732, -2.627223014831543, -2.360701322555542, -0.5053297281265259, -0.08112901449203491, -4.031546115875244, -3.548017978668213, -3.7473630905151367, -4.817002296447754, -2.935967206954956]"
 19,1353.363037109375,4,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:19,GGGGSAGGGGGAAGGRPS(+79.97)PPQENGKEPC(+57.02)EPSQS(+79.97)VTSTSEK,"G, G, G, G, S, A, G, G, G, G, G, A, A, G, G, R, P, S(+79.97), P, P, Q, E, N, G, K, E, P, C(+57.02), E, P, S, Q, S(+79.97), V, T, S, T, S, E, K",-34.095943450927734,"[-0.3195824921131134, -1.3936655521392822, -1.1236826181411743, -0.7992289066314697, -0.2012583613395691, -0.005753267090767622, -0.07621022313833237, -1.300059199333191, -1.4463657140731812, -1.1221880912780762, -0.6981537938117981, -1.6815447807312012, -0.3504905104637146, -1.444809913635254, -1.5379170179367065, -0.9235746264457703, -0.9498796463012695, -0.26199230551719666, -0.04976364225149155, -0.6369405388832092, -0.9404551982879639, -0.29938265681266785, -1.3071815967559814, -0.10240114480257034, -0.7078803777694702, -0.22844867408275604, -0.8289092779159546, -0.9006180763244629, -1.3896540403366089, -0.18864311277866364, -0.2939540445804596, -0.9182708859443665, -0.897458553314209, -1.0683497190475464, -0.998686671257019, -1.3441799879074097, -1.3926314115524292, -1.3044145107269287, -1.4491615295410156, -1.2121996879577637]"
 20,1348.856201171875,4,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:20,GSGSGRGS(+79.97)WGSGGGHSSYSQHFNKPIFLKPGQWTWISGTS,"G, S, G, S, G, R, G, S(+79.97), W, G, S, G, G, G, H, S, S, Y, S, Q, H, F, N, K, P, I, F, L, K, P, G, Q, W, T, W, I, S, G, T, S",-62.733360290527344,"[-0.3141501843929291, -0.14591962099075317, -0.4127911925315857, -0.0423421785235405, -0.1893325001001358, -1.3861042261123657, -0.8396965861320496, -1.3329275846481323, -1.982820987701416, -1.4959850311279297, -1.1631689071655273, -0.4160800278186798, -1.9834104776382446, -1.8969424962997437, -1.2355120182037354, -0.7272624373435974, -0.49926871061325073, -1.3329787254333496, -1.0549516677856445, -0.562333345413208, -0.6821218729019165, -0.908167839050293, -0.9920955896377563, -1.0761539936065674, -0.5029982328414917, -1.943763017654419, -1.5800557136535645, -1.903761863708496, -0.6185683608055115, -1.488539218902588, -0.8119556307792664, -3.762935161590576, -4.434981822967529, -2.770512104034424, -1.9104329347610474, -3.1854987144470215, -3.1550867557525635, -4.076790809631348, -4.346488952636719, -3.5684683322906494]""#;
+
+const INSTANOVO_V1_1_0: &str = r#"experiment_name,scan_number,spectrum_id,precursor_mz,precursor_charge,predictions,predictions_tokenised,log_probabilities,token_log_probabilities
+20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,10,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:10,563.2816162109375,2,LVNELTEFAK,"L, V, N, E, L, T, E, F, A, K",-0.4122,"[-0.0101, -0.0512, -0.0213, -0.0034, -0.0105, -0.0098, -0.0041, -0.0022, -0.0154, -0.0011]"
+20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,11,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:11,612.7720947265625,2,AC[UNIMOD:4]DM[UNIMOD:35]TKPEK,"A, C[UNIMOD:4], D, M[UNIMOD:35], T, K, P, E, K",-1.8934,"[-0.2101, -0.1512, -0.3213, -0.0834, -0.0105, -0.4098, -0.0041, -0.0622, -0.0154]"
+20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp,12,20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp:12,445.2291870117188,2,[UNIMOD:1]SAGEN[UNIMOD:7]K,"[UNIMOD:1], S, A, G, E, N[UNIMOD:7], K",-3.1021,"[-0.5101, -0.6512, -0.3213, -0.2834, -0.1105, -0.7098]"
+"#;
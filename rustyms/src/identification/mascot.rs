@@ -0,0 +1,473 @@
+use std::{io::BufRead, ops::Range, path::PathBuf, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    csv::{csv_header, csv_separate, CsvLine},
+    error::{Context, CustomError},
+    helper_functions::open_reader,
+    identification::{FlankingResidue, IdentifiedPeptide, MetaData, SpectrumId, SpectrumIds},
+    modification::SimpleModification,
+    ontologies::CustomDatabase,
+    system::{usize::Charge, Mass, MassOverCharge},
+    Modification, Peptidoform, SemiAmbiguous, SloppyParsingParameters,
+};
+
+/// The Regex to match against msconvert style titles (`File:"raw", NativeID:"id"`)
+static NATIVE_TITLE_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+/// The Regex to match against dotted titles (`raw.scan.scan.charge`)
+static DOTTED_TITLE_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+
+/// A single peptide match from a Mascot CSV export
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct MascotData {
+    /// The version of the Mascot export
+    pub version: MascotVersion,
+    /// The query number (one based), the order of the spectrum in the searched peak list
+    pub query: usize,
+    /// The rank of this peptide for this query (one based)
+    pub rank: usize,
+    /// The peptide with all fixed and variable modifications placed
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The accession of the protein, if exported
+    pub protein_accession: Option<String>,
+    /// The description of the protein, if exported
+    pub protein_description: Option<String>,
+    /// The experimental mz
+    pub mz: MassOverCharge,
+    /// The experimental (neutral) mass, if exported
+    pub mass: Option<Mass>,
+    /// The precursor charge
+    pub z: Charge,
+    /// The calculated (neutral) mass of the peptide, if exported
+    pub theoretical_mass: Option<Mass>,
+    /// The number of missed cleavages, if exported
+    pub missed_cleavages: Option<usize>,
+    /// The ions score
+    pub score: f64,
+    /// The expectation value, if exported
+    pub expect: Option<f64>,
+    /// The residue before this peptide in the protein
+    pub preceding_aa: FlankingResidue,
+    /// The residue after this peptide in the protein
+    pub following_aa: FlankingResidue,
+    /// The scan title, if exported
+    pub title: Option<String>,
+    /// The spectra resolved from the scan title, or the query number if no title was exported
+    pub scans: SpectrumIds,
+}
+
+/// The modifications defined in the header of a Mascot CSV export
+#[derive(Clone, Debug, Default)]
+struct MascotModifications {
+    /// The fixed modifications with the name as given in the file
+    fixed: Vec<(String, SimpleModification)>,
+    /// The variable modifications with their identifier as used in `pep_var_mod_pos`
+    variable: Vec<(u32, SimpleModification)>,
+}
+
+/// The section of the header of a Mascot CSV export
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Section {
+    Fixed,
+    Variable,
+    Other,
+}
+
+impl MascotData {
+    /// Parse a Mascot CSV export.
+    /// # Errors
+    /// If the file could not be opened or the modifications in the header are invalid.
+    pub fn parse_file(
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
+        Ok(Box::new(Self::parse_reader(
+            open_reader(path)?,
+            custom_database,
+        )?))
+    }
+
+    /// Parse a Mascot CSV export directly from a buffered reader. The header is read eagerly to
+    /// find the fixed and variable modifications, the peptide matches are read lazily. Both the
+    /// peptides assigned to protein hits and the unassigned peptides are returned.
+    /// # Errors
+    /// If the header could not be read, or the modifications in the header are invalid, or no
+    /// peptide table is present.
+    pub fn parse_reader<'a, T: BufRead + 'a>(
+        reader: T,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<impl Iterator<Item = Result<Self, CustomError>> + 'a, CustomError> {
+        let mut lines = reader.lines().enumerate();
+        let mut modifications = MascotModifications::default();
+        let mut section = Section::Other;
+        let mut header = None;
+        for (line_index, line) in lines.by_ref() {
+            let line = line.map_err(|err| {
+                CustomError::error(
+                    "Could not read line",
+                    err,
+                    Context::full_line(line_index, "(failed)"),
+                )
+            })?;
+            let trimmed = line.trim();
+            if is_table_header(trimmed) {
+                header = Some(csv_header(trimmed, b',')?);
+                break;
+            } else if trimmed.is_empty() {
+                section = Section::Other;
+            } else if trimmed.eq_ignore_ascii_case("\"fixed modifications\"") {
+                section = Section::Fixed;
+            } else if trimmed.eq_ignore_ascii_case("\"variable modifications\"") {
+                section = Section::Variable;
+            } else if section != Section::Other
+                && trimmed.starts_with(|c: char| c.is_ascii_alphanumeric())
+                && !trimmed.starts_with("Identifier")
+            {
+                modifications.add(section, line_index, &line, custom_database)?;
+            }
+        }
+        let mut header = header.ok_or_else(|| {
+            CustomError::error(
+                "Invalid Mascot file",
+                "No peptide table was found, the table should start with a line with the column names (eg 'prot_hit_num' or 'pep_query')",
+                Context::None,
+            )
+        })?;
+
+        Ok(lines.filter_map(move |(line_index, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    return Some(Err(CustomError::error(
+                        "Could not read line",
+                        err,
+                        Context::full_line(line_index, "(failed)"),
+                    )))
+                }
+            };
+            let trimmed = line.trim();
+            if is_table_header(trimmed) {
+                match csv_header(trimmed, b',') {
+                    Ok(h) => header = h,
+                    Err(err) => return Some(Err(err)),
+                }
+                None
+            } else if !trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+                // Empty lines, separators, and section titles, eg "Peptide matches not assigned to protein hits"
+                None
+            } else {
+                Some(
+                    CsvLine::new(line_index, line, &header, b',')
+                        .and_then(|line| Self::from_line(&line, &modifications, custom_database)),
+                )
+            }
+        }))
+    }
+
+    /// Parse a single peptide match
+    /// # Errors
+    /// If any required column is missing or any column contains an invalid value.
+    #[allow(clippy::missing_panics_doc)] // The regexes are valid and all groups are present on a match
+    fn from_line(
+        line: &CsvLine,
+        modifications: &MascotModifications,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let number = |name: &str| -> Result<Option<f64>, CustomError> {
+            optional(line, name)
+                .map(|(value, range)| {
+                    value.parse::<f64>().map_err(|err| {
+                        CustomError::error(
+                            "Invalid Mascot line",
+                            format!("The column '{name}' is not a number: {err}"),
+                            line.range_context(range.clone()),
+                        )
+                    })
+                })
+                .transpose()
+        };
+        let required = |name: &str| -> Result<f64, CustomError> {
+            number(name)?.ok_or_else(|| {
+                CustomError::error(
+                    "Invalid Mascot line",
+                    format!("The column '{name}' is required but empty or not present"),
+                    line.full_context(),
+                )
+            })
+        };
+        let flanking = |name: &str| -> Result<FlankingResidue, CustomError> {
+            optional(line, name).map_or(Ok(FlankingResidue::Unknown), |(value, range)| {
+                value.parse().map_err(|()| {
+                    CustomError::error(
+                        "Invalid Mascot line",
+                        "This flanking residue is not a valid amino acid, or '-' for a terminal",
+                        line.range_context(range.clone()),
+                    )
+                })
+            })
+        };
+
+        let (_, sequence) = line.index_column("pep_seq")?;
+        let mut peptide = Peptidoform::sloppy_pro_forma(
+            line.line(),
+            sequence.clone(),
+            custom_database,
+            &SloppyParsingParameters::default(),
+        )?;
+        let preceding_aa = flanking("pep_res_before")?;
+        modifications.place_fixed(&mut peptide, preceding_aa == FlankingResidue::Terminal);
+        if let Some((positions, range)) = optional(line, "pep_var_mod_pos") {
+            modifications.place_variable(
+                &mut peptide,
+                positions,
+                line.range_context(range.clone()),
+            )?;
+        }
+
+        let title = optional(line, "pep_scan_title").map(|(t, _)| t.to_string());
+        let query = required("pep_query")? as usize;
+
+        Ok(Self {
+            version: MascotVersion::Csv,
+            query,
+            rank: number("pep_rank")?.map_or(1, |r| r as usize),
+            peptide,
+            protein_accession: optional(line, "prot_acc").map(|(v, _)| v.to_string()),
+            protein_description: optional(line, "prot_desc").map(|(v, _)| v.to_string()),
+            mz: MassOverCharge::new::<crate::system::mz>(required("pep_exp_mz")?),
+            mass: number("pep_exp_mr")?.map(Mass::new::<crate::system::dalton>),
+            z: {
+                let (value, range) = line.index_column("pep_exp_z")?;
+                value
+                    .trim()
+                    .trim_end_matches('+')
+                    .parse::<usize>()
+                    .map(Charge::new::<crate::system::e>)
+                    .map_err(|err| {
+                        CustomError::error(
+                            "Invalid Mascot line",
+                            format!("The charge is not a valid number: {err}"),
+                            line.range_context(range.clone()),
+                        )
+                    })?
+            },
+            theoretical_mass: number("pep_calc_mr")?.map(Mass::new::<crate::system::dalton>),
+            missed_cleavages: number("pep_miss")?.map(|m| m as usize),
+            score: required("pep_score")?,
+            expect: number("pep_expect")?,
+            preceding_aa,
+            following_aa: flanking("pep_res_after")?,
+            scans: title.as_deref().map_or_else(
+                || SpectrumIds::FileNotKnown(vec![SpectrumId::Index(query.saturating_sub(1))]),
+                resolve_title,
+            ),
+            title,
+        })
+    }
+}
+
+impl MascotModifications {
+    /// Add a modification line from the fixed or variable modifications section
+    /// # Errors
+    /// If the line does not contain an identifier and name or the name is not a known modification.
+    fn add(
+        &mut self,
+        section: Section,
+        line_index: usize,
+        line: &str,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<(), CustomError> {
+        let fields = csv_separate(line, b',')?;
+        if fields.len() < 2 {
+            return Err(CustomError::error(
+                "Invalid Mascot modification",
+                "A modification line should contain at least the identifier and the name",
+                Context::full_line(line_index, line),
+            ));
+        }
+        let name = fields[1].clone();
+        let modification =
+            Modification::sloppy_modification(line, name.clone(), None, custom_database).map_err(
+                |err| err.with_context(Context::line_range(Some(line_index), line, name.clone())),
+            )?;
+        match section {
+            Section::Fixed => self.fixed.push((line[name].to_string(), modification)),
+            Section::Variable => {
+                let identifier = line[fields[0].clone()].trim().parse::<u32>().map_err(|_| {
+                    CustomError::error(
+                        "Invalid Mascot modification",
+                        "The identifier of a variable modification should be a number",
+                        Context::line_range(Some(line_index), line, fields[0].clone()),
+                    )
+                })?;
+                self.variable.push((identifier, modification));
+            }
+            Section::Other => (),
+        }
+        Ok(())
+    }
+
+    /// Place all fixed modifications on the residues (or termini) listed in their name, eg
+    /// `Carbamidomethyl (C)`
+    fn place_fixed(&self, peptide: &mut Peptidoform<SemiAmbiguous>, protein_n_term: bool) {
+        for (name, modification) in &self.fixed {
+            let sites = name
+                .rsplit_once('(')
+                .map_or("", |(_, sites)| sites.trim_end_matches(')'));
+            if sites.contains("N-term") {
+                if !sites.contains("Protein") || protein_n_term {
+                    peptide.add_simple_n_term(modification.clone());
+                }
+            } else if sites.contains("C-term") {
+                peptide.add_simple_c_term(modification.clone());
+            } else {
+                for element in peptide.sequence_mut() {
+                    if sites.contains(element.aminoacid.char()) {
+                        element.add_simple_modification(modification.clone());
+                    }
+                }
+            }
+        }
+    }
+
+    /// Place the variable modifications based on the positions string, eg `0.0010000.0`, the
+    /// first and last digit are the N and C terminus, the middle part the residues
+    /// # Errors
+    /// If the positions do not match the peptide or refer to an undefined modification.
+    fn place_variable(
+        &self,
+        peptide: &mut Peptidoform<SemiAmbiguous>,
+        positions: &str,
+        context: Context,
+    ) -> Result<(), CustomError> {
+        let parts: Vec<&str> = positions.split('.').collect();
+        if parts.len() != 3 || parts[1].len() != peptide.len() {
+            return Err(CustomError::error(
+                "Invalid Mascot modification positions",
+                "The positions should be formatted as 'N.residues.C' with one position per residue",
+                context,
+            ));
+        }
+        let lookup = |c: char| -> Result<Option<SimpleModification>, CustomError> {
+            match c.to_digit(36) {
+                Some(0) => Ok(None),
+                Some(id) => self
+                    .variable
+                    .iter()
+                    .find(|(i, _)| *i == id)
+                    .map(|(_, m)| Some(m.clone()))
+                    .ok_or_else(|| {
+                        CustomError::error(
+                            "Invalid Mascot modification positions",
+                            format!("Variable modification {c} is not defined in the header"),
+                            context.clone(),
+                        )
+                    }),
+                None => Err(CustomError::error(
+                    "Invalid Mascot modification positions",
+                    format!("'{c}' is not a valid modification identifier"),
+                    context.clone(),
+                )),
+            }
+        };
+        for c in parts[0].chars() {
+            if let Some(m) = lookup(c)? {
+                peptide.add_simple_n_term(m);
+            }
+        }
+        for (index, c) in parts[1].chars().enumerate() {
+            if let Some(m) = lookup(c)? {
+                peptide.sequence_mut()[index].add_simple_modification(m);
+            }
+        }
+        for c in parts[2].chars() {
+            if let Some(m) = lookup(c)? {
+                peptide.add_simple_c_term(m);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Check if this line is the header of a peptide table
+fn is_table_header(line: &str) -> bool {
+    line.starts_with("prot_hit_num") || line.starts_with("pep_query")
+}
+
+/// Get the value of an optional column, returns `None` if the column is not present or empty
+fn optional<'a>(line: &'a CsvLine, name: &str) -> Option<(&'a str, &'a Range<usize>)> {
+    line.index_column(name)
+        .ok()
+        .map(|(value, range)| (value.trim(), range))
+        .filter(|(value, _)| !value.is_empty())
+}
+
+/// Resolve the spectra from the scan title, supports msconvert style titles
+/// (`File:"raw", NativeID:"id"`) and dotted titles (`raw.scan.scan.charge`)
+#[allow(clippy::missing_panics_doc)] // The regexes are valid and all groups are present on a match
+fn resolve_title(title: &str) -> SpectrumIds {
+    NATIVE_TITLE_REGEX
+        .get_or_init(|| {
+            regex::Regex::new(r#"File:\s*"?([^",]+)"?,\s*NativeID:\s*"?([^"]+)"?"#).unwrap()
+        })
+        .captures(title)
+        .map(|m| {
+            (
+                m.get(1).unwrap().as_str(),
+                SpectrumId::Native(m.get(2).unwrap().as_str().to_string()),
+            )
+        })
+        .or_else(|| {
+            DOTTED_TITLE_REGEX
+                .get_or_init(|| {
+                    regex::Regex::new(r"^(.+)\.(\d+)\.\d+\.\d+(?:\.\d+\.dta)?$").unwrap()
+                })
+                .captures(title)
+                .and_then(|m| {
+                    m.get(2)
+                        .unwrap()
+                        .as_str()
+                        .parse::<usize>()
+                        .ok()
+                        .map(|scan| (m.get(1).unwrap().as_str(), SpectrumId::Index(scan)))
+                })
+        })
+        .map_or_else(
+            || SpectrumIds::FileNotKnown(vec![SpectrumId::Native(title.to_string())]),
+            |(raw_file, id)| SpectrumIds::FileKnown(vec![(PathBuf::from(raw_file), vec![id])]),
+        )
+}
+
+impl From<MascotData> for IdentifiedPeptide {
+    fn from(value: MascotData) -> Self {
+        Self {
+            score: Some(2.0 / (1.0 + 1.05_f64.powf(-value.score)) - 1.0),
+            original_score: None,
+            local_confidence: None,
+            metadata: MetaData::Mascot(value),
+        }
+    }
+}
+
+/// All supported Mascot versions
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum MascotVersion {
+    /// The CSV export from Mascot Server
+    #[default]
+    Csv,
+}
+
+impl std::fmt::Display for MascotVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Csv => "CSV export",
+            }
+        )
+    }
+}
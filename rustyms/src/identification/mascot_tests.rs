@@ -0,0 +1,126 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::BufReader;
+
+use crate::identification::{
+    test_identified_peptide, FlankingResidue, IdentifiedPeptide, MascotData, SpectrumId,
+    SpectrumIds,
+};
+
+#[test]
+fn mascot_csv() {
+    let mut peptides = 0;
+    for read in MascotData::parse_reader(BufReader::new(MASCOT_CSV.as_bytes()), None).unwrap() {
+        let read: IdentifiedPeptide = read.unwrap().into();
+        test_identified_peptide(&read, true, false).unwrap();
+        peptides += 1;
+    }
+    assert_eq!(peptides, 5);
+}
+
+#[test]
+fn mascot_modifications() {
+    let peptides = MascotData::parse_reader(BufReader::new(MASCOT_CSV.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        peptides[0].peptide.to_string(),
+        "LVNELTEFAK",
+        "No modifications should be placed"
+    );
+    assert_eq!(
+        peptides[1].peptide.to_string(),
+        "YIC[U:Carbamidomethyl]ENQDSISSK"
+    );
+    assert_eq!(
+        peptides[2].peptide.to_string(),
+        "[U:Acetyl]-M[U:Oxidation]DDREDLVYQAK"
+    );
+    assert_eq!(peptides[2].preceding_aa, FlankingResidue::Terminal);
+    assert_eq!(peptides[3].peptide.to_string(), "HPYFYAPELLYYANK");
+}
+
+#[test]
+fn mascot_scans() {
+    let peptides = MascotData::parse_reader(BufReader::new(MASCOT_CSV.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(peptides[0].query, 1);
+    assert_eq!(
+        peptides[0].scans,
+        SpectrumIds::FileKnown(vec![(
+            "sample_01.raw".into(),
+            vec![SpectrumId::Native(
+                "controllerType=0 controllerNumber=1 scan=1021".to_string()
+            )]
+        )])
+    );
+    assert_eq!(
+        peptides[1].scans,
+        SpectrumIds::FileKnown(vec![("sample_01".into(), vec![SpectrumId::Index(1187)])])
+    );
+    assert_eq!(
+        peptides[2].scans,
+        SpectrumIds::FileNotKnown(vec![SpectrumId::Native("Spectrum 12".to_string())])
+    );
+    // The unassigned peptide has no title, so the query is used
+    assert_eq!(peptides[4].protein_accession, None);
+    assert_eq!(
+        peptides[4].scans,
+        SpectrumIds::FileNotKnown(vec![SpectrumId::Index(21)])
+    );
+}
+
+#[test]
+fn mascot_undefined_modification() {
+    let file = MASCOT_CSV.replace("0.000000000000000.0", "0.000000000000300.0");
+    assert!(
+        MascotData::parse_reader(BufReader::new(file.as_bytes()), None)
+            .unwrap()
+            .any(|p| p.is_err())
+    );
+}
+
+#[test]
+fn not_mascot() {
+    assert!(MascotData::parse_reader(
+        BufReader::new(b"Header\n\"Fixed modifications\"\n".as_slice()),
+        None
+    )
+    .is_err());
+}
+
+const MASCOT_CSV: &str = r#"Header
+--------------------------------------------------------
+
+Search title,example search
+MS data file,sample_01.mgf
+Database,SwissProt (SwissProt_2023_03.fasta)
+Taxonomy,Homo sapiens (human)
+
+"Fixed modifications"
+--------------------------------------------------------
+Identifier,Name,Delta,Neutral loss(es)
+1,"Carbamidomethyl (C)",57.021464
+
+"Variable modifications"
+--------------------------------------------------------
+Identifier,Name,Delta,Neutral loss(es)
+1,"Acetyl (Protein N-term)",42.010565
+2,"Oxidation (M)",15.994915,0,63.998285
+
+"Protein hits"
+--------------------------------------------------------
+
+prot_hit_num,prot_acc,prot_desc,prot_score,prot_mass,prot_matches,pep_query,pep_rank,pep_isbold,pep_exp_mz,pep_exp_mr,pep_exp_z,pep_calc_mr,pep_delta,pep_miss,pep_score,pep_expect,pep_res_before,pep_seq,pep_res_after,pep_var_mod,pep_var_mod_pos,pep_scan_title
+1,ALBU_HUMAN,"Albumin OS=Homo sapiens OX=9606 GN=ALB PE=1 SV=2",412,71317,12,1,1,1,575.3111,1148.6077,2,1148.6077,0.0000,0,62.51,4.2e-07,K,LVNELTEFAK,T,,0.0000000000.0,"File:sample_01.raw, NativeID:controllerType=0 controllerNumber=1 scan=1021"
+1,ALBU_HUMAN,"Albumin OS=Homo sapiens OX=9606 GN=ALB PE=1 SV=2",412,71317,12,8,1,1,751.8338,1501.6531,2,1501.6532,-0.0001,0,71.08,6.1e-08,K,YICENQDSISSK,L,,0.000000000000.0,sample_01.1187.1187.2
+2,MAP2_HUMAN,"Microtubule-associated protein 2 OS=Homo sapiens OX=9606 GN=MAP2",58,199526,1,12,1,1,877.8986,1753.7826,2,1753.7826,0.0000,0,41.30,3.3e-04,-,MDDREDLVYQAK,L,Acetyl (Protein N-term); Oxidation (M),1.200000000000.0,Spectrum 12
+2,MAP2_HUMAN,"Microtubule-associated protein 2 OS=Homo sapiens OX=9606 GN=MAP2",58,199526,1,15,1,1,627.6428,1879.9066,3,1879.9079,-0.0013,0,36.12,0.0011,R,HPYFYAPELLYYANK,Y,,0.000000000000000.0,sample_01.1390.1390.3.0.dta
+
+"Peptide matches not assigned to protein hits"
+--------------------------------------------------------
+pep_query,pep_rank,pep_isbold,pep_exp_mz,pep_exp_mr,pep_exp_z,pep_calc_mr,pep_delta,pep_miss,pep_score,pep_expect,pep_res_before,pep_seq,pep_res_after,pep_var_mod,pep_var_mod_pos,pep_scan_title
+22,1,1,458.7612,915.5078,2+,915.5080,-0.0002,1,18.40,0.21,,AVLKELR,,,0.0000000.0,
+"#;
@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::{Location, OptionalColumn, OptionalLocation},
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
 };
 
@@ -106,6 +106,7 @@ impl From<MaxQuantData> for IdentifiedPeptide {
         Self {
             score: (!value.score.is_nan())
                 .then(|| 2.0 * (1.0 / (1.0 + 1.01_f64.powf(-value.score)) - 0.5)),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::MaxQuant(value),
         }
@@ -177,7 +178,7 @@ pub const MSMS: MaxQuantFormat = MaxQuantFormat {
     nem_probabilities: OptionalColumn::NotAvailable,
     nem_score_differences: OptionalColumn::NotAvailable,
     number_of_matches: OptionalColumn::Required("number of matches"),
-    oxidation_m_probabilities: OptionalColumn::NotAvailable,
+    oxidation_m_probabilities: OptionalColumn::Optional("oxidation (m) probabilities"),
     oxidation_m_score_differences: OptionalColumn::NotAvailable,
     peak_coverage: OptionalColumn::Required("peak coverage"),
     pep: "pep",
@@ -0,0 +1,208 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use super::{
+    common_parser::{Location, OptionalColumn, OptionalLocation},
+    csv::CsvLine,
+    BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
+    OpairMatchKind,
+};
+use crate::{
+    error::CustomError,
+    ontologies::CustomDatabase,
+    peptidoform::{SemiAmbiguous, SloppyParsingParameters},
+    system::{usize::Charge, Mass, MassOverCharge, Time},
+    Peptidoform,
+};
+use serde::{Deserialize, Serialize};
+
+static NUMBER_ERROR: (&str, &str) = (
+    "Invalid MetaMorpheus line",
+    "This column is not a number but it is required to be a number in this MetaMorpheus format",
+);
+
+format_family!(
+    /// The format for MetaMorpheus PSMTSV data
+    MetaMorpheusFormat,
+    /// The data for MetaMorpheus PSMTSV data, for ambiguous PSMs only the first peptide is kept
+    MetaMorpheusData,
+    MetaMorpheusVersion, [&METAMORPHEUS], b'\t', None;
+    required {
+        raw_file: PathBuf, |location: Location, _| Ok(Path::new(&location.get_string()).to_owned());
+        scan: usize, |location: Location, _| location.parse(NUMBER_ERROR);
+        rt: Time, |location: Location, _| location.parse::<f64>(NUMBER_ERROR).map(Time::new::<crate::system::time::min>);
+        precursor_scan_number: Option<usize>, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        z: Charge, |location: Location, _| location.parse::<usize>(NUMBER_ERROR).map(Charge::new::<crate::system::e>);
+        mz: MassOverCharge, |location: Location, _| location.parse::<f64>(NUMBER_ERROR).map(MassOverCharge::new::<crate::system::mz>);
+        mass: Mass, |location: Location, _| location.parse::<f64>(NUMBER_ERROR).map(Mass::new::<crate::system::dalton>);
+        score: f64, |location: Location, _| location.parse(NUMBER_ERROR);
+        delta_score: f64, |location: Location, _| location.parse(NUMBER_ERROR);
+        /// The notch (precursor mass shift) for each ambiguous peptide
+        notch: Vec<usize>, |location: Location, _| location.or_empty().array('|').map(|l| l.parse(NUMBER_ERROR)).collect::<Result<Vec<_>, _>>();
+        base_sequence: String, |location: Location, _| Ok(location.get_string());
+        /// The first full sequence if this PSM is ambiguous
+        peptide: Peptidoform<SemiAmbiguous>, |location: Location, custom_database: Option<&CustomDatabase>| {
+            let first = location.clone().split_once('|').map_or(location, |(first, _)| first);
+            Peptidoform::sloppy_pro_forma(
+                first.full_line(),
+                first.location.clone(),
+                custom_database,
+                &SloppyParsingParameters::default()
+            )
+        };
+        ambiguity_level: String, |location: Location, _| Ok(location.get_string());
+        mods: String, |location: Location, _| Ok(location.get_string());
+        /// The missed cleavages for each ambiguous peptide
+        missed_cleavages: Vec<usize>, |location: Location, _| location.or_empty().array('|').map(|l| l.parse(NUMBER_ERROR)).collect::<Result<Vec<_>, _>>();
+        /// The theoretical mass for each ambiguous peptide
+        theoretical_mass: Vec<Mass>, |location: Location, _| location.or_empty().array('|').map(|l| l.parse::<f64>(NUMBER_ERROR).map(Mass::new::<crate::system::dalton>)).collect::<Result<Vec<_>, _>>();
+        accession: Vec<String>, |location: Location, _| Ok(location.or_empty().array('|').map(Location::get_string).collect());
+        protein_name: Vec<String>, |location: Location, _| Ok(location.or_empty().array('|').map(Location::get_string).collect());
+        gene_name: Vec<String>, |location: Location, _| Ok(location.or_empty().array('|').map(Location::get_string).collect());
+        organism: Vec<String>, |location: Location, _| Ok(location.or_empty().array('|').map(Location::get_string).collect());
+        /// The location in the protein for each ambiguous peptide and protein, zero based
+        protein_location: Vec<Range<usize>>, |location: Location, _| location.or_empty().array('|').map(|l| parse_protein_location(&l)).collect::<Result<Vec<_>, _>>();
+        preceding_aa: String, |location: Location, _| Ok(location.get_string());
+        following_aa: String, |location: Location, _| Ok(location.get_string());
+        kind: OpairMatchKind, |location: Location, _| location.clone().split_once('|').map_or(location, |(first, _)| first).parse_with(|loc| {
+            match loc.as_str() {
+                "T" => Ok(OpairMatchKind::Target),
+                "C" => Ok(OpairMatchKind::Contamination),
+                "D" => Ok(OpairMatchKind::Decoy),
+                _ => Err(CustomError::error(
+                    "Invalid MetaMorpheus line",
+                    "The kind column does not contain a valid value (T/C/D)",
+                    loc.context(),
+                )),
+            }
+        });
+        matched_ion_series: String, |location: Location, _| Ok(location.get_string());
+        matched_ion_mz_ratios: String, |location: Location, _| Ok(location.get_string());
+        matched_ion_intensities: String, |location: Location, _| Ok(location.get_string());
+        matched_ion_counts: String, |location: Location, _| Ok(location.get_string());
+        q_value: f64, |location: Location, _| location.parse(NUMBER_ERROR);
+        pep: Option<f64>, |location: Location, _| location.or_empty().ignore("NA").parse(NUMBER_ERROR);
+        pep_q_value: Option<f64>, |location: Location, _| location.or_empty().ignore("NA").parse(NUMBER_ERROR);
+    }
+    optional {
+        normalized_spectral_angle: f64, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        localisation_score: f64, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        yion_score: f64, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        diagnostic_ion_score: f64, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        plausible_glycan_number: usize, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        total_glycosylation_sites: usize, |location: Location, _| location.or_empty().parse(NUMBER_ERROR);
+        glycan_mass: Mass, |location: Location, _| location.or_empty().parse::<f64>(NUMBER_ERROR).map(|m| m.map(Mass::new::<crate::system::dalton>));
+        plausible_glycan_composition: String, |location: Location, _| Ok(location.or_empty().get_string());
+        plausible_glycan_structure: String, |location: Location, _| Ok(location.or_empty().get_string());
+        glycan_localisation_level: String, |location: Location, _| Ok(location.or_empty().get_string().map(|l| l.trim_start_matches("Level").to_string()));
+        all_potential_glycan_localisations: String, |location: Location, _| Ok(location.or_empty().get_string());
+        all_site_specific_localisation_probabilities: String, |location: Location, _| Ok(location.or_empty().get_string());
+    }
+);
+
+/// Parse a protein location formatted as `[<start> to <end>]` (one based, inclusive)
+/// # Errors
+/// If the location is not formatted correctly.
+fn parse_protein_location(location: &Location) -> Result<Range<usize>, CustomError> {
+    location
+        .as_str()
+        .trim()
+        .strip_prefix('[')
+        .and_then(|l| l.strip_suffix(']'))
+        .and_then(|l| l.split_once(" to "))
+        .and_then(|(start, end)| {
+            Some(start.trim().parse::<usize>().ok()?.saturating_sub(1)..end.trim().parse().ok()?)
+        })
+        .ok_or_else(|| {
+            CustomError::error(
+                "Invalid MetaMorpheus line",
+                "The location is not valid, it should be defined like this [<start> to <end>]",
+                location.context(),
+            )
+        })
+}
+
+impl From<MetaMorpheusData> for IdentifiedPeptide {
+    fn from(value: MetaMorpheusData) -> Self {
+        Self {
+            score: Some((value.score / 100.0).clamp(-1.0, 1.0)),
+            original_score: None,
+            local_confidence: None,
+            metadata: MetaData::MetaMorpheus(value),
+        }
+    }
+}
+
+/// All possible MetaMorpheus versions
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum MetaMorpheusVersion {
+    /// MetaMorpheus 1.0 PSMTSV output, including the glyco search columns if present
+    #[default]
+    V1,
+}
+
+impl std::fmt::Display for MetaMorpheusVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::V1 => "v1",
+            }
+        )
+    }
+}
+
+/// The only supported format for MetaMorpheus data
+pub const METAMORPHEUS: MetaMorpheusFormat = MetaMorpheusFormat {
+    version: MetaMorpheusVersion::V1,
+    raw_file: "file name",
+    scan: "scan number",
+    rt: "scan retention time",
+    precursor_scan_number: "precursor scan number",
+    z: "precursor charge",
+    mz: "precursor mz",
+    mass: "precursor mass",
+    score: "score",
+    delta_score: "delta score",
+    notch: "notch",
+    base_sequence: "base sequence",
+    peptide: "full sequence",
+    ambiguity_level: "ambiguitylevel",
+    mods: "mods",
+    missed_cleavages: "missed cleavages",
+    theoretical_mass: "peptide monoisotopic mass",
+    accession: "protein accession",
+    protein_name: "protein name",
+    gene_name: "gene name",
+    organism: "organism name",
+    protein_location: "start and end residues in protein",
+    preceding_aa: "previous amino acid",
+    following_aa: "next amino acid",
+    kind: "decoy/contaminant/target",
+    matched_ion_series: "matched ion series",
+    matched_ion_mz_ratios: "matched ion mass-to-charge ratios",
+    matched_ion_intensities: "matched ion intensities",
+    matched_ion_counts: "matched ion counts",
+    q_value: "qvalue",
+    pep: "pep",
+    pep_q_value: "pep_qvalue",
+    normalized_spectral_angle: OptionalColumn::Optional("normalized spectral angle"),
+    localisation_score: OptionalColumn::Optional("localization score"),
+    yion_score: OptionalColumn::Optional("yion score"),
+    diagnostic_ion_score: OptionalColumn::Optional("diagonosticion score"),
+    plausible_glycan_number: OptionalColumn::Optional("plausible number of glycans"),
+    total_glycosylation_sites: OptionalColumn::Optional("total glycosylation sites"),
+    glycan_mass: OptionalColumn::Optional("glycanmass"),
+    plausible_glycan_composition: OptionalColumn::Optional("plausible glycancomposition"),
+    plausible_glycan_structure: OptionalColumn::Optional("plausible glycanstructure"),
+    glycan_localisation_level: OptionalColumn::Optional("glycanlocalizationlevel"),
+    all_potential_glycan_localisations: OptionalColumn::Optional(
+        "all potential glycan localizations",
+    ),
+    all_site_specific_localisation_probabilities: OptionalColumn::Optional(
+        "allsitespecificlocalizationprobability",
+    ),
+};
@@ -0,0 +1,87 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::BufReader;
+
+use crate::identification::{
+    test_format, IdentifiedPeptideSource, MetaMorpheusData, MetaMorpheusVersion, OpairMatchKind,
+};
+
+#[test]
+fn metamorpheus() {
+    match test_format::<MetaMorpheusData>(
+        BufReader::new(DATA.as_bytes()),
+        None,
+        false,
+        false,
+        Some(MetaMorpheusVersion::V1),
+    ) {
+        Ok(n) => assert_eq!(n, 4),
+        Err(e) => {
+            println!("{e}");
+            panic!("Failed identified peptides test");
+        }
+    }
+}
+
+#[test]
+fn metamorpheus_glyco() {
+    match test_format::<MetaMorpheusData>(
+        BufReader::new(GLYCO.as_bytes()),
+        None,
+        false,
+        false,
+        Some(MetaMorpheusVersion::V1),
+    ) {
+        Ok(n) => assert_eq!(n, 1),
+        Err(e) => {
+            println!("{e}");
+            panic!("Failed identified peptides test");
+        }
+    }
+}
+
+#[test]
+fn metamorpheus_metadata() {
+    let peptides = MetaMorpheusData::parse_reader(BufReader::new(DATA.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        peptides[1].peptide.to_string(),
+        "YIC[U:Carbamidomethyl]ENQDSISSK"
+    );
+    assert_eq!(peptides[1].protein_location, vec![138..150]);
+    // Ambiguous PSM
+    assert_eq!(
+        peptides[2].peptide.to_string(),
+        "[U:Acetyl]-M[U:Oxidation]DDREDLVYQAK"
+    );
+    assert_eq!(peptides[2].accession, vec!["P11137", "P11138"]);
+    assert_eq!(peptides[2].notch, vec![0, 0]);
+    assert_eq!(peptides[2].protein_location, vec![0..12, 0..12]);
+    assert_eq!(peptides[3].kind, OpairMatchKind::Decoy);
+    assert_eq!(peptides[3].peptide.to_string(), "KQELAS[U:Phospho]EK");
+    assert!(peptides[0].localisation_score.is_none());
+
+    let glyco = MetaMorpheusData::parse_reader(BufReader::new(GLYCO.as_bytes()), None)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        glyco.peptide.to_string(),
+        "T[Glycan:Hex1HexNAc1]PSAAYLWVGTGASEAK"
+    );
+    assert_eq!(glyco.plausible_glycan_number, Some(1));
+    assert_eq!(glyco.glycan_localisation_level.as_deref(), Some("1"));
+}
+
+const DATA: &str = r"File Name	Scan Number	Scan Retention Time	Num Experimental Peaks	Total Ion Current	Precursor Scan Number	Precursor Charge	Precursor MZ	Precursor Mass	Score	Delta Score	Notch	Base Sequence	Full Sequence	Essential Sequence	AmbiguityLevel	PSM Count (unambiguous, <0.01 q-value)	Mods	Mods Chemical Formulas	Mods Combined Chemical Formula	Num Variable Mods	Missed Cleavages	Peptide Monoisotopic Mass	Mass Diff (Da)	Mass Diff (ppm)	Protein Accession	Protein Name	Gene Name	Organism Name	Identified Sequence Variations	Splice Sites	Contaminant	Decoy	Peptide Description	Start and End Residues In Protein	Previous Amino Acid	Next Amino Acid	Theoreticals Searched	Decoy/Contaminant/Target	Matched Ion Series	Matched Ion Mass-To-Charge Ratios	Matched Ion Mass Diff (Da)	Matched Ion Mass Diff (Ppm)	Matched Ion Intensities	Matched Ion Counts	Normalized Spectral Angle	Localized Scores	Improvement Possible	Cumulative Target	Cumulative Decoy	QValue	Cumulative Target Notch	Cumulative Decoy Notch	QValue Notch	PEP	PEP_QValue
+sample_01	1021	34.52	312	12034000	1019	2	575.31108	1148.60761	25.143	12.104	0	LVNELTEFAK	LVNELTEFAK	LVNELTEFAK	1	1				0	0	1148.60759	0.00002	0.02	P02768	Serum albumin	primary:ALB	Homo sapiens			N	N	full	[66 to 75]	K	T	1532	T	[y1, y2, y3, b2]	[y1:147.11277, y2:218.14987, y3:365.21823, b2:213.15976]	[y1:0.00001, y2:0.00002, y3:-0.00001, b2:0.00003]	[y1:0.07, y2:0.09, y3:-0.03, b2:0.14]	[y1:120345, y2:340982, y3:98234, b2:45012]	4	0.7123		N	1	0	0	1	0	0	0.00012	0
+sample_01	1187	38.07	312	12034000	1185	2	751.83384	1501.65313	31.872	18.453	0	YICENQDSISSK	YIC[Common Fixed:Carbamidomethyl on C]ENQDSISSK	YICENQDSISSK	1	1	Carbamidomethyl on C 3	C2H3NO	C2H3NO	0	0	1501.65318	0.00002	0.02	P02768	Serum albumin	primary:ALB	Homo sapiens			N	N	full	[139 to 150]	K	L	1532	T	[y1, y2, y3]	[y1:147.11277, y2:234.14480, y3:321.17683]	[y1:0.00001, y2:0.00002, y3:0.00001]	[y1:0.07, y2:0.09, y3:0.03]	[y1:80345, y2:140982, y3:58234]	3	0.6541		N	2	0	0	2	0	0	0.00003	0
+sample_01	1390	41.88	312	12034000	1388	2	907.40460	1812.79465	18.204	0	0|0	MDDREDLVYQAK	[Common Biological:Acetylation on X]M[Common Variable:Oxidation on M]DDREDLVYQAK|[Common Biological:Acetylation on X]M[Common Variable:Oxidation on M]DDREDLVYQAK	[Common Biological:Acetylation on X]M[Common Variable:Oxidation on M]DDREDLVYQAK	1	1	Acetylation on X 1 Oxidation on M 1	C2H2O O	C2H2O2	2	1|1	1812.79463|1812.79463	0.00002	0.02	P11137|P11138	Microtubule-associated protein 2|Microtubule-associated protein 2B	primary:MAP2|primary:MAP2B	Homo sapiens|Homo sapiens			N	N	full	[1 to 12]|[1 to 12]	-|-	L|L	1532	T|T	[y1, y2]	[y1:147.11277, y2:218.14987]	[y1:0.00001, y2:0.00002]	[y1:0.07, y2:0.09]	[y1:20345, y2:40982]	2	0.4012		N	3	0	0	3	0	0	0.0213	0.0071
+sample_01	1502	44.31	312	12034000	1500	2	442.20001	882.38547	6.012	1.002	0	KQELASEK	KQELAS[Common Biological:Phosphorylation on S]EK	KQELAS[Common Biological:Phosphorylation on S]EK	1	1	Phosphorylation on S 6	HO3P	HO3P	1	1	1026.45404	0.00002	0.02	DECOY_P02768	Serum albumin	primary:ALB	Homo sapiens			N	Y	full	[201 to 208]	R	F	1532	D	[y1]	[y1:147.11277]	[y1:0.00004]	[y1:0.27]	[y1:2345]	1	0.1012		N	3	1	0.25	3	1	0.25	0.8213	0.2071
+";
+
+const GLYCO: &str = r"File Name	Scan Number	Scan Retention Time	Num Experimental Peaks	Total Ion Current	Precursor Scan Number	Precursor Charge	Precursor MZ	Precursor Mass	Score	Delta Score	Notch	Base Sequence	Full Sequence	Essential Sequence	AmbiguityLevel	PSM Count (unambiguous, <0.01 q-value)	Mods	Mods Chemical Formulas	Mods Combined Chemical Formula	Num Variable Mods	Missed Cleavages	Peptide Monoisotopic Mass	Mass Diff (Da)	Mass Diff (ppm)	Protein Accession	Protein Name	Gene Name	Organism Name	Identified Sequence Variations	Splice Sites	Contaminant	Decoy	Peptide Description	Start and End Residues In Protein	Previous Amino Acid	Next Amino Acid	Theoreticals Searched	Decoy/Contaminant/Target	Matched Ion Series	Matched Ion Mass-To-Charge Ratios	Matched Ion Mass Diff (Da)	Matched Ion Mass Diff (Ppm)	Matched Ion Intensities	Matched Ion Counts	Normalized Spectral Angle	Localized Scores	Improvement Possible	Cumulative Target	Cumulative Decoy	QValue	Cumulative Target Notch	Cumulative Decoy Notch	QValue Notch	PEP	PEP_QValue	Localization Score	Yion Score	DiagonosticIon Score	Plausible Number Of Glycans	Total Glycosylation sites	GlycanMass	Plausible GlycanComposition	N-Glycan motif Check	R138/144	Plausible GlycanStructure	GlycanLocalizationLevel	Localized Glycans with Peptide Site Specific Probability	Localized Glycans with Protein Site Specific Probability	All potential glycan localizations	AllSiteSpecificLocalizationProbability
+glyco_01	2210	52.14	312	12034000	2208	3	645.97000	1934.88816	22.811	9.021	0	TPSAAYLWVGTGASEAK	T[O-Glycosylation:H1N1 on X]PSAAYLWVGTGASEAK	T[O-Glycosylation:H1N1 on X]PSAAYLWVGTGASEAK	1	1	H1N1 on X 1			1	0	1934.88901	0.00002	0.02	P02765	Alpha-2-HS-glycoprotein	primary:AHSG	Homo sapiens			N	N	full	[339 to 355]	K	T	1532	T	[y1, y2]	[y1:147.11277, y2:218.14987]	[y1:0.00001, y2:0.00002]	[y1:0.07, y2:0.09]	[y1:20345, y2:40982]	2	0.5012		N	1	0	0	1	0	0	0.0013	0	22.811	3.201	4.002	1	2	365.13220	H1N1	FALSE	0.81	(N(H))	Level1	{@1[1-2]}	{@339[1-2]}	{@1[1-2]}	{@1[1-2,0.97]}
+";
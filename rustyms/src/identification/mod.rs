@@ -3,52 +3,100 @@
 #[macro_use]
 mod common_parser;
 
+#[cfg(feature = "align")]
+mod assembly;
+mod casanovo;
+mod cleavage_specificity;
 mod deepnovofamily;
+mod experiment;
 mod fasta;
+mod fdr;
 mod general;
 mod identified_peptide;
+mod indexed;
 mod instanovo;
+mod mascot;
 mod maxquant;
+mod metamorpheus;
 mod msfragger;
 mod mztab;
 mod novob;
 mod novor;
 mod opair;
+mod parse_report;
 mod peaks;
 mod pepnet;
+mod pepnovo;
+mod percolator;
 mod plgs;
 mod plink;
+mod pnovo;
 mod powernovo;
+mod protein_index;
+mod protein_inference;
+mod quality_control;
+mod quantification;
+mod rescore;
 mod sage;
+mod site_probabilities;
 mod ssl;
+mod tag_search;
+mod xtandem;
 
 use crate::*;
+#[cfg(feature = "align")]
+pub use assembly::*;
+pub use casanovo::*;
+pub use cleavage_specificity::*;
 pub use deepnovofamily::*;
+pub use experiment::*;
 pub use fasta::*;
+pub use fdr::*;
 pub use general::*;
 pub use identified_peptide::*;
+pub use indexed::*;
 pub use instanovo::*;
+pub use mascot::*;
 pub use maxquant::*;
+pub use metamorpheus::*;
 pub use msfragger::*;
 pub use mztab::*;
 pub use novob::*;
 pub use novor::*;
 pub use opair::*;
+pub use parse_report::*;
 pub use peaks::*;
 pub use pepnet::*;
+pub use pepnovo::*;
+pub use percolator::*;
 pub use plgs::*;
 pub use plink::*;
+pub use pnovo::*;
 pub use powernovo::*;
+pub use protein_index::*;
+pub use protein_inference::*;
+pub use quality_control::*;
+pub use quantification::*;
+pub use rescore::*;
 pub use sage::*;
+pub use site_probabilities::*;
 pub use ssl::*;
+pub use tag_search::*;
+pub use xtandem::*;
 
+#[cfg(test)]
+mod casanovo_tests;
 #[cfg(test)]
 mod deepnovofamily_tests;
 #[cfg(test)]
 mod instanovo_tests;
 #[cfg(test)]
+mod mascot_tests;
+#[cfg(test)]
 mod maxquant_tests;
 #[cfg(test)]
+mod metamorpheus_tests;
+#[cfg(test)]
 mod msfragger_tests;
 #[cfg(test)]
 mod mztab_test;
@@ -63,12 +111,20 @@ mod peaks_tests;
 #[cfg(test)]
 mod pepnet_tests;
 #[cfg(test)]
+mod pepnovo_tests;
+#[cfg(test)]
+mod percolator_tests;
+#[cfg(test)]
 mod plgs_tests;
 #[cfg(test)]
 mod plink_tests;
 #[cfg(test)]
+mod pnovo_tests;
+#[cfg(test)]
 mod powernovo_tests;
 #[cfg(test)]
 mod sage_tests;
 #[cfg(test)]
 mod ssl_tests;
+#[cfg(test)]
+mod xtandem_tests;
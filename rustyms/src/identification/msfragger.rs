@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::{Location, OptionalColumn, OptionalLocation},
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     fasta::FastaIdentifier,
     BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
 };
@@ -93,9 +93,37 @@ format_family!(
         raw_file: PathBuf, |location: Location, _| Ok(Some(location.get_string().into()));
         condition: String, |location: Location, _| Ok(Some(location.get_string()));
         group: String, |location: Location, _| Ok(Some(location.get_string()));
+        /// The PTMProphet localisations as column name (`sty:79.9663`), mass shift, and site probabilities (`PEPS(0.998)T(0.002)IDE`)
+        localisations: Vec<(String, Mass, String)>, |_: Location, _| Ok(None);
     }
 
-    fn post_process(_source: &CsvLine, mut parsed: Self, _custom_database: Option<&CustomDatabase>) -> Result<Self, CustomError> {
+    fn post_process(source: &CsvLine, mut parsed: Self, _custom_database: Option<&CustomDatabase>) -> Result<Self, CustomError> {
+        let localisations = source
+            .headers()
+            .enumerate()
+            .filter_map(|(index, name)| {
+                name.split_once(':')
+                    .filter(|(residues, _)| {
+                        !residues.is_empty()
+                            && residues.chars().all(|c| c.is_ascii_alphabetic())
+                    })
+                    .and_then(|(_, mass)| mass.trim().parse::<f64>().ok())
+                    .map(|mass| (index, name, mass))
+            })
+            .filter_map(|(index, name, mass)| {
+                let value = Location::column(index, source).get_string();
+                (!value.trim().is_empty()).then(|| {
+                    (
+                        name.to_string(),
+                        Mass::new::<crate::system::dalton>(mass),
+                        value,
+                    )
+                })
+            })
+            .collect_vec();
+        if !localisations.is_empty() {
+            parsed.localisations = Some(localisations);
+        }
         if let SpectrumId::Native(native) = &parsed.scan {
             if let Some(m) = IDENTIFER_REGEX
                 .get_or_init(|| regex::Regex::new(r"([^/]+)\.(\d+)\.\d+.\d+").unwrap())
@@ -120,6 +148,7 @@ impl From<MSFraggerData> for IdentifiedPeptide {
     fn from(value: MSFraggerData) -> Self {
         Self {
             score: Some(value.hyperscore),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::MSFragger(value),
         }
@@ -185,6 +214,7 @@ pub const V21: MSFraggerFormat = MSFraggerFormat {
     mapped_proteins: "mapped proteins",
     condition: OptionalColumn::Optional("condition"),
     group: OptionalColumn::Optional("group"),
+    localisations: OptionalColumn::NotAvailable,
 };
 
 /// v22
@@ -222,6 +252,7 @@ pub const V22: MSFraggerFormat = MSFraggerFormat {
     mapped_proteins: "mapped proteins",
     condition: OptionalColumn::Optional("condition"),
     group: OptionalColumn::Optional("group"),
+    localisations: OptionalColumn::NotAvailable,
 };
 
 /// The scans identifier for a MSFragger identification
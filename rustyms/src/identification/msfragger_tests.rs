@@ -1,7 +1,13 @@
 #![allow(clippy::missing_panics_doc)]
 use std::io::BufReader;
 
-use crate::identification::{test_format, MSFraggerData, MSFraggerVersion};
+use crate::{
+    identification::{
+        test_format, IdentifiedPeptide, IdentifiedPeptideSource, MSFraggerData, MSFraggerVersion,
+    },
+    modification::SimpleModificationInner,
+    Modification,
+};
 
 #[test]
 fn msfragger_v21() {
@@ -54,6 +60,35 @@ fn msfragger_v22() {
     }
 }
 
+#[test]
+fn msfragger_ptm_prophet() {
+    let mut lines = DATA_V22.lines();
+    let header = format!("{}\tSTY:79.9663\tSTY:79.9663 Best Localization", lines.next().unwrap());
+    let row = lines
+        .next()
+        .unwrap()
+        .replacen("HAVSEGTK\t\t", "HAVSEGTK\tHAVS[166.9984]EGTK\t", 1)
+        + "\tHAVS(0.75)EGT(0.25)K\t0.75";
+    let peptide: IdentifiedPeptide =
+        MSFraggerData::parse_reader(BufReader::new(format!("{header}\n{row}").as_bytes()), None)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .into();
+    let sites = peptide.site_probabilities().unwrap();
+    assert_eq!(sites.len(), 1);
+    assert!(matches!(*sites[0].0, SimpleModificationInner::Mass(_)));
+    assert_eq!(sites[0].1, vec![(3, 0.75), (6, 0.25)]);
+    let localised = peptide.localised_peptide().unwrap().unwrap();
+    assert_eq!(localised.get_ambiguous_modifications().len(), 1);
+    assert!(localised
+        .sequence()
+        .iter()
+        .flat_map(|s| &s.modifications)
+        .all(|m| matches!(m, Modification::Ambiguous { .. })));
+}
+
 const DATA_V21_MANUAL: &str = r"Spectrum	Spectrum File	Peptide	Modified Peptide	Extended Peptide	Prev AA	Next AA	Peptide Length	Charge	Retention	Observed Mass	Calibrated Observed Mass	Observed M/Z	Calibrated Observed M/Z	Calculated Peptide Mass	Calculated M/Z	Delta Mass	Expectation	Hyperscore	Nextscore	PeptideProphet Probability	Number of Enzymatic Termini	Number of Missed Cleavages	Protein Start	Protein End	Intensity	Assigned Modifications	Observed Modifications	Purity	Is Unique	Protein	Protein ID	Entry Name	Gene	Protein Description	Mapped Genes	Mapped Proteins	condition	group
 20240621_EX2_UM2_6579035_SA_EXT00_SS25_1A_Alk14_R1.21346.21346.2	D:\06-June\6579035\SS25\MSFragger_4-24\1_alk14_1\interact.pep.xml	GAQLSGGR	n[621]GAQLSGGR	.GAQLSGGR.GAPEPAQT	M	G	8	2	1811.7999	1364.8169	1364.8121	683.4157	683.4133	1364.814	683.4143	-0.0018	1.449549e-05	24.446	10.854	1	2	0	2	9	478745568	N-term(DB14 (N-term))		0	true	sp|A0A0U1RRL7|MMPOS_HUMAN	A0A0U1RRL7	MMPOS_HUMAN	MMP24OS	Protein MMP24OS			1_alk14_1	1_alk14
 20240621_EX2_UM2_6579035_SA_EXT00_SS25_1B_Alk14_R2.20911.20911.2	D:\06-June\6579035\SS25\MSFragger_4-24\1_alk14_2\interact.pep.xml	GAQLSGGR	n[621]GAQLSGGR	.GAQLSGGR.GAPEPAQT	M	G	8	2	1812.5955	1364.8169	1364.8116	683.4157	683.4131	1364.814	683.4143	-0.0023	1.071478e-05	24.441	10.843	1	2	0	2	9	405120480	N-term(DB14 (N-term))		0	true	sp|A0A0U1RRL7|MMPOS_HUMAN	A0A0U1RRL7	MMPOS_HUMAN	MMP24OS	Protein MMP24OS			1_alk14_2	1_alk14
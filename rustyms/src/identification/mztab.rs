@@ -1,18 +1,11 @@
-use std::{
-    collections::HashMap,
-    fs::File,
-    io::{BufRead, BufReader},
-    ops::Range,
-    str::FromStr,
-};
+use std::{collections::BTreeMap, io::BufRead, ops::Range, str::FromStr};
 
-use flate2::bufread::GzDecoder;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::{Context, CustomError},
-    helper_functions::{check_extension, explain_number_error},
+    helper_functions::{explain_number_error, open_reader},
     identification::{IdentifiedPeptide, MetaData, SpectrumId, SpectrumIds},
     modification::SimpleModification,
     ontologies::CustomDatabase,
@@ -68,7 +61,7 @@ pub struct MZTabData {
     /// Casanovo specific additional metadata with the amino acid confidence
     pub local_confidence: Option<Vec<f64>>,
     /// Any additional metadata
-    pub additional: HashMap<String, String>,
+    pub additional: BTreeMap<String, String>,
 }
 
 impl MZTabData {
@@ -79,26 +72,10 @@ impl MZTabData {
         path: impl AsRef<std::path::Path>,
         custom_database: Option<&CustomDatabase>,
     ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
-        let file = File::open(path.as_ref()).map_err(|e| {
-            CustomError::error(
-                "Could not open file",
-                e,
-                crate::error::Context::Show {
-                    line: path.as_ref().to_string_lossy().to_string(),
-                },
-            )
-        })?;
-        if check_extension(path, "gz") {
-            Ok(Box::new(Self::parse_reader(
-                BufReader::new(GzDecoder::new(BufReader::new(file))),
-                custom_database,
-            )))
-        } else {
-            Ok(Box::new(Self::parse_reader(
-                BufReader::new(file),
-                custom_database,
-            )))
-        }
+        Ok(Box::new(Self::parse_reader(
+            open_reader(path)?,
+            custom_database,
+        )))
     }
 
     /// Parse a mzTab file directly from a buffered reader
@@ -723,6 +700,7 @@ impl From<MZTabData> for IdentifiedPeptide {
                         .clamp(-1.0, 1.0)
                 })
                 .filter(|v| !v.is_nan()),
+            original_score: None,
             local_confidence: value.local_confidence.clone(),
             metadata: MetaData::MZTab(value),
         }
@@ -95,7 +95,7 @@ fn open_file(reader: impl BufRead) -> Result<usize, CustomError> {
     Ok(peptides)
 }
 
-const PRIDE_EXP_EXCERPT_AC_1643: &str = r"MTD	mzTab-version	1.0 rc5
+pub(super) const PRIDE_EXP_EXCERPT_AC_1643: &str = r"MTD	mzTab-version	1.0 rc5
 MTD	mzTab-mode	Complete
 MTD	mzTab-type	Identification
 MTD	mzTab-ID	1643
@@ -719,7 +719,7 @@ PSM	LGLGIDEDDPTVDDTSAAVTEEMPPLEGDDDTSR	48	P07901	1	UniProtKB	2013_08	[MS,MS:1001
 PSM	TLTIVDTGIGMTK	49	P07901	1	UniProtKB	2013_08	[MS,MS:1001207,Mascot,]	36	11-UNIMOD:35	ms_run[6]:scan=1123	1132.62	3	450.5838508	450.583	R	A	88	100
 PSM	MPEETQTQDQPMEEEEVETFAFQAEIAQLMSLIINTFYSNK	50	P07901	1	UniProtKB	2013_08	[MS,MS:1001207,Mascot,]	11	0-UNIMOD:35	ms_run[6]:scan=3188	3184.08	2	2405.619464	2405.6084	-	E	1	41"#;
 
-const CASANOVO_V3_2_0_A: &str = r"MTD	mzTab-version	1.0.0
+pub(super) const CASANOVO_V3_2_0_A: &str = r"MTD	mzTab-version	1.0.0
 MTD	mzTab-mode	Summary
 MTD	mzTab-type	Identification
 MTD	description	Casanovo identification file MM_peptides_casanovo_AspN
@@ -820,7 +820,7 @@ PSM	VKDDDYR	38	null	null	null	null	[MS, MS:1003281, Casanovo, 3.2.0]	-0.60386642
 PSM	GLSSHGR	39	null	null	null	null	[MS, MS:1003281, Casanovo, 3.2.0]	-0.5621655200208937	null	null	1	667.176513671875	713.36891216688	ms_run[1]:index=39	null	null	null	null	0.49872,0.23509,0.96360,0.66934,0.21727,0.12017,0.36065
 PSM	LYRPK	40	null	null	null	null	[MS, MS:1003281, Casanovo, 3.2.0]	-0.7804442211985588	null	null	1	607.20947265625	676.41407216688	ms_run[1]:index=40	null	null	null	null	0.18311,0.12588,0.11594,0.19753,0.47532";
 
-const CASANOVO_V3_2_0_B: &str = r"MTD	mzTab-version	1.0.0
+pub(super) const CASANOVO_V3_2_0_B: &str = r"MTD	mzTab-version	1.0.0
 MTD	mzTab-mode	Summary
 MTD	mzTab-type	Identification
 MTD	description	Casanovo identification file EH3420
@@ -921,7 +921,7 @@ PSM	GRALRRK	38	null	null	null	null	[MS, MS:1003281, Casanovo, 3.2.0]	-0.55639706
 PSM	SLDSERGR	39	null	null	null	null	[MS, MS:1003281, Casanovo, 3.2.0]	-0.5517001785337925	null	null	2	456.0073547363281	460.23322981688005	ms_run[1]:index=39	null	null	null	null	0.86180,0.33256,0.34989,0.51239,0.29720,0.32683,0.18481,0.72092
 PSM	GRPLSGSGSGLL	40	null	null	null	null	[MS, MS:1003281, Casanovo, 3.2.0]	-0.5062050955990951	null	null	2	556.092529296875	550.80656231688	ms_run[1]:index=40	null	null	null	null	0.99992,0.72701,0.68849,0.10854,0.25012,0.46942,0.37918,0.22213,0.23691,0.27216,0.78839,0.78325";
 
-const CASANOVO_V4_2_1: &str = r"MTD	mzTab-version	1.0.0
+pub(super) const CASANOVO_V4_2_1: &str = r"MTD	mzTab-version	1.0.0
 MTD	mzTab-mode	Summary
 MTD	mzTab-type	Identification
 MTD	description	Casanovo identification file 20191211_F1_Ag5_peng0013_SA_her_tryp_c421
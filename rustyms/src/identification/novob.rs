@@ -12,7 +12,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter, SequenceElement,
 };
 
@@ -119,6 +119,7 @@ impl From<NovoBData> for IdentifiedPeptide {
     fn from(value: NovoBData) -> Self {
         Self {
             score: Some(value.score_forward.max(value.score_reverse)),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::NovoB(value),
         }
@@ -1,6 +1,6 @@
 use super::{
     common_parser::{Location, OptionalColumn},
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
 };
 use crate::{
@@ -62,6 +62,7 @@ impl From<NovorData> for IdentifiedPeptide {
     fn from(value: NovorData) -> Self {
         Self {
             score: Some((value.score / 100.0).clamp(-1.0, 1.0)),
+            original_score: None,
             local_confidence: value
                 .local_confidence
                 .as_ref()
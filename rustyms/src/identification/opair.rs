@@ -5,7 +5,7 @@ use std::{
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     fasta::FastaIdentifier,
     BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
 };
@@ -196,6 +196,7 @@ impl From<OpairData> for IdentifiedPeptide {
     fn from(value: OpairData) -> Self {
         Self {
             score: Some(value.score / 100.0),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::Opair(value),
         }
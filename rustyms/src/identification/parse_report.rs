@@ -0,0 +1,238 @@
+//! Collect all row level errors when parsing identified peptide files
+
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    csv::CsvLine,
+    error::{Context, CustomError},
+    identification::IdentifiedPeptideSource,
+    ontologies::CustomDatabase,
+};
+
+/// The result of parsing a full file while collecting all row level errors instead of failing on
+/// the first one. This allows fixing all issues in a file in one go.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub struct ParseReport<T> {
+    /// All items that could be parsed successfully
+    pub parsed: Vec<T>,
+    /// All errors, in the order they occurred in the file
+    pub errors: Vec<RowError>,
+    /// If the error budget was exhausted and parsing stopped before the end of the file
+    pub aborted: bool,
+}
+
+/// A single error in a row of a file
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct RowError {
+    /// The line index (0 based) of the offending row, if known
+    pub line_index: Option<usize>,
+    /// The names of the columns highlighted by the error, can be empty if the error is about the
+    /// full row or the column could not be determined
+    pub columns: Vec<String>,
+    /// The full error
+    pub error: CustomError,
+}
+
+impl<T> Default for ParseReport<T> {
+    fn default() -> Self {
+        Self {
+            parsed: Vec::new(),
+            errors: Vec::new(),
+            aborted: false,
+        }
+    }
+}
+
+impl<T> ParseReport<T> {
+    /// Parse all lines from a CSV file, the format is detected on the first line that can be
+    /// parsed. Parsing stops when more than `error_budget` errors are found, if no budget is given
+    /// all lines are parsed.
+    pub(super) fn from_csv<I: Iterator<Item = Result<CsvLine, CustomError>>>(
+        lines: I,
+        custom_database: Option<&CustomDatabase>,
+        error_budget: Option<usize>,
+    ) -> Self
+    where
+        T: IdentifiedPeptideSource<Source = CsvLine>,
+        T::Format: 'static,
+    {
+        let mut report = Self::default();
+        let mut format = None;
+        for line in lines {
+            let result = line.map_err(RowError::from).and_then(|line| {
+                if let Some(format) = &format {
+                    T::parse_specific(&line, format, custom_database)
+                } else {
+                    T::parse(&line, custom_database).map(|(peptide, detected)| {
+                        format = Some(detected.clone());
+                        peptide
+                    })
+                }
+                .map_err(|error| RowError::new(&line, error))
+            });
+            if !report.add(result, error_budget) {
+                break;
+            }
+        }
+        report
+    }
+
+    /// Collect all results from an iterator of parsed items. Parsing stops when more than
+    /// `error_budget` errors are found, if no budget is given all items are collected.
+    pub(super) fn from_results(
+        results: impl Iterator<Item = Result<T, CustomError>>,
+        error_budget: Option<usize>,
+    ) -> Self {
+        let mut report = Self::default();
+        for result in results {
+            if !report.add(result.map_err(RowError::from), error_budget) {
+                break;
+            }
+        }
+        report
+    }
+
+    /// Add a single result, returns false if the error budget is exhausted
+    fn add(&mut self, result: Result<T, RowError>, error_budget: Option<usize>) -> bool {
+        match result {
+            Ok(item) => self.parsed.push(item),
+            Err(error) => {
+                self.errors.push(error);
+                if error_budget.is_some_and(|budget| self.errors.len() > budget) {
+                    self.aborted = true;
+                }
+            }
+        }
+        !self.aborted
+    }
+
+    /// Check if any errors occurred
+    pub fn is_ok(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// Get the parsed items if no errors occurred, otherwise combine all errors into a single error.
+    /// # Errors
+    /// If any row could not be parsed.
+    pub fn into_result(self) -> Result<Vec<T>, CustomError> {
+        if self.errors.is_empty() {
+            Ok(self.parsed)
+        } else {
+            Err(CustomError::error(
+                "Could not parse file",
+                format!(
+                    "{} row{} could not be parsed{}",
+                    self.errors.len(),
+                    if self.errors.len() == 1 { "" } else { "s" },
+                    if self.aborted {
+                        ", parsing was stopped as the error budget was exhausted"
+                    } else {
+                        ""
+                    }
+                ),
+                Context::none(),
+            )
+            .with_underlying_errors(self.errors.into_iter().map(|e| e.error).collect()))
+        }
+    }
+}
+
+impl RowError {
+    /// Create a row error for an error on the given line, the columns are determined based on the
+    /// location(s) highlighted in the error and its underlying errors.
+    fn new(line: &CsvLine, error: CustomError) -> Self {
+        let mut columns = Vec::new();
+        let mut stack = vec![&error];
+        while let Some(error) = stack.pop() {
+            if let Context::Line { offset, length, .. } = error.context() {
+                for (index, header) in line.headers().enumerate() {
+                    let range = line.range(index);
+                    if (range.contains(offset) || range.start == *offset)
+                        && *offset + length <= range.end.max(range.start + 1)
+                        && !columns.iter().any(|c| c == header)
+                    {
+                        columns.push(header.to_string());
+                    }
+                }
+            }
+            stack.extend(error.underlying_errors());
+        }
+        Self {
+            line_index: Some(line.line_index()),
+            columns,
+            error,
+        }
+    }
+}
+
+impl From<CustomError> for RowError {
+    fn from(error: CustomError) -> Self {
+        Self {
+            line_index: match error.context() {
+                Context::FullLine { line_index, .. } => Some(*line_index),
+                Context::Line { line_index, .. } => *line_index,
+                Context::Range {
+                    start_line_index, ..
+                }
+                | Context::RangeHighlights {
+                    start_line_index, ..
+                } => Some(*start_line_index),
+                _ => None,
+            },
+            columns: Vec::new(),
+            error,
+        }
+    }
+}
+
+impl Display for RowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line_index {
+            Some(index) => write!(f, "Row {}", index + 1)?,
+            None => write!(f, "Unknown row")?,
+        }
+        if !self.columns.is_empty() {
+            write!(f, " (column {})", self.columns.join(", "))?;
+        }
+        write!(f, ": {}", self.error)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::identification::SageData;
+
+    #[test]
+    fn sage_errors() {
+        let file = std::fs::read_to_string("src/identification/test_files/sage_v0_14.tsv").unwrap();
+        let mut lines = file.lines().map(ToString::to_string).collect::<Vec<_>>();
+        let header = lines[0].split('\t').collect::<Vec<_>>();
+        let charge = header.iter().position(|h| *h == "charge").unwrap();
+        let peptide = header.iter().position(|h| *h == "peptide").unwrap();
+        for (row, column, value) in [(2, charge, "two"), (5, peptide, "PEP[Unknown]TIDE")] {
+            let mut fields = lines[row].split('\t').collect::<Vec<_>>();
+            fields[column] = value;
+            lines[row] = fields.join("\t");
+        }
+        let file = lines.join("\n");
+
+        let report = SageData::parse_reader_with_report(file.as_bytes(), None, None).unwrap();
+        assert_eq!(report.parsed.len(), 17);
+        assert_eq!(report.errors.len(), 2);
+        assert!(!report.aborted);
+        assert_eq!(report.errors[0].line_index, Some(2));
+        assert_eq!(report.errors[0].columns, ["charge"]);
+        assert_eq!(report.errors[1].line_index, Some(5));
+        assert_eq!(report.errors[1].columns, ["peptide"]);
+        assert!(report.into_result().is_err());
+
+        let report = SageData::parse_reader_with_report(file.as_bytes(), None, Some(0)).unwrap();
+        assert_eq!(report.errors.len(), 1);
+        assert!(report.aborted);
+        assert_eq!(report.parsed.len(), 1);
+    }
+}
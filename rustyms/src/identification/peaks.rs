@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::{Location, OptionalColumn, OptionalLocation},
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     fasta::FastaIdentifier,
     modification::SimpleModification,
     peptidoform::PeptideModificationSearch,
@@ -34,7 +34,7 @@ format_family!(
     PeaksFormat,
     /// The data from any peaks file
     PeaksData,
-    PeaksVersion, [&V12, &V11, &V11_FEATURES, &XPLUS, &AB, &X_PATCHED, &X, &DB_PEPTIDE, &DB_PSM, &DB_PROTEIN_PEPTIDE], b',', None;
+    PeaksVersion, [&V12, &V11, &V11_FEATURES, &XPLUS, &ONLINE, &AB, &X_PATCHED, &X, &DB_PEPTIDE, &DB_PSM, &DB_PROTEIN_PEPTIDE], b',', None;
     required {
         peptide: (Option<crate::AminoAcid>, Vec<Peptidoform<SemiAmbiguous>>, Option<crate::AminoAcid>), |location: Location, custom_database: Option<&CustomDatabase>| {
             let n_flanking: Option<crate::AminoAcid> =
@@ -116,6 +116,8 @@ format_family!(
         rt_end: Time, |location: Location, _| location.or_empty().parse::<f64>(NUMBER_ERROR).map(|o| o.map(Time::new::<crate::system::time::s>));
         precursor_id: isize, |location: Location, _| location.parse::<isize>(NUMBER_ERROR);
         k0_range: std::ops::RangeInclusive<f64>, |location: Location, _| location.split_once('-').map(|(start, end)| Ok(start.parse(NUMBER_ERROR)?..=end.parse(NUMBER_ERROR)?));
+        /// The ion mobility (1/K0) of the precursor
+        k0: f64, |location: Location, _| location.or_empty().parse::<f64>(NUMBER_ERROR);
     }
 
     fn post_process(_source: &CsvLine, mut parsed: Self, _custom_database: Option<&CustomDatabase>) -> Result<Self, CustomError> {
@@ -143,6 +145,7 @@ impl From<PeaksData> for IdentifiedPeptide {
                         .logp
                         .map(|v| 2.0 * (1.0 / (1.0 + 1.025_f64.powf(-v)) - 0.5))
                 }),
+            original_score: None,
             local_confidence: value
                 .local_confidence
                 .as_ref()
@@ -192,6 +195,7 @@ pub const X: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version X of PEAKS export (made for build 31 January 2019)
 pub const X_PATCHED: PeaksFormat = PeaksFormat {
@@ -233,6 +237,7 @@ pub const X_PATCHED: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version X+ of PEAKS export (made for build 20 November 2019)
 pub const XPLUS: PeaksFormat = PeaksFormat {
@@ -274,6 +279,7 @@ pub const XPLUS: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version 11 of PEAKS export
 pub const V11: PeaksFormat = PeaksFormat {
@@ -315,6 +321,7 @@ pub const V11: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::Optional("precursor id"),
     k0_range: OptionalColumn::Optional("1/k0 range"),
+    k0: OptionalColumn::Optional("1/k0"),
 };
 /// Version 11 of PEAKS export
 pub const V11_FEATURES: PeaksFormat = PeaksFormat {
@@ -356,6 +363,7 @@ pub const V11_FEATURES: PeaksFormat = PeaksFormat {
     end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version 12 of PEAKS export
 pub const V12: PeaksFormat = PeaksFormat {
@@ -395,8 +403,51 @@ pub const V12: PeaksFormat = PeaksFormat {
     quality: OptionalColumn::NotAvailable,
     rt_begin: OptionalColumn::NotAvailable,
     rt_end: OptionalColumn::NotAvailable,
-    precursor_id: OptionalColumn::NotAvailable,
-    k0_range: OptionalColumn::NotAvailable,
+    precursor_id: OptionalColumn::Optional("precursor id"),
+    k0_range: OptionalColumn::Optional("1/k0 range"),
+    k0: OptionalColumn::Optional("1/k0"),
+};
+/// PEAKS Online de novo export
+pub const ONLINE: PeaksFormat = PeaksFormat {
+    version: PeaksVersion::Online,
+    scan: OptionalColumn::Required("scan"),
+    peptide: "peptide",
+    alc: OptionalColumn::Required("alc (%)"),
+    mz: "m/z",
+    z: OptionalColumn::Required("z"),
+    mass: OptionalColumn::Required("mass"),
+    rt: "rt",
+    area: "area",
+    ptm: OptionalColumn::Required("ptm"),
+    local_confidence: OptionalColumn::Required("local confidence (%)"),
+    tag: OptionalColumn::Optional("tag(>=0%)"),
+    mode: OptionalColumn::Required("mode"),
+    fraction: OptionalColumn::NotAvailable,
+    raw_file: OptionalColumn::Required("source file"),
+    feature: OptionalColumn::Optional("feature id"),
+    de_novo_score: OptionalColumn::Required("denovo score"),
+    predicted_rt: OptionalColumn::Optional("predict rt"),
+    accession: OptionalColumn::NotAvailable,
+    ascore: OptionalColumn::NotAvailable,
+    found_by: OptionalColumn::NotAvailable,
+    logp: OptionalColumn::NotAvailable,
+    feature_tryp_cid: OptionalColumn::NotAvailable,
+    feature_tryp_ead: OptionalColumn::NotAvailable,
+    area_tryp_ead: OptionalColumn::NotAvailable,
+    id: OptionalColumn::NotAvailable,
+    from_chimera: OptionalColumn::NotAvailable,
+    unique: OptionalColumn::NotAvailable,
+    protein_group: OptionalColumn::NotAvailable,
+    protein_id: OptionalColumn::NotAvailable,
+    protein_accession: OptionalColumn::NotAvailable,
+    start: OptionalColumn::NotAvailable,
+    end: OptionalColumn::NotAvailable,
+    quality: OptionalColumn::NotAvailable,
+    rt_begin: OptionalColumn::NotAvailable,
+    rt_end: OptionalColumn::NotAvailable,
+    precursor_id: OptionalColumn::Optional("precursor id"),
+    k0_range: OptionalColumn::Optional("1/k0 range"),
+    k0: OptionalColumn::Optional("1/k0"),
 };
 /// Version Ab of PEAKS export
 pub const AB: PeaksFormat = PeaksFormat {
@@ -438,6 +489,7 @@ pub const AB: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version DB peptide of PEAKS export
 pub const DB_PEPTIDE: PeaksFormat = PeaksFormat {
@@ -479,6 +531,7 @@ pub const DB_PEPTIDE: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version DB psm of PEAKS export
 pub const DB_PSM: PeaksFormat = PeaksFormat {
@@ -520,6 +573,7 @@ pub const DB_PSM: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 /// Version DB protein peptide of PEAKS export
 /// protein group, protein id, protein accession, unique, start, end,
@@ -562,6 +616,7 @@ pub const DB_PROTEIN_PEPTIDE: PeaksFormat = PeaksFormat {
     rt_end: OptionalColumn::NotAvailable,
     precursor_id: OptionalColumn::NotAvailable,
     k0_range: OptionalColumn::NotAvailable,
+    k0: OptionalColumn::NotAvailable,
 };
 
 /// All possible peaks versions
@@ -589,6 +644,8 @@ pub enum PeaksVersion {
     /// Version 12
     #[default]
     V12,
+    /// PEAKS Online de novo export
+    Online,
 }
 
 impl std::fmt::Display for PeaksVersion {
@@ -607,6 +664,7 @@ impl std::fmt::Display for PeaksVersion {
                 Self::V11 => "11",
                 Self::V11Features => "11 features",
                 Self::V12 => "12",
+                Self::Online => "Online",
             }
         )
     }
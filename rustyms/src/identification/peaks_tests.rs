@@ -148,6 +148,57 @@ fn peaks_12() {
     );
 }
 
+#[test]
+fn peaks_12_ion_mobility() {
+    assert_eq!(
+        test_format::<PeaksData>(
+            BufReader::new(DATA_12_ION_MOBILITY.as_bytes()),
+            None,
+            false,
+            true,
+            Some(PeaksVersion::V12)
+        )
+        .unwrap(),
+        3
+    );
+    let peptides = PeaksData::parse_reader(BufReader::new(DATA_12_ION_MOBILITY.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert!(peptides[0]
+        .k0
+        .is_some_and(|k0| (k0 - 0.8721).abs() < f64::EPSILON));
+    assert_eq!(peptides[0].precursor_id, Some(5012));
+    assert_eq!(peptides[2].k0, None);
+}
+
+#[test]
+fn peaks_online() {
+    assert_eq!(
+        test_format::<PeaksData>(
+            BufReader::new(DATA_ONLINE.as_bytes()),
+            None,
+            false,
+            true,
+            Some(PeaksVersion::Online)
+        )
+        .unwrap(),
+        3
+    );
+    let peptides = PeaksData::parse_reader(BufReader::new(DATA_ONLINE.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(peptides[0].version, PeaksVersion::Online);
+    assert!(peptides[1]
+        .k0
+        .is_some_and(|k0| (k0 - 1.0532).abs() < f64::EPSILON));
+    assert_eq!(
+        peptides[1].peptide.1[0].to_string(),
+        "C[U:Carbamidomethyl]PEPTIDEK"
+    );
+}
+
 #[test]
 fn peaks_ab() {
     assert_eq!(
@@ -573,3 +624,15 @@ const DATA_DB_PROTEIN_PEPTIDE: &str = r"Protein Group,Protein ID,Protein Accessi
 1,1,Herceptin,R.VVSVLTVLHQDWLNGK.E,Y,88.68,1806.9991,16,-0.1,904.5068,2,35.17,2.394E2,1.0949E3,1,F1:176568,230629 1-Trypsin CID 06.mzML,3,2,1,305,320,,,PEAKS DB
 1,1,Herceptin,K.DSTYSLSSTLTLSK.A,Y,88.53,1501.7511,14,-0.4,751.8825,2,30.22,1.0615E2,1.1102E3,1,F1:163866,230629 1-Trypsin CID 06.mzML,6,4,2,620,633,,,PEAKS DB
 1,1,Herceptin,K.FNWYVDGVEVHNAK.T,Y,88.47,1676.7947,14,0.5,839.4050,2,29.08,5.6654E3,1.6995E4,1,F1:160881,230629 1-Trypsin CID 06.mzML,6,3,3,278,291,,,PEAKS DB";
+
+const DATA_12_ION_MOBILITY: &str = r#""Source File","Scan","Peptide","Tag length","Deep Novo Score (%)","ALC (%)","Length","m/z","z","RT","Area","Mass","ppm","PTM","local confidence (%)","mode",tag(>=0%),"Feature Id","Precursor Id","1/k0","1/k0 Range"
+"sample_01.d",4245,TLSKADY,7,100.0,99.0,7,399.20514,2,20.9525,4.8573476E7,796.3967,-1.2,"",100 100 99 99 100 100 97,CID,TLSKADY,1008,5012,0.8721,0.8650-0.8790
+"sample_01.d",4478,SSPVTKSF,8,98.0,97.0,8,426.72626,2,27.9639,2.40281728E8,851.4388,-1.1,"",100 99 99 98 97 96 95 94,CID,SSPVTKSF,1492,5133,0.9102,0.9050-0.9160
+"sample_01.d",4501,LVNELTEFAK,10,95.0,93.0,10,575.31104,2,28.1020,1.2E7,1148.6077,0.4,"",90 91 92 93 94 95 96 97 98 99,CID,LVNELTEFAK,1512,-1,,
+"#;
+
+const DATA_ONLINE: &str = r#""Source File","Scan","Peptide","Tag length","Denovo Score","ALC (%)","Length","m/z","z","RT","Predict RT","Area","Mass","ppm","PTM","local confidence (%)","mode","Feature Id","1/K0"
+"sample_02.d",F1:1021,LVNELTEFAK,10,92,91.5,10,575.31104,2,24.51,24.20,1.2E7,1148.6077,0.4,"",90 91 92 93 94 95 96 97 98 99,CID,F1:311,0.9456
+"sample_02.d",F1:1187,C(+57.02)PEPTIDEK,9,88,86.0,9,603.26404,2,20.10,19.80,4.1E6,1204.5135,-0.7,"Carbamidomethylation",80 85 86 87 88 89 90 91 92,CID,F1:402,1.0532
+"sample_02.d",F1:1390,HPYFYAPELLYYANK,15,80,78.2,15,627.64281,3,30.22,,8.7E5,1879.9066,1.1,"",70 71 72 73 74 75 76 77 78 79 80 81 82 83 84,CID,,
+"#;
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter,
 };
 
@@ -47,6 +47,7 @@ impl From<PepNetData> for IdentifiedPeptide {
     fn from(value: PepNetData) -> Self {
         Self {
             score: Some(value.score),
+            original_score: None,
             local_confidence: Some(value.local_confidence.clone()),
             metadata: MetaData::PepNet(value),
         }
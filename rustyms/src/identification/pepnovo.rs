@@ -0,0 +1,277 @@
+use std::{io::BufRead, ops::Range, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::{explain_number_error, open_reader},
+    identification::{IdentifiedPeptide, MetaData},
+    ontologies::CustomDatabase,
+    system::{usize::Charge, Mass},
+    Peptidoform, SemiAmbiguous, SloppyParsingParameters,
+};
+
+static PARAMETERS_LOCK: OnceLock<SloppyParsingParameters> = OnceLock::new();
+
+/// Global parsing parameters, PepNovo writes modifications as `M+16`
+fn parameters() -> &'static SloppyParsingParameters {
+    PARAMETERS_LOCK.get_or_init(|| SloppyParsingParameters {
+        allow_unwrapped_modifications: true,
+        ..Default::default()
+    })
+}
+
+/// A single candidate from a PepNovo+ result file
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PepNovoData {
+    /// The version of PepNovo
+    pub version: PepNovoVersion,
+    /// The index of the input file (zero based)
+    pub file_index: usize,
+    /// The index of the spectrum in the input file (zero based)
+    pub spectrum_index: usize,
+    /// The title of the spectrum
+    pub title: String,
+    /// The rank of this candidate for this spectrum (zero based)
+    pub rank: usize,
+    /// The rank score
+    pub rank_score: f64,
+    /// The PepNovo score
+    pub score: f64,
+    /// The unexplained mass at the N terminus
+    pub n_gap: Mass,
+    /// The unexplained mass at the C terminus
+    pub c_gap: Mass,
+    /// The M+H of the predicted peptide
+    pub mh: Mass,
+    /// The precursor charge
+    pub z: Charge,
+    /// The predicted peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+}
+
+impl PepNovoData {
+    /// Parse a PepNovo+ result file.
+    /// # Errors
+    /// If the file could not be opened or does not look like a PepNovo+ result file.
+    pub fn parse_file(
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
+        Ok(Box::new(Self::parse_reader(
+            open_reader(path)?,
+            custom_database,
+        )?))
+    }
+
+    /// Parse a PepNovo+ result file directly from a buffered reader. The first line is read
+    /// eagerly to check that this is a PepNovo+ result file, the candidates are read lazily.
+    /// # Errors
+    /// If the first line could not be read or does not look like PepNovo+ output.
+    pub fn parse_reader<'a, T: BufRead + 'a>(
+        reader: T,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<impl Iterator<Item = Result<Self, CustomError>> + 'a, CustomError> {
+        let mut lines = reader.lines().enumerate().peekable();
+        while lines
+            .peek()
+            .is_some_and(|(_, line)| line.as_ref().is_ok_and(|l| l.trim().is_empty()))
+        {
+            lines.next();
+        }
+        match lines.peek() {
+            Some((_, Ok(line))) if line.starts_with("PepNovo") || line.starts_with(">>") => (),
+            Some((line_index, Ok(line))) => {
+                return Err(CustomError::error(
+                    "Not a PepNovo file",
+                    "A PepNovo+ result file should start with the PepNovo+ version or with a spectrum header ('>>')",
+                    Context::full_line(*line_index, line),
+                ))
+            }
+            Some((line_index, Err(err))) => {
+                return Err(CustomError::error(
+                    "Could not read line",
+                    err.to_string(),
+                    Context::full_line(*line_index, "(failed)"),
+                ))
+            }
+            None => {
+                return Err(CustomError::error(
+                    "Not a PepNovo file",
+                    "The file is empty",
+                    Context::None,
+                ))
+            }
+        }
+
+        let mut spectrum: Option<(usize, usize, String)> = None;
+        Ok(lines.filter_map(move |(line_index, line)| {
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    return Some(Err(CustomError::error(
+                        "Could not read line",
+                        err,
+                        Context::full_line(line_index, "(failed)"),
+                    )))
+                }
+            };
+            if let Some(header) = line.strip_prefix(">>") {
+                spectrum = match parse_spectrum_header(line_index, &line, header) {
+                    Ok(s) => Some(s),
+                    Err(err) => return Some(Err(err)),
+                };
+                None
+            } else if line.trim().is_empty()
+                || line.starts_with('#')
+                || !line.starts_with(|c: char| c.is_ascii_digit())
+            {
+                None
+            } else {
+                Some(spectrum.as_ref().map_or_else(
+                    || {
+                        Err(CustomError::error(
+                            "Invalid PepNovo line",
+                            "A candidate line should be preceded by a spectrum header ('>>')",
+                            Context::full_line(line_index, &line),
+                        ))
+                    },
+                    |(file_index, spectrum_index, title)| {
+                        Self::from_line(line_index, &line, custom_database).map(|mut candidate| {
+                            candidate.file_index = *file_index;
+                            candidate.spectrum_index = *spectrum_index;
+                            candidate.title.clone_from(title);
+                            candidate
+                        })
+                    },
+                ))
+            }
+        }))
+    }
+
+    /// Parse a single candidate line, the spectrum information is left empty
+    /// # Errors
+    /// If the line does not contain the eight expected columns or any column is invalid.
+    fn from_line(
+        line_index: usize,
+        line: &str,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let fields = crate::csv::csv_separate(line, b'\t')?;
+        if fields.len() != 8 {
+            return Err(CustomError::error(
+                "Invalid PepNovo line",
+                format!(
+                    "A candidate line should contain 8 columns (Index, RnkScr, PnvScr, N-Gap, C-Gap, [M+H], Charge, Sequence) but {} were found",
+                    fields.len()
+                ),
+                Context::full_line(line_index, line),
+            ));
+        }
+        let number = |range: &Range<usize>| {
+            line[range.clone()].trim().parse::<f64>().map_err(|err| {
+                CustomError::error(
+                    "Invalid PepNovo line",
+                    format!("This column is not a number: {err}"),
+                    Context::line_range(Some(line_index), line, range.clone()),
+                )
+            })
+        };
+        let integer = |range: &Range<usize>| {
+            line[range.clone()].trim().parse::<usize>().map_err(|err| {
+                CustomError::error(
+                    "Invalid PepNovo line",
+                    format!("This column {}", explain_number_error(&err)),
+                    Context::line_range(Some(line_index), line, range.clone()),
+                )
+            })
+        };
+        let mut sequence = fields[7].clone();
+        if line[sequence.clone()].starts_with('^') {
+            sequence.start += 1;
+        }
+
+        Ok(Self {
+            version: PepNovoVersion::PepNovoPlus,
+            rank: integer(&fields[0])?,
+            rank_score: number(&fields[1])?,
+            score: number(&fields[2])?,
+            n_gap: Mass::new::<crate::system::dalton>(number(&fields[3])?),
+            c_gap: Mass::new::<crate::system::dalton>(number(&fields[4])?),
+            mh: Mass::new::<crate::system::dalton>(number(&fields[5])?),
+            z: Charge::new::<crate::system::e>(integer(&fields[6])?),
+            peptide: Peptidoform::sloppy_pro_forma(line, sequence, custom_database, parameters())
+                .map_err(|err| {
+                err.with_context(Context::line_range(
+                    Some(line_index),
+                    line,
+                    fields[7].clone(),
+                ))
+            })?,
+            ..Self::default()
+        })
+    }
+}
+
+/// Parse the spectrum header `>> <file index> <spectrum index> <title>`
+/// # Errors
+/// If the indices are not valid numbers
+fn parse_spectrum_header(
+    line_index: usize,
+    line: &str,
+    header: &str,
+) -> Result<(usize, usize, String), CustomError> {
+    let mut split = header.trim().splitn(3, char::is_whitespace);
+    let mut index = || {
+        split.next().unwrap_or_default().parse::<usize>().map_err(|err| {
+            CustomError::error(
+                "Invalid PepNovo spectrum header",
+                format!(
+                    "A spectrum header should be '>> <file index> <spectrum index> <title>', the index {}",
+                    explain_number_error(&err)
+                ),
+                Context::full_line(line_index, line),
+            )
+        })
+    };
+    let file_index = index()?;
+    let spectrum_index = index()?;
+    Ok((
+        file_index,
+        spectrum_index,
+        split.next().unwrap_or_default().trim().to_string(),
+    ))
+}
+
+impl From<PepNovoData> for IdentifiedPeptide {
+    fn from(value: PepNovoData) -> Self {
+        Self {
+            score: Some(2.0 * (1.0 / (1.0 + 1.05_f64.powf(-value.score)) - 0.5)),
+            original_score: None,
+            local_confidence: None,
+            metadata: MetaData::PepNovo(value),
+        }
+    }
+}
+
+/// All supported PepNovo versions
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum PepNovoVersion {
+    /// PepNovo+ (build 20101117)
+    #[default]
+    PepNovoPlus,
+}
+
+impl std::fmt::Display for PepNovoVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::PepNovoPlus => "PepNovo+",
+            }
+        )
+    }
+}
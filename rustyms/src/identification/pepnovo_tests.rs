@@ -0,0 +1,78 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::{BufRead, BufReader};
+
+use crate::{
+    error::CustomError,
+    identification::{test_identified_peptide, IdentifiedPeptide, PepNovoData},
+};
+
+#[test]
+fn pepnovo_plus() {
+    assert_eq!(
+        open_file(BufReader::new(PEPNOVO_PLUS.as_bytes())).unwrap(),
+        8
+    );
+}
+
+#[test]
+fn pepnovo_metadata() {
+    let candidates = PepNovoData::parse_reader(BufReader::new(PEPNOVO_PLUS.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let candidate = &candidates[4];
+    assert_eq!(candidate.file_index, 0);
+    assert_eq!(candidate.spectrum_index, 2);
+    assert_eq!(candidate.title, "20230408_F1_UM4_Peng0013.4517.4517.3");
+    assert_eq!(candidate.rank, 1);
+    assert_eq!(candidate.z.value, 3);
+    assert!((candidate.score - 38.112).abs() < f64::EPSILON);
+    assert_eq!(candidate.peptide.len(), 13);
+    assert_eq!(candidates[7].peptide.get_n_term().len(), 1);
+}
+
+#[test]
+fn not_pepnovo() {
+    assert!(
+        PepNovoData::parse_reader(BufReader::new(b"Sequence\tScore\n".as_slice()), None).is_err()
+    );
+}
+
+/// Read all PSMs
+/// # Errors
+/// If any PSM could not be parsed.
+fn open_file(reader: impl BufRead) -> Result<usize, CustomError> {
+    let mut peptides = 0;
+    for read in PepNovoData::parse_reader(reader, None)? {
+        let peptide: IdentifiedPeptide = read?.into();
+        peptides += 1;
+
+        test_identified_peptide(&peptide, true, false).unwrap();
+    }
+    Ok(peptides)
+}
+
+const PEPNOVO_PLUS: &str = r"PepNovo+ Build 20101117
+Copyright 2010 The Regents of the University of California. All Rights Reserved.
+Created by Ari Frank (arf@cs.ucsd.edu)
+
+Initializing models (this might take a few seconds)... 0.43 secs.
+The score models were not found for the given fragmentation model, using default settings...
+PTMs considered: C+57 M+16 
+>> 0 0 20230408_F1_UM4_Peng0013.4480.4480.2
+#Index	RnkScr	PnvScr	N-Gap	C-Gap	[M+H]	Charge	Sequence
+0	4.186	66.302	0.000	0.000	1043.567	2	LVNELTEFAK
+1	3.402	62.105	0.000	0.000	1043.567	2	VLNELTEFAK
+2	1.857	55.881	113.084	0.000	1043.567	2	NELTEFAK
+
+>> 0 1 20230408_F1_UM4_Peng0013.4498.4498.2
+No solutions found.
+
+>> 0 2 20230408_F1_UM4_Peng0013.4517.4517.3
+#Index	RnkScr	PnvScr	N-Gap	C-Gap	[M+H]	Charge	Sequence
+0	2.944	41.057	0.000	0.000	1687.756	3	YLYEIARRHPYFYAPE
+1	1.203	38.112	0.000	0.000	1635.768	3	ADC+57GM+16EAKSFQER
+2	-0.537	31.977	0.000	18.011	1617.757	3	ADC+57GMEAKSFQER
+3	-1.108	30.540	0.000	0.000	1619.773	3	DAC+57GM+16EAKSFQER
+4	-1.562	29.104	0.000	0.000	1661.767	3	^+42ADC+57GMEAKSFQER
+";
@@ -0,0 +1,289 @@
+use std::io::Write;
+
+use crate::{
+    error::{Context, CustomError},
+    identification::{FlankingResidue, IdentifiedPeptide, IdentifiedPeptideSource, MetaData},
+    ontologies::CustomDatabase,
+    system::{usize::Charge, Mass},
+    Peptidoform, SemiAmbiguous, SloppyParsingParameters,
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    common_parser::{Location, OptionalColumn},
+    csv::CsvLine,
+    BoxedIdentifiedPeptideIter,
+};
+
+static NUMBER_ERROR: (&str, &str) = (
+    "Invalid Percolator line",
+    "This column is not a number but it is required to be a number in this Percolator format",
+);
+static FLANKING_ERROR: (&str, &str) = (
+    "Invalid Percolator line",
+    "This flanking residue is not a valid amino acid, or '-' for a terminal",
+);
+
+/// Check if this line is the optional second line of a Percolator input file, which contains the
+/// default direction of the features instead of data.
+fn is_default_direction(line: &str) -> bool {
+    line.get(..16)
+        .is_some_and(|start| start.eq_ignore_ascii_case("defaultdirection"))
+}
+
+format_family!(
+    /// The format for any Percolator file, either the input (pin) or the output (pout) format
+    PercolatorFormat,
+    /// The data from any Percolator file
+    PercolatorData,
+    PercolatorVersion, [&PERCOLATOR_PIN, &PERCOLATOR_POUT], b'\t', None;
+    csv_options {
+        const TRAILING_LIST: bool = true;
+        fn is_metadata_line(line: &str) -> bool {
+            is_default_direction(line)
+        }
+    }
+    required {
+        /// The PSM ID
+        id: String, |location: Location, _| Ok(location.get_string());
+        /// The peptide with the flanking residues, if these are given
+        peptide: (FlankingResidue, Peptidoform<SemiAmbiguous>, FlankingResidue), |location: Location, custom_database: Option<&CustomDatabase>| {
+            let text = location.as_str();
+            let flanked = text.len() > 4
+                && text.as_bytes()[1] == b'.'
+                && text.as_bytes()[text.len() - 2] == b'.';
+            let (n_flanking, range, c_flanking) = if flanked {
+                let start = location.location.start;
+                let end = location.location.end;
+                (
+                    Location { line: location.line, location: start..start + 1 }.parse(FLANKING_ERROR)?,
+                    start + 2..end - 2,
+                    Location { line: location.line, location: end - 1..end }.parse(FLANKING_ERROR)?,
+                )
+            } else {
+                (FlankingResidue::Unknown, location.location.clone(), FlankingResidue::Unknown)
+            };
+            Peptidoform::sloppy_pro_forma(
+                location.full_line(),
+                range,
+                custom_database,
+                &SloppyParsingParameters::default(),
+            ).map(|p| (n_flanking, p, c_flanking))
+        };
+        /// All proteins this peptide maps to
+        proteins: Vec<String>, |location: Location, _| Ok(location.as_str().split('\t').map(str::trim).filter(|p| !p.is_empty()).map(ToString::to_string).collect());
+    }
+    optional {
+        /// If this is a decoy, only available in the input format
+        decoy: bool, |location: Location, _| location.parse::<i8>(NUMBER_ERROR).map(|v| Some(v == -1));
+        /// The scan number, only available in the input format
+        scan: usize, |location: Location, _| location.parse(NUMBER_ERROR).map(Some);
+        /// The experimental mass, optional in the input format
+        mass: Mass, |location: Location, _| location.parse::<f64>(NUMBER_ERROR).map(|v| Some(Mass::new::<crate::system::dalton>(v)));
+        /// The theoretical mass, optional in the input format
+        theoretical_mass: Mass, |location: Location, _| location.parse::<f64>(NUMBER_ERROR).map(|v| Some(Mass::new::<crate::system::dalton>(v)));
+        /// The SVM score, only available in the output format
+        score: f64, |location: Location, _| location.parse(NUMBER_ERROR).map(Some);
+        /// The q-value, only available in the output format
+        q_value: f64, |location: Location, _| location.parse(NUMBER_ERROR).map(Some);
+        /// The posterior error probability, only available in the output format
+        posterior_error_probability: f64, |location: Location, _| location.parse(NUMBER_ERROR).map(Some);
+        /// All features with their column name (lowercase), only available in the input format
+        features: Vec<(String, f64)>, |_: Location, _| Ok(None);
+    }
+
+    fn post_process(source: &CsvLine, mut parsed: Self, _custom_database: Option<&CustomDatabase>) -> Result<Self, CustomError> {
+        if parsed.version == PercolatorVersion::Pin {
+            let known = [PERCOLATOR_PIN.id, "label", "scannr", "expmass", "calcmass", PERCOLATOR_PIN.peptide, PERCOLATOR_PIN.proteins];
+            let mut features = Vec::new();
+            for (index, name) in source.headers().enumerate() {
+                if !known.contains(&name) {
+                    features.push((
+                        name.to_string(),
+                        Location::column(index, source).parse::<f64>(NUMBER_ERROR)?,
+                    ));
+                }
+            }
+            parsed.features = Some(features);
+        }
+        Ok(parsed)
+    }
+);
+
+impl PercolatorData {
+    /// Get the value for the feature with the given name, the names are in lowercase. Only
+    /// available for the input format.
+    pub fn feature(&self, name: &str) -> Option<f64> {
+        self.features
+            .as_ref()
+            .and_then(|features| features.iter().find(|(n, _)| n == name))
+            .map(|(_, v)| *v)
+    }
+
+    /// Get the charge, as encoded in the one-hot charge features (`Charge1`, `Charge2`, etc).
+    /// Only available for the input format.
+    pub fn charge(&self) -> Option<Charge> {
+        self.features.as_ref().and_then(|features| {
+            features.iter().find_map(|(name, value)| {
+                name.strip_prefix("charge")
+                    .and_then(|z| z.parse::<usize>().ok())
+                    .filter(|_| (*value - 1.0).abs() < f64::EPSILON)
+                    .map(Charge::new::<crate::system::e>)
+            })
+        })
+    }
+}
+
+/// Write the given PSMs as a Percolator input file (pin). The columns are `SpecId`, `Label`,
+/// `ScanNr`, `ExpMass` and `CalcMass` (if the first PSM has these masses), all features of the
+/// first PSM, `Peptide`, and `Proteins`. The peptide is written with its flanking residues
+/// (`K.PEPTIDE.R`), unless both are unknown. A missing label is written as target and a missing
+/// scan number as 0.
+/// # Errors
+/// If the writer could not be written to, or if any PSM lacks a mass or feature that the first PSM has.
+pub fn write_pin<'a>(
+    mut writer: impl Write,
+    psms: impl IntoIterator<Item = &'a PercolatorData>,
+) -> Result<(), CustomError> {
+    let write_error = |error: std::io::Error| {
+        CustomError::error("Could not write Percolator file", error, Context::none())
+    };
+    let mut psms = psms.into_iter().peekable();
+    let Some(first) = psms.peek() else {
+        return Ok(());
+    };
+    let masses = first.mass.is_some() && first.theoretical_mass.is_some();
+    let features = first
+        .features
+        .iter()
+        .flatten()
+        .map(|(name, _)| name.clone())
+        .collect::<Vec<_>>();
+
+    write!(writer, "SpecId\tLabel\tScanNr").map_err(write_error)?;
+    if masses {
+        write!(writer, "\tExpMass\tCalcMass").map_err(write_error)?;
+    }
+    for name in &features {
+        write!(writer, "\t{name}").map_err(write_error)?;
+    }
+    writeln!(writer, "\tPeptide\tProteins").map_err(write_error)?;
+
+    for psm in psms {
+        let missing = |what: &str| {
+            CustomError::error(
+                "Could not write Percolator file",
+                format!(
+                    "The PSM '{}' does not have {what}, while the first PSM does",
+                    psm.id
+                ),
+                Context::none(),
+            )
+        };
+        write!(
+            writer,
+            "{}\t{}\t{}",
+            psm.id,
+            if psm.decoy == Some(true) { -1 } else { 1 },
+            psm.scan.unwrap_or_default()
+        )
+        .map_err(write_error)?;
+        if masses {
+            let (Some(mass), Some(theoretical_mass)) = (psm.mass, psm.theoretical_mass) else {
+                return Err(missing("a mass"));
+            };
+            write!(writer, "\t{}\t{}", mass.value, theoretical_mass.value).map_err(write_error)?;
+        }
+        for name in &features {
+            let value = psm
+                .feature(name)
+                .ok_or_else(|| missing(&format!("the feature '{name}'")))?;
+            write!(writer, "\t{value}").map_err(write_error)?;
+        }
+        let flank = |flank: &FlankingResidue| match flank {
+            FlankingResidue::AminoAcid(aa) => aa.to_string(),
+            FlankingResidue::Terminal | FlankingResidue::Unknown => "-".to_string(),
+        };
+        let (n_flank, peptide, c_flank) = &psm.peptide;
+        if *n_flank == FlankingResidue::Unknown && *c_flank == FlankingResidue::Unknown {
+            write!(writer, "\t{peptide}").map_err(write_error)?;
+        } else {
+            write!(writer, "\t{}.{peptide}.{}", flank(n_flank), flank(c_flank))
+                .map_err(write_error)?;
+        }
+        for protein in &psm.proteins {
+            write!(writer, "\t{protein}").map_err(write_error)?;
+        }
+        writeln!(writer).map_err(write_error)?;
+    }
+    Ok(())
+}
+
+impl From<PercolatorData> for IdentifiedPeptide {
+    fn from(value: PercolatorData) -> Self {
+        Self {
+            score: value
+                .posterior_error_probability
+                .map(|pep| (1.0 - pep).clamp(-1.0, 1.0)),
+            original_score: None,
+            local_confidence: None,
+            metadata: MetaData::Percolator(value),
+        }
+    }
+}
+
+/// The Percolator input format (pin), with any number of features
+pub const PERCOLATOR_PIN: PercolatorFormat = PercolatorFormat {
+    version: PercolatorVersion::Pin,
+    id: "specid",
+    peptide: "peptide",
+    proteins: "proteins",
+    decoy: OptionalColumn::Required("label"),
+    scan: OptionalColumn::Required("scannr"),
+    mass: OptionalColumn::Optional("expmass"),
+    theoretical_mass: OptionalColumn::Optional("calcmass"),
+    score: OptionalColumn::NotAvailable,
+    q_value: OptionalColumn::NotAvailable,
+    posterior_error_probability: OptionalColumn::NotAvailable,
+    features: OptionalColumn::NotAvailable,
+};
+
+/// The Percolator output format (pout)
+pub const PERCOLATOR_POUT: PercolatorFormat = PercolatorFormat {
+    version: PercolatorVersion::Pout,
+    id: "psmid",
+    peptide: "peptide",
+    proteins: "proteinids",
+    decoy: OptionalColumn::NotAvailable,
+    scan: OptionalColumn::NotAvailable,
+    mass: OptionalColumn::NotAvailable,
+    theoretical_mass: OptionalColumn::NotAvailable,
+    score: OptionalColumn::Required("score"),
+    q_value: OptionalColumn::Required("q-value"),
+    posterior_error_probability: OptionalColumn::Required("posterior_error_prob"),
+    features: OptionalColumn::NotAvailable,
+};
+
+/// All possible Percolator versions
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize)]
+pub enum PercolatorVersion {
+    /// The input format, containing the features
+    #[default]
+    Pin,
+    /// The output format, containing the scores
+    Pout,
+}
+
+impl std::fmt::Display for PercolatorVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Pin => "pin",
+                Self::Pout => "pout",
+            }
+        )
+    }
+}
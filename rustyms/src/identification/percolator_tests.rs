@@ -0,0 +1,125 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::BufReader;
+
+use crate::identification::{
+    test_format, FlankingResidue, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
+    write_pin, PercolatorData, PercolatorVersion,
+};
+
+#[test]
+fn percolator_pin() {
+    match test_format::<PercolatorData>(
+        BufReader::new(PIN.as_bytes()),
+        None,
+        true,
+        false,
+        Some(PercolatorVersion::Pin),
+    ) {
+        Ok(n) => assert_eq!(n, 4),
+        Err(e) => {
+            println!("{e}");
+            panic!("Failed identified peptides test");
+        }
+    }
+}
+
+#[test]
+fn percolator_pout() {
+    match test_format::<PercolatorData>(
+        BufReader::new(POUT.as_bytes()),
+        None,
+        true,
+        false,
+        Some(PercolatorVersion::Pout),
+    ) {
+        Ok(n) => assert_eq!(n, 4),
+        Err(e) => {
+            println!("{e}");
+            panic!("Failed identified peptides test");
+        }
+    }
+}
+
+#[test]
+fn percolator_pin_details() {
+    let peptides = PercolatorData::parse_reader(PIN.as_bytes(), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let first = &peptides[0];
+    assert_eq!(first.id, "target_0_1001_2_1");
+    assert_eq!(first.scan, Some(1001));
+    assert_eq!(first.decoy, Some(false));
+    assert_eq!(
+        first.peptide.0,
+        FlankingResidue::AminoAcid(crate::AminoAcid::Lysine)
+    );
+    assert_eq!(first.peptide.1.to_string(), "PEPTIDEK");
+    assert_eq!(first.peptide.2, FlankingResidue::Terminal);
+    assert_eq!(first.proteins, ["sp|P12345|PROT1", "sp|P54321|PROT2"]);
+    assert_eq!(first.feature("lnrscore"), Some(2.5));
+    assert_eq!(first.charge().map(|z| z.value), Some(2));
+    assert_eq!(peptides[1].proteins, ["sp|P12345|PROT1"]);
+    assert_eq!(peptides[2].decoy, Some(true));
+    assert_eq!(peptides[3].charge().map(|z| z.value), Some(3));
+
+    let identified: IdentifiedPeptide = first.clone().into();
+    assert!(matches!(identified.metadata, MetaData::Percolator(_)));
+    assert_eq!(identified.is_decoy(), Some(false));
+    assert_eq!(identified.charge().map(|z| z.value), Some(2));
+    assert!(identified.experimental_mz().is_some());
+}
+
+#[test]
+fn percolator_pout_details() {
+    let peptides = PercolatorData::parse_reader(POUT.as_bytes(), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(peptides[0].score, Some(4.21));
+    assert_eq!(peptides[0].q_value, Some(0.0001));
+    assert_eq!(peptides[0].posterior_error_probability, Some(1.2e-5));
+    assert_eq!(peptides[0].features, None);
+    assert_eq!(peptides[0].peptide.1.to_string(), "PEPTIDEK");
+    assert_eq!(peptides[2].proteins.len(), 3);
+    let identified: IdentifiedPeptide = peptides[0].clone().into();
+    assert!((identified.score.unwrap() - (1.0 - 1.2e-5)).abs() < 1e-10);
+}
+
+#[test]
+fn percolator_pin_round_trip() {
+    let peptides = PercolatorData::parse_reader(PIN.as_bytes(), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let mut written = Vec::new();
+    write_pin(&mut written, &peptides).unwrap();
+    let reparsed = PercolatorData::parse_reader(written.as_slice(), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(peptides, reparsed);
+}
+
+#[test]
+fn percolator_pin_missing_feature() {
+    let mut peptides = PercolatorData::parse_reader(PIN.as_bytes(), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    peptides[1].features = Some(Vec::new());
+    assert!(write_pin(Vec::new(), &peptides).is_err());
+}
+
+const PIN: &str = "SpecId\tLabel\tScanNr\tExpMass\tCalcMass\tlnrSp\tdeltLCn\tdeltCn\tlnrScore\tCharge1\tCharge2\tCharge3\tPeptide\tProteins
+DefaultDirection\t-\t-\t-\t-\t0.1\t0\t0.3\t1.2\t0\t0\t0\t-\t-
+target_0_1001_2_1\t1\t1001\t927.4549\t927.4549\t0.0\t0.0\t0.41\t2.5\t0\t1\t0\tK.PEPTIDEK.-\tsp|P12345|PROT1\tsp|P54321|PROT2
+target_0_1002_2_1\t1\t1002\t966.4860\t966.4862\t-1.1\t0.0\t0.22\t1.1\t0\t1\t0\tR.AGM[+15.9949]LSSDTR.A\tsp|P12345|PROT1
+decoy_0_1003_2_1\t-1\t1003\t927.4550\t927.4549\t-2.3\t0.0\t0.05\t0.2\t0\t1\t0\tR.PEDITPEK.L\tdecoy_sp|P12345|PROT1
+target_0_1004_3_1\t1\t1004\t1499.7510\t1499.7500\t0.0\t0.0\t0.33\t1.9\t0\t0\t1\t-.MAGGWHKTLR.S\tsp|P99999|PROT3";
+
+const POUT: &str = "PSMId\tscore\tq-value\tposterior_error_prob\tpeptide\tproteinIds
+target_0_1001_2_1\t4.21\t0.0001\t1.2e-5\tK.PEPTIDEK.-\tsp|P12345|PROT1\tsp|P54321|PROT2
+target_0_1004_3_1\t2.05\t0.0001\t3.1e-4\t-.MAGGWHKTLR.S\tsp|P99999|PROT3
+target_0_1002_2_1\t0.51\t0.004\t0.021\tR.AGM[+15.9949]LSSDTR.A\tsp|P12345|PROT1\tsp|P11111|PROT4\tsp|P22222|PROT5
+target_0_1005_2_1\t-0.8\t0.12\t0.71\tWHKTLR\tsp|P99999|PROT3";
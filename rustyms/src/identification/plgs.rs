@@ -11,7 +11,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::{Location, OptionalColumn, OptionalLocation},
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     fasta::FastaIdentifier,
     placement_rule::PlacementRule,
     BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
@@ -149,6 +149,7 @@ impl From<PLGSData> for IdentifiedPeptide {
     fn from(value: PLGSData) -> Self {
         Self {
             score: Some(2.0 / (1.0 + 1.3_f64.powf(-value.peptide_score)) - 1.0),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::PLGS(value),
         }
@@ -6,7 +6,7 @@ use crate::{
     helper_functions::explain_number_error,
     identification::{
         common_parser::{Location, OptionalColumn, OptionalLocation},
-        csv::{parse_csv, CsvLine},
+        csv::CsvLine,
         modification::SimpleModification,
         BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
         Modification,
@@ -382,6 +382,7 @@ impl From<PLinkData> for IdentifiedPeptide {
     fn from(value: PLinkData) -> Self {
         Self {
             score: Some(1.0 - value.score),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::PLink(value),
         }
@@ -0,0 +1,261 @@
+use std::{io::BufRead, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::{explain_number_error, open_reader},
+    identification::{IdentifiedPeptide, MetaData, SpectrumId, SpectrumIds},
+    modification::Ontology,
+    ontologies::CustomDatabase,
+    system::usize::Charge,
+    AminoAcid, Peptidoform, SemiAmbiguous, SequenceElement, SloppyParsingParameters,
+};
+
+static PARAMETERS_LOCK: OnceLock<SloppyParsingParameters> = OnceLock::new();
+
+/// Global parsing parameters, using the pNovo 3 default modifications: carbamidomethylated
+/// cysteines and oxidised methionines written as `m`
+#[allow(clippy::missing_panics_doc)] // These modifications exist
+fn parameters() -> &'static SloppyParsingParameters {
+    PARAMETERS_LOCK.get_or_init(|| SloppyParsingParameters {
+        custom_alphabet: vec![
+            (
+                b'C',
+                SequenceElement::new(AminoAcid::Cysteine.into(), None)
+                    .with_simple_modification(Ontology::Unimod.find_id(4, None).unwrap()),
+            ),
+            (
+                b'm',
+                SequenceElement::new(AminoAcid::Methionine.into(), None)
+                    .with_simple_modification(Ontology::Unimod.find_id(35, None).unwrap()),
+            ),
+        ],
+        ..Default::default()
+    })
+}
+
+/// The Regex to match against pFind style spectrum titles (`raw.scan.scan.charge.rank.dta`)
+static TITLE_REGEX: OnceLock<regex::Regex> = OnceLock::new();
+
+/// A single candidate from a pNovo result file
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct PNovoData {
+    /// The version of pNovo
+    pub version: PNovoVersion,
+    /// The index of the spectrum in the result file (one based)
+    pub spectrum_index: usize,
+    /// The title of the spectrum
+    pub title: String,
+    /// The raw file, if the title is a pFind style title
+    pub raw_file: Option<std::path::PathBuf>,
+    /// The scan number, if the title is a pFind style title
+    pub scan: Option<usize>,
+    /// The precursor charge, if the title is a pFind style title
+    pub z: Option<Charge>,
+    /// The rank of this candidate for this spectrum (one based)
+    pub rank: usize,
+    /// The predicted peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The pNovo score
+    pub score: f64,
+}
+
+impl PNovoData {
+    /// Parse a pNovo result file.
+    /// # Errors
+    /// If the file could not be opened.
+    pub fn parse_file(
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
+        Ok(Box::new(Self::parse_reader(
+            open_reader(path)?,
+            custom_database,
+        )))
+    }
+
+    /// Parse a pNovo result file directly from a buffered reader. The file consists of spectrum
+    /// lines (`S1\ttitle`) each followed by the candidates for that spectrum
+    /// (`P1\tsequence\tscore`).
+    pub fn parse_reader<'a, T: BufRead + 'a>(
+        reader: T,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> impl Iterator<Item = Result<Self, CustomError>> + 'a {
+        let mut spectrum: Option<(usize, String)> = None;
+        reader
+            .lines()
+            .enumerate()
+            .filter_map(move |(line_index, line)| {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(err) => {
+                        return Some(Err(CustomError::error(
+                            "Could not read line",
+                            err,
+                            Context::full_line(line_index, "(failed)"),
+                        )))
+                    }
+                };
+                let mut fields = line.trim_end().split('\t');
+                let tag = fields.next().unwrap_or_default();
+                if line.trim().is_empty() {
+                    None
+                } else if let Some(index) = tag.strip_prefix('S') {
+                    match parse_index(line_index, &line, index) {
+                        Ok(index) => {
+                            spectrum =
+                                Some((index, fields.next().unwrap_or_default().trim().to_string()));
+                            None
+                        }
+                        Err(err) => Some(Err(err)),
+                    }
+                } else if let Some(rank) = tag.strip_prefix('P') {
+                    Some(spectrum.as_ref().map_or_else(
+                        || {
+                            Err(CustomError::error(
+                                "Invalid pNovo line",
+                                "A candidate line should be preceded by a spectrum line ('S')",
+                                Context::full_line(line_index, &line),
+                            ))
+                        },
+                        |(spectrum_index, title)| {
+                            Self::from_line(
+                                line_index,
+                                &line,
+                                rank,
+                                *spectrum_index,
+                                title,
+                                custom_database,
+                            )
+                        },
+                    ))
+                } else {
+                    Some(Err(CustomError::error(
+                        "Invalid pNovo line",
+                        "A line should start with a spectrum ('S') or candidate ('P') tag",
+                        Context::full_line(line_index, &line),
+                    )))
+                }
+            })
+    }
+
+    /// The scans for this candidate, based on the pFind style title if possible
+    pub(super) fn scans(&self) -> SpectrumIds {
+        match (&self.raw_file, self.scan) {
+            (Some(raw_file), Some(scan)) => {
+                SpectrumIds::FileKnown(vec![(raw_file.clone(), vec![SpectrumId::Index(scan)])])
+            }
+            _ => SpectrumIds::FileNotKnown(vec![SpectrumId::Native(self.title.clone())]),
+        }
+    }
+
+    /// Parse a single candidate line
+    /// # Errors
+    /// If the rank, sequence, or score is invalid.
+    #[allow(clippy::missing_panics_doc)] // The regex is valid and all groups are present on a match
+    fn from_line(
+        line_index: usize,
+        line: &str,
+        rank: &str,
+        spectrum_index: usize,
+        title: &str,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Self, CustomError> {
+        let fields = crate::csv::csv_separate(line, b'\t')?;
+        if fields.len() < 3 {
+            return Err(CustomError::error(
+                "Invalid pNovo line",
+                "A candidate line should contain at least the rank, sequence, and score",
+                Context::full_line(line_index, line),
+            ));
+        }
+        let (raw_file, scan, z) = TITLE_REGEX
+            .get_or_init(|| regex::Regex::new(r"^(.+)\.(\d+)\.\d+\.(\d+)\.\d+\.dta$").unwrap())
+            .captures(title)
+            .map_or((None, None, None), |m| {
+                (
+                    Some(m.get(1).unwrap().as_str().into()),
+                    m.get(2).unwrap().as_str().parse::<usize>().ok(),
+                    m.get(3)
+                        .unwrap()
+                        .as_str()
+                        .parse::<usize>()
+                        .ok()
+                        .map(Charge::new::<crate::system::e>),
+                )
+            });
+
+        Ok(Self {
+            version: PNovoVersion::V3,
+            spectrum_index,
+            title: title.to_string(),
+            raw_file,
+            scan,
+            z,
+            rank: parse_index(line_index, line, rank)?,
+            peptide: Peptidoform::sloppy_pro_forma(
+                line,
+                fields[1].clone(),
+                custom_database,
+                parameters(),
+            )?,
+            score: line[fields[2].clone()]
+                .trim()
+                .parse::<f64>()
+                .map_err(|err| {
+                    CustomError::error(
+                        "Invalid pNovo line",
+                        format!("The score is not a number: {err}"),
+                        Context::line_range(Some(line_index), line, fields[2].clone()),
+                    )
+                })?,
+        })
+    }
+}
+
+/// Parse the index following the tag of a spectrum or candidate line
+/// # Errors
+/// If the index is not a valid number
+fn parse_index(line_index: usize, line: &str, index: &str) -> Result<usize, CustomError> {
+    index.parse::<usize>().map_err(|err| {
+        CustomError::error(
+            "Invalid pNovo line",
+            format!("The index {}", explain_number_error(&err)),
+            Context::line(Some(line_index), line, 1, index.len()),
+        )
+    })
+}
+
+impl From<PNovoData> for IdentifiedPeptide {
+    fn from(value: PNovoData) -> Self {
+        Self {
+            score: Some((value.score / 100.0).clamp(-1.0, 1.0)),
+            original_score: None,
+            local_confidence: None,
+            metadata: MetaData::PNovo(value),
+        }
+    }
+}
+
+/// All supported pNovo versions
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum PNovoVersion {
+    /// pNovo 3
+    #[default]
+    V3,
+}
+
+impl std::fmt::Display for PNovoVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::V3 => "v3",
+            }
+        )
+    }
+}
@@ -0,0 +1,69 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::{BufRead, BufReader};
+
+use crate::{
+    error::CustomError,
+    identification::{test_identified_peptide, IdentifiedPeptide, PNovoData, SpectrumIds},
+};
+
+#[test]
+fn pnovo_v3() {
+    assert_eq!(open_file(BufReader::new(PNOVO_V3.as_bytes())).unwrap(), 5);
+}
+
+#[test]
+fn pnovo_metadata() {
+    let candidates = PNovoData::parse_reader(BufReader::new(PNOVO_V3.as_bytes()), None)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let first = &candidates[0];
+    assert_eq!(first.spectrum_index, 1);
+    assert_eq!(first.rank, 1);
+    assert_eq!(first.scan, Some(10_182));
+    assert_eq!(first.z.map(|z| z.value), Some(2));
+    assert!(matches!(first.scans(), SpectrumIds::FileKnown(_)));
+    // Lowercase m is an oxidised methionine, C is carbamidomethylated
+    let modified = &candidates[3];
+    assert_eq!(
+        modified.peptide.to_string(),
+        "AC[U:Carbamidomethyl]DM[U:Oxidation]TK"
+    );
+    let last = candidates.last().unwrap();
+    assert_eq!(last.spectrum_index, 3);
+    assert_eq!(last.scan, None);
+}
+
+#[test]
+fn pnovo_missing_spectrum() {
+    assert!(
+        PNovoData::parse_reader(BufReader::new(b"P1\tPEPTIDE\t50.0\n".as_slice()), None)
+            .next()
+            .unwrap()
+            .is_err()
+    );
+}
+
+/// Read all PSMs
+/// # Errors
+/// If any PSM could not be parsed.
+fn open_file(reader: impl BufRead) -> Result<usize, CustomError> {
+    let mut peptides = 0;
+    for read in PNovoData::parse_reader(reader, None) {
+        let peptide: IdentifiedPeptide = read?.into();
+        peptides += 1;
+
+        test_identified_peptide(&peptide, false, false).unwrap();
+    }
+    Ok(peptides)
+}
+
+const PNOVO_V3: &str = "S1\t20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp.10182.10182.2.0.dta
+P1\tLVNELTEFAK\t87.31
+P2\tVLNELTEFAK\t85.02
+P3\tLVNELTEFKA\t71.88
+S2\t20230408_F1_UM4_Peng0013_SA_EXT00_her_01_tryp.10190.10190.3.0.dta
+P1\tACDmTK\t64.29
+
+S3\tcontroller_spectrum_17
+P1\tSAGEK\t40.5
+";
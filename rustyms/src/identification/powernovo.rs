@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter,
 };
 
@@ -65,6 +65,7 @@ impl From<PowerNovoData> for IdentifiedPeptide {
     fn from(value: PowerNovoData) -> Self {
         Self {
             score: Some(value.score),
+            original_score: None,
             local_confidence: Some(value.local_confidence.clone()),
             metadata: MetaData::PowerNovo(value),
         }
@@ -0,0 +1,264 @@
+//! A suffix array index over a protein database for fast peptide to protein mapping
+
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::identification::FastaData;
+
+/// The separator placed between the proteins in the index, this never occurs in a peptide so
+/// matches cannot span two proteins
+const SEPARATOR: u8 = b'$';
+
+/// A suffix array index over the sequences of a protein database, to look up in which proteins
+/// a peptide occurs in `O(m log n)` time for a peptide of length `m` in a database with `n`
+/// residues. If isoleucine and leucine are folded both the database and the queries are
+/// matched with I and L seen as identical, as these cannot be distinguished by mass.
+///
+/// ```rust
+/// # use rustyms::identification::*;
+/// let database = FastaData::parse_reader(
+///     std::io::BufReader::new(">sp|P1|A A\nMPEPTIDEK\n>sp|P2|B B\nPEPTLDER\n".as_bytes()),
+///     None,
+/// )
+/// .unwrap();
+/// let index = ProteinIndex::new(&database, true);
+/// assert_eq!(index.proteins("PEPTIDE"), [0, 1]);
+/// assert!(index.is_unique("MPEPTIDE"));
+/// assert!(!index.contains("PEPTIDEKP"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProteinIndex {
+    /// All protein sequences concatenated with a separator after every protein
+    text: Vec<u8>,
+    /// The start of all suffixes of the text, in lexicographic order of the suffixes
+    suffixes: Vec<usize>,
+    /// The start of every protein in the text
+    starts: Vec<usize>,
+    /// If isoleucine is folded into leucine
+    fold_isoleucine: bool,
+}
+
+/// The location of a peptide in the database, see [`ProteinIndex::find`]
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ProteinLocation {
+    /// The index of the protein in the database
+    pub protein: usize,
+    /// The index of the first residue of the peptide in the protein sequence
+    pub start: usize,
+    /// The index of the residue after the last residue of the peptide in the protein sequence
+    pub end: usize,
+}
+
+impl ProteinLocation {
+    /// Get the range the peptide spans in the protein sequence
+    pub const fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl ProteinIndex {
+    /// Build the index for the given proteins. If `fold_isoleucine` is set isoleucine and
+    /// leucine are seen as identical. If the crate feature `rayon` is turned on (the default)
+    /// the suffixes are sorted in parallel.
+    pub fn new(proteins: &[FastaData], fold_isoleucine: bool) -> Self {
+        let mut text = Vec::new();
+        let mut starts = Vec::with_capacity(proteins.len());
+        for protein in proteins {
+            starts.push(text.len());
+            text.extend(protein.peptide().sequence().iter().map(|s| {
+                fold(
+                    u8::try_from(s.aminoacid.char()).unwrap_or(b'X'),
+                    fold_isoleucine,
+                )
+            }));
+            text.push(SEPARATOR);
+        }
+        // Suffixes starting with the separator can never match a peptide
+        let mut suffixes: Vec<usize> = (0..text.len()).filter(|i| text[*i] != SEPARATOR).collect();
+        // Only compare up to and including the separator, anything after belongs to the next
+        // protein and comparing that would make sorting duplicated proteins very slow
+        let compare = |a: &usize, b: &usize| suffix(&text, *a).cmp(suffix(&text, *b));
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            suffixes.par_sort_unstable_by(compare);
+        }
+        #[cfg(not(feature = "rayon"))]
+        suffixes.sort_unstable_by(compare);
+
+        Self {
+            text,
+            suffixes,
+            starts,
+            fold_isoleucine,
+        }
+    }
+
+    /// The number of proteins in this index
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+
+    /// Check if there are no proteins in this index
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+
+    /// Check if isoleucine and leucine are seen as identical
+    pub const fn fold_isoleucine(&self) -> bool {
+        self.fold_isoleucine
+    }
+
+    /// Get the range of suffixes that start with the given peptide
+    fn suffix_range(&self, peptide: &str) -> Range<usize> {
+        let pattern: Vec<u8> = peptide
+            .bytes()
+            .map(|b| fold(b.to_ascii_uppercase(), self.fold_isoleucine))
+            .collect();
+        if pattern.is_empty() {
+            return 0..0;
+        }
+        let prefix =
+            |suffix: usize| &self.text[suffix..(suffix + pattern.len()).min(self.text.len())];
+        let start = self
+            .suffixes
+            .partition_point(|suffix| prefix(*suffix) < pattern.as_slice());
+        let end = start
+            + self.suffixes[start..]
+                .partition_point(|suffix| prefix(*suffix) == pattern.as_slice());
+        start..end
+    }
+
+    /// Find all locations of the given peptide, given as one letter amino acid codes, in the
+    /// database. The locations are sorted on protein and location in the protein.
+    pub fn find(&self, peptide: &str) -> Vec<ProteinLocation> {
+        let mut locations: Vec<ProteinLocation> = self.suffixes[self.suffix_range(peptide)]
+            .iter()
+            .map(|position| {
+                let protein = self.starts.partition_point(|start| start <= position) - 1;
+                let start = position - self.starts[protein];
+                ProteinLocation {
+                    protein,
+                    start,
+                    end: start + peptide.len(),
+                }
+            })
+            .collect();
+        locations.sort_unstable();
+        locations
+    }
+
+    /// Get the indices of all proteins in the database that contain the given peptide, sorted
+    /// and without duplicates
+    pub fn proteins(&self, peptide: &str) -> Vec<usize> {
+        let mut proteins: Vec<usize> = self
+            .find(peptide)
+            .into_iter()
+            .map(|location| location.protein)
+            .collect();
+        proteins.dedup();
+        proteins
+    }
+
+    /// Check if the given peptide occurs in any protein in the database
+    pub fn contains(&self, peptide: &str) -> bool {
+        !self.suffix_range(peptide).is_empty()
+    }
+
+    /// Check if the given peptide occurs in exactly one protein in the database (it can occur
+    /// multiple times in that protein)
+    pub fn is_unique(&self, peptide: &str) -> bool {
+        self.proteins(peptide).len() == 1
+    }
+}
+
+/// Fold isoleucine into leucine if needed
+const fn fold(residue: u8, fold_isoleucine: bool) -> u8 {
+    if fold_isoleucine && residue == b'I' {
+        b'L'
+    } else {
+        residue
+    }
+}
+
+/// Get the suffix of the text starting at the given position up to and including the next
+/// separator
+fn suffix(text: &[u8], start: usize) -> &[u8] {
+    let end = text[start..]
+        .iter()
+        .position(|b| *b == SEPARATOR)
+        .map_or(text.len(), |offset| start + offset + 1);
+    &text[start..end]
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    fn database() -> Vec<FastaData> {
+        FastaData::parse_reader(
+            std::io::BufReader::new(
+                ">sp|P1|A A\nMPEPTIDEKPEPTIDE\n>sp|P2|B B\nPEPTLDER\n>sp|P3|C C\nAAA\n".as_bytes(),
+            ),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn exact() {
+        let index = ProteinIndex::new(&database(), false);
+        assert_eq!(index.len(), 3);
+        assert_eq!(
+            index.find("PEPTIDE"),
+            [
+                ProteinLocation {
+                    protein: 0,
+                    start: 1,
+                    end: 8,
+                },
+                ProteinLocation {
+                    protein: 0,
+                    start: 9,
+                    end: 16,
+                }
+            ]
+        );
+        assert_eq!(index.proteins("PEPTIDE"), [0]);
+        assert!(index.is_unique("PEPTIDE"));
+        assert!(index.is_unique("peptlde"));
+        assert_eq!(index.proteins("AA"), [2]);
+        assert!(!index.contains("PEPTIDEKPEPTIDEP"));
+        // Matches cannot span two proteins
+        assert!(!index.contains("PEPTIDEPEPTLDER"));
+        assert!(!index.contains(""));
+        assert!(!index.contains("W"));
+    }
+
+    #[test]
+    fn fold_isoleucine() {
+        let index = ProteinIndex::new(&database(), true);
+        assert_eq!(index.proteins("PEPTIDE"), [0, 1]);
+        assert_eq!(index.proteins("PEPTLDE"), [0, 1]);
+        assert!(!index.is_unique("PEPTIDE"));
+        assert_eq!(index.find("PEPTLDER")[0].range(), 0..8);
+    }
+
+    #[test]
+    fn duplicated_proteins() {
+        let database = FastaData::parse_reader(
+            std::io::BufReader::new(
+                b">sp|P1|A A\nMPEPTIDEK\n>sp|P2|B B\nMPEPTIDEK\n>sp|P3|C C\nPEPTIDEKR\n".as_slice(),
+            ),
+            None,
+        )
+        .unwrap();
+        let index = ProteinIndex::new(&database, false);
+        assert_eq!(index.proteins("PEPTIDEK"), [0, 1, 2]);
+        assert_eq!(index.proteins("MPEPTIDEK"), [0, 1]);
+        assert_eq!(index.proteins("EKR"), [2]);
+        assert!(!index.contains("EKM"));
+    }
+}
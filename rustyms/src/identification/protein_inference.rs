@@ -0,0 +1,417 @@
+//! Parsimonious protein inference, grouping identified peptides into protein groups
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identification::{
+        q_values, FastaData, FdrSettings, IdentifiedPeptide, ProteinIndex, ReturnedPeptide,
+        TargetDecoyPsm,
+    },
+    Peptidoform,
+};
+
+/// The settings for protein inference, see [`infer_proteins`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProteinInferenceSettings {
+    /// The settings for the target-decoy analysis, used on both the PSM and the protein level.
+    /// Target-decoy competition is only applied on the PSM level.
+    ///
+    /// Default: [`FdrSettings::default`].
+    pub fdr: FdrSettings,
+    /// The PSM level FDR threshold, only PSMs (both targets and decoys) with a q-value at or
+    /// below this threshold are used for inference.
+    ///
+    /// Default: 0.01.
+    pub psm_fdr: f64,
+}
+
+impl Default for ProteinInferenceSettings {
+    fn default() -> Self {
+        Self {
+            fdr: FdrSettings::default(),
+            psm_fdr: 0.01,
+        }
+    }
+}
+
+impl ProteinInferenceSettings {
+    /// Set the target-decoy settings
+    #[must_use]
+    pub fn fdr(self, fdr: FdrSettings) -> Self {
+        Self { fdr, ..self }
+    }
+
+    /// Set the PSM level FDR threshold
+    #[must_use]
+    pub fn psm_fdr(self, psm_fdr: f64) -> Self {
+        Self { psm_fdr, ..self }
+    }
+}
+
+/// A peptide spectrum match that can be used for protein inference. This is implemented for
+/// [`IdentifiedPeptide`] so it can be used for all identification formats, and for tuples of a
+/// peptidoform with its score and decoy status for PSMs from other sources.
+pub trait ProteinInferencePsm: TargetDecoyPsm {
+    /// The amino acid sequence of the peptide, as one letter codes without modifications. `None`
+    /// if the peptide is not known or cannot be represented as a linear peptide.
+    fn sequence(&self) -> Option<String>;
+}
+
+impl ProteinInferencePsm for IdentifiedPeptide {
+    fn sequence(&self) -> Option<String> {
+        self.peptide()
+            .and_then(ReturnedPeptide::peptide)
+            .map(|peptide| {
+                peptide
+                    .sequence()
+                    .iter()
+                    .map(|s| s.aminoacid.char())
+                    .collect()
+            })
+    }
+}
+
+impl<Complexity> ProteinInferencePsm for (Peptidoform<Complexity>, f64, bool) {
+    fn sequence(&self) -> Option<String> {
+        Some(
+            self.0
+                .sequence()
+                .iter()
+                .map(|s| s.aminoacid.char())
+                .collect(),
+        )
+    }
+}
+
+/// If a peptide is only found in the proteins of a single protein group or in multiple groups
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PeptideSharing {
+    /// This peptide is only found in the proteins of one protein group
+    Unique,
+    /// This peptide is found in the proteins of multiple protein groups
+    Shared,
+}
+
+/// A peptide that is part of a protein group
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InferredPeptide {
+    /// The amino acid sequence, as one letter codes without modifications
+    pub sequence: String,
+    /// The indices of all PSMs with this sequence in the PSMs given to [`infer_proteins`]
+    pub psms: Vec<usize>,
+    /// The best score of all PSMs with this sequence
+    pub score: f64,
+    /// If this peptide is unique to the protein group
+    pub sharing: PeptideSharing,
+}
+
+/// A group of proteins that cannot be distinguished based on the identified peptides
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProteinGroup {
+    /// The accessions of all proteins in this group, in the order of the database
+    pub proteins: Vec<String>,
+    /// All peptides found in the proteins of this group
+    pub peptides: Vec<InferredPeptide>,
+    /// The score of this group, the best score of its peptides
+    pub score: f64,
+    /// If all proteins in this group are decoys
+    pub decoy: bool,
+    /// The protein level q-value, the lowest FDR at which this group is accepted
+    pub q_value: f64,
+}
+
+impl ProteinGroup {
+    /// Iterate over all peptides that are unique to this group
+    pub fn unique_peptides(&self) -> impl Iterator<Item = &InferredPeptide> + '_ {
+        self.peptides
+            .iter()
+            .filter(|p| p.sharing == PeptideSharing::Unique)
+    }
+}
+
+/// A PSM with its index in the input, to be able to refer back to the PSM after the q-value
+/// calculation
+struct IndexedPsm<'a, P> {
+    index: usize,
+    psm: &'a P,
+}
+
+impl<P: TargetDecoyPsm> TargetDecoyPsm for IndexedPsm<'_, P> {
+    fn score(&self) -> Option<f64> {
+        self.psm.score()
+    }
+
+    fn is_decoy(&self, settings: &FdrSettings) -> bool {
+        self.psm.is_decoy(settings)
+    }
+
+    fn spectrum(&self) -> Option<Vec<(Option<PathBuf>, String)>> {
+        self.psm.spectrum()
+    }
+}
+
+/// Infer the minimal set of protein groups that explains all identified peptides (Occam's
+/// razor). The steps are:
+/// 1. Only the PSMs passing the PSM level FDR threshold are used, see [`q_values`].
+/// 2. The peptides are matched to all proteins in the database containing their amino acid
+///    sequence, using a [`ProteinIndex`]. Peptides not found in any protein are ignored.
+/// 3. Proteins with the exact same set of peptides are grouped, and groups whose peptides are a
+///    strict subset of the peptides of another group are removed.
+/// 4. Groups are greedily selected, every time picking the group that explains the most
+///    peptides that are not yet explained, until all peptides are explained.
+/// 5. The peptides are classified as unique or shared between the selected groups, and a group
+///    is a decoy if all of its proteins are decoys (see [`FdrSettings::is_decoy_protein`]).
+/// 6. The protein level q-values are calculated with the best peptide score as the group score.
+///
+/// The groups are returned sorted from best to worst score.
+///
+/// ```rust
+/// # use rustyms::{*, identification::*};
+/// let database = FastaData::parse_reader(
+///     std::io::BufReader::new(
+///         ">sp|P1|A A\nMPEPTIDEKAAGGLLR\n>sp|P2|B B\nAAGGLLR\n>sp|rev_P1|A A\nRLLGGAAKEDITPEPM\n"
+///             .as_bytes(),
+///     ),
+///     None,
+/// )
+/// .unwrap();
+/// let psm = |sequence, score, decoy| (Peptidoform::pro_forma(sequence, None).unwrap(), score, decoy);
+/// let psms = [psm("MPEPTIDEK", 10.0, false), psm("AAGGLLR", 9.0, false)];
+/// let groups = infer_proteins(&psms, &database, &ProteinInferenceSettings::default());
+/// // P2 is explained by P1 so only P1 is reported
+/// assert_eq!(groups.len(), 1);
+/// assert_eq!(groups[0].proteins, ["P1"]);
+/// assert_eq!(groups[0].unique_peptides().count(), 2);
+/// ```
+pub fn infer_proteins<P: ProteinInferencePsm>(
+    psms: &[P],
+    database: &[FastaData],
+    settings: &ProteinInferenceSettings,
+) -> Vec<ProteinGroup> {
+    // Collect the accepted peptides, the PSMs are sorted from best to worst so the first PSM for
+    // every sequence has the best score
+    let mut peptides: Vec<(String, Vec<usize>, f64)> = Vec::new();
+    let mut peptide_index: BTreeMap<String, usize> = BTreeMap::new();
+    for scored in q_values(
+        psms.iter()
+            .enumerate()
+            .map(|(index, psm)| IndexedPsm { index, psm }),
+        &settings.fdr,
+    ) {
+        if scored.q_value > settings.psm_fdr {
+            continue;
+        }
+        let Some(sequence) = scored.psm.psm.sequence().filter(|s| !s.is_empty()) else {
+            continue;
+        };
+        if let Some(index) = peptide_index.get(&sequence) {
+            peptides[*index].1.push(scored.psm.index);
+        } else {
+            peptide_index.insert(sequence.clone(), peptides.len());
+            peptides.push((sequence, vec![scored.psm.index], scored.score));
+        }
+    }
+
+    // Match the peptides to the proteins and group proteins with the same peptides
+    let index = ProteinIndex::new(database, false);
+    let mut protein_peptides: BTreeMap<usize, BTreeSet<usize>> = BTreeMap::new();
+    for (peptide_index, (peptide, _, _)) in peptides.iter().enumerate() {
+        for protein in index.proteins(peptide) {
+            protein_peptides
+                .entry(protein)
+                .or_default()
+                .insert(peptide_index);
+        }
+    }
+    let mut groups: BTreeMap<BTreeSet<usize>, Vec<usize>> = BTreeMap::new();
+    for (protein, matched) in protein_peptides {
+        groups.entry(matched).or_default().push(protein);
+    }
+    let groups: Vec<(BTreeSet<usize>, Vec<usize>)> = groups
+        .iter()
+        .filter(|(peptides, _)| {
+            !groups
+                .keys()
+                .any(|other| other.len() > peptides.len() && other.is_superset(peptides))
+        })
+        .map(|(peptides, proteins)| (peptides.clone(), proteins.clone()))
+        .collect();
+
+    // Greedily select the groups that explain the most unexplained peptides
+    let mut unexplained: BTreeSet<usize> = groups.iter().flat_map(|(p, _)| p).copied().collect();
+    let mut selected: Vec<&(BTreeSet<usize>, Vec<usize>)> = Vec::new();
+    while !unexplained.is_empty() {
+        let Some(best) = groups
+            .iter()
+            .max_by_key(|(peptides, proteins)| {
+                (
+                    peptides.intersection(&unexplained).count(),
+                    peptides.len(),
+                    std::cmp::Reverse(proteins[0]),
+                )
+            })
+            .filter(|(peptides, _)| !peptides.is_disjoint(&unexplained))
+        else {
+            break;
+        };
+        unexplained.retain(|p| !best.0.contains(p));
+        selected.push(best);
+    }
+
+    // Classify the peptides and score the groups
+    let mut occurrences = vec![0_usize; peptides.len()];
+    for peptide in selected.iter().flat_map(|(p, _)| p) {
+        occurrences[*peptide] += 1;
+    }
+    let better = |a: f64, b: f64| {
+        if settings.fdr.higher_score_is_better {
+            a.max(b)
+        } else {
+            a.min(b)
+        }
+    };
+    let groups = selected.into_iter().map(|(group_peptides, proteins)| {
+        let peptides: Vec<InferredPeptide> = group_peptides
+            .iter()
+            .map(|index| {
+                let (sequence, psms, score) = &peptides[*index];
+                InferredPeptide {
+                    sequence: sequence.clone(),
+                    psms: psms.clone(),
+                    score: *score,
+                    sharing: if occurrences[*index] == 1 {
+                        PeptideSharing::Unique
+                    } else {
+                        PeptideSharing::Shared
+                    },
+                }
+            })
+            .collect();
+        let score = peptides
+            .iter()
+            .map(|p| p.score)
+            .reduce(better)
+            .unwrap_or(f64::NAN);
+        let decoy = proteins.iter().all(|index| {
+            settings
+                .fdr
+                .is_decoy_protein(&database[*index].identifier().to_string())
+        });
+        let group = ProteinGroup {
+            proteins: proteins
+                .iter()
+                .map(|index| database[*index].identifier().accession().to_string())
+                .collect(),
+            peptides,
+            score,
+            decoy,
+            q_value: 1.0,
+        };
+        (group, score, decoy)
+    });
+
+    // Protein level FDR
+    q_values(groups, &settings.fdr.clone().competition(false))
+        .into_iter()
+        .map(|scored| ProteinGroup {
+            q_value: scored.q_value,
+            ..scored.psm.0
+        })
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc, clippy::float_cmp)]
+mod tests {
+    use super::*;
+    use crate::peptidoform::SemiAmbiguous;
+
+    fn psm(sequence: &str, score: f64, decoy: bool) -> (Peptidoform<SemiAmbiguous>, f64, bool) {
+        (
+            Peptidoform::pro_forma(sequence, None)
+                .unwrap()
+                .into_semi_ambiguous()
+                .unwrap(),
+            score,
+            decoy,
+        )
+    }
+
+    #[test]
+    fn parsimony() {
+        let database = FastaData::parse_reader(
+            std::io::BufReader::new(
+                ">sp|P1|A A\nMPEPTIDEKAAGGLLR\n>sp|P2|B B\nAAGGLLR\n>sp|P3|C C\nSAMEPEPTIDEK\n\
+                 >sp|P4|D D\nSAMEPEPTIDEKWW\n>sp|P5|E E\nMPEPTIDEKLLLLLLR\n\
+                 >sp|rev_P1|A A\nRLLGGAAKEDITPEPM\n"
+                    .as_bytes(),
+            ),
+            None,
+        )
+        .unwrap();
+        let psms = [
+            psm("MPEPTIDEK", 10.0, false),
+            psm("AAGGLLR", 9.0, false),
+            psm("AAGGLLR", 8.0, false),
+            psm("SAMEPEPTIDEK", 7.0, false),
+            psm("LLLLLLR", 6.0, false),
+            psm("RLLGGAAK", 5.0, true),
+            psm("NOTFOUND", 4.0, false),
+        ];
+        let groups = infer_proteins(
+            &psms,
+            &database,
+            &ProteinInferenceSettings::default().psm_fdr(1.0),
+        );
+        assert_eq!(
+            groups
+                .iter()
+                .map(|g| g.proteins.clone())
+                .collect::<Vec<_>>(),
+            [
+                vec!["P1".to_string()],
+                vec!["P5".to_string()],
+                vec!["P3".to_string(), "P4".to_string()],
+                vec!["rev_P1".to_string()],
+            ]
+        );
+        assert_eq!(
+            groups[0]
+                .peptides
+                .iter()
+                .map(|p| (p.sequence.as_str(), p.psms.clone(), p.sharing))
+                .collect::<Vec<_>>(),
+            [
+                ("MPEPTIDEK", vec![0], PeptideSharing::Shared),
+                ("AAGGLLR", vec![1, 2], PeptideSharing::Unique),
+            ]
+        );
+        assert_eq!(groups[0].score, 10.0);
+        assert_eq!(groups[1].unique_peptides().count(), 1);
+        assert!(groups[..3].iter().all(|g| !g.decoy && g.q_value == 0.0));
+        assert!(groups[3].decoy);
+        assert_eq!(groups[3].q_value, 1.0 / 3.0);
+    }
+
+    #[test]
+    fn psm_threshold() {
+        let database = FastaData::parse_reader(
+            std::io::BufReader::new(">sp|P1|A A\nMPEPTIDEK\n>sp|P2|B B\nAAGGLLR\n".as_bytes()),
+            None,
+        )
+        .unwrap();
+        let psms = [
+            psm("MPEPTIDEK", 10.0, false),
+            psm("DECOYK", 9.0, true),
+            psm("AAGGLLR", 8.0, false),
+        ];
+        let groups = infer_proteins(&psms, &database, &ProteinInferenceSettings::default());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(groups[0].proteins, ["P1"]);
+    }
+}
@@ -0,0 +1,389 @@
+//! Run level quality control metrics for identified peptides
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identification::{IdentifiedPeptide, SpectrumIds},
+    modification::SimpleModification,
+    system::{time::min, Mass},
+    MolecularFormula, MultiChemical, Protease,
+};
+
+/// Collects identified peptides per raw file and summarises them into [`RunQualityReport`]s.
+/// Peptides for which the raw file is not known are grouped together in a single run without
+/// a raw file.
+///
+/// ```rust
+/// # use rustyms::identification::*;
+/// # fn main() -> Result<(), rustyms::error::CustomError> {
+/// let mut collector = RunQualityCollector::new(None);
+/// collector.extend(open_identified_peptides_file("src/identification/test_files/sage_v0_14.tsv", None)?.filter_map(Result::ok));
+/// let reports = collector.reports();
+/// assert_eq!(reports.len(), 3);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Default)]
+pub struct RunQualityCollector<'a> {
+    protease: Option<&'a Protease>,
+    runs: BTreeMap<Option<PathBuf>, RunAccumulator>,
+}
+
+/// The intermediate state for a single run
+#[derive(Clone, Debug, Default)]
+struct RunAccumulator {
+    identifications: usize,
+    retention_times: Vec<f64>,
+    ppm_errors: Vec<f64>,
+    missed_cleavages: BTreeMap<usize, usize>,
+    modifications: BTreeMap<SimpleModification, usize>,
+    modified_peptides: usize,
+    charges: BTreeMap<usize, usize>,
+}
+
+impl<'a> RunQualityCollector<'a> {
+    /// Create a new empty collector. If a protease is given the missed cleavages are determined
+    /// for all peptides, otherwise these statistics are not reported.
+    pub const fn new(protease: Option<&'a Protease>) -> Self {
+        Self {
+            protease,
+            runs: BTreeMap::new(),
+        }
+    }
+
+    /// Add a single identified peptide to the statistics for its run
+    pub fn add(&mut self, peptide: &IdentifiedPeptide) {
+        let raw_file = match peptide.scans() {
+            SpectrumIds::FileKnown(files) => files.into_iter().next().map(|(file, _)| file),
+            SpectrumIds::FileNotKnown(_) | SpectrumIds::None => None,
+        };
+        let run = self.runs.entry(raw_file).or_default();
+        run.identifications += 1;
+
+        if let Some(rt) = peptide.retention_time() {
+            run.retention_times.push(rt.get::<min>());
+        }
+        if let Some(charge) = peptide.charge() {
+            *run.charges.entry(charge.value).or_default() += 1;
+        }
+        if let Some((experimental, theoretical)) = peptide.experimental_mass().and_then(|mass| {
+            peptide
+                .peptide()
+                .and_then(|p| closest_theoretical_mass(&p.formulas(), mass))
+                .map(|theoretical| (mass, theoretical))
+        }) {
+            run.ppm_errors
+                .push((experimental - theoretical).value / theoretical.value * 1e6);
+        }
+        if let Some(returned) = peptide.peptide() {
+            let compound = returned.compound_peptidoform();
+            let mut modified = false;
+            let mut cleavages = 0;
+            for peptidoform in compound.peptidoforms() {
+                for modification in peptidoform
+                    .get_n_term()
+                    .iter()
+                    .chain(peptidoform.get_c_term())
+                    .chain(peptidoform.sequence().iter().flat_map(|s| &s.modifications))
+                    .filter_map(crate::Modification::simple)
+                {
+                    *run.modifications.entry(modification.clone()).or_default() += 1;
+                    modified = true;
+                }
+                if let Some(protease) = self.protease {
+                    if peptidoform.len() > protease.n_term.len() + protease.c_term.len() {
+                        cleavages += protease.match_locations(peptidoform.sequence()).len();
+                    }
+                }
+            }
+            if modified {
+                run.modified_peptides += 1;
+            }
+            if self.protease.is_some() {
+                *run.missed_cleavages.entry(cleavages).or_default() += 1;
+            }
+        }
+    }
+
+    /// Get the quality control report for all runs seen so far, sorted on raw file
+    pub fn reports(&self) -> Vec<RunQualityReport> {
+        self.runs
+            .iter()
+            .map(|(raw_file, run)| run.report(raw_file.clone(), self.protease.is_some()))
+            .collect()
+    }
+}
+
+impl<'b> Extend<&'b IdentifiedPeptide> for RunQualityCollector<'_> {
+    fn extend<T: IntoIterator<Item = &'b IdentifiedPeptide>>(&mut self, iter: T) {
+        for peptide in iter {
+            self.add(peptide);
+        }
+    }
+}
+
+impl Extend<IdentifiedPeptide> for RunQualityCollector<'_> {
+    fn extend<T: IntoIterator<Item = IdentifiedPeptide>>(&mut self, iter: T) {
+        for peptide in iter {
+            self.add(&peptide);
+        }
+    }
+}
+
+impl RunAccumulator {
+    fn report(&self, raw_file: Option<PathBuf>, missed_cleavages: bool) -> RunQualityReport {
+        let mut identifications_per_minute: BTreeMap<usize, usize> = BTreeMap::new();
+        for rt in &self.retention_times {
+            *identifications_per_minute
+                .entry(rt.max(0.0).floor() as usize)
+                .or_default() += 1;
+        }
+        RunQualityReport {
+            raw_file,
+            identifications: self.identifications,
+            retention_time_range: Distribution::new(&self.retention_times)
+                .map(|d| (d.minimum, d.maximum)),
+            identifications_per_minute: identifications_per_minute.into_iter().collect(),
+            mass_error: Distribution::new(&self.ppm_errors),
+            missed_cleavages: missed_cleavages.then(|| {
+                self.missed_cleavages
+                    .iter()
+                    .map(|(k, v)| (*k, *v))
+                    .collect()
+            }),
+            modified_peptides: self.modified_peptides,
+            modifications: self
+                .modifications
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect(),
+            charges: self.charges.iter().map(|(k, v)| (*k, *v)).collect(),
+        }
+    }
+}
+
+/// The quality control metrics for a single run
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RunQualityReport {
+    /// The raw file, if known
+    pub raw_file: Option<PathBuf>,
+    /// The total number of identified peptides
+    pub identifications: usize,
+    /// The first and last retention time of any identified peptide (in minutes)
+    pub retention_time_range: Option<(f64, f64)>,
+    /// The number of identified peptides in each minute of retention time, (minute, count) sorted on minute, minutes without identifications are left out
+    pub identifications_per_minute: Vec<(usize, usize)>,
+    /// The distribution of the signed precursor mass error (in ppm)
+    pub mass_error: Option<Distribution>,
+    /// The number of peptides with the given number of missed cleavages (missed cleavages, count), only available if a protease was given
+    pub missed_cleavages: Option<Vec<(usize, usize)>>,
+    /// The number of peptides with at least one modification
+    pub modified_peptides: usize,
+    /// The number of times each modification is seen
+    pub modifications: Vec<(SimpleModification, usize)>,
+    /// The number of peptides with the given precursor charge (charge, count)
+    pub charges: Vec<(usize, usize)>,
+}
+
+impl RunQualityReport {
+    /// The average number of identified peptides per minute over the full identified retention time range
+    pub fn average_identifications_per_minute(&self) -> Option<f64> {
+        self.retention_time_range.and_then(|(start, end)| {
+            (end > start).then(|| self.identifications as f64 / (end - start))
+        })
+    }
+
+    /// The fraction of peptides that has at least one missed cleavage, only available if a protease was given
+    pub fn missed_cleavage_rate(&self) -> Option<f64> {
+        self.missed_cleavages.as_ref().map(|missed| {
+            let (with, total) = missed.iter().fold((0, 0), |(with, total), (n, count)| {
+                (with + if *n > 0 { *count } else { 0 }, total + count)
+            });
+            if total == 0 {
+                0.0
+            } else {
+                with as f64 / total as f64
+            }
+        })
+    }
+
+    /// The fraction of peptides that has at least one modification
+    pub fn modification_rate(&self) -> f64 {
+        if self.identifications == 0 {
+            0.0
+        } else {
+            self.modified_peptides as f64 / self.identifications as f64
+        }
+    }
+
+    /// The fraction of peptides that has the given modification
+    pub fn modification_rate_of(&self, modification: &SimpleModification) -> f64 {
+        if self.identifications == 0 {
+            0.0
+        } else {
+            self.modifications
+                .iter()
+                .find(|(m, _)| m == modification)
+                .map_or(0, |(_, n)| *n) as f64
+                / self.identifications as f64
+        }
+    }
+}
+
+/// A summary of a distribution of values
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct Distribution {
+    /// The number of values
+    pub count: usize,
+    /// The average value
+    pub mean: f64,
+    /// The standard deviation
+    pub standard_deviation: f64,
+    /// The lowest value
+    pub minimum: f64,
+    /// The first quartile
+    pub first_quartile: f64,
+    /// The median
+    pub median: f64,
+    /// The third quartile
+    pub third_quartile: f64,
+    /// The highest value
+    pub maximum: f64,
+}
+
+impl Distribution {
+    /// Summarise the given values, returns None if there are no (non NaN) values
+    pub fn new(values: &[f64]) -> Option<Self> {
+        let mut sorted: Vec<f64> = values.iter().copied().filter(|v| !v.is_nan()).collect();
+        if sorted.is_empty() {
+            return None;
+        }
+        sorted.sort_unstable_by(f64::total_cmp);
+        let count = sorted.len();
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let standard_deviation =
+            (sorted.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / count as f64).sqrt();
+        let quantile = |q: f64| {
+            let position = q * (count - 1) as f64;
+            let low = sorted[position.floor() as usize];
+            let high = sorted[position.ceil() as usize];
+            (high - low).mul_add(position.fract(), low)
+        };
+        Some(Self {
+            count,
+            mean,
+            standard_deviation,
+            minimum: sorted[0],
+            first_quartile: quantile(0.25),
+            median: quantile(0.5),
+            third_quartile: quantile(0.75),
+            maximum: sorted[count - 1],
+        })
+    }
+}
+
+/// Get the theoretical mass of the formula closest to the experimental mass. If a peptide has
+/// multiple formulas with different masses that are equally close the theoretical mass is
+/// ambiguous and `None` is returned.
+fn closest_theoretical_mass(formulas: &[MolecularFormula], experimental: Mass) -> Option<Mass> {
+    let mut masses = formulas
+        .iter()
+        .map(MolecularFormula::monoisotopic_mass)
+        .collect::<Vec<_>>();
+    masses.sort_unstable_by(|a, b| {
+        (experimental - *a)
+            .abs()
+            .value
+            .total_cmp(&(experimental - *b).abs().value)
+    });
+    match masses.as_slice() {
+        [closest, next, ..]
+            if closest != next
+                && (experimental - *closest).abs() == (experimental - *next).abs() =>
+        {
+            None
+        }
+        [closest, ..] => Some(*closest),
+        [] => None,
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{identification::open_identified_peptides_file, AminoAcid};
+
+    #[test]
+    fn distribution() {
+        let distribution = Distribution::new(&[4.0, 1.0, 3.0, 2.0, f64::NAN]).unwrap();
+        assert_eq!(distribution.count, 4);
+        assert!((distribution.mean - 2.5).abs() < f64::EPSILON);
+        assert!((distribution.median - 2.5).abs() < f64::EPSILON);
+        assert!((distribution.first_quartile - 1.75).abs() < f64::EPSILON);
+        assert!((distribution.minimum - 1.0).abs() < f64::EPSILON);
+        assert!((distribution.maximum - 4.0).abs() < f64::EPSILON);
+        assert_eq!(Distribution::new(&[]), None);
+    }
+
+    #[test]
+    fn closest_mass() {
+        let low = MolecularFormula::with_additional_mass(100.0);
+        let high = MolecularFormula::with_additional_mass(101.0);
+        let mass = |value: f64| Mass::new::<crate::system::dalton>(value);
+        assert_eq!(
+            closest_theoretical_mass(std::slice::from_ref(&low), mass(101.0)),
+            Some(mass(100.0))
+        );
+        assert_eq!(
+            closest_theoretical_mass(&[low.clone(), high.clone()], mass(100.75)),
+            Some(mass(101.0))
+        );
+        assert_eq!(
+            closest_theoretical_mass(&[low.clone(), high], mass(100.5)),
+            None
+        );
+        assert_eq!(
+            closest_theoretical_mass(&[low.clone(), low], mass(100.5)),
+            Some(mass(100.0))
+        );
+        assert_eq!(closest_theoretical_mass(&[], mass(100.0)), None);
+    }
+
+    #[test]
+    fn sage_runs() {
+        let protease = Protease::c_terminal_of(&[AminoAcid::GlutamicAcid]);
+        let mut collector = RunQualityCollector::new(Some(&protease));
+        collector.extend(
+            open_identified_peptides_file("src/identification/test_files/sage_v0_14.tsv", None)
+                .unwrap()
+                .map(Result::unwrap),
+        );
+        let reports = collector.reports();
+        assert_eq!(reports.iter().map(|r| r.identifications).sum::<usize>(), 19);
+        for report in &reports {
+            assert!(report.raw_file.is_some());
+            assert_eq!(
+                report.charges.iter().map(|(_, n)| n).sum::<usize>(),
+                report.identifications
+            );
+            assert_eq!(
+                report
+                    .identifications_per_minute
+                    .iter()
+                    .map(|(_, n)| n)
+                    .sum::<usize>(),
+                report.identifications
+            );
+            assert!(report.mass_error.is_some());
+            assert!(report.missed_cleavage_rate().is_some());
+        }
+        let serialised = bincode::serialize(&reports).unwrap();
+        let back: Vec<RunQualityReport> = bincode::deserialize(&serialised).unwrap();
+        assert_eq!(back, reports);
+    }
+}
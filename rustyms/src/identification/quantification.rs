@@ -0,0 +1,323 @@
+//! Label-free quantification of identified peptides on their precursor signal in MS1 spectra
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    identification::{IdentifiedPeptide, SpectrumIds},
+    spectrum::{PeakSpectrum, RawSpectrum},
+    system::{
+        time::{min, s},
+        MassOverCharge, Time,
+    },
+    Precision, Tolerance, WithinTolerance,
+};
+
+/// The settings for matching identified peptides back to their precursor signal in MS1 spectra,
+/// see [`IdentifiedPeptide::precursor_area`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct QuantificationSettings {
+    /// The maximal retention time difference between the identification and an MS1 spectrum for
+    /// the spectrum to be included in the extracted ion chromatogram.
+    ///
+    /// Default: 1 minute.
+    pub rt_window: Time,
+    /// The tolerance for matching the precursor m/z to the MS1 peaks.
+    ///
+    /// Default: the comparison tolerance of the global [`Precision`].
+    pub tolerance: Tolerance<MassOverCharge>,
+}
+
+impl Default for QuantificationSettings {
+    fn default() -> Self {
+        Self {
+            rt_window: Time::new::<min>(1.0),
+            tolerance: Precision::global().tolerance(),
+        }
+    }
+}
+
+impl QuantificationSettings {
+    /// Set the maximal retention time difference
+    #[must_use]
+    pub fn rt_window(self, rt_window: Time) -> Self {
+        Self { rt_window, ..self }
+    }
+
+    /// Set the m/z tolerance
+    #[must_use]
+    pub fn tolerance(self, tolerance: Tolerance<MassOverCharge>) -> Self {
+        Self { tolerance, ..self }
+    }
+}
+
+impl IdentifiedPeptide {
+    /// Quantify this peptide on its precursor signal. The extracted ion chromatogram is built
+    /// from the most intense peak within the m/z tolerance of the experimental precursor m/z in
+    /// all given MS1 spectra within the retention time window around the retention time of this
+    /// identification. The area is integrated with the trapezoidal rule, in intensity × seconds.
+    ///
+    /// Returns `None` if this peptide has no experimental m/z or retention time, or if less than
+    /// two MS1 spectra fall within the retention time window, or if no signal was found.
+    pub fn precursor_area(
+        &self,
+        ms1: &[RawSpectrum],
+        settings: &QuantificationSettings,
+    ) -> Option<f64> {
+        let mz = self.experimental_mz()?;
+        let rt = self.retention_time()?;
+        let (low, high) = settings.tolerance.bounds(mz);
+        let mut trace: Vec<(f64, f64)> = ms1
+            .iter()
+            .filter_map(|spectrum| {
+                let spectrum_rt = spectrum.rt?;
+                ((spectrum_rt - rt).abs() <= settings.rt_window).then(|| {
+                    (
+                        spectrum_rt.get::<s>(),
+                        spectrum
+                            .binary_search(low, high)
+                            .iter()
+                            .filter(|peak| settings.tolerance.within(&peak.mz, &mz))
+                            .map(|peak| *peak.intensity)
+                            .fold(0.0, f64::max),
+                    )
+                })
+            })
+            .collect();
+        trace.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+        let area: f64 = trace
+            .windows(2)
+            .map(|w| (w[1].0 - w[0].0) * (w[0].1 + w[1].1) / 2.0)
+            .sum();
+        (area > 0.0).then_some(area)
+    }
+}
+
+/// A peptide × run matrix of precursor areas for label-free quantification, see
+/// [`QuantificationMatrix::new`].
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct QuantificationMatrix {
+    /// The peptides (as ProForma) of the rows, sorted
+    pub peptides: Vec<String>,
+    /// The runs of the columns, in the order as given
+    pub runs: Vec<PathBuf>,
+    /// The precursor areas, per peptide per run, `None` if the peptide was not quantified in
+    /// that run
+    pub areas: Vec<Vec<Option<f64>>>,
+}
+
+impl QuantificationMatrix {
+    /// Quantify all given peptides on the MS1 spectra of the run they were identified in, see
+    /// [`IdentifiedPeptide::precursor_area`]. Peptides are matched to runs on the file stem of
+    /// their raw file, if the raw file is not known this is only possible if there is a single
+    /// run. If multiple identifications of the same peptide and charge exist in one run the
+    /// highest area is used (as these quantify the same signal), the areas of different charge
+    /// states are summed. Peptides without a peptide sequence or area are ignored.
+    ///
+    /// ```rust
+    /// # use rustyms::identification::*;
+    /// # use std::path::PathBuf;
+    /// # fn main() -> Result<(), rustyms::error::CustomError> {
+    /// let peptides = open_identified_peptides_file("src/identification/test_files/sage_v0_14.tsv", None)?
+    ///     .filter_map(Result::ok)
+    ///     .collect::<Vec<_>>();
+    /// // These would be the MS1 spectra of the run, for example loaded with mzdata
+    /// let runs = vec![(PathBuf::from("LFQ_Orbitrap_AIF_Condition_A_Sample_Alpha_01.mzML"), Vec::new())];
+    /// let matrix = QuantificationMatrix::new(&peptides, &runs, &QuantificationSettings::default());
+    /// let mut csv = Vec::new();
+    /// matrix.write_csv(&mut csv)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new<'a>(
+        peptides: impl IntoIterator<Item = &'a IdentifiedPeptide>,
+        runs: &[(PathBuf, Vec<RawSpectrum>)],
+        settings: &QuantificationSettings,
+    ) -> Self {
+        let mut per_charge: BTreeMap<(String, usize, usize), f64> = BTreeMap::new();
+        for peptide in peptides {
+            let Some(sequence) = peptide.peptide().map(|p| p.to_string()) else {
+                continue;
+            };
+            let Some(run) = find_run(peptide, runs) else {
+                continue;
+            };
+            let Some(area) = peptide.precursor_area(&runs[run].1, settings) else {
+                continue;
+            };
+            let charge = peptide.charge().map_or(0, |c| c.value);
+            let entry = per_charge.entry((sequence, charge, run)).or_insert(area);
+            *entry = entry.max(area);
+        }
+
+        let mut areas: BTreeMap<String, Vec<Option<f64>>> = BTreeMap::new();
+        for ((sequence, _, run), area) in per_charge {
+            let row = areas
+                .entry(sequence)
+                .or_insert_with(|| vec![None; runs.len()]);
+            row[run] = Some(row[run].unwrap_or_default() + area);
+        }
+
+        let (peptides, areas) = areas.into_iter().unzip();
+        Self {
+            peptides,
+            runs: runs.iter().map(|(run, _)| run.clone()).collect(),
+            areas,
+        }
+    }
+
+    /// Get the area for the given peptide (as ProForma) in the given run
+    pub fn get(&self, peptide: &str, run: &Path) -> Option<f64> {
+        let row = self.peptides.iter().position(|p| p == peptide)?;
+        let column = self.runs.iter().position(|r| r == run)?;
+        self.areas[row][column]
+    }
+
+    /// Write this matrix as CSV, with a column 'peptide' followed by one column per run. Areas
+    /// that are not quantified are left empty.
+    /// # Errors
+    /// If the writer could not be written to.
+    pub fn write_csv(&self, writer: impl Write) -> Result<(), CustomError> {
+        crate::csv::write_csv(
+            writer,
+            self.peptides.iter().zip(&self.areas).map(|(peptide, row)| {
+                std::iter::once(("peptide".to_string(), peptide.clone())).chain(
+                    self.runs.iter().zip(row).map(|(run, area)| {
+                        (
+                            run.to_string_lossy().to_string(),
+                            area.map_or_else(String::new, |a| a.to_string()),
+                        )
+                    }),
+                )
+            }),
+        )
+        .map_err(|error| {
+            CustomError::error(
+                "Could not write quantification matrix",
+                error,
+                Context::none(),
+            )
+        })
+    }
+}
+
+/// Find the index of the run the given peptide was identified in
+fn find_run(peptide: &IdentifiedPeptide, runs: &[(PathBuf, Vec<RawSpectrum>)]) -> Option<usize> {
+    match peptide.scans() {
+        SpectrumIds::FileKnown(files) => files.iter().find_map(|(file, _)| {
+            let stem = file.file_stem()?;
+            runs.iter()
+                .position(|(run, _)| run.file_stem() == Some(stem))
+        }),
+        SpectrumIds::FileNotKnown(_) | SpectrumIds::None => (runs.len() == 1).then_some(0),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        identification::open_identified_peptides_file,
+        spectrum::RawPeak,
+        system::{e, usize::Charge},
+    };
+
+    /// Simulate the MS1 spectra for a single precursor with a triangular elution profile
+    fn ms1(mz: MassOverCharge, rt: Time, apex: f64) -> Vec<RawSpectrum> {
+        (-10_i32..=10)
+            .map(|step| {
+                let mut spectrum = RawSpectrum::default();
+                spectrum.rt = Some(rt + Time::new::<s>(f64::from(step) * 3.0));
+                spectrum.extend([
+                    RawPeak::new(mz - MassOverCharge::new::<crate::system::mz>(1.0), 1e9),
+                    RawPeak::new(mz, apex * (1.0 - f64::from(step.abs()) / 10.0)),
+                ]);
+                spectrum
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quantify() {
+        let peptide: IdentifiedPeptide =
+            open_identified_peptides_file("src/identification/test_files/sage_v0_14.tsv", None)
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap();
+        let (mz, rt) = (
+            peptide.experimental_mz().unwrap(),
+            peptide.retention_time().unwrap(),
+        );
+        let settings = QuantificationSettings::default();
+        // The window of one minute includes the full triangle of 60 seconds wide
+        let area = peptide
+            .precursor_area(&ms1(mz, rt, 1000.0), &settings)
+            .unwrap();
+        assert!((area - 30_000.0).abs() < 1e-6, "{area}");
+        let narrow = peptide
+            .precursor_area(
+                &ms1(mz, rt, 1000.0),
+                &settings.rt_window(Time::new::<s>(3.0)),
+            )
+            .unwrap();
+        assert!((narrow - 5_700.0).abs() < 1e-6, "{narrow}");
+        assert!(peptide
+            .precursor_area(
+                &ms1(
+                    mz + MassOverCharge::new::<crate::system::mz>(0.1),
+                    rt,
+                    1000.0
+                ),
+                &settings
+            )
+            .is_none());
+
+        let raw_file = match peptide.scans() {
+            SpectrumIds::FileKnown(files) => files[0].0.with_extension("mzML"),
+            _ => panic!("Sage should have a known raw file"),
+        };
+        let mut other_charge = peptide.clone();
+        if let crate::identification::MetaData::Sage(data) = &mut other_charge.metadata {
+            data.z = Charge::new::<e>(data.z.value + 1);
+        }
+        let mut run = ms1(mz, rt, 1000.0);
+        for (spectrum, other) in
+            run.iter_mut()
+                .zip(ms1(other_charge.experimental_mz().unwrap(), rt, 1000.0))
+        {
+            spectrum.extend(other);
+        }
+        let runs = vec![
+            (raw_file.clone(), run),
+            (PathBuf::from("other.mzML"), ms1(mz, rt, 1000.0)),
+        ];
+        let matrix = QuantificationMatrix::new(
+            [&peptide, &peptide, &other_charge],
+            &runs,
+            &QuantificationSettings::default(),
+        );
+        assert_eq!(matrix.peptides.len(), 1);
+        let sequence = peptide.peptide().unwrap().to_string();
+        // The duplicate is counted once, the other charge is added
+        assert_eq!(matrix.get(&sequence, &raw_file), Some(60_000.0));
+        assert_eq!(matrix.get(&sequence, Path::new("other.mzML")), None);
+
+        let mut csv = Vec::new();
+        matrix.write_csv(&mut csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines = csv.lines().collect::<Vec<_>>();
+        assert_eq!(
+            lines[0],
+            format!("peptide,{},other.mzML", raw_file.to_string_lossy())
+        );
+        assert_eq!(lines[1], format!("{sequence},60000,"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Recompute the scores of identified peptides from annotated spectra
+
+use crate::{
+    fragment::Fragment,
+    identification::IdentifiedPeptide,
+    spectrum::{AnnotatableSpectrum, AnnotatedSpectrum},
+    MassMode, Model,
+};
+
+/// A scoring function to calculate a score for an identified peptide from an annotated spectrum,
+/// see [`IdentifiedPeptide::rescore`]. All scores are normalised to the range -1.0..=1.0 so they
+/// can be used as [`IdentifiedPeptide::score`].
+#[derive(Clone, Copy, Debug)]
+pub enum AnnotationScore {
+    /// The fraction of the total intensity that is annotated, see [`AnnotatedSpectrum::intensity_explained`]
+    IntensityExplained,
+    /// The X!Tandem hyperscore (see [`AnnotatedSpectrum::hyperscore`]), normalised as
+    /// `hyperscore / (hyperscore + 20)`
+    Hyperscore,
+    /// The binomial score (see [`AnnotatedSpectrum::binomial_score`]), normalised as
+    /// `1 - 10^(-score / 10)`, which is one minus the probability of a random match
+    Binomial,
+    /// A custom scoring function, given the annotated spectrum and all theoretical fragments, the
+    /// resulting score is clamped to -1.0..=1.0
+    Custom(fn(&AnnotatedSpectrum, &[Fragment]) -> f64),
+}
+
+impl AnnotationScore {
+    /// Calculate the normalised score for the given annotated spectrum
+    pub fn score(self, annotated: &AnnotatedSpectrum, fragments: &[Fragment]) -> f64 {
+        match self {
+            Self::IntensityExplained => annotated.intensity_explained(),
+            Self::Hyperscore => {
+                let score = annotated.hyperscore().max(0.0);
+                score / (score + 20.0)
+            }
+            Self::Binomial => 1.0 - 10.0_f64.powf(-annotated.binomial_score(fragments) / 10.0),
+            Self::Custom(function) => function(annotated, fragments),
+        }
+        .clamp(-1.0, 1.0)
+    }
+}
+
+impl IdentifiedPeptide {
+    /// Replace the score of this peptide with a score calculated from the given annotated
+    /// spectrum. The original score from the format is stored in [`Self::original_score`], if
+    /// this peptide was rescored before the original score is kept. This allows scores from
+    /// different programs to be replaced by one comparable metric.
+    pub fn rescore(
+        &mut self,
+        annotated: &AnnotatedSpectrum,
+        fragments: &[Fragment],
+        scoring: AnnotationScore,
+    ) -> f64 {
+        let score = scoring.score(annotated, fragments);
+        if self.original_score.is_none() {
+            self.original_score = Some(self.score);
+        }
+        self.score = Some(score);
+        score
+    }
+
+    /// Annotate the given spectrum with this peptide and replace the score with a score
+    /// calculated from this annotation, see [`Self::rescore`]. The theoretical fragments are
+    /// generated up to the charge of this peptide and limited to the m/z range of the model.
+    /// Returns the annotated spectrum, or `None` if this peptide has no peptide sequence or no
+    /// charge, in which case the score is left untouched.
+    pub fn annotate_and_rescore<S: AnnotatableSpectrum>(
+        &mut self,
+        spectrum: &S,
+        model: &Model,
+        mode: MassMode,
+        scoring: AnnotationScore,
+    ) -> Option<AnnotatedSpectrum> {
+        let peptide = self.peptide()?.compound_peptidoform().into_owned();
+        let charge = self.charge()?;
+        let fragments: Vec<Fragment> = peptide
+            .generate_theoretical_fragments(charge, model)
+            .into_iter()
+            .filter(|f| f.mz(mode).is_some_and(|mz| model.mz_range.contains(&mz)))
+            .collect();
+        let annotated = spectrum.annotate(peptide, &fragments, model, mode);
+        self.rescore(&annotated, &fragments, scoring);
+        Some(annotated)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        identification::{open_identified_peptides_file, MetaData},
+        system::{e, usize::Charge},
+        CompoundPeptidoformIon,
+    };
+
+    #[test]
+    fn rescore() {
+        let model = Model::all();
+        let spectrum = crate::rawfile::mgf::open("data/annotated_example.mgf")
+            .unwrap()
+            .remove(0);
+        let mut peptide: IdentifiedPeptide =
+            open_identified_peptides_file("src/identification/test_files/sage_v0_14.tsv", None)
+                .unwrap()
+                .next()
+                .unwrap()
+                .unwrap();
+        let original = peptide.score;
+        let annotated = peptide
+            .annotate_and_rescore(
+                &spectrum,
+                &model,
+                MassMode::Monoisotopic,
+                AnnotationScore::IntensityExplained,
+            )
+            .unwrap();
+        assert_eq!(peptide.original_score, Some(original));
+        assert_eq!(peptide.score, Some(annotated.intensity_explained()));
+        assert!(matches!(peptide.metadata, MetaData::Sage(_)));
+
+        // Rescoring again keeps the original score from the format
+        let correct = CompoundPeptidoformIon::pro_forma("VAEINPSNGGTTFNEKFKGGKATJ", None).unwrap();
+        let fragments = correct.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+        let annotated = spectrum.annotate(correct, &fragments, &model, MassMode::Monoisotopic);
+        for scoring in [
+            AnnotationScore::IntensityExplained,
+            AnnotationScore::Hyperscore,
+            AnnotationScore::Binomial,
+            AnnotationScore::Custom(|_, _| 2.0),
+        ] {
+            let score = peptide.rescore(&annotated, &fragments, scoring);
+            assert!((0.0..=1.0).contains(&score), "{scoring:?}: {score}");
+            assert!(score > 0.0, "{scoring:?}: {score}");
+            assert_eq!(peptide.original_score, Some(original));
+        }
+        assert_eq!(peptide.score, Some(1.0));
+
+        // A peptide without a score from the format keeps that missing score after rescoring twice
+        peptide.score = None;
+        peptide.original_score = None;
+        peptide.rescore(&annotated, &fragments, AnnotationScore::Hyperscore);
+        peptide.rescore(&annotated, &fragments, AnnotationScore::Binomial);
+        assert_eq!(peptide.original_score, Some(None));
+    }
+}
@@ -13,7 +13,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter, IdentifiedPeptide, IdentifiedPeptideSource, MetaData,
 };
 
@@ -75,6 +75,7 @@ impl From<SageData> for IdentifiedPeptide {
     fn from(value: SageData) -> Self {
         Self {
             score: Some(value.sage_discriminant_score.clamp(-1.0, 1.0)),
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::Sage(value),
         }
@@ -0,0 +1,319 @@
+use std::sync::Arc;
+
+use crate::{
+    error::{Context, CustomError},
+    identification::{
+        IdentifiedPeptide, MSFraggerData, MaxQuantData, MetaData, PeaksData, ReturnedPeptide,
+    },
+    modification::{Ontology, SimpleModification, SimpleModificationInner},
+    peptidoform::{SemiAmbiguous, SimpleLinear},
+    system::Mass,
+    Chemical, Modification, MultiChemical, Peptidoform,
+};
+
+/// Parse a site probability string as used by MaxQuant (the `<modification> Probabilities`
+/// columns) and FragPipe (the PTMProphet localisation columns), for example
+/// `AAM(0.75)M(0.25)K`. Surrounding underscores (as used by MaxQuant for modified sequences)
+/// are ignored. This returns the bare sequence and the 0 based sequence index and probability
+/// for every site.
+/// ```rust
+/// # use rustyms::identification::parse_site_probabilities;
+/// let (sequence, sites) = parse_site_probabilities("AAM(0.75)M(0.25)K").unwrap();
+/// assert_eq!(sequence, "AAMMK");
+/// assert_eq!(sites, vec![(2, 0.75), (3, 0.25)]);
+/// ```
+/// # Errors
+/// If a probability is not a valid number, is not closed, or is not preceded by a residue.
+pub fn parse_site_probabilities(value: &str) -> Result<(String, Vec<(usize, f64)>), CustomError> {
+    let trimmed = value.trim().trim_matches('_');
+    let offset = value.find(trimmed).unwrap_or_default();
+    let mut sequence = String::with_capacity(trimmed.len());
+    let mut sites = Vec::new();
+    let mut index = 0;
+    while let Some(c) = trimmed[index..].chars().next() {
+        if c == '(' {
+            let Some(end) = trimmed[index..].find(')').map(|end| index + end) else {
+                return Err(CustomError::error(
+                    "Invalid site probability",
+                    "The site probability is not closed",
+                    Context::line(None, value, offset + index, 1),
+                ));
+            };
+            let Some(position) = sequence.len().checked_sub(1) else {
+                return Err(CustomError::error(
+                    "Invalid site probability",
+                    "A site probability has to follow a residue",
+                    Context::line(None, value, offset + index, end - index + 1),
+                ));
+            };
+            let probability = trimmed[index + 1..end]
+                .trim()
+                .parse::<f64>()
+                .map_err(|err| {
+                    CustomError::error(
+                        "Invalid site probability",
+                        format!("The site probability is not a valid number: {err}"),
+                        Context::line(None, value, offset + index + 1, end - index - 1),
+                    )
+                })?;
+            sites.push((position, probability));
+            index = end + 1;
+        } else {
+            sequence.push(c);
+            index += c.len_utf8();
+        }
+    }
+    Ok((sequence, sites))
+}
+
+/// Parse a PEAKS AScore column, for example
+/// `S5:Phosphorylation (STY):1000.00;T7:Phosphorylation (STY):0.00`. This returns for every site
+/// the 0 based sequence index, the modification name, and the localisation probability. The
+/// AScore is a -10log10 transformed probability of a false localisation, so the localisation
+/// probability is calculated as `1 - 10^(-AScore/10)`.
+/// ```rust
+/// # use rustyms::identification::parse_peaks_ascore;
+/// let sites = parse_peaks_ascore("S5:Phosphorylation (STY):20.00").unwrap();
+/// assert_eq!(sites[0].0, 4);
+/// assert_eq!(sites[0].1, "Phosphorylation (STY)");
+/// assert!((sites[0].2 - 0.99).abs() < 1e-9);
+/// ```
+/// # Errors
+/// If any of the sites is not formatted correctly.
+pub fn parse_peaks_ascore(value: &str) -> Result<Vec<(usize, String, f64)>, CustomError> {
+    let error = |site: &str, description: &str| {
+        let offset = site.as_ptr() as usize - value.as_ptr() as usize;
+        CustomError::error(
+            "Invalid PEAKS AScore",
+            description,
+            Context::line(None, value, offset, site.len()),
+        )
+    };
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|site| !site.is_empty())
+        .map(|site| {
+            let (location, rest) = site
+                .split_once(':')
+                .ok_or_else(|| error(site, "A site should be formatted as 'S5:name:AScore'"))?;
+            let (name, score) = rest
+                .rsplit_once(':')
+                .ok_or_else(|| error(site, "A site should be formatted as 'S5:name:AScore'"))?;
+            let position = location
+                .get(1..)
+                .and_then(|p| p.parse::<usize>().ok())
+                .and_then(|p| p.checked_sub(1))
+                .ok_or_else(|| error(location, "The site location is not valid"))?;
+            let score = score
+                .trim()
+                .parse::<f64>()
+                .map_err(|_| error(score, "The AScore is not a valid number"))?;
+            Ok((
+                position,
+                name.trim().to_string(),
+                1.0 - 10.0_f64.powf(-score / 10.0),
+            ))
+        })
+        .collect()
+}
+
+/// Find the modification placed on any of the sites that matches the given mass shift. MSFragger
+/// reports modified residues with the mass of the residue and modification combined, so both the
+/// mass shift and the combined mass are accepted. If none is found a mass modification is returned.
+fn placed_mass_modification(
+    peptide: Option<&Peptidoform<SemiAmbiguous>>,
+    mass: Mass,
+    sites: &[(usize, f64)],
+) -> SimpleModification {
+    let matches = |a: Mass, b: Mass| (a - b).value.abs() < 0.01;
+    peptide
+        .and_then(|peptide| {
+            sites
+                .iter()
+                .filter_map(|(index, _)| peptide.sequence().get(*index))
+                .find_map(|element| {
+                    let residue = element.aminoacid.aminoacid().formulas()[0].monoisotopic_mass();
+                    element.modifications.iter().find_map(|m| match m {
+                        Modification::Simple(simple) => {
+                            let shift = simple.formula().monoisotopic_mass();
+                            (matches(shift, mass) || matches(shift, mass + residue))
+                                .then(|| simple.clone())
+                        }
+                        _ => None,
+                    })
+                })
+        })
+        .unwrap_or_else(|| Arc::new(SimpleModificationInner::Mass(mass.into())))
+}
+
+impl IdentifiedPeptide {
+    /// Get the site probabilities for all modifications for which the search engine reported
+    /// these. This is supported for MaxQuant (oxidation, carbamidomethyl, and NEM probability
+    /// columns), FragPipe (PTMProphet localisation columns), and PEAKS (AScore column). The sites
+    /// are given as 0 based sequence index and probability.
+    /// # Errors
+    /// If the site probabilities are not formatted correctly, or if a PEAKS modification name
+    /// could not be recognised.
+    /// # Panics
+    /// If the MaxQuant modifications are missing from the Unimod database.
+    #[allow(clippy::type_complexity)]
+    pub fn site_probabilities(
+        &self,
+    ) -> Result<Vec<(SimpleModification, Vec<(usize, f64)>)>, CustomError> {
+        match &self.metadata {
+            MetaData::MaxQuant(MaxQuantData {
+                oxidation_m_probabilities,
+                carbamidomethyl_c_probabilities,
+                nem_probabilities,
+                ..
+            }) => [
+                (35, oxidation_m_probabilities),
+                (4, carbamidomethyl_c_probabilities),
+                (108, nem_probabilities),
+            ]
+            .into_iter()
+            .filter_map(|(id, probabilities)| {
+                probabilities
+                    .as_ref()
+                    .filter(|p| !p.trim().is_empty())
+                    .map(|p| (id, p))
+            })
+            .map(|(id, probabilities)| {
+                Ok((
+                    Ontology::Unimod
+                        .find_id(id, None)
+                        .expect("Unimod modification missing"),
+                    parse_site_probabilities(probabilities)?.1,
+                ))
+            })
+            .collect(),
+            MetaData::Peaks(PeaksData {
+                ascore: Some(ascore),
+                ..
+            }) => {
+                let mut result: Vec<(SimpleModification, Vec<(usize, f64)>)> = Vec::new();
+                for (position, name, probability) in parse_peaks_ascore(ascore)? {
+                    let modification =
+                        Modification::sloppy_modification(&name, 0..name.len(), None, None)?;
+                    if let Some(entry) = result.iter_mut().find(|(m, _)| *m == modification) {
+                        entry.1.push((position, probability));
+                    } else {
+                        result.push((modification, vec![(position, probability)]));
+                    }
+                }
+                Ok(result)
+            }
+            MetaData::MSFragger(MSFraggerData {
+                localisations: Some(localisations),
+                peptide,
+                ..
+            }) => localisations
+                .iter()
+                .map(|(_, mass, probabilities)| {
+                    let sites = parse_site_probabilities(probabilities)?.1;
+                    Ok((
+                        placed_mass_modification(peptide.as_ref(), *mass, &sites),
+                        sites,
+                    ))
+                })
+                .collect(),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    /// Get the peptide with the site probabilities reported by the search engine applied, so
+    /// all modifications with site probabilities are ambiguous modifications with the
+    /// probabilities as localisation scores, see [`Self::site_probabilities`] and
+    /// [`Peptidoform::apply_site_probabilities`]. Returns `None` if the peptide is not a single
+    /// linear peptide.
+    /// # Errors
+    /// If the site probabilities could not be parsed.
+    pub fn localised_peptide(&self) -> Result<Option<Peptidoform<SimpleLinear>>, CustomError> {
+        let mut peptide = match self.peptide() {
+            Some(ReturnedPeptide::LinearSemiAmbiguous(peptide)) => {
+                Peptidoform::<SimpleLinear>::from(peptide.clone())
+            }
+            Some(ReturnedPeptide::LinearSimpleLinear(peptide)) => peptide.clone(),
+            _ => return Ok(None),
+        };
+        for (modification, sites) in self.site_probabilities()? {
+            let _ = peptide.apply_site_probabilities(&modification, &sites);
+        }
+        Ok(Some(peptide))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{modification::SimpleModificationInner, SequencePosition};
+
+    #[test]
+    fn site_probabilities() {
+        assert_eq!(
+            parse_site_probabilities("_AAM(0.5)M(0.5)K_"),
+            Ok(("AAMMK".to_string(), vec![(2, 0.5), (3, 0.5)]))
+        );
+        assert_eq!(
+            parse_site_probabilities("PEPTIDE"),
+            Ok(("PEPTIDE".to_string(), Vec::new()))
+        );
+        assert!(parse_site_probabilities("(0.5)AM").is_err());
+        assert!(parse_site_probabilities("AM(0.5").is_err());
+        assert!(parse_site_probabilities("AM(x)").is_err());
+    }
+
+    #[test]
+    fn ascore() {
+        let sites =
+            parse_peaks_ascore("S5:Phosphorylation (STY):1000.00;T7:Phosphorylation (STY):0.00")
+                .unwrap();
+        assert_eq!(sites.len(), 2);
+        assert_eq!(sites[0].0, 4);
+        assert!((sites[0].2 - 1.0).abs() < 1e-9);
+        assert_eq!(sites[1].0, 6);
+        assert!(sites[1].2.abs() < 1e-9);
+        assert!(parse_peaks_ascore("S:Phosphorylation (STY):1.0").is_err());
+        assert!(parse_peaks_ascore("S5:Phosphorylation (STY)").is_err());
+        assert_eq!(parse_peaks_ascore(""), Ok(Vec::new()));
+    }
+
+    #[test]
+    fn apply() {
+        let mut peptide = Peptidoform::pro_forma("AAM[Oxidation]MK", None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap();
+        let oxidation = Ontology::Unimod.find_id(35, None).unwrap();
+        let phospho = Ontology::Unimod.find_id(21, None).unwrap();
+        assert!(!peptide.apply_site_probabilities(&phospho, &[(2, 1.0)]));
+        assert!(peptide.apply_site_probabilities(&oxidation, &[(2, 0.75), (3, 0.25)]));
+        assert_eq!(
+            peptide.get_ambiguous_modifications(),
+            vec![vec![SequencePosition::Index(2), SequencePosition::Index(3)]]
+        );
+        let scores = peptide
+            .sequence()
+            .iter()
+            .flat_map(|s| &s.modifications)
+            .filter_map(|m| match m {
+                Modification::Ambiguous {
+                    localisation_score,
+                    preferred,
+                    modification,
+                    ..
+                } => {
+                    assert!(matches!(
+                        **modification,
+                        SimpleModificationInner::Database { .. }
+                    ));
+                    Some((localisation_score.map(|s| s.0), *preferred))
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(scores, vec![(Some(0.75), true), (Some(0.25), false)]);
+    }
+}
@@ -15,7 +15,7 @@ use serde::{Deserialize, Serialize};
 
 use super::{
     common_parser::Location,
-    csv::{parse_csv, CsvLine},
+    csv::CsvLine,
     BoxedIdentifiedPeptideIter,
 };
 
@@ -60,6 +60,7 @@ impl From<SpectrumSequenceListData> for IdentifiedPeptide {
     fn from(value: SpectrumSequenceListData) -> Self {
         Self {
             score: value.score,
+            original_score: None,
             local_confidence: None,
             metadata: MetaData::SpectrumSequenceList(value),
         }
@@ -0,0 +1,265 @@
+//! Search sequence tags against a protein database
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    identification::{FastaData, ProteinIndex},
+    spectrum::SequenceTag,
+    system::{da, Mass},
+    MolecularFormula, MultiChemical, Peptidoform, SemiAmbiguous, SequenceElement, Tolerance,
+};
+
+/// The settings for a sequence tag search, see [`ProteinIndex::tag_search`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TagSearchSettings {
+    /// The tolerance for matching the flanking masses of a tag to the residues in the protein.
+    ///
+    /// Default: 0.05 Da.
+    pub tolerance: Tolerance<Mass>,
+    /// The maximal number of residues in a candidate peptide (inclusive).
+    ///
+    /// Default: 50.
+    pub max_length: usize,
+}
+
+impl Default for TagSearchSettings {
+    fn default() -> Self {
+        Self {
+            tolerance: Tolerance::new_absolute(da(0.05)),
+            max_length: 50,
+        }
+    }
+}
+
+impl TagSearchSettings {
+    /// Set the tolerance
+    #[must_use]
+    pub fn tolerance(self, tolerance: Tolerance<Mass>) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Set the maximal number of residues in a candidate peptide
+    #[must_use]
+    pub const fn max_length(self, max_length: usize) -> Self {
+        Self { max_length, ..self }
+    }
+}
+
+/// A candidate peptide found in a tag search, see [`ProteinIndex::tag_search`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TagCandidate {
+    /// The candidate peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The index of the protein in the database
+    pub protein: usize,
+    /// The index of the first residue of the peptide in the protein sequence
+    pub start: usize,
+    /// The index of the residue after the last residue of the peptide in the protein sequence
+    pub end: usize,
+    /// The indices of all tags that support this candidate
+    pub tags: Vec<usize>,
+}
+
+impl ProteinIndex {
+    /// Search the given sequence tags against the database, this index has to be built from the
+    /// same database. Every protein that contains the sequence of a tag and has residues N and C
+    /// terminal of the tag that match the flanking masses of the tag results in a candidate
+    /// peptide. The candidates can then be scored against the spectrum with a full fragment
+    /// search. If a flanking mass is not known all possible termini on that side are returned,
+    /// as long as the candidate is not longer than the maximal length.
+    ///
+    /// The flanking masses are compared to the unmodified residue masses, so tags that are
+    /// generated with modified building blocks only match when the modifications are within the
+    /// tag itself. Candidates supported by multiple tags are only returned once, the candidates
+    /// are sorted on protein and location in the protein.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, identification::*, spectrum::*};
+    /// # let spectrum = RawSpectrum::default();
+    /// # let database = FastaData::parse_reader(std::io::BufReader::new(">sp|P1|A A\nMPEPTIDEK\n".as_bytes()), None).unwrap();
+    /// let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+    /// let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default());
+    /// let index = ProteinIndex::new(&database, true);
+    /// for candidate in index.tag_search(&database, &tags, &TagSearchSettings::default()) {
+    ///     println!("{} supported by {} tags", candidate.peptide, candidate.tags.len());
+    /// }
+    /// ```
+    pub fn tag_search(
+        &self,
+        database: &[FastaData],
+        tags: &[SequenceTag],
+        settings: &TagSearchSettings,
+    ) -> Vec<TagCandidate> {
+        let mut candidates: BTreeMap<(usize, usize, usize), Vec<usize>> = BTreeMap::new();
+        for (tag_index, tag) in tags.iter().enumerate() {
+            let sequence: String = tag
+                .sequence
+                .sequence()
+                .iter()
+                .map(|s| s.aminoacid.char())
+                .collect();
+            if sequence.is_empty() || sequence.len() > settings.max_length {
+                continue;
+            }
+            for location in self.find(&sequence) {
+                let Some(protein) = database.get(location.protein) else {
+                    continue;
+                };
+                let residues = protein.peptide().sequence();
+                let slack = settings.max_length - sequence.len();
+                let starts = flank_boundaries(
+                    location.start,
+                    (0..location.start).rev().take(slack).map(|i| (i, i)),
+                    residues,
+                    tag.n_flank,
+                    &settings.tolerance,
+                );
+                let ends = flank_boundaries(
+                    location.end,
+                    (location.end..residues.len())
+                        .take(slack)
+                        .map(|i| (i, i + 1)),
+                    residues,
+                    tag.c_flank,
+                    &settings.tolerance,
+                );
+                for start in &starts {
+                    for end in &ends {
+                        if end - start <= settings.max_length {
+                            let supporting = candidates
+                                .entry((location.protein, *start, *end))
+                                .or_default();
+                            if !supporting.contains(&tag_index) {
+                                supporting.push(tag_index);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        candidates
+            .into_iter()
+            .map(|((protein, start, end), tags)| TagCandidate {
+                peptide: database[protein].peptide().sub_peptide(start..end),
+                protein,
+                start,
+                end,
+                tags,
+            })
+            .collect()
+    }
+}
+
+/// The monoisotopic mass of the unmodified residue
+fn residue_mass(residue: &SequenceElement<SemiAmbiguous>) -> Mass {
+    residue
+        .aminoacid
+        .aminoacid()
+        .formulas()
+        .iter()
+        .next()
+        .map_or_else(|| da(0.0), MolecularFormula::monoisotopic_mass)
+}
+
+/// Check if the given flanking mass matches the given mass, an unknown flank matches any mass
+fn flank_matches(mass: Mass, flank: Option<Mass>, tolerance: &Tolerance<Mass>) -> bool {
+    flank.map_or(true, |flank| {
+        let (low, high) = tolerance.bounds(flank);
+        low <= mass && mass <= high
+    })
+}
+
+/// Walk away from the tag over the given residues, accumulating the residue masses, and return
+/// all peptide boundaries (the tag boundary itself and the boundary after every residue) where
+/// the accumulated mass matches the flanking mass. If the flank is unknown all boundaries are
+/// returned. The walk stops as soon as the accumulated mass is above the flanking mass.
+fn flank_boundaries(
+    tag_boundary: usize,
+    residues: impl Iterator<Item = (usize, usize)>,
+    sequence: &[SequenceElement<SemiAmbiguous>],
+    flank: Option<Mass>,
+    tolerance: &Tolerance<Mass>,
+) -> Vec<usize> {
+    let upper = flank.map(|flank| tolerance.bounds(flank).1);
+    let mut mass = da(0.0);
+    let mut result = Vec::new();
+    if flank_matches(mass, flank, tolerance) {
+        result.push(tag_boundary);
+    }
+    for (residue, boundary) in residues {
+        mass += residue_mass(&sequence[residue]);
+        if upper.is_some_and(|upper| mass > upper) {
+            break;
+        }
+        if flank_matches(mass, flank, tolerance) {
+            result.push(boundary);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        building_blocks,
+        spectrum::{RawPeak, RawSpectrum, SequenceTagSettings},
+        system::{mz, MassOverCharge},
+        AminoAcid, Chemical, MolecularCharge,
+    };
+
+    #[test]
+    fn search() {
+        let database = FastaData::parse_reader(
+            std::io::BufReader::new(">sp|P1|A A\nMKAGWEDKLLR\n>sp|P2|B B\nMKGGGWEDRR\n".as_bytes()),
+            None,
+        )
+        .unwrap();
+        let peptide = Peptidoform::pro_forma("AGWEDK", None)
+            .unwrap()
+            .into_unambiguous()
+            .unwrap();
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        // b2 to b5: AG|WED|K
+        let mut b = proton;
+        let mut spectrum = RawSpectrum::default();
+        spectrum.mass = Some(peptide.formula().monoisotopic_mass());
+        for (index, aa) in peptide.sequence().iter().enumerate() {
+            b += aa.aminoacid.aminoacid().formulas()[0]
+                .monoisotopic_mass()
+                .value;
+            if (1..5).contains(&index) {
+                spectrum.extend([RawPeak::new(MassOverCharge::new::<mz>(b), 1.0)]);
+            }
+        }
+        let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+        let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default());
+        let index = ProteinIndex::new(&database, false);
+        let candidates = index.tag_search(&database, &tags, &TagSearchSettings::default());
+        assert_eq!(candidates.len(), 1);
+        assert_eq!(candidates[0].peptide.to_string(), "AGWEDK");
+        assert_eq!((candidates[0].protein, candidates[0].start), (0, 2));
+        assert!(candidates[0].tags.len() > 1);
+
+        // Without precursor mass the C terminal flank of the b tags is not known
+        spectrum.mass = None;
+        let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default());
+        let candidates = index.tag_search(
+            &database,
+            &tags,
+            &TagSearchSettings::default().max_length(7),
+        );
+        assert!(candidates
+            .iter()
+            .all(|c| c.protein == 0 && c.start == 2 && c.end - c.start <= 7));
+        assert!(candidates
+            .iter()
+            .any(|c| c.peptide.to_string() == "AGWEDKL"));
+    }
+}
@@ -0,0 +1,375 @@
+use std::{io::BufRead, ops::Range, sync::Arc};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::open_reader,
+    identification::{FastaIdentifier, IdentifiedPeptide, MetaData, SpectrumId, SpectrumIds},
+    modification::SimpleModificationInner,
+    ontologies::CustomDatabase,
+    system::{usize::Charge, Mass, MassOverCharge, Time},
+    Peptidoform, SemiAmbiguous, SloppyParsingParameters,
+};
+
+/// A single peptide from an X!Tandem BioML result file, peptides that are found in multiple
+/// proteins (or multiple times in one protein) are reported once with all domains listed
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct XTandemData {
+    /// The version of the file format
+    pub version: XTandemVersion,
+    /// The software version as reported in the input parameters (eg `X! Tandem Vengeance (2015.12.15.2)`)
+    pub software_version: Option<String>,
+    /// The id of the group, this is the spectrum id as given by X!Tandem
+    pub id: usize,
+    /// The peptide with all modifications placed as mass shifts
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The experimental M+H
+    pub experimental_mh: Mass,
+    /// The calculated M+H of the peptide
+    pub mh: Mass,
+    /// The precursor charge
+    pub z: Charge,
+    /// The retention time, if reported
+    pub rt: Option<Time>,
+    /// The expectation value of the peptide
+    pub expect: f64,
+    /// The hyperscore
+    pub hyperscore: f64,
+    /// The hyperscore of the next best peptide for this spectrum, if reported
+    pub next_score: Option<f64>,
+    /// The number of missed cleavages, if reported
+    pub missed_cleavages: Option<usize>,
+    /// All locations of this peptide in the searched proteins
+    pub domains: Vec<XTandemDomain>,
+    /// The spectrum title, if reported
+    pub title: Option<String>,
+}
+
+/// The location of a peptide (domain) in a protein
+#[derive(Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+pub struct XTandemDomain {
+    /// The domain id, formatted as `group.protein.domain`
+    pub id: String,
+    /// The protein identifier
+    pub protein: FastaIdentifier<String>,
+    /// The full protein label
+    pub protein_label: String,
+    /// The log10 of the expectation value of the protein, if reported
+    pub protein_expect: Option<f64>,
+    /// The location of the peptide in the protein, zero based and end exclusive
+    pub location: Range<usize>,
+    /// The residues before the peptide in the protein
+    pub preceding: String,
+    /// The residues after the peptide in the protein
+    pub following: String,
+}
+
+impl XTandemData {
+    /// Parse an X!Tandem BioML result file.
+    /// # Errors
+    /// If the file could not be opened or is not a valid X!Tandem result file.
+    pub fn parse_file(
+        path: impl AsRef<std::path::Path>,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Box<dyn Iterator<Item = Result<Self, CustomError>> + '_>, CustomError> {
+        Ok(Box::new(Self::parse_reader(
+            open_reader(path)?,
+            custom_database,
+        )?))
+    }
+
+    /// Parse an X!Tandem BioML result file directly from a buffered reader. As this is an XML
+    /// file the whole file is read and parsed eagerly.
+    /// # Errors
+    /// If the file could not be read or is not valid XML with a `bioml` root.
+    pub fn parse_reader<'a, T: BufRead + 'a>(
+        mut reader: T,
+        custom_database: Option<&'a CustomDatabase>,
+    ) -> Result<impl Iterator<Item = Result<Self, CustomError>> + 'a, CustomError> {
+        let mut text = String::new();
+        reader.read_to_string(&mut text).map_err(|err| {
+            CustomError::error("Could not read X!Tandem file", err, Context::None)
+        })?;
+        let document = roxmltree::Document::parse(&text).map_err(|err| {
+            CustomError::error(
+                "Invalid X!Tandem file",
+                format!("The file is not valid XML: {err}"),
+                Context::None,
+            )
+        })?;
+        let root = document.root_element();
+        if !root.has_tag_name("bioml") {
+            return Err(CustomError::error(
+                "Invalid X!Tandem file",
+                format!(
+                    "The root element should be 'bioml' but is '{}'",
+                    root.tag_name().name()
+                ),
+                Context::None,
+            ));
+        }
+
+        let software_version = root
+            .children()
+            .filter(|group| group.attribute("type") == Some("parameters"))
+            .flat_map(|group| group.children())
+            .find(|note| {
+                note.has_tag_name("note") && note.attribute("label") == Some("process, version")
+            })
+            .and_then(|note| note.text())
+            .map(|version| version.trim().to_string());
+
+        let mut peptides = Vec::new();
+        for group in root
+            .children()
+            .filter(|group| group.has_tag_name("group") && group.attribute("type") == Some("model"))
+        {
+            match Self::from_group(group, software_version.as_ref(), custom_database) {
+                Ok(group) => peptides.extend(group.into_iter().map(Ok)),
+                Err(err) => peptides.push(Err(err)),
+            }
+        }
+        Ok(peptides.into_iter())
+    }
+
+    /// Parse a single model group, this returns one peptide per distinct peptide in this group
+    /// # Errors
+    /// If any required attribute is missing or invalid or any peptide could not be parsed.
+    fn from_group(
+        group: roxmltree::Node,
+        software_version: Option<&String>,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Result<Vec<Self>, CustomError> {
+        let title = group
+            .children()
+            .filter(|support| support.attribute("label") == Some("fragment ion mass spectrum"))
+            .flat_map(|support| support.children())
+            .find(|note| {
+                note.has_tag_name("note")
+                    && note
+                        .attribute("label")
+                        .is_some_and(|label| label.eq_ignore_ascii_case("description"))
+            })
+            .and_then(|note| note.text())
+            .map(|title| title.trim().to_string());
+        let id = attribute::<usize>(group, "id")?;
+        let experimental_mh = Mass::new::<crate::system::dalton>(attribute(group, "mh")?);
+        let z = Charge::new::<crate::system::e>(attribute(group, "z")?);
+        let rt = group
+            .attribute("rt")
+            .map(|rt| parse_time(group, rt))
+            .transpose()?;
+
+        let mut peptides: Vec<Self> = Vec::new();
+        for protein in group.children().filter(|p| p.has_tag_name("protein")) {
+            let protein_label = protein.attribute("label").unwrap_or_default().to_string();
+            let protein_identifier = protein_label
+                .split_ascii_whitespace()
+                .next()
+                .unwrap_or_default();
+            let protein_expect = optional_attribute(protein, "expect")?;
+            for domain in protein
+                .children()
+                .filter(|p| p.has_tag_name("peptide"))
+                .flat_map(|p| p.children())
+                .filter(|d| d.has_tag_name("domain"))
+            {
+                let start = attribute::<usize>(domain, "start")?;
+                let end = attribute::<usize>(domain, "end")?;
+                let peptide = parse_domain_peptide(domain, start, custom_database)?;
+                let location = XTandemDomain {
+                    id: domain.attribute("id").unwrap_or_default().to_string(),
+                    protein: format!(">{protein_identifier}")
+                        .parse()
+                        .unwrap_or_else(|_| {
+                            FastaIdentifier::Undefined(protein_identifier.to_string())
+                        }),
+                    protein_label: protein_label.clone(),
+                    protein_expect,
+                    location: start.saturating_sub(1)..end,
+                    preceding: domain.attribute("pre").unwrap_or_default().to_string(),
+                    following: domain.attribute("post").unwrap_or_default().to_string(),
+                };
+                if let Some(existing) = peptides.iter_mut().find(|p| p.peptide == peptide) {
+                    existing.domains.push(location);
+                } else {
+                    peptides.push(Self {
+                        version: XTandemVersion::BioML,
+                        software_version: software_version.cloned(),
+                        id,
+                        peptide,
+                        experimental_mh,
+                        mh: Mass::new::<crate::system::dalton>(attribute(domain, "mh")?),
+                        z,
+                        rt,
+                        expect: attribute(domain, "expect")?,
+                        hyperscore: attribute(domain, "hyperscore")?,
+                        next_score: optional_attribute(domain, "nextscore")?,
+                        missed_cleavages: optional_attribute(domain, "missed_cleavages")?,
+                        domains: vec![location],
+                        title: title.clone(),
+                    });
+                }
+            }
+        }
+        Ok(peptides)
+    }
+
+    /// The experimental neutral mass, calculated from the reported M+H
+    pub(super) fn experimental_mass(&self) -> Mass {
+        self.experimental_mh - crate::molecular_formula!(H 1 Electron -1).monoisotopic_mass()
+    }
+
+    /// The experimental mz, calculated from the reported M+H and charge
+    pub(super) fn experimental_mz(&self) -> MassOverCharge {
+        let proton = crate::molecular_formula!(H 1 Electron -1).monoisotopic_mass();
+        MassOverCharge::new::<crate::system::mz>(
+            (self.experimental_mh - proton).value / self.z.to_float().value + proton.value,
+        )
+    }
+
+    /// The scans for this peptide, the group id is the one based index of the spectrum
+    pub(super) fn scans(&self) -> SpectrumIds {
+        SpectrumIds::FileNotKnown(vec![self.title.as_ref().map_or_else(
+            || SpectrumId::Index(self.id.saturating_sub(1)),
+            |title| SpectrumId::Native(title.clone()),
+        )])
+    }
+}
+
+/// Parse the peptide of a domain and place all modifications (`aa` elements) as mass shifts
+/// # Errors
+/// If the sequence is invalid or a modification is placed outside of the peptide.
+fn parse_domain_peptide(
+    domain: roxmltree::Node,
+    start: usize,
+    custom_database: Option<&CustomDatabase>,
+) -> Result<Peptidoform<SemiAmbiguous>, CustomError> {
+    let sequence = domain.attribute("seq").unwrap_or_default();
+    let mut peptide = Peptidoform::sloppy_pro_forma(
+        sequence,
+        0..sequence.len(),
+        custom_database,
+        &SloppyParsingParameters::default(),
+    )?;
+    for aa in domain.children().filter(|aa| aa.has_tag_name("aa")) {
+        let at = attribute::<usize>(aa, "at")?;
+        let index = at
+            .checked_sub(start)
+            .filter(|index| *index < peptide.len())
+            .ok_or_else(|| {
+                CustomError::error(
+                    "Invalid X!Tandem modification",
+                    format!(
+                        "The modification at {at} is outside of the peptide starting at {start}"
+                    ),
+                    Context::show(sequence),
+                )
+            })?;
+        let mass = attribute::<f64>(aa, "modified")?;
+        peptide.sequence_mut()[index].add_simple_modification(Arc::new(
+            SimpleModificationInner::Mass(Mass::new::<crate::system::dalton>(mass).into()),
+        ));
+    }
+    Ok(peptide)
+}
+
+/// Parse the retention time, either as ISO 8601 duration in seconds (`PT12.3S`) or as plain seconds
+/// # Errors
+/// If the retention time is not a number.
+fn parse_time(node: roxmltree::Node, value: &str) -> Result<Time, CustomError> {
+    value
+        .trim()
+        .trim_start_matches("PT")
+        .trim_end_matches('S')
+        .parse::<f64>()
+        .map(Time::new::<crate::system::s>)
+        .map_err(|err| {
+            CustomError::error(
+                "Invalid X!Tandem file",
+                format!("The retention time '{value}' is not a number: {err}"),
+                Context::show(node_description(node)),
+            )
+        })
+}
+
+/// Get and parse a required attribute
+/// # Errors
+/// If the attribute is missing or invalid.
+fn attribute<T: std::str::FromStr>(node: roxmltree::Node, name: &str) -> Result<T, CustomError> {
+    optional_attribute(node, name)?.ok_or_else(|| {
+        CustomError::error(
+            "Invalid X!Tandem file",
+            format!(
+                "The attribute '{name}' is required on '{}'",
+                node.tag_name().name()
+            ),
+            Context::show(node_description(node)),
+        )
+    })
+}
+
+/// Get and parse an optional attribute
+/// # Errors
+/// If the attribute is present but invalid.
+fn optional_attribute<T: std::str::FromStr>(
+    node: roxmltree::Node,
+    name: &str,
+) -> Result<Option<T>, CustomError> {
+    node.attribute(name)
+        .map(|value| {
+            value.trim().parse::<T>().map_err(|_| {
+                CustomError::error(
+                    "Invalid X!Tandem file",
+                    format!(
+                        "The attribute '{name}' on '{}' has an invalid value '{value}'",
+                        node.tag_name().name()
+                    ),
+                    Context::show(node_description(node)),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Describe a node by its tag and id, for use in error messages
+fn node_description(node: roxmltree::Node) -> String {
+    node.attribute("id").map_or_else(
+        || format!("<{}>", node.tag_name().name()),
+        |id| format!("<{} id=\"{id}\">", node.tag_name().name()),
+    )
+}
+
+impl From<XTandemData> for IdentifiedPeptide {
+    fn from(value: XTandemData) -> Self {
+        Self {
+            score: Some((value.hyperscore / 100.0).clamp(-1.0, 1.0)),
+            original_score: None,
+            local_confidence: None,
+            metadata: MetaData::XTandem(value),
+        }
+    }
+}
+
+/// All supported X!Tandem versions
+#[derive(
+    Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub enum XTandemVersion {
+    /// The BioML output format
+    #[default]
+    BioML,
+}
+
+impl std::fmt::Display for XTandemVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::result::Result<(), std::fmt::Error> {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::BioML => "BioML",
+            }
+        )
+    }
+}
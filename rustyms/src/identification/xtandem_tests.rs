@@ -0,0 +1,123 @@
+#![allow(clippy::missing_panics_doc)]
+use std::io::BufReader;
+
+use crate::identification::{
+    test_identified_peptide, FastaIdentifier, IdentifiedPeptide, SpectrumId, SpectrumIds,
+    XTandemData,
+};
+
+#[test]
+fn xtandem_bioml() {
+    let mut peptides = 0;
+    for read in XTandemData::parse_reader(BufReader::new(XTANDEM.as_bytes()), None).unwrap() {
+        let read: IdentifiedPeptide = read.unwrap().into();
+        test_identified_peptide(&read, true, false).unwrap();
+        peptides += 1;
+    }
+    assert_eq!(peptides, 3);
+}
+
+#[test]
+fn xtandem_metadata() {
+    let peptides = XTandemData::parse_reader(BufReader::new(XTANDEM.as_bytes()), None)
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    assert_eq!(
+        peptides[0].software_version.as_deref(),
+        Some("X! Tandem Vengeance (2015.12.15.2)")
+    );
+    assert_eq!(peptides[0].id, 1);
+    assert_eq!(peptides[0].peptide.to_string(), "LVNELTEFAK");
+    assert!((peptides[0].hyperscore - 45.3).abs() < f64::EPSILON);
+    assert!((peptides[0].expect - 4.2e-7).abs() < f64::EPSILON);
+    assert_eq!(peptides[0].z.value, 2);
+    assert!(peptides[0]
+        .rt
+        .is_some_and(|rt| (rt.value - 1234.5).abs() < f64::EPSILON));
+    assert_eq!(
+        peptides[0].scans(),
+        SpectrumIds::FileNotKnown(vec![SpectrumId::Native(
+            "sample_01.1021.1021.2".to_string()
+        )])
+    );
+    // Shared peptide, reported once with both domains
+    assert_eq!(peptides[1].peptide.to_string(), "YIC[+57.02146]ENQDSISSK");
+    assert_eq!(peptides[1].domains.len(), 2);
+    assert_eq!(peptides[1].domains[0].location, 138..150);
+    assert_eq!(
+        peptides[1].domains[0].protein,
+        FastaIdentifier::SwissProt("P02768".to_string(), "ALBU_HUMAN".to_string())
+    );
+    assert_eq!(peptides[1].domains[1].protein_label, "ALBU_BOVIN");
+    assert_eq!(peptides[1].domains[1].preceding, "ICRK");
+    // No title, so the group id is used
+    assert_eq!(
+        peptides[2].scans(),
+        SpectrumIds::FileNotKnown(vec![SpectrumId::Index(11)])
+    );
+    assert_eq!(peptides[2].peptide.to_string(), "M[+15.99491]DDREDLVYQAK");
+}
+
+#[test]
+fn xtandem_invalid_modification() {
+    let file = XTANDEM.replace(r#"at="13""#, r#"at="40""#);
+    assert!(
+        XTandemData::parse_reader(BufReader::new(file.as_bytes()), None)
+            .unwrap()
+            .any(|p| p.is_err())
+    );
+}
+
+#[test]
+fn not_xtandem() {
+    assert!(XTandemData::parse_reader(BufReader::new(b"<mzML></mzML>".as_slice()), None).is_err());
+    assert!(XTandemData::parse_reader(BufReader::new(b"PSM\tsequence".as_slice()), None).is_err());
+}
+
+const XTANDEM: &str = r#"<?xml version="1.0"?>
+<?xml-stylesheet type="text/xsl" href="tandem-style.xsl"?>
+<bioml xmlns:GAML="http://www.bioml.com/gaml/" label="models from 'sample_01.mgf'">
+<group id="1" mh="1149.614980" z="2" rt="PT1234.5S" expect="4.2e-07" label="sp|P02768|ALBU_HUMAN Serum albumin OS=Homo sapiens" type="model" sumI="6.12" maxI="125000" fI="1250" act="0" >
+<protein expect="-185.1" id="1.1" uid="1" label="sp|P02768|ALBU_HUMAN Serum albumin OS=Homo sapiens" sumI="8.01" >
+<note label="description">sp|P02768|ALBU_HUMAN Serum albumin OS=Homo sapiens</note>
+<file type="peptide" URL="swissprot.fasta"/>
+<peptide start="1" end="609">
+<domain id="1.1.1" start="66" end="75" expect="4.2e-07" mh="1149.6149" delta="0.0001" hyperscore="45.3" nextscore="20.1" y_score="12.1" y_ions="7" b_score="5.2" b_ions="3" pre="EFAK" post="TCVA" seq="LVNELTEFAK" missed_cleavages="0">
+</domain>
+</peptide>
+</protein>
+<group label="fragment ion mass spectrum" type="support">
+<note label="Description">sample_01.1021.1021.2</note>
+</group>
+</group>
+<group id="8" mh="1502.661000" z="2" rt="1301.2" expect="6.1e-08" label="sp|P02768|ALBU_HUMAN Serum albumin OS=Homo sapiens" type="model" sumI="6.40" maxI="98000" fI="980" act="0" >
+<protein expect="-185.1" id="8.1" uid="1" label="sp|P02768|ALBU_HUMAN Serum albumin OS=Homo sapiens" sumI="8.01" >
+<peptide start="1" end="609">
+<domain id="8.1.1" start="139" end="150" expect="6.1e-08" mh="1502.6605" delta="0.0005" hyperscore="51.0" nextscore="22.4" pre="FYAK" post="LKEC" seq="YICENQDSISSK" missed_cleavages="0">
+<aa type="C" at="141" modified="57.02146" />
+</domain>
+</peptide>
+</protein>
+<protein expect="-12.0" id="8.2" uid="2" label="ALBU_BOVIN" sumI="3.20" >
+<peptide start="1" end="607">
+<domain id="8.2.1" start="139" end="150" expect="6.1e-08" mh="1502.6605" delta="0.0005" hyperscore="51.0" nextscore="22.4" pre="ICRK" post="LKEC" seq="YICENQDSISSK" missed_cleavages="0">
+<aa type="C" at="141" modified="57.02146" />
+</domain>
+</peptide>
+</protein>
+</group>
+<group id="12" mh="1770.788000" z="2" expect="3.3e-04" label="sp|P11137|MTAP2_HUMAN Microtubule-associated protein 2" type="model" sumI="5.10" maxI="45000" fI="450" act="0" >
+<protein expect="-3.5" id="12.1" uid="3" label="sp|P11137|MTAP2_HUMAN Microtubule-associated protein 2" sumI="5.10" >
+<peptide start="1" end="1827">
+<domain id="12.1.1" start="13" end="24" expect="3.3e-04" mh="1770.7884" delta="-0.0004" hyperscore="30.2" pre="MADE" post="LQGK" seq="MDDREDLVYQAK" missed_cleavages="1">
+<aa type="M" at="13" modified="15.99491" />
+</domain>
+</peptide>
+</protein>
+</group>
+<group label="input parameters" type="parameters">
+<note type="input" label="process, version">X! Tandem Vengeance (2015.12.15.2)</note>
+</group>
+</bioml>
+"#;
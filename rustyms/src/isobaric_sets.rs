@@ -1,5 +1,3 @@
-use std::cmp::Ordering;
-
 use itertools::Itertools;
 
 use crate::{
@@ -8,9 +6,84 @@ use crate::{
     peptidoform::SimpleLinear,
     placement_rule::{PlacementRule, Position},
     system::{fraction, Mass, Ratio},
-    AminoAcid, Chemical, Peptidoform, SemiAmbiguous, SequenceElement, SequencePosition, Tolerance,
+    AminoAcid, Chemical, Element, MolecularFormula, Peptidoform, SemiAmbiguous, SequenceElement,
+    SequencePosition, Tolerance,
 };
 
+/// Constraints on the elemental composition of isobaric sets, see [`find_isobaric_sets`]. By
+/// default there are no constraints.
+///
+/// ```rust
+/// # use rustyms::*;
+/// // At most one sulfur and only the elements common in peptides
+/// let constraints = ElementConstraints::default()
+///     .max(Element::S, 1)
+///     .only(&[Element::C, Element::H, Element::N, Element::O, Element::S]);
+/// assert!(constraints.allows(&molecular_formula!(C 5 H 11 N 1 O 2 S 1)));
+/// assert!(!constraints.allows(&molecular_formula!(C 10 H 22 N 2 O 4 S 2)));
+/// assert!(!constraints.allows(&molecular_formula!(C 5 H 11 N 1 O 2 Se 1)));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct ElementConstraints {
+    /// The maximal number of atoms for specific elements
+    maximum: Vec<(Element, i32)>,
+    /// The only elements that are allowed, if set
+    allowed: Option<Vec<Element>>,
+}
+
+impl ElementConstraints {
+    /// Set the maximal number of atoms of the given element, all isotopes of the element are
+    /// counted together
+    #[must_use]
+    pub fn max(mut self, element: Element, count: i32) -> Self {
+        self.maximum.retain(|(e, _)| *e != element);
+        self.maximum.push((element, count));
+        self
+    }
+
+    /// Forbid any atoms of the given element
+    #[must_use]
+    pub fn forbid(self, element: Element) -> Self {
+        self.max(element, 0)
+    }
+
+    /// Only allow the given elements, all other elements are forbidden (electrons are always
+    /// allowed)
+    #[must_use]
+    pub fn only(self, elements: &[Element]) -> Self {
+        Self {
+            allowed: Some(elements.to_vec()),
+            ..self
+        }
+    }
+
+    /// Check if there are no constraints
+    pub fn is_empty(&self) -> bool {
+        self.maximum.is_empty() && self.allowed.is_none()
+    }
+
+    /// Check if the given formula satisfies these constraints
+    pub fn allows(&self, formula: &MolecularFormula) -> bool {
+        let count = |element: Element| {
+            formula
+                .elements()
+                .iter()
+                .filter(|(e, _, _)| *e == element)
+                .map(|(_, _, n)| *n)
+                .sum::<i32>()
+        };
+        self.allowed.as_ref().map_or(true, |allowed| {
+            formula
+                .elements()
+                .iter()
+                .all(|(e, _, n)| *n <= 0 || *e == Element::Electron || allowed.contains(e))
+        }) && self
+            .maximum
+            .iter()
+            .all(|(element, max)| count(*element) <= *max)
+    }
+}
+
 /// A list of building blocks for a sequence defined by its sequence elements and its mass.
 pub type BuildingBlocks = Vec<(SequenceElement<SemiAmbiguous>, Mass)>;
 /// A list of all combinations of terminal modifications and their accompanying amino acid
@@ -136,7 +209,7 @@ pub fn building_blocks(
                 .collect_vec()
             })
             .collect_vec();
-        options.sort_unstable_by(|a, b| a.2.value.total_cmp(&b.2.value));
+        options.sort_by(|a, b| a.2.value.total_cmp(&b.2.value));
         options
     }
 
@@ -205,7 +278,7 @@ pub fn building_blocks(
                     .collect_vec()
             })
             .collect();
-        options.sort_unstable_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+        options.sort_by(|a, b| a.1.value.total_cmp(&b.1.value));
         options
     };
 
@@ -217,10 +290,77 @@ pub fn building_blocks(
     )
 }
 
-/// Find the isobaric sets for the given mass with the given modifications and ppm error.
-/// The modifications are placed on any location they are allowed based on the given placement
-/// rules, so using any modifications which provide those is advised. If the provided [`LinearPeptide`]
-/// has multiple formulas, it uses the formula with the lowest monoisotopic mass.
+/// The settings for [`find_isobaric_sets`]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct IsobaricSetSettings {
+    /// The minimal length of a set (inclusive), including the base selection and any amino acids
+    /// with terminal modifications.
+    ///
+    /// Default: 1.
+    pub min_length: usize,
+    /// The maximal length of a set (inclusive), including the base selection and any amino acids
+    /// with terminal modifications.
+    ///
+    /// Default: [`usize::MAX`].
+    pub max_length: usize,
+    /// The constraints on the elemental composition of the sets.
+    ///
+    /// Default: no constraints.
+    pub constraints: ElementConstraints,
+}
+
+impl Default for IsobaricSetSettings {
+    fn default() -> Self {
+        Self {
+            min_length: 1,
+            max_length: usize::MAX,
+            constraints: ElementConstraints::default(),
+        }
+    }
+}
+
+impl IsobaricSetSettings {
+    /// Set the minimal and maximal length of a set (both inclusive)
+    #[must_use]
+    pub fn length(self, min_length: usize, max_length: usize) -> Self {
+        Self {
+            min_length,
+            max_length,
+            ..self
+        }
+    }
+
+    /// Set the constraints on the elemental composition
+    #[must_use]
+    pub fn constraints(self, constraints: ElementConstraints) -> Self {
+        Self {
+            constraints,
+            ..self
+        }
+    }
+}
+
+/// Find the isobaric sets for the given mass with the given modifications and ppm error. The
+/// mass is the mass of all amino acids and modifications, without the water of the peptide
+/// termini. The modifications are placed on any location they are allowed based on the given
+/// placement rules, so using any modifications which provide those is advised. Terminal
+/// modifications are placed on the first or last amino acid of the set. A variable terminal
+/// modification is optional, while a fixed terminal modification is always placed (if any
+/// fixed modification can be placed on that terminus). If the provided base selection has
+/// multiple formulas, it uses the formula with the lowest monoisotopic mass. The base selection
+/// is placed at the start of all sets (after any generated N terminal amino acid), and if it has
+/// terminal modifications itself no terminal modifications are generated for that terminus.
+///
+/// The length of the sets (including the base selection) and the elemental composition of the
+/// full sets (including the base selection, but without the water of the peptide termini, the
+/// same as the mass) can be restricted with the settings. Any building block that on its own
+/// does not satisfy the element constraints is not used, which can prune the search space
+/// considerably, and any set that does not satisfy the constraints is skipped.
+///
+/// The sets are generated in a deterministic order. For every combination of terminal
+/// modifications (first without, then in order of mass) all combinations of the remaining
+/// building blocks are generated in order of mass, as such every composition is only generated
+/// once and is not repeated in any other order.
 /// # Panics
 /// Panics if any of the modifications does not have a defined mass. Or if the weight of the
 /// base selection is already in the tolerance of the given mass.
@@ -231,136 +371,338 @@ pub fn find_isobaric_sets(
     fixed: &[(SimpleModification, Option<PlacementRule>)],
     variable: &[(SimpleModification, Option<PlacementRule>)],
     base: Option<&Peptidoform<SimpleLinear>>,
+    settings: &IsobaricSetSettings,
 ) -> IsobaricSetIterator {
     let bounds = tolerance.bounds(mass);
+    let water = molecular_formula!(H 2 O 1);
     let base_mass = base
         .and_then(|b| {
             b.formulas()
                 .mass_bounds()
                 .into_option()
-                .map(|(f, _)| f.monoisotopic_mass())
+                .map(|(f, _)| (f - &water).monoisotopic_mass())
         })
         .unwrap_or_default();
     let bounds = (bounds.0 - base_mass, bounds.1 - base_mass);
     assert!(bounds.0.value > 0.0, "Cannot have a base selection that has a weight within the tolerance of the intended final mass for isobaric search.");
-    let (n_term, center, c_term) = building_blocks(amino_acids, fixed, variable);
+    let (mut n_term, mut center, mut c_term) = building_blocks(amino_acids, fixed, variable);
+    // Blocks without a positive mass could be repeated indefinitely
+    center.retain(|(_, mass)| mass.value > 0.0);
+    let constraints = &settings.constraints;
+    if !constraints.is_empty() {
+        let allowed = |element: &SequenceElement<SemiAmbiguous>, extra: &MolecularFormula| {
+            element
+                .formulas_all(
+                    &[],
+                    &[],
+                    &mut Vec::new(),
+                    false,
+                    SequencePosition::default(),
+                    0,
+                )
+                .0
+                .iter()
+                .any(|f| constraints.allows(&(f + extra)))
+        };
+        n_term.retain(|(element, modification, _)| allowed(element, &modification.formula()));
+        center.retain(|(element, _)| allowed(element, &MolecularFormula::default()));
+        c_term.retain(|(element, modification, _)| allowed(element, &modification.formula()));
+    }
 
-    IsobaricSetIterator::new(n_term, c_term, center, bounds, base)
+    // Determine the options for both termini
+    let (fixed_n, _, fixed_c) = if fixed.is_empty() {
+        (Vec::new(), Vec::new(), Vec::new())
+    } else {
+        building_blocks(amino_acids, fixed, &[])
+    };
+    let terminal_options =
+        |options: &TerminalBuildingBlocks, fixed: &TerminalBuildingBlocks, defined: bool| {
+            if defined {
+                vec![None]
+            } else if fixed.is_empty() {
+                std::iter::once(None)
+                    .chain((0..options.len()).map(Some))
+                    .collect_vec()
+            } else {
+                (0..options.len())
+                    .filter(|i| fixed.iter().any(|f| f.1 == options[*i].1))
+                    .map(Some)
+                    .collect_vec()
+            }
+        };
+    let n_options = terminal_options(
+        &n_term,
+        &fixed_n,
+        base.is_some_and(|b| !b.get_n_term().is_empty()),
+    );
+    let c_options = terminal_options(
+        &c_term,
+        &fixed_c,
+        base.is_some_and(|b| !b.get_c_term().is_empty()),
+    );
+    let terminals = n_options
+        .iter()
+        .flat_map(|n| c_options.iter().map(|c| (*n, *c)))
+        .collect_vec();
+
+    IsobaricSetIterator::new(n_term, c_term, center, bounds, base, settings, terminals)
 }
 
-/// Iteratively generate isobaric sets based on the given settings.
+/// Find the isobaric sets for the given mass in parallel, see [`find_isobaric_sets`] for all
+/// details. The search is split on the terminal options and the first building block, and these
+/// parts are searched in parallel on the rayon thread pool. The sets are sent back over a channel
+/// as soon as they are found, so the sets are returned in a non deterministic order. Dropping
+/// the returned iterator stops the search.
+///
+/// Only available with feature `rayon`.
+/// # Panics
+/// Panics if any of the modifications does not have a defined mass. Or if the weight of the
+/// base selection is already in the tolerance of the given mass.
+#[cfg(feature = "rayon")]
+pub fn par_find_isobaric_sets(
+    mass: Mass,
+    tolerance: Tolerance<Mass>,
+    amino_acids: &[AminoAcid],
+    fixed: &[(SimpleModification, Option<PlacementRule>)],
+    variable: &[(SimpleModification, Option<PlacementRule>)],
+    base: Option<&Peptidoform<SimpleLinear>>,
+    settings: &IsobaricSetSettings,
+) -> ParallelIsobaricSetIterator {
+    use rayon::prelude::*;
+
+    let parts = find_isobaric_sets(
+        mass,
+        tolerance,
+        amino_acids,
+        fixed,
+        variable,
+        base,
+        settings,
+    )
+    .split();
+    let (sender, receiver) = std::sync::mpsc::channel();
+    rayon::spawn(move || {
+        parts.into_par_iter().for_each_with(sender, |sender, part| {
+            for set in part {
+                if sender.send(set).is_err() {
+                    // The receiving iterator is dropped
+                    break;
+                }
+            }
+        });
+    });
+    ParallelIsobaricSetIterator { receiver }
+}
+
+/// Receives the isobaric sets from a parallel search, see [`par_find_isobaric_sets`].
+///
+/// Only available with feature `rayon`.
+#[cfg(feature = "rayon")]
 #[derive(Debug)]
+pub struct ParallelIsobaricSetIterator {
+    receiver: std::sync::mpsc::Receiver<Peptidoform<SimpleLinear>>,
+}
+
+#[cfg(feature = "rayon")]
+impl Iterator for ParallelIsobaricSetIterator {
+    type Item = Peptidoform<SimpleLinear>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.receiver.recv().ok()
+    }
+}
+
+/// Iteratively generate isobaric sets based on the given settings, see [`find_isobaric_sets`].
+#[derive(Clone, Debug)]
 pub struct IsobaricSetIterator {
-    n_term: Vec<(SequenceElement<SemiAmbiguous>, SimpleModification, Mass)>,
-    c_term: Vec<(SequenceElement<SemiAmbiguous>, SimpleModification, Mass)>,
-    center: Vec<(SequenceElement<SemiAmbiguous>, Mass)>,
-    sizes: (Mass, Mass),
+    /// The possible combinations of N terminal modifications and amino acids
+    n_term: TerminalBuildingBlocks,
+    /// The possible combinations of C terminal modifications and amino acids
+    c_term: TerminalBuildingBlocks,
+    /// The possible building blocks for the rest of the set, sorted on mass
+    center: BuildingBlocks,
+    /// The mass bounds for the generated part of the sets (without the base selection)
     bounds: (Mass, Mass),
-    state: (Option<usize>, Option<usize>, Vec<usize>),
+    /// The length bounds for the generated part of the sets (without the base selection)
+    length: (usize, usize),
+    /// The combinations of terminal options (indices into `n_term` and `c_term`) to try
+    terminals: Vec<(Option<usize>, Option<usize>)>,
+    /// The index of the current combination of terminal options
+    terminal: usize,
+    /// The current selection of center building blocks, always sorted to prevent generating
+    /// permutations of the same set
+    selection: Vec<usize>,
+    /// The number of building blocks at the start of the selection that are never changed
+    root: usize,
+    /// If the current combination of terminal options has not been checked without any center
+    /// building blocks yet
+    fresh: bool,
     base: Option<Peptidoform<SimpleLinear>>,
+    constraints: ElementConstraints,
 }
 
 impl IsobaricSetIterator {
-    /// `n_term` & `c_term` are the possible combinations of terminal modifications with their valid placements and the full mass of this combo
-    /// # Panics
-    /// If there is not at least one element in the `center` list.
     fn new(
-        n_term: Vec<(SequenceElement<SemiAmbiguous>, SimpleModification, Mass)>,
-        c_term: Vec<(SequenceElement<SemiAmbiguous>, SimpleModification, Mass)>,
-        center: Vec<(SequenceElement<SemiAmbiguous>, Mass)>,
+        n_term: TerminalBuildingBlocks,
+        c_term: TerminalBuildingBlocks,
+        center: BuildingBlocks,
         bounds: (Mass, Mass),
         base: Option<&Peptidoform<SimpleLinear>>,
+        settings: &IsobaricSetSettings,
+        terminals: Vec<(Option<usize>, Option<usize>)>,
     ) -> Self {
-        let sizes = (center.first().unwrap().1, center.last().unwrap().1);
-        let mut iter = Self {
+        let base_length = base.map_or(0, Peptidoform::len);
+        Self {
             n_term,
             c_term,
             center,
-            sizes,
             bounds,
-            state: (None, None, Vec::new()),
+            length: (
+                settings.min_length.saturating_sub(base_length),
+                settings.max_length.saturating_sub(base_length),
+            ),
+            terminals,
+            terminal: 0,
+            selection: Vec::new(),
+            root: 0,
+            fresh: true,
             base: base.cloned(),
-        };
-        while iter.current_mass() < iter.bounds.0 - iter.sizes.0 {
-            iter.state.2.push(0);
+            constraints: settings.constraints.clone(),
         }
-        iter
     }
 
+    /// The number of amino acids placed with the current terminal options
+    fn terminal_length(&self) -> usize {
+        self.terminals.get(self.terminal).map_or(0, |(n, c)| {
+            usize::from(n.is_some()) + usize::from(c.is_some())
+        })
+    }
+
+    /// The mass of the current terminal options and selection
     fn current_mass(&self) -> Mass {
-        self.state.0.map(|i| self.n_term[i].2).unwrap_or_default()
-            + self.state.1.map(|i| self.c_term[i].2).unwrap_or_default()
+        let (n, c) = self.terminals[self.terminal];
+        n.map(|i| self.n_term[i].2).unwrap_or_default()
+            + c.map(|i| self.c_term[i].2).unwrap_or_default()
             + self
-                .state
-                .2
+                .selection
                 .iter()
-                .copied()
-                .map(|i| self.center[i].1)
+                .map(|i| self.center[*i].1)
                 .sum::<Mass>()
     }
 
-    fn mass_fits(&self) -> Ordering {
+    /// Check if the current selection is a valid set
+    fn fits(&self) -> bool {
         let mass = self.current_mass();
-        if mass < self.bounds.0 {
-            Ordering::Less
-        } else if mass > self.bounds.1 {
-            Ordering::Greater
-        } else {
-            Ordering::Equal
+        let length = self.selection.len() + self.terminal_length();
+        mass >= self.bounds.0
+            && mass <= self.bounds.1
+            && length >= self.length.0.max(1)
+            && length <= self.length.1
+    }
+
+    /// Try to add a building block to the selection, starting at the given index. The block is
+    /// only added if the upper mass bound is not exceeded and the lower mass bound can still be
+    /// reached within the maximal length. Returns if a block was added.
+    fn try_add(&mut self, start: usize) -> bool {
+        let mass = self.current_mass();
+        let length = self.selection.len() + self.terminal_length() + 1;
+        let Some(heaviest) = self.center.last().map(|c| c.1) else {
+            return false;
+        };
+        for index in start..self.center.len() {
+            let new = mass + self.center[index].1;
+            if new > self.bounds.1 {
+                // All following blocks are heavier
+                return false;
+            }
+            let remaining = self.length.1.saturating_sub(length) as f64;
+            if new + heaviest * Ratio::new::<fraction>(remaining) >= self.bounds.0 {
+                self.selection.push(index);
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Split this search into independent parts that together generate the same sets. For every
+    /// combination of terminal options there is one part for the terminal options on their own,
+    /// and one part for every first building block.
+    #[cfg(feature = "rayon")]
+    fn split(self) -> Vec<Self> {
+        let mut parts = Vec::new();
+        for terminal in self.terminals.iter().copied() {
+            parts.push(Self {
+                center: Vec::new(),
+                terminals: vec![terminal],
+                terminal: 0,
+                selection: Vec::new(),
+                root: 0,
+                fresh: true,
+                ..self.clone()
+            });
+            for first in 0..self.center.len() {
+                parts.push(Self {
+                    terminals: vec![terminal],
+                    terminal: 0,
+                    selection: vec![first],
+                    root: 1,
+                    fresh: true,
+                    ..self.clone()
+                });
+            }
+        }
+        parts
+    }
+
+    /// Go to the next selection in depth first order, returns false if all selections for the
+    /// current terminal options are exhausted
+    fn step(&mut self) -> bool {
+        if self.fresh {
+            self.fresh = false;
+            return true;
+        }
+        if self.selection.len() + self.terminal_length() < self.length.1
+            && self.try_add(self.selection.last().copied().unwrap_or_default())
+        {
+            return true;
+        }
+        while self.selection.len() > self.root {
+            let Some(last) = self.selection.pop() else {
+                break;
+            };
+            if self.try_add(last + 1) {
+                return true;
+            }
         }
+        false
     }
 
     /// # Panics
     /// If the base sequence is empty.
     fn peptide(&self) -> Peptidoform<SimpleLinear> {
+        let (n, c) = self.terminals[self.terminal];
         let mut sequence = Vec::with_capacity(
             self.base.as_ref().map(Peptidoform::len).unwrap_or_default()
-                + self.state.2.len()
-                + usize::from(self.state.0.is_some())
-                + usize::from(self.state.1.is_some()),
+                + self.selection.len()
+                + self.terminal_length(),
         );
-        if self
-            .base
-            .as_ref()
-            .is_some_and(|b| !b.get_n_term().is_empty())
-        {
-            sequence.push(self.base.as_ref().unwrap().sequence()[0].clone());
-        } else if let Some(n) = self.state.0.map(|i| self.n_term[i].clone()) {
-            sequence.push(n.0.into());
+        if let Some(n) = n {
+            sequence.push(self.n_term[n].0.clone().into());
         }
         if let Some(base) = &self.base {
-            let n = usize::from(base.get_n_term().is_empty());
-            let c = usize::from(base.get_c_term().is_empty());
-            sequence.extend(base.sequence()[n..base.len() - n - c].iter().cloned());
+            sequence.extend(base.sequence().iter().cloned());
         }
         sequence.extend(
-            self.state
-                .2
+            self.selection
                 .iter()
-                .copied()
-                .map(|i| self.center[i].0.clone().into()),
+                .map(|i| self.center[*i].0.clone().into()),
         );
-        if self
-            .base
-            .as_ref()
-            .is_some_and(|b| !b.get_c_term().is_empty())
-        {
-            sequence.push(
-                self.base
-                    .as_ref()
-                    .unwrap()
-                    .sequence()
-                    .last()
-                    .unwrap()
-                    .clone(),
-            );
-        } else if let Some(c) = self.state.1.map(|i| self.c_term[i].clone()) {
-            sequence.push(c.0.into());
+        if let Some(c) = c {
+            sequence.push(self.c_term[c].0.clone().into());
         }
         Peptidoform::new(sequence)
             .n_term(self.base.as_ref().map_or_else(
                 || {
-                    self.state.0.map_or(Vec::new(), |i| {
+                    n.map_or(Vec::new(), |i| {
                         vec![Modification::Simple(self.n_term[i].1.clone())]
                     })
                 },
@@ -368,130 +710,38 @@ impl IsobaricSetIterator {
             ))
             .c_term(self.base.as_ref().map_or_else(
                 || {
-                    self.state.0.map_or(Vec::new(), |i| {
+                    c.map_or(Vec::new(), |i| {
                         vec![Modification::Simple(self.c_term[i].1.clone())]
                     })
                 },
                 |b| b.get_c_term().to_vec(),
             ))
     }
-
-    /// Reset the state for the center selection
-    fn reset_center_state(&mut self) {
-        self.state.2.clear();
-        while self.current_mass() < self.bounds.0 - self.sizes.0 {
-            self.state.2.push(0);
-        }
-    }
 }
 
 impl Iterator for IsobaricSetIterator {
     type Item = Peptidoform<SimpleLinear>;
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            // N terminal loop
-            loop {
-                // C terminal loop
-
-                // Main loop
-                while !self.state.2.is_empty() {
-                    // Do state + 1 at the highest level where this is still possible and check if that one fits the bounds
-                    // Until every level is full then pop and try with one fewer number of amino acids
-                    while !self.state.2.iter().all(|s| *s == self.center.len() - 1) {
-                        let mut level = self.state.2.len() - 1;
-                        loop {
-                            if self.state.2[level] == self.center.len() - 1 {
-                                if level == 0 {
-                                    break;
-                                }
-                                level -= 1;
-                            } else {
-                                // Update this level
-                                self.state.2[level] += 1;
-                                // Reset the levels above, has to start at minimal at the index of this level to prevent 'rotations' of the set to show up
-                                for l in level + 1..self.state.2.len() {
-                                    self.state.2[l] = self.state.2[level];
-                                }
-                                match self.mass_fits() {
-                                    Ordering::Greater => {
-                                        // If the mass is too great the level below will have the be changed, otherwise it could only be getting heavier with the next iteration(s)
-                                        if level == 0 {
-                                            break;
-                                        }
-                                        level -= 1;
-                                    }
-                                    Ordering::Equal => {
-                                        return Some(self.peptide());
-                                    }
-                                    Ordering::Less => {
-                                        // If there a way to reach at least the lower limit by having all the heaviest options selected try and reach them.
-                                        // Otherwise this will increase this level again next iteration.
-                                        if self.state.2[0..level]
-                                            .iter()
-                                            .map(|i| self.center[*i].1)
-                                            .sum::<Mass>()
-                                            + Ratio::new::<fraction>(
-                                                (self.state.2.len() - level) as f64,
-                                            ) * self.sizes.1
-                                            > self.bounds.0
-                                        {
-                                            level = self.state.2.len() - 1;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    self.state.2.pop();
-                    // Stop the search when there is no possibility for a fitting answer
-                    if self.sizes.1 * Ratio::new::<fraction>(self.state.2.len() as f64)
-                        < self.bounds.0
-                    {
-                        break;
-                    }
-                    // Reset the levels to be all 0s again
-                    for level in 0..self.state.2.len() {
-                        self.state.2[level] = 0;
-                    }
-                }
-
-                // Try the next C terminal option
-                if let Some(c) = self.state.1 {
-                    if c + 1 >= self.c_term.len() {
-                        break;
-                    }
-                    self.state.1 = Some(c + 1);
-                } else if self.c_term.is_empty()
-                    || self
-                        .base
-                        .as_ref()
-                        .is_some_and(|b| !b.get_c_term().is_empty())
-                {
-                    // If the base piece has a defined C term mod do not try possible C term mods in the isobaric generation
-                    break;
-                } else {
-                    self.state.1 = Some(0);
-                }
-                self.reset_center_state();
+        while self.terminal < self.terminals.len() {
+            if !self.step() {
+                // Try the next combination of terminal options
+                self.terminal += 1;
+                self.selection.clear();
+                self.fresh = true;
+                continue;
             }
-            // Try the next N terminal option
-            if let Some(n) = self.state.0 {
-                if n + 1 >= self.n_term.len() {
-                    break;
+            if self.fits() {
+                let peptide = self.peptide();
+                let water = molecular_formula!(H 2 O 1);
+                if self.constraints.is_empty()
+                    || peptide
+                        .formulas()
+                        .iter()
+                        .any(|f| self.constraints.allows(&(f - &water)))
+                {
+                    return Some(peptide);
                 }
-                self.state.0 = Some(n + 1);
-            } else if self.n_term.is_empty()
-                || self
-                    .base
-                    .as_ref()
-                    .is_some_and(|b| !b.get_n_term().is_empty())
-            {
-                // If the base piece has a defined N term mod do not try possible N term mods in the isobaric generation
-                break;
-            } else {
-                self.state.1 = Some(0);
             }
-            self.reset_center_state();
         }
         None
     }
@@ -502,6 +752,7 @@ impl Iterator for IsobaricSetIterator {
 mod tests {
 
     use super::*;
+    use crate::modification::Ontology;
     #[test]
     fn simple_isobaric_sets() {
         let pep = Peptidoform::pro_forma("AG", None)
@@ -515,6 +766,7 @@ mod tests {
             &[],
             &[],
             None,
+            &IsobaricSetSettings::default(),
         )
         .collect();
         assert_eq!(
@@ -531,4 +783,233 @@ mod tests {
             ]
         );
     }
+
+    #[test]
+    fn element_constraints() {
+        let find = |constraints: &ElementConstraints| {
+            find_isobaric_sets(
+                molecular_formula!(C 6 H 12 N 2 O 1).monoisotopic_mass(),
+                Tolerance::new_absolute(crate::system::da(0.05)),
+                AminoAcid::UNIQUE_MASS_AMINO_ACIDS,
+                &[],
+                &[],
+                None,
+                &IsobaricSetSettings::default().constraints(constraints.clone()),
+            )
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+        };
+        // K (C6H12N2O) is within 0.05 Da of Q and GA (both C5H8N2O2)
+        assert_eq!(find(&ElementConstraints::default()), ["GA", "Q", "K"]);
+        assert_eq!(
+            find(&ElementConstraints::default().max(Element::O, 1)),
+            ["K"]
+        );
+        assert_eq!(
+            find(&ElementConstraints::default().max(Element::C, 5)),
+            ["GA", "Q"]
+        );
+        assert!(find(&ElementConstraints::default().forbid(Element::N)).is_empty());
+        assert_eq!(
+            find(&ElementConstraints::default().only(&[
+                Element::C,
+                Element::H,
+                Element::N,
+                Element::O
+            ]))
+            .len(),
+            3
+        );
+        let sulfur = ElementConstraints::default().max(Element::S, 1);
+        assert!(sulfur.allows(&molecular_formula!(C 5 H 9 N 1 O 1 S 1)));
+        assert!(!sulfur.allows(&molecular_formula!(C 6 H 10 N 2 O 2 S 2)));
+    }
+
+    fn find(
+        mass: &str,
+        tolerance: Tolerance<Mass>,
+        fixed: &[(SimpleModification, Option<PlacementRule>)],
+        variable: &[(SimpleModification, Option<PlacementRule>)],
+        base: Option<&str>,
+        settings: &IsobaricSetSettings,
+    ) -> Vec<String> {
+        let mass = Peptidoform::pro_forma(mass, None)
+            .unwrap()
+            .into_simple_linear()
+            .unwrap()
+            .formulas()[0]
+            .monoisotopic_mass()
+            - molecular_formula!(H 2 O 1).monoisotopic_mass();
+        let base = base.map(|b| {
+            Peptidoform::pro_forma(b, None)
+                .unwrap()
+                .into_simple_linear()
+                .unwrap()
+        });
+        find_isobaric_sets(
+            mass,
+            tolerance,
+            AminoAcid::UNIQUE_MASS_AMINO_ACIDS,
+            fixed,
+            variable,
+            base.as_ref(),
+            settings,
+        )
+        .map(|p| p.to_string())
+        .collect()
+    }
+
+    #[test]
+    fn length() {
+        let ppm = Tolerance::new_ppm(10.0);
+        let all = find("GGA", ppm, &[], &[], None, &IsobaricSetSettings::default());
+        assert_eq!(all, ["GGA", "GQ", "AN"]);
+        assert_eq!(
+            find(
+                "GGA",
+                ppm,
+                &[],
+                &[],
+                None,
+                &IsobaricSetSettings::default().length(3, 3)
+            ),
+            ["GGA"]
+        );
+        assert_eq!(
+            find(
+                "GGA",
+                ppm,
+                &[],
+                &[],
+                None,
+                &IsobaricSetSettings::default().length(1, 2)
+            ),
+            ["GQ", "AN"]
+        );
+        assert!(find(
+            "GGA",
+            ppm,
+            &[],
+            &[],
+            None,
+            &IsobaricSetSettings::default().length(4, 10)
+        )
+        .is_empty());
+        // Deterministic
+        assert_eq!(
+            all,
+            find("GGA", ppm, &[], &[], None, &IsobaricSetSettings::default())
+        );
+    }
+
+    #[test]
+    fn terminal_modifications() {
+        let ppm = Tolerance::new_ppm(10.0);
+        let acetyl = (
+            Ontology::Unimod.find_id(1, None).unwrap(),
+            Some(PlacementRule::Terminal(Position::AnyNTerm)),
+        );
+        let amidated = (
+            Ontology::Unimod.find_id(2, None).unwrap(),
+            Some(PlacementRule::Terminal(Position::AnyCTerm)),
+        );
+        let settings = IsobaricSetSettings::default().length(1, 2);
+        // Variable modifications are optional
+        assert_eq!(
+            find("AG", ppm, &[], std::slice::from_ref(&acetyl), None, &settings),
+            ["GA", "Q"]
+        );
+        let variable = find("[Acetyl]-AG", ppm, &[], std::slice::from_ref(&acetyl), None, &settings);
+        assert!(variable.contains(&"[U:Acetyl]-AG".to_string()));
+        assert!(variable.contains(&"[U:Acetyl]-GA".to_string()));
+        assert!(variable.contains(&"[U:Acetyl]-Q".to_string()));
+        // Fixed modifications are always placed
+        assert!(find("AG", ppm, std::slice::from_ref(&acetyl), &[], None, &settings).is_empty());
+        let fixed = find("[Acetyl]-AG", ppm, &[acetyl], &[], None, &settings);
+        assert!(!fixed.is_empty());
+        assert!(fixed.iter().all(|p| p.starts_with("[U:Acetyl]-")));
+        // C terminal modifications end up on the C terminus
+        let c_term = find("AG-[Amidated]", ppm, &[], &[amidated], None, &settings);
+        assert!(c_term.contains(&"GA-[U:Amidated]".to_string()));
+        assert!(c_term.iter().all(|p| p.ends_with("-[U:Amidated]")));
+    }
+
+    #[test]
+    fn base() {
+        let ppm = Tolerance::new_ppm(10.0);
+        let sets = find(
+            "WGA",
+            ppm,
+            &[],
+            &[],
+            Some("W"),
+            &IsobaricSetSettings::default(),
+        );
+        assert_eq!(sets, ["WGA", "WQ"]);
+        assert_eq!(
+            find(
+                "WGA",
+                ppm,
+                &[],
+                &[],
+                Some("W"),
+                &IsobaricSetSettings::default().length(1, 2)
+            ),
+            ["WQ"]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "rayon")]
+    fn parallel() {
+        let acetyl = (
+            Ontology::Unimod.find_id(1, None).unwrap(),
+            Some(PlacementRule::Terminal(Position::AnyNTerm)),
+        );
+        for (mass, base) in [("GGA", None), ("[Acetyl]-WGGA", None), ("WGGA", Some("W"))] {
+            let mass = Peptidoform::pro_forma(mass, None)
+                .unwrap()
+                .into_simple_linear()
+                .unwrap()
+                .formulas()[0]
+                .monoisotopic_mass()
+                - molecular_formula!(H 2 O 1).monoisotopic_mass();
+            let base = base.map(|b| {
+                Peptidoform::pro_forma(b, None)
+                    .unwrap()
+                    .into_simple_linear()
+                    .unwrap()
+            });
+            let settings = IsobaricSetSettings::default().length(1, 5);
+            let tolerance = Tolerance::new_ppm(10.0);
+            let amino_acids = AminoAcid::UNIQUE_MASS_AMINO_ACIDS;
+            let variable = std::slice::from_ref(&acetyl);
+            let mut sequential = find_isobaric_sets(
+                mass,
+                tolerance,
+                amino_acids,
+                &[],
+                variable,
+                base.as_ref(),
+                &settings,
+            )
+            .map(|p| p.to_string())
+            .collect_vec();
+            let mut parallel = par_find_isobaric_sets(
+                mass,
+                tolerance,
+                amino_acids,
+                &[],
+                variable,
+                base.as_ref(),
+                &settings,
+            )
+            .map(|p| p.to_string())
+            .collect_vec();
+            sequential.sort();
+            parallel.sort();
+            assert!(!sequential.is_empty());
+            assert_eq!(sequential, parallel);
+        }
+    }
 }
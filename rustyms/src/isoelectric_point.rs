@@ -0,0 +1,424 @@
+//! Isoelectric point calculation for peptidoforms
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    modification::{ModificationId, Ontology, SimpleModificationInner},
+    peptidoform::{AtMax, Linear},
+    system::{e, usize::Charge},
+    AminoAcid, Modification, Peptidoform,
+};
+
+/// The pKa values of all ionisable groups in a peptide, used to calculate the charge of a
+/// peptidoform at a given pH and its isoelectric point, see
+/// [`Peptidoform::isoelectric_point`].
+///
+/// ```rust
+/// # use rustyms::*;
+/// let table = PkaTable::lehninger().side_chain(AminoAcid::Histidine, Some(6.5));
+/// let peptide = Peptidoform::pro_forma("HHHHHH", None).unwrap().into_linear().unwrap();
+/// let pi = peptide.isoelectric_point_with(&table);
+/// assert!(pi > 7.0 && pi < 9.0);
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PkaTable {
+    /// The pKa of the N terminal amine
+    n_term: f64,
+    /// The pKa of the C terminal carboxylic acid
+    c_term: f64,
+    /// The pKa of the side chain of every amino acid (if ionisable), indexed by the amino acid
+    side_chains: [Option<f64>; AminoAcid::TOTAL_NUMBER],
+}
+
+impl Default for PkaTable {
+    /// The EMBOSS pKa values, see [`PkaTable::emboss`]
+    fn default() -> Self {
+        Self::emboss()
+    }
+}
+
+impl PkaTable {
+    /// Create a pKa table from the N and C terminal pKa and the side chain pKa values for the
+    /// given amino acids, all other amino acids are not ionisable
+    pub fn new(n_term: f64, c_term: f64, side_chains: &[(AminoAcid, f64)]) -> Self {
+        let mut table = Self {
+            n_term,
+            c_term,
+            side_chains: [None; AminoAcid::TOTAL_NUMBER],
+        };
+        for (aa, pka) in side_chains {
+            table.side_chains[*aa as usize] = Some(*pka);
+        }
+        table
+    }
+
+    /// The pKa values as used by EMBOSS iep (<https://emboss.sourceforge.net/apps/cvs/emboss/apps/iep.html>)
+    pub fn emboss() -> Self {
+        Self::new(
+            8.6,
+            3.6,
+            &[
+                (AminoAcid::Cysteine, 8.5),
+                (AminoAcid::AsparticAcid, 3.9),
+                (AminoAcid::GlutamicAcid, 4.1),
+                (AminoAcid::Histidine, 6.5),
+                (AminoAcid::Lysine, 10.8),
+                (AminoAcid::Arginine, 12.5),
+                (AminoAcid::Tyrosine, 10.1),
+                (AminoAcid::Selenocysteine, 5.2),
+            ],
+        )
+    }
+
+    /// The pKa values of the free amino acids as listed in Lehninger Principles of Biochemistry
+    pub fn lehninger() -> Self {
+        Self::new(
+            9.69,
+            2.34,
+            &[
+                (AminoAcid::Cysteine, 8.33),
+                (AminoAcid::AsparticAcid, 3.86),
+                (AminoAcid::GlutamicAcid, 4.25),
+                (AminoAcid::Histidine, 6.0),
+                (AminoAcid::Lysine, 10.5),
+                (AminoAcid::Arginine, 12.4),
+                (AminoAcid::Tyrosine, 10.07),
+                (AminoAcid::Selenocysteine, 5.2),
+            ],
+        )
+    }
+
+    /// Set the pKa of the N terminal amine
+    #[must_use]
+    pub const fn n_term(self, n_term: f64) -> Self {
+        Self { n_term, ..self }
+    }
+
+    /// Set the pKa of the C terminal carboxylic acid
+    #[must_use]
+    pub const fn c_term(self, c_term: f64) -> Self {
+        Self { c_term, ..self }
+    }
+
+    /// Set the pKa of the side chain of the given amino acid, `None` means not ionisable
+    #[must_use]
+    pub const fn side_chain(mut self, aminoacid: AminoAcid, pka: Option<f64>) -> Self {
+        self.side_chains[aminoacid as usize] = pka;
+        self
+    }
+
+    /// Get the pKa of the side chain of the given amino acid, if ionisable
+    pub const fn get_side_chain(&self, aminoacid: AminoAcid) -> Option<f64> {
+        self.side_chains[aminoacid as usize]
+    }
+}
+
+/// An ionisable group with its pKa and if it is basic (positive when protonated) or acidic
+/// (negative when deprotonated)
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct IonisableGroup {
+    pka: f64,
+    basic: bool,
+}
+
+impl IonisableGroup {
+    const fn acid(pka: f64) -> Self {
+        Self { pka, basic: false }
+    }
+
+    const fn base(pka: f64) -> Self {
+        Self { pka, basic: true }
+    }
+
+    /// The (partial) charge of this group at the given pH
+    fn charge(self, ph: f64) -> f64 {
+        if self.basic {
+            1.0 / (1.0 + 10.0_f64.powf(ph - self.pka))
+        } else {
+            -1.0 / (1.0 + 10.0_f64.powf(self.pka - ph))
+        }
+    }
+}
+
+/// The effect of a modification on the ionisable groups, if it removes the group it is placed on
+/// and any groups it adds
+struct ModificationEffect {
+    removes: bool,
+    adds: &'static [IonisableGroup],
+}
+
+/// Get the effect of a modification on the ionisable groups, only a selection of common Unimod
+/// modifications is recognised, all others are assumed to not change the charge
+fn modification_effect(modification: &Modification) -> Option<ModificationEffect> {
+    const STRONG_ACID: IonisableGroup = IonisableGroup::acid(f64::NEG_INFINITY);
+    const FIXED_CHARGE: IonisableGroup = IonisableGroup::base(f64::INFINITY);
+    const PHOSPHATE: &[IonisableGroup] = &[STRONG_ACID, IonisableGroup::acid(6.5)];
+    const CARBOXYLIC_ACID: &[IonisableGroup] = &[IonisableGroup::acid(3.5)];
+    const DEAMIDATED: &[IonisableGroup] = &[IonisableGroup::acid(4.0)];
+    let simple = match modification {
+        Modification::Simple(simple) => simple,
+        Modification::Ambiguous {
+            modification,
+            preferred: true,
+            ..
+        } => modification,
+        Modification::Ambiguous { .. } | Modification::CrossLink { .. } => return None,
+    };
+    let SimpleModificationInner::Database {
+        id:
+            ModificationId {
+                ontology: Ontology::Unimod,
+                id: Some(id),
+                ..
+            },
+        ..
+    } = &**simple
+    else {
+        return None;
+    };
+    let (removes, adds): (bool, &'static [IonisableGroup]) = match id {
+        // Phospho, the phosphate has a strong first and a weak second acidic group
+        21 => (true, PHOSPHATE),
+        // Sulfo
+        40 => (true, &[STRONG_ACID]),
+        // Acetyl, Formyl, Carbamidomethyl, Carbamyl, Propionamide, Methylthio, Gln->pyro-Glu,
+        // Glu->pyro-Glu, Amidated, Nethylmaleimide, TMT, iTRAQ
+        1 | 122 | 4 | 5 | 24 | 39 | 28 | 27 | 2 | 108 | 737 | 214 | 730 => (true, &[]),
+        // Carboxymethyl, replaces the thiol with a carboxylic acid
+        6 => (true, CARBOXYLIC_ACID),
+        // Deamidated, creates an aspartic or glutamic acid
+        7 => (false, DEAMIDATED),
+        // Trimethyl, a quaternary amine with a fixed positive charge
+        37 => (true, &[FIXED_CHARGE]),
+        _ => return None,
+    };
+    Some(ModificationEffect { removes, adds })
+}
+
+impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
+    /// All ionisable groups in this peptidoform with the given pKa table
+    fn ionisable_groups(&self, table: &PkaTable) -> Vec<IonisableGroup> {
+        let mut groups = Vec::new();
+        let mut add = |base: Option<IonisableGroup>, modifications: &[Modification]| {
+            let mut removed = false;
+            for effect in modifications.iter().filter_map(modification_effect) {
+                removed |= effect.removes;
+                groups.extend_from_slice(effect.adds);
+            }
+            if let Some(base) = base.filter(|_| !removed) {
+                groups.push(base);
+            }
+        };
+        if self.is_empty() {
+            return Vec::new();
+        }
+        add(Some(IonisableGroup::base(table.n_term)), self.get_n_term());
+        add(Some(IonisableGroup::acid(table.c_term)), self.get_c_term());
+        for element in self.sequence() {
+            let aa = element.aminoacid.aminoacid();
+            let base = table.get_side_chain(aa).map(|pka| {
+                if matches!(
+                    aa,
+                    AminoAcid::Lysine | AminoAcid::Arginine | AminoAcid::Histidine
+                ) {
+                    IonisableGroup::base(pka)
+                } else {
+                    IonisableGroup::acid(pka)
+                }
+            });
+            add(base, &element.modifications);
+        }
+        groups
+    }
+
+    /// Calculate the net charge of this peptidoform at the given pH with the default (EMBOSS) pKa
+    /// values, see [`Self::charge_at_ph_with`].
+    /// ```rust
+    /// # use rustyms::*;
+    /// let peptide = Peptidoform::pro_forma("PEPTIDEK", None).unwrap().into_linear().unwrap();
+    /// assert!(peptide.charge_at_ph(2.0) > 0.0);
+    /// assert!(peptide.charge_at_ph(7.0) < 0.0);
+    /// ```
+    pub fn charge_at_ph(&self, ph: f64) -> f64 {
+        self.charge_at_ph_with(ph, &PkaTable::default())
+    }
+
+    /// Calculate the net charge of this peptidoform at the given pH with the given pKa values,
+    /// using the Henderson-Hasselbalch equation for every ionisable group. The ionisable groups
+    /// (including the effect of modifications) are determined in the same way as for
+    /// [`Self::isoelectric_point_with`].
+    pub fn charge_at_ph_with(&self, ph: f64, table: &PkaTable) -> f64 {
+        self.ionisable_groups(table)
+            .iter()
+            .map(|group| group.charge(ph))
+            .sum()
+    }
+
+    /// Predict the most likely charge states of this peptidoform in positive mode electrospray
+    /// ionisation, using the default (EMBOSS) pKa values and a pH of 2.7 (0.1% formic acid), see
+    /// [`Self::predicted_charge_states_with`].
+    /// ```rust
+    /// # use rustyms::*;
+    /// let peptide = Peptidoform::pro_forma("PEPTIDEK", None).unwrap().into_linear().unwrap();
+    /// let states = peptide.predicted_charge_states();
+    /// assert_eq!(states[0].0.value, 2);
+    /// ```
+    pub fn predicted_charge_states(&self) -> Vec<(Charge, f64)> {
+        self.predicted_charge_states_with(2.7, &PkaTable::default())
+    }
+
+    /// Predict the most likely charge states of this peptidoform in positive mode electrospray
+    /// ionisation at the given pH with the given pKa values. Every basic group (the N terminus,
+    /// basic side chains, and fixed charges from modifications) is assumed to be protonated
+    /// independently with the probability given by its pKa, acidic groups are ignored. The
+    /// returned charge states are sorted from most to least likely with their relative
+    /// probability, the uncharged state is left out and the probabilities are normalised to sum
+    /// to one. If the peptidoform has no basic groups no charge states are returned. This is a
+    /// simple model that overestimates high charge states for short peptides, but it can be
+    /// used to prioritise the charges used for fragment generation.
+    pub fn predicted_charge_states_with(&self, ph: f64, table: &PkaTable) -> Vec<(Charge, f64)> {
+        // The distribution of the number of protonated groups (a Poisson binomial distribution)
+        let mut distribution = vec![1.0];
+        for group in self
+            .ionisable_groups(table)
+            .into_iter()
+            .filter(|group| group.basic)
+        {
+            let probability = group.charge(ph);
+            let mut next = vec![0.0; distribution.len() + 1];
+            for (charge, chance) in distribution.iter().enumerate() {
+                next[charge] += chance * (1.0 - probability);
+                next[charge + 1] += chance * probability;
+            }
+            distribution = next;
+        }
+        let total = distribution.iter().skip(1).sum::<f64>();
+        if total <= 0.0 {
+            return Vec::new();
+        }
+        let mut states = distribution
+            .into_iter()
+            .enumerate()
+            .skip(1)
+            .map(|(charge, chance)| (Charge::new::<e>(charge), chance / total))
+            .collect::<Vec<_>>();
+        states.sort_by(|a, b| b.1.total_cmp(&a.1));
+        states
+    }
+
+    /// Calculate the isoelectric point of this peptidoform with the default (EMBOSS) pKa
+    /// values, see [`Self::isoelectric_point_with`].
+    /// ```rust
+    /// # use rustyms::*;
+    /// let peptide = Peptidoform::pro_forma("PEPTIDE", None).unwrap().into_linear().unwrap();
+    /// let phospho = Peptidoform::pro_forma("PEPT[Phospho]IDE", None).unwrap().into_linear().unwrap();
+    /// assert!(phospho.isoelectric_point() < peptide.isoelectric_point());
+    /// ```
+    pub fn isoelectric_point(&self) -> f64 {
+        self.isoelectric_point_with(&PkaTable::default())
+    }
+
+    /// Calculate the isoelectric point of this peptidoform with the given pKa values. This is the
+    /// pH (between 0 and 14) at which the net charge of the peptidoform is zero, found with a
+    /// bisection search. The N and C terminal groups and all ionisable side chains are taken into
+    /// account. Modifications are taken into account for some common Unimod modifications, for
+    /// example a phosphorylation adds acidic groups and removes the side chain of tyrosine, an
+    /// acetylation removes the amine it is placed on, and a deamidation adds an acidic group.
+    /// Modifications of unknown position are only taken into account on their preferred
+    /// location. If the peptidoform is positive or negative over the full pH range 14 or 0 is
+    /// returned respectively.
+    pub fn isoelectric_point_with(&self, table: &PkaTable) -> f64 {
+        let groups = self.ionisable_groups(table);
+        let charge = |ph: f64| groups.iter().map(|group| group.charge(ph)).sum::<f64>();
+        let (mut low, mut high) = (0.0, 14.0);
+        if charge(high) >= 0.0 {
+            return high;
+        }
+        if charge(low) <= 0.0 {
+            return low;
+        }
+        // 14 / 2^30 gives a precision well below 0.0001 pH units
+        for _ in 0..30 {
+            let middle = (low + high) / 2.0;
+            if charge(middle) > 0.0 {
+                low = middle;
+            } else {
+                high = middle;
+            }
+        }
+        (low + high) / 2.0
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc, clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    fn pi(peptide: &str) -> f64 {
+        Peptidoform::pro_forma(peptide, None)
+            .unwrap()
+            .into_linear()
+            .unwrap()
+            .isoelectric_point()
+    }
+
+    #[test]
+    fn isoelectric_point() {
+        // With only the termini the isoelectric point is the average of their pKa values
+        assert!((pi("GGG") - 6.1).abs() < 0.001, "{}", pi("GGG"));
+        assert!((3.0..4.0).contains(&pi("PEPTIDE")), "{}", pi("PEPTIDE"));
+        assert!((10.0..12.0).contains(&pi("KKKKK")), "{}", pi("KKKKK"));
+        // Neutral modifications
+        assert!((pi("M[Oxidation]AGIC") - pi("MAGIC")).abs() < 1e-9);
+        // Acidic and basic modifications
+        assert!(pi("PEPS[Phospho]IDEK") < pi("PEPSIDEK"));
+        assert!(pi("[Acetyl]-GGGK[Acetyl]") < pi("GGGK"));
+        assert!(pi("GGGN[Deamidated]") < pi("GGGN"));
+        assert!(pi("GGGK[Trimethyl]") > pi("GGGK"));
+        assert!(pi("PEPTIDE-[Amidated]") > pi("PEPTIDE"));
+        assert_eq!(pi("K[Trimethyl]K[Trimethyl]"), 14.0);
+        assert!(pi("AAY[Phospho]") < pi("AAY"));
+    }
+
+    #[test]
+    fn tables() {
+        let peptide = Peptidoform::pro_forma("ACDEFGHIKLMNPQRSTVWY", None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let emboss = peptide.isoelectric_point_with(&PkaTable::emboss());
+        let lehninger = peptide.isoelectric_point_with(&PkaTable::lehninger());
+        assert!((emboss - lehninger).abs() < 1.0);
+        assert!(peptide.charge_at_ph(emboss).abs() < 0.001);
+        assert!(peptide.charge_at_ph(1.0) > 0.0);
+        assert_eq!(
+            PkaTable::emboss().get_side_chain(AminoAcid::Lysine),
+            Some(10.8)
+        );
+        assert_eq!(PkaTable::emboss().get_side_chain(AminoAcid::Glycine), None);
+    }
+
+    #[test]
+    fn charge_states() {
+        let states = |peptide: &str| {
+            Peptidoform::pro_forma(peptide, None)
+                .unwrap()
+                .into_linear()
+                .unwrap()
+                .predicted_charge_states()
+                .into_iter()
+                .map(|(charge, chance)| (charge.value, chance))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(states("[Acetyl]-PEPTIDE"), Vec::new());
+        assert_eq!(states("PEPTIDE")[0].0, 1);
+        assert_eq!(states("PEPTIDEK")[0].0, 2);
+        assert_eq!(states("KPEPTIDEKR")[0].0, 4);
+        assert_eq!(states("KPEPTIDEKR").len(), 4);
+        assert!((states("KPEPTIDEKR").iter().map(|s| s.1).sum::<f64>() - 1.0).abs() < 1e-9);
+        assert!(states("PEPHTIDEK").iter().any(|s| s.0 == 3));
+        assert_eq!(states("K[Trimethyl]")[0].0, 2);
+        assert!(states("K[Trimethyl]")[0].1 > 0.999);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::MolecularFormula;
+use crate::{
+    system::{da, Mass},
+    MolecularFormula,
+};
 use itertools::Itertools;
 use ndarray::{arr1, concatenate, s, Array1, Axis};
 use probability::distribution::{Binomial, Discrete};
@@ -101,4 +104,143 @@ impl MolecularFormula {
         }
         result
     }
+
+    /// Get the isotopic distribution including the fine structure, using the natural distribution
+    /// as defined by CIAAW. All elements are considered, except for elements with a defined
+    /// isotope and elements with a negative number of atoms, these are taken to only contribute
+    /// their (monoisotopic) mass. The return is a list of masses (of the full formula, so
+    /// including additional mass and electrons) with their probability, sorted on mass.
+    ///
+    /// All isotopologues that are closer together than the given resolution (in dalton) are
+    /// merged into one peak with the probability weighted average mass, so at a resolution of
+    /// 0.5 Da this gives the same distribution as [`Self::isotopic_distribution`] but with
+    /// exact masses, while at a resolution of a few mDa the fine structure (for example the
+    /// separate <sup>13</sup>C and <sup>15</sup>N peaks) is resolved. Isotopologues with a
+    /// probability below the threshold are ignored, so the probability is normalized to
+    /// (approximately) 1 total area.
+    pub fn fine_isotopic_distribution(&self, resolution: Mass, threshold: f64) -> Vec<(Mass, f64)> {
+        let resolution = resolution.value.abs();
+        // Merge intermediate results at a much finer scale to not shift the final masses
+        let intermediate = (resolution / 100.0).max(1e-9);
+        let mut result = vec![(0.0, 1.0)];
+        for (element, isotope, amount) in self.elements() {
+            if isotope.is_some() || *amount <= 0 {
+                continue;
+            }
+            let Some(monoisotopic) = element.mass(None) else {
+                continue;
+            };
+            let atom = element
+                .isotopes()
+                .iter()
+                .filter(|i| i.2 != 0.0)
+                .map(|i| ((i.1 - monoisotopic).value, i.2))
+                .collect_vec();
+            if atom.len() < 2 {
+                // Only a single species, so no distribution is needed
+                continue;
+            }
+            // Raise the distribution of a single atom to the power of the number of atoms by
+            // repeated squaring
+            let mut power = atom;
+            let mut amount = amount.unsigned_abs();
+            let mut element_distribution = vec![(0.0, 1.0)];
+            while amount > 0 {
+                if amount & 1 == 1 {
+                    element_distribution =
+                        convolve(&element_distribution, &power, intermediate, threshold);
+                }
+                amount >>= 1;
+                if amount > 0 {
+                    power = convolve(&power, &power, intermediate, threshold);
+                }
+            }
+            result = convolve(&result, &element_distribution, intermediate, threshold);
+        }
+        let monoisotopic = self.monoisotopic_mass().value;
+        merge(result, resolution)
+            .into_iter()
+            .map(|(offset, probability)| (da(monoisotopic + offset), probability))
+            .collect()
+    }
+}
+
+/// Combine two distributions of (mass offset, probability), merging the peaks within the given
+/// resolution and removing all peaks below the threshold
+fn convolve(
+    a: &[(f64, f64)],
+    b: &[(f64, f64)],
+    resolution: f64,
+    threshold: f64,
+) -> Vec<(f64, f64)> {
+    let combined = a
+        .iter()
+        .cartesian_product(b)
+        .map(|(a, b)| (a.0 + b.0, a.1 * b.1))
+        .filter(|(_, probability)| *probability >= threshold)
+        .collect_vec();
+    merge(combined, resolution)
+}
+
+/// Merge all peaks that are within the given resolution of the previous peak, the merged peak
+/// gets the probability weighted average mass
+fn merge(mut peaks: Vec<(f64, f64)>, resolution: f64) -> Vec<(f64, f64)> {
+    peaks.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    let mut result: Vec<(f64, f64)> = Vec::with_capacity(peaks.len());
+    for (mass, probability) in peaks {
+        match result.last_mut() {
+            Some(last) if mass - last.0 < resolution => {
+                let total = last.1 + probability;
+                last.0 = last.0.mul_add(last.1, mass * probability) / total;
+                last.1 = total;
+            }
+            _ => result.push((mass, probability)),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fine_structure() {
+        let formula = molecular_formula!(C 50 H 80 N 14 O 15 S 1);
+        let nominal = formula.isotopic_distribution(1e-6);
+        let coarse = formula.fine_isotopic_distribution(da(0.5), 1e-9);
+        let monoisotopic = formula.monoisotopic_mass();
+        assert!((coarse[0].0 - monoisotopic).value.abs() < 1e-9);
+        for (index, (mass, probability)) in coarse.iter().take(4).enumerate() {
+            assert!(((*mass - monoisotopic).value - index as f64).abs() < 0.05);
+            assert!(
+                (probability - nominal[index]).abs() < 0.01,
+                "{index}: {probability} vs {}",
+                nominal[index]
+            );
+        }
+        assert!((coarse.iter().map(|p| p.1).sum::<f64>() - 1.0).abs() < 1e-4);
+
+        // At high resolution the +1 peak splits into at least 13C, 15N, 2H, and 33S
+        let fine = formula.fine_isotopic_distribution(da(0.000_1), 1e-6);
+        let plus_one = fine
+            .iter()
+            .filter(|(mass, _)| ((*mass - monoisotopic).value - 1.0).abs() < 0.1)
+            .collect_vec();
+        assert!(plus_one.len() >= 4, "{plus_one:?}");
+        let carbon = plus_one.iter().max_by(|a, b| a.1.total_cmp(&b.1)).unwrap();
+        assert!(((carbon.0 - monoisotopic).value - 1.003_355).abs() < 1e-5);
+        assert!((plus_one.iter().map(|p| p.1).sum::<f64>() - coarse[1].1).abs() < 1e-4);
+
+        // Specific isotopes only shift the mass
+        let labelled = molecular_formula!([13 C 6] H 12 O 6);
+        let distribution = labelled.fine_isotopic_distribution(da(0.001), 1e-9);
+        assert!(
+            (distribution[0].0 - labelled.monoisotopic_mass())
+                .value
+                .abs()
+                < 1e-9
+        );
+    }
 }
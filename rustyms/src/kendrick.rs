@@ -0,0 +1,241 @@
+//! Kendrick mass and Kendrick mass defect analysis
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spectrum::PeakSpectrum,
+    system::{da, usize::Charge, Mass},
+    Chemical, MolecularCharge, MolecularFormula, RawSpectrum,
+};
+
+/// A Kendrick mass scale, which rescales masses so that the repeating unit has an integer mass.
+/// All members of a homologous series (masses that only differ in the number of repeating units,
+/// eg a polymer or a glycan series) then have the same Kendrick mass defect, which allows
+/// detecting these series.
+///
+/// ```rust
+/// # use rustyms::{*, system::da};
+/// let scale = KendrickScale::hexose();
+/// // Man5 and Man6 (HexNAc2Hex5 and HexNAc2Hex6)
+/// let man5 = scale.kendrick_mass_defect(da(1216.4229));
+/// let man6 = scale.kendrick_mass_defect(da(1378.4757));
+/// assert!((man5 - man6).value.abs() < 0.001);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KendrickScale {
+    /// The exact (monoisotopic) mass of the repeating unit
+    unit: Mass,
+    /// The nominal mass of the repeating unit
+    nominal: f64,
+}
+
+/// A single peak in Kendrick space, see [`KendrickScale::spectrum`]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KendrickPoint {
+    /// The neutral mass
+    pub mass: Mass,
+    /// The Kendrick mass
+    pub kendrick_mass: Mass,
+    /// The Kendrick mass defect
+    pub kendrick_mass_defect: Mass,
+    /// The intensity of the peak
+    pub intensity: f64,
+}
+
+impl KendrickScale {
+    /// Create a Kendrick scale for the given repeating unit, using the monoisotopic mass
+    pub fn new(unit: &MolecularFormula) -> Self {
+        Self::from_mass(unit.monoisotopic_mass())
+    }
+
+    /// Create a Kendrick scale for a repeating unit with the given exact mass, the nominal mass of
+    /// the unit is this mass rounded to the nearest integer
+    pub fn from_mass(unit: Mass) -> Self {
+        Self {
+            unit,
+            nominal: unit.value.round(),
+        }
+    }
+
+    /// The classical Kendrick scale with CH<sub>2</sub> as repeating unit
+    pub fn ch2() -> Self {
+        Self::new(&molecular_formula!(C 1 H 2))
+    }
+
+    /// A Kendrick scale with a hexose residue (Hex, C<sub>6</sub>H<sub>10</sub>O<sub>5</sub>) as
+    /// repeating unit
+    pub fn hexose() -> Self {
+        Self::new(&molecular_formula!(C 6 H 10 O 5))
+    }
+
+    /// A Kendrick scale with a N-acetylhexosamine residue (HexNAc,
+    /// C<sub>8</sub>H<sub>13</sub>NO<sub>5</sub>) as repeating unit
+    pub fn hexnac() -> Self {
+        Self::new(&molecular_formula!(C 8 H 13 N 1 O 5))
+    }
+
+    /// The exact mass of the repeating unit
+    pub const fn unit(&self) -> Mass {
+        self.unit
+    }
+
+    /// The nominal mass of the repeating unit
+    pub const fn nominal_unit(&self) -> f64 {
+        self.nominal
+    }
+
+    /// The Kendrick mass for the given mass, the mass multiplied by the nominal unit mass divided
+    /// by the exact unit mass
+    pub fn kendrick_mass(&self, mass: Mass) -> Mass {
+        da(mass.value * self.nominal / self.unit.value)
+    }
+
+    /// The Kendrick mass defect for the given mass, the nominal Kendrick mass (rounded to the
+    /// nearest integer) minus the Kendrick mass
+    pub fn kendrick_mass_defect(&self, mass: Mass) -> Mass {
+        let kendrick_mass = self.kendrick_mass(mass);
+        da(kendrick_mass.value.round() - kendrick_mass.value)
+    }
+
+    /// Calculate the Kendrick mass and mass defect for all peaks in the given spectrum. All peaks
+    /// are assumed to have the given charge (with protons as charge carriers) and are converted
+    /// to neutral masses first.
+    pub fn spectrum(&self, spectrum: &RawSpectrum, charge: Charge) -> Vec<KendrickPoint> {
+        let z = charge.value.max(1) as f64;
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        spectrum
+            .spectrum()
+            .map(|peak| {
+                let mass = da((peak.mz.value - proton) * z);
+                KendrickPoint {
+                    mass,
+                    kendrick_mass: self.kendrick_mass(mass),
+                    kendrick_mass_defect: self.kendrick_mass_defect(mass),
+                    intensity: *peak.intensity,
+                }
+            })
+            .collect()
+    }
+
+    /// Find all homologous series in the given masses. A series is a set of masses with the same
+    /// Kendrick mass defect (within the given tolerance) that are consecutive multiples of the
+    /// repeating unit apart. Only series with at least `min_length` members are returned, each as
+    /// the indices into the given masses sorted on mass. Because the defect is based on rounding
+    /// series with a defect close to ±0.5 Da can be split up.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, system::da};
+    /// let scale = KendrickScale::ch2();
+    /// let ch2 = scale.unit().value;
+    /// let masses = [da(300.0), da(300.0 + ch2), da(451.3), da(300.0 + 2.0 * ch2)];
+    /// assert_eq!(scale.series(&masses, da(0.002), 3), vec![vec![0, 1, 3]]);
+    /// ```
+    pub fn series(&self, masses: &[Mass], tolerance: Mass, min_length: usize) -> Vec<Vec<usize>> {
+        let points = masses
+            .iter()
+            .enumerate()
+            .map(|(index, mass)| {
+                let kendrick_mass = self.kendrick_mass(*mass).value;
+                (
+                    index,
+                    kendrick_mass.round(),
+                    kendrick_mass.round() - kendrick_mass,
+                )
+            })
+            .sorted_by(|a, b| a.2.total_cmp(&b.2))
+            .collect_vec();
+
+        // Group all masses with the same defect
+        let mut groups: Vec<Vec<(usize, f64, f64)>> = Vec::new();
+        for point in points {
+            match groups.last_mut() {
+                Some(group) if point.2 - group[0].2 <= tolerance.value => group.push(point),
+                _ => groups.push(vec![point]),
+            }
+        }
+
+        // Split every group in series with consecutive numbers of repeating units
+        let mut result = Vec::new();
+        for group in groups {
+            let classes = group
+                .into_iter()
+                .into_group_map_by(|p| p.1.rem_euclid(self.nominal) as i64);
+            for mut members in classes.into_values() {
+                members.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let mut series: Vec<usize> = Vec::new();
+                let mut last = None;
+                for member in members {
+                    if last.is_some_and(|last: f64| member.1 - last > self.nominal) {
+                        if series.len() >= min_length {
+                            result.push(series.clone());
+                        }
+                        series.clear();
+                    }
+                    series.push(member.0);
+                    last = Some(member.1);
+                }
+                if series.len() >= min_length {
+                    result.push(series);
+                }
+            }
+        }
+        result.sort();
+        result
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc, clippy::float_cmp)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defect() {
+        let scale = KendrickScale::ch2();
+        assert_eq!(scale.nominal_unit(), 14.0);
+        assert!((scale.kendrick_mass(scale.unit()).value - 14.0).abs() < 1e-9);
+        assert!(
+            scale
+                .kendrick_mass_defect(da(scale.unit().value * 10.0))
+                .value
+                .abs()
+                < 1e-9
+        );
+        let hexnac = KendrickScale::hexnac();
+        assert_eq!(hexnac.nominal_unit(), 203.0);
+        let base = 500.123;
+        let a = hexnac.kendrick_mass_defect(da(base));
+        let b = hexnac.kendrick_mass_defect(da(3.0f64.mul_add(hexnac.unit().value, base)));
+        assert!((a - b).value.abs() < 1e-9);
+    }
+
+    #[test]
+    fn series() {
+        let scale = KendrickScale::hexose();
+        let hex = scale.unit().value;
+        let base = 892.317; // HexNAc2Hex3
+        let masses = [
+            da(base),
+            da(base + hex),
+            da(2.0f64.mul_add(hex, base)),
+            da(1000.0),
+            da(4.0f64.mul_add(hex, base)),
+            da(5.0f64.mul_add(hex, base)),
+            da(6.0f64.mul_add(hex, base)),
+            da(1001.0),
+        ];
+        assert_eq!(
+            scale.series(&masses, da(0.001), 3),
+            vec![vec![0, 1, 2], vec![4, 5, 6]]
+        );
+        assert_eq!(
+            scale.series(&masses, da(0.001), 4),
+            Vec::<Vec<usize>>::new()
+        );
+        assert_eq!(scale.series(&masses, da(0.001), 1).len(), 4);
+    }
+}
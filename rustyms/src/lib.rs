@@ -15,10 +15,18 @@
 /// Only available with feature `align`.
 pub mod align;
 
+#[cfg(any(test, feature = "golden"))]
+/// Only available with feature `golden`.
+pub mod golden;
+
 #[cfg(feature = "identification")]
 /// Only available with feature `identification`.
 pub mod identification;
 
+#[cfg(feature = "identification")]
+/// Only available with feature `identification`.
+mod facade;
+
 #[cfg(feature = "imgt")]
 /// Only available with feature `imgt`.
 pub mod imgt;
@@ -41,10 +49,14 @@ mod element;
 pub mod error;
 pub mod fragment;
 pub mod glycan;
+mod hydrophobicity;
 mod isobaric_sets;
+mod isoelectric_point;
 #[cfg(feature = "isotopes")]
 /// Only available with feature `isotopes`.
 mod isotopes;
+mod kendrick;
+mod limits;
 mod mass_mode;
 pub mod model;
 pub mod modification;
@@ -53,15 +65,23 @@ mod molecular_charge;
 mod multi;
 mod mzpaf;
 mod neutral_loss;
+#[path = "shared/obo.rs"]
+mod obo;
 pub mod ontologies;
+mod ontology_modification;
 pub mod peptidoform;
 pub mod placement_rule;
+mod precision;
+pub mod prelude;
 mod protease;
 #[cfg(feature = "rand")]
 /// Only available with features `rand`.
 mod rand;
 pub mod rawfile;
+mod retention_time;
+pub mod row_sink;
 mod sequence_element;
+mod sequence_logo;
 #[path = "shared/sequence_position.rs"]
 mod sequence_position;
 pub mod spectrum;
@@ -70,16 +90,26 @@ mod tolerance;
 
 pub use crate::element::*;
 pub use crate::formula::*;
-pub use crate::isobaric_sets::{building_blocks, find_isobaric_sets};
+pub use crate::isobaric_sets::{
+    building_blocks, find_isobaric_sets, ElementConstraints, IsobaricSetSettings,
+};
+#[cfg(feature = "rayon")]
+pub use crate::isobaric_sets::{par_find_isobaric_sets, ParallelIsobaricSetIterator};
+pub use crate::isoelectric_point::*;
+pub use crate::kendrick::*;
+pub use crate::limits::*;
 pub use crate::mass_mode::MassMode;
 pub use crate::model::Model;
 pub use crate::modification::{CrossLinkName, Modification};
-pub use crate::molecular_charge::MolecularCharge;
+pub use crate::molecular_charge::{Adduct, MolecularCharge};
 pub use crate::multi::*;
 pub use crate::neutral_loss::*;
 pub use crate::peptidoform::*;
+pub use crate::precision::*;
 pub use crate::protease::*;
+pub use crate::retention_time::*;
 pub use crate::sequence_element::SequenceElement;
+pub use crate::sequence_logo::*;
 pub use crate::sequence_position::*;
 pub use crate::spectrum::{AnnotatableSpectrum, AnnotatedSpectrum, RawSpectrum};
 pub use crate::tolerance::*;
@@ -0,0 +1,260 @@
+//! Limits to guard against combinatorial explosions when handling untrusted input
+
+use serde::{Deserialize, Serialize};
+
+/// The limits on the combinatorial complexity of peptidoforms. Some ProForma definitions, for
+/// example many ambiguous amino acids or many modifications of unknown position, result in an
+/// exponential number of formulas and fragments. Checking a peptidoform against these limits
+/// before calculating its formulas or fragments allows servers that accept untrusted ProForma to
+/// return an error instead of exhausting memory. The limits are checked by
+/// [`CompoundPeptidoformIon::check_complexity`](crate::CompoundPeptidoformIon::check_complexity),
+/// [`CompoundPeptidoformIon::try_formulas`](crate::CompoundPeptidoformIon::try_formulas), and
+/// [`CompoundPeptidoformIon::try_generate_theoretical_fragments`](crate::CompoundPeptidoformIon::try_generate_theoretical_fragments).
+/// ProForma can be parsed and checked in one go with
+/// [`CompoundPeptidoformIon::pro_forma_with_limits`](crate::CompoundPeptidoformIon::pro_forma_with_limits).
+///
+/// ```rust
+/// # use rustyms::*;
+/// let limits = ComplexityLimits::default().formulas(100);
+/// let peptide = CompoundPeptidoformIon::pro_forma("BBBBBBBBBB", None).unwrap();
+/// let error = peptide.check_complexity(&limits).unwrap_err();
+/// assert_eq!(error.kind, ComplexityKind::Formulas);
+/// assert_eq!(error.count, 1024);
+/// // Only checked when the limits are given
+/// assert!(CompoundPeptidoformIon::pro_forma(&"B".repeat(20), None).is_ok());
+/// assert!(CompoundPeptidoformIon::pro_forma_with_limits(
+///     &"B".repeat(20),
+///     None,
+///     &ComplexityLimits::default()
+/// )
+/// .is_err());
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ComplexityLimits {
+    /// The maximal number of formulas for a single compound peptidoform.
+    ///
+    /// Default: 100 000.
+    pub formulas: usize,
+    /// The maximal number of ways the modifications of unknown position of a single peptidoform
+    /// can be placed.
+    ///
+    /// Default: 100 000.
+    pub ambiguous_combinations: usize,
+    /// The maximal number of theoretical fragments for a single compound peptidoform.
+    ///
+    /// Default: 10 000 000.
+    pub fragments: usize,
+}
+
+impl Default for ComplexityLimits {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ComplexityLimits {
+    /// Create the default limits, see the documentation on the fields for the defaults
+    pub const fn new() -> Self {
+        Self {
+            formulas: 100_000,
+            ambiguous_combinations: 100_000,
+            fragments: 10_000_000,
+        }
+    }
+
+    /// No limits at all
+    pub const UNLIMITED: Self = Self {
+        formulas: usize::MAX,
+        ambiguous_combinations: usize::MAX,
+        fragments: usize::MAX,
+    };
+
+    /// Set the maximal number of formulas
+    #[must_use]
+    pub const fn formulas(self, formulas: usize) -> Self {
+        Self { formulas, ..self }
+    }
+
+    /// Set the maximal number of combinations of modifications of unknown position
+    #[must_use]
+    pub const fn ambiguous_combinations(self, ambiguous_combinations: usize) -> Self {
+        Self {
+            ambiguous_combinations,
+            ..self
+        }
+    }
+
+    /// Set the maximal number of theoretical fragments
+    #[must_use]
+    pub const fn fragments(self, fragments: usize) -> Self {
+        Self { fragments, ..self }
+    }
+
+    /// Check the given count against the limit for the given kind of complexity
+    /// # Errors
+    /// If the count is higher than the limit.
+    pub const fn check(
+        &self,
+        kind: ComplexityKind,
+        count: usize,
+    ) -> Result<(), ComplexityExceeded> {
+        let limit = match kind {
+            ComplexityKind::Formulas => self.formulas,
+            ComplexityKind::AmbiguousCombinations => self.ambiguous_combinations,
+            ComplexityKind::Fragments => self.fragments,
+        };
+        if count > limit {
+            Err(ComplexityExceeded { kind, count, limit })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// The kinds of complexity that are limited, see [`ComplexityLimits`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum ComplexityKind {
+    /// The number of formulas
+    Formulas,
+    /// The number of combinations of modifications of unknown position
+    AmbiguousCombinations,
+    /// The number of theoretical fragments
+    Fragments,
+}
+
+impl std::fmt::Display for ComplexityKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Formulas => "formulas",
+                Self::AmbiguousCombinations => "combinations of modifications of unknown position",
+                Self::Fragments => "fragments",
+            }
+        )
+    }
+}
+
+/// The error returned when a [`ComplexityLimits`] limit is exceeded. The count is saturated at
+/// [`usize::MAX`] for very complex peptidoforms.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ComplexityExceeded {
+    /// The kind of complexity that exceeded its limit
+    pub kind: ComplexityKind,
+    /// The (estimated) count
+    pub count: usize,
+    /// The limit
+    pub limit: usize,
+}
+
+impl std::fmt::Display for ComplexityExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Complexity exceeded: {} {} where at most {} are allowed",
+            self.count, self.kind, self.limit
+        )
+    }
+}
+
+impl std::error::Error for ComplexityExceeded {}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        system::e, system::usize::Charge, AminoAcid, CompoundPeptidoformIon, Model, Peptidoform,
+        SemiAmbiguous,
+    };
+
+    #[test]
+    fn limits() {
+        let peptide = CompoundPeptidoformIon::pro_forma("BZEPTIDE", None).unwrap();
+        assert!(peptide.check_complexity(&ComplexityLimits::new()).is_ok());
+        assert_eq!(
+            peptide.try_formulas(&ComplexityLimits::new()).unwrap(),
+            peptide.formulas()
+        );
+        assert_eq!(
+            peptide.check_complexity(&ComplexityLimits::new().formulas(3)),
+            Err(ComplexityExceeded {
+                kind: ComplexityKind::Formulas,
+                count: 4,
+                limit: 3
+            })
+        );
+        let ambiguous = CompoundPeptidoformIon::pro_forma("[Phospho]^2?SSSSMMMM", None).unwrap();
+        let error = ambiguous
+            .check_complexity(&ComplexityLimits::new().ambiguous_combinations(10))
+            .unwrap_err();
+        assert_eq!(error.kind, ComplexityKind::AmbiguousCombinations);
+        assert!(error.count > 10);
+        let error = peptide
+            .try_generate_theoretical_fragments(
+                Charge::new::<e>(2),
+                &Model::all(),
+                &ComplexityLimits::new().fragments(10),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind, ComplexityKind::Fragments);
+        assert_eq!(error.count, 64); // Estimated before generation
+        assert!(error.to_string().starts_with("Complexity exceeded"));
+        let error = peptide
+            .try_generate_theoretical_fragments(
+                Charge::new::<e>(2),
+                &Model::all(),
+                &ComplexityLimits::new().fragments(100),
+            )
+            .unwrap_err();
+        assert_eq!(error.kind, ComplexityKind::Fragments);
+        assert!(error.count > 100);
+    }
+
+    #[test]
+    fn unchecked_parse() {
+        let text = "B".repeat(20);
+        assert!(CompoundPeptidoformIon::pro_forma(&text, None).is_ok());
+        assert!(
+            CompoundPeptidoformIon::pro_forma_with_limits(&text, None, &ComplexityLimits::new())
+                .is_err()
+        );
+        assert!(CompoundPeptidoformIon::pro_forma_with_limits(
+            &text,
+            None,
+            &ComplexityLimits::UNLIMITED
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn cleavable_cross_link() {
+        let peptide = CompoundPeptidoformIon::pro_forma(
+            "AK[C:DSSO#XL1]A//AK[#XL1]A",
+            Some(&crate::fragmentation_tests::custom_database()),
+        )
+        .unwrap();
+        let error = peptide
+            .check_complexity(&ComplexityLimits::new().formulas(1))
+            .unwrap_err();
+        assert_eq!(error.kind, ComplexityKind::Formulas);
+        assert!(error.count > 1);
+    }
+
+    #[test]
+    fn saturating() {
+        let peptide = CompoundPeptidoformIon::from(
+            std::iter::repeat(AminoAcid::AmbiguousAsparagine)
+                .take(200)
+                .collect::<Peptidoform<SemiAmbiguous>>(),
+        );
+        let error = peptide
+            .check_complexity(&ComplexityLimits::new())
+            .unwrap_err();
+        assert_eq!(error.count, usize::MAX);
+        assert!(peptide
+            .check_complexity(&ComplexityLimits::UNLIMITED)
+            .is_ok());
+    }
+}
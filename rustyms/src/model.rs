@@ -1,13 +1,20 @@
 //! Handle model instantiation.
 
-use std::ops::RangeInclusive;
+use std::{
+    io::{BufWriter, Read, Write},
+    ops::RangeInclusive,
+    path::Path,
+};
 
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    fragment::PeptidePosition,
-    system::{e, f64::MassOverCharge, isize::Charge, mz},
-    NeutralLoss, Tolerance,
+    error::{Context, CustomError},
+    fragment::{FragmentKind, PeptidePosition},
+    glycan::MonoSaccharide,
+    helper_functions::{create_file, open_reader},
+    system::{e, f64::MassOverCharge, isize::Charge, mz, Mass},
+    AminoAcid, Fragment, NeutralLoss, Peptidoform, SequencePosition, Tolerance,
 };
 
 /// Control what charges are allowed for an ion series. Defined as an inclusive range.
@@ -54,6 +61,11 @@ impl ChargeRange {
         start: ChargePoint::Absolute(1),
         end: ChargePoint::Relative(0),
     };
+    /// Range from 1 to one below the precursor, all charges of charge reduced precursors
+    pub const CHARGE_REDUCED: Self = Self {
+        start: ChargePoint::Absolute(1),
+        end: ChargePoint::Relative(-1),
+    };
 }
 
 /// A reference point for charge range definition.
@@ -75,8 +87,21 @@ impl ChargePoint {
     }
 }
 /// A model for the fragmentation, allowing control over what theoretical fragments to generate.
+///
+/// A model can be stored with the results or shared between applications as JSON, see
+/// [`Self::save`] and [`Self::load`]. Because the model implements [`Serialize`] and
+/// [`Deserialize`] any other serde format (for example TOML) can be used as well. When reading a
+/// model all missing settings are taken from [`Self::none`], so a handwritten file only needs to
+/// contain the settings that are used:
+/// ```rust
+/// # use rustyms::{*, model::*};
+/// let json = r#"{"y": {"location": "All"}, "precursor": [[], {"start": {"Relative": 0}, "end": {"Relative": 0}}]}"#;
+/// let model = Model::read(json.as_bytes()).unwrap();
+/// assert_eq!(model, Model::none().y(PrimaryIonSeries::default()));
+/// ```
 #[non_exhaustive]
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(default = "Model::none")]
 #[allow(clippy::struct_excessive_bools)]
 pub struct Model {
     /// a series ions
@@ -97,12 +122,20 @@ pub struct Model {
     pub y: PrimaryIonSeries,
     /// z series ions
     pub z: PrimaryIonSeries,
+    /// Rules that suppress or force the generation of the primary ion series based on the amino
+    /// acids around the fragmented bond, see [`FragmentationRule`]
+    pub fragmentation_rules: Vec<FragmentationRule>,
     /// precursor ions
     pub precursor: (Vec<NeutralLoss>, ChargeRange),
-    /// immonium ions
-    pub immonium: (bool, ChargeRange),
+    /// immonium ions and their related ions
+    pub immonium: ImmoniumModel,
     /// m ions, loss of the amino acid side chain from the precursor (follows precursor charge)
     pub m: bool,
+    /// Charge reduced precursors `(M+nH)^(n-k)+•`, the precursor after capturing electrons
+    /// without dissociating, as seen in ETD and ECD spectra. The charge range gives the charges of
+    /// the charge reduced species, only charges below the precursor charge are generated. These
+    /// get the same neutral losses as the precursor.
+    pub charge_reduced_precursor: (bool, ChargeRange),
     /// If the neutral losses specific for modifications should be generated
     pub modification_specific_neutral_losses: bool,
     /// If the diagnostic ions specific for modifications should be generated with the allowed charge range
@@ -115,15 +148,105 @@ pub struct Model {
     pub tolerance: Tolerance<MassOverCharge>,
     /// The range in which fragments fall, can be used to limit the theoretical fragments to a known window
     pub mz_range: RangeInclusive<MassOverCharge>,
+    /// The maximal total mass of all neutral losses on a single fragment, fragments with a higher
+    /// total loss are not generated. Gains count as negative losses. If `None` there is no limit.
+    pub max_neutral_loss_mass: Option<Mass>,
+    /// The isotope peaks that are generated for every fragment, given as the highest isotope
+    /// (so 2 generates the +1 and +2 isotope peaks) and the minimal probability of an isotope
+    /// peak in the isotopic distribution of the fragment. With the highest isotope set to 0 only
+    /// the monoisotopic peaks are generated. See [`Fragment::with_isotopes`].
+    ///
+    /// Only available with crate feature 'isotopes'.
+    #[cfg(feature = "isotopes")]
+    pub isotope_peaks: (usize, f64),
+}
+
+/// Reading and writing models
+impl Model {
+    /// Load a model from a JSON file, see [`Self::save`]. Gzipped files (with the extension `gz`)
+    /// are uncompressed automatically.
+    ///
+    /// # Format
+    /// The file contains a single JSON object with the fields of [`Model`] as keys, any missing
+    /// key is taken from [`Self::none`]. The values are the serde representations of the types:
+    /// * A [`PrimaryIonSeries`] (`a` to `z`) is an object with a `location` (`"All"`, `"None"`,
+    ///   `{"SkipN": 1}`, `{"TakeN": {"skip": 0, "take": 1}}` etc, see [`Location`]),
+    ///   `neutral_losses`, `max_neutral_losses`, and `charge_range`.
+    /// * A [`ChargeRange`] is an object with a `start` and `end` that are either
+    ///   `{"Absolute": 1}` or `{"Relative": 0}` (relative to the precursor charge).
+    /// * A [`NeutralLoss`] is `{"Loss": formula}` or `{"Gain": formula}` where the formula is
+    ///   `{"elements": [["H", null, 2], ["O", null, 1]], "additional_mass": 0.0, "labels": []}`,
+    ///   the elements being the element, isotope (or `null`), and count.
+    /// * Tuples, like `precursor` and `charge_reduced_precursor`, are arrays.
+    /// * The `tolerance` is `{"Relative": 0.00002}` (a fraction, so 20 ppm) or `{"Absolute": 0.02}`
+    ///   (in m/z), the `mz_range` is `{"start": 0.0, "end": 2000.0}` (in m/z), and the
+    ///   `max_neutral_loss_mass` is a mass in Dalton or `null`.
+    ///
+    /// The easiest way to get a starting point is to save one of the built-in models, for example
+    /// [`Self::ethcd`], with [`Self::save`].
+    /// # Errors
+    /// If the file could not be opened or if it does not contain a valid model.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        Self::read(open_reader(path)?)
+    }
+
+    /// Read a model in JSON format from a reader, see [`Self::load`].
+    /// # Errors
+    /// If the reader could not be read or if it does not contain a valid model.
+    pub fn read(reader: impl Read) -> Result<Self, CustomError> {
+        serde_json::from_reader(reader).map_err(|error| {
+            CustomError::error(
+                "Invalid fragmentation model",
+                format!("The fragmentation model could not be read: {error}"),
+                Context::none(),
+            )
+        })
+    }
+
+    /// Save this model as a JSON file, it can be loaded again with [`Self::load`].
+    /// # Errors
+    /// If the file could not be created or written.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), CustomError> {
+        let file = create_file(path.as_ref()).map_err(|error| {
+            CustomError::error(
+                "Could not create file",
+                error,
+                Context::show(path.as_ref().to_string_lossy()),
+            )
+        })?;
+        self.write(BufWriter::new(file))
+    }
+
+    /// Write this model in JSON format to a writer, see [`Self::save`].
+    /// # Errors
+    /// If the writer could not be written to.
+    pub fn write(&self, mut writer: impl Write) -> Result<(), CustomError> {
+        serde_json::to_writer_pretty(&mut writer, self)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.flush())
+            .map_err(|error| {
+                CustomError::error(
+                    "Could not write fragmentation model",
+                    error,
+                    Context::none(),
+                )
+            })
+    }
 }
 
 /// The settings for any primary ion series
 #[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PrimaryIonSeries {
     /// Which locations are assumed to lead to fragmentation
     pub location: Location,
     /// The allowed neutral losses
     pub neutral_losses: Vec<NeutralLoss>,
+    /// The maximal number of neutral losses that are combined on a single fragment, the same loss
+    /// can be applied multiple times. So with water and ammonia loss and a maximum of 2 the
+    /// fragments with -H2O, -NH3, -2H2O, -H2O-NH3, and -2NH3 are all generated. Use
+    /// [`Model::max_neutral_loss_mass`] to limit the total mass that can be lost.
+    pub max_neutral_losses: usize,
     /// The allowed charges
     pub charge_range: ChargeRange,
 }
@@ -142,6 +265,14 @@ impl PrimaryIonSeries {
             ..self
         }
     }
+    /// Replace the maximal number of combined neutral losses, see [`Self::max_neutral_losses`].
+    #[must_use]
+    pub fn max_neutral_losses(self, max_neutral_losses: usize) -> Self {
+        Self {
+            max_neutral_losses,
+            ..self
+        }
+    }
     /// Replace the charge range
     #[must_use]
     pub fn charge_range(self, charge_range: ChargeRange) -> Self {
@@ -157,6 +288,7 @@ impl std::default::Default for PrimaryIonSeries {
         Self {
             location: Location::All,
             neutral_losses: Vec::new(),
+            max_neutral_losses: 1,
             charge_range: ChargeRange::ONE_TO_PRECURSOR,
         }
     }
@@ -172,10 +304,25 @@ pub struct GlycanModel {
     pub compositional_range: RangeInclusive<usize>,
     /// The allowed neutral losses
     pub neutral_losses: Vec<NeutralLoss>,
-    /// The allowed charges for oxonium ions (B, internal fragments etc)
+    /// The allowed charges for oxonium ions (B, A, internal fragments etc)
     pub oxonium_charge_range: ChargeRange,
-    /// The allowed charges for other glycan fragments (Y)
+    /// The allowed charges for other glycan fragments (Y, X)
     pub other_charge_range: ChargeRange,
+    /// For glycans where only the mass is known (i.e. GNOme weight entries) all compositions of
+    /// common monosaccharides that fit the mass are determined, if at most this number of
+    /// compositions fit the compositional fragments for all of these are generated. Set to 0 to
+    /// disable this fallback.
+    pub mass_only_compositions: usize,
+    /// The cross-ring cleavages (A and X fragments) that are generated in glycans with defined
+    /// structures, given per monosaccharide as the two broken ring bonds (e.g. `(0, 2)` for
+    /// <sup>0,2</sup>A and <sup>0,2</sup>X). The monosaccharides are matched on base sugar,
+    /// substituents, and ring size, the name is ignored and a base sugar without an isomer (like
+    /// in HexNAc) matches all isomers of that base sugar. The bonds are numbered according to
+    /// Domon and Costello, bond 0 is between the ring oxygen and the anomeric carbon. Cleavages
+    /// that do not fit in the ring of a monosaccharide are skipped. As the linkage positions are not
+    /// stored the branches of the cleaved monosaccharide can be on either side, so fragments are
+    /// generated for every distribution of the branches over the A and X fragment.
+    pub cross_ring_cleavages: Vec<(MonoSaccharide, (u8, u8))>,
 }
 
 impl GlycanModel {
@@ -219,6 +366,25 @@ impl GlycanModel {
             ..self
         }
     }
+    /// Set the maximal number of compositions for glycans where only the mass is known, see [`Self::mass_only_compositions`].
+    #[must_use]
+    pub fn mass_only_compositions(self, mass_only_compositions: usize) -> Self {
+        Self {
+            mass_only_compositions,
+            ..self
+        }
+    }
+    /// Replace the cross-ring cleavages, see [`Self::cross_ring_cleavages`].
+    #[must_use]
+    pub fn cross_ring_cleavages(
+        self,
+        cross_ring_cleavages: Vec<(MonoSaccharide, (u8, u8))>,
+    ) -> Self {
+        Self {
+            cross_ring_cleavages,
+            ..self
+        }
+    }
     /// Default set for models that allow glycan fragmentation
     pub const ALLOW: Self = Self {
         allow_structural: true,
@@ -226,6 +392,8 @@ impl GlycanModel {
         neutral_losses: Vec::new(),
         oxonium_charge_range: ChargeRange::ONE,
         other_charge_range: ChargeRange::ONE_TO_PRECURSOR,
+        mass_only_compositions: 3,
+        cross_ring_cleavages: Vec::new(),
     };
     /// Default set for models that disallow glycan fragmentation
     pub const DISALLOW: Self = Self {
@@ -234,36 +402,206 @@ impl GlycanModel {
         neutral_losses: Vec::new(),
         oxonium_charge_range: ChargeRange::ONE,
         other_charge_range: ChargeRange::ONE_TO_PRECURSOR,
+        mass_only_compositions: 0,
+        cross_ring_cleavages: Vec::new(),
+    };
+}
+
+/// The settings for immonium ions and the related ions (common neutral losses from the immonium
+/// ions). Immonium ions are generated including the modifications on the residue, so for example
+/// phospho tyrosine results in an immonium ion at 216.04 instead of 136.08.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub struct ImmoniumModel {
+    /// The amino acids that generate immonium ions, `None` means all amino acids
+    pub amino_acids: Option<Vec<AminoAcid>>,
+    /// If the known related ions for each amino acid are generated
+    pub related_ions: bool,
+    /// Additional related ions for specific amino acids, given as a neutral loss or gain from the
+    /// immonium ion of that amino acid
+    pub custom_related_ions: Vec<(AminoAcid, NeutralLoss)>,
+    /// The allowed charges
+    pub charge_range: ChargeRange,
+}
+
+impl ImmoniumModel {
+    /// Set the amino acids that generate immonium ions, see [`Self::amino_acids`].
+    #[must_use]
+    pub fn amino_acids(self, amino_acids: Option<Vec<AminoAcid>>) -> Self {
+        Self {
+            amino_acids,
+            ..self
+        }
+    }
+    /// Set if the known related ions are generated
+    #[must_use]
+    pub fn related_ions(self, related_ions: bool) -> Self {
+        Self {
+            related_ions,
+            ..self
+        }
+    }
+    /// Replace the additional related ions, see [`Self::custom_related_ions`].
+    #[must_use]
+    pub fn custom_related_ions(self, custom_related_ions: Vec<(AminoAcid, NeutralLoss)>) -> Self {
+        Self {
+            custom_related_ions,
+            ..self
+        }
+    }
+    /// Replace the charge range
+    #[must_use]
+    pub fn charge_range(self, charge_range: ChargeRange) -> Self {
+        Self {
+            charge_range,
+            ..self
+        }
+    }
+    /// Check if immonium ions are generated for the given amino acid
+    pub fn allows(&self, amino_acid: AminoAcid) -> bool {
+        self.amino_acids
+            .as_ref()
+            .map_or(true, |amino_acids| amino_acids.contains(&amino_acid))
+    }
+    /// Get all neutral losses and gains from the immonium ion that give the related ions for the
+    /// given amino acid
+    pub fn related_ion_losses(&self, amino_acid: AminoAcid) -> Vec<NeutralLoss> {
+        let mut losses = if self.related_ions {
+            amino_acid.immonium_losses()
+        } else {
+            Vec::new()
+        };
+        losses.extend(
+            self.custom_related_ions
+                .iter()
+                .filter(|(aa, _)| *aa == amino_acid)
+                .map(|(_, loss)| loss.clone()),
+        );
+        losses
+    }
+    /// Default set for models that generate immonium ions for all amino acids with their known
+    /// related ions
+    pub const ALLOW: Self = Self {
+        amino_acids: None,
+        related_ions: true,
+        custom_related_ions: Vec::new(),
+        charge_range: ChargeRange::ONE,
+    };
+    /// Default set for models that do not generate immonium ions
+    pub const DISALLOW: Self = Self {
+        amino_acids: Some(Vec::new()),
+        related_ions: false,
+        custom_related_ions: Vec::new(),
+        charge_range: ChargeRange::ONE,
     };
 }
 
-/// A struct to handle all possible fragments that could be generated on a single location
+/// A rule to change the generation of primary ion series (a, b, c, d, v, w, x, y, and z) based
+/// on the amino acids on both sides of the fragmented peptide bond. For N terminal ions this is
+/// the bond after the last residue of the fragment, for C terminal ions this is the bond before
+/// the first residue of the fragment. Rules are applied on top of the [`Location`] of each ion
+/// series and the last matching rule in [`Model::fragmentation_rules`] wins. As no intensities are
+/// predicted, enhancing a cleavage means that the ions are generated even if the location of the
+/// ion series would not allow it. Bonds to the peptide termini never match.
+/// ```rust
+/// # use rustyms::{*, fragment::FragmentKind, model::*};
+/// // Suppress b and y ions N terminal to proline, but always generate them C terminal to aspartic acid
+/// let model = Model::none()
+///     .b(PrimaryIonSeries::default())
+///     .y(PrimaryIonSeries::default())
+///     .fragmentation_rules(vec![
+///         FragmentationRule::suppress(vec![FragmentKind::b, FragmentKind::y])
+///             .c_terminal(vec![AminoAcid::Proline]),
+///         FragmentationRule::enhance(vec![FragmentKind::b, FragmentKind::y])
+///             .n_terminal(vec![AminoAcid::AsparticAcid]),
+///     ]);
+/// ```
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
+pub struct FragmentationRule {
+    /// The ion series this rule applies to
+    pub ions: Vec<FragmentKind>,
+    /// The amino acids that match N terminal of the bond, empty matches any amino acid
+    pub n_terminal: Vec<AminoAcid>,
+    /// The amino acids that match C terminal of the bond, empty matches any amino acid
+    pub c_terminal: Vec<AminoAcid>,
+    /// If the ions are generated (`true`) or suppressed (`false`) on matching bonds
+    pub allow: bool,
+}
+
+impl FragmentationRule {
+    /// A rule that suppresses the given ion series on all bonds, restrict it with
+    /// [`Self::n_terminal`] and [`Self::c_terminal`].
+    pub const fn suppress(ions: Vec<FragmentKind>) -> Self {
+        Self {
+            ions,
+            n_terminal: Vec::new(),
+            c_terminal: Vec::new(),
+            allow: false,
+        }
+    }
+    /// A rule that generates the given ion series on all bonds, restrict it with
+    /// [`Self::n_terminal`] and [`Self::c_terminal`].
+    pub fn enhance(ions: Vec<FragmentKind>) -> Self {
+        Self {
+            allow: true,
+            ..Self::suppress(ions)
+        }
+    }
+    /// Replace the amino acids N terminal of the bond
+    #[must_use]
+    pub fn n_terminal(self, n_terminal: Vec<AminoAcid>) -> Self {
+        Self { n_terminal, ..self }
+    }
+    /// Replace the amino acids C terminal of the bond
+    #[must_use]
+    pub fn c_terminal(self, c_terminal: Vec<AminoAcid>) -> Self {
+        Self { c_terminal, ..self }
+    }
+    /// Check if this rule applies to the given ion series on a bond between the two given amino
+    /// acids, a `None` indicates a peptide terminus.
+    pub fn matches(
+        &self,
+        kind: FragmentKind,
+        n_terminal: Option<AminoAcid>,
+        c_terminal: Option<AminoAcid>,
+    ) -> bool {
+        let side = |options: &[AminoAcid], aa: Option<AminoAcid>| {
+            aa.is_some_and(|aa| options.is_empty() || options.contains(&aa))
+        };
+        self.ions.contains(&kind)
+            && side(&self.n_terminal, n_terminal)
+            && side(&self.c_terminal, c_terminal)
+    }
+}
+
+/// A struct to handle all possible fragments that could be generated on a single location. Every
+/// ion series is given as: if it is possible, the neutral losses, the charge range, and the maximal
+/// number of combined neutral losses.
 #[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Hash)]
 #[non_exhaustive]
 pub struct PossibleIons<'a> {
     /// a series ions
-    pub a: (bool, &'a [NeutralLoss], ChargeRange),
+    pub a: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// b series ions
-    pub b: (bool, &'a [NeutralLoss], ChargeRange),
+    pub b: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// c series ions
-    pub c: (bool, &'a [NeutralLoss], ChargeRange),
+    pub c: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// d series ions (side chain fragmentation from a)
-    pub d: (bool, &'a [NeutralLoss], ChargeRange),
+    pub d: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// v series ions (full side chain broken off)
-    pub v: (bool, &'a [NeutralLoss], ChargeRange),
+    pub v: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// w series ions (side chain fragmentation from z)
-    pub w: (bool, &'a [NeutralLoss], ChargeRange),
+    pub w: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// x series ions
-    pub x: (bool, &'a [NeutralLoss], ChargeRange),
+    pub x: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// y series ions
-    pub y: (bool, &'a [NeutralLoss], ChargeRange),
+    pub y: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// z series ions
-    pub z: (bool, &'a [NeutralLoss], ChargeRange),
+    pub z: (bool, &'a [NeutralLoss], ChargeRange, usize),
     /// precursor ions
     pub precursor: (&'a [NeutralLoss], ChargeRange),
     /// immonium
-    pub immonium: (bool, ChargeRange),
+    pub immonium: &'a ImmoniumModel,
 }
 
 impl PossibleIons<'_> {
@@ -330,6 +668,14 @@ impl Model {
     pub fn z(self, z: PrimaryIonSeries) -> Self {
         Self { z, ..self }
     }
+    /// Set the fragmentation rules, see [`FragmentationRule`]
+    #[must_use]
+    pub fn fragmentation_rules(self, fragmentation_rules: Vec<FragmentationRule>) -> Self {
+        Self {
+            fragmentation_rules,
+            ..self
+        }
+    }
     /// Set glycan
     #[must_use]
     pub fn glycan(self, glycan: GlycanModel) -> Self {
@@ -345,17 +691,22 @@ impl Model {
     }
     /// Set immonium
     #[must_use]
-    pub fn immonium(self, state: (bool, ChargeRange)) -> Self {
-        Self {
-            immonium: state,
-            ..self
-        }
+    pub fn immonium(self, immonium: ImmoniumModel) -> Self {
+        Self { immonium, ..self }
     }
     /// Set m
     #[must_use]
     pub fn m(self, state: bool) -> Self {
         Self { m: state, ..self }
     }
+    /// Set charge reduced precursors
+    #[must_use]
+    pub fn charge_reduced_precursor(self, state: (bool, ChargeRange)) -> Self {
+        Self {
+            charge_reduced_precursor: state,
+            ..self
+        }
+    }
     /// Set modification specific neutral losses
     #[must_use]
     pub fn modification_specific_neutral_losses(self, state: bool) -> Self {
@@ -393,101 +744,163 @@ impl Model {
     pub fn mz_range(self, mz_range: RangeInclusive<MassOverCharge>) -> Self {
         Self { mz_range, ..self }
     }
+    /// Set the maximal total neutral loss mass per fragment
+    #[must_use]
+    pub fn max_neutral_loss_mass(self, max_neutral_loss_mass: Option<Mass>) -> Self {
+        Self {
+            max_neutral_loss_mass,
+            ..self
+        }
+    }
+    /// Set the isotope peaks, see [`Self::isotope_peaks`].
+    ///
+    /// Only available with crate feature 'isotopes'.
+    #[cfg(feature = "isotopes")]
+    #[must_use]
+    pub fn isotope_peaks(self, isotope_peaks: (usize, f64)) -> Self {
+        Self {
+            isotope_peaks,
+            ..self
+        }
+    }
 }
 
 impl Model {
-    /// Give all possible ions for the given N position
-    pub fn ions(&self, position: PeptidePosition) -> PossibleIons {
+    /// Check if the total neutral loss of the given fragment is within the limit of this model,
+    /// see [`Self::max_neutral_loss_mass`].
+    pub fn allows_neutral_loss(&self, fragment: &Fragment) -> bool {
+        self.max_neutral_loss_mass.map_or(true, |max| {
+            fragment.neutral_loss.is_empty()
+                || fragment.loss_formula_sum().monoisotopic_mass() <= max
+        })
+    }
+
+    /// Give all possible ions for the given N position in the given peptidoform, this applies
+    /// the [`Self::fragmentation_rules`] on the bonds around this position.
+    pub fn ions<Complexity>(
+        &self,
+        position: PeptidePosition,
+        peptidoform: &Peptidoform<Complexity>,
+    ) -> PossibleIons<'_> {
         let c_position = position.flip_terminal();
+        let residue = |index: Option<usize>| {
+            index
+                .and_then(|i| peptidoform.sequence().get(i))
+                .map(|element| element.aminoacid.aminoacid())
+        };
+        let index = match position.sequence_index {
+            SequencePosition::Index(index) => Some(index),
+            SequencePosition::NTerm | SequencePosition::CTerm => None,
+        };
+        // N terminal ions break the bond after this residue, C terminal ions the bond before it
+        let n_bond = (residue(index), residue(index.map(|i| i + 1)));
+        let c_bond = (
+            residue(index.and_then(|i| i.checked_sub(1))),
+            residue(index),
+        );
+        let possible =
+            |kind: FragmentKind,
+             location: &Location,
+             position: PeptidePosition,
+             (n_terminal, c_terminal): (Option<AminoAcid>, Option<AminoAcid>)| {
+                self.fragmentation_rules
+                    .iter()
+                    .rev()
+                    .find(|rule| rule.matches(kind, n_terminal, c_terminal))
+                    .map_or_else(|| location.possible(position), |rule| rule.allow)
+            };
         PossibleIons {
             a: (
-                self.a.location.possible(position),
+                possible(FragmentKind::a, &self.a.location, position, n_bond),
                 self.a.neutral_losses.as_slice(),
                 self.a.charge_range,
+                self.a.max_neutral_losses,
             ),
             b: (
-                self.b.location.possible(position),
+                possible(FragmentKind::b, &self.b.location, position, n_bond),
                 self.b.neutral_losses.as_slice(),
                 self.b.charge_range,
+                self.b.max_neutral_losses,
             ),
             c: (
-                self.c.location.possible(position),
+                possible(FragmentKind::c, &self.c.location, position, n_bond),
                 self.c.neutral_losses.as_slice(),
                 self.c.charge_range,
+                self.c.max_neutral_losses,
             ),
             d: (
-                self.d.location.possible(position),
+                possible(FragmentKind::d, &self.d.location, position, n_bond),
                 self.d.neutral_losses.as_slice(),
                 self.d.charge_range,
+                self.d.max_neutral_losses,
             ),
             v: (
-                self.v.location.possible(c_position),
+                possible(FragmentKind::v, &self.v.location, c_position, c_bond),
                 self.v.neutral_losses.as_slice(),
                 self.v.charge_range,
+                self.v.max_neutral_losses,
             ),
             w: (
-                self.w.location.possible(c_position),
+                possible(FragmentKind::w, &self.w.location, c_position, c_bond),
                 self.w.neutral_losses.as_slice(),
                 self.w.charge_range,
+                self.w.max_neutral_losses,
             ),
             x: (
-                self.x.location.possible(c_position),
+                possible(FragmentKind::x, &self.x.location, c_position, c_bond),
                 self.x.neutral_losses.as_slice(),
                 self.x.charge_range,
+                self.x.max_neutral_losses,
             ),
             y: (
-                self.y.location.possible(c_position),
+                possible(FragmentKind::y, &self.y.location, c_position, c_bond),
                 self.y.neutral_losses.as_slice(),
                 self.y.charge_range,
+                self.y.max_neutral_losses,
             ),
             z: (
-                self.z.location.possible(c_position),
+                possible(FragmentKind::z, &self.z.location, c_position, c_bond),
                 self.z.neutral_losses.as_slice(),
                 self.z.charge_range,
+                self.z.max_neutral_losses,
             ),
             precursor: (self.precursor.0.as_slice(), self.precursor.1),
-            immonium: self.immonium,
+            immonium: &self.immonium,
         }
     }
 
-    /// Generate all possible fragments
+    /// Generate all possible fragments, with water loss on all ion series
     pub fn all() -> Self {
-        Self {
-            a: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            b: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            c: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            d: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            v: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            w: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            x: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            y: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            z: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            precursor: (
-                vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))],
-                ChargeRange::PRECURSOR,
-            ),
-            immonium: (true, ChargeRange::ONE),
-            m: true,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::ALLOW
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .c(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .d(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .v(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .w(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .x(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .z(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(vec![water.clone()], ChargeRange::PRECURSOR)
+            .immonium(ImmoniumModel::ALLOW)
+            .m(true)
+            .charge_reduced_precursor((true, ChargeRange::CHARGE_REDUCED))
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
     }
 
-    /// Generate no fragments (except for precursor)
+    /// Generate no fragments (except for precursor). This is the starting point for building
+    /// custom models with the builder methods, for example:
+    /// ```rust
+    /// # use rustyms::{*, model::*};
+    /// let model = Model::none()
+    ///     .b(PrimaryIonSeries::default())
+    ///     .y(PrimaryIonSeries::default().charge_range(ChargeRange::ONE))
+    ///     .tolerance(Tolerance::new_ppm(10.0));
+    /// ```
     pub fn none() -> Self {
         Self {
             a: PrimaryIonSeries::default().location(Location::None),
@@ -499,221 +912,195 @@ impl Model {
             x: PrimaryIonSeries::default().location(Location::None),
             y: PrimaryIonSeries::default().location(Location::None),
             z: PrimaryIonSeries::default().location(Location::None),
+            fragmentation_rules: Vec::new(),
             precursor: (vec![], ChargeRange::PRECURSOR),
-            immonium: (false, ChargeRange::ONE),
+            immonium: ImmoniumModel::DISALLOW,
             m: false,
+            charge_reduced_precursor: (false, ChargeRange::CHARGE_REDUCED),
             modification_specific_neutral_losses: false,
             modification_specific_diagnostic_ions: (false, ChargeRange::ONE),
             glycan: GlycanModel::DISALLOW,
             allow_cross_link_cleavage: false,
             tolerance: Tolerance::new_ppm(20.0),
             mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
+            max_neutral_loss_mass: None,
+            #[cfg(feature = "isotopes")]
+            isotope_peaks: (0, 0.0),
         }
     }
 
-    /// electron-transfer/higher-energy collisional dissociation
+    /// Higher-energy collisional dissociation (beam type CID). Generates b and y ions (up to the
+    /// precursor charge) and singly charged a ions, all with water loss and b and y also with
+    /// ammonia loss. Also generates immonium ions, glycan fragments (oxonium and Y ions), and
+    /// modification specific neutral losses and diagnostic ions.
+    pub fn hcd() -> Self {
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        let ammonia = NeutralLoss::Loss(molecular_formula!(H 3 N 1));
+        Self::none()
+            .a(PrimaryIonSeries::default()
+                .neutral_losses(vec![water.clone()])
+                .charge_range(ChargeRange::ONE))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone(), ammonia.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone(), ammonia.clone()]))
+            .precursor(vec![water.clone(), ammonia], ChargeRange::PRECURSOR)
+            .immonium(ImmoniumModel::ALLOW)
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
+    }
+
+    /// Resonance (ion trap) collision induced dissociation. Generates b and y ions with water and
+    /// ammonia loss and only the a ion at the first position (a2, which is very common). Glycans
+    /// are fragmented but because of the low mass cut-off of ion traps no immonium ions are
+    /// generated. As ion traps are generally low resolution the tolerance is set to 0.5 m/z.
+    pub fn cid() -> Self {
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        let ammonia = NeutralLoss::Loss(molecular_formula!(H 3 N 1));
+        Self::none()
+            .a(PrimaryIonSeries::default()
+                .location(Location::TakeN { skip: 0, take: 1 })
+                .neutral_losses(vec![water.clone()]))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone(), ammonia.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone(), ammonia.clone()]))
+            .precursor(vec![water.clone(), ammonia], ChargeRange::PRECURSOR)
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
+            .tolerance(Tolerance::new_absolute(MassOverCharge::new::<mz>(0.5)))
+    }
+
+    /// Electron-transfer/higher-energy collisional dissociation. Generates b, c, y, z, and w
+    /// ions with water loss, the first a and d ions, and precursors with water loss at all
+    /// charges up to the precursor charge together with all charge reduced precursors.
     pub fn ethcd() -> Self {
-        Self {
-            a: PrimaryIonSeries::default().location(Location::TakeN { skip: 0, take: 1 }),
-            b: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            c: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            d: PrimaryIonSeries::default().location(Location::TakeN { skip: 0, take: 1 }),
-            v: PrimaryIonSeries::default().location(Location::None),
-            w: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            x: PrimaryIonSeries::default().location(Location::None),
-            y: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            z: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            precursor: (
-                vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))],
-                ChargeRange::ONE_TO_PRECURSOR,
-            ),
-            immonium: (false, ChargeRange::ONE),
-            m: false,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::ALLOW
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default().location(Location::TakeN { skip: 0, take: 1 }))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .c(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .d(PrimaryIonSeries::default().location(Location::TakeN { skip: 0, take: 1 }))
+            .w(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .z(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(vec![water.clone()], ChargeRange::ONE_TO_PRECURSOR)
+            .charge_reduced_precursor((true, ChargeRange::CHARGE_REDUCED))
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
     }
 
-    /// EAD
+    /// Electron activated dissociation. Generates all ion series with water loss, immonium
+    /// ions, and precursors with water loss at all charges up to the precursor charge.
     pub fn ead() -> Self {
-        Self {
-            a: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            b: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            c: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            d: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            v: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            w: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            x: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            y: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            z: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            precursor: (
-                vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))],
-                ChargeRange::ONE_TO_PRECURSOR,
-            ),
-            immonium: (true, ChargeRange::ONE),
-            m: false,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::ALLOW
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .c(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .d(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .v(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .w(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .x(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .z(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(vec![water.clone()], ChargeRange::ONE_TO_PRECURSOR)
+            .immonium(ImmoniumModel::ALLOW)
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
     }
 
-    /// hot EACID
+    /// Hot electron activated collision induced dissociation. Generates all ion series except v
+    /// with water loss, and precursors with water loss at all charges up to the precursor charge.
     pub fn hot_eacid() -> Self {
-        Self {
-            a: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            b: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            c: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            d: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            v: PrimaryIonSeries::default().location(Location::None),
-            w: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            x: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            y: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            z: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            precursor: (
-                vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))],
-                ChargeRange::ONE_TO_PRECURSOR,
-            ),
-            immonium: (false, ChargeRange::ONE),
-            m: false,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::ALLOW
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .c(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .d(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .w(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .x(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .z(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(vec![water.clone()], ChargeRange::ONE_TO_PRECURSOR)
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
     }
 
-    /// CID Hcd
+    /// Combined CID and HCD model. Generates b and y ions with water loss and the first a and d
+    /// ions. Glycans are not fragmented. See [`Self::cid`] and [`Self::hcd`] for more specific
+    /// models.
     pub fn cid_hcd() -> Self {
-        Self {
-            a: PrimaryIonSeries::default()
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default()
                 .location(Location::TakeN { skip: 0, take: 1 })
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            b: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            c: PrimaryIonSeries::default().location(Location::None),
-            d: PrimaryIonSeries::default()
+                .neutral_losses(vec![water.clone()]))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .d(PrimaryIonSeries::default()
                 .location(Location::TakeN { skip: 0, take: 1 })
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            v: PrimaryIonSeries::default().location(Location::None),
-            w: PrimaryIonSeries::default().location(Location::None),
-            x: PrimaryIonSeries::default().location(Location::None),
-            y: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            z: PrimaryIonSeries::default().location(Location::None),
-            precursor: (
-                vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))],
-                ChargeRange::PRECURSOR,
-            ),
-            immonium: (false, ChargeRange::ONE),
-            m: false,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::DISALLOW,
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+                .neutral_losses(vec![water.clone()]))
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(vec![water], ChargeRange::PRECURSOR)
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .allow_cross_link_cleavage(true)
     }
 
-    /// ETD
+    /// Electron transfer dissociation. Generates c, y, and z ions with water loss, and the
+    /// precursor with common ETD losses at the precursor charge and the two charge reduced
+    /// species. Glycans are not fragmented.
     pub fn etd() -> Self {
-        Self {
-            a: PrimaryIonSeries::default().location(Location::None),
-            b: PrimaryIonSeries::default().location(Location::None),
-            c: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            d: PrimaryIonSeries::default().location(Location::None),
-            v: PrimaryIonSeries::default().location(Location::None),
-            w: PrimaryIonSeries::default().location(Location::None), // TODO: Are w ions also formed here?
-            x: PrimaryIonSeries::default().location(Location::None),
-            y: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            z: PrimaryIonSeries::default()
-                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]),
-            precursor: (
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .c(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            // TODO: Are w ions also formed here?
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .z(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(
                 vec![
-                    NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
+                    water,
                     NeutralLoss::Loss(molecular_formula!(H 1 O 1)),
                     NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
                     NeutralLoss::Loss(molecular_formula!(C 1 H 1 O 2)),
                     NeutralLoss::Loss(molecular_formula!(C 2 H 3 O 2)),
                 ],
+                ChargeRange::PRECURSOR,
+            )
+            .charge_reduced_precursor((
+                true,
                 ChargeRange {
                     start: ChargePoint::Relative(-2),
-                    end: ChargePoint::Relative(0),
+                    end: ChargePoint::Relative(-1),
                 },
-            ),
-            immonium: (false, ChargeRange::ONE),
-            m: false,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::DISALLOW,
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+            ))
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .allow_cross_link_cleavage(true)
     }
 
-    /// Top Down ETD
+    /// Top down electron transfer dissociation. Generates c and z ions with water and ammonia
+    /// loss and up to three hydrogen gains, and the precursor with common ETD losses and gains,
+    /// as well as all charge reduced precursors.
     pub fn td_etd() -> Self {
-        Self {
-            a: PrimaryIonSeries::default().location(Location::None),
-            b: PrimaryIonSeries::default().location(Location::None),
-            c: PrimaryIonSeries::default().neutral_losses(vec![
-                NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
-                NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
-                NeutralLoss::Gain(molecular_formula!(H 1)),
-                NeutralLoss::Gain(molecular_formula!(H 2)),
-                NeutralLoss::Gain(molecular_formula!(H 3)),
-            ]),
-            d: PrimaryIonSeries::default().location(Location::None),
-            v: PrimaryIonSeries::default().location(Location::None),
-            w: PrimaryIonSeries::default().location(Location::None),
-            x: PrimaryIonSeries::default().location(Location::None),
-            y: PrimaryIonSeries::default().location(Location::None),
-            z: PrimaryIonSeries::default().neutral_losses(vec![
-                NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
-                NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
-                NeutralLoss::Gain(molecular_formula!(H 1)),
-                NeutralLoss::Gain(molecular_formula!(H 2)),
-                NeutralLoss::Gain(molecular_formula!(H 3)),
-            ]),
-            precursor: (
+        let losses = vec![
+            NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
+            NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
+            NeutralLoss::Gain(molecular_formula!(H 1)),
+            NeutralLoss::Gain(molecular_formula!(H 2)),
+            NeutralLoss::Gain(molecular_formula!(H 3)),
+        ];
+        Self::none()
+            .c(PrimaryIonSeries::default().neutral_losses(losses.clone()))
+            .z(PrimaryIonSeries::default().neutral_losses(losses))
+            .precursor(
                 vec![
                     NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
                     NeutralLoss::Loss(molecular_formula!(H 1 O 1)),
@@ -725,16 +1112,90 @@ impl Model {
                     NeutralLoss::Gain(molecular_formula!(H 3)),
                 ],
                 ChargeRange::PRECURSOR,
-            ),
-            immonium: (false, ChargeRange::ONE),
-            m: false,
-            modification_specific_neutral_losses: true,
-            modification_specific_diagnostic_ions: (true, ChargeRange::ONE),
-            glycan: GlycanModel::DISALLOW,
-            allow_cross_link_cleavage: true,
-            tolerance: Tolerance::new_ppm(20.0),
-            mz_range: MassOverCharge::new::<mz>(0.0)..=MassOverCharge::new::<mz>(f64::MAX),
-        }
+            )
+            .charge_reduced_precursor((true, ChargeRange::CHARGE_REDUCED))
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .allow_cross_link_cleavage(true)
+    }
+
+    /// Electron capture dissociation. Generates c ions and z ions (including z+1 by hydrogen
+    /// transfer) and w ions from secondary fragmentation of the z· ions. The precursor is
+    /// generated at the precursor charge and the two charge reduced species with common losses.
+    /// Because ECD leaves labile modifications intact no modification specific neutral losses
+    /// and no glycan fragments are generated.
+    pub fn ecd() -> Self {
+        Self::none()
+            .c(PrimaryIonSeries::default())
+            .w(PrimaryIonSeries::default())
+            .z(PrimaryIonSeries::default()
+                .neutral_losses(vec![NeutralLoss::Gain(molecular_formula!(H 1))]))
+            .precursor(
+                vec![
+                    NeutralLoss::Loss(molecular_formula!(H 2 O 1)),
+                    NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
+                    NeutralLoss::Gain(molecular_formula!(H 1)),
+                ],
+                ChargeRange::PRECURSOR,
+            )
+            .charge_reduced_precursor((
+                true,
+                ChargeRange {
+                    start: ChargePoint::Relative(-2),
+                    end: ChargePoint::Relative(-1),
+                },
+            ))
+            .allow_cross_link_cleavage(true)
+    }
+
+    /// Ultraviolet photodissociation (193 nm). Generates all backbone ion series, with a+1 (a·),
+    /// y-1, and z· ions, as well as the side chain d, v, and w ions. Also generates immonium ions,
+    /// glycan fragments, and modification specific neutral losses and diagnostic ions.
+    pub fn uvpd() -> Self {
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default()
+                .neutral_losses(vec![NeutralLoss::Gain(molecular_formula!(H 1))]))
+            .b(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .c(PrimaryIonSeries::default())
+            .d(PrimaryIonSeries::default())
+            .v(PrimaryIonSeries::default())
+            .w(PrimaryIonSeries::default())
+            .x(PrimaryIonSeries::default())
+            .y(PrimaryIonSeries::default().neutral_losses(vec![
+                water.clone(),
+                NeutralLoss::Loss(molecular_formula!(H 1)),
+            ]))
+            .z(PrimaryIonSeries::default())
+            .precursor(vec![water.clone()], ChargeRange::PRECURSOR)
+            .immonium(ImmoniumModel::ALLOW)
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .glycan(GlycanModel::ALLOW.neutral_losses(vec![water]))
+            .allow_cross_link_cleavage(true)
+    }
+
+    /// Negative mode collision induced dissociation of deprotonated peptides. Generates a and y
+    /// ions (the main backbone cleavages in negative mode) and c ions, with water loss, and the
+    /// precursor with losses of water, ammonia, and carbon dioxide. This model only defines the
+    /// generated ions, the charge carriers still have to be set on the peptidoform.
+    pub fn negative_mode() -> Self {
+        let water = NeutralLoss::Loss(molecular_formula!(H 2 O 1));
+        Self::none()
+            .a(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .c(PrimaryIonSeries::default())
+            .y(PrimaryIonSeries::default().neutral_losses(vec![water.clone()]))
+            .precursor(
+                vec![
+                    water,
+                    NeutralLoss::Loss(molecular_formula!(H 3 N 1)),
+                    NeutralLoss::Loss(molecular_formula!(C 1 O 2)),
+                ],
+                ChargeRange::PRECURSOR,
+            )
+            .modification_specific_neutral_losses(true)
+            .modification_specific_diagnostic_ions((true, ChargeRange::ONE))
+            .allow_cross_link_cleavage(true)
     }
 }
 
@@ -785,14 +1246,150 @@ impl Location {
     }
 }
 
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn presets() {
+    let position = PeptidePosition::n(crate::SequencePosition::Index(2), 5);
+    let peptide = Peptidoform::pro_forma("AAAAA", None).unwrap();
+    let series = |model: &Model| {
+        let ions = model.ions(position, &peptide);
+        [
+            ('a', ions.a.0),
+            ('b', ions.b.0),
+            ('c', ions.c.0),
+            ('d', ions.d.0),
+            ('v', ions.v.0),
+            ('w', ions.w.0),
+            ('x', ions.x.0),
+            ('y', ions.y.0),
+            ('z', ions.z.0),
+        ]
+        .iter()
+        .filter(|(_, allowed)| *allowed)
+        .map(|(name, _)| *name)
+        .collect::<String>()
+    };
+    assert_eq!(series(&Model::hcd()), "aby");
+    assert_eq!(series(&Model::cid()), "by");
+    assert_eq!(series(&Model::etd()), "cyz");
+    assert_eq!(series(&Model::ethcd()), "bcwyz");
+    assert_eq!(series(&Model::ecd()), "cwz");
+    assert_eq!(series(&Model::uvpd()), "abcdvwxyz");
+    assert_eq!(series(&Model::negative_mode()), "acy");
+    assert_eq!(series(&Model::none().b(PrimaryIonSeries::default())), "b");
+    assert_eq!(Model::hcd().a.charge_range, ChargeRange::ONE);
+    assert!(Model::hcd().immonium.allows(AminoAcid::Tyrosine));
+    assert_eq!(
+        Model::cid().tolerance,
+        Tolerance::new_absolute(MassOverCharge::new::<mz>(0.5))
+    );
+}
+
 #[test]
 #[allow(clippy::missing_panics_doc, clippy::similar_names)]
 fn location_all() {
     let all = Model::all();
-    let ions_n0 = all.ions(PeptidePosition::n(crate::SequencePosition::default(), 2));
-    let ions_c0 = all.ions(PeptidePosition::c(crate::SequencePosition::default(), 2));
+    let peptide = Peptidoform::pro_forma("AA", None).unwrap();
+    let ions_n0 = all.ions(
+        PeptidePosition::n(crate::SequencePosition::default(), 2),
+        &peptide,
+    );
+    let ions_c0 = all.ions(
+        PeptidePosition::c(crate::SequencePosition::default(), 2),
+        &peptide,
+    );
     assert!(ions_n0.a.0);
     assert!(!ions_n0.x.0);
     assert!(!ions_c0.a.0);
     assert!(ions_c0.x.0);
 }
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn fragmentation_rules() {
+    let peptide = Peptidoform::pro_forma("ADPAK", None).unwrap();
+    let model = Model::none()
+        .b(PrimaryIonSeries::default())
+        .c(PrimaryIonSeries::default())
+        .y(PrimaryIonSeries::default().location(Location::None))
+        .fragmentation_rules(vec![
+            FragmentationRule::suppress(vec![FragmentKind::b]).c_terminal(vec![AminoAcid::Proline]),
+            FragmentationRule::enhance(vec![FragmentKind::y])
+                .n_terminal(vec![AminoAcid::AsparticAcid]),
+        ]);
+    let ions = |index: usize| {
+        let ions = model.ions(
+            PeptidePosition::n(crate::SequencePosition::Index(index), peptide.len()),
+            &peptide,
+        );
+        (ions.b.0, ions.c.0, ions.y.0)
+    };
+    assert_eq!(ions(0), (true, true, false)); // A|D
+    assert_eq!(ions(1), (false, true, false)); // D|P: no b
+    assert_eq!(ions(2), (true, true, true)); // P|A: y starting at P follows D|P
+    assert_eq!(ions(3), (true, true, false)); // A|K
+    assert_eq!(ions(4), (false, false, false)); // K| is the C terminus
+
+    // The last matching rule wins and rules never match the peptide termini
+    let model = Model::none().fragmentation_rules(vec![
+        FragmentationRule::enhance(vec![FragmentKind::b]),
+        FragmentationRule::suppress(vec![FragmentKind::b]).n_terminal(vec![AminoAcid::Alanine]),
+    ]);
+    let b = |index: usize| {
+        model
+            .ions(
+                PeptidePosition::n(crate::SequencePosition::Index(index), peptide.len()),
+                &peptide,
+            )
+            .b
+            .0
+    };
+    assert_eq!(
+        (0..5).map(b).collect::<Vec<_>>(),
+        [false, true, true, false, false]
+    );
+}
+
+#[test]
+#[allow(clippy::missing_panics_doc)]
+fn serialization() {
+    for model in [
+        Model::all(),
+        Model::none(),
+        Model::hcd(),
+        Model::ethcd(),
+        Model::etd(),
+        Model::td_etd(),
+        Model::uvpd(),
+        Model::none()
+            .b(PrimaryIonSeries::default().max_neutral_losses(3))
+            .tolerance(Tolerance::new_absolute(MassOverCharge::new::<mz>(0.02)))
+            .max_neutral_loss_mass(Some(Mass::new::<crate::system::dalton>(50.0)))
+            .fragmentation_rules(vec![FragmentationRule::suppress(vec![
+                FragmentKind::c,
+                FragmentKind::z,
+            ])
+            .c_terminal(vec![AminoAcid::Proline])]),
+    ] {
+        let mut buffer = Vec::new();
+        model.write(&mut buffer).unwrap();
+        assert_eq!(Model::read(buffer.as_slice()).unwrap(), model);
+    }
+    assert_eq!(Model::read(&b"{}"[..]).unwrap(), Model::none());
+    assert!(Model::read(&br#"{"b": 1}"#[..]).is_err());
+    // The format as documented on `Model::load`
+    let json = br#"{
+        "b": {"location": {"SkipN": 1}, "neutral_losses": [{"Loss": {"elements": [["H", null, 2], ["O", null, 1]], "additional_mass": 0.0, "labels": []}}], "max_neutral_losses": 1, "charge_range": {"start": {"Absolute": 1}, "end": {"Relative": 0}}},
+        "tolerance": {"Absolute": 0.02},
+        "max_neutral_loss_mass": 50.0
+    }"#;
+    assert_eq!(
+        Model::read(json.as_slice()).unwrap(),
+        Model::none()
+            .b(PrimaryIonSeries::default()
+                .location(Location::SkipN(1))
+                .neutral_losses(vec![NeutralLoss::Loss(molecular_formula!(H 2 O 1))]))
+            .tolerance(Tolerance::new_absolute(MassOverCharge::new::<mz>(0.02)))
+            .max_neutral_loss_mass(Some(Mass::new::<crate::system::dalton>(50.0)))
+    );
+}
@@ -6,7 +6,7 @@ use serde::{Deserialize, Serialize};
 
 use std::{
     cmp::Ordering,
-    collections::{BTreeSet, HashSet},
+    collections::BTreeSet,
     fmt::{Display, Write},
     sync::Arc,
 };
@@ -15,10 +15,10 @@ use crate::{
     glycan::{GlycanStructure, MonoSaccharide},
     molecular_charge::CachedCharge,
     peptidoform::Linked,
-    placement_rule::{PlacementRule, Position},
-    system::OrderedMass,
+    placement_rule::{PlacementRule, Position, ProteinTermini},
+    system::{dalton, Mass, OrderedMass},
     AmbiguousLabel, AminoAcid, Chemical, DiagnosticIon, Fragment, Model, MolecularFormula, Multi,
-    NeutralLoss, Peptidoform, SequenceElement, SequencePosition,
+    NeutralLoss, Peptidoform, SequenceElement, SequencePosition, Tolerance,
 };
 
 include!("shared/modification.rs");
@@ -97,6 +97,45 @@ impl std::iter::Sum for RulePossible {
     }
 }
 
+/// The provenance of a modification in a parsed ProForma definition. This keeps the original
+/// text the modification was parsed from and the ontology it was resolved in, so exports can
+/// reproduce the original definition and ontology backed modifications can be distinguished from
+/// mass only modifications. See [`Peptidoform::modification_provenance`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ModificationProvenance {
+    /// The position of the modification, `None` for labile modifications and modifications of
+    /// unknown position
+    pub position: Option<SequencePosition>,
+    /// The original text, without the enclosing brackets, e.g. `U:Oxidation` or `+15.9949`
+    pub text: String,
+    /// The ontology this modification was resolved in, `None` for modifications defined by a
+    /// mass, formula, or glycan
+    pub ontology: Option<Ontology>,
+    /// If this modification was defined as a mass shift only
+    pub mass_only: bool,
+}
+
+impl ModificationProvenance {
+    /// Create the provenance for a modification parsed from the given text
+    pub(crate) fn new(
+        position: Option<SequencePosition>,
+        text: &str,
+        modification: Option<&SimpleModificationInner>,
+    ) -> Self {
+        Self {
+            position,
+            text: text.to_string(),
+            ontology: modification.and_then(SimpleModificationInner::ontology),
+            mass_only: matches!(modification, Some(SimpleModificationInner::Mass(_))),
+        }
+    }
+
+    /// Check if this modification is backed by an ontology
+    pub const fn is_ontology_backed(&self) -> bool {
+        self.ontology.is_some()
+    }
+}
+
 impl Chemical for SimpleModificationInner {
     /// Get the molecular formula for this modification.
     fn formula_inner(
@@ -141,6 +180,16 @@ impl SimpleModificationInner {
         }
     }
 
+    /// Get the ontology this modification is defined in. Only defined for modifications from ontologies.
+    pub const fn ontology(&self) -> Option<Ontology> {
+        match self {
+            Self::Mass(_) | Self::Formula(_) | Self::Glycan(_) | Self::GlycanStructure(_) => None,
+            Self::Database { id, .. } | Self::Linker { id, .. } | Self::Gno { id, .. } => {
+                Some(id.ontology)
+            }
+        }
+    }
+
     /// Internal formula code with the logic to make all labels right
     pub(crate) fn formula_inner(
         &self,
@@ -178,6 +227,17 @@ impl SimpleModificationInner {
         &self,
         seq: &SequenceElement<T>,
         position: SequencePosition,
+    ) -> RulePossible {
+        self.is_possible_in_protein(seq, position, ProteinTermini::UNKNOWN)
+    }
+
+    /// Check to see if this modification can be placed on the specified element, where protein
+    /// terminal rules only match if the peptide terminus is also a protein terminus.
+    pub fn is_possible_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        termini: ProteinTermini,
     ) -> RulePossible {
         match self {
             Self::Database { specificities, .. } if specificities.is_empty() => {
@@ -189,7 +249,8 @@ impl SimpleModificationInner {
                     .iter()
                     .enumerate()
                     .filter_map(|(index, (rules, _, _))| {
-                        PlacementRule::any_possible(rules, seq, position).then_some(index)
+                        PlacementRule::any_possible_in_protein(rules, seq, position, termini)
+                            .then_some(index)
                     })
                     .collect();
                 if matching.is_empty() {
@@ -206,15 +267,22 @@ impl SimpleModificationInner {
                 .enumerate()
                 .map(|(index, spec)| match spec {
                     LinkerSpecificity::Symmetric(rules, _, _) => {
-                        if PlacementRule::any_possible(rules, seq, position) {
+                        if PlacementRule::any_possible_in_protein(rules, seq, position, termini) {
                             RulePossible::Symmetric(BTreeSet::from([index]))
                         } else {
                             RulePossible::No
                         }
                     }
                     LinkerSpecificity::Asymmetric((rules_left, rules_right), _, _) => {
-                        let left = PlacementRule::any_possible(rules_left, seq, position);
-                        let right = PlacementRule::any_possible(rules_right, seq, position);
+                        let left = PlacementRule::any_possible_in_protein(
+                            rules_left, seq, position, termini,
+                        );
+                        let right = PlacementRule::any_possible_in_protein(
+                            rules_right,
+                            seq,
+                            position,
+                            termini,
+                        );
                         if left && right {
                             RulePossible::Symmetric(BTreeSet::from([index]))
                         } else if left {
@@ -491,6 +559,18 @@ impl Modification {
         matches!(self, Self::Ambiguous { .. })
     }
 
+    /// Estimate the number of formulas this modification can have, without calculating them. A
+    /// cleavable cross-link can be bound or broken into any of its stubs. The formulas of the
+    /// linked peptide are not included.
+    pub(crate) fn estimated_formulas(&self) -> usize {
+        match self {
+            Self::Simple(_) | Self::Ambiguous { .. } => 1,
+            Self::CrossLink { linker, side, .. } => {
+                side.allowed_rules(linker).1.len().saturating_add(1)
+            }
+        }
+    }
+
     /// Get the formula for the whole addition (or subtraction) for this modification
     pub(crate) fn formula_inner(
         &self,
@@ -500,18 +580,18 @@ impl Modification {
         allow_ms_cleavable: bool,
         sequence_index: SequencePosition,
         peptidoform_index: usize,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
         match self {
             Self::Simple(modification) | Self::Ambiguous { modification, .. } => {
                 match &**modification {
                     // A linker that is not cross-linked is hydrolysed
                     SimpleModificationInner::Linker { formula, .. } => (
                         (formula.clone() + molecular_formula!(H 2 O 1)).into(),
-                        HashSet::new(),
+                        BTreeSet::new(),
                     ),
                     s => (
                         s.formula_inner(sequence_index, peptidoform_index).into(),
-                        HashSet::new(),
+                        BTreeSet::new(),
                     ),
                 }
             }
@@ -523,7 +603,7 @@ impl Modification {
                 ..
             } => {
                 if applied_cross_links.contains(name) {
-                    (Multi::default(), HashSet::default())
+                    (Multi::default(), BTreeSet::default())
                 } else if visited_peptides.contains(other_peptide) {
                     applied_cross_links.push(name.clone());
                     (
@@ -531,7 +611,7 @@ impl Modification {
                             .formula_inner(sequence_index, peptidoform_index)
                             .with_label(AmbiguousLabel::CrossLinkBound(name.clone()))
                             .into(),
-                        HashSet::from([name.clone()]),
+                        BTreeSet::from([name.clone()]),
                     )
                 } else {
                     applied_cross_links.push(name.clone());
@@ -549,7 +629,7 @@ impl Modification {
                             })
                             .unique()
                             .collect();
-                        let mut seen_peptides = HashSet::from([name.clone()]);
+                        let mut seen_peptides = BTreeSet::from([name.clone()]);
 
                         options.extend_from_slice(&{
                             let (f, seen) = all_peptides[*other_peptide].formulas_inner(
@@ -637,6 +717,20 @@ impl Modification {
         )
     }
 
+    /// Check to see if this modification can be placed on the specified element, see
+    /// [`SimpleModificationInner::is_possible_in_protein`]
+    pub fn is_possible_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        termini: ProteinTermini,
+    ) -> RulePossible {
+        self.simple().map_or(
+            RulePossible::Symmetric(std::collections::BTreeSet::new()),
+            |s| s.is_possible_in_protein(seq, position, termini),
+        )
+    }
+
     /// Generate theoretical fragments for side chains (glycans)
     pub(crate) fn generate_theoretical_fragments(
         &self,
@@ -702,11 +796,39 @@ impl SimpleModificationInner {
                 full_formula,
                 attachment,
             ),
+            Self::Gno {
+                composition: GnoComposition::Weight(weight),
+                ..
+            } if model.glycan.mass_only_compositions > 0 => {
+                // GNOme weights are given for the free glycan with two decimals
+                MonoSaccharide::compositions_for_mass(
+                    **weight - molecular_formula!(H 2 O 1).monoisotopic_mass(),
+                    Tolerance::new_absolute(Mass::new::<dalton>(0.01)),
+                    MASS_ONLY_MAX_MONOSACCHARIDES,
+                    model.glycan.mass_only_compositions,
+                )
+                .iter()
+                .flat_map(|composition| {
+                    MonoSaccharide::theoretical_fragments(
+                        composition,
+                        model,
+                        peptidoform_ion_index,
+                        peptidoform_index,
+                        charge_carriers,
+                        full_formula,
+                        attachment,
+                    )
+                })
+                .collect()
+            }
             _ => Vec::new(),
         }
     }
 }
 
+/// The maximal number of monosaccharides in a composition for glycans where only the mass is known
+const MASS_ONLY_MAX_MONOSACCHARIDES: usize = 40;
+
 /// The structure to lookup ambiguous modifications, with a list of all modifications (the order is fixed) with for each modification their name and the actual modification itself (if already defined)
 pub type AmbiguousLookup = Vec<AmbiguousLookupEntry>;
 
@@ -760,6 +882,7 @@ impl AmbiguousLookupEntry {
             colocalise_placed_modifications: self.colocalise_placed_modifications,
             colocalise_modifications_of_unknown_position: self
                 .colocalise_modifications_of_unknown_position,
+            ..Default::default()
         }
     }
 }
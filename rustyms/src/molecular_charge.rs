@@ -1,15 +1,22 @@
-use std::{cmp::Ordering, collections::HashMap, hash::Hash};
+use std::{
+    cmp::Ordering,
+    collections::BTreeMap,
+    hash::Hash,
+    ops::{Add, Mul},
+};
 
 use crate::{
-    model::ChargeRange, system::isize::Charge, Chemical, Element, MolecularFormula,
-    SequencePosition,
+    model::ChargeRange,
+    system::{e, f64::MassOverCharge, isize::Charge},
+    Chemical, Element, MolecularFormula, SequencePosition, Tolerance, WithinTolerance,
 };
+use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct CachedCharge {
     charge: MolecularCharge,
-    options: HashMap<Charge, Vec<MolecularCharge>>,
+    options: BTreeMap<Charge, Vec<MolecularCharge>>,
     number: Charge,
 }
 
@@ -40,7 +47,7 @@ impl From<MolecularCharge> for CachedCharge {
         let n = value.charge();
         Self {
             charge: value,
-            options: HashMap::new(),
+            options: BTreeMap::new(),
             number: n,
         }
     }
@@ -50,7 +57,7 @@ impl From<&MolecularCharge> for CachedCharge {
     fn from(value: &MolecularCharge) -> Self {
         Self {
             charge: value.clone(),
-            options: HashMap::new(),
+            options: BTreeMap::new(),
             number: value.charge(),
         }
     }
@@ -177,6 +184,182 @@ impl MolecularCharge {
     }
 }
 
+impl MolecularCharge {
+    /// Create a charge state with the given number of the given adduct
+    pub fn adduct(adduct: Adduct, amount: isize) -> Self {
+        Self {
+            charge_carriers: vec![(amount, adduct.formula())],
+        }
+    }
+
+    /// Add the given number of the given adduct to this charge state
+    #[must_use]
+    pub fn with_adduct(self, adduct: Adduct, amount: isize) -> Self {
+        self + Self::adduct(adduct, amount)
+    }
+
+    /// Get all combinations of the given adducts that result in exactly the given charge. Only
+    /// the adducts with the same sign as the given charge are used.
+    pub fn adduct_combinations(adducts: &[Adduct], charge: Charge) -> Vec<Self> {
+        let mut combinations = vec![(Vec::new(), charge.value)];
+        for adduct in adducts.iter().copied().unique() {
+            let adduct_charge = adduct.charge().value;
+            if adduct_charge == 0 || adduct_charge.signum() != charge.value.signum() {
+                continue;
+            }
+            combinations = combinations
+                .into_iter()
+                .flat_map(
+                    |(carriers, remaining): (Vec<(isize, MolecularFormula)>, isize)| {
+                        (0..=remaining / adduct_charge).map(move |n| {
+                            let mut carriers = carriers.clone();
+                            carriers.push((n, adduct.formula()));
+                            (carriers, remaining - n * adduct_charge)
+                        })
+                    },
+                )
+                .collect();
+        }
+        combinations
+            .into_iter()
+            .filter(|(_, remaining)| *remaining == 0)
+            .map(|(charge_carriers, _)| Self { charge_carriers }.simplified())
+            .collect()
+    }
+
+    /// Get all combinations of the given adducts, with an absolute charge of at most
+    /// `max_charge`, that result in a theoretical m/z for the given neutral formula that is
+    /// within the tolerance of the given m/z. Both positive and negative charge states are
+    /// considered. The monoisotopic m/z of each matching adduct variant is returned as well.
+    #[allow(clippy::cast_precision_loss)]
+    pub fn adduct_variants(
+        formula: &MolecularFormula,
+        mz: MassOverCharge,
+        adducts: &[Adduct],
+        max_charge: Charge,
+        tolerance: Tolerance<MassOverCharge>,
+    ) -> Vec<(Self, MassOverCharge)> {
+        (1..=max_charge.value)
+            .flat_map(|charge| [charge, -charge])
+            .flat_map(|charge| {
+                Self::adduct_combinations(adducts, Charge::new::<e>(charge))
+                    .into_iter()
+                    .map(move |carriers| {
+                        let theoretical = (formula + &carriers.formula()).monoisotopic_mass()
+                            / crate::system::f64::Charge::new::<e>(charge.abs() as f64);
+                        (carriers, theoretical)
+                    })
+            })
+            .filter(|(_, theoretical)| tolerance.within(theoretical, &mz))
+            .collect()
+    }
+}
+
+impl From<Adduct> for MolecularCharge {
+    fn from(value: Adduct) -> Self {
+        Self::adduct(value, 1)
+    }
+}
+
+impl Add for MolecularCharge {
+    type Output = Self;
+    fn add(mut self, rhs: Self) -> Self::Output {
+        self.charge_carriers.extend(rhs.charge_carriers);
+        self.simplified()
+    }
+}
+
+impl Mul<isize> for MolecularCharge {
+    type Output = Self;
+    fn mul(mut self, rhs: isize) -> Self::Output {
+        for carrier in &mut self.charge_carriers {
+            carrier.0 *= rhs;
+        }
+        self.simplified()
+    }
+}
+
+/// A common adduct ion that can act as charge carrier, use [`MolecularCharge`] to combine
+/// multiple adducts into a single charge state.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize, Hash)]
+pub enum Adduct {
+    /// A proton, H+
+    Proton,
+    /// A sodium ion, Na+
+    Sodium,
+    /// A potassium ion, K+
+    Potassium,
+    /// An ammonium ion, NH4+
+    Ammonium,
+    /// A calcium ion, Ca2+
+    Calcium,
+    /// An iron(III) ion, Fe3+
+    Iron,
+    /// A hydride ion, H-, used in negative mode
+    Hydride,
+}
+
+impl Adduct {
+    /// All adducts
+    pub const ALL: &'static [Self] = &[
+        Self::Proton,
+        Self::Sodium,
+        Self::Potassium,
+        Self::Ammonium,
+        Self::Calcium,
+        Self::Iron,
+        Self::Hydride,
+    ];
+    /// All positively charged adducts
+    pub const POSITIVE: &'static [Self] = &[
+        Self::Proton,
+        Self::Sodium,
+        Self::Potassium,
+        Self::Ammonium,
+        Self::Calcium,
+        Self::Iron,
+    ];
+    /// All negatively charged adducts
+    pub const NEGATIVE: &'static [Self] = &[Self::Hydride];
+
+    /// The full formula of this adduct, the charge is defined as missing or additional electrons
+    pub fn formula(self) -> MolecularFormula {
+        match self {
+            Self::Proton => molecular_formula!(H 1 Electron -1),
+            Self::Sodium => molecular_formula!(Na 1 Electron -1),
+            Self::Potassium => molecular_formula!(K 1 Electron -1),
+            Self::Ammonium => molecular_formula!(N 1 H 4 Electron -1),
+            Self::Calcium => molecular_formula!(Ca 1 Electron -2),
+            Self::Iron => molecular_formula!(Fe 1 Electron -3),
+            Self::Hydride => molecular_formula!(H 1 Electron 1),
+        }
+    }
+
+    /// The charge of this adduct
+    pub fn charge(self) -> Charge {
+        self.formula().charge()
+    }
+
+    /// The common notation for this adduct, for example `Na+` or `Ca2+`
+    pub const fn notation(self) -> &'static str {
+        match self {
+            Self::Proton => "H+",
+            Self::Sodium => "Na+",
+            Self::Potassium => "K+",
+            Self::Ammonium => "NH4+",
+            Self::Calcium => "Ca2+",
+            Self::Iron => "Fe3+",
+            Self::Hydride => "H-",
+        }
+    }
+}
+
+impl std::fmt::Display for Adduct {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.notation())
+    }
+}
+
 impl Chemical for MolecularCharge {
     fn formula_inner(
         &self,
@@ -229,9 +412,12 @@ impl std::fmt::Display for MolecularCharge {
 #[cfg(test)]
 #[allow(clippy::missing_panics_doc)]
 mod tests {
-    use crate::Chemical;
+    use crate::{
+        system::{e, isize::Charge, mz, MassOverCharge},
+        Chemical, Tolerance,
+    };
 
-    use super::MolecularCharge;
+    use super::{Adduct, MolecularCharge};
 
     #[test]
     fn simple_charge_options() {
@@ -240,4 +426,63 @@ mod tests {
         assert_eq!(options.len(), 1);
         assert_eq!(options[0].formula(), molecular_formula!(H 1 Electron -1));
     }
+
+    #[test]
+    fn adduct_arithmetic() {
+        assert_eq!(
+            MolecularCharge::from(Adduct::Proton) + MolecularCharge::adduct(Adduct::Proton, 1),
+            MolecularCharge::proton(2)
+        );
+        assert_eq!(
+            MolecularCharge::from(Adduct::Proton) * 3,
+            MolecularCharge::proton(3)
+        );
+        let mixed = MolecularCharge::proton(1)
+            .with_adduct(Adduct::Sodium, 2)
+            .with_adduct(Adduct::Calcium, 1);
+        assert_eq!(mixed.charge(), Charge::new::<e>(5));
+        assert_eq!(
+            mixed.formula(),
+            molecular_formula!(H 1 Na 2 Ca 1 Electron -5)
+        );
+        assert_eq!(Adduct::Hydride.charge(), Charge::new::<e>(-1));
+        assert_eq!(Adduct::Iron.to_string(), "Fe3+");
+    }
+
+    #[test]
+    fn adduct_combinations() {
+        let options = MolecularCharge::adduct_combinations(
+            &[
+                Adduct::Proton,
+                Adduct::Sodium,
+                Adduct::Calcium,
+                Adduct::Hydride,
+            ],
+            Charge::new::<e>(2),
+        );
+        assert_eq!(options.len(), 4, "{options:?}");
+        assert!(options.iter().all(|o| o.charge() == Charge::new::<e>(2)));
+        assert!(options.contains(&MolecularCharge::adduct(Adduct::Calcium, 1)));
+        assert_eq!(
+            MolecularCharge::adduct_combinations(Adduct::ALL, Charge::new::<e>(-2)),
+            vec![MolecularCharge::adduct(Adduct::Hydride, 2)]
+        );
+    }
+
+    #[test]
+    fn adduct_variants() {
+        let glucose = molecular_formula!(C 6 H 12 O 6);
+        let sodiated = (&glucose + &Adduct::Sodium.formula())
+            .monoisotopic_mass()
+            .value;
+        let variants = MolecularCharge::adduct_variants(
+            &glucose,
+            MassOverCharge::new::<mz>(sodiated + 0.0005),
+            Adduct::ALL,
+            Charge::new::<e>(2),
+            Tolerance::new_ppm(10.0),
+        );
+        assert_eq!(variants.len(), 1, "{variants:?}");
+        assert_eq!(variants[0].0, MolecularCharge::from(Adduct::Sodium));
+    }
 }
@@ -4,12 +4,13 @@ use std::{ops::Range, sync::OnceLock};
 
 use crate::{
     error::{Context, CustomError},
-    helper_functions::{explain_number_error, next_number, Characters, RangeExtension, RangeMaths},
+    helper_functions::{explain_number_error, next_number, RangeExtension, RangeMaths},
     modification::{Ontology, SimpleModification},
     system::{e, isize::Charge, mz, MassOverCharge},
     AminoAcid, MolecularCharge, MolecularFormula, NeutralLoss, Tolerance,
 };
-// TODO: custom errors are off as they assume the input to be Bytes but now get Character offsets.
+// All ranges and offsets in this parser are byte offsets into the full line. All syntactically
+// relevant characters are ASCII so any offset directly after such a character is a char boundary.
 
 /// Parse a mzPAF peak annotation line (can contain multiple annotations).
 /// # Errors
@@ -18,13 +19,13 @@ pub fn parse_mzpaf(line: &str) -> Result<Vec<PeakAnnotation>, CustomError> {
     let mut annotations = Vec::new();
 
     // Parse first
-    let (mut range, a) = parse_annotation(line, 0..line.chars().count())?;
+    let (mut range, a) = parse_annotation(line, 0..line.len())?;
     annotations.push(a);
 
     // Parse any following
     while !range.is_empty() {
-        if line.chars().nth(range.start_index()) == Some(',') {
-            range.add_start(1_usize);
+        if byte_at(line, range.start_index()) == Some(b',') {
+            range = range.add_start(1_usize);
         } else {
             return Err(CustomError::error(
                 "Invalid mzPAF annotation delimiter",
@@ -40,14 +41,36 @@ pub fn parse_mzpaf(line: &str) -> Result<Vec<PeakAnnotation>, CustomError> {
     Ok(annotations)
 }
 
+/// Get the byte at the given byte offset, if present
+fn byte_at(line: &str, index: usize) -> Option<u8> {
+    line.as_bytes().get(index).copied()
+}
+
+/// Get the range of the text enclosed by the bracket at `open` (byte offset) and the first
+/// following `close` character.
+/// # Errors
+/// When there is no closing character.
+fn enclosed(line: &str, open: usize, close: char, kind: &str) -> Result<Range<usize>, CustomError> {
+    line.get(open + 1..)
+        .and_then(|rest| rest.find(close))
+        .map(|len| open + 1..open + 1 + len)
+        .ok_or_else(|| {
+            CustomError::error(
+                format!("Invalid mzPAF {kind}"),
+                format!("No closing '{close}' could be found"),
+                Context::line(None, line, open, 1),
+            )
+        })
+}
+
 /// Parse a single mzPAF peak annotation.
 /// # Errors
 /// When the annotation does not follow the format.
 fn parse_annotation(
     line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, PeakAnnotation), CustomError> {
-    let (left_range, auxiliary) = if line.chars().nth(range.start_index()) == Some('&') {
+    range: Range<usize>,
+) -> Result<(Range<usize>, PeakAnnotation), CustomError> {
+    let (left_range, auxiliary) = if byte_at(line, range.start_index()) == Some(b'&') {
         (range.add_start(1_usize), true)
     } else {
         (range.clone(), false)
@@ -113,11 +136,11 @@ fn parse_analyte_number(
     next_number::<false, false, usize>(line, range.clone()).map_or_else(
         || Ok((range.clone(), None)),
         |num| {
-            if line.chars().nth(num.0) != Some('@') {
+            if byte_at(line, range.start_index() + num.0) != Some(b'@') {
                 return Err(CustomError::error(
                     "Invalid mzPAF analyte number",
                     "The analyte number should be followed by an at sign '@'",
-                    Context::line(None, line, num.0, 1),
+                    Context::line(None, line, range.start_index() + num.0, 1),
                 ));
             }
             Ok((
@@ -126,7 +149,7 @@ fn parse_analyte_number(
                     CustomError::error(
                         "Invalid mzPAF analyte number",
                         format!("The analyte number number {}", explain_number_error(&err)),
-                        Context::line(None, line, 0, num.0),
+                        Context::line(None, line, range.start_index(), num.0),
                     )
                 })?),
             ))
@@ -137,12 +160,9 @@ fn parse_analyte_number(
 /// Parse a mzPAF ion.
 /// # Errors
 /// When the ion is not formatted correctly.
-fn parse_ion(
-    line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, IonType), CustomError> {
-    match line.chars().nth(range.start_index()) {
-        Some('?') => {
+fn parse_ion(line: &str, range: Range<usize>) -> Result<(Range<usize>, IonType), CustomError> {
+    match byte_at(line, range.start_index()) {
+        Some(b'?') => {
             if let Some(ordinal) =
                 next_number::<false, false, usize>(line, range.add_start(1_usize))
             {
@@ -160,14 +180,14 @@ fn parse_ion(
                 Ok((range.add_start(1_usize), IonType::Unknown(None)))
             }
         }
-        Some(c @ ('a' | 'b' | 'c' | 'x' | 'y' | 'z')) => {
+        Some(c @ (b'a' | b'b' | b'c' | b'x' | b'y' | b'z')) => {
             if let Some(ordinal) =
                 next_number::<false, false, usize>(line, range.add_start(1_usize))
             {
                 Ok((
                     range.add_start(1 + ordinal.0),
                     IonType::MainSeries(
-                        c,
+                        char::from(c),
                         ordinal.2.map_err(|err| {
                             CustomError::error(
                                 "Invalid mzPAF unknown ion ordinal",
@@ -186,54 +206,41 @@ fn parse_ion(
                 ))
             }
         }
-        Some('I') => {
-            let amino_acid = line[range.clone()].chars().nth(1).ok_or_else(|| {
+        Some(b'I') => {
+            let amino_acid = line[range.start_index() + 1..range.end]
+                .chars()
+                .next()
+                .ok_or_else(|| {
+                    CustomError::error(
+                        "Invalid mzPAF immonium",
+                        "The source amino acid for this immonium ion should be present like 'IA'",
+                        Context::line(None, line, range.start_index(), 1),
+                    )
+                })?;
+            let amino_acid_end = range.start_index() + 1 + amino_acid.len_utf8();
+            let amino_acid = AminoAcid::try_from(amino_acid).map_err(|()| {
                 CustomError::error(
-                    "Invalid mzPAF immonium",
-                    "The source amino acid for this immonium ion should be present like 'IA'",
-                    Context::line(None, line, range.start_index(), 1),
+                    "Invalid mzPAF immonium ion",
+                    "The provided amino acid is not a known amino acid",
+                    Context::line(None, line, range.start_index() + 1, amino_acid.len_utf8()),
                 )
             })?;
-            let modification = if line[range.clone()].chars().nth(2) == Some('[') {
-                let first = line[range.clone()].char_indices().nth(3).unwrap().0;
-                let last = line[range.clone()]
-                    .char_indices()
-                    .skip(3)
-                    .take_while(|(_, c)| *c != ']')
-                    .last()
-                    .unwrap();
-                Some((
-                    last.0 + last.1.len_utf8() - first,
-                    Ontology::Unimod
-                        .find_name(
-                            &line[range.clone()][first..last.0 + last.1.len_utf8()],
-                            None,
-                        )
-                        .ok_or_else(|| {
-                            Ontology::Unimod.find_closest(
-                                &line[range.clone()][first..last.0 + last.1.len_utf8()],
-                                None,
-                            )
-                        })?,
-                ))
+            let (end, modification) = if byte_at(line, amino_acid_end) == Some(b'[') {
+                let name = enclosed(line, amino_acid_end, ']', "immonium modification")?;
+                (
+                    name.end + 1,
+                    Some(
+                        Ontology::Unimod
+                            .find_name(&line[name.clone()], None)
+                            .ok_or_else(|| Ontology::Unimod.find_closest(&line[name], None))?,
+                    ),
+                )
             } else {
-                None
+                (amino_acid_end, None)
             };
-            Ok((
-                range.add_start(2 + modification.as_ref().map_or(0, |m| m.0)),
-                IonType::Immonium(
-                    AminoAcid::try_from(amino_acid).map_err(|()| {
-                        CustomError::error(
-                            "Invalid mzPAF immonium ion",
-                            "The provided amino acid is not a known amino acid",
-                            Context::line(None, line, range.start_index() + 1, 1),
-                        )
-                    })?,
-                    modification.map(|m| m.1),
-                ),
-            ))
+            Ok((end..range.end, IonType::Immonium(amino_acid, modification)))
         }
-        Some('m') => {
+        Some(b'm') => {
             let first_ordinal = next_number::<false, false, usize>(line, range.add_start(1_usize))
                 .ok_or_else(|| {
                     CustomError::error(
@@ -242,28 +249,22 @@ fn parse_ion(
                         Context::line(None, line, range.start_index(), 1),
                     )
                 })?;
-            if line[range.clone()].chars().nth(first_ordinal.0) != Some(':') {
+            if byte_at(line, range.start_index() + 1 + first_ordinal.0) != Some(b':') {
                 return Err(CustomError::error(
                     "Invalid mzPAF internal ion ordinal separator",
                     "The internal ion ordinal separator should be a colon ':', like 'm4:6'",
                     Context::line(None, line, range.start_index() + 1 + first_ordinal.0, 1),
                 ));
             }
-            assert!(
-                line[range.clone()].chars().nth(first_ordinal.0) == Some(':'),
-                "Needs to be separated by colon"
-            );
-            let second_ordinal = next_number::<false, false, usize>(
-                line,
-                range.add_start(2 + first_ordinal.0 as isize),
-            )
-            .ok_or_else(|| {
-                CustomError::error(
-                    "Invalid mzPAF internal ion second ordinal",
-                    "The second ordinal for an internal ion should be present",
-                    Context::line(None, line, range.start_index() + 1 + first_ordinal.0, 1),
-                )
-            })?;
+            let second_ordinal =
+                next_number::<false, false, usize>(line, range.add_start(2 + first_ordinal.0))
+                    .ok_or_else(|| {
+                        CustomError::error(
+                            "Invalid mzPAF internal ion second ordinal",
+                            "The second ordinal for an internal ion should be present",
+                            Context::line(None, line, range.start_index() + 1 + first_ordinal.0, 1),
+                        )
+                    })?;
             let first_location = first_ordinal.2.map_err(|err| {
                 CustomError::error(
                     "Invalid mzPAF internal ion first ordinal",
@@ -288,101 +289,79 @@ fn parse_ion(
                 IonType::Internal(first_location, second_location),
             ))
         }
-        Some('_') => {
+        Some(b'_') => {
             // Format less strings
             // TODO: Potentially recognise the following as known contaminants:
             // 0@_{y1(R)}
             // 0@_{a2(LP)}
             // 0@_{b2(LP)}
-
-            let (len, name) = if line[range.clone()].chars().nth(1) == Some('{') {
-                let first = line[range.clone()].char_indices().nth(2).unwrap().0;
-                let last = line[range.clone()]
-                    .char_indices()
-                    .skip(2)
-                    .take_while(|(_, c)| *c != '}')
-                    .last()
-                    .unwrap();
-                Ok((
-                    last.0 + last.1.len_utf8() - first,
-                    &line[range.clone()][first..last.0 + last.1.len_utf8()],
-                ))
-            } else {
-                Err(CustomError::error(
+            if byte_at(line, range.start_index() + 1) != Some(b'{') {
+                return Err(CustomError::error(
                     "Invalid mzPAF named compound",
                     "A named compound must be named with curly braces '{}' after the '_'",
                     Context::line(None, line, range.start_index(), 1),
-                ))
-            }?;
-            Ok((range.add_start(3 + len), IonType::Named(name.to_string())))
+                ));
+            }
+            let name = enclosed(line, range.start_index() + 1, '}', "named compound")?;
+            Ok((
+                name.end + 1..range.end,
+                IonType::Named(line[name].to_string()),
+            ))
         }
-        Some('p') => Ok((range.add_start(1_usize), IonType::Precursor)),
-        Some('r') => {
+        Some(b'p') => Ok((range.add_start(1_usize), IonType::Precursor)),
+        Some(b'r') => {
             // Same name as neutral losses
-            let (len, name) = if line[range.clone()].chars().nth(1) == Some('[') {
-                let first = line[range.clone()].char_indices().nth(2).unwrap().0;
-                let last = line[range.clone()]
-                    .char_indices()
-                    .skip(2)
-                    .take_while(|(_, c)| *c != ']')
-                    .last()
-                    .unwrap();
-                Ok((
-                    last.0 + last.1.len_utf8() - first,
-                    &line[range.clone()][first..last.0 + last.1.len_utf8()],
-                ))
-            } else {
-                Err(CustomError::error(
+            if byte_at(line, range.start_index() + 1) != Some(b'[') {
+                return Err(CustomError::error(
                     "Invalid mzPAF reporter ion",
                     "A reporter ion must be named with square braces '[]' after the 'r'",
                     Context::line(None, line, range.start_index(), 1),
-                ))
-            }?;
+                ));
+            }
+            let name = enclosed(line, range.start_index() + 1, ']', "reporter ion")?;
             mz_paf_named_molecules()
                 .iter()
-                .find_map(|n| (n.0 == name).then_some(n.1.clone()))
+                .find_map(|n| (n.0 == &line[name.clone()]).then_some(n.1.clone()))
                 .map_or_else(
                     || {
                         Err(CustomError::error(
                             "Unknown mzPAF named reporter ion",
                             "Unknown name",
-                            Context::line(None, line, range.start_index() + 1, len),
+                            Context::line(None, line, name.start, name.len()),
                         ))
                     },
-                    |formula| Ok((range.add_start(3 + len), IonType::Reporter(formula))),
+                    |formula| Ok((name.end + 1..range.end, IonType::Reporter(formula))),
                 )
         }
-        Some('f') => {
+        Some(b'f') => {
             // Simple formula
-            let formula_range = if line[range.clone()].chars().nth(1) == Some('{') {
-                let first = line[range.clone()].char_indices().nth(2).unwrap().0;
-                let last = line[range.clone()]
-                    .char_indices()
-                    .skip(2)
-                    .take_while(|(_, c)| *c != '}')
-                    .last()
-                    .unwrap();
-                Ok(range.start_index() + first..range.start_index() + last.0 + last.1.len_utf8())
-            } else {
-                Err(CustomError::error(
+            if byte_at(line, range.start_index() + 1) != Some(b'{') {
+                return Err(CustomError::error(
                     "Invalid mzPAF formula",
                     "A formula must have the formula defined with curly braces '{}' after the 'f'",
                     Context::line(None, line, range.start_index(), 1),
-                ))
-            }?;
+                ));
+            }
+            let formula_range = enclosed(line, range.start_index() + 1, '}', "formula")?;
             let formula =
                 MolecularFormula::from_pro_forma(line, formula_range.clone(), false, false, true)?;
 
-            Ok((
-                range.add_start(3 + formula_range.len()),
-                IonType::Formula(formula),
-            ))
+            Ok((formula_range.end + 1..range.end, IonType::Formula(formula)))
         }
-        Some('s') => todo!(), // TODO: return as Formula
+        Some(b's') => Err(CustomError::error(
+            "Unsupported mzPAF ion",
+            "SMILES ions are not yet supported",
+            Context::line(None, line, range.start_index(), 1),
+        )), // TODO: return as Formula
         Some(_) => Err(CustomError::error(
             "Invalid ion",
             "An ion cannot start with this character",
-            Context::line(None, line, range.start, 1),
+            Context::line(
+                None,
+                line,
+                range.start_index(),
+                line[range].chars().next().map_or(1, char::len_utf8),
+            ),
         )),
         None => Err(CustomError::error(
             "Invalid ion",
@@ -392,79 +371,63 @@ fn parse_ion(
     }
 }
 
+/// Parse any number of mzPAF neutral losses or gains.
+/// # Errors
+/// When a neutral loss is not a valid formula or a known name.
 fn parse_neutral_loss(
     line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, Vec<NeutralLoss>), CustomError> {
-    let mut offset = 0;
+    range: Range<usize>,
+) -> Result<(Range<usize>, Vec<NeutralLoss>), CustomError> {
+    let mut index = range.start_index();
     let mut neutral_losses = Vec::new();
-    while let Some(c @ ('-' | '+')) = line.chars().nth(range.start_index() + offset) {
-        if line.chars().nth(range.start_index() + 1) == Some('[') {
-            let first = line.char_indices().nth(range.start_index() + 2).unwrap().0;
-            let last = line
-                .char_indices()
-                .skip(range.start_index() + 2)
-                .take_while(|(_, c)| *c != ']')
-                .last()
-                .unwrap();
-            //Ok(first..last.0 + last.1.len_utf8());
-            let name = line[first..last.0 + last.1.len_utf8()].to_ascii_lowercase();
-
-            offset += 1 + last.0 + last.1.len_utf8() - first;
-
-            if let Some(formula) = mz_paf_named_molecules()
+    while let Some(c @ (b'-' | b'+')) = byte_at(line, index).filter(|_| index < range.end) {
+        let formula = if byte_at(line, index + 1) == Some(b'[') {
+            let name = enclosed(line, index + 1, ']', "named neutral loss")?;
+            let lowercase = line[name.clone()].to_ascii_lowercase();
+            index = name.end + 1;
+            mz_paf_named_molecules()
                 .iter()
-                .find_map(|n| (n.0 == name).then_some(n.1.clone()))
-            {
-                neutral_losses.push(match c {
-                    '+' => NeutralLoss::Gain(formula),
-                    '-' => NeutralLoss::Loss(formula),
-                    _ => unreachable!(),
-                });
-            } else {
+                .find_map(|n| (n.0 == lowercase).then_some(n.1.clone()))
+                .ok_or_else(|| {
+                    CustomError::error(
+                        "Unknown mzPAF named neutral loss",
+                        "Unknown name",
+                        Context::line(None, line, name.start, name.len()),
+                    )
+                })?
+        } else {
+            let start = index + 1;
+            let end = line[start..range.end]
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .map_or(range.end, |len| start + len);
+            if start == end {
                 return Err(CustomError::error(
-                    "Unknown mzPAF named neutral loss",
-                    "Unknown name",
-                    Context::line(None, line, offset - name.len() - 1, name.len()),
+                    "Invalid mzPAF neutral loss",
+                    "A neutral loss should be a formula or a name in square brackets, like '-H2O' or '-[hex]'",
+                    Context::line(None, line, index, 1),
                 ));
             }
+            index = end;
+            MolecularFormula::from_pro_forma(line, start..end, false, false, true)?
+        };
+        neutral_losses.push(if c == b'+' {
+            NeutralLoss::Gain(formula)
         } else {
-            let first = line.char_indices().nth(range.start_index() + 1).unwrap().0;
-            let last = line
-                .char_indices()
-                .skip(range.start_index() + 2)
-                .take_while(|(_, c)| c.is_ascii_alphanumeric())
-                .last()
-                .unwrap();
-            let formula = MolecularFormula::from_pro_forma(
-                line,
-                first..last.0 + last.1.len_utf8(),
-                false,
-                false,
-                true,
-            )?;
-            neutral_losses.push(match c {
-                '+' => NeutralLoss::Gain(formula),
-                '-' => NeutralLoss::Loss(formula),
-                _ => unreachable!(),
-            });
-            offset += 1 + last.0 + last.1.len_utf8() - first;
-        }
+            NeutralLoss::Loss(formula)
+        });
     }
-    Ok((range.add_start(offset), neutral_losses))
+    Ok((index..range.end, neutral_losses))
 }
 
+/// Parse a mzPAF adduct type. '[M+2Na]'
+/// # Errors
+/// When the adduct type is not formatted correctly.
 fn parse_adduct_type(
     line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, Option<MolecularCharge>), CustomError> {
-    if line.chars().nth(range.start_index()) == Some('[') {
-        if line
-            .chars()
-            .nth(range.start_index() + 1)
-            .map(|c| c.to_ascii_lowercase())
-            != Some('m')
-        {
+    range: Range<usize>,
+) -> Result<(Range<usize>, Option<MolecularCharge>), CustomError> {
+    if byte_at(line, range.start_index()) == Some(b'[') {
+        if byte_at(line, range.start_index() + 1).map(|c| c.to_ascii_lowercase()) != Some(b'm') {
             return Err(CustomError::error(
                 "Invalid mzPAF adduct type",
                 "The adduct type should start with 'M', as in '[M+nA]'",
@@ -472,58 +435,43 @@ fn parse_adduct_type(
             ));
         }
         let mut carriers = Vec::new();
-        let mut offset = 2;
-        while let Some(number) = next_number::<true, false, isize>(line, range.add_start(offset)) {
-            let first = line
-                .char_indices()
-                .nth(range.start_index() + offset + number.0)
-                .unwrap()
-                .0;
-            let last = line
-                .char_indices()
-                .skip(range.start_index() + offset + number.0)
-                .take_while(|(_, c)| c.is_ascii_alphanumeric())
-                .fold((0, 0, ' '), |acc, (index, c)| (acc.0 + 1, index, c));
-            let formula = MolecularFormula::from_pro_forma(
-                line,
-                first..last.1 + last.2.len_utf8(),
-                false,
-                false,
-                true,
-            )?;
+        let mut index = range.start_index() + 2;
+        while let Some(number) = next_number::<true, false, isize>(line, index..range.end) {
+            let start = index + number.0;
+            let end = line[start..range.end]
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .map_or(range.end, |len| start + len);
+            let formula = MolecularFormula::from_pro_forma(line, start..end, false, false, true)?;
             carriers.push((
                 number.2.map_err(|err| {
                     CustomError::error(
                         "Invalid mzPAF adduct ordinal",
                         format!("The ordinal number {}", explain_number_error(&err)),
-                        Context::line(None, line, range.start_index() + offset, number.0),
+                        Context::line(None, line, index, number.0),
                     )
                 })?,
                 formula,
             ));
-            offset += number.0 + last.0;
+            index = end;
         }
-        if line.chars().nth(range.start_index() + offset) != Some(']') {
+        if byte_at(line, index) != Some(b']') {
             return Err(CustomError::error(
                 "Invalid mzPAF adduct type",
                 "The adduct type should be closed with ']'",
-                Context::line(None, line, range.start_index() + offset, 1),
+                Context::line(None, line, index, 1),
             ));
         }
-        Ok((
-            range.add_start(offset + 1),
-            Some(MolecularCharge::new(&carriers)),
-        ))
+        Ok((index + 1..range.end, Some(MolecularCharge::new(&carriers))))
     } else {
         Ok((range, None))
     }
 }
 
-fn parse_charge(
-    line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, Charge), CustomError> {
-    if line.chars().nth(range.start_index()) == Some('^') {
+/// Parse a mzPAF charge. '^2'
+/// # Errors
+/// When the charge is not a number.
+fn parse_charge(line: &str, range: Range<usize>) -> Result<(Range<usize>, Charge), CustomError> {
+    if byte_at(line, range.start_index()) == Some(b'^') {
         let charge =
             next_number::<false, false, u32>(line, range.add_start(1_usize)).ok_or_else(|| {
                 CustomError::error(
@@ -547,42 +495,14 @@ fn parse_charge(
     }
 }
 
-// fn parse_adduct(
-//     line: &str,
-//     range: Range<usize>,
-// ) -> Result<(Characters, MolecularFormula), CustomError> {
-//     if line[range.clone()].chars().next() == Some('^') {
-//         let charge =
-//             next_number::<false, false, u32>(line, range.add_start(1)).ok_or_else(|| {
-//                 CustomError::error(
-//                     "Invalid mzPAF charge",
-//                     "The number after the charge symbol should be present, eg '^2'.",
-//                     Context::line(None, line, range.start_index(), 1),
-//                 )
-//             })?;
-//         Ok((
-//             charge.0 + 1,
-//             Charge::new::<e>(charge.2.map_err(|err| {
-//                 CustomError::error(
-//                     "Invalid mzPAF charge",
-//                     format!("The charge number {}", explain_number_error(&err)),
-//                     Context::line(None, line, range.start_index() + 1, charge.0),
-//                 )
-//             })? as isize),
-//         ))
-//     } else {
-//         Ok((0, Charge::new::<e>(1)))
-//     }
-// }
-
 /// Parse a mzPAF deviation, either a ppm or mz deviation.
 /// # Errors
 /// When the deviation is not '<number>' or '<number>ppm'.
 fn parse_deviation(
     line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, Option<Tolerance<MassOverCharge>>), CustomError> {
-    if line.chars().nth(range.start_index()) == Some('/') {
+    range: Range<usize>,
+) -> Result<(Range<usize>, Option<Tolerance<MassOverCharge>>), CustomError> {
+    if byte_at(line, range.start_index()) == Some(b'/') {
         let number =
             next_number::<true, true, f64>(line, range.add_start(1_usize)).ok_or_else(|| {
                 CustomError::error(
@@ -598,13 +518,10 @@ fn parse_deviation(
                 Context::line_range(None, line, range.start + 1..range.start + 1 + number.0),
             )
         })?;
+        let unit_start = range.start_index() + 1 + number.0;
         if line
-            .chars()
-            .skip(range.start_index() + 1)
-            .take(3)
-            .collect::<String>()
-            .to_ascii_lowercase()
-            == "ppm"
+            .get(unit_start..unit_start + 3)
+            .is_some_and(|unit| unit.eq_ignore_ascii_case("ppm"))
         {
             Ok((
                 range.add_start(1 + number.0 + 3),
@@ -628,9 +545,9 @@ fn parse_deviation(
 /// When the deviation is not '*<number>'.
 fn parse_confidence(
     line: &str,
-    range: Range<Characters>,
-) -> Result<(Range<Characters>, Option<f64>), CustomError> {
-    if line.chars().nth(range.start_index()) == Some('*') {
+    range: Range<usize>,
+) -> Result<(Range<usize>, Option<f64>), CustomError> {
+    if byte_at(line, range.start_index()) == Some(b'*') {
         let number =
             next_number::<true, true, f64>(line, range.add_start(1_usize)).ok_or_else(|| {
                 CustomError::error(
@@ -742,3 +659,75 @@ fn mz_paf_named_molecules() -> &'static Vec<(&'static str, MolecularFormula)> {
 }
 
 static MZPAF_NAMED_MOLECULES_CELL: OnceLock<Vec<(&str, MolecularFormula)>> = OnceLock::new();
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_valid() {
+        let annotations =
+            parse_mzpaf("b2-H2O/3.2ppm,y3^2*0.8,&1@m2:4,IA,_{contaminant},p").unwrap();
+        assert_eq!(annotations.len(), 6);
+        assert!(annotations[2].auxiliary);
+        assert_eq!(annotations[2].analyte_number, Some(1));
+        assert_eq!(annotations[1].confidence, Some(0.8));
+        assert!(matches!(annotations[0].ion, IonType::MainSeries('b', 2)));
+        assert_eq!(annotations[0].neutral_losses.len(), 1);
+        assert!(matches!(
+            annotations[3].ion,
+            IonType::Immonium(AminoAcid::Alanine, None)
+        ));
+    }
+
+    #[test]
+    fn malformed_and_non_ascii() {
+        for line in [
+            "",
+            "b",
+            "1",
+            "1@",
+            "m",
+            "m1",
+            "m1:",
+            "I",
+            "IÅ",
+            "IA[",
+            "IA[]",
+            "_",
+            "_{",
+            "_{é",
+            "r[",
+            "r[ö]",
+            "f{",
+            "f{}",
+            "f{Cé}",
+            "b2-",
+            "b2-[",
+            "b2-[hèx]",
+            "b2-é",
+            "b2+",
+            "b2[",
+            "b2[M",
+            "b2[M+",
+            "b2[M+2é]",
+            "b2^",
+            "b2^é",
+            "b2/",
+            "b2/é",
+            "b2/1pp",
+            "b2*",
+            "b2,",
+            "b2,é",
+            "é",
+            "ééé",
+            "s{CCO}",
+            "b2/1ppmé",
+        ] {
+            if let Err(err) = parse_mzpaf(line) {
+                let _ = err.to_string();
+            }
+        }
+    }
+}
@@ -1,18 +1,153 @@
 //! The available ontologies
+//!
+//! # Thread safety
+//! The built in ontologies are loaded lazily on first use and are stored in statics, so they are
+//! loaded only once and are shared between all threads without any locking after loading. A
+//! [`CustomDatabase`] is never changed after it is created, so it can be shared between threads
+//! by reference, or if the workers need to own it, as a [`SharedCustomDatabase`] which is cheap
+//! to clone.
+//!
+//! All major types in this crate (peptidoforms, modifications, formulas, fragments, models, and
+//! spectra) are [`Send`] and [`Sync`], so they can be freely used in parallel pipelines.
+//!
+//! Name lookups in the built in ontologies use an index that is built once on first use and
+//! shared between all threads as well.
+//!
+//! # Loading ontologies at runtime
+//! The Unimod, PSI-MOD, and XLMOD ontologies can be parsed from their OBO files at runtime, for
+//! example to use a newer release than the one built into this crate or to pin a specific
+//! release. The parsed list can be used as a [`CustomDatabase`] or it can replace the built in
+//! ontology with [`Ontology::set_database`], as long as this is done before the ontology is
+//! used for the first time.
+//! ```rust,no_run
+//! # use rustyms::{*, modification::Ontology};
+//! Ontology::Unimod.load_file("unimod.obo").unwrap();
+//! let peptide = Peptidoform::pro_forma("PEPT[U:Phospho]IDE", None).unwrap();
+//! ```
+//!
+//! # Sharing custom databases
+//! A [`CustomDatabase`] can be saved to and loaded from a JSON file with
+//! [`save_custom_database`] and [`load_custom_database`]. This keeps all information of the
+//! modifications, including the linker specificities, stubs, and diagnostic ions, so a collection
+//! of custom modifications can be shared between runs and users.
+//! ```rust,no_run
+//! # use rustyms::{*, ontologies::*};
+//! let database = load_custom_database("custom_modifications.json").unwrap();
+//! let peptide = Peptidoform::pro_forma("PEPT[C:my modification]IDE", Some(&database)).unwrap();
+//! save_custom_database(&database, "custom_modifications_copy.json.gz").unwrap();
+//! ```
 
-use std::sync::OnceLock;
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufWriter, Read, Write},
+    path::Path,
+    sync::{Arc, OnceLock},
+};
 
+use flate2::{write::GzEncoder, Compression};
 use itertools::Itertools;
 
 pub use crate::modification::OntologyModificationList;
 use crate::{
     error::{Context, CustomError},
+    helper_functions::{check_extension, create_file, open_reader},
     modification::{Ontology, SimpleModification},
+    obo::OboOntology,
+    ontology_modification::OntologyModification,
+    placement_rule::Position,
+    system::Mass,
+    AminoAcid, Chemical, MassMode, Tolerance,
 };
 
 /// A database of custom modifications
 pub type CustomDatabase = OntologyModificationList;
 
+/// A database of custom modifications that can be cheaply cloned to share it between threads,
+/// use it by passing `Some(&database)` wherever an `Option<&CustomDatabase>` is expected.
+/// ```rust
+/// # use rustyms::{*, ontologies::*};
+/// let database: SharedCustomDatabase = SharedCustomDatabase::new(CustomDatabase::new());
+/// let handles = (0..2)
+///     .map(|_| {
+///         let database = database.clone();
+///         std::thread::spawn(move || {
+///             Peptidoform::pro_forma("PEPTIDE", Some(&database)).is_ok()
+///         })
+///     })
+///     .collect::<Vec<_>>();
+/// assert!(handles.into_iter().all(|h| h.join().unwrap()));
+/// ```
+pub type SharedCustomDatabase = Arc<CustomDatabase>;
+
+/// Load a custom database from a JSON file, gzipped files are uncompressed automatically. See
+/// [`save_custom_database`] for the inverse operation.
+/// # Errors
+/// If the file could not be opened or if it is not a valid custom database.
+pub fn load_custom_database(path: impl AsRef<Path>) -> Result<CustomDatabase, CustomError> {
+    read_custom_database(open_reader(path)?)
+}
+
+/// Read a custom database in JSON format from a reader, see [`load_custom_database`].
+/// # Errors
+/// If the reader could not be read or if it does not contain a valid custom database.
+pub fn read_custom_database(reader: impl Read) -> Result<CustomDatabase, CustomError> {
+    let mut database: CustomDatabase = serde_json::from_reader(reader).map_err(|e| {
+        CustomError::error(
+            "Invalid custom database",
+            format!("The custom database could not be read: {e}"),
+            Context::none(),
+        )
+    })?;
+    // The names are used for case insensitive lookups
+    for (_, name, _) in &mut database {
+        name.make_ascii_lowercase();
+    }
+    Ok(database)
+}
+
+/// Save a custom database as a JSON file, if the path has the extension `gz` the file is
+/// compressed. The file can be loaded again with [`load_custom_database`].
+/// # Errors
+/// If the file could not be created or written.
+pub fn save_custom_database(
+    database: &CustomDatabase,
+    path: impl AsRef<Path>,
+) -> Result<(), CustomError> {
+    let file = create_file(path.as_ref()).map_err(|e| {
+        CustomError::error(
+            "Could not create file",
+            e,
+            Context::show(path.as_ref().to_string_lossy()),
+        )
+    })?;
+    if check_extension(&path, "gz") {
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        write_custom_database(database, &mut encoder)?;
+        encoder.finish().map(|_| ()).map_err(|e| {
+            CustomError::error(
+                "Could not write file",
+                e,
+                Context::show(path.as_ref().to_string_lossy()),
+            )
+        })
+    } else {
+        write_custom_database(database, BufWriter::new(file))
+    }
+}
+
+/// Write a custom database in JSON format to a writer, see [`save_custom_database`].
+/// # Errors
+/// If the writer could not be written to.
+pub fn write_custom_database(
+    database: &CustomDatabase,
+    mut writer: impl Write,
+) -> Result<(), CustomError> {
+    serde_json::to_writer_pretty(&mut writer, database)
+        .map_err(std::io::Error::from)
+        .and_then(|()| writer.flush())
+        .map_err(|e| CustomError::error("Could not write custom database", e, Context::none()))
+}
+
 /// An empty list of modifications (needed for lifetime reasons)
 static EMPTY_LIST: OntologyModificationList = Vec::new();
 
@@ -95,6 +230,11 @@ impl Ontology {
         custom_database: Option<&CustomDatabase>,
     ) -> Option<SimpleModification> {
         let code = code.to_ascii_lowercase();
+        if let Some(index) = self.name_index() {
+            return index
+                .get(&code)
+                .map(|i| self.lookup(custom_database)[*i].2.clone());
+        }
         for option in self.lookup(custom_database) {
             if option.1 == code {
                 return Some(option.2.clone());
@@ -103,6 +243,26 @@ impl Ontology {
         None
     }
 
+    /// Get the index from name to position in the lookup list for the built in ontologies, this
+    /// is built on first use. The first occurrence is used if a name occurs multiple times.
+    fn name_index(self) -> Option<&'static HashMap<String, usize>> {
+        let cell = match self {
+            Self::Gnome => &NAME_INDEX_CELLS[0],
+            Self::Psimod => &NAME_INDEX_CELLS[1],
+            Self::Unimod => &NAME_INDEX_CELLS[2],
+            Self::Resid => &NAME_INDEX_CELLS[3],
+            Self::Xlmod => &NAME_INDEX_CELLS[4],
+            Self::Custom => return None,
+        };
+        Some(cell.get_or_init(|| {
+            let mut index = HashMap::new();
+            for (i, option) in self.lookup(None).iter().enumerate() {
+                index.entry(option.1.clone()).or_insert(i);
+            }
+            index
+        }))
+    }
+
     /// Find the given id in this ontology
     pub fn find_id(
         self,
@@ -116,6 +276,147 @@ impl Ontology {
         }
         None
     }
+
+    /// Find all modifications in all ontologies (and the custom database if given) with a mass
+    /// (using the given mass mode) within the tolerance of the given mass, see
+    /// [`crate::modification_search_mass`]. If a placement is given only modifications that can be
+    /// placed on that amino acid at that position are returned. The candidates are ranked on the
+    /// absolute difference in mass, with the most similar first. Ties are ordered on ontology
+    /// (Unimod, PSI-MOD, GNOme, XLMOD, RESID, then custom) and then on the order within the
+    /// ontology. Every candidate is returned with its mass minus the given mass.
+    pub fn find_by_mass(
+        mass: Mass,
+        tolerance: Tolerance<Mass>,
+        placement: Option<(AminoAcid, Position)>,
+        mass_mode: MassMode,
+        custom_database: Option<&CustomDatabase>,
+    ) -> Vec<(SimpleModification, Mass)> {
+        let positions = placement.map(|(aa, position)| [(vec![aa], position)]);
+        let mut candidates = crate::modification_search_mass(
+            mass,
+            tolerance,
+            positions.as_ref().map(<[_; 1]>::as_slice),
+            mass_mode,
+            custom_database,
+        )
+        .map(|(_, _, _, modification)| {
+            let difference = modification.formula().mass(mass_mode) - mass;
+            (modification, difference)
+        })
+        .collect_vec();
+        candidates.sort_by(|a, b| a.1.value.abs().total_cmp(&b.1.value.abs()));
+        candidates
+    }
+
+    /// Parse the modifications for this ontology from an OBO file, gzipped files are
+    /// uncompressed automatically. Only Unimod, PSI-MOD, and XLMOD can be parsed. The resulting
+    /// list can be used as a [`CustomDatabase`] or it can replace the built in ontology with
+    /// [`Self::set_database`].
+    /// # Errors
+    /// If this ontology cannot be parsed from a file, if the file could not be opened, or if it
+    /// is not a valid OBO file for this ontology.
+    pub fn parse_file(
+        self,
+        path: impl AsRef<Path>,
+    ) -> Result<OntologyModificationList, CustomError> {
+        self.check_loadable()?;
+        self.parse_obo(&OboOntology::from_file(path)?)
+    }
+
+    /// Parse the modifications for this ontology from an OBO file, see [`Self::parse_file`].
+    /// # Errors
+    /// If this ontology cannot be parsed from a file, if the reader could not be read, or if it
+    /// is not a valid OBO file for this ontology.
+    pub fn parse_reader(
+        self,
+        reader: impl BufRead,
+    ) -> Result<OntologyModificationList, CustomError> {
+        self.check_loadable()?;
+        self.parse_obo(&OboOntology::from_raw(reader)?)
+    }
+
+    /// Parse the modifications for this ontology from an OBO file and use these instead of the
+    /// built in ontology, see [`Self::parse_file`] and [`Self::set_database`].
+    /// # Errors
+    /// If the file could not be parsed or if the ontology could not be replaced.
+    pub fn load_file(self, path: impl AsRef<Path>) -> Result<(), CustomError> {
+        self.set_database(self.parse_file(path)?)
+    }
+
+    /// Use the given modifications instead of the built in ontology. This has to be done before
+    /// the ontology is used for the first time, as the built in ontology is loaded on first use
+    /// and cannot be changed afterwards. The list is used as is, so make sure all modifications
+    /// have the correct ontology set in their id.
+    /// # Errors
+    /// If this is the custom ontology (pass the custom database to the functions that need it
+    /// instead) or if the ontology was already loaded.
+    pub fn set_database(self, database: OntologyModificationList) -> Result<(), CustomError> {
+        let cell = match self {
+            Self::Gnome => &GNOME_CELL,
+            Self::Psimod => &PSIMOD_CELL,
+            Self::Unimod => &UNIMOD_CELL,
+            Self::Resid => &RESID_CELL,
+            Self::Xlmod => &XLMOD_CELL,
+            Self::Custom => {
+                return Err(CustomError::error(
+                    "Cannot set the custom database",
+                    "The custom database is not stored globally, pass it to the functions that need it instead",
+                    Context::none(),
+                ))
+            }
+        };
+        cell.set(database).map_err(|_| {
+            CustomError::error(
+                "Ontology already loaded",
+                format!(
+                    "{} was already used so it cannot be replaced anymore, set the database before using it",
+                    self.name()
+                ),
+                Context::none(),
+            )
+        })
+    }
+
+    /// Check if this ontology can be parsed from a file
+    /// # Errors
+    /// If this is not Unimod, PSI-MOD, or XLMOD.
+    fn check_loadable(self) -> Result<(), CustomError> {
+        if matches!(self, Self::Unimod | Self::Psimod | Self::Xlmod) {
+            Ok(())
+        } else {
+            Err(CustomError::error(
+                "Unsupported ontology",
+                format!(
+                    "{} cannot be parsed from a file, only Unimod, PSI-MOD, and XLMOD are supported",
+                    self.name()
+                ),
+                Context::none(),
+            ))
+        }
+    }
+
+    /// Parse the modifications for this ontology from the given OBO file
+    /// # Errors
+    /// If any term is not valid for this ontology.
+    /// # Panics
+    /// If this ontology cannot be parsed from a file, use [`Self::check_loadable`] first.
+    fn parse_obo(self, obo: &OboOntology) -> Result<OntologyModificationList, CustomError> {
+        let modifications = match self {
+            Self::Unimod => OntologyModification::parse_unimod(obo)?,
+            Self::Psimod => OntologyModification::parse_psi_mod(obo)?,
+            Self::Xlmod => OntologyModification::parse_xlmod(obo)?,
+            _ => unreachable!(),
+        };
+        let mut list = modifications
+            .into_iter()
+            .map(OntologyModification::into_mod)
+            .collect_vec();
+        if self == Self::Xlmod {
+            // Same order as the built in ontology
+            list.sort_unstable();
+        }
+        Ok(list)
+    }
 }
 
 /// Get the unimod ontology
@@ -155,3 +456,287 @@ static PSIMOD_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
 static GNOME_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
 static RESID_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
 static XLMOD_CELL: OnceLock<OntologyModificationList> = OnceLock::new();
+static NAME_INDEX_CELLS: [OnceLock<HashMap<String, usize>>; 5] = [
+    OnceLock::new(),
+    OnceLock::new(),
+    OnceLock::new(),
+    OnceLock::new(),
+    OnceLock::new(),
+];
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::Chemical;
+
+    const fn assert_send_sync<T: Send + Sync>() {}
+
+    #[test]
+    fn send_sync() {
+        assert_send_sync::<SharedCustomDatabase>();
+        assert_send_sync::<SimpleModification>();
+        assert_send_sync::<crate::Modification>();
+        assert_send_sync::<crate::MolecularFormula>();
+        assert_send_sync::<crate::Multi<crate::MolecularFormula>>();
+        assert_send_sync::<crate::CompoundPeptidoformIon>();
+        assert_send_sync::<crate::Peptidoform<crate::Linear>>();
+        assert_send_sync::<crate::Fragment>();
+        assert_send_sync::<crate::Model>();
+        assert_send_sync::<crate::RawSpectrum>();
+        assert_send_sync::<crate::AnnotatedSpectrum>();
+        #[cfg(feature = "identification")]
+        assert_send_sync::<crate::identification::IdentifiedPeptide>();
+    }
+
+    #[test]
+    fn shared_lookup() {
+        let database: SharedCustomDatabase = Arc::new(vec![(
+            Some(0),
+            "test".to_string(),
+            Ontology::Unimod.find_id(35, None).unwrap(),
+        )]);
+        let worker = {
+            let database = database.clone();
+            std::thread::spawn(move || Ontology::Custom.find_name("test", Some(&database)))
+        };
+        let found = worker.join().unwrap();
+        assert!(found.is_some());
+        assert_eq!(found, Ontology::Custom.find_name("test", Some(&database)));
+    }
+
+    #[test]
+    fn parse_obo() {
+        let unimod = Ontology::Unimod
+            .parse_reader(
+                "format-version: 1.2
+
+[Term]
+id: UNIMOD:21
+name: Phospho
+def: \"Phosphorylation.\" [RESID:AA0036, UNIMODURL:http\\://www.unimod.org/modifications_view.php?editid1=21]
+xref: delta_composition \"H O(3) P\"
+xref: spec_1_site \"T\"
+xref: spec_1_position \"Anywhere\"
+xref: spec_1_neutral_loss_98_composition \"H(3) O(4) P\"
+xref: spec_1_neutral_loss_0_composition \"0\"
+xref: spec_1_site \"S\"
+xref: spec_2_site \"Y\"
+xref: spec_2_position \"Anywhere\"
+is_a: UNIMOD:0 ! unimod root node
+"
+                .as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(unimod.len(), 1);
+        assert_eq!((unimod[0].0, unimod[0].1.as_str()), (Some(21), "phospho"));
+        assert_eq!(
+            unimod[0].2.formula(),
+            Ontology::Unimod.find_id(21, None).unwrap().formula()
+        );
+        let crate::modification::SimpleModificationInner::Database {
+            specificities, id, ..
+        } = &*unimod[0].2
+        else {
+            panic!("Phospho should be a modification")
+        };
+        assert_eq!(specificities.len(), 2);
+        assert_eq!(id.cross_ids.len(), 1);
+        assert_eq!(
+            Ontology::Custom.find_name("Phospho", Some(&unimod)),
+            Some(unimod[0].2.clone())
+        );
+
+        let psimod = Ontology::Psimod
+            .parse_reader(
+                "[Term]
+id: MOD:00046
+name: O-phospho-L-serine
+synonym: \"OPSer\" EXACT PSI-MOD-label []
+property_value: DiffFormula \"C 0 H 1 N 0 O 3 P 1\" xsd:string
+property_value: DiffMono \"79.966331\" xsd:float
+property_value: Origin \"S\" xsd:string
+"
+                .as_bytes(),
+            )
+            .unwrap();
+        assert_eq!(psimod[0].0, Some(46));
+        assert_eq!(
+            psimod[0].2.formula(),
+            Ontology::Psimod.find_id(46, None).unwrap().formula()
+        );
+
+        let xlmod = Ontology::Xlmod
+            .parse_reader(
+                "[Term]
+id: XLMOD:02001
+name: DSS
+property_value: bridgeFormula: \"C8 H10 O2\" xsd:string
+property_value: monoIsotopicMass: \"138.06807961\" xsd:double
+property_value: reactionSites: \"2\" xsd:nonNegativeInteger
+property_value: spacerLength: \"11.4\" xsd:float
+"
+                .as_bytes(),
+            )
+            .unwrap();
+        assert!(matches!(
+            &*xlmod[0].2,
+            crate::modification::SimpleModificationInner::Linker { length, .. }
+                if *length == Some(ordered_float::OrderedFloat(11.4))
+        ));
+        assert_eq!(
+            xlmod[0].2.formula(),
+            Ontology::Xlmod.find_id(2001, None).unwrap().formula()
+        );
+    }
+
+    #[test]
+    fn xlmod_linkers() {
+        use crate::modification::{LinkerSpecificity, SimpleModificationInner};
+        // DSSO only defines its specificities through its reactive group
+        let Some(SimpleModificationInner::Linker { specificities, .. }) =
+            Ontology::Xlmod.find_id(2126, None).as_deref().cloned()
+        else {
+            panic!("DSSO should be a cross-linker")
+        };
+        assert!(
+            matches!(&specificities[..], [LinkerSpecificity::Symmetric(rules, _, _)] if !rules.is_empty())
+        );
+        // EDC has a single heterofunctional reactive group
+        let Some(SimpleModificationInner::Linker { specificities, .. }) =
+            Ontology::Xlmod.find_id(2010, None).as_deref().cloned()
+        else {
+            panic!("EDC should be a cross-linker")
+        };
+        assert!(matches!(
+            &specificities[..],
+            [LinkerSpecificity::Asymmetric(..)]
+        ));
+
+        let xlmod = Ontology::Xlmod
+            .parse_reader(
+                "[Term]
+id: XLMOD:00101
+name: NHS ester
+property_value: specificities: \"(K,Protein N-term)\" xsd:string
+
+[Term]
+id: XLMOD:00035
+name: CID cleavable
+
+[Term]
+id: XLMOD:09999
+name: test linker
+property_value: monoIsotopicMass: \"150.0\" xsd:double
+property_value: CID_Fragment: \"50.0\" xsd:double
+property_value: CID_Fragment: \"100.0\" xsd:double
+property_value: reporterMass: \"120.0\" xsd:double
+property_value: reactionSites: \"2\" xsd:nonNegativeInteger
+relationship: has_reactive_group XLMOD:00101 ! NHS ester
+relationship: is_cleavable XLMOD:00035 ! CID cleavable
+"
+                .as_bytes(),
+            )
+            .unwrap();
+        let linker = xlmod
+            .iter()
+            .find(|(id, _, _)| *id == Some(9999))
+            .expect("The linker should be parsed");
+        let SimpleModificationInner::Linker { specificities, .. } = &*linker.2 else {
+            panic!("The test linker should be a cross-linker")
+        };
+        let [LinkerSpecificity::Symmetric(rules, stubs, diagnostic_ions)] = &specificities[..]
+        else {
+            panic!("The test linker should have one symmetric specificity")
+        };
+        assert_eq!(rules.len(), 2);
+        assert_eq!(stubs.len(), 1);
+        assert_eq!(diagnostic_ions.len(), 1);
+    }
+
+    #[test]
+    fn custom_database_round_trip() {
+        let database: CustomDatabase = vec![
+            (
+                Some(1),
+                "dsso".to_string(),
+                Ontology::Xlmod.find_id(2126, None).unwrap(),
+            ),
+            (
+                Some(2),
+                "phospho".to_string(),
+                Ontology::Unimod.find_id(21, None).unwrap(),
+            ),
+        ];
+        let mut buffer = Vec::new();
+        write_custom_database(&database, &mut buffer).unwrap();
+        let read = read_custom_database(buffer.as_slice()).unwrap();
+        assert_eq!(read, database);
+        assert_eq!(
+            Ontology::Custom.find_name("DSSO", Some(&read)),
+            Ontology::Xlmod.find_id(2126, None)
+        );
+        assert!(read_custom_database(b"[[1, \"test\"]]".as_slice()).is_err());
+    }
+
+    #[test]
+    fn find_by_mass() {
+        let phospho = Ontology::Unimod.find_id(21, None).unwrap();
+        let candidates = Ontology::find_by_mass(
+            phospho.formula().monoisotopic_mass(),
+            Tolerance::new_ppm(10.0),
+            Some((AminoAcid::Serine, Position::Anywhere)),
+            MassMode::Monoisotopic,
+            None,
+        );
+        assert_eq!(candidates.first().map(|c| &c.0), Some(&phospho));
+        assert!(candidates
+            .windows(2)
+            .all(|w| w[0].1.value.abs() <= w[1].1.value.abs()));
+        assert!(candidates.iter().all(|c| c
+            .0
+            .is_possible_aa(AminoAcid::Serine, Position::Anywhere)
+            .any_possible()));
+        // Phosphorylation is not possible on a glycine
+        assert!(!Ontology::find_by_mass(
+            phospho.formula().monoisotopic_mass(),
+            Tolerance::new_ppm(10.0),
+            Some((AminoAcid::Glycine, Position::Anywhere)),
+            MassMode::Monoisotopic,
+            None,
+        )
+        .iter()
+        .any(|c| c.0 == phospho));
+    }
+
+    #[test]
+    fn parse_obo_errors() {
+        assert!(Ontology::Unimod
+            .parse_reader(b"[Term]\nname: Phospho\n".as_slice())
+            .is_err());
+        assert!(Ontology::Psimod
+            .parse_reader(
+                b"[Term]\nid: MOD:1\nname: x\nproperty_value: TermSpec \"X\" xsd:string\n"
+                    .as_slice()
+            )
+            .is_err());
+        assert!(Ontology::Gnome.parse_reader(b"".as_slice()).is_err());
+        // A huge specificity index does not allocate all rules up to that index
+        let unimod = Ontology::Unimod
+            .parse_reader(
+                b"[Term]\nid: UNIMOD:1\nname: x\nxref: delta_composition \"H\"\nxref: spec_999999999_site \"S\"\nxref: spec_999999999_position \"Anywhere\"\n"
+                    .as_slice(),
+            )
+            .unwrap();
+        let crate::modification::SimpleModificationInner::Database { specificities, .. } =
+            &*unimod[0].2
+        else {
+            panic!("x should be a modification")
+        };
+        assert_eq!(specificities.len(), 1);
+        assert!(Ontology::Custom.set_database(Vec::new()).is_err());
+        // Already loaded, so it cannot be replaced
+        assert!(Ontology::Unimod.find_id(21, None).is_some());
+        assert!(Ontology::Unimod.set_database(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,20 @@
+//! Parsing of modifications from ontology files, shared with the database generation
+
+use std::{collections::BTreeMap, sync::Arc};
+
+use itertools::Itertools;
+use ordered_float::OrderedFloat;
+use regex::Regex;
+use thin_vec::ThinVec;
+
+use crate::{
+    error::{Context, CustomError},
+    modification::{
+        LinkerSpecificity, ModificationId, Ontology, SimpleModification, SimpleModificationInner,
+    },
+    obo::{OboObject, OboOntology, OboValue},
+    placement_rule::{PlacementRule, Position},
+    AminoAcid, DiagnosticIon, MolecularFormula, NeutralLoss,
+};
+
+include!("shared/ontology_modification.rs");
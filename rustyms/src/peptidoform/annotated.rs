@@ -54,6 +54,8 @@ pub enum Region {
     ConstantLight,
     SecratoryTail,
     MembraneTail(Option<usize>),
+    /// The signal peptide, cleaved off in the mature protein
+    SignalPeptide,
     Other(String),
     /// When multiple regions are joined, or when the exact boundary is not known
     Joined(Vec<Region>),
@@ -81,6 +83,7 @@ impl std::fmt::Display for Region {
             Self::ConstantLight => write!(f, "CL"),
             Self::SecratoryTail => write!(f, "CHS"),
             Self::MembraneTail(n) => write!(f, "M{}", n.map_or(String::new(), |n| n.to_string())),
+            Self::SignalPeptide => write!(f, "SP"),
             Self::Other(o) => write!(f, "{o}"),
             Self::Joined(o) => write!(f, "{}", o.iter().join("-")),
             Self::None => Ok(()),
@@ -97,6 +100,7 @@ impl std::str::FromStr for Region {
             "CHS" => Self::SecratoryTail,
             "H" => Self::Hinge(None),
             "M" => Self::MembraneTail(None),
+            "SP" => Self::SignalPeptide,
             cdr if cdr.starts_with("CDR") => cdr[3..]
                 .parse::<usize>()
                 .map_or(Self::Other(cdr.to_string()), |c| {
@@ -4,8 +4,8 @@ use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    peptidoform::Linked, system::usize::Charge, Fragment, Model, MolecularFormula, Multi,
-    Peptidoform, PeptidoformIon,
+    peptidoform::Linked, system::usize::Charge, ComplexityExceeded, ComplexityKind,
+    ComplexityLimits, Fragment, Model, MolecularFormula, Multi, Peptidoform, PeptidoformIon,
 };
 
 /// A single full ProForma entry. This entry can contain multiple sets of cross-linked peptides.
@@ -34,6 +34,58 @@ impl CompoundPeptidoformIon {
         self.0.iter().flat_map(|p| p.formulas().to_vec()).collect()
     }
 
+    /// Check the complexity of this compound peptidoform against the given limits, without
+    /// calculating any formulas or fragments. The number of formulas is estimated from the
+    /// ambiguous amino acids and modifications of unknown position.
+    /// # Errors
+    /// If the number of combinations of modifications of unknown position of any peptidoform or
+    /// the estimated number of formulas is higher than the limits.
+    pub fn check_complexity(&self, limits: &ComplexityLimits) -> Result<(), ComplexityExceeded> {
+        for peptidoform in self.peptidoforms() {
+            limits.check(
+                ComplexityKind::AmbiguousCombinations,
+                peptidoform.ambiguous_combinations(),
+            )?;
+        }
+        let formulas = self.0.iter().fold(0_usize, |acc, ion| {
+            acc.saturating_add(Self::estimated_formulas(ion))
+        });
+        limits.check(ComplexityKind::Formulas, formulas)
+    }
+
+    /// Estimate the number of formulas for a single peptidoform ion, saturating at [`usize::MAX`].
+    fn estimated_formulas(ion: &PeptidoformIon) -> usize {
+        ion.peptidoforms()
+            .iter()
+            .fold(1_usize, |acc, p| acc.saturating_mul(p.estimated_formulas()))
+    }
+
+    /// Estimate the number of theoretical fragments without generating them. This is a lower
+    /// bound of one fragment per formula per cleavage site per charge, saturating at [`usize::MAX`].
+    fn estimated_fragments(&self, max_charge: Charge) -> usize {
+        self.0.iter().fold(0_usize, |acc, ion| {
+            acc.saturating_add(
+                Self::estimated_formulas(ion)
+                    .saturating_mul(ion.peptidoforms().iter().map(Peptidoform::len).sum())
+                    .saturating_mul(max_charge.value.max(1)),
+            )
+        })
+    }
+
+    /// Get all possible formulas for this compound peptidoform, but only if the complexity is
+    /// within the given limits, see [`Self::check_complexity`].
+    /// # Errors
+    /// If any of the limits is exceeded.
+    pub fn try_formulas(
+        &self,
+        limits: &ComplexityLimits,
+    ) -> Result<Multi<MolecularFormula>, ComplexityExceeded> {
+        self.check_complexity(limits)?;
+        let formulas = self.formulas();
+        limits.check(ComplexityKind::Formulas, formulas.len())?;
+        Ok(formulas)
+    }
+
     /// Assume there is exactly one peptidoform in this compound peptidoform.
     #[doc(alias = "assume_linear")]
     pub fn singular(mut self) -> Option<PeptidoformIon> {
@@ -60,6 +112,10 @@ impl CompoundPeptidoformIon {
     }
 
     /// Generate the theoretical fragments for this compound peptidoform.
+    ///
+    /// The order of the fragments only depends on the peptidoform and the model, so the output is
+    /// identical between runs. This also holds for the fragments generated for a single
+    /// [`PeptidoformIon`] or [`Peptidoform`](crate::Peptidoform).
     pub fn generate_theoretical_fragments(
         &self,
         max_charge: Charge,
@@ -72,6 +128,31 @@ impl CompoundPeptidoformIon {
         base
     }
 
+    /// Generate the theoretical fragments for this compound peptidoform, but only if the
+    /// complexity is within the given limits, see [`Self::check_complexity`]. The number of
+    /// fragments is estimated before generation and checked again after each peptidoform ion, so
+    /// generation stops as soon as the limit is exceeded.
+    /// # Errors
+    /// If any of the limits is exceeded.
+    pub fn try_generate_theoretical_fragments(
+        &self,
+        max_charge: Charge,
+        model: &Model,
+        limits: &ComplexityLimits,
+    ) -> Result<Vec<Fragment>, ComplexityExceeded> {
+        self.check_complexity(limits)?;
+        limits.check(
+            ComplexityKind::Fragments,
+            self.estimated_fragments(max_charge),
+        )?;
+        let mut base = Vec::new();
+        for (index, peptidoform) in self.peptidoform_ions().iter().enumerate() {
+            base.extend(peptidoform.generate_theoretical_fragments_inner(max_charge, model, index));
+            limits.check(ComplexityKind::Fragments, base.len())?;
+        }
+        Ok(base)
+    }
+
     /// Display this compound peptidoform.
     /// `specification_compliant` Displays this compound peptidoform either normalised to the
     /// internal representation (with false) or as fully spec compliant ProForma (no glycan
@@ -7,8 +7,9 @@ use crate::{
     modification::{GnoComposition, Ontology, SimpleModification, SimpleModificationInner},
     ontologies::CustomDatabase,
     placement_rule::Position,
-    system::{ratio::ppm, Mass, Ratio},
-    AminoAcid, Chemical, MassMode, Modification, MolecularFormula, Tolerance, WithinTolerance,
+    system::Mass,
+    AminoAcid, Chemical, MassMode, Modification, MolecularFormula, Precision, Tolerance,
+    WithinTolerance,
 };
 
 use super::Peptidoform;
@@ -146,7 +147,7 @@ impl Default for PeptideModificationSearch {
             replace_formulas: false,
             allow_terminal_redefinition: true,
             mass_mode: MassMode::Monoisotopic,
-            tolerance: Tolerance::Relative(Ratio::new::<ppm>(10.0).into()),
+            tolerance: Precision::global().tolerance(),
             modifications: Vec::new(),
             ontologies: Vec::new(),
             custom_database: None,
@@ -177,7 +178,8 @@ impl PeptideModificationSearch {
         }
     }
 
-    /// Set the tolerance of matches, default is 10 ppm
+    /// Set the tolerance of matches, default is the comparison tolerance of the global
+    /// [`Precision`] policy (10 ppm)
     #[must_use]
     pub fn tolerance(self, tolerance: Tolerance<Mass>) -> Self {
         Self {
@@ -0,0 +1,180 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{AminoAcid, Peptidoform};
+
+/// A candidate glycosylation site in a sequence, see [`Peptidoform::glycosylation_sites`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct GlycosylationSite {
+    /// The index of the glycosylated residue (N, S, or T) in the sequence
+    pub position: usize,
+    /// The motif that identified this site
+    pub kind: GlycosylationSiteKind,
+}
+
+/// The motif that identified a candidate glycosylation site.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum GlycosylationSiteKind {
+    /// The canonical N-glycosylation sequon N-X-S/T with X any amino acid except P
+    NSequon,
+    /// The atypical N-glycosylation sequon N-X-C with X any amino acid except P
+    NSequonAtypical,
+    /// An O-glycosylation site: S/T with a proline at position -1 or +3
+    OProline,
+    /// An O-glycosylation site: S/T in a mucin-like stretch, at least four of the six surrounding
+    /// residues (-3 to +3) are S, T, or P
+    OMucinLike,
+}
+
+impl GlycosylationSiteKind {
+    /// Check if this is an N-glycosylation site
+    pub const fn is_n_glycosylation(self) -> bool {
+        matches!(self, Self::NSequon | Self::NSequonAtypical)
+    }
+
+    /// Check if this is an O-glycosylation site
+    pub const fn is_o_glycosylation(self) -> bool {
+        matches!(self, Self::OProline | Self::OMucinLike)
+    }
+}
+
+impl<Complexity> Peptidoform<Complexity> {
+    /// Find all N-glycosylation sequons (N-X-S/T and the atypical N-X-C, where X is not P) in
+    /// this sequence. Only complete sequons are reported, so an N in one of the last two
+    /// positions is never a site, which is something to keep in mind when looking at peptides
+    /// instead of full protein sequences. Ambiguous amino acids (B, X, etc.) never match.
+    pub fn n_glycosylation_sites(&self) -> Vec<GlycosylationSite> {
+        let sequence = self.amino_acids();
+        sequence
+            .windows(3)
+            .enumerate()
+            .filter_map(|(position, window)| match window {
+                [AminoAcid::Asparagine, x, AminoAcid::Serine | AminoAcid::Threonine]
+                    if *x != AminoAcid::Proline =>
+                {
+                    Some(GlycosylationSite {
+                        position,
+                        kind: GlycosylationSiteKind::NSequon,
+                    })
+                }
+                [AminoAcid::Asparagine, x, AminoAcid::Cysteine] if *x != AminoAcid::Proline => {
+                    Some(GlycosylationSite {
+                        position,
+                        kind: GlycosylationSiteKind::NSequonAtypical,
+                    })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Find candidate O-glycosylation sites in this sequence. O-glycosylation does not have a
+    /// consensus motif so this uses two common heuristics: an S/T with a proline at position -1
+    /// or +3, or an S/T in a mucin-like S/T/P rich stretch (see [`GlycosylationSiteKind`]). If
+    /// both apply to a residue it is reported as [`GlycosylationSiteKind::OProline`].
+    pub fn o_glycosylation_sites(&self) -> Vec<GlycosylationSite> {
+        let sequence = self.amino_acids();
+        let is = |index: Option<usize>, options: &[AminoAcid]| {
+            index
+                .and_then(|i| sequence.get(i))
+                .is_some_and(|aa| options.contains(aa))
+        };
+        (0..sequence.len())
+            .filter(|position| is(Some(*position), &[AminoAcid::Serine, AminoAcid::Threonine]))
+            .filter_map(|position| {
+                if is(position.checked_sub(1), &[AminoAcid::Proline])
+                    || is(Some(position + 3), &[AminoAcid::Proline])
+                {
+                    Some(GlycosylationSiteKind::OProline)
+                } else {
+                    let surrounding = (position.saturating_sub(3)..=position + 3)
+                        .filter(|i| *i != position)
+                        .filter(|i| {
+                            is(
+                                Some(*i),
+                                &[AminoAcid::Serine, AminoAcid::Threonine, AminoAcid::Proline],
+                            )
+                        })
+                        .count();
+                    (surrounding >= 4).then_some(GlycosylationSiteKind::OMucinLike)
+                }
+                .map(|kind| GlycosylationSite { position, kind })
+            })
+            .collect()
+    }
+
+    /// Find all candidate N- and O-glycosylation sites in this sequence, sorted on position. See
+    /// [`Self::n_glycosylation_sites`] and [`Self::o_glycosylation_sites`] for the motifs used.
+    /// These positions can be used for targeted placement of glycan modifications.
+    /// ```rust
+    /// # use rustyms::{*, peptidoform::GlycosylationSiteKind};
+    /// let peptide = Peptidoform::pro_forma("ANGTPAANPA", None).unwrap();
+    /// let sites = peptide.glycosylation_sites();
+    /// assert_eq!(sites.len(), 1);
+    /// assert_eq!(sites[0].position, 1);
+    /// assert_eq!(sites[0].kind, GlycosylationSiteKind::NSequon);
+    /// ```
+    pub fn glycosylation_sites(&self) -> Vec<GlycosylationSite> {
+        let mut sites = self.n_glycosylation_sites();
+        sites.extend(self.o_glycosylation_sites());
+        sites.sort_unstable();
+        sites
+    }
+
+    /// Get the plain amino acids of this sequence
+    fn amino_acids(&self) -> Vec<AminoAcid> {
+        self.sequence()
+            .iter()
+            .map(|seq| seq.aminoacid.aminoacid())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    fn sites(sequence: &str) -> Vec<(usize, GlycosylationSiteKind)> {
+        Peptidoform::pro_forma(sequence, None)
+            .unwrap()
+            .glycosylation_sites()
+            .into_iter()
+            .map(|s| (s.position, s.kind))
+            .collect()
+    }
+
+    #[test]
+    fn n_sequons() {
+        assert_eq!(sites("ANGSA"), [(1, GlycosylationSiteKind::NSequon)]);
+        assert_eq!(
+            sites("ANGCA"),
+            [(1, GlycosylationSiteKind::NSequonAtypical)]
+        );
+        assert_eq!(sites("ANPCA"), []);
+        assert_eq!(sites("AANG"), []);
+        assert_eq!(
+            sites("NNTGNAS"),
+            [
+                (0, GlycosylationSiteKind::NSequon),
+                (4, GlycosylationSiteKind::NSequon)
+            ]
+        );
+    }
+
+    #[test]
+    fn o_sites() {
+        assert_eq!(sites("APSAA"), [(2, GlycosylationSiteKind::OProline)]);
+        assert_eq!(sites("AASAAPA"), [(2, GlycosylationSiteKind::OProline)]);
+        assert_eq!(sites("AASAAAA"), []);
+        assert_eq!(
+            sites("TSTSTST"),
+            [
+                (1, GlycosylationSiteKind::OMucinLike),
+                (2, GlycosylationSiteKind::OMucinLike),
+                (3, GlycosylationSiteKind::OMucinLike),
+                (4, GlycosylationSiteKind::OMucinLike),
+                (5, GlycosylationSiteKind::OMucinLike),
+            ]
+        );
+    }
+}
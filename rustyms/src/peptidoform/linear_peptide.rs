@@ -2,25 +2,24 @@
 
 use crate::{
     checked_aminoacid::CheckedAminoAcid,
-    fragment::{DiagnosticPosition, Fragment, FragmentType, PeptidePosition},
-    glycan::MonoSaccharide,
+    fragment::{BackboneCleavage, DiagnosticPosition, Fragment, FragmentType, PeptidePosition},
     helper_functions::{peptide_range_contains, RangeExtension},
     modification::{
-        CrossLinkName, GnoComposition, LinkerSpecificity, Modification, SimpleModification,
+        CrossLinkName, LinkerSpecificity, Modification, ModificationProvenance, SimpleModification,
         SimpleModificationInner,
     },
     molecular_charge::{CachedCharge, MolecularCharge},
     peptidoform::*,
-    placement_rule::PlacementRule,
+    placement_rule::{PlacementRule, Position, ProteinTermini},
     system::usize::Charge,
-    AmbiguousLabel, DiagnosticIon, Element, Model, MolecularFormula, Multi, MultiChemical,
-    NeutralLoss, Protease, SequenceElement, SequencePosition,
+    AmbiguousLabel, DiagnosticIon, DigestedPeptidoform, Element, Model, MolecularFormula, Multi,
+    MultiChemical, NeutralLoss, Protease, SequenceElement, SequencePosition,
 };
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashSet,
+    collections::BTreeSet,
     fmt::{Display, Write},
     marker::PhantomData,
     num::NonZeroU16,
@@ -84,7 +83,7 @@ use std::{
 /// PEPTIDE/3[1Zn+2,1H+1]
 /// ```
 ///
-#[derive(PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Peptidoform<Complexity> {
     /// Global isotope modifications, saved as the element and the species that
     /// all occurrence of that element will consist of. For example (N, 15) will
@@ -103,6 +102,9 @@ pub struct Peptidoform<Complexity> {
     modifications_of_unknown_position: Vec<AmbiguousEntry>,
     /// The adduct ions, if specified
     charge_carriers: Option<MolecularCharge>,
+    /// The provenance of all modifications as parsed from ProForma, not used in comparisons
+    #[serde(default)]
+    provenance: Vec<ModificationProvenance>,
     /// The marker indicating which level of complexity this peptide (potentially) uses
     marker: PhantomData<Complexity>,
 }
@@ -130,6 +132,7 @@ impl<Complexity> Default for Peptidoform<Complexity> {
             sequence: Vec::new(),
             modifications_of_unknown_position: Vec::new(),
             charge_carriers: None,
+            provenance: Vec::new(),
             marker: PhantomData,
         }
     }
@@ -145,6 +148,7 @@ impl<Complexity> Clone for Peptidoform<Complexity> {
             sequence: self.sequence.clone(),
             modifications_of_unknown_position: self.modifications_of_unknown_position.clone(),
             charge_carriers: self.charge_carriers.clone(),
+            provenance: self.provenance.clone(),
             marker: PhantomData,
         }
     }
@@ -178,6 +182,29 @@ impl<Complexity> std::hash::Hash for Peptidoform<Complexity> {
 
 impl<Complexity> Eq for Peptidoform<Complexity> {}
 
+impl<Complexity: Ord> PartialOrd for Peptidoform<Complexity> {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<Complexity: Ord> Ord for Peptidoform<Complexity> {
+    /// Compare the same fields as used for equality, so ignoring the provenance
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.global
+            .cmp(&other.global)
+            .then_with(|| self.labile.cmp(&other.labile))
+            .then_with(|| self.n_term.cmp(&other.n_term))
+            .then_with(|| self.c_term.cmp(&other.c_term))
+            .then_with(|| self.sequence.cmp(&other.sequence))
+            .then_with(|| {
+                self.modifications_of_unknown_position
+                    .cmp(&other.modifications_of_unknown_position)
+            })
+            .then_with(|| self.charge_carriers.cmp(&other.charge_carriers))
+    }
+}
+
 /// Implement the complexity checks to reduce the complexity of a peptide in a controlled fashion.
 impl<Complexity> Peptidoform<Complexity> {
     /// Check if this peptide does not use any of the features reserved for [`Linked`].
@@ -304,6 +331,7 @@ impl<Complexity> Peptidoform<Complexity> {
                 .collect(),
             modifications_of_unknown_position: self.modifications_of_unknown_position,
             charge_carriers: self.charge_carriers,
+            provenance: self.provenance,
             marker: PhantomData,
         }
     }
@@ -405,6 +433,44 @@ impl<Complexity> Peptidoform<Complexity> {
         }
     }
 
+    /// Place the given modification on all locations allowed by its placement rules, as is done
+    /// for fixed modifications. Protein terminal rules are only used if the peptide terminus is
+    /// also a protein terminus, as given by `termini` (see [`crate::identification::FastaData::digest`]).
+    /// Terminal locations are only used for terminal rules and modifications without placement
+    /// rules are not placed. Returns the number of locations the modification was placed on.
+    pub fn add_fixed_modification(
+        &mut self,
+        modification: &SimpleModification,
+        termini: ProteinTermini,
+    ) -> usize {
+        let SimpleModificationInner::Database { specificities, .. } = &**modification else {
+            return 0;
+        };
+        let positions = self
+            .iter(..)
+            .filter(|(position, seq)| {
+                specificities
+                    .iter()
+                    .flat_map(|(rules, _, _)| rules)
+                    .any(|rule| {
+                        let terminal = match rule {
+                            PlacementRule::AminoAcid(_, position)
+                            | PlacementRule::PsiModification(_, position)
+                            | PlacementRule::Terminal(position) => *position != Position::Anywhere,
+                            PlacementRule::Anywhere => false,
+                        };
+                        (terminal || matches!(position.sequence_index, SequencePosition::Index(_)))
+                            && rule.is_possible_in_protein(seq, position.sequence_index, termini)
+                    })
+            })
+            .map(|(position, _)| position.sequence_index)
+            .collect_vec();
+        for position in &positions {
+            self.add_simple_modification(*position, modification.clone());
+        }
+        positions.len()
+    }
+
     /// Set the charge carriers, use [`Self::charge_carriers`] unless absolutely necessary.
     pub(super) fn set_charge_carriers(&mut self, charge_carriers: Option<MolecularCharge>) {
         self.charge_carriers = charge_carriers;
@@ -626,7 +692,12 @@ impl<Complexity> Peptidoform<Complexity> {
         applied_cross_links: &mut Vec<CrossLinkName>,
         allow_ms_cleavable: bool,
         peptidoform_index: usize,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
+        let start = match aa_range.start_bound() {
+            std::ops::Bound::Included(start) => *start,
+            std::ops::Bound::Excluded(start) => start + 1,
+            std::ops::Bound::Unbounded => 0,
+        };
         // Calculate all formulas for the selected AA range without any ambiguous modifications
         let (formulas, seen) = self.sequence[(
             aa_range.start_bound().cloned(),
@@ -635,14 +706,14 @@ impl<Complexity> Peptidoform<Complexity> {
             .iter()
             .enumerate()
             .fold(
-                (base.clone(), HashSet::new()),
+                (base.clone(), BTreeSet::new()),
                 |previous_aa_formulas, (index, aa)| {
                     let (f, s) = aa.formulas_base(
                         all_peptides,
                         visited_peptides,
                         applied_cross_links,
                         allow_ms_cleavable,
-                        SequencePosition::Index(index),
+                        SequencePosition::Index(start + index),
                         peptidoform_index,
                     );
                     (
@@ -807,7 +878,7 @@ impl<Complexity> Peptidoform<Complexity> {
             let (modifications_total, modifications_cross_links) = self.sequence[sequence_index]
                 .modifications
                 .iter()
-                .fold((Multi::default(), HashSet::new()), |acc, m| {
+                .fold((Multi::default(), BTreeSet::new()), |acc, m| {
                     let (f, s) = m.formula_inner(
                         all_peptides,
                         &[peptidoform_index],
@@ -830,7 +901,7 @@ impl<Complexity> Peptidoform<Complexity> {
                         &mut charge_carriers,
                         SequencePosition::Index(sequence_index),
                         self.sequence.len(),
-                        &model.ions(position),
+                        &model.ions(position, self),
                         peptidoform_ion_index,
                         peptidoform_index,
                         (
@@ -919,6 +990,41 @@ impl<Complexity> Peptidoform<Complexity> {
             model.precursor.1,
         ));
 
+        // Generate the charge reduced precursors, the precursor at its full charge that captured
+        // some electrons, so that the charge is lower but the mass contains all charge carriers
+        if model.charge_reduced_precursor.0 {
+            let precursor_charge = charge_carriers.charge();
+            for charge in model
+                .charge_reduced_precursor
+                .1
+                .charges_iter(precursor_charge)
+                .filter(|charge| *charge < precursor_charge)
+            {
+                let electrons = precursor_charge.value - charge.value;
+                let electron_formula = molecular_formula!(Electron 1)
+                    * i32::try_from(electrons).expect("Too many electrons captured");
+                output.extend(
+                    Fragment::generate_all(
+                        &full_precursor,
+                        peptidoform_ion_index,
+                        peptidoform_index,
+                        &FragmentType::PrecursorChargeReduced(electrons.unsigned_abs()),
+                        &Multi::default(),
+                        &precursor_neutral_losses,
+                        &mut charge_carriers,
+                        crate::model::ChargeRange::PRECURSOR,
+                    )
+                    .into_iter()
+                    .map(|mut fragment| {
+                        fragment.formula = fragment.formula.map(|f| f + &electron_formula);
+                        fragment.charge =
+                            Charge::new::<crate::system::e>(charge.value.unsigned_abs());
+                        fragment
+                    }),
+                );
+            }
+        }
+
         // Add glycan fragmentation to all peptide fragments
         // Assuming that only one glycan can ever fragment at the same time,
         // and that no peptide fragmentation occurs during glycan fragmentation
@@ -959,6 +1065,7 @@ impl<Complexity> Peptidoform<Complexity> {
                         deviation: None,
                         confidence: None,
                         auxiliary: false,
+                        isotope: 0,
                     }
                     .with_charge_range(
                         &mut charge_carriers,
@@ -970,41 +1077,27 @@ impl<Complexity> Peptidoform<Complexity> {
 
         // Add labile glycan fragments
         for modification in &self.labile {
-            match &**modification {
-                SimpleModificationInner::Glycan(composition) => {
-                    output.extend(MonoSaccharide::theoretical_fragments(
-                        composition,
-                        model,
-                        peptidoform_ion_index,
-                        peptidoform_index,
-                        &mut charge_carriers,
-                        &full_formula,
-                        None,
-                    ));
-                }
-                SimpleModificationInner::GlycanStructure(structure)
-                | SimpleModificationInner::Gno {
-                    composition: GnoComposition::Topology(structure),
-                    ..
-                } => {
-                    output.extend(
-                        structure
-                            .clone()
-                            .determine_positions()
-                            .generate_theoretical_fragments(
-                                model,
-                                peptidoform_ion_index,
-                                peptidoform_index,
-                                &mut charge_carriers,
-                                &full_formula,
-                                None,
-                            ),
-                    );
-                }
-                _ => (),
-            }
+            output.extend(modification.generate_theoretical_fragments(
+                model,
+                peptidoform_ion_index,
+                peptidoform_index,
+                &mut charge_carriers,
+                &full_formula,
+                None,
+            ));
+        }
+
+        #[cfg(feature = "isotopes")]
+        if model.isotope_peaks.0 > 0 {
+            output = output
+                .into_iter()
+                .flat_map(|fragment| {
+                    fragment.with_isotopes(model.isotope_peaks.0, model.isotope_peaks.1)
+                })
+                .collect();
         }
 
+        output.retain(|fragment| model.allows_neutral_loss(fragment));
         output
     }
 
@@ -1023,7 +1116,7 @@ impl<Complexity> Peptidoform<Complexity> {
         applied_cross_links: &mut Vec<CrossLinkName>,
         allow_ms_cleavable: bool,
         peptidoform_index: usize,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
         let (ambiguous_mods_masses, seen) = self.ambiguous_patterns(
             range.clone(),
             aa_range,
@@ -1058,6 +1151,36 @@ impl<Complexity> Peptidoform<Complexity> {
         self.modifications_of_unknown_position.len()
     }
 
+    /// Get the number of ways the modifications of unknown position can be placed, this is an
+    /// upper bound as it does not take the colocalisation rules into account. This saturates at
+    /// [`usize::MAX`].
+    pub(crate) fn ambiguous_combinations(&self) -> usize {
+        self.modifications_of_unknown_position
+            .iter()
+            .fold(1_usize, |acc, entry| {
+                acc.saturating_mul(entry.positions.len().max(1))
+            })
+    }
+
+    /// Estimate the number of formulas for this peptide without calculating them. This takes the
+    /// ambiguous amino acids, modifications with multiple formulas, and modifications of unknown
+    /// position into account. This saturates at [`usize::MAX`].
+    pub(crate) fn estimated_formulas(&self) -> usize {
+        let amino_acids = self
+            .sequence
+            .iter()
+            .fold(self.ambiguous_combinations(), |acc, element| {
+                acc.saturating_mul(element.aminoacid.aminoacid().formulas().len().max(1))
+            });
+        self.n_term
+            .iter()
+            .chain(&self.c_term)
+            .chain(self.sequence.iter().flat_map(|element| &element.modifications))
+            .fold(amino_acids, |acc, modification| {
+                acc.saturating_mul(modification.estimated_formulas())
+            })
+    }
+
     /// Gives all the formulas for the whole peptide with no C and N terminal modifications. With the global isotope modifications applied.
     #[allow(clippy::missing_panics_doc)] // Global isotope mods are guaranteed to be correct
     fn bare_formulas_inner(
@@ -1103,7 +1226,7 @@ impl<Complexity> Peptidoform<Complexity> {
         visited_peptides: &[usize],
         applied_cross_links: &mut Vec<CrossLinkName>,
         allow_ms_cleavable: bool,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
         debug_assert!(
             !visited_peptides.contains(&peptidoform_index),
             "Cannot get the formula for a peptide that is already visited"
@@ -1124,7 +1247,7 @@ impl<Complexity> Peptidoform<Complexity> {
             peptidoform_index,
         );
         let mut placed = vec![false; self.modifications_of_unknown_position.len()];
-        let mut seen = HashSet::new();
+        let mut seen = BTreeSet::new();
         for (index, pos) in self.sequence.iter().enumerate() {
             let (pos_f, pos_seen) = pos.formulas_greedy(
                 &mut placed,
@@ -1336,6 +1459,17 @@ impl<Complexity> Peptidoform<Complexity> {
                     ..m
                 })
                 .collect(),
+            provenance: self
+                .provenance
+                .iter()
+                .map(|p| ModificationProvenance {
+                    position: p.position.map(|loc| match loc {
+                        SequencePosition::Index(i) => SequencePosition::Index(self.len() - 1 - i),
+                        other => other.reverse(self.len()),
+                    }),
+                    ..p.clone()
+                })
+                .collect(),
             ..self.clone()
         }
     }
@@ -1383,25 +1517,55 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
     /// Get a region of this peptide as a new peptide (with all terminal/global/ambiguous modifications).
     #[must_use]
     pub fn sub_peptide(&self, index: impl RangeBounds<usize>) -> Self {
+        let start = match index.start_bound() {
+            std::ops::Bound::Included(start) => *start,
+            std::ops::Bound::Excluded(start) => start + 1,
+            std::ops::Bound::Unbounded => 0,
+        };
+        let contains_c_term = self
+            .len()
+            .checked_sub(1)
+            .is_some_and(|last| index.contains(&last));
         Self {
             n_term: if index.contains(&0) {
                 self.n_term.clone()
             } else {
                 Vec::new()
             },
-            c_term: if index.contains(&(self.len() - 1)) {
+            c_term: if contains_c_term {
                 self.c_term.clone()
             } else {
                 Vec::new()
             },
             sequence: self.sequence[(index.start_bound().cloned(), index.end_bound().cloned())]
                 .to_vec(),
+            provenance: self
+                .provenance
+                .iter()
+                .filter_map(|p| match p.position {
+                    Some(SequencePosition::NTerm) => index.contains(&0).then(|| p.clone()),
+                    Some(SequencePosition::CTerm) => contains_c_term.then(|| p.clone()),
+                    Some(SequencePosition::Index(i)) => {
+                        index.contains(&i).then(|| ModificationProvenance {
+                            position: Some(SequencePosition::Index(i - start)),
+                            ..p.clone()
+                        })
+                    }
+                    None => Some(p.clone()),
+                })
+                .collect(),
             ..self.clone()
         }
     }
 
     /// Digest this sequence with the given protease and the given maximal number of missed cleavages.
-    pub fn digest(&self, protease: &Protease, max_missed_cleavages: usize) -> Vec<Self> {
+    /// Every peptide is returned with its location in this sequence and the number of missed
+    /// cleavages, which can be used to build coverage maps.
+    pub fn digest(
+        &self,
+        protease: &Protease,
+        max_missed_cleavages: usize,
+    ) -> Vec<DigestedPeptidoform<Complexity>> {
         let mut sites = vec![0];
         sites.extend_from_slice(&protease.match_locations(&self.sequence));
         sites.push(self.len());
@@ -1409,8 +1573,18 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
         let mut result = Vec::new();
 
         for (index, start) in sites.iter().enumerate() {
-            for end in sites.iter().skip(index).take(max_missed_cleavages + 1) {
-                result.push(self.sub_peptide((*start)..*end));
+            for (missed_cleavages, end) in sites
+                .iter()
+                .skip(index + 1)
+                .take(max_missed_cleavages + 1)
+                .enumerate()
+            {
+                result.push(DigestedPeptidoform {
+                    peptide: self.sub_peptide((*start)..*end),
+                    start: *start,
+                    end: *end,
+                    missed_cleavages,
+                });
             }
         }
         result
@@ -1433,7 +1607,9 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
     }
 
     /// Generate the theoretical fragments for this peptide, with the given maximal charge of the fragments, and the given model.
-    /// With the global isotope modifications applied.
+    /// With the global isotope modifications applied. The order of the fragments is
+    /// deterministic, see
+    /// [`CompoundPeptidoformIon::generate_theoretical_fragments`](crate::CompoundPeptidoformIon::generate_theoretical_fragments).
     ///
     /// # Panics
     /// If `max_charge` outside the range `1..=u64::MAX`.
@@ -1445,6 +1621,69 @@ impl<Complexity: AtMax<Linear>> Peptidoform<Complexity> {
         self.generate_theoretical_fragments_inner(max_charge, model, 0, 0, &[])
     }
 
+    /// Get all backbone cleavages for this peptide, one for every peptide bond, without any ion
+    /// type specific chemistry applied. This can be used to build custom ion types, see
+    /// [`BackboneCleavage`]. The formulas contain all options for ambiguous amino acids and
+    /// modifications, and the modification specific neutral losses if `neutral_losses` is set.
+    /// With the global isotope modifications applied.
+    ///
+    /// # Panics
+    /// If the global isotope modifications are invalid.
+    pub fn backbone_cleavages(&self, neutral_losses: bool) -> Vec<BackboneCleavage> {
+        let apply_global = |formulas: Multi<MolecularFormula>| {
+            formulas
+                .iter()
+                .map(|f| {
+                    f.with_global_isotope_modifications(&self.global)
+                        .expect("Invalid global isotope modification")
+                })
+                .collect::<Multi<MolecularFormula>>()
+        };
+        (0..self.len().saturating_sub(1))
+            .map(|sequence_index| {
+                let mut cross_links = Vec::new();
+                let n_terminal = self
+                    .all_masses(
+                        ..=sequence_index,
+                        ..=sequence_index,
+                        &self.get_n_term_mass(&[], &[0], &mut cross_links, false, 0),
+                        neutral_losses,
+                        &[],
+                        &[0],
+                        &mut cross_links,
+                        false,
+                        0,
+                    )
+                    .0;
+                let c_terminal = self
+                    .all_masses(
+                        sequence_index + 1..,
+                        sequence_index + 1..,
+                        &self.get_c_term_mass(&[], &[0], &mut cross_links, false, 0),
+                        neutral_losses,
+                        &[],
+                        &[0],
+                        &mut cross_links,
+                        false,
+                        0,
+                    )
+                    .0;
+                BackboneCleavage {
+                    n_position: PeptidePosition::n(
+                        SequencePosition::Index(sequence_index),
+                        self.len(),
+                    ),
+                    c_position: PeptidePosition::c(
+                        SequencePosition::Index(sequence_index + 1),
+                        self.len(),
+                    ),
+                    n_terminal: apply_global(n_terminal),
+                    c_terminal: apply_global(c_terminal),
+                }
+            })
+            .collect()
+    }
+
     /// Gives the formulas for the whole peptide. With the global isotope modifications applied. (Any B/Z will result in multiple possible formulas.)
     #[allow(clippy::missing_panics_doc)] // Can not panic (unless state is already corrupted)
     pub fn formulas(&self) -> Multi<MolecularFormula> {
@@ -1533,6 +1772,19 @@ impl<Complexity: AtLeast<Linear>> Peptidoform<Complexity> {
         &mut self.labile
     }
 
+    /// Get the provenance of all modifications, the original text and ontology each modification
+    /// was parsed from. This is only filled for peptidoforms parsed from ProForma and does not
+    /// cover global modifications. It is not updated when modifications are added or removed
+    /// after parsing.
+    pub fn modification_provenance(&self) -> &[ModificationProvenance] {
+        &self.provenance
+    }
+
+    /// Set the provenance of all modifications
+    pub(super) fn set_modification_provenance(&mut self, provenance: Vec<ModificationProvenance>) {
+        self.provenance = provenance;
+    }
+
     /// Get the charge carriers, if there are any
     pub const fn get_charge_carriers(&self) -> Option<&MolecularCharge> {
         self.charge_carriers.as_ref()
@@ -1556,9 +1808,13 @@ impl<Complexity: AtLeast<SimpleLinear>> Peptidoform<Complexity> {
     }
 
     /// Add a new global modification of unknown position. If the modification would be placed on a
-    /// terminal but something is already placed there it is ignored.
+    /// terminal but something is already placed there it is ignored. The possible positions are
+    /// the positions allowed by the placement rules of the modification that are also allowed by
+    /// the settings, see [`MUPSettings`](crate::MUPSettings).
     /// # Errors
     /// When there are no possible locations return false, the modification is then not applied.
+    /// This also returns false if the peptidoform already contains the maximal number of
+    /// modifications of unknown position as set in the settings.
     #[must_use]
     pub fn add_unknown_position_modification(
         &mut self,
@@ -1566,12 +1822,25 @@ impl<Complexity: AtLeast<SimpleLinear>> Peptidoform<Complexity> {
         range: impl RangeBounds<usize>,
         settings: &crate::MUPSettings,
     ) -> bool {
+        if settings
+            .max_total
+            .is_some_and(|max| self.modifications_of_unknown_position.len() >= max)
+        {
+            return false;
+        }
         let possible_positions = self
             .iter(range)
             .filter(|(position, seq)| {
                 modification
                     .is_possible(seq, position.sequence_index)
                     .any_possible()
+                    && match position.sequence_index {
+                        SequencePosition::NTerm => settings.n_terminal,
+                        SequencePosition::CTerm => settings.c_terminal,
+                        SequencePosition::Index(_) => !settings
+                            .excluded_amino_acids
+                            .contains(&seq.aminoacid.aminoacid()),
+                    }
                     && (settings.position.is_none()
                         || settings.position.as_ref().is_some_and(|rules| {
                             rules
@@ -1685,6 +1954,69 @@ impl<Complexity: AtLeast<SimpleLinear>> Peptidoform<Complexity> {
             }
         }
     }
+
+    /// Apply the site probabilities as reported by a search engine for the given modification.
+    /// All occurrences of this modification placed on the sequence are replaced by ambiguous
+    /// modifications over all given sites (0 based sequence indices), with the probabilities as
+    /// localisation scores and the original location as preferred location. Sites outside of the
+    /// sequence are ignored. See [`crate::identification::parse_site_probabilities`] to parse the
+    /// site probabilities.
+    /// # Returns
+    /// False when the modification is not placed on the sequence or there are no valid sites,
+    /// the peptide is then not changed.
+    #[must_use]
+    pub fn apply_site_probabilities(
+        &mut self,
+        modification: &SimpleModification,
+        sites: &[(usize, f64)],
+    ) -> bool {
+        let positions = sites
+            .iter()
+            .filter(|(index, _)| *index < self.len())
+            .map(|(index, probability)| {
+                (
+                    SequencePosition::Index(*index),
+                    Some(OrderedFloat(*probability)),
+                )
+            })
+            .collect_vec();
+        let placed = self
+            .sequence
+            .iter()
+            .enumerate()
+            .flat_map(|(index, element)| {
+                element
+                    .modifications
+                    .iter()
+                    .filter(|m| matches!(m, Modification::Simple(s) if s == modification))
+                    .map(move |_| index)
+            })
+            .collect_vec();
+        if positions.is_empty() || placed.is_empty() {
+            return false;
+        }
+        for index in &placed {
+            let element = &mut self.sequence[*index];
+            if let Some(i) = element
+                .modifications
+                .iter()
+                .position(|m| matches!(m, Modification::Simple(s) if s == modification))
+            {
+                element.modifications.remove(i);
+            }
+        }
+        for index in placed {
+            let _ = self.add_ambiguous_modification(
+                modification.clone(),
+                None,
+                &positions,
+                Some(SequencePosition::Index(index)),
+                None,
+                false,
+            );
+        }
+        true
+    }
 }
 
 impl<OwnComplexity: AtMax<SemiAmbiguous>> Peptidoform<OwnComplexity> {
@@ -1701,6 +2033,7 @@ impl<OwnComplexity: AtMax<SemiAmbiguous>> Peptidoform<OwnComplexity> {
         OwnComplexity: HighestOf<OtherComplexity>,
     {
         if self.c_term.is_empty() && other.n_term.is_empty() {
+            let offset = self.len();
             Some(Peptidoform::<OwnComplexity::HighestLevel> {
                 global: self.global,
                 labile: self.labile.into_iter().chain(other.labile).collect(),
@@ -1714,6 +2047,24 @@ impl<OwnComplexity: AtMax<SemiAmbiguous>> Peptidoform<OwnComplexity> {
                     .collect(),
                 modifications_of_unknown_position: Vec::new(),
                 charge_carriers: self.charge_carriers,
+                provenance: self
+                    .provenance
+                    .into_iter()
+                    .chain(
+                        other
+                            .provenance
+                            .into_iter()
+                            .map(|p| ModificationProvenance {
+                                position: p.position.map(|position| match position {
+                                    SequencePosition::Index(index) => {
+                                        SequencePosition::Index(index + offset)
+                                    }
+                                    other => other,
+                                }),
+                                ..p
+                            }),
+                    )
+                    .collect(),
                 marker: PhantomData,
             })
         } else {
@@ -1742,6 +2093,7 @@ where
             sequence: value.into_iter().map(std::convert::Into::into).collect(),
             modifications_of_unknown_position: Vec::new(),
             charge_carriers: None,
+            provenance: Vec::new(),
             marker: PhantomData,
         }
     }
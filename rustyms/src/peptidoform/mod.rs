@@ -4,6 +4,7 @@ mod annotated;
 mod complexity;
 mod compound_peptidoform_ion;
 mod find_modifications;
+mod glycosylation_sites;
 mod linear_peptide;
 mod parse;
 mod parse_modification;
@@ -17,6 +18,7 @@ pub use annotated::*;
 pub use complexity::*;
 pub use compound_peptidoform_ion::*;
 pub use find_modifications::*;
+pub use glycosylation_sites::*;
 pub use linear_peptide::*;
 pub use parse_modification::*;
 pub use parse_sloppy::SloppyParsingParameters;
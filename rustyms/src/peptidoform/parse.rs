@@ -1,4 +1,4 @@
-use std::{collections::BTreeMap, num::NonZeroU16};
+use std::{collections::BTreeMap, num::NonZeroU16, ops::Range};
 
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
@@ -7,7 +7,8 @@ use crate::{
     error::{Context, CustomError},
     helper_functions::*,
     modification::{
-        AmbiguousLookup, CrossLinkLookup, Modification, SimpleModification, SimpleModificationInner,
+        AmbiguousLookup, CrossLinkLookup, Modification, ModificationProvenance, SimpleModification,
+        SimpleModificationInner,
     },
     molecular_charge::MolecularCharge,
     ontologies::CustomDatabase,
@@ -65,6 +66,36 @@ impl Peptidoform<Linked> {
                 })
             })
     }
+
+    /// Parse many linear peptides in ProForma notation at once, see [`Self::pro_forma`]. If the
+    /// `rayon` feature is enabled the lines are parsed in parallel. The ontologies and their name
+    /// lookups are loaded once and shared between all threads. The results are returned in the
+    /// same order as the lines, and any error has its line number set to the index of its line.
+    ///
+    /// ```rust
+    /// # use rustyms::*;
+    /// let parsed = Peptidoform::parse_many(&["PEPTIDE", "PEM[Oxidation]TIDE", "PEP[Unknown]"], None);
+    /// assert_eq!(parsed.len(), 3);
+    /// assert!(parsed[0].is_ok() && parsed[1].is_ok() && parsed[2].is_err());
+    /// ```
+    pub fn parse_many<S: AsRef<str> + Sync>(
+        lines: &[S],
+        custom_database: Option<&CustomDatabase>,
+    ) -> Vec<Result<Self, CustomError>> {
+        let parse = |(index, line): (usize, &S)| {
+            Self::pro_forma(line.as_ref(), custom_database)
+                .map_err(|error| error.overwrite_line_number(index))
+        };
+        #[cfg(feature = "rayon")]
+        {
+            use rayon::prelude::*;
+            lines.par_iter().enumerate().map(parse).collect()
+        }
+        #[cfg(not(feature = "rayon"))]
+        {
+            lines.iter().enumerate().map(parse).collect()
+        }
+    }
 }
 
 impl PeptidoformIon {
@@ -128,6 +159,25 @@ impl CompoundPeptidoformIon {
         }
     }
 
+    /// Parse a compound peptidoform in the [ProForma specification](https://github.com/HUPO-PSI/ProForma)
+    /// and check it against the given [`ComplexityLimits`](crate::ComplexityLimits), see
+    /// [`Self::check_complexity`]. Use this when parsing untrusted input.
+    ///
+    /// # Errors
+    /// It fails when the string is not a valid ProForma string. Or when the compound peptidoform
+    /// exceeds the given limits.
+    pub fn pro_forma_with_limits(
+        value: &str,
+        custom_database: Option<&CustomDatabase>,
+        limits: &crate::ComplexityLimits,
+    ) -> Result<Self, CustomError> {
+        let result = Self::pro_forma(value, custom_database)?;
+        result.check_complexity(limits).map_err(|err| {
+            CustomError::error("Peptidoform too complex", err, Context::full_line(0, value))
+        })?;
+        Ok(result)
+    }
+
     /// # Errors
     /// It returns an error if the line is not a supported ProForma line.
     fn parse_peptidoform(
@@ -221,13 +271,15 @@ impl CompoundPeptidoformIon {
         )> = Vec::new();
         let mut unknown_position_modifications = Vec::new();
         let mut ranged_unknown_position_modifications = Vec::new();
+        let mut provenance: Vec<(Option<SequencePosition>, Range<usize>, ReturnModification)> =
+            Vec::new();
         let mut ending = End::Empty;
 
         // Unknown position mods
         if let Some(result) =
             global_unknown_position_mods(chars, index, line, custom_database, &mut ambiguous_lookup)
         {
-            let (buf, mods) = result.map_err(|errors| {
+            let (buf, mods, ranges) = result.map_err(|errors| {
                 CustomError::error(
                     "Some unknown position modifications are invalid",
                     "See the underlying errors for more details.",
@@ -239,12 +291,22 @@ impl CompoundPeptidoformIon {
             })?;
             index = buf;
 
+            provenance.extend(
+                ranges.into_iter().map(|(range, id)| {
+                    (None, range, ReturnModification::Ambiguous(id, None, false))
+                }),
+            );
             unknown_position_modifications = mods;
         }
 
         // Labile modification(s)
         let (mut index, labile) = labile_modifications(line, index, custom_database)?;
-        peptide = peptide.labile(labile);
+        provenance.extend(
+            labile
+                .iter()
+                .map(|(m, range)| (None, range.clone(), ReturnModification::Defined(m.clone()))),
+        );
+        peptide = peptide.labile(labile.into_iter().map(|(m, _)| m));
 
         // N term modification
         if chars.get(index) == Some(&b'[') {
@@ -260,6 +322,14 @@ impl CompoundPeptidoformIon {
                 cross_link_lookup,
                 custom_database,
             )
+            .map(|m| {
+                provenance.push((
+                    Some(SequencePosition::NTerm),
+                    index + 1..end_index - 1,
+                    m.0.clone(),
+                ));
+                m
+            })
             .map(|m| match m.0 {
                 ReturnModification::Defined(simple) => Some(simple),
                 ReturnModification::CrossLinkReferenced(id) => {
@@ -348,6 +418,11 @@ impl CompoundPeptidoformIon {
                             "A ranged ambiguous modification has to be fully defined, so no ambiguous modification is allowed",
                             Context::line(None, line, index, 1),
                         ))?;
+                        provenance.push((
+                            None,
+                            index + 1..end_index,
+                            ReturnModification::Defined(modification.clone()),
+                        ));
                         index = end_index + 1;
                         ranged_unknown_position_modifications.push((
                             start,
@@ -384,6 +459,12 @@ impl CompoundPeptidoformIon {
                     )?;
                     let start_index = index +1;
                     index = end_index + 1;
+                    let position = if is_c_term {
+                        Some(SequencePosition::CTerm)
+                    } else {
+                        peptide.len().checked_sub(1).map(SequencePosition::Index)
+                    };
+                    provenance.push((position, start_index..end_index, modification.clone()));
                     if is_c_term {
                         if let Some(m) =
                             match modification {
@@ -530,6 +611,23 @@ impl CompoundPeptidoformIon {
         peptide
             .apply_ranged_unknown_position_modification(&ranged_unknown_position_modifications)?;
         peptide.enforce_modification_rules()?;
+        peptide.set_modification_provenance(
+            provenance
+                .into_iter()
+                .map(|(position, range, modification)| {
+                    let modification = match modification {
+                        ReturnModification::Defined(m) => Some(m),
+                        ReturnModification::Ambiguous(id, _, _) => {
+                            ambiguous_lookup[id].modification.clone()
+                        }
+                        ReturnModification::CrossLinkReferenced(id) => {
+                            cross_link_lookup[id].1.clone()
+                        }
+                    };
+                    ModificationProvenance::new(position, &line[range], modification.as_deref())
+                })
+                .collect(),
+        );
 
         Ok(LinearPeptideResult {
             peptide,
@@ -698,6 +796,10 @@ pub fn parse_placement_rules(
     Ok(result)
 }
 
+/// The modifications of unknown position: the index from where to continue parsing, the ambiguous
+/// ids of all modifications, and the text range with ambiguous id of every definition
+type UnknownPositionModifications = (usize, Vec<usize>, Vec<(Range<usize>, usize)>);
+
 /// If the text is recognised as a unknown mods list it is Some(..), if it has errors during parsing Some(Err(..))
 /// The returned happy path contains the mods and the index from where to continue parsing.
 /// # Errors
@@ -710,9 +812,10 @@ pub(super) fn global_unknown_position_mods(
     line: &str,
     custom_database: Option<&CustomDatabase>,
     ambiguous_lookup: &mut AmbiguousLookup,
-) -> Option<Result<(usize, Vec<usize>), Vec<CustomError>>> {
+) -> Option<Result<UnknownPositionModifications, Vec<CustomError>>> {
     let mut index = start;
     let mut modifications = Vec::new();
+    let mut ranges = Vec::new();
     let mut errs = Vec::new();
     let mut cross_link_lookup = Vec::new();
 
@@ -753,6 +856,7 @@ pub(super) fn global_unknown_position_mods(
                 continue;
             }
         };
+        ranges.push((start_index + 1..index - 1, id));
         let number = if chars.get(index) == Some(&b'^') {
             if let Some((len, num)) = next_num(chars, index + 1, false) {
                 index += len + 1;
@@ -789,7 +893,7 @@ pub(super) fn global_unknown_position_mods(
     }
     if chars.get(index) == Some(&b'?') {
         Some(if errs.is_empty() {
-            Ok((index + 1, modifications))
+            Ok((index + 1, modifications, ranges))
         } else {
             Err(errs)
         })
@@ -799,6 +903,9 @@ pub(super) fn global_unknown_position_mods(
     }
 }
 
+/// The parsed labile modifications with the location of their definition
+type LabileModifications = Vec<(SimpleModification, Range<usize>)>;
+
 /// Parse labile modifications `{mod}{mod2}`. These are assumed to fall off from the peptide in the MS.
 /// # Errors
 /// If the mods are not followed by a closing brace. Or if the mods are ambiguous.
@@ -806,7 +913,7 @@ fn labile_modifications(
     line: &str,
     mut index: usize,
     custom_database: Option<&CustomDatabase>,
-) -> Result<(usize, Vec<SimpleModification>), CustomError> {
+) -> Result<(usize, LabileModifications), CustomError> {
     let chars = line.as_bytes();
     let mut labile = Vec::new();
     while chars.get(index) == Some(&b'{') {
@@ -818,7 +925,7 @@ fn labile_modifications(
             )
         })?;
 
-        labile.push(
+        labile.push((
             SimpleModificationInner::try_from(
                 line,
                 index + 1..end_index,
@@ -835,7 +942,8 @@ fn labile_modifications(
                     )
                 })
             })?,
-        );
+            index + 1..end_index,
+        ));
         index = end_index + 1;
     }
     Ok((index, labile))
@@ -22,7 +22,7 @@ use crate::{
     helper_functions::*,
     ontologies::CustomDatabase,
     system::{dalton, Mass},
-    Element, MolecularFormula,
+    AminoAcid, Element, MolecularFormula,
 };
 
 impl SimpleModificationInner {
@@ -98,7 +98,16 @@ enum SingleReturnModification {
     ColocaliseModificationsOfUnknownPosition(bool),
 }
 
-/// Settings for a modification of unknown position
+/// Settings for a modification of unknown position, see
+/// [`Peptidoform::add_unknown_position_modification`](crate::Peptidoform::add_unknown_position_modification).
+/// ```rust
+/// # use rustyms::*;
+/// let settings = MUPSettings::default()
+///     .excluded_amino_acids(vec![AminoAcid::Methionine])
+///     .terminals(false, false)
+///     .max_total(3);
+/// ```
+#[allow(clippy::struct_excessive_bools)]
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Serialize, Deserialize)]
 pub struct MUPSettings {
     /// The additional placement rules
@@ -109,6 +118,14 @@ pub struct MUPSettings {
     pub(crate) colocalise_placed_modifications: bool,
     /// Allow this mup to colocalise with other mups
     pub(crate) colocalise_modifications_of_unknown_position: bool,
+    /// The amino acids this mup cannot be placed on, only applies to the side chain positions
+    pub(crate) excluded_amino_acids: Vec<AminoAcid>,
+    /// Allow this mup to be placed on the N terminus
+    pub(crate) n_terminal: bool,
+    /// Allow this mup to be placed on the C terminus
+    pub(crate) c_terminal: bool,
+    /// The maximal number of mups on the peptidoform, including this one
+    pub(crate) max_total: Option<usize>,
 }
 
 impl std::default::Default for MUPSettings {
@@ -118,6 +135,84 @@ impl std::default::Default for MUPSettings {
             limit: None,
             colocalise_placed_modifications: true,
             colocalise_modifications_of_unknown_position: true,
+            excluded_amino_acids: Vec::new(),
+            n_terminal: true,
+            c_terminal: true,
+            max_total: None,
+        }
+    }
+}
+
+impl MUPSettings {
+    /// Set the additional placement rules, the modification can only be placed on positions
+    /// allowed by both the modification itself and at least one of these rules
+    #[must_use]
+    pub fn position(self, position: Vec<PlacementRule>) -> Self {
+        Self {
+            position: Some(position),
+            ..self
+        }
+    }
+
+    /// Set the maximal number of copies of this modification on a single position
+    #[must_use]
+    pub fn limit(self, limit: usize) -> Self {
+        Self {
+            limit: Some(limit),
+            ..self
+        }
+    }
+
+    /// Set if this modification can be placed on positions with placed modifications
+    #[must_use]
+    pub fn colocalise_placed_modifications(self, colocalise_placed_modifications: bool) -> Self {
+        Self {
+            colocalise_placed_modifications,
+            ..self
+        }
+    }
+
+    /// Set if this modification can be placed on positions with other modifications of unknown
+    /// position
+    #[must_use]
+    pub fn colocalise_modifications_of_unknown_position(
+        self,
+        colocalise_modifications_of_unknown_position: bool,
+    ) -> Self {
+        Self {
+            colocalise_modifications_of_unknown_position,
+            ..self
+        }
+    }
+
+    /// Set the amino acids this modification cannot be placed on. This only applies to the side
+    /// chain positions, the termini are controlled with [`Self::terminals`].
+    #[must_use]
+    pub fn excluded_amino_acids(self, excluded_amino_acids: Vec<AminoAcid>) -> Self {
+        Self {
+            excluded_amino_acids,
+            ..self
+        }
+    }
+
+    /// Set if this modification can be placed on the N and C terminus
+    #[must_use]
+    pub fn terminals(self, n_terminal: bool, c_terminal: bool) -> Self {
+        Self {
+            n_terminal,
+            c_terminal,
+            ..self
+        }
+    }
+
+    /// Set the maximal number of modifications of unknown position on the peptidoform, including
+    /// this modification. If the peptidoform already contains this many modifications of unknown
+    /// position this modification is not placed.
+    #[must_use]
+    pub fn max_total(self, max_total: usize) -> Self {
+        Self {
+            max_total: Some(max_total),
+            ..self
         }
     }
 }
@@ -233,7 +233,8 @@ static SLOPPY_MOD_NUMERIC_END_REGEX: OnceLock<Regex> = OnceLock::new();
 impl Modification {
     /// Parse a modification defined by sloppy names
     /// # Errors
-    /// If the name is not in Unimod, PSI-MOD, the custom database, or the predefined list of common trivial names.
+    /// If the name is not in Unimod, PSI-MOD, the custom database, or the predefined list of common trivial names,
+    /// and it is not a Unimod or PSI-MOD accession (eg `UNIMOD:35`).
     /// Or if this is the case when the modification follows a known structure (eg `mod (AAs)`).
     #[allow(clippy::missing_panics_doc)]
     pub fn sloppy_modification(
@@ -251,6 +252,8 @@ impl Modification {
                     Some(("u", tail)) => Ontology::Unimod.find_name(tail, None),
                     Some(("m", tail)) => Ontology::Psimod.find_name(tail, None),
                     Some(("c", tail)) => Ontology::Custom.find_name(tail, custom_database),
+                    Some(("unimod", tail)) => tail.trim().parse::<usize>().ok().and_then(|id| Ontology::Unimod.find_id(id, None)),
+                    Some(("mod", tail)) => tail.trim().parse::<usize>().ok().and_then(|id| Ontology::Psimod.find_id(id, None)),
                     _ => None
                 }
             })
@@ -306,11 +309,22 @@ impl Modification {
     ) -> Option<SimpleModification> {
         let name = name.trim().to_lowercase();
         match name.as_str() {
-            "o" | "ox" => Ontology::Unimod.find_id(35, None), // oxidation
+            "o" | "ox" | "hydroxylation" => Ontology::Unimod.find_id(35, None), // oxidation
             "cam" | "carbamidomethylation" => Ontology::Unimod.find_id(4, None), // carbamidomethyl
-            "nem" => Ontology::Unimod.find_id(108, None),     // Nethylmaleimide
-            "deamidation" => Ontology::Unimod.find_id(7, None), // deamidated
-            "formylation" => Ontology::Unimod.find_id(122, None), // formyl
+            "nem" => Ontology::Unimod.find_id(108, None),                       // Nethylmaleimide
+            "deamidation" | "citrullination" => Ontology::Unimod.find_id(7, None), // deamidated
+            "formylation" => Ontology::Unimod.find_id(122, None),               // formyl
+            "acetylation" => Ontology::Unimod.find_id(1, None),                 // acetyl
+            "phosphorylation" => Ontology::Unimod.find_id(21, None),            // phospho
+            "methylation" => Ontology::Unimod.find_id(34, None),                // methyl
+            "dimethylation" => Ontology::Unimod.find_id(36, None),              // dimethyl
+            "trimethylation" => Ontology::Unimod.find_id(37, None),             // trimethyl
+            "carbamylation" => Ontology::Unimod.find_id(5, None),               // carbamyl
+            "succinylation" => Ontology::Unimod.find_id(64, None),              // succinyl
+            "ammonia loss" => Ontology::Unimod.find_id(385, None),              // ammonia-loss
+            "sodium" => Ontology::Unimod.find_id(30, None),                     // cation:na
+            "pyro-glu from e" => Ontology::Unimod.find_id(27, None),            // glu->pyro-glu
+            "pyro-glu from q" => Ontology::Unimod.find_id(28, None),            // gln->pyro-glu
             "pyro-glu" => Ontology::Unimod.find_id(
                 if position.is_some_and(|p| p.aminoacid.aminoacid() == AminoAcid::GlutamicAcid) {
                     27
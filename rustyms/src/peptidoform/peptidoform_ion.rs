@@ -48,7 +48,9 @@ impl PeptidoformIon {
             .unwrap_or_default()
     }
 
-    /// Generate the theoretical fragments for this peptidoform.
+    /// Generate the theoretical fragments for this peptidoform. The order of the fragments is
+    /// deterministic, see
+    /// [`CompoundPeptidoformIon::generate_theoretical_fragments`](crate::CompoundPeptidoformIon::generate_theoretical_fragments).
     pub fn generate_theoretical_fragments(
         &self,
         max_charge: Charge,
@@ -112,12 +114,37 @@ impl PeptidoformIon {
         position_2: (usize, SequencePosition),
         linker: SimpleModification,
         name: CrossLinkName,
+    ) -> bool {
+        self.add_cross_link_inner(position_1, position_2, linker, name, true)
+    }
+
+    /// Add a cross-link to this peptidoform without checking its placement rules. If it is not
+    /// placed according to its placement rules no specificity is used, so any stubs and
+    /// diagnostic ions of the cross-linker are not generated. Returns false if any of the
+    /// positions does not exist.
+    pub(crate) fn add_unchecked_cross_link(
+        &mut self,
+        position_1: (usize, SequencePosition),
+        position_2: (usize, SequencePosition),
+        linker: SimpleModification,
+        name: CrossLinkName,
+    ) -> bool {
+        self.add_cross_link_inner(position_1, position_2, linker, name, false)
+    }
+
+    fn add_cross_link_inner(
+        &mut self,
+        position_1: (usize, SequencePosition),
+        position_2: (usize, SequencePosition),
+        linker: SimpleModification,
+        name: CrossLinkName,
+        enforce_placement: bool,
     ) -> bool {
         let pos_1 = self.0.get(position_1.0).map(|seq| &seq[position_1.1]);
         let pos_2 = self.0.get(position_2.0).map(|seq| &seq[position_2.1]);
         if let (Some(pos_1), Some(pos_2)) = (pos_1, pos_2) {
             let left = linker.is_possible(pos_1, position_1.1);
-            let right = linker.is_possible(pos_2, position_1.1);
+            let right = linker.is_possible(pos_2, position_2.1);
             let specificity = if matches!(
                 &*linker,
                 SimpleModificationInner::Formula(_)
@@ -173,7 +200,15 @@ impl PeptidoformIon {
                     }
                     _ => None,
                 }
-            };
+            }
+            .or_else(|| {
+                (!enforce_placement).then(|| {
+                    (
+                        CrossLinkSide::Symmetric(std::collections::BTreeSet::default()),
+                        CrossLinkSide::Symmetric(std::collections::BTreeSet::default()),
+                    )
+                })
+            });
             if let Some((left, right)) = specificity {
                 self.0[position_1.0].add_modification(
                     position_1.1,
@@ -2,7 +2,7 @@ use std::{num::NonZeroU16, sync::Arc};
 
 use crate::{
     model::PrimaryIonSeries,
-    modification::{self, ModificationId, SimpleModificationInner},
+    modification::{self, ModificationId, Ontology, SimpleModificationInner},
     peptidoform::{
         parse::{global_modifications, parse_charge_state},
         GlobalModification,
@@ -10,7 +10,7 @@ use crate::{
     placement_rule::{self, PlacementRule, Position},
     system::{da, usize::Charge},
     AminoAcid, CompoundPeptidoformIon, Element, Model, MolecularCharge, MultiChemical, Peptidoform,
-    PeptidoformIon,
+    PeptidoformIon, SequencePosition,
 };
 
 #[test]
@@ -476,3 +476,125 @@ fn hydrolysed_xl() {
 
     assert_eq!(peptide_xl.formula(), peptide_mod.formula());
 }
+
+#[test]
+#[allow(clippy::literal_string_with_formatting_args)]
+fn modification_provenance() {
+    let peptide = Peptidoform::pro_forma(
+        "[Phospho]?{Hex}[U:Acetyl]-AM[+15.9949]S[Phospho#g1]T[#g1]-[Amidated]",
+        None,
+    )
+    .unwrap();
+    let provenance = peptide.modification_provenance();
+    let find = |text: &str| provenance.iter().find(|p| p.text == text).unwrap();
+    assert_eq!(provenance.len(), 7);
+    assert_eq!(find("Phospho").position, None);
+    assert_eq!(find("Hex").position, None);
+    assert_eq!(find("U:Acetyl").position, Some(SequencePosition::NTerm));
+    assert_eq!(find("U:Acetyl").ontology, Some(Ontology::Unimod));
+    assert_eq!(find("+15.9949").position, Some(SequencePosition::Index(1)));
+    assert!(find("+15.9949").mass_only);
+    assert!(!find("+15.9949").is_ontology_backed());
+    assert_eq!(
+        find("Phospho#g1").position,
+        Some(SequencePosition::Index(2))
+    );
+    assert_eq!(find("#g1").ontology, Some(Ontology::Unimod));
+    assert_eq!(find("Amidated").position, Some(SequencePosition::CTerm));
+    assert!(!find("Amidated").mass_only);
+
+    // Provenance does not influence equality or ordering
+    let other = Peptidoform::pro_forma(
+        "[U:Phospho]?{U:Hex}[Acetyl]-AM[+15.9949]S[Phospho#g1]T[#g1]-[UNIMOD:2]",
+        None,
+    )
+    .unwrap();
+    assert_eq!(peptide, other);
+    assert_eq!(peptide.cmp(&other), std::cmp::Ordering::Equal);
+    let reversed = peptide.reverse();
+    assert_eq!(
+        reversed
+            .modification_provenance()
+            .iter()
+            .find(|p| p.text == "+15.9949")
+            .unwrap()
+            .position,
+        Some(SequencePosition::Index(2))
+    );
+}
+
+#[test]
+fn sub_peptide_empty() {
+    let empty = Peptidoform::<crate::Linear>::default();
+    assert_eq!(empty.sub_peptide(..), empty);
+}
+
+#[test]
+fn parse_many() {
+    let lines = (0..40)
+        .map(|i| match i % 4 {
+            0 => "PEPTIDE".to_string(),
+            1 => format!("[U:Acetyl]-PEM[Oxidation]T{}IDE", "A".repeat(i % 7)),
+            2 => "{Hex}AC[Carbamidomethyl]K[+12.01]".to_string(),
+            _ => "PEP[NotAModification]".to_string(),
+        })
+        .collect::<Vec<_>>();
+    let parsed = Peptidoform::parse_many(&lines, None);
+    assert_eq!(parsed.len(), lines.len());
+    for (index, (line, result)) in lines.iter().zip(&parsed).enumerate() {
+        match (result, Peptidoform::pro_forma(line, None)) {
+            (Ok(many), Ok(single)) => assert_eq!(*many, single),
+            (Err(error), Err(_)) => assert_eq!(
+                *error,
+                Peptidoform::pro_forma(line, None)
+                    .unwrap_err()
+                    .overwrite_line_number(index)
+            ),
+            _ => panic!("Different results for line {index}: {line}"),
+        }
+    }
+    assert_eq!(parsed.iter().filter(|r| r.is_err()).count(), 10);
+}
+
+#[test]
+fn unknown_position_settings() {
+    let mut peptide = Peptidoform::pro_forma("AMCM", None)
+        .unwrap()
+        .into_simple_linear()
+        .unwrap();
+    let modification = Arc::new(SimpleModificationInner::Mass(da(5.0).into()));
+    let settings = crate::MUPSettings::default()
+        .excluded_amino_acids(vec![AminoAcid::Methionine])
+        .terminals(false, false)
+        .max_total(1);
+    assert!(peptide.add_unknown_position_modification(modification.clone(), .., &settings));
+    assert_eq!(
+        peptide.get_ambiguous_modifications(),
+        vec![vec![SequencePosition::Index(0), SequencePosition::Index(2)]]
+    );
+    assert!(!peptide.add_unknown_position_modification(modification.clone(), .., &settings));
+    assert!(peptide.add_unknown_position_modification(
+        modification.clone(),
+        ..,
+        &settings.clone().max_total(2).terminals(true, false)
+    ));
+    assert_eq!(
+        peptide.get_ambiguous_modifications()[1],
+        vec![
+            SequencePosition::NTerm,
+            SequencePosition::Index(0),
+            SequencePosition::Index(2)
+        ]
+    );
+    assert!(!peptide.add_unknown_position_modification(
+        modification,
+        ..,
+        &crate::MUPSettings::default()
+            .excluded_amino_acids(vec![
+                AminoAcid::Alanine,
+                AminoAcid::Methionine,
+                AminoAcid::Cysteine
+            ])
+            .terminals(false, false)
+    ));
+}
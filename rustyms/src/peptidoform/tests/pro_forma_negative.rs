@@ -22,3 +22,5 @@ parse_test!(ne "(Q[gln->pyro-glu]S)[Dehydro]", negative_example_19);
 parse_test!(ne "()[Dehydro]S", negative_example_20);
 parse_test!(ne "S()[Dehydro]", negative_example_21);
 parse_test!(ne "AA[+1#xl1]/2//AA[#XL1]", negative_example_22);
+parse_test!(ne "A[Formula:Formula:C2]A", negative_example_23);
+parse_test!(ne "A[formula:formula:]A", negative_example_24);
@@ -17,6 +17,32 @@ fn sloppy_names() {
     );
 }
 
+#[test]
+fn sloppy_accessions() {
+    assert_eq!(
+        Modification::sloppy_modification("UNIMOD:35", 0..9, None, None),
+        Ok(Ontology::Unimod.find_id(35, None).unwrap())
+    );
+    assert_eq!(
+        Modification::sloppy_modification("MOD:00046", 0..9, None, None),
+        Ok(Ontology::Psimod.find_id(46, None).unwrap())
+    );
+}
+
+#[test]
+fn sloppy_names_metamorpheus() {
+    let name = "Common Biological:Phosphorylation on S";
+    assert_eq!(
+        Modification::sloppy_modification(name, 0..name.len(), None, None),
+        Ok(Ontology::Unimod.find_id(21, None).unwrap())
+    );
+    let name = "Common Artifact:Pyro-glu from Q on Q";
+    assert_eq!(
+        Modification::sloppy_modification(name, 0..name.len(), None, None),
+        Ok(Ontology::Unimod.find_id(28, None).unwrap())
+    );
+}
+
 #[test]
 fn sloppy_names_custom() {
     let db = Some(vec![(
@@ -56,10 +56,11 @@ pub fn cross_links(
                     }
                 },
                 2 => {
-                    if !peptidoform.add_cross_link(locations[0], locations[1], linker.clone(), definition.0.clone()) {
+                    // ProForma allows cross-linkers on any residue, so the placement rules are not enforced
+                    if !peptidoform.add_unchecked_cross_link(locations[0], locations[1], linker.clone(), definition.0.clone()) {
                         return Err(CustomError::error(
                             "Invalid cross-link",
-                            format!("The cross-link named '{}' cannot be placed according to its location specificities", definition.0),
+                            format!("The cross-link named '{}' is placed on a location that does not exist", definition.0),
                             Context::full_line(0, line),
                         ))
                     }
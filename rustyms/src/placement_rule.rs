@@ -12,12 +12,46 @@ use crate::{
 
 include!("shared/placement_rule.rs");
 
+/// Which termini of a peptide are also termini of the protein it originates from. This is used to
+/// distinguish protein terminal placement rules from peptide terminal placement rules.
+#[derive(
+    Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Serialize, Deserialize,
+)]
+pub struct ProteinTermini {
+    /// If the N terminus of this peptide is the (mature) N terminus of the protein
+    pub n_term: bool,
+    /// If the C terminus of this peptide is the C terminus of the protein
+    pub c_term: bool,
+}
+
+impl ProteinTermini {
+    /// The protein context is not known, so any peptide terminus could be a protein terminus
+    pub const UNKNOWN: Self = Self {
+        n_term: true,
+        c_term: true,
+    };
+}
+
 impl PlacementRule {
-    /// Check if this rule fits with the given location
+    /// Check if this rule fits with the given location. If the protein context is known use
+    /// [`Self::is_possible_in_protein`] instead as this assumes any terminus could be a protein
+    /// terminus.
     pub fn is_possible<T>(&self, seq: &SequenceElement<T>, position: SequencePosition) -> bool {
+        self.is_possible_in_protein(seq, position, ProteinTermini::UNKNOWN)
+    }
+
+    /// Check if this rule fits with the given location, where the protein terminal rules are only
+    /// possible if the peptide terminus is also a protein terminus.
+    pub fn is_possible_in_protein<T>(
+        &self,
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        termini: ProteinTermini,
+    ) -> bool {
         match self {
             Self::AminoAcid(aa, r_pos) => {
-                aa.iter().any(|a| *a == seq.aminoacid.aminoacid()) && r_pos.is_possible(position)
+                aa.iter().any(|a| *a == seq.aminoacid.aminoacid())
+                    && r_pos.is_possible_in_protein(position, termini)
             }
             Self::PsiModification(mod_index, r_pos) => {
                 seq.modifications.iter().any(|m| {
@@ -39,10 +73,10 @@ impl PlacementRule {
                     } else {
                         false
                     }
-                }) && r_pos.is_possible(position)
+                }) && r_pos.is_possible_in_protein(position, termini)
             }
             Self::Terminal(r_pos) => {
-                r_pos.is_possible(position)
+                r_pos.is_possible_in_protein(position, termini)
                     && (position == SequencePosition::NTerm || position == SequencePosition::CTerm)
             }
             Self::Anywhere => true,
@@ -72,6 +106,18 @@ impl PlacementRule {
         rules.iter().any(|r| r.is_possible(seq, position))
     }
 
+    /// Check if any of the given rules are possible, see [`Self::is_possible_in_protein`]
+    pub fn any_possible_in_protein<T>(
+        rules: &[Self],
+        seq: &SequenceElement<T>,
+        position: SequencePosition,
+        termini: ProteinTermini,
+    ) -> bool {
+        rules
+            .iter()
+            .any(|r| r.is_possible_in_protein(seq, position, termini))
+    }
+
     /// Check if any of the given rules are possible
     pub fn any_possible_aa(rules: &[Self], aa: AminoAcid, position: Position) -> bool {
         rules.iter().any(|r| r.is_possible_aa(aa, position))
@@ -123,10 +169,23 @@ impl FromStr for PlacementRule {
 impl Position {
     /// See if the given peptide position is a valid position given this [`Position`] as placement rule.
     pub fn is_possible(self, position: SequencePosition) -> bool {
+        self.is_possible_in_protein(position, ProteinTermini::UNKNOWN)
+    }
+
+    /// See if the given peptide position is a valid position given this [`Position`] as placement
+    /// rule, where the protein terminal positions are only valid if the peptide terminus is also a
+    /// protein terminus.
+    pub fn is_possible_in_protein(
+        self,
+        position: SequencePosition,
+        termini: ProteinTermini,
+    ) -> bool {
         match self {
             Self::Anywhere => true,
-            Self::AnyNTerm | Self::ProteinNTerm => position == SequencePosition::NTerm,
-            Self::AnyCTerm | Self::ProteinCTerm => position == SequencePosition::CTerm,
+            Self::AnyNTerm => position == SequencePosition::NTerm,
+            Self::ProteinNTerm => position == SequencePosition::NTerm && termini.n_term,
+            Self::AnyCTerm => position == SequencePosition::CTerm,
+            Self::ProteinCTerm => position == SequencePosition::CTerm && termini.c_term,
         }
     }
 
@@ -214,4 +273,49 @@ mod tests {
             "unimod deamidated at end"
         );
     }
+
+    #[test]
+    fn protein_termini() {
+        let seq = SequenceElement::new(CheckedAminoAcid::M, None);
+        let rule = PlacementRule::AminoAcid(vec![AminoAcid::Methionine], Position::ProteinNTerm);
+        assert!(rule.is_possible(&seq, SequencePosition::NTerm));
+        assert!(!rule.is_possible_in_protein(
+            &seq,
+            SequencePosition::NTerm,
+            ProteinTermini::default()
+        ));
+        assert!(rule.is_possible_in_protein(
+            &seq,
+            SequencePosition::NTerm,
+            ProteinTermini {
+                n_term: true,
+                c_term: false
+            }
+        ));
+        assert!(
+            PlacementRule::Terminal(Position::AnyNTerm).is_possible_in_protein(
+                &seq,
+                SequencePosition::NTerm,
+                ProteinTermini::default()
+            )
+        );
+
+        // Met-loss is only allowed on protein N terminal methionines
+        let met_loss = Ontology::Unimod.find_id(765, None).unwrap();
+        let mut peptide = crate::Peptidoform::pro_forma("MKAAK", None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        assert_eq!(
+            peptide
+                .clone()
+                .add_fixed_modification(&met_loss, ProteinTermini::default()),
+            0
+        );
+        assert_eq!(
+            peptide.add_fixed_modification(&met_loss, ProteinTermini::UNKNOWN),
+            1
+        );
+        assert_eq!(peptide.get_n_term().len(), 1);
+    }
 }
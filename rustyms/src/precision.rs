@@ -0,0 +1,194 @@
+//! The precision policy that is used for displaying and comparing masses
+
+use std::sync::RwLock;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    system::{Mass, MassOverCharge},
+    Tolerance, WithinTolerance,
+};
+
+/// The precision policy, defining the number of decimal places shown for masses, m/z values, and
+/// ppm values, and the default tolerance for mass comparisons. This is used by the [`Display`](std::fmt::Display)
+/// implementations of [`Fragment`](crate::Fragment) and [`Tolerance`], and as the default tolerance
+/// for mass based alignment and modification searches. The policy used by the library is set
+/// with [`Precision::set_global`] so that all outputs agree with each other.
+///
+/// ```rust
+/// # use rustyms::{*, system::da};
+/// let precision = Precision::default().mass_decimals(Some(2));
+/// assert_eq!(precision.format_mass(15.994_915), "15.99");
+/// assert_eq!(Precision::default().format_mass(15.994_915), "15.994915");
+/// assert!(precision.same_mass(da(1000.0), da(1000.005)));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Precision {
+    /// The number of decimal places for masses, `None` shows the full precision.
+    ///
+    /// Default: `None`.
+    pub mass_decimals: Option<usize>,
+    /// The number of decimal places for m/z values, `None` shows the full precision.
+    ///
+    /// Default: `None`.
+    pub mz_decimals: Option<usize>,
+    /// The number of decimal places for ppm values, `None` shows the full precision.
+    ///
+    /// Default: `None`.
+    pub ppm_decimals: Option<usize>,
+    /// The tolerance in ppm for mass comparisons when no explicit tolerance is given.
+    ///
+    /// Default: 10 ppm.
+    pub comparison_ppm: f64,
+}
+
+/// The precision policy used throughout the library
+static GLOBAL_PRECISION: RwLock<Precision> = RwLock::new(Precision::new());
+
+impl Default for Precision {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Precision {
+    /// Create the default precision policy, see the documentation on the fields for the defaults
+    pub const fn new() -> Self {
+        Self {
+            mass_decimals: None,
+            mz_decimals: None,
+            ppm_decimals: None,
+            comparison_ppm: 10.0,
+        }
+    }
+
+    /// Get the precision policy that is currently used throughout the library
+    pub fn global() -> Self {
+        *GLOBAL_PRECISION
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Set this precision policy to be used throughout the library
+    pub fn set_global(self) {
+        *GLOBAL_PRECISION
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = self;
+    }
+
+    /// Set the number of decimal places for masses
+    #[must_use]
+    pub const fn mass_decimals(self, mass_decimals: Option<usize>) -> Self {
+        Self {
+            mass_decimals,
+            ..self
+        }
+    }
+
+    /// Set the number of decimal places for m/z values
+    #[must_use]
+    pub const fn mz_decimals(self, mz_decimals: Option<usize>) -> Self {
+        Self {
+            mz_decimals,
+            ..self
+        }
+    }
+
+    /// Set the number of decimal places for ppm values
+    #[must_use]
+    pub const fn ppm_decimals(self, ppm_decimals: Option<usize>) -> Self {
+        Self {
+            ppm_decimals,
+            ..self
+        }
+    }
+
+    /// Set the tolerance in ppm for mass comparisons
+    #[must_use]
+    pub const fn comparison_ppm(self, comparison_ppm: f64) -> Self {
+        Self {
+            comparison_ppm,
+            ..self
+        }
+    }
+
+    /// Format a mass (in dalton) with the number of decimal places of this policy
+    pub fn format_mass(&self, value: f64) -> String {
+        format_decimals(value, self.mass_decimals, false)
+    }
+
+    /// Format a mass (in dalton) with the number of decimal places of this policy, always
+    /// showing the sign
+    pub fn format_signed_mass(&self, value: f64) -> String {
+        format_decimals(value, self.mass_decimals, true)
+    }
+
+    /// Format an m/z value with the number of decimal places of this policy
+    pub fn format_mz(&self, value: f64) -> String {
+        format_decimals(value, self.mz_decimals, false)
+    }
+
+    /// Format a ppm value with the number of decimal places of this policy
+    pub fn format_ppm(&self, value: f64) -> String {
+        format_decimals(value, self.ppm_decimals, false)
+    }
+
+    /// The tolerance to use for mass comparisons
+    pub fn tolerance<T>(&self) -> Tolerance<T> {
+        Tolerance::new_ppm(self.comparison_ppm)
+    }
+
+    /// Check if two masses are the same within the comparison tolerance of this policy
+    pub fn same_mass(&self, a: Mass, b: Mass) -> bool {
+        self.tolerance::<Mass>().within(&a, &b)
+    }
+
+    /// Check if two m/z values are the same within the comparison tolerance of this policy
+    pub fn same_mz(&self, a: MassOverCharge, b: MassOverCharge) -> bool {
+        self.tolerance::<MassOverCharge>().within(&a, &b)
+    }
+}
+
+/// Format a number with the given number of decimal places, or with full precision if no number
+/// is given
+fn format_decimals(value: f64, decimals: Option<usize>, signed: bool) -> String {
+    match (decimals, signed) {
+        (Some(decimals), true) => format!("{value:+.decimals$}"),
+        (Some(decimals), false) => format!("{value:.decimals$}"),
+        (None, true) => format!("{value:+}"),
+        (None, false) => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::system::{da, mz};
+
+    #[test]
+    fn format() {
+        let precision = Precision::new()
+            .mass_decimals(Some(3))
+            .mz_decimals(Some(1))
+            .ppm_decimals(Some(0));
+        assert_eq!(precision.format_mass(42.010_565), "42.011");
+        assert_eq!(precision.format_signed_mass(42.010_565), "+42.011");
+        assert_eq!(precision.format_signed_mass(-18.010_565), "-18.011");
+        assert_eq!(precision.format_mz(500.276_3), "500.3");
+        assert_eq!(precision.format_ppm(-4.6), "-5");
+        assert_eq!(Precision::new().format_signed_mass(0.5), "+0.5");
+        assert_eq!(Precision::new().format_mz(500.276_3), "500.2763");
+    }
+
+    #[test]
+    fn compare() {
+        let precision = Precision::new().comparison_ppm(5.0);
+        assert!(precision.same_mass(da(1000.0), da(1000.004)));
+        assert!(!precision.same_mass(da(1000.0), da(1000.006)));
+        assert!(precision.same_mz(
+            MassOverCharge::new::<mz>(500.0),
+            MassOverCharge::new::<mz>(500.002)
+        ));
+    }
+}
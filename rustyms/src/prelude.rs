@@ -0,0 +1,29 @@
+//! The most commonly used types, traits, and functions, to get started quickly import everything
+//! from this module.
+//!
+//! ```rust
+//! use rustyms::prelude::*;
+//! let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDE/2", None).unwrap();
+//! let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(2), &Model::all());
+//! # assert!(!fragments.is_empty());
+//! ```
+
+#[cfg(feature = "identification")]
+pub use crate::facade::{
+    annotate_file, digest_database, digest_fasta, open_search, AnnotatedPsm, DigestedDatabase,
+    DigestedPeptide, DigestionSettings, OpenSearchMatch, OpenSearchSettings,
+};
+#[cfg(feature = "identification")]
+pub use crate::identification::{
+    open_identified_peptides_file, Experiment, FastaData, IdentifiedPeptide,
+};
+pub use crate::{
+    error::CustomError,
+    fragment::Fragment,
+    rawfile::mgf,
+    spectrum::{AnnotatableSpectrum, AnnotatedSpectrum, PeakSpectrum, RawSpectrum},
+    system::{da, e, mz, usize::Charge, Mass, MassOverCharge},
+    AminoAcid, Chemical, CompoundPeptidoformIon, MassMode, Model, MolecularCharge,
+    MolecularFormula, MultiChemical, Peptidoform, PeptidoformIon, Protease, Tolerance,
+    WithinTolerance,
+};
@@ -1,11 +1,18 @@
 use itertools::Itertools;
+use serde::{Deserialize, Serialize};
 
-use crate::{AminoAcid, SequenceElement};
+use std::ops::Range;
+
+use crate::{
+    error::{Context, CustomError},
+    AminoAcid, Peptidoform, SequenceElement,
+};
 
 /// A protease defined by it ability to cut at any site identified by the right amino acids at the n and c terminal.
 /// Each position is identified by an option, a none means that there is no specificity at this position. If there is
 /// a specificity at a certain position any amino acid that is contained in the set is allowed (see
 /// [`crate::CheckedAminoAcid::canonical_identical`]).
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Protease {
     /// The amino acids n terminal of the cut site.
     pub n_term: Vec<Option<Vec<AminoAcid>>>,
@@ -25,22 +32,244 @@ impl Protease {
     /// Define a protease that cuts on the n terminal side of the provided amino acids.
     pub fn n_terminal_of(residues: &[AminoAcid]) -> Self {
         Self {
-            n_term: vec![Some(residues.to_vec())],
-            c_term: Vec::new(),
+            n_term: Vec::new(),
+            c_term: vec![Some(residues.to_vec())],
         }
     }
 
     /// Define a protease that cuts on the c terminal side of the provided amino acids.
     pub fn c_terminal_of(residues: &[AminoAcid]) -> Self {
         Self {
-            c_term: vec![Some(residues.to_vec())],
-            n_term: Vec::new(),
+            n_term: vec![Some(residues.to_vec())],
+            c_term: Vec::new(),
+        }
+    }
+
+    /// Define a protease that cuts on the c terminal side of the provided amino acids, unless
+    /// the next amino acid is one of the excluded amino acids.
+    fn c_terminal_of_except(residues: &[AminoAcid], excluded: &[AminoAcid]) -> Self {
+        Self {
+            n_term: vec![Some(residues.to_vec())],
+            c_term: vec![Some(all_except(excluded))],
+        }
+    }
+
+    /// Trypsin, cuts c terminal of K and R, unless followed by P.
+    pub fn trypsin() -> Self {
+        Self::c_terminal_of_except(
+            &[AminoAcid::Lysine, AminoAcid::Arginine],
+            &[AminoAcid::Proline],
+        )
+    }
+
+    /// Trypsin/P, cuts c terminal of K and R, also if followed by P.
+    pub fn trypsin_p() -> Self {
+        Self::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine])
+    }
+
+    /// Lys-C, cuts c terminal of K.
+    pub fn lys_c() -> Self {
+        Self::c_terminal_of(&[AminoAcid::Lysine])
+    }
+
+    /// Lys-N, cuts n terminal of K.
+    pub fn lys_n() -> Self {
+        Self::n_terminal_of(&[AminoAcid::Lysine])
+    }
+
+    /// Arg-C, cuts c terminal of R, unless followed by P.
+    pub fn arg_c() -> Self {
+        Self::c_terminal_of_except(&[AminoAcid::Arginine], &[AminoAcid::Proline])
+    }
+
+    /// Glu-C (in bicarbonate buffer), cuts c terminal of E.
+    pub fn glu_c() -> Self {
+        Self::c_terminal_of(&[AminoAcid::GlutamicAcid])
+    }
+
+    /// Asp-N, cuts n terminal of D.
+    pub fn asp_n() -> Self {
+        Self::n_terminal_of(&[AminoAcid::AsparticAcid])
+    }
+
+    /// Chymotrypsin (high specificity), cuts c terminal of F, Y, and W, unless followed by P.
+    pub fn chymotrypsin() -> Self {
+        Self::c_terminal_of_except(
+            &[
+                AminoAcid::Phenylalanine,
+                AminoAcid::Tyrosine,
+                AminoAcid::Tryptophan,
+            ],
+            &[AminoAcid::Proline],
+        )
+    }
+
+    /// Pepsin (pH > 2), cuts c terminal of F, L, W, and Y, unless followed by P.
+    pub fn pepsin() -> Self {
+        Self::c_terminal_of_except(
+            &[
+                AminoAcid::Phenylalanine,
+                AminoAcid::Leucine,
+                AminoAcid::Tryptophan,
+                AminoAcid::Tyrosine,
+            ],
+            &[AminoAcid::Proline],
+        )
+    }
+
+    /// Proteinase K, cuts c terminal of A, F, I, L, V, W, and Y.
+    pub fn proteinase_k() -> Self {
+        Self::c_terminal_of(&[
+            AminoAcid::Alanine,
+            AminoAcid::Phenylalanine,
+            AminoAcid::Isoleucine,
+            AminoAcid::Leucine,
+            AminoAcid::Valine,
+            AminoAcid::Tryptophan,
+            AminoAcid::Tyrosine,
+        ])
+    }
+
+    /// Thermolysin, cuts n terminal of A, F, I, L, M, and V, unless preceded by D or E.
+    pub fn thermolysin() -> Self {
+        Self {
+            n_term: vec![Some(all_except(&[
+                AminoAcid::AsparticAcid,
+                AminoAcid::GlutamicAcid,
+            ]))],
+            c_term: vec![Some(vec![
+                AminoAcid::Alanine,
+                AminoAcid::Phenylalanine,
+                AminoAcid::Isoleucine,
+                AminoAcid::Leucine,
+                AminoAcid::Methionine,
+                AminoAcid::Valine,
+            ])],
         }
     }
 
+    /// Elastase, cuts c terminal of A, G, I, L, S, and V.
+    pub fn elastase() -> Self {
+        Self::c_terminal_of(&[
+            AminoAcid::Alanine,
+            AminoAcid::Glycine,
+            AminoAcid::Isoleucine,
+            AminoAcid::Leucine,
+            AminoAcid::Serine,
+            AminoAcid::Valine,
+        ])
+    }
+
+    /// Get one of the built in proteases by name. The name is matched case insensitive and
+    /// ignoring dashes, spaces, and underscores, so `Lys-C`, `LysC`, and `lys_c` are all
+    /// recognised. Returns None if the name is not known.
+    /// ```rust
+    /// # use rustyms::Protease;
+    /// assert_eq!(Protease::from_name("Trypsin/P"), Some(Protease::trypsin_p()));
+    /// assert_eq!(Protease::from_name("lys-c"), Some(Protease::lys_c()));
+    /// assert_eq!(Protease::from_name("unknown"), None);
+    /// ```
+    pub fn from_name(name: &str) -> Option<Self> {
+        let name = name
+            .chars()
+            .filter(|c| !matches!(c, '-' | ' ' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match name.as_str() {
+            "trypsin" => Some(Self::trypsin()),
+            "trypsin/p" => Some(Self::trypsin_p()),
+            "lysc" => Some(Self::lys_c()),
+            "lysn" => Some(Self::lys_n()),
+            "argc" => Some(Self::arg_c()),
+            "gluc" | "v8" => Some(Self::glu_c()),
+            "aspn" => Some(Self::asp_n()),
+            "chymotrypsin" => Some(Self::chymotrypsin()),
+            "pepsin" => Some(Self::pepsin()),
+            "proteinasek" => Some(Self::proteinase_k()),
+            "thermolysin" => Some(Self::thermolysin()),
+            "elastase" => Some(Self::elastase()),
+            _ => None,
+        }
+    }
+
+    /// Define a protease from a cleavage rule. The rule lists the positions around the cut site,
+    /// with the cut site itself indicated by a pipe `|`. Every position is a single amino acid
+    /// (`K`), a set of amino acids (`[KR]`), a negated set of amino acids (`[^P]`), or any amino
+    /// acid (`X` or `.`).
+    /// ```rust
+    /// # use rustyms::Protease;
+    /// assert_eq!(Protease::from_rule("[KR]|[^P]").unwrap(), Protease::trypsin());
+    /// assert_eq!(Protease::from_rule("|D").unwrap(), Protease::asp_n());
+    /// assert!(Protease::from_rule("[KR][^P]").is_err());
+    /// ```
+    /// # Errors
+    /// If the rule does not contain exactly one cut site, contains an invalid amino acid, or has
+    /// an unclosed set.
+    pub fn from_rule(rule: &str) -> Result<Self, CustomError> {
+        let error = |explanation: &str, offset: usize, length: usize| {
+            CustomError::error(
+                "Invalid cleavage rule",
+                explanation,
+                Context::line(None, rule, offset, length),
+            )
+        };
+        let amino_acid = |c: char, offset: usize| {
+            AminoAcid::try_from(c).map_err(|()| error("Not a valid amino acid", offset, 1))
+        };
+        let mut n_term = Vec::new();
+        let mut c_term = None;
+        let mut chars = rule.char_indices();
+        while let Some((index, c)) = chars.next() {
+            let position = match c {
+                '|' if c_term.is_none() => {
+                    c_term = Some(Vec::new());
+                    continue;
+                }
+                '|' => return Err(error("A rule can only contain one cut site", index, 1)),
+                'X' | 'x' | '.' => None,
+                '[' => {
+                    let mut set = Vec::new();
+                    let mut negated = false;
+                    let mut closed = false;
+                    for (i, c) in chars.by_ref() {
+                        match c {
+                            ']' => {
+                                closed = true;
+                                break;
+                            }
+                            '^' if set.is_empty() && !negated => negated = true,
+                            c => set.push(amino_acid(c, i)?),
+                        }
+                    }
+                    if !closed {
+                        return Err(error("Unclosed set", index, rule.len() - index));
+                    }
+                    Some(if negated { all_except(&set) } else { set })
+                }
+                c if c.is_ascii_whitespace() => continue,
+                c => Some(vec![amino_acid(c, index)?]),
+            };
+            c_term.as_mut().unwrap_or(&mut n_term).push(position);
+        }
+        let c_term = c_term.ok_or_else(|| {
+            error(
+                "A rule should contain a cut site, indicated with '|'",
+                0,
+                rule.len(),
+            )
+        })?;
+        Ok(Self { n_term, c_term })
+    }
+
     /// All locations in the given sequence where this protease could cut
     pub fn match_locations<T>(&self, sequence: &[SequenceElement<T>]) -> Vec<usize> {
-        (self.n_term.len()..sequence.len() - self.c_term.len())
+        // A cut at the very start or end of the sequence is not a cut
+        let start = self.n_term.len().max(1);
+        let end = sequence
+            .len()
+            .saturating_sub(self.c_term.len())
+            .min(sequence.len().saturating_sub(1));
+        (start..=end)
             .filter(|i| self.matches_at(&sequence[i - self.n_term.len()..i + self.c_term.len()]))
             .collect_vec()
     }
@@ -63,3 +292,166 @@ impl Protease {
         true
     }
 }
+
+/// A peptide resulting from a digestion, see [`Peptidoform::digest`], with its location in the
+/// digested sequence.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DigestedPeptidoform<Complexity> {
+    /// The peptide
+    pub peptide: Peptidoform<Complexity>,
+    /// The index of the first residue of this peptide in the parent sequence
+    pub start: usize,
+    /// The index of the residue after the last residue of this peptide in the parent sequence
+    pub end: usize,
+    /// The number of cleavage sites within this peptide that were not cut
+    pub missed_cleavages: usize,
+}
+
+impl<Complexity> DigestedPeptidoform<Complexity> {
+    /// Get the range this peptide spans in the parent sequence
+    pub const fn range(&self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+/// Get all canonical amino acids except the given amino acids
+fn all_except(excluded: &[AminoAcid]) -> Vec<AminoAcid> {
+    AminoAcid::CANONICAL_AMINO_ACIDS
+        .iter()
+        .copied()
+        .filter(|aa| !excluded.contains(aa))
+        .collect()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::Peptidoform;
+
+    fn digest(protease: &Protease, sequence: &str) -> Vec<String> {
+        Peptidoform::pro_forma(sequence, None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap()
+            .digest(protease, 0)
+            .iter()
+            .map(|p| p.peptide.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn locations() {
+        let peptide = Peptidoform::pro_forma("AAKAARAA", None)
+            .unwrap()
+            .into_semi_ambiguous()
+            .unwrap();
+        assert_eq!(
+            peptide
+                .digest(&Protease::trypsin(), 1)
+                .iter()
+                .map(|p| (p.peptide.to_string(), p.range(), p.missed_cleavages))
+                .collect_vec(),
+            [
+                ("AAK".to_string(), 0..3, 0),
+                ("AAKAAR".to_string(), 0..6, 1),
+                ("AAR".to_string(), 3..6, 0),
+                ("AARAA".to_string(), 3..8, 1),
+                ("AA".to_string(), 6..8, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn catalogue() {
+        assert_eq!(
+            digest(&Protease::trypsin(), "AAKPAARAAKAA"),
+            ["AAKPAAR", "AAK", "AA"]
+        );
+        assert_eq!(
+            digest(&Protease::trypsin_p(), "AAKPAARAAKAA"),
+            ["AAK", "PAAR", "AAK", "AA"]
+        );
+        assert_eq!(digest(&Protease::lys_c(), "AAKPAARK"), ["AAK", "PAARK"]);
+        assert_eq!(digest(&Protease::lys_n(), "AAKPAARK"), ["AA", "KPAAR", "K"]);
+        assert_eq!(digest(&Protease::arg_c(), "AARPAARAK"), ["AARPAAR", "AK"]);
+        assert_eq!(digest(&Protease::glu_c(), "AAEAADAA"), ["AAE", "AADAA"]);
+        assert_eq!(digest(&Protease::asp_n(), "AAEAADAA"), ["AAEAA", "DAA"]);
+        assert_eq!(
+            digest(&Protease::chymotrypsin(), "AAFPAAWAAYAL"),
+            ["AAFPAAW", "AAY", "AL"]
+        );
+        assert_eq!(digest(&Protease::pepsin(), "GGLGGFPG"), ["GGL", "GGFPG"]);
+        assert_eq!(
+            digest(&Protease::proteinase_k(), "GGAGGGWG"),
+            ["GGA", "GGGW", "G"]
+        );
+        assert_eq!(
+            digest(&Protease::thermolysin(), "GGLGELGGMG"),
+            ["GG", "LGELGG", "MG"]
+        );
+        assert_eq!(
+            digest(&Protease::elastase(), "WWAWWSWW"),
+            ["WWA", "WWS", "WW"]
+        );
+    }
+
+    #[test]
+    fn cut_side() {
+        let c_terminal = Protease::c_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]);
+        assert_eq!(digest(&c_terminal, "AAKAARAA"), ["AAK", "AAR", "AA"]);
+        let n_terminal = Protease::n_terminal_of(&[AminoAcid::Lysine, AminoAcid::Arginine]);
+        assert_eq!(digest(&n_terminal, "AAKAARAA"), ["AA", "KAA", "RAA"]);
+    }
+
+    #[test]
+    fn no_cut_at_termini() {
+        assert_eq!(digest(&Protease::lys_c(), "AAK"), ["AAK"]);
+        assert_eq!(digest(&Protease::lys_n(), "KAA"), ["KAA"]);
+        assert_eq!(digest(&Protease::trypsin(), "K"), ["K"]);
+    }
+
+    #[test]
+    fn names() {
+        for name in [
+            "Trypsin",
+            "Trypsin/P",
+            "Lys-C",
+            "LysN",
+            "arg_c",
+            "Glu-C",
+            "Asp-N",
+            "Chymotrypsin",
+            "Pepsin",
+            "Proteinase K",
+            "Thermolysin",
+            "Elastase",
+        ] {
+            assert!(Protease::from_name(name).is_some(), "{name}");
+        }
+        assert_eq!(Protease::from_name("V8"), Some(Protease::glu_c()));
+        assert_eq!(Protease::from_name("Trypsin"), Some(Protease::trypsin()));
+        assert_ne!(Protease::from_name("Trypsin"), Some(Protease::trypsin_p()));
+    }
+
+    #[test]
+    fn rules() {
+        assert_eq!(
+            Protease::from_rule("[KR]|[^P]").unwrap(),
+            Protease::trypsin()
+        );
+        assert_eq!(Protease::from_rule("[KR]|").unwrap(), Protease::trypsin_p());
+        assert_eq!(Protease::from_rule("|K").unwrap(), Protease::lys_n());
+        assert_eq!(
+            Protease::from_rule("[^DE] | [AFILMV]").unwrap(),
+            Protease::thermolysin()
+        );
+        let any = Protease::from_rule("XE|.").unwrap();
+        assert_eq!(any.n_term, vec![None, Some(vec![AminoAcid::GlutamicAcid])]);
+        assert_eq!(any.c_term, vec![None]);
+        assert!(Protease::from_rule("KR").is_err());
+        assert!(Protease::from_rule("K||R").is_err());
+        assert!(Protease::from_rule("K|[^P").is_err());
+        assert!(Protease::from_rule("K|1").is_err());
+    }
+}
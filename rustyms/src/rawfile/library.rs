@@ -0,0 +1,309 @@
+//! Write spectral libraries (MSP or mzSpecLib) from annotated spectra
+use std::io::Write;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Context, CustomError},
+    fragment::Fragment,
+    identification::IdentifiedPeptide,
+    spectrum::{AnnotatedPeak, AnnotatedSpectrum, PeakSpectrum},
+    system::{f64::Mass, time::s, usize::Charge},
+    Chemical, MolecularCharge,
+};
+
+/// The format of a spectral library
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LibraryFormat {
+    /// The [mzSpecLib](https://www.psidev.info/mzspeclib) text format
+    #[default]
+    MzSpecLib,
+    /// The NIST MSP format
+    Msp,
+}
+
+/// Write a spectral library with all given spectra, optionally with the identified peptide that
+/// gave rise to each spectrum for additional metadata (retention time, charge, score, and
+/// protein). The peptide of each annotated spectrum is used as the analyte, and all peak
+/// annotations are written as [mzPAF](https://www.psidev.info/mzPAF), see [`crate::fragment::Fragment::to_mzpaf`].
+/// Unannotated peaks are written with the unknown annotation ('?').
+///
+/// ```rust
+/// # use rustyms::{*, rawfile::library::*, spectrum::AnnotatableSpectrum, system::{usize::Charge, e}};
+/// let model = Model::all();
+/// let spectrum = &rawfile::mgf::open("data/example.mgf").unwrap()[0];
+/// let peptide = CompoundPeptidoformIon::pro_forma("WFWF", None).unwrap();
+/// let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &model);
+/// let annotated = spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic);
+/// let mut library = Vec::new();
+/// write(&mut library, LibraryFormat::Msp, [(&annotated, None)]).unwrap();
+/// assert!(String::from_utf8(library).unwrap().starts_with("Name: WFWF/"));
+/// ```
+/// # Errors
+/// If the writer could not be written to.
+pub fn write<'a>(
+    mut writer: impl Write,
+    format: LibraryFormat,
+    entries: impl IntoIterator<Item = (&'a AnnotatedSpectrum, Option<&'a IdentifiedPeptide>)>,
+) -> Result<(), CustomError> {
+    match format {
+        LibraryFormat::MzSpecLib => {
+            writeln!(writer, "<mzSpecLib>\nMS:1003186|library format version=1.0")
+                .map_err(write_error)?;
+            for (index, (spectrum, identified)) in entries.into_iter().enumerate() {
+                write_mzspeclib_entry(&mut writer, index + 1, spectrum, identified)
+                    .map_err(write_error)?;
+            }
+        }
+        LibraryFormat::Msp => {
+            for (spectrum, identified) in entries {
+                write_msp_entry(&mut writer, spectrum, identified).map_err(write_error)?;
+            }
+        }
+    }
+    writer.flush().map_err(write_error)
+}
+
+/// Write a single mzSpecLib spectrum.
+/// # Errors
+/// If the writer could not be written to.
+fn write_mzspeclib_entry(
+    writer: &mut impl Write,
+    key: usize,
+    spectrum: &AnnotatedSpectrum,
+    identified: Option<&IdentifiedPeptide>,
+) -> std::io::Result<()> {
+    let charge = precursor_charge(spectrum, identified);
+    writeln!(writer, "<Spectrum={key}>")?;
+    writeln!(writer, "MS:1003237|library spectrum key={key}")?;
+    writeln!(
+        writer,
+        "MS:1003061|library spectrum name={}",
+        name(spectrum, charge)
+    )?;
+    if let Some(mz) = precursor_mz(spectrum.mass, charge) {
+        writeln!(
+            writer,
+            "MS:1003208|experimental precursor monoisotopic m/z={mz}"
+        )?;
+    }
+    if let Some(charge) = charge {
+        writeln!(writer, "MS:1000041|charge state={}", charge.value)?;
+    }
+    if let Some(rt) = spectrum
+        .rt
+        .or_else(|| identified.and_then(IdentifiedPeptide::retention_time))
+    {
+        writeln!(
+            writer,
+            "[1]MS:1000894|retention time={}\n[1]UO:0000000|unit=UO:0000010|second",
+            rt.get::<s>()
+        )?;
+    }
+    for (index, peptidoform_ion) in spectrum.peptide.peptidoform_ions().iter().enumerate() {
+        writeln!(writer, "<Analyte={}>", index + 1)?;
+        writeln!(
+            writer,
+            "MS:1003169|proforma peptidoform sequence={peptidoform_ion}"
+        )?;
+        if let Some(formula) = peptidoform_ion.formulas().first() {
+            writeln!(
+                writer,
+                "MS:1001117|theoretical neutral mass={}",
+                formula.monoisotopic_mass().value
+            )?;
+        }
+        if let Some(protein) = identified.and_then(IdentifiedPeptide::protein_name) {
+            writeln!(writer, "MS:1000885|protein accession={protein}")?;
+        }
+    }
+    // The score is normalised to -1..=1 but not a probability, so it is stored as an engine specific statistic
+    if let Some(score) = identified.and_then(|i| i.score) {
+        writeln!(
+            writer,
+            "<Interpretation=1>\nMS:1001143|PSM-level search engine specific statistic={score}"
+        )?;
+    }
+    writeln!(writer, "<Peaks>")?;
+    for peak in spectrum.spectrum() {
+        writeln!(
+            writer,
+            "{}\t{}\t{}",
+            peak.experimental_mz.value,
+            peak.intensity,
+            annotation(peak)
+        )?;
+    }
+    writeln!(writer)
+}
+
+/// Write a single MSP spectrum.
+/// # Errors
+/// If the writer could not be written to.
+fn write_msp_entry(
+    writer: &mut impl Write,
+    spectrum: &AnnotatedSpectrum,
+    identified: Option<&IdentifiedPeptide>,
+) -> std::io::Result<()> {
+    let charge = precursor_charge(spectrum, identified);
+    writeln!(writer, "Name: {}", name(spectrum, charge))?;
+    if let Some(formula) = spectrum.peptide.formulas().first() {
+        writeln!(writer, "MW: {}", formula.monoisotopic_mass().value)?;
+    }
+    let precursor = precursor_mz(spectrum.mass, charge);
+    if let Some(mz) = precursor {
+        writeln!(writer, "PrecursorMZ: {mz}")?;
+    }
+    let mut comment = Vec::new();
+    if let Some(charge) = charge {
+        comment.push(format!("Charge={}", charge.value));
+    }
+    if let Some(mz) = precursor {
+        comment.push(format!("Parent={mz}"));
+    }
+    if let Some(rt) = spectrum
+        .rt
+        .or_else(|| identified.and_then(IdentifiedPeptide::retention_time))
+    {
+        comment.push(format!("RetentionTime={}", rt.get::<s>()));
+    }
+    if let Some(score) = identified.and_then(|i| i.score) {
+        comment.push(format!("Score={score}"));
+    }
+    if let Some(protein) = identified.and_then(IdentifiedPeptide::protein_name) {
+        comment.push(format!("Protein={protein}"));
+    }
+    comment.push(format!("ProForma={}", spectrum.peptide));
+    writeln!(writer, "Comment: {}", comment.join(" "))?;
+    writeln!(writer, "Num peaks: {}", spectrum.spectrum().len())?;
+    for peak in spectrum.spectrum() {
+        writeln!(
+            writer,
+            "{}\t{}\t\"{}\"",
+            peak.experimental_mz.value,
+            peak.intensity,
+            annotation(peak)
+        )?;
+    }
+    writeln!(writer)
+}
+
+/// The name of a library spectrum, the ProForma sequence followed by the charge (if known)
+fn name(spectrum: &AnnotatedSpectrum, charge: Option<Charge>) -> String {
+    charge.map_or_else(
+        || spectrum.peptide.to_string(),
+        |charge| format!("{}/{}", spectrum.peptide, charge.value),
+    )
+}
+
+/// The precursor charge as found in the spectrum, or as given by the identified peptide
+fn precursor_charge(
+    spectrum: &AnnotatedSpectrum,
+    identified: Option<&IdentifiedPeptide>,
+) -> Option<Charge> {
+    spectrum
+        .charge
+        .or_else(|| identified.and_then(IdentifiedPeptide::charge))
+}
+
+/// Calculate the protonated precursor m/z
+fn precursor_mz(mass: Option<Mass>, charge: Option<Charge>) -> Option<f64> {
+    let (mass, charge) = (mass?, charge?);
+    let z = isize::try_from(charge.value).ok().filter(|z| *z > 0)?;
+    Some((mass + MolecularCharge::proton(z).formula().monoisotopic_mass()).value / z as f64)
+}
+
+/// The mzPAF annotation of a peak, '?' if unannotated
+fn annotation(peak: &AnnotatedPeak) -> String {
+    if peak.annotation.is_empty() {
+        "?".to_string()
+    } else {
+        peak.annotation
+            .iter()
+            .map(Fragment::to_mzpaf)
+            .unique()
+            .join(",")
+    }
+}
+
+/// Convert an IO error into a [`CustomError`]
+fn write_error(error: std::io::Error) -> CustomError {
+    CustomError::error("Could not write spectral library", error, Context::none())
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        mzpaf::parse_mzpaf,
+        spectrum::AnnotatableSpectrum,
+        system::{dalton, e},
+        CompoundPeptidoformIon, MassMode, Model,
+    };
+
+    #[test]
+    fn round_trip_annotations() {
+        let model = Model::all();
+        let mut spectrum = crate::rawfile::mgf::open("data/annotated_example.mgf")
+            .unwrap()
+            .remove(0);
+        spectrum.mass = Some(Mass::new::<dalton>(1000.0));
+        let peptide = CompoundPeptidoformIon::pro_forma("VAEINPSNGGTTFNEKFKGGKATJ", None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+        let annotated = spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic);
+        assert!(annotated.spectrum().any(|p| !p.annotation.is_empty()));
+
+        for format in [LibraryFormat::MzSpecLib, LibraryFormat::Msp] {
+            let mut library = Vec::new();
+            write(&mut library, format, [(&annotated, None)]).unwrap();
+            let library = String::from_utf8(library).unwrap();
+            let peaks = library
+                .lines()
+                .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+                .collect_vec();
+            assert_eq!(peaks.len(), annotated.spectrum().len());
+            for line in peaks {
+                let annotation = line.split('\t').nth(2).unwrap().trim_matches('"');
+                parse_mzpaf(annotation).unwrap();
+            }
+            assert!(library.contains("VAEINPSNGGTTFNEKFKGGKATJ/4"));
+            assert!(library.contains("251.007276"));
+            assert!(library.contains("\ty2-H2O1") || library.contains("\t\"y2-H2O1\""));
+        }
+    }
+
+    #[test]
+    fn annotation_deviations() {
+        let model = Model::all();
+        let mut spectrum = crate::rawfile::mgf::open("data/annotated_example.mgf")
+            .unwrap()
+            .remove(0);
+        spectrum.mass = Some(Mass::new::<dalton>(1000.0));
+        let peptide = CompoundPeptidoformIon::pro_forma("VAEINPSNGGTTFNEKFKGGKATJ", None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+        let annotated = crate::spectrum::annotate(
+            &spectrum,
+            peptide,
+            &fragments,
+            model.tolerance,
+            MassMode::Monoisotopic,
+        );
+
+        for format in [LibraryFormat::MzSpecLib, LibraryFormat::Msp] {
+            let mut library = Vec::new();
+            write(&mut library, format, [(&annotated, None)]).unwrap();
+            let library = String::from_utf8(library).unwrap();
+            for line in library
+                .lines()
+                .filter(|l| l.starts_with(|c: char| c.is_ascii_digit()))
+            {
+                let annotation = line.split('\t').nth(2).unwrap().trim_matches('"');
+                parse_mzpaf(annotation).unwrap();
+            }
+            assert!(library.contains("y2-H2O1/"));
+            assert!(library.contains("ppm"));
+        }
+    }
+}
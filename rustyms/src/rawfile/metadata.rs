@@ -0,0 +1,138 @@
+//! Run level metadata of raw files
+
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::system::f64::Time;
+
+/// The metadata of a single run (raw file), as needed for reporting formats like mzTab and mzIdentML.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct RunMetadata {
+    /// The name of the original raw file
+    pub raw_file: Option<PathBuf>,
+    /// The instrument model, eg 'Q Exactive'
+    pub instrument_model: Option<String>,
+    /// The instrument serial number
+    pub instrument_serial: Option<String>,
+    /// The acquisition method(s), for Thermo files this is the full text of the instrument method
+    pub acquisition_method: Vec<String>,
+    /// The start time stamp of the run, in RFC 3339 format
+    pub start_time_stamp: Option<String>,
+    /// The gradient length, defined as the time between the first and the last spectrum
+    pub gradient_length: Option<Time>,
+}
+
+#[cfg(feature = "mzdata")]
+impl RunMetadata {
+    /// Extract the metadata from any mzdata reader, this supports mzML, and if the respective
+    /// mzdata features are turned on Thermo raw files and Bruker TDF files. The gradient length is
+    /// not determined as that needs access to the spectra, see [`Self::from_mzdata_source`].
+    pub fn from_mzdata(source: &impl mzdata::meta::MSDataFileMetadata) -> Self {
+        const CUSTOMIZATION: u32 = 1_000_032;
+        const SERIAL_NUMBER: u32 = 1_000_529;
+
+        let mut metadata = Self {
+            raw_file: source.source_file_name().map(PathBuf::from),
+            start_time_stamp: source
+                .run_description()
+                .and_then(|run| run.start_time)
+                .map(|time| time.to_rfc3339()),
+            ..Self::default()
+        };
+        let mut configurations = source
+            .instrument_configurations()
+            .iter()
+            .collect::<Vec<_>>();
+        configurations.sort_unstable_by_key(|(id, _)| **id);
+        for (_, configuration) in configurations {
+            for param in &configuration.params {
+                let is_ms =
+                    param.controlled_vocabulary == Some(mzdata::params::ControlledVocabulary::MS);
+                match param.accession {
+                    Some(SERIAL_NUMBER) if is_ms => {
+                        metadata
+                            .instrument_serial
+                            .get_or_insert_with(|| param.value.to_string());
+                    }
+                    Some(CUSTOMIZATION) if is_ms => {
+                        let method = param.value.to_string();
+                        if !metadata.acquisition_method.contains(&method) {
+                            metadata.acquisition_method.push(method);
+                        }
+                    }
+                    // Instrument models are given as a valueless term
+                    Some(_) if is_ms && param.value.to_string().is_empty() => {
+                        metadata
+                            .instrument_model
+                            .get_or_insert_with(|| param.name.clone());
+                    }
+                    _ => (),
+                }
+            }
+        }
+        metadata
+    }
+
+    /// Extract the metadata from any mzdata reader that also allows random access to the spectra,
+    /// see [`Self::from_mzdata`]. The gradient length is determined by the retention time of the
+    /// first and last spectrum.
+    pub fn from_mzdata_source<C, D, S, R>(source: &mut R) -> Self
+    where
+        C: mzdata::prelude::CentroidLike + Default,
+        D: mzdata::prelude::DeconvolutedCentroidLike + Default,
+        S: mzdata::prelude::SpectrumLike<C, D>,
+        R: mzdata::prelude::SpectrumSource<C, D, S> + mzdata::meta::MSDataFileMetadata,
+    {
+        let mut metadata = Self::from_mzdata(source);
+        let last = source.len().checked_sub(1);
+        metadata.gradient_length = last
+            .and_then(|last| {
+                source
+                    .get_spectrum_by_index(0)
+                    .zip(source.get_spectrum_by_index(last))
+            })
+            .map(|(first, last)| {
+                Time::new::<crate::system::time::min>(last.start_time() - first.start_time())
+            });
+        metadata
+    }
+}
+
+#[cfg(all(test, feature = "mzdata"))]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mzml() {
+        let mut reader = mzdata::io::MzMLReader::new_indexed(
+            std::fs::File::open("data/metadata_example.mzML").unwrap(),
+        );
+        let metadata = RunMetadata::from_mzdata_source(&mut reader);
+        assert_eq!(
+            metadata.raw_file.as_deref(),
+            Some(std::path::Path::new("example.raw"))
+        );
+        assert_eq!(metadata.instrument_model.as_deref(), Some("Q Exactive"));
+        assert_eq!(
+            metadata.instrument_serial.as_deref(),
+            Some("Exactive Series slot #1")
+        );
+        assert_eq!(metadata.acquisition_method, ["Top10 HCD 60 min"]);
+        assert_eq!(
+            metadata.start_time_stamp.as_deref(),
+            Some("2024-01-02T03:04:05+00:00")
+        );
+        assert!(
+            (metadata
+                .gradient_length
+                .unwrap()
+                .get::<crate::system::time::min>()
+                - 60.0)
+                .abs()
+                < 1e-10
+        );
+    }
+}
@@ -1,17 +1,18 @@
 //! Handle MGF reader reading
 use std::{
     fs::File,
-    io::{BufRead, BufReader},
+    io::{BufRead, BufReader, Seek, SeekFrom, Write},
     path::Path,
+    sync::OnceLock,
 };
 
-use ordered_float::OrderedFloat;
 use regex::Regex;
+use serde::{Deserialize, Serialize};
 use uom::num_traits::Zero;
 
 use crate::{
     error::{Context, CustomError},
-    helper_functions::check_extension,
+    helper_functions::{check_extension, open_file},
     spectrum::{PeakSpectrum, RawPeak, RawSpectrum},
     system::{
         charge::e,
@@ -21,6 +22,7 @@ use crate::{
         time::s,
         usize::Charge,
     },
+    Precision,
 };
 use flate2::bufread::GzDecoder;
 
@@ -33,8 +35,31 @@ use flate2::bufread::GzDecoder;
 /// * When any expected number in the file is not a number
 /// * When there is only one column (separated by space or tab) on a data row
 pub fn open(path: impl AsRef<Path>) -> Result<Vec<RawSpectrum>, CustomError> {
+    open_stream(path)?.collect()
+}
+
+/// Open a MGF file and return the contained spectra. Open it from a raw buffered reader.
+///
+/// # Errors
+/// It returns an error when:
+/// * The file could not be opened
+/// * Any line in the file could not be read
+/// * When any expected number in the file is not a number
+/// * When there is only one column (separated by space or tab) on a data row
+pub fn open_raw<T: std::io::Read>(reader: T) -> Result<Vec<RawSpectrum>, CustomError> {
+    MgfReader::new(BufReader::new(reader)).collect()
+}
+
+/// Open a MGF file and return an iterator that lazily reads one spectrum at a time. This keeps
+/// the memory usage constant regardless of the size of the file. It will uncompress gzipped
+/// files automatically.
+///
+/// # Errors
+/// It returns an error when the file could not be opened. Any error in reading a spectrum is
+/// returned by the iterator, see [`MgfReader`].
+pub fn open_stream(path: impl AsRef<Path>) -> Result<MgfReader<Box<dyn BufRead>>, CustomError> {
     let path = path.as_ref();
-    let file = File::open(path).map_err(|err| {
+    let file = open_file(path).map_err(|err| {
         CustomError::error(
             "Could not open file",
             format!("Additional info: {err}"),
@@ -42,43 +67,61 @@ pub fn open(path: impl AsRef<Path>) -> Result<Vec<RawSpectrum>, CustomError> {
         )
     })?;
     if check_extension(path, "gz") {
-        open_raw(GzDecoder::new(BufReader::new(file)))
+        Ok(MgfReader::new(Box::new(BufReader::new(GzDecoder::new(
+            BufReader::new(file),
+        )))))
     } else {
-        open_raw(file)
+        Ok(MgfReader::new(Box::new(BufReader::new(file))))
     }
 }
 
-/// Open a MGF file and return the contained spectra. Open it from a raw buffered reader.
+/// An iterator over the spectra in a MGF file, reading one spectrum at a time.
 ///
-/// # Errors
-/// It returns an error when:
-/// * The file could not be opened
-/// * Any line in the file could not be read
-/// * When any expected number in the file is not a number
-/// * When there is only one column (separated by space or tab) on a data row
-#[allow(clippy::missing_panics_doc)]
-pub fn open_raw<T: std::io::Read>(reader: T) -> Result<Vec<RawSpectrum>, CustomError> {
-    let reader = BufReader::new(reader);
-    let mut current = RawSpectrum::default();
-    let mut output = Vec::new();
-    for (line_index, line) in reader.lines().enumerate() {
-        let line = line.map_err(|err| {
+/// If a spectrum could not be parsed the error is returned and the reader continues with the
+/// next spectrum.
+pub struct MgfReader<R: BufRead> {
+    lines: std::iter::Enumerate<std::io::Lines<R>>,
+    line_offset: usize,
+    current: RawSpectrum,
+    skip_to_end: bool,
+}
+
+impl<R: BufRead> MgfReader<R> {
+    /// Create a new reader for the given buffered reader
+    pub fn new(reader: R) -> Self {
+        Self::with_line_offset(reader, 0)
+    }
+
+    /// Create a new reader with the line numbers in errors offset by the given amount
+    fn with_line_offset(reader: R, line_offset: usize) -> Self {
+        Self {
+            lines: reader.lines().enumerate(),
+            line_offset,
+            current: RawSpectrum::default(),
+            skip_to_end: false,
+        }
+    }
+
+    /// Parse a single line, returns the spectrum if this line finishes a spectrum
+    /// # Errors
+    /// When any expected number is not a number or a data row does not have enough columns.
+    #[allow(clippy::missing_panics_doc)]
+    fn parse_line(
+        &mut self,
+        line_index: usize,
+        line: &str,
+    ) -> Result<Option<RawSpectrum>, CustomError> {
+        let base_error = || {
             CustomError::error(
                 "Could not read mgf file",
-                format!("Error while reading line: {err}"),
-                Context::show(format!("Line number {}", line_index + 1)),
+                "..",
+                Context::full_line(line_index, line),
             )
-        })?;
-        let base_error = CustomError::error(
-            "Could not read mgf file",
-            "..",
-            Context::full_line(line_index, line.clone()),
-        );
-        match line.as_str() {
+        };
+        match line {
             "BEGIN IONS" | "" => (),
             "END IONS" => {
-                output.push(current);
-                current = RawSpectrum::default();
+                return Ok(Some(std::mem::take(&mut self.current)));
             }
             t if t.contains('=') => {
                 // THe previous line made sure it will always contain an equals sign
@@ -86,48 +129,59 @@ pub fn open_raw<T: std::io::Read>(reader: T) -> Result<Vec<RawSpectrum>, CustomE
                 match key {
                     "PEPMASS" => match value.split_once(' ') {
                         None => {
-                            current.mass =
+                            self.current.mass =
                                 Some(Mass::new::<dalton>(value.parse().map_err(|_| {
-                                    base_error.with_long_description(format!(
+                                    base_error().with_long_description(format!(
                                         "Not a number {key} for PEPMASS"
                                     ))
                                 })?));
                         }
                         Some((mass, intensity)) => {
-                            current.mass =
+                            self.current.mass =
                                 Some(Mass::new::<dalton>(mass.parse().map_err(|_| {
-                                    base_error.with_long_description(format!(
+                                    base_error().with_long_description(format!(
                                         "Not a number {key} for PEPMASS"
                                     ))
                                 })?));
-                            current.intensity = Some(intensity.parse().map_err(|_| {
-                                base_error.with_long_description(format!(
+                            self.current.intensity = Some(intensity.parse().map_err(|_| {
+                                base_error().with_long_description(format!(
                                     "Not a number {key} for PEPMASS"
                                 ))
                             })?);
                         }
                     },
                     "CHARGE" => {
-                        current.charge = Some(parse_charge(value).map_err(|()| {
-                            base_error
+                        self.current.charge = Some(parse_charge(value).map_err(|()| {
+                            base_error()
                                 .with_long_description(format!("Not a number {key} for CHARGE"))
                         })?);
                     }
-                    "RT" => {
-                        current.rt = Some(Time::new::<s>(value.parse().map_err(|_| {
-                            base_error.with_long_description(format!("Not a number {key} for RT"))
+                    "RT" | "RTINSECONDS" => {
+                        self.current.rt = Some(Time::new::<s>(value.parse().map_err(|_| {
+                            base_error().with_long_description(format!("Not a number {key} for RT"))
                         })?));
                     }
-                    "RTINSECONDS" => {
-                        current.rt = Some(Time::new::<s>(value.parse().map_err(|_| {
-                            base_error.with_long_description(format!("Not a number {key} for RT"))
-                        })?));
+                    "ION_MOBILITY" | "1/K0" => {
+                        self.current.ion_mobility = Some(value.parse().map_err(|_| {
+                            base_error().with_long_description(format!(
+                                "Not a number {key} for ION_MOBILITY"
+                            ))
+                        })?);
+                    }
+                    "CCS" => {
+                        self.current.ccs = Some(value.parse().map_err(|_| {
+                            base_error()
+                                .with_long_description(format!("Not a number {key} for CCS"))
+                        })?);
+                    }
+                    "TITLE" => parse_title(value, &mut self.current),
+                    "SCANS" if self.current.raw_scan_number.is_none() => {
+                        self.current.raw_scan_number = value.parse().ok();
                     }
-                    "TITLE" => parse_title(value, &mut current),
-                    "SEQUENCE" => current.sequence = Some(value.to_owned()),
+                    "SEQUENCE" => self.current.sequence = Some(value.to_owned()),
                     "NUM_SCANS" => {
-                        current.num_scans = value.parse().map_err(|_| {
-                            base_error
+                        self.current.num_scans = value.parse().map_err(|_| {
+                            base_error()
                                 .with_long_description(format!("Not a number {key} for NUM_SCANS"))
                         })?;
                     }
@@ -140,32 +194,335 @@ pub fn open_raw<T: std::io::Read>(reader: T) -> Result<Vec<RawSpectrum>, CustomE
                 } else {
                     t.split('\t').collect::<Vec<_>>()
                 };
-                let mut peak = RawPeak {
-                    mz: MassOverCharge::zero(),
-                    intensity: OrderedFloat(0.0),
-                };
+                let mut peak = RawPeak::new(MassOverCharge::zero(), 0.0);
                 if split.len() < 2 {
-                    return Err(base_error.with_long_description("Not enough columns"));
+                    return Err(base_error().with_long_description("Not enough columns"));
                 }
                 peak.mz = MassOverCharge::new::<mz>(split[0].parse().map_err(|_| {
-                    base_error.with_long_description(format!("Not a number {} for MZ", split[0]))
+                    base_error().with_long_description(format!("Not a number {} for MZ", split[0]))
                 })?);
                 peak.intensity = split[1].parse().map_err(|_| {
-                    base_error
+                    base_error()
                         .with_long_description(format!("Not a number {} for INTENSITY", split[1]))
                 })?;
                 if split.len() >= 3 {
                     _ = parse_charge(split[2]).map_err(|()| {
-                        base_error
+                        base_error()
                             .with_long_description(format!("Not a number {} for CHARGE", split[2]))
                     })?;
                 }
-                current.add_peak(peak);
+                self.current.add_peak(peak);
             }
             _ => {}
         }
+        Ok(None)
     }
-    Ok(output)
+}
+
+impl<R: BufRead> Iterator for MgfReader<R> {
+    type Item = Result<RawSpectrum, CustomError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((line_index, line)) = self.lines.next() {
+            let line_index = line_index + self.line_offset;
+            let line = match line {
+                Ok(line) => line,
+                Err(err) => {
+                    return Some(Err(CustomError::error(
+                        "Could not read mgf file",
+                        format!("Error while reading line: {err}"),
+                        Context::show(format!("Line number {}", line_index + 1)),
+                    )))
+                }
+            };
+            if self.skip_to_end {
+                if line == "END IONS" {
+                    self.skip_to_end = false;
+                    self.current = RawSpectrum::default();
+                }
+                continue;
+            }
+            match self.parse_line(line_index, &line) {
+                Ok(Some(spectrum)) => return Some(Ok(spectrum)),
+                Ok(None) => (),
+                Err(err) => {
+                    self.skip_to_end = true;
+                    return Some(Err(err));
+                }
+            }
+        }
+        None
+    }
+}
+
+impl<R: BufRead> std::iter::FusedIterator for MgfReader<R> {}
+
+/// An index into a MGF file, allowing random access to the spectra by index, scan number, or
+/// title without keeping all spectra in memory. Only the location and identifiers of each
+/// spectrum are kept in memory.
+pub struct IndexedMgf<R: BufRead + Seek> {
+    reader: R,
+    index: Vec<MgfIndexEntry>,
+}
+
+/// The location and identifiers of a single spectrum in a MGF file
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub struct MgfIndexEntry {
+    /// The byte offset of the `BEGIN IONS` line
+    pub offset: u64,
+    /// The line index of the `BEGIN IONS` line
+    pub line_index: usize,
+    /// The title of the spectrum
+    pub title: String,
+    /// The scan number, from the title or `SCANS` line
+    pub scan: Option<usize>,
+}
+
+impl IndexedMgf<BufReader<File>> {
+    /// Open and index the MGF file at the given path.
+    ///
+    /// # Errors
+    /// If the file could not be opened, if it is gzipped (which does not allow random access),
+    /// or if any line could not be read.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        let path = path.as_ref();
+        if check_extension(path, "gz") {
+            return Err(CustomError::error(
+                "Could not index mgf file",
+                "Gzipped files do not allow random access, decompress the file first or use the streaming reader",
+                Context::show(path.display()),
+            ));
+        }
+        let file = open_file(path).map_err(|err| {
+            CustomError::error(
+                "Could not open file",
+                format!("Additional info: {err}"),
+                Context::show(path.display()),
+            )
+        })?;
+        Self::new(BufReader::new(file))
+    }
+}
+
+impl<R: BufRead + Seek> IndexedMgf<R> {
+    /// Index the given reader, this reads through the whole file once.
+    ///
+    /// # Errors
+    /// If any line could not be read.
+    pub fn new(mut reader: R) -> Result<Self, CustomError> {
+        let mut index = Vec::new();
+        let mut offset = reader.stream_position().map_err(|err| {
+            CustomError::error(
+                "Could not index mgf file",
+                format!("Error while reading: {err}"),
+                Context::none(),
+            )
+        })?;
+        let mut line = String::new();
+        let mut line_index = 0;
+        let mut current: Option<(MgfIndexEntry, RawSpectrum)> = None;
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line).map_err(|err| {
+                CustomError::error(
+                    "Could not index mgf file",
+                    format!("Error while reading line: {err}"),
+                    Context::show(format!("Line number {}", line_index + 1)),
+                )
+            })?;
+            if read == 0 {
+                break;
+            }
+            match line.trim_end_matches(['\n', '\r']) {
+                "BEGIN IONS" => {
+                    current = Some((
+                        MgfIndexEntry {
+                            offset,
+                            line_index,
+                            title: String::new(),
+                            scan: None,
+                        },
+                        RawSpectrum::default(),
+                    ));
+                }
+                "END IONS" => {
+                    if let Some((mut entry, spectrum)) = current.take() {
+                        entry.title = spectrum.title;
+                        entry.scan = spectrum.raw_scan_number;
+                        index.push(entry);
+                    }
+                }
+                t => {
+                    if let Some((_, spectrum)) = current.as_mut() {
+                        if let Some(title) = t.strip_prefix("TITLE=") {
+                            parse_title(title, spectrum);
+                        } else if let Some(scan) = t.strip_prefix("SCANS=") {
+                            if spectrum.raw_scan_number.is_none() {
+                                spectrum.raw_scan_number = scan.parse().ok();
+                            }
+                        }
+                    }
+                }
+            }
+            offset += read as u64;
+            line_index += 1;
+        }
+        Ok(Self { reader, index })
+    }
+
+    /// Get the index with the location and identifiers for all spectra
+    pub fn index(&self) -> &[MgfIndexEntry] {
+        &self.index
+    }
+
+    /// The number of spectra in this file
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    /// Check if this file has no spectra
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// Read the spectrum at the given index (0 based), returns None if the index is out of range.
+    ///
+    /// # Errors
+    /// If the spectrum could not be read or parsed.
+    pub fn get_by_index(&mut self, index: usize) -> Option<Result<RawSpectrum, CustomError>> {
+        let entry = self.index.get(index)?.clone();
+        Some(self.read_entry(&entry))
+    }
+
+    /// Read the first spectrum with the given scan number, returns None if no spectrum has this scan number.
+    ///
+    /// # Errors
+    /// If the spectrum could not be read or parsed.
+    pub fn get_by_scan(&mut self, scan: usize) -> Option<Result<RawSpectrum, CustomError>> {
+        let entry = self
+            .index
+            .iter()
+            .find(|entry| entry.scan == Some(scan))?
+            .clone();
+        Some(self.read_entry(&entry))
+    }
+
+    /// Read the first spectrum with the given title, returns None if no spectrum has this title.
+    ///
+    /// # Errors
+    /// If the spectrum could not be read or parsed.
+    pub fn get_by_title(&mut self, title: &str) -> Option<Result<RawSpectrum, CustomError>> {
+        let entry = self
+            .index
+            .iter()
+            .find(|entry| entry.title == title)?
+            .clone();
+        Some(self.read_entry(&entry))
+    }
+
+    /// Read the spectrum at the given location
+    /// # Errors
+    /// If the spectrum could not be read or parsed.
+    fn read_entry(&mut self, entry: &MgfIndexEntry) -> Result<RawSpectrum, CustomError> {
+        self.reader
+            .seek(SeekFrom::Start(entry.offset))
+            .map_err(|err| {
+                CustomError::error(
+                    "Could not read mgf file",
+                    format!("Error while seeking: {err}"),
+                    Context::show(format!("Line number {}", entry.line_index + 1)),
+                )
+            })?;
+        MgfReader::with_line_offset(&mut self.reader, entry.line_index)
+            .next()
+            .unwrap_or_else(|| {
+                Err(CustomError::error(
+                    "Could not read mgf file",
+                    "The spectrum ended unexpectedly",
+                    Context::show(format!("Line number {}", entry.line_index + 1)),
+                ))
+            })
+    }
+}
+
+/// Write the given spectra as a MGF file. The title, precursor mass, charge, retention time, ion
+/// mobility, collisional cross section, scan number, sequence, and all peaks are written, so
+/// reading the file back results in the same spectra. The m/z values are written with the
+/// precision of the global [`Precision`](crate::Precision) policy.
+///
+/// ```rust
+/// # use rustyms::rawfile::mgf;
+/// let spectra = mgf::open("data/example.mgf").unwrap();
+/// let mut buffer = Vec::new();
+/// mgf::write(&mut buffer, &spectra).unwrap();
+/// let reread = mgf::open_raw(buffer.as_slice()).unwrap();
+/// assert_eq!(reread, spectra);
+/// ```
+/// # Errors
+/// If the writer could not be written to.
+pub fn write<'a>(
+    mut writer: impl Write,
+    spectra: impl IntoIterator<Item = &'a RawSpectrum>,
+) -> Result<(), CustomError> {
+    let precision = Precision::global();
+    for spectrum in spectra {
+        write_spectrum(&mut writer, spectrum, &precision).map_err(|error| {
+            CustomError::error("Could not write mgf file", error, Context::none())
+        })?;
+    }
+    writer
+        .flush()
+        .map_err(|error| CustomError::error("Could not write mgf file", error, Context::none()))
+}
+
+/// Write a single spectrum as MGF.
+/// # Errors
+/// If the writer could not be written to.
+fn write_spectrum(
+    writer: &mut impl Write,
+    spectrum: &RawSpectrum,
+    precision: &Precision,
+) -> std::io::Result<()> {
+    writeln!(writer, "BEGIN IONS")?;
+    if !spectrum.title.is_empty() {
+        writeln!(writer, "TITLE={}", spectrum.title)?;
+    }
+    if let Some(mass) = spectrum.mass {
+        match spectrum.intensity {
+            Some(intensity) => writeln!(writer, "PEPMASS={} {intensity}", mass.get::<dalton>())?,
+            None => writeln!(writer, "PEPMASS={}", mass.get::<dalton>())?,
+        }
+    }
+    if let Some(charge) = spectrum.charge {
+        writeln!(writer, "CHARGE={}+", charge.value)?;
+    }
+    if let Some(rt) = spectrum.rt {
+        writeln!(writer, "RTINSECONDS={}", rt.get::<s>())?;
+    }
+    if let Some(ion_mobility) = spectrum.ion_mobility {
+        writeln!(writer, "ION_MOBILITY={ion_mobility}")?;
+    }
+    if let Some(ccs) = spectrum.ccs {
+        writeln!(writer, "CCS={ccs}")?;
+    }
+    if let Some(scan) = spectrum.raw_scan_number {
+        writeln!(writer, "SCANS={scan}")?;
+    }
+    if spectrum.num_scans != 0 {
+        writeln!(writer, "NUM_SCANS={}", spectrum.num_scans)?;
+    }
+    if let Some(sequence) = &spectrum.sequence {
+        writeln!(writer, "SEQUENCE={sequence}")?;
+    }
+    for peak in spectrum.spectrum() {
+        writeln!(
+            writer,
+            "{} {}",
+            precision.format_mz(peak.mz.get::<mz>()),
+            peak.intensity
+        )?;
+    }
+    writeln!(writer, "END IONS")
 }
 
 /// # Errors
@@ -182,18 +539,21 @@ fn parse_charge(input: &str) -> Result<Charge, ()> {
     }
 }
 
+static MS_CONVERT_TITLE_REGEX: OnceLock<Regex> = OnceLock::new();
+static OTHER_TITLE_REGEX: OnceLock<Regex> = OnceLock::new();
+
 #[allow(clippy::missing_panics_doc)]
 fn parse_title(title: &str, spectrum: &mut RawSpectrum) {
     // basic structure: <name>.<scan>.<scan>.<experiment?>? File:"<name>", NativeID:"(<header>) +"
-    let ms_convert_format: Regex =
-        Regex::new(r#"(.+)\.(\d+)\.\d+\.\d* File:".*", NativeID:"(.+)""#).unwrap();
+    let ms_convert_format = MS_CONVERT_TITLE_REGEX
+        .get_or_init(|| Regex::new(r#"(.+)\.(\d+)\.\d+\.\d* File:".*", NativeID:"(.+)""#).unwrap());
     // other structure: <name>.ScanId;v=<num>;d1=<scan>.<scan>.<experiment?>_INDEX<index>
-    let other_format: Regex =
-        Regex::new(r"(.+)\.ScanId;v=\d+;d1=(\d+)\.\d+\.\d*_INDEX(\d+)").unwrap();
+    let other_format = OTHER_TITLE_REGEX
+        .get_or_init(|| Regex::new(r"(.+)\.ScanId;v=\d+;d1=(\d+)\.\d+\.\d*_INDEX(\d+)").unwrap());
 
     spectrum.title = title.to_string();
     if let Some(ms_convert) = ms_convert_format.captures(title) {
-        spectrum.raw_file = Some(ms_convert[1].to_string());
+        spectrum.raw_file = Some(ms_convert[1].into());
         spectrum.raw_scan_number = ms_convert[2].parse().ok(); // By definition will always work thanks to the regex
         for header in ms_convert[3].split(' ') {
             match header.split_once('=') {
@@ -207,7 +567,7 @@ fn parse_title(title: &str, spectrum: &mut RawSpectrum) {
             }
         }
     } else if let Some(other) = other_format.captures(title) {
-        spectrum.raw_file = Some(other[1].to_string());
+        spectrum.raw_file = Some(other[1].into());
         spectrum.raw_scan_number = other[2].parse().ok(); // By definition will always work thanks to the regex
         spectrum.raw_index = other[3].parse().ok(); // By definition will always work thanks to the regex
     }
@@ -227,6 +587,34 @@ mod tests {
         assert!(spectra[0][0].mz < spectra[0][1].mz);
     }
 
+    #[test]
+    fn stream_and_index() {
+        let data = "BEGIN IONS\nTITLE=first\nSCANS=10\nPEPMASS=500.0\n100.0 10.0\n200.0 20.0\nEND IONS\n\nBEGIN IONS\nTITLE=second\nSCANS=12\n150.0 15.0\nEND IONS\nBEGIN IONS\nTITLE=third\nPEPMASS=not a number\n150.0 15.0\nEND IONS\nBEGIN IONS\nTITLE=fourth\nSCANS=14\n300.0 30.0\nEND IONS\n";
+        let spectra = MgfReader::new(data.as_bytes()).collect::<Vec<_>>();
+        assert_eq!(spectra.len(), 4);
+        assert_eq!(spectra[0].as_ref().unwrap().title, "first");
+        assert_eq!(spectra[0].as_ref().unwrap().spectrum().len(), 2);
+        assert_eq!(spectra[1].as_ref().unwrap().raw_scan_number, Some(12));
+        assert!(spectra[2].is_err());
+        assert_eq!(spectra[3].as_ref().unwrap().title, "fourth");
+        assert!(open_raw(data.as_bytes()).is_err());
+
+        let mut indexed = IndexedMgf::new(std::io::Cursor::new(data)).unwrap();
+        assert_eq!(indexed.len(), 4);
+        assert_eq!(indexed.index()[1].scan, Some(12));
+        let second = indexed.get_by_scan(12).unwrap().unwrap();
+        assert_eq!(second.title, "second");
+        assert_eq!(second.spectrum().len(), 1);
+        assert_eq!(
+            indexed.get_by_title("first").unwrap().unwrap().mass,
+            spectra[0].as_ref().unwrap().mass
+        );
+        assert!(indexed.get_by_index(2).unwrap().is_err());
+        assert_eq!(indexed.get_by_index(3).unwrap().unwrap().title, "fourth");
+        assert!(indexed.get_by_scan(11).is_none());
+        assert!(indexed.get_by_index(4).is_none());
+    }
+
     #[test]
     fn test_titles() {
         assert_eq!(
@@ -455,7 +843,7 @@ mod tests {
         let mut spectrum = RawSpectrum::default();
         parse_title(title, &mut spectrum);
         (
-            spectrum.raw_file,
+            spectrum.raw_file.map(|p| p.to_string_lossy().to_string()),
             spectrum.raw_scan_number,
             spectrum.sample,
             spectrum.period,
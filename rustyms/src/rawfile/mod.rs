@@ -1,2 +1,7 @@
 //! Handling raw files
+#[cfg(feature = "identification")]
+pub mod library;
+mod metadata;
 pub mod mgf;
+
+pub use metadata::RunMetadata;
@@ -0,0 +1,236 @@
+//! Retention time prediction for peptidoforms
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    system::{time::s, Time},
+    AminoAcid, Linear, Peptidoform,
+};
+
+/// A predictor for the retention time of peptidoforms. The prediction is given in the units of the
+/// predictor (for example a hydrophobicity index), use a [`RetentionTimeCalibration`] to map
+/// these predictions onto the retention time of a specific chromatographic setup. This trait can
+/// be implemented to plug in external (machine learning) predictors.
+pub trait RetentionTimePredictor {
+    /// Predict the retention of the given peptidoform, in the units of this predictor. Returns
+    /// `None` if this predictor cannot predict a retention for this peptidoform.
+    fn predict(&self, peptidoform: &Peptidoform<Linear>) -> Option<f64>;
+}
+
+/// A simple hydrophobicity based retention time predictor, modelled after SSRCalc (Krokhin et
+/// al. 2004, <https://doi.org/10.1074/mcp.M300079-MCP200>). The hydrophobicity index is the sum
+/// of the retention coefficients of all amino acids, with an additional weight for the first
+/// three N terminal amino acids (0.42, 0.22, and 0.05) as these have a reduced interaction with
+/// the column when positively charged, and a length correction for short peptides (less than 10
+/// amino acids). Modifications are not taken into account.
+///
+/// This is meant as a baseline model, it has no cluster, pI, or helicity corrections.
+///
+/// ```rust
+/// # use rustyms::*;
+/// let predictor = HydrophobicityPredictor::default();
+/// let hydrophobic = Peptidoform::pro_forma("LLFWLLK", None).unwrap().into_linear().unwrap();
+/// let hydrophilic = Peptidoform::pro_forma("SGDNSKR", None).unwrap().into_linear().unwrap();
+/// assert!(predictor.predict(&hydrophobic).unwrap() > predictor.predict(&hydrophilic).unwrap());
+/// ```
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HydrophobicityPredictor {
+    /// The retention coefficient for every amino acid, indexed by the amino acid
+    coefficients: [f64; AminoAcid::TOTAL_NUMBER],
+}
+
+impl Default for HydrophobicityPredictor {
+    /// The retention coefficients for reversed phase chromatography with TFA as ion pairing
+    /// agent (Guo et al. 1986, <https://doi.org/10.1016/0021-9673(86)80102-9>). The ambiguous
+    /// amino acids get the average value, selenocysteine the value of cysteine, pyrrolysine the
+    /// value of lysine, and unknown amino acids a coefficient of zero.
+    fn default() -> Self {
+        let mut coefficients = [0.0; AminoAcid::TOTAL_NUMBER];
+        for (aa, value) in [
+            (AminoAcid::Tryptophan, 8.8),
+            (AminoAcid::Phenylalanine, 8.1),
+            (AminoAcid::Leucine, 8.1),
+            (AminoAcid::Isoleucine, 7.4),
+            (AminoAcid::Methionine, 5.5),
+            (AminoAcid::Valine, 5.0),
+            (AminoAcid::Tyrosine, 4.5),
+            (AminoAcid::Cysteine, 2.6),
+            (AminoAcid::Proline, 2.0),
+            (AminoAcid::Alanine, 2.0),
+            (AminoAcid::GlutamicAcid, 1.1),
+            (AminoAcid::Threonine, 0.6),
+            (AminoAcid::AsparticAcid, 0.2),
+            (AminoAcid::Glutamine, 0.0),
+            (AminoAcid::Serine, -0.2),
+            (AminoAcid::Glycine, -0.2),
+            (AminoAcid::Arginine, -0.6),
+            (AminoAcid::Asparagine, -0.6),
+            (AminoAcid::Histidine, -2.1),
+            (AminoAcid::Lysine, -2.1),
+            (AminoAcid::AmbiguousAsparagine, 0.5 * (-0.6 + 0.2)),
+            (AminoAcid::AmbiguousLeucine, 0.5 * (8.1 + 7.4)),
+            (AminoAcid::AmbiguousGlutamine, 0.5 * (0.0 + 1.1)),
+            (AminoAcid::Selenocysteine, 2.6),
+            (AminoAcid::Pyrrolysine, -2.1),
+            (AminoAcid::Unknown, 0.0),
+        ] {
+            coefficients[aa as usize] = value;
+        }
+        Self { coefficients }
+    }
+}
+
+impl HydrophobicityPredictor {
+    /// Set the retention coefficient for the given amino acid
+    #[must_use]
+    pub const fn coefficient(mut self, aminoacid: AminoAcid, coefficient: f64) -> Self {
+        self.coefficients[aminoacid as usize] = coefficient;
+        self
+    }
+
+    /// Get the retention coefficient for the given amino acid
+    pub const fn get_coefficient(&self, aminoacid: AminoAcid) -> f64 {
+        self.coefficients[aminoacid as usize]
+    }
+}
+
+impl RetentionTimePredictor for HydrophobicityPredictor {
+    fn predict(&self, peptidoform: &Peptidoform<Linear>) -> Option<f64> {
+        let sequence = peptidoform.sequence();
+        if sequence.is_empty() {
+            return None;
+        }
+        let coefficients = sequence
+            .iter()
+            .map(|seq| self.get_coefficient(seq.aminoacid.aminoacid()))
+            .collect::<Vec<_>>();
+        let n_terminal: f64 = coefficients
+            .iter()
+            .zip([0.42, 0.22, 0.05])
+            .map(|(c, w)| c * w)
+            .sum();
+        let length = coefficients.len();
+        let length_correction = if length < 10 {
+            0.027_f64.mul_add(-((10 - length) as f64), 1.0)
+        } else {
+            1.0
+        };
+        Some(length_correction * (coefficients.iter().sum::<f64>() + n_terminal))
+    }
+}
+
+/// A linear calibration from the units of a [`RetentionTimePredictor`] to the retention time of
+/// a specific chromatographic setup.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetentionTimeCalibration {
+    /// The retention time change per unit of the predictor
+    pub slope: Time,
+    /// The retention time at a predicted value of zero
+    pub intercept: Time,
+}
+
+impl RetentionTimeCalibration {
+    /// Fit a calibration with linear least squares on the given pairs of predicted values and
+    /// observed retention times. Returns `None` if there are less than two points or all
+    /// predicted values are identical.
+    pub fn fit(points: impl IntoIterator<Item = (f64, Time)>) -> Option<Self> {
+        let points: Vec<(f64, f64)> = points
+            .into_iter()
+            .map(|(x, y)| (x, y.get::<s>()))
+            .filter(|(x, y)| x.is_finite() && y.is_finite())
+            .collect();
+        if points.len() < 2 {
+            return None;
+        }
+        let n = points.len() as f64;
+        let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+        let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+        let (covariance, variance) = points.iter().fold((0.0, 0.0), |(c, v), (x, y)| {
+            (
+                (x - mean_x).mul_add(y - mean_y, c),
+                (x - mean_x).mul_add(x - mean_x, v),
+            )
+        });
+        if variance == 0.0 {
+            return None;
+        }
+        let slope = covariance / variance;
+        Some(Self {
+            slope: Time::new::<s>(slope),
+            intercept: Time::new::<s>(slope.mul_add(-mean_x, mean_y)),
+        })
+    }
+
+    /// Fit a calibration on the given peptidoforms with their observed retention times, see
+    /// [`Self::fit`]. Peptidoforms that cannot be predicted are ignored.
+    pub fn fit_peptidoforms<'a>(
+        predictor: &(impl RetentionTimePredictor + ?Sized),
+        peptidoforms: impl IntoIterator<Item = (&'a Peptidoform<Linear>, Time)>,
+    ) -> Option<Self> {
+        Self::fit(
+            peptidoforms
+                .into_iter()
+                .filter_map(|(p, rt)| predictor.predict(p).map(|value| (value, rt))),
+        )
+    }
+
+    /// Get the retention time for the given predicted value
+    pub fn retention_time(&self, predicted: f64) -> Time {
+        self.slope * predicted + self.intercept
+    }
+
+    /// Predict the retention time for the given peptidoform
+    pub fn predict(
+        &self,
+        predictor: &(impl RetentionTimePredictor + ?Sized),
+        peptidoform: &Peptidoform<Linear>,
+    ) -> Option<Time> {
+        predictor
+            .predict(peptidoform)
+            .map(|value| self.retention_time(value))
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::system::time::min;
+
+    #[test]
+    fn calibrate() {
+        let predictor = HydrophobicityPredictor::default();
+        let peptides = ["SGDNSKR", "AGEHLK", "ELVISLIVESK", "FLWWLLK", "TPEVDDEALEK"].map(|p| {
+            Peptidoform::pro_forma(p, None)
+                .unwrap()
+                .into_linear()
+                .unwrap()
+        });
+        let predicted = peptides
+            .iter()
+            .map(|p| predictor.predict(p).unwrap())
+            .collect::<Vec<_>>();
+        // Simulate a gradient with retention time = 2 min * HI + 10 min
+        let observed = predicted
+            .iter()
+            .map(|p| Time::new::<min>(2.0f64.mul_add(*p, 10.0)))
+            .collect::<Vec<_>>();
+        let calibration = RetentionTimeCalibration::fit_peptidoforms(
+            &predictor,
+            peptides.iter().zip(observed.iter().copied()),
+        )
+        .unwrap();
+        assert!((calibration.slope.get::<min>() - 2.0).abs() < 1e-10);
+        assert!((calibration.intercept.get::<min>() - 10.0).abs() < 1e-10);
+        for (peptide, rt) in peptides.iter().zip(&observed) {
+            let prediction = calibration.predict(&predictor, peptide).unwrap();
+            assert!((prediction - *rt).abs().get::<s>() < 1e-6);
+        }
+        assert!(RetentionTimeCalibration::fit([(1.0, Time::new::<min>(5.0))]).is_none());
+        assert!(RetentionTimeCalibration::fit([
+            (1.0, Time::new::<min>(5.0)),
+            (1.0, Time::new::<min>(6.0))
+        ])
+        .is_none());
+    }
+}
@@ -0,0 +1,187 @@
+//! Write rows one by one, so results can be written while they are being calculated
+
+use std::{collections::BTreeMap, io::Write};
+
+use itertools::Itertools;
+
+use crate::csv::quote;
+
+/// A destination for rows that are produced one by one, so results can be written while they
+/// are being calculated instead of collecting all results in memory first.
+pub trait RowSink<T> {
+    /// The error that can occur while writing
+    type Error;
+
+    /// Write a single row
+    /// # Errors
+    /// If the row could not be written.
+    fn write_row(&mut self, row: T) -> Result<(), Self::Error>;
+
+    /// Write any rows that are still buffered
+    /// # Errors
+    /// If the buffered rows could not be written.
+    fn finish(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Write a CSV file row by row, see [`RowSink`]. In contrast to [`write_csv`](crate::csv::write_csv) the columns have
+/// to be known beforehand, the header is written on creation. Any column that is missing in a
+/// row is left empty, and any comma (,) containing values and headers are wrapped in apostrophes
+/// (").
+///
+/// ```rust
+/// # use rustyms::row_sink::*;
+/// let mut buffer = Vec::new();
+/// let mut writer = CsvWriter::new(&mut buffer, ["name", "value"]).unwrap();
+/// writer.write_row([("value", "1".to_string()), ("name", "a,b".to_string())]).unwrap();
+/// writer.write_row([("name", "c".to_string())]).unwrap();
+/// writer.finish().unwrap();
+/// assert_eq!(String::from_utf8(buffer).unwrap(), "name,value\n\"a,b\",1\nc,\n");
+/// ```
+#[derive(Debug)]
+pub struct CsvWriter<W: Write> {
+    writer: W,
+    columns: Vec<String>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    /// Create a new writer with the given columns and write the header
+    /// # Errors
+    /// If the header could not be written.
+    pub fn new(
+        mut writer: W,
+        columns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Result<Self, std::io::Error> {
+        let columns = columns.into_iter().map(Into::into).collect_vec();
+        writeln!(writer, "{}", columns.iter().cloned().map(quote).join(","))?;
+        Ok(Self { writer, columns })
+    }
+
+    /// Get the columns of this file
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Get the underlying writer back
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Write a single row, given as (column, value) pairs
+    /// # Errors
+    /// If the row contains a column that is not defined for this file, or if the row could not be
+    /// written.
+    pub fn write_row(
+        &mut self,
+        row: impl IntoIterator<Item = (impl AsRef<str>, String)>,
+    ) -> Result<(), std::io::Error> {
+        let mut line = vec![String::new(); self.columns.len()];
+        for (column, value) in row {
+            let index = self
+                .columns
+                .iter()
+                .position(|c| c == column.as_ref())
+                .ok_or_else(|| {
+                    std::io::Error::new(
+                        std::io::ErrorKind::InvalidInput,
+                        format!("Unknown CSV column '{}'", column.as_ref()),
+                    )
+                })?;
+            line[index] = quote(value);
+        }
+        writeln!(self.writer, "{}", line.join(","))
+    }
+
+    /// Flush the underlying writer
+    /// # Errors
+    /// If the writer could not be flushed.
+    pub fn finish(&mut self) -> Result<(), std::io::Error> {
+        self.writer.flush()
+    }
+}
+
+impl<W: Write, R, C> RowSink<R> for CsvWriter<W>
+where
+    R: IntoIterator<Item = (C, String)>,
+    C: AsRef<str>,
+{
+    type Error = std::io::Error;
+
+    fn write_row(&mut self, row: R) -> Result<(), Self::Error> {
+        Self::write_row(self, row)
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        Self::finish(self)
+    }
+}
+
+/// Restore the order of rows that are produced out of order, for example from a parallel
+/// iterator. The rows are given with their index and are passed on to the inner sink as soon as
+/// all rows with a lower index are written, so only rows that arrive ahead of a missing row are
+/// kept in memory. Any rows that are still buffered when finishing (because some index was never
+/// given) are written in order.
+///
+/// ```rust
+/// # use rustyms::row_sink::*;
+/// let mut buffer = Vec::new();
+/// let mut writer = OrderedSink::new(CsvWriter::new(&mut buffer, ["n"]).unwrap());
+/// for n in [2, 0, 1, 4] {
+///     writer.write_row((n, [("n", n.to_string())])).unwrap();
+/// }
+/// writer.finish().unwrap();
+/// drop(writer);
+/// assert_eq!(String::from_utf8(buffer).unwrap(), "n\n0\n1\n2\n4\n");
+/// ```
+#[derive(Debug)]
+pub struct OrderedSink<S, T> {
+    inner: S,
+    next: usize,
+    pending: BTreeMap<usize, T>,
+}
+
+impl<S, T> OrderedSink<S, T> {
+    /// Create a new ordered sink, expecting the first row to have index 0
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            next: 0,
+            pending: BTreeMap::new(),
+        }
+    }
+
+    /// The number of rows that are waiting for a row with a lower index
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Get the inner sink back, any pending rows are discarded
+    pub fn into_inner(self) -> S {
+        self.inner
+    }
+}
+
+impl<S: RowSink<T>, T> RowSink<(usize, T)> for OrderedSink<S, T> {
+    type Error = S::Error;
+
+    fn write_row(&mut self, (index, row): (usize, T)) -> Result<(), Self::Error> {
+        if index != self.next {
+            self.pending.insert(index, row);
+            return Ok(());
+        }
+        self.inner.write_row(row)?;
+        self.next += 1;
+        while let Some(row) = self.pending.remove(&self.next) {
+            self.inner.write_row(row)?;
+            self.next += 1;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<(), Self::Error> {
+        while let Some((index, row)) = self.pending.pop_first() {
+            self.inner.write_row(row)?;
+            self.next = index + 1;
+        }
+        self.inner.finish()
+    }
+}
@@ -1,6 +1,6 @@
 #![warn(dead_code)]
 
-use std::{collections::HashSet, fmt::Write, marker::PhantomData, num::NonZeroU32};
+use std::{collections::BTreeSet, fmt::Write, marker::PhantomData, num::NonZeroU32};
 
 use crate::{
     error::{Context, CustomError},
@@ -146,7 +146,7 @@ impl<T> SequenceElement<T> {
         allow_ms_cleavable: bool,
         sequence_index: SequencePosition,
         peptidoform_index: usize,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
         let (formula, seen) = self
             .modifications
             .iter()
@@ -164,7 +164,7 @@ impl<T> SequenceElement<T> {
                     ))
                 }
             })
-            .fold((Multi::default(), HashSet::new()), |(am, av), (m, v)| {
+            .fold((Multi::default(), BTreeSet::new()), |(am, av), (m, v)| {
                 (am * m, av.union(&v).cloned().collect())
             });
         (
@@ -186,7 +186,7 @@ impl<T> SequenceElement<T> {
         allow_ms_cleavable: bool,
         sequence_index: SequencePosition,
         peptidoform_index: usize,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
         let (formula, seen) = self
             .modifications
             .iter()
@@ -201,7 +201,7 @@ impl<T> SequenceElement<T> {
                             modification
                                 .formula_inner(sequence_index, peptidoform_index)
                                 .into(),
-                            HashSet::default(),
+                            BTreeSet::default(),
                         )
                     })
                 } else {
@@ -215,7 +215,7 @@ impl<T> SequenceElement<T> {
                     ))
                 }
             })
-            .fold((Multi::default(), HashSet::new()), |(am, av), (m, v)| {
+            .fold((Multi::default(), BTreeSet::new()), |(am, av), (m, v)| {
                 (am * m, av.union(&v).cloned().collect())
             });
         (
@@ -235,7 +235,7 @@ impl<T> SequenceElement<T> {
         allow_ms_cleavable: bool,
         sequence_index: SequencePosition,
         peptidoform_index: usize,
-    ) -> (Multi<MolecularFormula>, HashSet<CrossLinkName>) {
+    ) -> (Multi<MolecularFormula>, BTreeSet<CrossLinkName>) {
         let (formula, seen) = self
             .modifications
             .iter()
@@ -249,7 +249,7 @@ impl<T> SequenceElement<T> {
                     peptidoform_index,
                 )
             })
-            .fold((Multi::default(), HashSet::new()), |(am, av), (m, v)| {
+            .fold((Multi::default(), BTreeSet::new()), |(am, av), (m, v)| {
                 (am * m, av.union(&v).cloned().collect())
             });
         (
@@ -0,0 +1,262 @@
+//! Positional amino acid frequencies and sequence logos for sets of aligned peptides
+
+use serde::{Deserialize, Serialize};
+
+use crate::{AminoAcid, Peptidoform};
+
+/// The number of canonical amino acids, these are the alphabet for the information content
+const STANDARD_AMINO_ACIDS: usize = AminoAcid::CANONICAL_AMINO_ACIDS.len();
+
+/// The number of times each amino acid occurs at each position for a set of aligned peptides,
+/// for example immunopeptides of the same length or the windows around cleavage sites. This can
+/// be used to find the preferences at each position, and can be visualised as a sequence logo
+/// with [`Self::to_svg`].
+///
+/// ```rust
+/// # use rustyms::*;
+/// let peptides = ["SIINFEKL", "SIYNFEKL", "AIINFEKV"]
+///     .map(|p| Peptidoform::pro_forma(p, None).unwrap().into_linear().unwrap());
+/// let matrix = PositionFrequencyMatrix::from_peptidoforms(&peptides);
+/// assert_eq!(matrix.len(), 8);
+/// assert_eq!(matrix.count(0, AminoAcid::Serine), 2);
+/// assert!((matrix.frequency(1, AminoAcid::Isoleucine) - 1.0).abs() < f64::EPSILON);
+/// assert!(matrix.information_content(1, false) > matrix.information_content(0, false));
+/// assert!(matrix.to_svg(20.0, 100.0).starts_with("<svg"));
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionFrequencyMatrix {
+    /// The counts for every position, indexed by the amino acid
+    counts: Vec<[usize; AminoAcid::TOTAL_NUMBER]>,
+    /// The total number of sequences
+    sequences: usize,
+}
+
+impl PositionFrequencyMatrix {
+    /// Create a matrix from the given aligned sequences. All sequences are aligned on their
+    /// first residue, if the sequences have different lengths the positions past the end of
+    /// the shorter sequences are treated as gaps.
+    pub fn from_sequences<S: IntoIterator<Item = AminoAcid>>(
+        sequences: impl IntoIterator<Item = S>,
+    ) -> Self {
+        Self::from_windows(
+            sequences
+                .into_iter()
+                .map(|sequence| sequence.into_iter().map(Some)),
+        )
+    }
+
+    /// Create a matrix from the given aligned windows, where `None` is a gap at that position.
+    /// This can be used for windows that extend past the end of a sequence, for example the
+    /// residues around a cleavage site close to a protein terminus.
+    pub fn from_windows<S: IntoIterator<Item = Option<AminoAcid>>>(
+        windows: impl IntoIterator<Item = S>,
+    ) -> Self {
+        let mut matrix = Self::default();
+        for window in windows {
+            matrix.add_window(window);
+        }
+        matrix
+    }
+
+    /// Add a single aligned window to this matrix, where `None` is a gap at that position
+    pub fn add_window(&mut self, window: impl IntoIterator<Item = Option<AminoAcid>>) {
+        for (position, aminoacid) in window.into_iter().enumerate() {
+            if position >= self.counts.len() {
+                self.counts
+                    .resize(position + 1, [0; AminoAcid::TOTAL_NUMBER]);
+            }
+            if let Some(aminoacid) = aminoacid {
+                self.counts[position][aminoacid as usize] += 1;
+            }
+        }
+        self.sequences += 1;
+    }
+
+    /// Create a matrix from the given aligned peptidoforms, see [`Self::from_sequences`].
+    /// Modifications are ignored.
+    pub fn from_peptidoforms<'a, Complexity: 'a>(
+        peptidoforms: impl IntoIterator<Item = &'a Peptidoform<Complexity>>,
+    ) -> Self {
+        Self::from_sequences(peptidoforms.into_iter().map(|peptidoform| {
+            peptidoform
+                .sequence()
+                .iter()
+                .map(|element| element.aminoacid.aminoacid())
+        }))
+    }
+
+    /// The number of positions
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Check if there are no positions
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// The number of sequences used to build this matrix
+    pub const fn sequences(&self) -> usize {
+        self.sequences
+    }
+
+    /// The number of times the given amino acid occurs at the given position (0 based), 0 if the
+    /// position is outside of the matrix
+    pub fn count(&self, position: usize, aminoacid: AminoAcid) -> usize {
+        self.counts
+            .get(position)
+            .map_or(0, |counts| counts[aminoacid as usize])
+    }
+
+    /// The number of sequences that have a residue at the given position
+    pub fn occupancy(&self, position: usize) -> usize {
+        self.counts
+            .get(position)
+            .map_or(0, |counts| counts.iter().sum())
+    }
+
+    /// The frequency of the given amino acid at the given position, relative to the number of
+    /// sequences with a residue at this position. This is 0 if no sequence has a residue here.
+    pub fn frequency(&self, position: usize, aminoacid: AminoAcid) -> f64 {
+        let occupancy = self.occupancy(position);
+        if occupancy == 0 {
+            0.0
+        } else {
+            self.count(position, aminoacid) as f64 / occupancy as f64
+        }
+    }
+
+    /// The information content in bits at the given position, defined as `log2(20) - (H + e)`
+    /// with `H` the Shannon entropy of the amino acid frequencies and `e` the optional small
+    /// sample correction `19 / (2 ln(2) n)` with `n` the number of sequences with a residue at
+    /// this position (Schneider et al. 1986, <https://doi.org/10.1016/0022-2836(86)90165-8>).
+    /// The correction is only meaningful for large sets of sequences, for less than 4 sequences
+    /// it is larger than the maximal information content. The result is never negative.
+    pub fn information_content(&self, position: usize, small_sample_correction: bool) -> f64 {
+        let occupancy = self.occupancy(position);
+        if occupancy == 0 {
+            return 0.0;
+        }
+        let entropy: f64 = self.counts[position]
+            .iter()
+            .filter(|c| **c > 0)
+            .map(|c| {
+                let frequency = *c as f64 / occupancy as f64;
+                -frequency * frequency.log2()
+            })
+            .sum();
+        let correction = if small_sample_correction {
+            (STANDARD_AMINO_ACIDS - 1) as f64 / (2.0 * std::f64::consts::LN_2 * occupancy as f64)
+        } else {
+            0.0
+        };
+        ((STANDARD_AMINO_ACIDS as f64).log2() - entropy - correction).max(0.0)
+    }
+
+    /// Render this matrix as a sequence logo in SVG. Every position is a stack of letters with
+    /// the total height of the stack proportional to the information content (without small
+    /// sample correction, see [`Self::information_content`]) and the height of
+    /// every letter proportional to its frequency, the most frequent letter is on top. The
+    /// amino acids are coloured by chemistry: polar (green), amide (purple), basic (blue),
+    /// acidic (red), and hydrophobic (black).
+    pub fn to_svg(&self, position_width: f64, height: f64) -> String {
+        let max_bits = (STANDARD_AMINO_ACIDS as f64).log2();
+        // The height of capital letters in a monospace font as fraction of the font size
+        let cap_height = 0.72;
+        let char_width = 0.6;
+        let letters: String = (0..self.len())
+            .flat_map(|position| {
+                let stack_height = self.information_content(position, false) / max_bits * height;
+                let mut letters = AminoAcid::ALL_AMINO_ACIDS
+                    .iter()
+                    .map(|aa| (*aa, self.frequency(position, *aa)))
+                    .filter(|(_, f)| *f > 0.0)
+                    .collect::<Vec<_>>();
+                letters.sort_by(|a, b| a.1.total_cmp(&b.1));
+                let mut y = height;
+                letters
+                    .into_iter()
+                    .filter_map(|(aa, frequency)| {
+                        let letter_height = frequency * stack_height;
+                        y -= letter_height;
+                        (letter_height > 0.0).then(|| {
+                            format!(
+                                "<text transform=\"translate({:.2},{:.2}) scale({:.4},{:.4})\" fill=\"{}\">{}</text>",
+                                position as f64 * position_width,
+                                y + letter_height,
+                                position_width / char_width / 100.0,
+                                letter_height / cap_height / 100.0,
+                                logo_colour(aa),
+                                aa.char()
+                            )
+                        })
+                    })
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.2}\" height=\"{height:.2}\" viewBox=\"0 0 {:.2} {height:.2}\" font-family=\"monospace\" font-weight=\"bold\" font-size=\"100\">{letters}</svg>",
+            self.len() as f64 * position_width,
+            self.len() as f64 * position_width,
+        )
+    }
+}
+
+/// The colour for an amino acid in a sequence logo
+const fn logo_colour(aminoacid: AminoAcid) -> &'static str {
+    match aminoacid {
+        AminoAcid::Glycine
+        | AminoAcid::Serine
+        | AminoAcid::Threonine
+        | AminoAcid::Tyrosine
+        | AminoAcid::Cysteine
+        | AminoAcid::Selenocysteine => "#109648",
+        AminoAcid::Glutamine | AminoAcid::Asparagine => "#5E239D",
+        AminoAcid::Lysine | AminoAcid::Arginine | AminoAcid::Histidine | AminoAcid::Pyrrolysine => {
+            "#255C99"
+        }
+        AminoAcid::AsparticAcid | AminoAcid::GlutamicAcid => "#D62839",
+        _ => "#221E22",
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frequencies() {
+        let matrix = PositionFrequencyMatrix::from_sequences([
+            vec![AminoAcid::Alanine, AminoAcid::Lysine, AminoAcid::Leucine],
+            vec![AminoAcid::Alanine, AminoAcid::Arginine],
+            vec![AminoAcid::Glycine, AminoAcid::Lysine, AminoAcid::Leucine],
+            vec![AminoAcid::Alanine, AminoAcid::Lysine, AminoAcid::Valine],
+        ]);
+        assert_eq!(matrix.len(), 3);
+        assert_eq!(matrix.sequences(), 4);
+        assert_eq!(matrix.occupancy(2), 3);
+        assert_eq!(matrix.count(1, AminoAcid::Lysine), 3);
+        assert_eq!(matrix.count(5, AminoAcid::Lysine), 0);
+        assert!((matrix.frequency(0, AminoAcid::Alanine) - 0.75).abs() < f64::EPSILON);
+        assert!((matrix.frequency(2, AminoAcid::Leucine) - 2.0 / 3.0).abs() < f64::EPSILON);
+        assert!(matrix.frequency(3, AminoAcid::Leucine).abs() < f64::EPSILON);
+        assert!(matrix.information_content(3, false).abs() < f64::EPSILON);
+        assert!(matrix.information_content(2, true).abs() < f64::EPSILON);
+        assert!(matrix.information_content(0, false) > 0.0);
+
+        // A fully conserved position with many sequences approaches the maximal information
+        let conserved =
+            PositionFrequencyMatrix::from_sequences(vec![[AminoAcid::Tryptophan]; 1000]);
+        let max = 20.0_f64.log2();
+        assert!((conserved.information_content(0, false) - max).abs() < f64::EPSILON);
+        assert!(conserved.information_content(0, true) < max);
+        assert!(conserved.information_content(0, true) > max - 0.02);
+
+        let svg = matrix.to_svg(10.0, 50.0);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<text").count(), 6);
+        assert!(svg.contains(">K</text>"));
+    }
+}
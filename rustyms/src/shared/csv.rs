@@ -3,19 +3,17 @@
 use std::{
     collections::{BTreeMap, HashMap},
     fmt::Debug,
-    fs::File,
     io::{BufRead, BufReader, Write},
     ops::Range,
     str::FromStr,
 };
 
-use flate2::bufread::GzDecoder;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 
 use crate::{
     error::{Context, CustomError},
-    helper_functions::check_extension,
+    helper_functions::open_reader,
 };
 
 /// A single line in a CSV file
@@ -28,6 +26,53 @@ pub struct CsvLine {
 
 #[allow(dead_code)]
 impl CsvLine {
+    /// Separate the given line into the columns of the given header
+    /// # Errors
+    /// If the line is empty or does not have the same number of columns as the header.
+    pub(crate) fn new(
+        line_index: usize,
+        line: String,
+        header: &[String],
+        separator: u8,
+    ) -> Result<Self, CustomError> {
+        Self::new_with_trailing_list(line_index, line, header, separator, false)
+    }
+
+    /// Separate the given line into the columns of the given header. If `trailing_list` is set
+    /// the last column spans all remaining fields, for formats that separate the items of a list
+    /// in the last column with the same separator as the columns.
+    /// # Errors
+    /// If the line is empty or does not have the same number of columns as the header.
+    pub(crate) fn new_with_trailing_list(
+        line_index: usize,
+        line: String,
+        header: &[String],
+        separator: u8,
+        trailing_list: bool,
+    ) -> Result<Self, CustomError> {
+        csv_separate(&line, separator).and_then(|mut row| {
+            if trailing_list && row.len() > header.len() {
+                let end = row.last().map_or(line.len(), |r| r.end);
+                row.truncate(header.len());
+                if let Some(last) = row.last_mut() {
+                    last.end = end;
+                }
+            }
+            if header.len() == row.len() {
+                Ok(Self {
+                    line_index,
+                    line,
+                    fields: header.iter().cloned().zip(row).collect(),
+                })
+            } else {
+                Err(CustomError::error(
+                    "Incorrect number of columns",
+                    format!("It does not have the correct number of columns. {} columns were expected but {} were found.", header.len(), row.len()),
+                    Context::full_line(line_index, line),
+                ))
+            }
+        })
+    }
     /// Get the line index (0 based)
     pub const fn line_index(&self) -> usize {
         self.line_index
@@ -156,35 +201,29 @@ pub fn parse_csv(
     separator: u8,
     provided_header: Option<Vec<String>>,
 ) -> Result<Box<dyn Iterator<Item = Result<CsvLine, CustomError>>>, CustomError> {
-    let file = File::open(path.as_ref()).map_err(|e| {
-        CustomError::error(
-            "Could not open file",
-            e,
-            crate::error::Context::Show {
-                line: path.as_ref().to_string_lossy().to_string(),
-            },
-        )
-    })?;
-    if check_extension(path, "gz") {
-        Ok(Box::new(parse_csv_raw(
-            GzDecoder::new(BufReader::new(file)),
-            separator,
-            provided_header,
-        )?))
-    } else {
-        Ok(Box::new(parse_csv_raw(file, separator, provided_header)?))
-    }
+    Ok(Box::new(parse_csv_raw(
+        open_reader(path)?,
+        separator,
+        provided_header,
+    )?))
 }
 
 /// Parse a CSV file from a raw `BufReader`
 /// # Errors
 /// If no header is provided and the first line could not be read as a header line.
-/// Or if the 'sep=C' uses a character that is more than 1 byte wide in utf8.
+/// Or if the separator (or the 'sep=C' line) is not an ASCII character.
 pub fn parse_csv_raw<T: std::io::Read>(
     reader: T,
     mut separator: u8,
     provided_header: Option<Vec<String>>,
 ) -> Result<CsvLineIter<T>, CustomError> {
+    if !separator.is_ascii() {
+        return Err(CustomError::error(
+            "Unicode value separators not supported",
+            "The separator has to be an ASCII character, any other byte could be part of a multi byte character",
+            Context::None,
+        ));
+    }
     let reader = BufReader::new(reader);
     let mut lines = reader.lines().enumerate().peekable();
     let mut skip = false;
@@ -214,16 +253,15 @@ pub fn parse_csv_raw<T: std::io::Read>(
         })?;
         let header_line = column_headers
             .map_err(|err| CustomError::error("Could not read header line", err, Context::None))?;
-        csv_separate(&header_line, separator)?
-            .into_iter()
-            .map(|r| header_line[r].to_lowercase())
-            .collect()
+        csv_header(&header_line, separator)?
     };
 
     Ok(CsvLineIter {
         lines,
         header: column_headers,
         separator,
+        skip: |_| false,
+        trailing_list: false,
     })
 }
 
@@ -232,36 +270,68 @@ pub struct CsvLineIter<T: std::io::Read> {
     lines: std::iter::Peekable<std::iter::Enumerate<std::io::Lines<BufReader<T>>>>,
     header: Vec<String>,
     separator: u8,
+    skip: fn(&str) -> bool,
+    trailing_list: bool,
+}
+
+impl<T: std::io::Read> CsvLineIter<T> {
+    /// Skip all lines for which the given function returns true, for formats that contain lines
+    /// with metadata in between the rows
+    #[must_use]
+    pub(crate) fn skip_lines(self, skip: fn(&str) -> bool) -> Self {
+        Self { skip, ..self }
+    }
+
+    /// Let the last column span all remaining fields, see [`CsvLine::new_with_trailing_list`]
+    #[must_use]
+    pub(crate) fn trailing_list(self, trailing_list: bool) -> Self {
+        Self {
+            trailing_list,
+            ..self
+        }
+    }
 }
 
 impl<T: std::io::Read> Iterator for CsvLineIter<T> {
     type Item = Result<CsvLine, CustomError>;
     fn next(&mut self) -> Option<Self::Item> {
+        while self
+            .lines
+            .peek()
+            .and_then(|(_, l)| l.as_ref().ok())
+            .is_some_and(|l| (self.skip)(l))
+        {
+            let _ = self.lines.next();
+        }
         self.lines.next().map(|(line_index, line)| {
-            let line = line.map_err(|err|CustomError::error(
+            let line = line.map_err(|err| {
+                CustomError::error(
                     "Could not read line",
                     err,
                     Context::full_line(line_index, "(failed)"),
-                ))?;
-            csv_separate(&line, self.separator).and_then(|row| {
-                if self.header.len() == row.len() {
-                    Ok(CsvLine {
-                        line_index,
-                        line,
-                        fields: self.header.iter().cloned().zip(row).collect(),
-                    })
-                } else {
-                    Err(CustomError::error(
-                        "Incorrect number of columns",
-                        format!("It does not have the correct number of columns. {} columns were expected but {} were found.", self.header.len(), row.len()),
-                        Context::full_line(line_index, line),
-                    ))
-                }
-            })
+                )
+            })?;
+            CsvLine::new_with_trailing_list(
+                line_index,
+                line,
+                &self.header,
+                self.separator,
+                self.trailing_list,
+            )
         })
     }
 }
 
+/// Separate the header line into the (lowercase) column names
+/// # Errors
+/// If the line is empty.
+pub(crate) fn csv_header(line: &str, separator: u8) -> Result<Vec<String>, CustomError> {
+    Ok(csv_separate(line, separator)?
+        .into_iter()
+        .map(|r| line[r].to_lowercase())
+        .collect())
+}
+
 /// # Errors
 /// If the line is empty.
 pub(crate) fn csv_separate(line: &str, separator: u8) -> Result<Vec<Range<usize>>, CustomError> {
@@ -392,18 +462,12 @@ pub fn write_csv(
         .into_iter()
         .map(|row| {
             let mut new_row = vec![String::new(); order.len()];
-            for (column, mut value) in row {
-                if value.contains(',') {
-                    value = format!("\"{value}\"");
-                }
+            for (column, value) in row {
+                let value = quote(value);
                 if let Some(index) = order.iter().position(|i| *i == column) {
                     new_row[index] = value;
                 } else {
-                    if column.contains(',') {
-                        order.push(format!("\"{column}\""));
-                    } else {
-                        order.push(column.to_string());
-                    }
+                    order.push(quote(column));
                     new_row.push(value);
                 }
             }
@@ -423,3 +487,12 @@ pub fn write_csv(
     }
     Ok(())
 }
+
+/// Wrap any comma (,) containing value in apostrophes (")
+pub(crate) fn quote(value: String) -> String {
+    if value.contains(',') {
+        format!("\"{value}\"")
+    } else {
+        value
+    }
+}
@@ -3,7 +3,10 @@ use crate::{
     helper_functions::{explain_number_error, RangeExtension},
     Element, MolecularFormula, COMMON_ELEMENT_PARSE_LIST, ELEMENT_PARSE_LIST,
 };
-use std::{num::NonZeroU16, ops::RangeBounds};
+use std::{
+    num::NonZeroU16,
+    ops::{Bound, RangeBounds},
+};
 
 impl MolecularFormula {
     /// Parse ProForma formulas: `[13C2][12C-2]H2N`.
@@ -31,11 +34,12 @@ impl MolecularFormula {
     /// Allows electrons to be used to define the charge of the formula
     /// ## Allow empty
     /// Allows the string `(empty)` to be used to denote an empty formula
+    /// ## Range
+    /// The range is given in bytes, an empty range results in an empty formula.
     /// # Errors
     /// If the formula is not valid according to the above specification, with some help on what is going wrong.
-    /// # Panics
-    /// It can panic if the string contains not UTF8 symbols.
-    #[allow(dead_code)]
+    /// Also if the range does not lie on character boundaries within the given string.
+    #[allow(dead_code, clippy::missing_panics_doc)] // The unwraps only happen when an element is set
     pub fn from_pro_forma(
         value: &str,
         range: impl RangeBounds<usize>,
@@ -43,8 +47,24 @@ impl MolecularFormula {
         allow_empty: bool,
         allow_uncommon_elements: bool,
     ) -> Result<Self, CustomError> {
-        let (mut index, end) = range.bounds(value.len().saturating_sub(1));
-        if allow_empty && value[index..=end].eq_ignore_ascii_case("(empty)") {
+        let mut index = range.start_index();
+        let end = match range.end_bound() {
+            Bound::Unbounded => value.len(),
+            Bound::Excluded(n) => *n,
+            Bound::Included(n) => n + 1,
+        };
+        let Some(text) = value.get(index..end.max(index)) else {
+            return Err(CustomError::error(
+                "Invalid ProForma molecular formula",
+                "The formula is not located at a valid position in the text",
+                Context::show(value),
+            ));
+        };
+        if text.is_empty() {
+            return Ok(Self::default());
+        }
+        let end = end - 1; // Inclusive end from here on
+        if allow_empty && text.eq_ignore_ascii_case("(empty)") {
             return Ok(Self::default());
         }
         let mut element = None;
@@ -159,7 +179,7 @@ impl MolecularFormula {
                             .collect::<Vec<_>>(),
                     )
                     .map_or_else(
-                        |e| panic!("Non UTF8 in ProForma molecular formula, error: {e}"),
+                        |_| unreachable!("Only ASCII digits and dashes are taken"),
                         |v| {
                             (
                                 v.parse::<i32>().map_err(|err| {
@@ -194,20 +214,24 @@ impl MolecularFormula {
                 b':' if allow_charge => {
                     if Some(&b'z') == bytes.get(index + 1) {
                         index += 2;
-                        let num = value[index..=end].parse::<i32>().map_err(|err| {
-                            CustomError::error(
-                                "Invalid ProForma molecular formula",
-                                format!("The charge number is {}", explain_number_error(&err)),
-                                Context::line(None, value, index, end - index),
-                            )
-                        })?;
+                        let num = value
+                            .get(index..=end)
+                            .unwrap_or_default()
+                            .parse::<i32>()
+                            .map_err(|err| {
+                                CustomError::error(
+                                    "Invalid ProForma molecular formula",
+                                    format!("The charge number is {}", explain_number_error(&err)),
+                                    Context::line(None, value, index, end - index),
+                                )
+                            })?;
                         let _ = result.add((Element::Electron, None, -num));
                         break 'main_parse_loop;
                     }
                     return Err(CustomError::error(
                         "Invalid ProForma molecular formula",
                             "A charge tag was not set up properly, a charge tag should be formed as ':z<sign><number>'",
-                        Context::line(None, value, index - 1, if bytes.len() < index {1} else {2}),
+                        Context::line(None, value, index.saturating_sub(1), if bytes.len() < index {1} else {2}),
                     ));
                 }
                 _ => {
@@ -304,6 +304,30 @@ impl MolecularFormula {
         self.elements.is_empty() && self.additional_mass == 0.0
     }
 
+    /// All elements (without electrons and zero counts) sorted according to the Hill notation
+    #[allow(dead_code)]
+    pub(in super::super) fn hill_ordered_elements(
+        &self,
+    ) -> Vec<&(Element, Option<NonZeroU16>, i32)> {
+        let is_plain = |e: &(Element, Option<NonZeroU16>, i32), element: Element| {
+            e.0 == element && e.1.is_none()
+        };
+        let mut ordered = Vec::with_capacity(self.elements.len());
+        if let Some(carbon) = self.elements.iter().find(|e| is_plain(e, Element::C)) {
+            ordered.push(carbon);
+            ordered.extend(self.elements.iter().find(|e| is_plain(e, Element::H)));
+            ordered.extend(self.elements.iter().filter(|e| {
+                !(is_plain(e, Element::H)
+                    || is_plain(e, Element::C)
+                    || is_plain(e, Element::Electron))
+            }));
+        } else {
+            ordered.extend(self.elements.iter().filter(|e| e.0 != Element::Electron));
+        }
+        ordered.retain(|e| e.2 != 0);
+        ordered
+    }
+
     /// The generic backbone to do the Hill notation sorting
     #[allow(dead_code)]
     pub(in super::super) fn hill_notation_generic(
@@ -311,37 +335,8 @@ impl MolecularFormula {
         f: impl Fn(&(Element, Option<NonZeroU16>, i32), &mut String),
     ) -> String {
         let mut buffer = String::new();
-        if let Some(carbon) = self
-            .elements
-            .iter()
-            .find(|e| e.0 == Element::C && e.1.is_none())
-        {
-            if carbon.2 != 0 {
-                f(carbon, &mut buffer);
-            }
-            if let Some(hydrogen) = self
-                .elements
-                .iter()
-                .find(|e| e.0 == Element::H && e.1.is_none())
-            {
-                if hydrogen.2 != 0 {
-                    f(hydrogen, &mut buffer);
-                }
-            }
-            for element in self.elements.iter().filter(|e| {
-                !((e.0 == Element::H || e.0 == Element::C || e.0 == Element::Electron)
-                    && e.1.is_none())
-            }) {
-                if element.2 != 0 {
-                    f(element, &mut buffer);
-                }
-            }
-        } else {
-            for element in &self.elements {
-                if element.2 != 0 && element.0 != Element::Electron {
-                    f(element, &mut buffer);
-                }
-            }
+        for element in self.hill_ordered_elements() {
+            f(element, &mut buffer);
         }
         if self.additional_mass != 0.0 {
             write!(&mut buffer, "{:+}", self.additional_mass).unwrap();
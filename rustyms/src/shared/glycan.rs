@@ -76,7 +76,9 @@ impl MonoSaccharide {
         Some(composition)
     }
 
-    /// Parse the given text (will be changed to lowercase) as a glycan composition.
+    /// Parse the given text (will be changed to lowercase) as a glycan composition. Both the
+    /// ProForma style (`Hex5HexNAc4NeuAc2Fuc1`) and the Byonic style with the counts in
+    /// parentheses (`HexNAc(4)Hex(5)NeuAc(2)Fuc(1)`) are supported.
     /// # Errors
     /// When the composition could not be read. Or when any of the glycans occurs outside of the valid range
     pub fn from_composition(text: &str) -> Result<Vec<(Self, isize)>, CustomError> {
@@ -84,7 +86,7 @@ impl MonoSaccharide {
             CustomError::error("Invalid glycan composition", "..", Context::show(text));
         Self::simplify_composition(
             crate::helper_functions::parse_named_counter(
-                &text.to_ascii_lowercase(),
+                &remove_count_parentheses(&text.to_ascii_lowercase()),
                 glycan_parse_list(),
                 false,
             )
@@ -433,6 +435,31 @@ impl Display for MonoSaccharide {
     }
 }
 
+/// Remove the parentheses around the counts in a Byonic style glycan composition
+/// (`HexNAc(4)Hex(5)`), parentheses around anything else (like in `HexNAc(S)`) are kept.
+fn remove_count_parentheses(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(open) = rest.find('(') {
+        result.push_str(&rest[..open]);
+        let inner = rest[open + 1..]
+            .find(')')
+            .map(|close| &rest[open + 1..open + 1 + close])
+            .filter(|inner| {
+                !inner.trim().is_empty() && inner.trim().bytes().all(|c| c.is_ascii_digit())
+            });
+        if let Some(inner) = inner {
+            result.push_str(inner.trim());
+            rest = &rest[open + inner.len() + 2..];
+        } else {
+            result.push('(');
+            rest = &rest[open + 1..];
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
 /// The base sugar of a monosaccharide, optionally with the isomeric state saved as well.
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub enum BaseSugar {
@@ -1,6 +1,6 @@
 use std::{
     ops::{Add, Deref, Mul, MulAssign, Neg, Sub},
-    rc::Rc,
+    sync::Arc,
 };
 
 use itertools::{Itertools, MinMaxResult};
@@ -9,9 +9,10 @@ use serde::{Deserialize, Serialize};
 use crate::system::OrderedMass;
 
 /// A collection of potentially multiple of the generic type, it is used be able to easily
-/// combine multiple of this multi struct into all possible combinations.
+/// combine multiple of this multi struct into all possible combinations. The order of the
+/// options is deterministic, combining the same inputs always results in the same order.
 #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
-pub struct Multi<M>(Rc<[M]>);
+pub struct Multi<M>(Arc<[M]>);
 
 impl<M: Eq + std::hash::Hash + Clone> Multi<M> {
     /// Get all unique values
@@ -42,7 +43,7 @@ impl<M> Deref for Multi<M> {
 impl<M: Default> Default for Multi<M> {
     // Default is one empty M to make the cartesian product with a default return useful results
     fn default() -> Self {
-        Self(Rc::new([M::default()]))
+        Self(Arc::new([M::default()]))
     }
 }
 
@@ -263,13 +264,13 @@ where
 
 impl<M> From<M> for Multi<M> {
     fn from(value: M) -> Self {
-        Self(Rc::new([value]))
+        Self(Arc::new([value]))
     }
 }
 
 impl<M: Clone> From<&M> for Multi<M> {
     fn from(value: &M) -> Self {
-        Self(Rc::new([value.clone()]))
+        Self(Arc::new([value.clone()]))
     }
 }
 
@@ -0,0 +1,170 @@
+use std::{collections::BTreeMap, path::Path};
+
+use crate::{
+    error::{Context, CustomError},
+    helper_functions::open_reader,
+};
+
+/// A parsed OBO ontology file
+#[derive(Debug, Default, Clone)]
+pub struct OboOntology {
+    /// The header lines as key value pairs
+    pub headers: Vec<(String, String)>,
+    /// All stanzas in the file
+    pub objects: Vec<OboObject>,
+}
+
+/// A single stanza (e.g. `[Term]`) from an OBO file
+#[derive(Debug, Default, Clone)]
+pub struct OboObject {
+    /// The stanza type, e.g. `Term`
+    pub name: String,
+    /// All tag value lines, grouped on tag
+    pub lines: BTreeMap<String, Vec<String>>,
+    /// All `property_value` lines, grouped on property
+    pub property_values: BTreeMap<String, Vec<OboValue>>,
+}
+
+/// The value of a `property_value` line
+#[derive(Debug, Clone)]
+pub enum OboValue {
+    /// A string value
+    String(String),
+    /// A floating point value
+    Float(f64),
+    /// An integer value
+    Integer(isize),
+    /// A boolean value
+    Boolean(bool),
+}
+
+impl std::fmt::Display for OboValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::String(s) => write!(f, "{s}"),
+            Self::Float(s) => write!(f, "{s}"),
+            Self::Integer(s) => write!(f, "{s}"),
+            Self::Boolean(s) => write!(f, "{s}"),
+        }
+    }
+}
+
+impl OboOntology {
+    /// Parse an OBO file, gzipped files are uncompressed automatically
+    /// # Errors
+    /// If the file could not be opened or is not a valid OBO file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, CustomError> {
+        Self::from_raw(open_reader(path)?)
+    }
+
+    /// Parse an OBO file from a reader
+    /// # Errors
+    /// If the reader could not be read or is not a valid OBO file.
+    pub fn from_raw<T: std::io::BufRead>(reader: T) -> Result<Self, CustomError> {
+        let mut obo = Self::default();
+        let mut recent_obj = None;
+
+        for (line_index, line) in reader.lines().enumerate() {
+            let line = line
+                .map_err(|e| CustomError::error("Could not read OBO file", e, Context::none()))?
+                .trim_end()
+                .to_string();
+            let error = |short: &str, long: String| {
+                CustomError::error(short, long, Context::full_line(line_index, &line))
+            };
+            if line.is_empty() {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                if let Some(obj) = recent_obj {
+                    obo.objects.push(obj);
+                }
+                recent_obj = Some(OboObject::new(&line[1..=line.len() - 2]));
+            } else if let Some((id, value_line)) = line.split_once(':') {
+                if let Some(obj) = &mut recent_obj {
+                    if id == "property_value" {
+                        let value_line = value_line.trim();
+                        let (Some(first_space), Some(last_space)) =
+                            (value_line.find(' '), value_line.rfind(' '))
+                        else {
+                            return Err(error(
+                                "Invalid property value",
+                                "A property value should contain a name and a value".to_string(),
+                            ));
+                        };
+                        if first_space == last_space {
+                            let name = value_line[..first_space].trim();
+                            let value = value_line[first_space..].trim().trim_matches('"');
+                            obj.property_values
+                                .entry(name.to_string())
+                                .or_default()
+                                .push(OboValue::String(value.to_string()));
+                        } else {
+                            let name = value_line[..first_space].trim().trim_end_matches(':');
+                            let value =
+                                value_line[first_space..last_space].trim().trim_matches('"');
+                            let unit = value_line[last_space..].trim();
+                            let value = match unit {
+                                "xsd:string" => OboValue::String(value.to_string()),
+                                "xsd:double" | "xsd:float" => {
+                                    if !value.starts_with('-') && value.contains('-') {
+                                        // Some ontologies use a range
+                                        OboValue::String(value.to_string())
+                                    } else {
+                                        OboValue::Float(value.parse().map_err(|err| {
+                                            error("Invalid float", format!("'{value}' {err}"))
+                                        })?)
+                                    }
+                                }
+                                "xsd:boolean" => OboValue::Boolean(value == "true" || value == "1"),
+                                "xsd:integer"
+                                | "xsd:nonNegativeInteger"
+                                | "xsd:positiveInteger" => {
+                                    OboValue::Integer(value.parse().map_err(|err| {
+                                        error("Invalid integer", format!("'{value}' {err}"))
+                                    })?)
+                                }
+                                dt => {
+                                    return Err(error(
+                                        "Invalid property value",
+                                        format!("Undefined datatype '{dt}'"),
+                                    ))
+                                }
+                            };
+                            obj.property_values
+                                .entry(name.to_string())
+                                .or_default()
+                                .push(value);
+                        }
+                    } else {
+                        obj.lines
+                            .entry(id.trim().to_string())
+                            .or_default()
+                            .push(value_line.trim().to_string());
+                    }
+                } else {
+                    obo.headers.push((id.to_string(), value_line.to_string()));
+                }
+            } else {
+                return Err(error(
+                    "Invalid line in OBO file",
+                    "A line should be a stanza header or a tag value pair".to_string(),
+                ));
+            }
+        }
+        if let Some(obj) = recent_obj {
+            obo.objects.push(obj);
+        }
+        Ok(obo)
+    }
+}
+
+impl OboObject {
+    /// Create a new empty stanza of the given type
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            ..Self::default()
+        }
+    }
+}
@@ -0,0 +1,697 @@
+/// A modification as defined in an ontology file, before it is turned into a [`SimpleModification`]
+#[derive(Debug, Default)]
+pub struct OntologyModification {
+    /// The diff formula
+    pub formula: MolecularFormula,
+    /// The name
+    pub name: String,
+    /// The ontology this modification is defined in
+    pub ontology: Ontology,
+    /// The numerical id
+    pub id: usize,
+    /// The description
+    pub description: String,
+    /// All synonyms
+    pub synonyms: ThinVec<String>,
+    /// All cross reference ids
+    pub cross_ids: ThinVec<(String, String)>,
+    /// The modification or cross-linker specific data
+    pub data: ModData,
+}
+
+/// The data specific for a modification or a cross-linker
+#[derive(Debug)]
+pub enum ModData {
+    /// A modification
+    Mod {
+        /// The placement rules, neutral losses, and diagnostic ions
+        specificities: Vec<(Vec<PlacementRule>, Vec<NeutralLoss>, Vec<DiagnosticIon>)>,
+    },
+    /// A cross-linker
+    Linker {
+        /// The length, if known
+        length: Option<OrderedFloat<f64>>,
+        /// All possible specificities
+        specificities: Vec<LinkerSpecificity>,
+    },
+}
+
+impl Default for ModData {
+    fn default() -> Self {
+        Self::Mod {
+            specificities: Vec::new(),
+        }
+    }
+}
+
+impl OntologyModification {
+    /// Simplify the placement rules
+    pub fn simplify_rules(&mut self) {
+        match &mut self.data {
+            ModData::Mod {
+                specificities: ref mut rules,
+            } => {
+                let mut new: Vec<(Vec<PlacementRule>, Vec<NeutralLoss>, Vec<DiagnosticIon>)> =
+                    Vec::new();
+                for rule in rules.iter() {
+                    let rule = (
+                        rule.0.clone(),
+                        rule.1.iter().unique().sorted().cloned().collect(),
+                        rule.2.iter().unique().sorted().cloned().collect(),
+                    ); // Remove duplicate neutral losses and diagnostic ions, and sort for a better guarantee of equality
+                    if new.is_empty() {
+                        new.push(rule.clone());
+                    } else {
+                        let mut found = false;
+                        for new_rule in &mut new {
+                            // Check if there is a rule with the same neutral loss and diagnostic ions (these can be location specific)
+                            if new_rule.1 == rule.1 && new_rule.2 == rule.2 {
+                                found = true;
+                                // Check if there are other rules in this set of neutral&diagnostic that also use AA placements
+                                // If there are, and they are on the same position, merge the AA set
+                                for position in &rule.0 {
+                                    let mut pos_found = false;
+                                    for new_position in &mut new_rule.0 {
+                                        if let (
+                                            PlacementRule::AminoAcid(new_aa, new_pos),
+                                            PlacementRule::AminoAcid(aa, pos),
+                                        ) = (new_position, position)
+                                        {
+                                            if *new_pos == *pos {
+                                                for a in aa {
+                                                    if !new_aa.contains(a) {
+                                                        new_aa.push(*a);
+                                                    }
+                                                }
+                                                new_aa.sort_unstable();
+                                                pos_found = true;
+                                                break;
+                                            }
+                                        }
+                                    }
+                                    if !pos_found {
+                                        new_rule.0.push(position.clone());
+                                    }
+                                }
+                            }
+                        }
+                        if !found {
+                            new.push(rule.clone());
+                        }
+                    }
+                }
+                rules.clear();
+                rules.extend(new);
+            }
+            ModData::Linker { specificities, .. } => {
+                *specificities = specificities.iter().unique().sorted().cloned().collect();
+            }
+        }
+    }
+
+    /// Turn this into an entry for an [`OntologyModificationList`], this simplifies the rules
+    pub fn into_mod(mut self) -> (Option<usize>, String, SimpleModification) {
+        self.simplify_rules();
+        let id = ModificationId {
+            ontology: self.ontology,
+            name: self.name.clone(),
+            id: Some(self.id),
+            description: self.description,
+            synonyms: self.synonyms,
+            cross_ids: self.cross_ids,
+        };
+        match self.data {
+            ModData::Mod { specificities } => (
+                Some(self.id),
+                self.name.to_ascii_lowercase(),
+                Arc::new(SimpleModificationInner::Database {
+                    id,
+                    formula: self.formula,
+                    specificities,
+                }),
+            ),
+            ModData::Linker {
+                specificities,
+                length,
+            } => (
+                Some(self.id),
+                self.name.to_ascii_lowercase(),
+                Arc::new(SimpleModificationInner::Linker {
+                    specificities,
+                    formula: self.formula,
+                    id,
+                    length,
+                }),
+            ),
+        }
+    }
+
+    /// Create a new modification with the id, name, description, cross ids, and synonyms from the
+    /// given OBO term
+    /// # Errors
+    /// If the id or name is missing or if any of these lines is invalid.
+    fn from_obo_term(obj: &OboObject, ontology: Ontology) -> Result<Self, CustomError> {
+        let error = |long: String, context: &str| {
+            CustomError::error(
+                format!("Invalid {} term", ontology.name()),
+                long,
+                Context::show(context),
+            )
+        };
+        let id_line = obj
+            .lines
+            .get("id")
+            .and_then(|v| v.first())
+            .ok_or_else(|| error("A term should have an id".to_string(), &obj.name))?;
+        let id = id_line
+            .split_once(':')
+            .and_then(|(_, id)| id.parse().ok())
+            .ok_or_else(|| {
+                error(
+                    "The id should be a prefix and a number separated by a colon".to_string(),
+                    id_line,
+                )
+            })?;
+        let name = obj
+            .lines
+            .get("name")
+            .and_then(|v| v.first())
+            .ok_or_else(|| error("A term should have a name".to_string(), id_line))?
+            .clone();
+        let mut modification = Self {
+            name,
+            ontology,
+            id,
+            ..Self::default()
+        };
+        if let Some(line) = obj.lines.get("def").and_then(|v| v.first()) {
+            let (description, ids) = line
+                .strip_prefix('"')
+                .and_then(|l| l.split_once('"'))
+                .ok_or_else(|| {
+                    error(
+                        "A definition should start with a quoted text".to_string(),
+                        line,
+                    )
+                })?;
+            modification.description = description.to_string();
+            let ids = ids
+                .trim()
+                .strip_prefix('[')
+                .and_then(|ids| ids.strip_suffix(']'))
+                .ok_or_else(|| {
+                    error(
+                        "A definition should end with a list of cross ids".to_string(),
+                        line,
+                    )
+                })?;
+            for id in ids.split(',').map(str::trim).filter(|id| !id.is_empty()) {
+                let (resource, id) = id.split_once(':').ok_or_else(|| {
+                    error(
+                        "A cross id should be a resource and an id separated by a colon"
+                            .to_string(),
+                        line,
+                    )
+                })?;
+                if ontology == Ontology::Unimod {
+                    // Some urls have escaped colons
+                    if resource != "UNIMODURL" {
+                        modification
+                            .cross_ids
+                            .push((resource.to_string(), id.replace("\\:", ":")));
+                    }
+                } else {
+                    modification
+                        .cross_ids
+                        .push((resource.to_string(), id.to_string()));
+                }
+            }
+        }
+        for line in obj.lines.get("synonym").into_iter().flatten() {
+            let (synonym, _) = line
+                .strip_prefix('"')
+                .and_then(|l| l.split_once('"'))
+                .ok_or_else(|| error("A synonym should be a quoted text".to_string(), line))?;
+            modification.synonyms.push(synonym.to_string());
+        }
+        Ok(modification)
+    }
+
+    /// Parse all modifications from a Unimod OBO file, terms without a delta composition are ignored
+    /// # Errors
+    /// If any term is not a valid Unimod modification.
+    /// # Panics
+    /// If the built in regexes are invalid, always report a panic if it occurs here.
+    pub fn parse_unimod(obo: &OboOntology) -> Result<Vec<Self>, CustomError> {
+        let re_position = Regex::new("spec_(\\d+)_position \"(.+)\"").unwrap();
+        let re_site = Regex::new("spec_(\\d+)_site \"(.+)\"").unwrap();
+        let re_neutral_loss =
+            Regex::new("spec_(\\d+)_neutral_loss_\\d+_composition \"(.+)\"").unwrap();
+        let error = |long: String, context: &str| {
+            CustomError::error("Invalid Unimod term", long, Context::show(context))
+        };
+        let index = |line: &str, groups: &regex::Captures| {
+            groups[1]
+                .parse::<usize>()
+                .ok()
+                .and_then(|i| i.checked_sub(1))
+                .ok_or_else(|| error("Invalid specificity index".to_string(), line))
+        };
+        let mut mods = Vec::new();
+
+        for obj in obo.objects.iter().filter(|obj| obj.name == "Term") {
+            let mut modification = Self::from_obo_term(obj, Ontology::Unimod)?;
+            let mut take = false;
+            // The rules (site, position, neutral losses) by index
+            let mut mod_rules: BTreeMap<usize, (String, String, Vec<NeutralLoss>)> =
+                BTreeMap::new();
+            for line in obj.lines.get("xref").into_iter().flatten() {
+                if line.starts_with("delta_composition") {
+                    modification.formula = MolecularFormula::from_unimod(line, 19..line.len())?;
+                    take = true;
+                } else if let Some(groups) = re_position.captures(line) {
+                    mod_rules.entry(index(line, &groups)?).or_default().1 = groups[2].to_string();
+                } else if let Some(groups) = re_site.captures(line) {
+                    mod_rules
+                        .entry(index(line, &groups)?)
+                        .or_default()
+                        .0
+                        .push_str(&groups[2]);
+                } else if let Some(groups) = re_neutral_loss.captures(line) {
+                    if &groups[2] != "0" {
+                        let loss =
+                            NeutralLoss::Loss(MolecularFormula::from_unimod(&groups[2], ..)?);
+                        mod_rules
+                            .entry(index(line, &groups)?)
+                            .or_default()
+                            .2
+                            .push(loss);
+                    }
+                }
+            }
+            let mut rules = Vec::new();
+            for (site, position, losses) in mod_rules.into_values() {
+                if site.is_empty() && position.is_empty() {
+                    continue;
+                }
+                let position = unimod_position(&position).ok_or_else(|| {
+                    error(format!("Invalid position '{position}'"), &modification.name)
+                })?;
+                let rule = if site == "C-term" || site == "N-term" {
+                    PlacementRule::Terminal(position)
+                } else {
+                    PlacementRule::AminoAcid(
+                        site.chars()
+                            .map(|c| {
+                                AminoAcid::try_from(c).map_err(|()| {
+                                    error(format!("Invalid amino acid '{c}'"), &modification.name)
+                                })
+                            })
+                            .collect::<Result<_, _>>()?,
+                        position,
+                    )
+                };
+                rules.push((vec![rule], losses, Vec::new()));
+            }
+            modification.data = ModData::Mod {
+                specificities: rules,
+            };
+            if take {
+                mods.push(modification);
+            }
+        }
+
+        Ok(mods)
+    }
+
+    /// Parse all modifications from a PSI-MOD OBO file
+    /// # Errors
+    /// If any term is not a valid PSI-MOD modification.
+    pub fn parse_psi_mod(obo: &OboOntology) -> Result<Vec<Self>, CustomError> {
+        let error = |long: String, context: &str| {
+            CustomError::error("Invalid PSI-MOD term", long, Context::show(context))
+        };
+        let mut mods = Vec::new();
+
+        for obj in obo.objects.iter().filter(|obj| obj.name == "Term") {
+            let mut modification = Self::from_obo_term(obj, Ontology::Psimod)?;
+            let mut rules = Vec::new();
+            let mut origins = Vec::new();
+            let mut term = None;
+            for (id, value) in &obj.property_values {
+                let Some(value) = value.first().map(ToString::to_string) else {
+                    continue;
+                };
+                if id == "DiffFormula" {
+                    modification.formula = MolecularFormula::from_psi_mod(&value, ..)?;
+                } else if id == "Origin" {
+                    origins = value.split(',').map(|s| s.trim().to_string()).collect();
+                } else if id == "TermSpec" {
+                    term = Some(match value.as_str() {
+                        "N-term" => Position::AnyNTerm,
+                        "C-term" => Position::AnyCTerm,
+                        _ => {
+                            return Err(error(
+                                format!("Invalid TermSpec '{value}'"),
+                                &modification.name,
+                            ))
+                        }
+                    });
+                }
+            }
+            // If the list of possible origins contains "X" than the mod can be placed on any aminoacid
+            // But if there is a TermSpec definition that should still be accounted for
+            let all_aminoacids = origins.iter().any(|o| o == "X");
+            if !all_aminoacids {
+                for origin in &origins {
+                    let rule = if origin.len() == 1 {
+                        PlacementRule::AminoAcid(
+                            vec![AminoAcid::try_from(origin).map_err(|()| {
+                                error(format!("Invalid amino acid '{origin}'"), &modification.name)
+                            })?],
+                            term.unwrap_or(Position::Anywhere),
+                        )
+                    } else {
+                        PlacementRule::PsiModification(
+                            origin
+                                .split_once(':')
+                                .and_then(|(_, id)| id.parse().ok())
+                                .ok_or_else(|| {
+                                    error(format!("Invalid origin '{origin}'"), &modification.name)
+                                })?,
+                            term.unwrap_or(Position::Anywhere),
+                        )
+                    };
+                    rules.push((vec![rule], Vec::new(), Vec::new()));
+                }
+            }
+            if origins.is_empty() || all_aminoacids {
+                if let Some(term) = term {
+                    rules.push((vec![PlacementRule::Terminal(term)], Vec::new(), Vec::new()));
+                }
+            }
+            modification.data = ModData::Mod {
+                specificities: rules,
+            };
+            mods.push(modification);
+        }
+
+        Ok(mods)
+    }
+
+    /// Parse all modifications and cross-linkers from an XLMOD OBO file, trifunctional
+    /// cross-linkers are seen as cross-linkers between two of their sites. Cross-linkers without
+    /// their own specificities get the specificities of their reactive groups, and modifications
+    /// derived from a cross-linker (like hydrolysed dead ends) get the specificities of that
+    /// cross-linker. A pair of CID fragments of a cleavable cross-linker that sum up to the mass
+    /// of the cross-linker are stubs, all other CID fragments and reporter masses are diagnostic
+    /// ions.
+    /// # Errors
+    /// If any term is not a valid XLMOD modification or cross-linker.
+    pub fn parse_xlmod(obo: &OboOntology) -> Result<Vec<Self>, CustomError> {
+        let terms: Vec<&OboObject> = obo
+            .objects
+            .iter()
+            .filter(|obj| obj.name == "Term")
+            .collect();
+        let by_id: std::collections::HashMap<&str, &OboObject> = terms
+            .iter()
+            .filter_map(|obj| {
+                obj.lines
+                    .get("id")
+                    .and_then(|v| v.first())
+                    .map(|id| (id.as_str(), *obj))
+            })
+            .collect();
+        let mut mods = Vec::new();
+
+        for obj in terms {
+            let mut modification = Self::from_obo_term(obj, Ontology::Xlmod)?;
+            let error = |property: &str| {
+                CustomError::error(
+                    "Invalid XLMOD term",
+                    format!("Invalid value for '{property}'"),
+                    Context::show(&modification.name),
+                )
+            };
+            let float = |property: &str, value: &OboValue| {
+                if let OboValue::Float(n) = value {
+                    Ok(*n)
+                } else {
+                    Err(error(property))
+                }
+            };
+
+            let mut sites = None;
+            let mut length = None;
+            let mut mass = None;
+            let mut formula = None;
+            let mut diagnostic_ions = Vec::new();
+            let mut fragments = Vec::new();
+            for (id, values) in &obj.property_values {
+                let Some(value) = values.first() else {
+                    continue;
+                };
+                match id.as_str() {
+                    "reactionSites" => {
+                        sites = Some(if let OboValue::Integer(n) = value {
+                            u8::try_from(*n).map_err(|_| error(id))?
+                        } else {
+                            return Err(error(id));
+                        });
+                    }
+                    "spacerLength" => {
+                        // Can contain ranges, which are ignored
+                        length = float(id, value).ok().map(OrderedFloat);
+                    }
+                    "monoIsotopicMass" => {
+                        mass = Some(float(id, value)?);
+                    }
+                    "deadEndFormula" => {
+                        sites = Some(1);
+                        formula = Some(MolecularFormula::from_xlmod(&value.to_string(), ..)?);
+                    }
+                    "bridgeFormula" => {
+                        sites = Some(2);
+                        formula = Some(MolecularFormula::from_xlmod(&value.to_string(), ..)?);
+                    }
+                    "reporterMass" => {
+                        // reporterMass: "555.2481" xsd:double
+                        for value in values {
+                            diagnostic_ions.push(DiagnosticIon(
+                                MolecularFormula::with_additional_mass(float(id, value)?),
+                            ));
+                        }
+                    }
+                    "CID_Fragment" => {
+                        // CID_Fragment: "828.5" xsd:double
+                        for value in values {
+                            fragments.push(float(id, value)?);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            let (mut origins, inherited) = xlmod_specificities(obj).map_or_else(
+                || (xlmod_inherited_specificities(obj, &by_id, true), true),
+                |origins| (origins, false),
+            );
+            let linker = matches!(sites, Some(2 | 3));
+            if inherited && !linker {
+                // A modification derived from a heterofunctional cross-linker can be on either side
+                if let Some(right) = origins.1.take() {
+                    origins.0 = xlmod_union(vec![std::mem::take(&mut origins.0), right]);
+                }
+            }
+            let symmetric = origins.1.is_none();
+            let origins = (
+                xlmod_placement_rules(&origins.0, &modification.name)?,
+                xlmod_placement_rules(&origins.1.unwrap_or_default(), &modification.name)?,
+            );
+            let cleavable = obj
+                .lines
+                .get("relationship")
+                .is_some_and(|r| r.iter().any(|r| r.starts_with("is_cleavable ")));
+            let stubs = match (fragments.as_slice(), mass) {
+                ([a, b], Some(mass)) if cleavable && linker && (a + b - mass).abs() < 0.01 => {
+                    vec![(
+                        MolecularFormula::with_additional_mass(*a),
+                        MolecularFormula::with_additional_mass(*b),
+                    )]
+                }
+                _ => {
+                    diagnostic_ions.extend(fragments.iter().map(|fragment| {
+                        DiagnosticIon(MolecularFormula::with_additional_mass(*fragment))
+                    }));
+                    Vec::new()
+                }
+            };
+            // Ignore the mass if a formula is set
+            modification.formula = formula
+                .or_else(|| mass.map(MolecularFormula::with_additional_mass))
+                .unwrap_or_default();
+            modification.data = if linker || !symmetric {
+                ModData::Linker {
+                    length,
+                    specificities: vec![if symmetric {
+                        LinkerSpecificity::Symmetric(origins.0, stubs, diagnostic_ions)
+                    } else {
+                        LinkerSpecificity::Asymmetric(
+                            (origins.0, origins.1),
+                            stubs,
+                            diagnostic_ions,
+                        )
+                    }],
+                }
+            } else {
+                ModData::Mod {
+                    specificities: vec![(origins.0, Vec::new(), diagnostic_ions)],
+                }
+            };
+            mods.push(modification);
+        }
+
+        Ok(mods)
+    }
+}
+
+/// The specificities of an XLMOD term, the second side is only set for asymmetric cross-linkers,
+/// an empty side means anywhere
+type XlmodSpecificities = (Vec<String>, Option<Vec<String>>);
+
+/// Get the specificities defined in this XLMOD term, if any
+fn xlmod_specificities(obj: &OboObject) -> Option<XlmodSpecificities> {
+    let split = |s: &str| -> Vec<String> {
+        s.trim_matches(['(', ')'])
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .collect()
+    };
+    // specificities: "(C,U)" xsd:string
+    // specificities: "(K,N,Q,R,Protein N-term)&(E,D,Protein C-term)" xsd:string
+    let value = obj
+        .property_values
+        .get("specificities")?
+        .first()?
+        .to_string();
+    let mut origins = value.split_once('&').map_or_else(
+        || (split(&value), None),
+        |(l, r)| (split(l), Some(split(r))),
+    );
+    // secondarySpecificities: "(S,T,Y)" xsd:string
+    if let Some(secondary) = obj
+        .property_values
+        .get("secondarySpecificities")
+        .and_then(|v| v.first())
+    {
+        origins.0.extend(split(&secondary.to_string()));
+    }
+    Some(origins)
+}
+
+/// Get the specificities of an XLMOD term from the reactive groups it is related to. A single
+/// reactive group (or multiple identical groups) results in the specificities of that group, two
+/// different reactive groups result in a set for each side. If the term has no reactive groups and
+/// `follow_parent` is set the specificities of the cross-linker this is a side product of are used.
+fn xlmod_inherited_specificities(
+    obj: &OboObject,
+    terms: &std::collections::HashMap<&str, &OboObject>,
+    follow_parent: bool,
+) -> XlmodSpecificities {
+    let related = |relationship: &str| -> Vec<&OboObject> {
+        obj.lines
+            .get("relationship")
+            .into_iter()
+            .flatten()
+            .filter_map(|line| {
+                let mut parts = line.split_whitespace();
+                (parts.next() == Some(relationship))
+                    .then(|| parts.next())
+                    .flatten()
+                    .and_then(|id| terms.get(id).copied())
+            })
+            .collect()
+    };
+    let mut groups: Vec<XlmodSpecificities> = Vec::new();
+    for group in related("has_reactive_group") {
+        // A reactive group without specificities (like a photoreactive group) reacts anywhere
+        let group = xlmod_specificities(group).unwrap_or_default();
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    match groups.len() {
+        0 if follow_parent => related("is_side_product_of").first().map_or_else(
+            || (Vec::new(), None),
+            |parent| {
+                xlmod_specificities(parent)
+                    .unwrap_or_else(|| xlmod_inherited_specificities(parent, terms, false))
+            },
+        ),
+        // A single reactive group (like a carbodiimide) can itself be heterofunctional
+        1 => groups.pop().unwrap_or_default(),
+        2 if groups.iter().all(|(_, right)| right.is_none()) => {
+            let right = groups.pop().map(|(left, _)| left);
+            (groups.pop().unwrap_or_default().0, right)
+        }
+        _ => (
+            xlmod_union(
+                groups
+                    .into_iter()
+                    .flat_map(|(left, right)| std::iter::once(left).chain(right))
+                    .collect(),
+            ),
+            None,
+        ),
+    }
+}
+
+/// Combine multiple sets of specificities, if any of these is empty (anywhere) the result is empty
+fn xlmod_union(sets: Vec<Vec<String>>) -> Vec<String> {
+    if sets.iter().any(Vec::is_empty) {
+        Vec::new()
+    } else {
+        sets.into_iter().flatten().collect()
+    }
+}
+
+/// Get the position from the name used in Unimod
+fn unimod_position(name: &str) -> Option<Position> {
+    match name {
+        "" | "Anywhere" => Some(Position::Anywhere),
+        "Any N-term" => Some(Position::AnyNTerm),
+        "Any C-term" => Some(Position::AnyCTerm),
+        "Protein N-term" => Some(Position::ProteinNTerm),
+        "Protein C-term" => Some(Position::ProteinCTerm),
+        _ => None,
+    }
+}
+
+/// Get the placement rules from the specificities as used in XLMOD
+/// # Errors
+/// If any specificity is not an amino acid or protein terminus.
+fn xlmod_placement_rules(bricks: &[String], name: &str) -> Result<Vec<PlacementRule>, CustomError> {
+    if bricks.is_empty() {
+        Ok(vec![PlacementRule::Anywhere])
+    } else {
+        bricks
+            .iter()
+            .filter(|brick| *brick != "Thy")
+            .map(|brick| match brick.as_str() {
+                "Protein N-term" => Ok(PlacementRule::Terminal(Position::ProteinNTerm)),
+                "Protein C-term" => Ok(PlacementRule::Terminal(Position::ProteinCTerm)),
+                _ => AminoAcid::try_from(brick)
+                    .ok()
+                    .filter(|_| brick.len() == 1)
+                    .map(|aa| PlacementRule::AminoAcid(vec![aa], Position::Anywhere))
+                    .ok_or_else(|| {
+                        CustomError::error(
+                            "Invalid XLMOD term",
+                            format!("Invalid placement rule '{brick}'"),
+                            Context::show(name),
+                        )
+                    }),
+            })
+            .collect()
+    }
+}
@@ -1,6 +1,6 @@
 //! Annotated spectra
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, ops::RangeBounds};
 
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
@@ -29,12 +29,36 @@ pub struct AnnotatedSpectrum {
     pub charge: Option<Charge>,
     /// The found precursor mass
     pub mass: Option<Mass>,
+    /// The found precursor ion mobility, for timsTOF data this is the inverse reduced mobility (1/K0 in V·s/cm²)
+    pub ion_mobility: Option<f64>,
+    /// The found precursor collisional cross section (in Å²)
+    pub ccs: Option<f64>,
     /// The peptide with which this spectrum was annotated
     pub peptide: CompoundPeptidoformIon,
     /// The spectrum
     pub(super) spectrum: Vec<AnnotatedPeak>,
 }
 
+impl AnnotatedSpectrum {
+    /// Get all peaks that are annotated with at least one fragment
+    pub fn matched_peaks(&self) -> impl Iterator<Item = &AnnotatedPeak> {
+        self.spectrum.iter().filter(|p| !p.annotation.is_empty())
+    }
+
+    /// Get all peaks that are not annotated with any fragment
+    pub fn unmatched_peaks(&self) -> impl Iterator<Item = &AnnotatedPeak> {
+        self.spectrum.iter().filter(|p| p.annotation.is_empty())
+    }
+
+    /// Filter the spectrum to retain all peaks with an ion mobility within the given range. Peaks
+    /// without a known ion mobility are retained.
+    pub fn ion_mobility_filter(&mut self, range: impl RangeBounds<f64>) {
+        self.spectrum
+            .retain(|p| p.ion_mobility.map_or(true, |im| range.contains(&im)));
+        self.spectrum.shrink_to_fit();
+    }
+}
+
 impl Extend<AnnotatedPeak> for AnnotatedSpectrum {
     fn extend<T: IntoIterator<Item = AnnotatedPeak>>(&mut self, iter: T) {
         self.spectrum.extend(iter);
@@ -119,6 +143,10 @@ pub struct AnnotatedPeak {
     pub experimental_mz: MassOverCharge,
     /// The experimental intensity
     pub intensity: OrderedFloat<f64>,
+    /// The experimental ion mobility, for timsTOF data this is the inverse reduced mobility (1/K0 in V·s/cm²)
+    pub ion_mobility: Option<f64>,
+    /// The experimental collisional cross section (in Å²)
+    pub ccs: Option<f64>,
     /// The annotation, if present
     pub annotation: Vec<Fragment>, // Could become Vec<(Fragment, Vec<MatchedIsotopeDistribution>)> when isotope matching is finally in place
     /// Any annotation as isotope from a given fragment
@@ -131,6 +159,8 @@ impl AnnotatedPeak {
         Self {
             experimental_mz: peak.mz,
             intensity: peak.intensity,
+            ion_mobility: peak.ion_mobility,
+            ccs: peak.ccs,
             annotation: vec![annotation],
             isotope_annotation: Vec::new(),
         }
@@ -141,6 +171,8 @@ impl AnnotatedPeak {
         Self {
             experimental_mz: peak.mz,
             intensity: peak.intensity,
+            ion_mobility: peak.ion_mobility,
+            ccs: peak.ccs,
             annotation: Vec::new(),
             isotope_annotation: Vec::new(),
         }
@@ -170,6 +202,8 @@ impl PartialEq for AnnotatedPeak {
             .total_cmp(&other.experimental_mz.value)
             == Ordering::Equal
             && self.intensity.total_cmp(&other.intensity) == Ordering::Equal
+            && self.ion_mobility.map(OrderedFloat) == other.ion_mobility.map(OrderedFloat)
+            && self.ccs.map(OrderedFloat) == other.ccs.map(OrderedFloat)
             && self.annotation == other.annotation
     }
 }
@@ -0,0 +1,414 @@
+//! De novo sequencing based on spectrum graphs
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    model::{Location, PrimaryIonSeries},
+    spectrum::PeakSpectrum,
+    system::{da, e, Mass},
+    Chemical, Model, MolecularCharge, Peptidoform, RawSpectrum, SemiAmbiguous, SequenceElement,
+};
+
+/// The settings for de novo sequencing, see [`RawSpectrum::de_novo`].
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeNovoSettings {
+    /// The maximal number of residues that can be bridged by a single edge in the spectrum graph,
+    /// this allows missing fragment peaks. The order of the residues within such a gap cannot be
+    /// determined.
+    ///
+    /// Default: 2.
+    pub max_gap: usize,
+    /// The penalty for every residue in a gap, in the same unit as the score of the nodes (the
+    /// relative intensity).
+    ///
+    /// Default: 0.1.
+    pub gap_penalty: f64,
+    /// The maximal number of candidates that are returned.
+    ///
+    /// Default: 10.
+    pub max_candidates: usize,
+}
+
+impl Default for DeNovoSettings {
+    fn default() -> Self {
+        Self {
+            max_gap: 2,
+            gap_penalty: 0.1,
+            max_candidates: 10,
+        }
+    }
+}
+
+impl DeNovoSettings {
+    /// Set the maximal number of residues in a gap
+    #[must_use]
+    pub const fn max_gap(self, max_gap: usize) -> Self {
+        Self { max_gap, ..self }
+    }
+
+    /// Set the penalty for every residue in a gap
+    #[must_use]
+    pub const fn gap_penalty(self, gap_penalty: f64) -> Self {
+        Self {
+            gap_penalty,
+            ..self
+        }
+    }
+
+    /// Set the maximal number of candidates
+    #[must_use]
+    pub const fn max_candidates(self, max_candidates: usize) -> Self {
+        Self {
+            max_candidates,
+            ..self
+        }
+    }
+}
+
+/// A de novo sequencing candidate, see [`RawSpectrum::de_novo`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeNovoCandidate {
+    /// The peptide
+    pub peptide: Peptidoform<SemiAmbiguous>,
+    /// The score of the path through the spectrum graph, the summed score of all nodes minus the
+    /// gap penalties
+    pub score: f64,
+    /// The confidence for every residue in the peptide, between 0 and 1. This is the average
+    /// support of the two nodes flanking the residue, divided by the number of residues between
+    /// these nodes.
+    pub confidence: Vec<f64>,
+}
+
+/// A node in the spectrum graph, a possible prefix residue mass
+#[derive(Clone, Copy, Debug)]
+struct Node {
+    /// The mass of all residues N terminal of this node
+    mass: Mass,
+    /// The allowed error on the mass (in Dalton)
+    error: f64,
+    /// The score, the summed relative intensity of all supporting peaks
+    score: f64,
+}
+
+/// An entry in the k best list of a node
+#[derive(Clone, Copy, Debug)]
+struct Entry {
+    score: f64,
+    /// The previous node, entry in the list of that node, and the index of the used combination
+    previous: Option<(usize, usize, usize)>,
+}
+
+impl RawSpectrum {
+    /// De novo sequence this spectrum. This builds a spectrum graph: every peak is interpreted as
+    /// all ion series from the model (a, b, and c as N terminal and x, y, and z· as C terminal
+    /// series) with any of the charges allowed for the series given the precursor charge. Every
+    /// interpretation gives a node at the mass of all residues N terminal of the fragmentation
+    /// site, nodes within the model tolerance are merged. The N and C termini are added as well.
+    /// Two nodes are connected if the mass difference between them matches one of the given
+    /// building blocks, or a combination of up to [`DeNovoSettings::max_gap`] building blocks.
+    /// The highest scoring paths from the N to the C terminus are returned as candidates, sorted
+    /// on descending score.
+    ///
+    /// The spectrum has to have a known precursor mass, which is assumed to be the neutral mass.
+    /// If no precursor charge is known it is assumed to be 1. For spectra with high charge
+    /// fragments consider deconvoluting the spectrum first. Only the given building blocks are
+    /// used, so terminal modifications are not placed.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, spectrum::*};
+    /// # let spectrum = RawSpectrum::default();
+    /// let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+    /// let candidates = spectrum.de_novo(&blocks, &Model::cid_hcd(), &DeNovoSettings::default());
+    /// for candidate in candidates {
+    ///     println!("{} {:.3} {:?}", candidate.peptide, candidate.score, candidate.confidence);
+    /// }
+    /// ```
+    pub fn de_novo(
+        &self,
+        building_blocks: &[(SequenceElement<SemiAmbiguous>, Mass)],
+        model: &Model,
+        settings: &DeNovoSettings,
+    ) -> Vec<DeNovoCandidate> {
+        let Some(precursor) = self.mass else {
+            return Vec::new();
+        };
+        let water = molecular_formula!(H 2 O 1).monoisotopic_mass();
+        let total = precursor - water;
+        if total.value <= 0.0 || building_blocks.is_empty() || settings.max_candidates == 0 {
+            return Vec::new();
+        }
+
+        let nodes = self.spectrum_graph_nodes(total, model);
+        let combinations = block_combinations(building_blocks, settings.max_gap.max(1));
+        let Some(largest) = combinations.last().map(|c| c.1) else {
+            return Vec::new();
+        };
+
+        // Find the k best paths to every node, the nodes are sorted on mass so this is a single
+        // pass over all nodes
+        let mut best: Vec<Vec<Entry>> = vec![Vec::new(); nodes.len()];
+        best[0].push(Entry {
+            score: nodes[0].score,
+            previous: None,
+        });
+        for to in 1..nodes.len() {
+            let mut entries = Vec::new();
+            for from in (0..to).rev() {
+                let difference = (nodes[to].mass - nodes[from].mass).value;
+                let error = nodes[to].error + nodes[from].error;
+                if difference - error > largest.value {
+                    break;
+                }
+                if best[from].is_empty() {
+                    continue;
+                }
+                let start = combinations.partition_point(|c| c.1.value < difference - error);
+                for (index, (blocks, mass)) in combinations.iter().enumerate().skip(start) {
+                    if mass.value > difference + error {
+                        break;
+                    }
+                    let penalty = settings.gap_penalty * (blocks.len() - 1) as f64;
+                    for (entry_index, entry) in best[from].iter().enumerate() {
+                        entries.push(Entry {
+                            score: entry.score + nodes[to].score - penalty,
+                            previous: Some((from, entry_index, index)),
+                        });
+                    }
+                }
+            }
+            entries.sort_by(|a, b| b.score.total_cmp(&a.score));
+            entries.truncate(settings.max_candidates);
+            best[to] = entries;
+        }
+
+        // Trace back the best paths to the C terminus
+        let last = nodes.len() - 1;
+        best[last]
+            .iter()
+            .map(|end| {
+                let mut sequence = Vec::new();
+                let mut confidence = Vec::new();
+                let mut current = (last, *end);
+                while let Some((from, entry_index, combination)) = current.1.previous {
+                    let blocks = &combinations[combination].0;
+                    let support = (nodes[from].score.min(1.0) + nodes[current.0].score.min(1.0))
+                        / 2.0
+                        / blocks.len() as f64;
+                    for block in blocks.iter().rev() {
+                        sequence.push(building_blocks[*block].0.clone());
+                        confidence.push(support);
+                    }
+                    current = (from, best[from][entry_index]);
+                }
+                sequence.reverse();
+                confidence.reverse();
+                DeNovoCandidate {
+                    peptide: Peptidoform::new(sequence),
+                    score: end.score,
+                    confidence,
+                }
+            })
+            .collect()
+    }
+
+    /// Create the nodes for the spectrum graph, sorted on mass, with the N terminus as first and
+    /// the C terminus as last node
+    fn spectrum_graph_nodes(&self, total: Mass, model: &Model) -> Vec<Node> {
+        let proton = MolecularCharge::proton(1).formula().monoisotopic_mass();
+        let water = molecular_formula!(H 2 O 1).monoisotopic_mass();
+        let carbon_monoxide = molecular_formula!(C 1 O 1).monoisotopic_mass();
+        let ammonia = molecular_formula!(N 1 H 3).monoisotopic_mass();
+        let hydrogen = molecular_formula!(H 1).monoisotopic_mass();
+        // The offset from the neutral mass of the ion to the prefix or suffix residue mass
+        let series: [(&PrimaryIonSeries, bool, Mass); 6] = [
+            (&model.a, true, carbon_monoxide),
+            (&model.b, true, da(0.0)),
+            (&model.c, true, -ammonia),
+            (
+                &model.x,
+                false,
+                hydrogen + hydrogen - water - carbon_monoxide,
+            ),
+            (&model.y, false, -water),
+            (&model.z, false, ammonia - water - hydrogen),
+        ];
+        let precursor_charge = crate::system::isize::Charge::new::<e>(
+            self.charge
+                .map_or(1, |c| isize::try_from(c.value).unwrap_or(isize::MAX)),
+        );
+        let max_intensity = self
+            .spectrum()
+            .map(|p| *p.intensity)
+            .fold(0.0, f64::max)
+            .max(f64::MIN_POSITIVE);
+
+        let mut nodes = Vec::new();
+        for peak in self.spectrum() {
+            let (low, high) = model.tolerance.bounds(peak.mz);
+            let mz_error = (high.value - low.value) / 2.0;
+            let score = *peak.intensity / max_intensity;
+            for (series, n_terminal, offset) in &series {
+                if series.location == Location::None {
+                    continue;
+                }
+                for charge in series.charge_range.charges_iter(precursor_charge) {
+                    let z = charge.value as f64;
+                    let neutral = da((peak.mz.value - proton.value) * z);
+                    let residues = neutral + *offset;
+                    let mass = if *n_terminal {
+                        residues
+                    } else {
+                        total - residues
+                    };
+                    if mass.value > 0.0 && mass < total {
+                        nodes.push(Node {
+                            mass,
+                            error: mz_error * z,
+                            score,
+                        });
+                    }
+                }
+            }
+        }
+        nodes.sort_by(|a, b| a.mass.value.total_cmp(&b.mass.value));
+
+        // Merge nodes within their tolerance
+        let mut merged: Vec<Node> = Vec::with_capacity(nodes.len() + 2);
+        merged.push(Node {
+            mass: da(0.0),
+            error: 0.0,
+            score: 1.0,
+        });
+        for node in nodes {
+            let last = merged.len() - 1;
+            // The N terminus is never merged
+            if last > 0
+                && (node.mass - merged[last].mass).value <= node.error.max(merged[last].error)
+            {
+                let previous = merged[last];
+                let weight = previous.score + node.score;
+                merged[last] = Node {
+                    mass: da(previous
+                        .mass
+                        .value
+                        .mul_add(previous.score, node.mass.value * node.score)
+                        / weight),
+                    error: previous.error.max(node.error),
+                    score: weight,
+                };
+            } else {
+                merged.push(node);
+            }
+        }
+        merged.push(Node {
+            mass: total,
+            error: 0.0,
+            score: 1.0,
+        });
+        merged
+    }
+}
+
+/// All combinations (with repetition) of up to the given number of building blocks, sorted on
+/// mass. Every combination is given as the indices of the building blocks and the total mass.
+fn block_combinations(
+    building_blocks: &[(SequenceElement<SemiAmbiguous>, Mass)],
+    max: usize,
+) -> Vec<(Vec<usize>, Mass)> {
+    let mut combinations = (1..=max)
+        .flat_map(|size| (0..building_blocks.len()).combinations_with_replacement(size))
+        .map(|blocks| {
+            let mass = blocks.iter().map(|b| building_blocks[*b].1).sum::<Mass>();
+            (blocks, mass)
+        })
+        .filter(|(_, mass)| mass.value > 0.0)
+        .collect_vec();
+    combinations.sort_by(|a, b| a.1.value.total_cmp(&b.1.value));
+    combinations
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        building_blocks,
+        fragment::FragmentType,
+        spectrum::RawPeak,
+        system::{mz, MassOverCharge},
+        AminoAcid,
+    };
+
+    /// Create a spectrum with the b and y ions (singly charged) of the given peptide, without the
+    /// ions for the cleavages after the given number of residues
+    fn spectrum(peptide: &str, skip: &[usize]) -> RawSpectrum {
+        let peptide = Peptidoform::pro_forma(peptide, None)
+            .unwrap()
+            .into_linear()
+            .unwrap();
+        let model = Model::none()
+            .b(PrimaryIonSeries::default().location(Location::All))
+            .y(PrimaryIonSeries::default().location(Location::All));
+        let fragments = peptide
+            .generate_theoretical_fragments(crate::system::usize::Charge::new::<e>(1), &model);
+        let mut spectrum = RawSpectrum::default();
+        spectrum.mass = Some(peptide.formulas()[0].monoisotopic_mass());
+        spectrum.extend(
+            fragments
+                .iter()
+                .filter(|f| match &f.ion {
+                    FragmentType::b(p) => !skip.contains(&p.series_number),
+                    FragmentType::y(p) => !skip.contains(&(peptide.len() - p.series_number)),
+                    _ => false,
+                })
+                .map(|f| {
+                    RawPeak::new(
+                        MassOverCharge::new::<mz>(
+                            f.mz(crate::MassMode::Monoisotopic).unwrap().value,
+                        ),
+                        1.0,
+                    )
+                }),
+        );
+        spectrum
+    }
+
+    #[test]
+    fn complete_ladder() {
+        let spectrum = spectrum("PEPTIDEK", &[]);
+        let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+        let candidates = spectrum.de_novo(&blocks, &Model::cid_hcd(), &DeNovoSettings::default());
+        assert!(!candidates.is_empty());
+        let best = &candidates[0];
+        assert_eq!(best.peptide.to_string(), "PEPTJDEK");
+        assert_eq!(best.confidence.len(), 8);
+        assert!(best.confidence.iter().all(|c| (0.0..=1.0).contains(c)));
+        assert!(candidates.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!(candidates.len() <= 10);
+    }
+
+    #[test]
+    fn gaps() {
+        // Missing the fragments between the E and P
+        let spectrum = spectrum("PEPTIDEK", &[2]);
+        let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+        let candidates = spectrum.de_novo(&blocks, &Model::cid_hcd(), &DeNovoSettings::default());
+        let best = &candidates[0];
+        assert_eq!(best.peptide.len(), 8);
+        assert!(best.confidence[1] < best.confidence[4]);
+        assert!(
+            spectrum
+                .de_novo(
+                    &blocks,
+                    &Model::cid_hcd(),
+                    &DeNovoSettings::default().max_gap(1).max_candidates(3)
+                )
+                .len()
+                <= 3
+        );
+        assert!(RawSpectrum::default()
+            .de_novo(&blocks, &Model::cid_hcd(), &DeNovoSettings::default())
+            .is_empty());
+    }
+}
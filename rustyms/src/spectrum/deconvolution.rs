@@ -0,0 +1,318 @@
+//! Determine the precursor charge and monoisotopic mass from an MS1 spectrum
+
+use std::ops::RangeInclusive;
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spectrum::{raw::ISOTOPE_SPACING, PeakSpectrum},
+    system::{
+        da, e,
+        f64::{Mass, MassOverCharge},
+        mz,
+        usize::Charge,
+    },
+    Chemical, MolecularCharge, MolecularFormula, RawSpectrum, Tolerance, WithinTolerance,
+};
+
+/// The settings for precursor deconvolution, see [`RawSpectrum::deconvolute_precursor`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PrecursorDeconvolution {
+    /// The tolerance for matching the isotope peaks.
+    ///
+    /// Default: 10 ppm.
+    pub tolerance: Tolerance<MassOverCharge>,
+    /// The lowest charge that is considered.
+    ///
+    /// Default: 1.
+    pub min_charge: Charge,
+    /// The highest charge that is considered.
+    ///
+    /// Default: 6.
+    pub max_charge: Charge,
+    /// The isotope picking errors that are considered, given as the number of isotopes the
+    /// selected peak is above the monoisotopic peak. So an error of 1 means that the selected peak
+    /// is the second isotope peak, and an error of -1 means that the selected peak is found just
+    /// before the monoisotopic peak.
+    ///
+    /// Default: -1 to 1.
+    pub isotope_errors: RangeInclusive<isize>,
+    /// The minimal number of isotope peaks that have to be found.
+    ///
+    /// Default: 2.
+    pub min_isotopes: usize,
+}
+
+impl Default for PrecursorDeconvolution {
+    fn default() -> Self {
+        Self {
+            tolerance: Tolerance::new_ppm(10.0),
+            min_charge: Charge::new::<e>(1),
+            max_charge: Charge::new::<e>(6),
+            isotope_errors: -1..=1,
+            min_isotopes: 2,
+        }
+    }
+}
+
+impl PrecursorDeconvolution {
+    /// Set the tolerance
+    #[must_use]
+    pub fn tolerance(self, tolerance: Tolerance<MassOverCharge>) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Set the range of charges
+    #[must_use]
+    pub const fn charge_range(self, min_charge: Charge, max_charge: Charge) -> Self {
+        Self {
+            min_charge,
+            max_charge,
+            ..self
+        }
+    }
+
+    /// Set the isotope errors
+    #[must_use]
+    pub const fn isotope_errors(self, isotope_errors: RangeInclusive<isize>) -> Self {
+        Self {
+            isotope_errors,
+            ..self
+        }
+    }
+
+    /// Set the minimal number of isotope peaks
+    #[must_use]
+    pub const fn min_isotopes(self, min_isotopes: usize) -> Self {
+        Self {
+            min_isotopes,
+            ..self
+        }
+    }
+}
+
+/// A possible precursor as found by [`RawSpectrum::deconvolute_precursor`]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PrecursorCandidate {
+    /// The charge
+    pub charge: Charge,
+    /// The neutral monoisotopic mass
+    pub mass: Mass,
+    /// The m/z of the monoisotopic peak
+    pub mz: MassOverCharge,
+    /// The isotope picking error of the selected peak, the number of isotopes the selected peak is
+    /// above the monoisotopic peak
+    pub isotope_error: isize,
+    /// The number of isotope peaks that were found
+    pub isotopes: usize,
+    /// The score, between 0 and 1. This is the cosine similarity between the averagine isotope
+    /// distribution and the found isotope peaks (including the absence of a peak just before the
+    /// monoisotopic peak), multiplied by the fraction of the intensity in the isotope envelope
+    /// that is explained by the isotope peaks.
+    pub score: f64,
+}
+
+impl RawSpectrum {
+    /// Determine the most likely charges and monoisotopic masses for a precursor. This spectrum
+    /// should be the MS1 spectrum (or the isolation window slice of it) and the selected m/z the
+    /// m/z that was selected for fragmentation. For every charge and isotope error in the settings
+    /// the expected isotope envelope is compared to the peaks in this spectrum, using an averagine
+    /// model for the isotope distribution. This corrects for the instrument picking the second
+    /// isotope peak (isotope error 1) or a peak just before the monoisotopic peak (isotope error
+    /// -1) as the monoisotopic peak. The candidates are sorted on descending score, so the first
+    /// candidate is the most likely precursor.
+    ///
+    /// Only available with crate feature 'isotopes'.
+    /// ```rust
+    /// # use rustyms::{*, spectrum::*, system::{e, mz, MassOverCharge}};
+    /// # let ms1 = RawSpectrum::default();
+    /// # let mut ms2 = RawSpectrum::default();
+    /// let candidates =
+    ///     ms1.deconvolute_precursor(MassOverCharge::new::<mz>(500.0), &PrecursorDeconvolution::default());
+    /// if let Some(best) = candidates.first() {
+    ///     ms2.mass = Some(best.mass);
+    ///     ms2.charge = Some(best.charge);
+    /// }
+    /// ```
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn deconvolute_precursor(
+        &self,
+        selected: MassOverCharge,
+        settings: &PrecursorDeconvolution,
+    ) -> Vec<PrecursorCandidate> {
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        let mut candidates = Vec::new();
+        for charge in settings.min_charge.value.max(1)..=settings.max_charge.value {
+            let spacing = ISOTOPE_SPACING / charge as f64;
+            for isotope_error in settings.isotope_errors.clone() {
+                let monoisotopic = (isotope_error as f64).mul_add(-spacing, selected.value);
+                let mass = (monoisotopic - proton) * charge as f64;
+                if mass <= 0.0 {
+                    continue;
+                }
+                let distribution =
+                    MolecularFormula::averagine(da(mass)).isotopic_distribution(0.001);
+                let max = distribution.iter().copied().fold(0.0, f64::max);
+                let length = distribution
+                    .iter()
+                    .rposition(|a| *a >= max * 0.05)
+                    .map_or(1, |i| i + 1);
+                // The first position is the peak just before the monoisotopic peak, which should
+                // not be present
+                let expected = std::iter::once(0.0)
+                    .chain(distribution.iter().take(length).copied())
+                    .collect_vec();
+                let observed = (-1..length as isize)
+                    .map(|i| {
+                        self.isotope_intensity(
+                            (i as f64).mul_add(spacing, monoisotopic),
+                            &settings.tolerance,
+                        )
+                    })
+                    .collect_vec();
+                let isotopes = observed.iter().skip(1).filter(|i| **i > 0.0).count();
+                if isotopes < settings.min_isotopes.max(1) {
+                    continue;
+                }
+                let dot: f64 = expected.iter().zip(&observed).map(|(a, b)| a * b).sum();
+                let norm = expected.iter().map(|a| a * a).sum::<f64>().sqrt()
+                    * observed.iter().map(|b| b * b).sum::<f64>().sqrt();
+                let envelope = self.envelope_intensity(
+                    monoisotopic - spacing,
+                    ((length - 1) as f64).mul_add(spacing, monoisotopic),
+                    &settings.tolerance,
+                );
+                let explained = observed.iter().skip(1).sum::<f64>() / envelope;
+                candidates.push(PrecursorCandidate {
+                    charge: Charge::new::<e>(charge),
+                    mass: da(mass),
+                    mz: MassOverCharge::new::<mz>(monoisotopic),
+                    isotope_error,
+                    isotopes,
+                    score: dot / norm * explained.min(1.0),
+                });
+            }
+        }
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates
+    }
+
+    /// The intensity of the most intense peak within the tolerance of the given m/z, or 0 if
+    /// there is no such peak
+    fn isotope_intensity(&self, value: f64, tolerance: &Tolerance<MassOverCharge>) -> f64 {
+        let value = MassOverCharge::new::<mz>(value);
+        let (low, high) = tolerance.bounds(value);
+        self.binary_search(low, high)
+            .iter()
+            .filter(|p| tolerance.within(&p.mz, &value))
+            .map(|p| *p.intensity)
+            .fold(0.0, f64::max)
+    }
+
+    /// The total intensity of all peaks between the given m/z values (extended by the tolerance)
+    fn envelope_intensity(
+        &self,
+        start: f64,
+        end: f64,
+        tolerance: &Tolerance<MassOverCharge>,
+    ) -> f64 {
+        let low = tolerance.bounds(MassOverCharge::new::<mz>(start)).0;
+        let high = tolerance.bounds(MassOverCharge::new::<mz>(end)).1;
+        self.binary_search(low, high)
+            .iter()
+            .filter(|p| p.mz >= low && p.mz <= high)
+            .map(|p| *p.intensity)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{spectrum::RawPeak, Peptidoform};
+
+    /// Create an MS1 spectrum with the isotope envelope of the given peptide at the given charge
+    fn envelope(peptide: &str, charge: usize, extra: &[(f64, f64)]) -> (RawSpectrum, Mass) {
+        let formula = Peptidoform::pro_forma(peptide, None)
+            .unwrap()
+            .into_linear()
+            .unwrap()
+            .formulas()
+            .to_vec()
+            .pop()
+            .unwrap();
+        let mass = formula.monoisotopic_mass();
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend(
+            formula
+                .isotopic_distribution(0.001)
+                .iter()
+                .enumerate()
+                .map(|(i, a)| {
+                    (
+                        (i as f64).mul_add(ISOTOPE_SPACING, mass.value) / charge as f64 + proton,
+                        a * 1000.0,
+                    )
+                })
+                .chain(extra.iter().copied())
+                .map(|(m, i)| RawPeak::new(MassOverCharge::new::<mz>(m), i)),
+        );
+        (spectrum, mass)
+    }
+
+    #[test]
+    fn charge_state() {
+        for charge in 1..=4 {
+            let (spectrum, mass) = envelope("EM[Oxidation]EVEESPEKPEPTIDEK", charge, &[]);
+            let monoisotopic = spectrum.spectrum().next().unwrap().mz;
+            let candidates =
+                spectrum.deconvolute_precursor(monoisotopic, &PrecursorDeconvolution::default());
+            let best = candidates[0];
+            assert_eq!(best.charge.value, charge, "{candidates:?}");
+            assert_eq!(best.isotope_error, 0);
+            assert!((best.mass - mass).value.abs() < 0.01);
+            assert!(best.score > 0.9);
+        }
+    }
+
+    #[test]
+    fn isotope_error() {
+        // The second isotope peak was selected
+        let (spectrum, mass) = envelope("WFWHWYWMWHWRWK", 2, &[]);
+        let second = spectrum.spectrum().nth(1).unwrap().mz;
+        let best = spectrum.deconvolute_precursor(second, &PrecursorDeconvolution::default())[0];
+        assert_eq!(best.charge.value, 2);
+        assert_eq!(best.isotope_error, 1);
+        assert!((best.mass - mass).value.abs() < 0.01);
+        // A small peak just before the monoisotopic peak was selected
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        let before = (mass.value - ISOTOPE_SPACING) / 2.0 + proton;
+        let (spectrum, mass) = envelope("WFWHWYWMWHWRWK", 2, &[(before, 20.0)]);
+        let best = spectrum.deconvolute_precursor(
+            MassOverCharge::new::<mz>(before),
+            &PrecursorDeconvolution::default(),
+        )[0];
+        assert_eq!(best.charge.value, 2);
+        assert_eq!(best.isotope_error, -1);
+        assert!((best.mass - mass).value.abs() < 0.01);
+        // Nothing found
+        assert!(RawSpectrum::default()
+            .deconvolute_precursor(
+                MassOverCharge::new::<mz>(before),
+                &PrecursorDeconvolution::default()
+            )
+            .is_empty());
+    }
+}
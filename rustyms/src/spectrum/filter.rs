@@ -0,0 +1,198 @@
+//! Preprocessing of raw spectra before annotation
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    system::{f64::MassOverCharge, mz},
+    RawSpectrum, Tolerance,
+};
+
+/// A pipeline of filter steps that are applied in order to a raw spectrum before annotation. The
+/// default pipeline does not do anything.
+///
+/// ```rust
+/// # use rustyms::{spectrum::*, system::{mz, MassOverCharge}, *};
+/// let filter = SpectrumFilter::default()
+///     .remove_below(MassOverCharge::new::<mz>(150.0))
+///     .relative_intensity(0.01)
+///     .top_per_window(MassOverCharge::new::<mz>(100.0), 10);
+/// let mut spectrum = rawfile::mgf::open("data/example.mgf").unwrap().remove(0);
+/// filter.apply(&mut spectrum);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpectrumFilter {
+    /// The steps in this pipeline, applied in order
+    pub steps: Vec<FilterStep>,
+}
+
+/// A single preprocessing step for a raw spectrum
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FilterStep {
+    /// Remove all peaks with an intensity below the given threshold
+    AbsoluteIntensity(f64),
+    /// Remove all peaks with an intensity below the given fraction of the intensity of the most
+    /// intense peak
+    RelativeIntensity(f64),
+    /// Divide the spectrum in windows of the given size (starting at 0) and only keep the most
+    /// intense peaks in each window
+    TopPerWindow {
+        /// The size of each window
+        window: MassOverCharge,
+        /// The maximal number of peaks to keep per window
+        top: usize,
+    },
+    /// Limit the ratio between the most and least intense peak to the given number, by lowering
+    /// the intensity of the most intense peaks to the given number of times the intensity of the
+    /// least intense peak. This prevents a few very intense peaks (eg unfragmented precursor)
+    /// from dominating any intensity based scoring.
+    DynamicRange(f64),
+    /// Remove all peaks within the tolerance of the protonated precursor for all charge states up to the
+    /// precursor charge, based on the precursor mass and charge of the spectrum. If either is not
+    /// known this step does not do anything.
+    RemovePrecursor(Tolerance<MassOverCharge>),
+    /// Remove all peaks below the given mz, eg to remove the immonium ion region
+    RemoveBelow(MassOverCharge),
+}
+
+impl SpectrumFilter {
+    /// Add a step to this pipeline
+    #[must_use]
+    pub fn step(mut self, step: FilterStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Add an absolute intensity threshold, see [`FilterStep::AbsoluteIntensity`]
+    #[must_use]
+    pub fn absolute_intensity(self, threshold: f64) -> Self {
+        self.step(FilterStep::AbsoluteIntensity(threshold))
+    }
+
+    /// Add a relative intensity threshold, see [`FilterStep::RelativeIntensity`]
+    #[must_use]
+    pub fn relative_intensity(self, threshold: f64) -> Self {
+        self.step(FilterStep::RelativeIntensity(threshold))
+    }
+
+    /// Only keep the top peaks per window, see [`FilterStep::TopPerWindow`]
+    #[must_use]
+    pub fn top_per_window(self, window: MassOverCharge, top: usize) -> Self {
+        self.step(FilterStep::TopPerWindow { window, top })
+    }
+
+    /// Limit the dynamic range, see [`FilterStep::DynamicRange`]
+    #[must_use]
+    pub fn dynamic_range(self, ratio: f64) -> Self {
+        self.step(FilterStep::DynamicRange(ratio))
+    }
+
+    /// Remove the precursor peaks, see [`FilterStep::RemovePrecursor`]
+    #[must_use]
+    pub fn remove_precursor(self, tolerance: Tolerance<MassOverCharge>) -> Self {
+        self.step(FilterStep::RemovePrecursor(tolerance))
+    }
+
+    /// Remove all peaks below the given mz, see [`FilterStep::RemoveBelow`]
+    #[must_use]
+    pub fn remove_below(self, threshold: MassOverCharge) -> Self {
+        self.step(FilterStep::RemoveBelow(threshold))
+    }
+
+    /// Apply all steps, in order, to the given spectrum
+    pub fn apply(&self, spectrum: &mut RawSpectrum) {
+        for step in &self.steps {
+            step.apply(spectrum);
+        }
+    }
+}
+
+impl FilterStep {
+    /// Apply this step to the given spectrum
+    pub fn apply(&self, spectrum: &mut RawSpectrum) {
+        match self {
+            Self::AbsoluteIntensity(threshold) => spectrum.absolute_noise_filter(*threshold),
+            Self::RelativeIntensity(threshold) => spectrum.relative_noise_filter(*threshold),
+            Self::TopPerWindow { window, top } => {
+                spectrum.top_x_intensity_filter(window.get::<mz>(), *top);
+            }
+            Self::DynamicRange(ratio) => spectrum.dynamic_range_filter(*ratio),
+            Self::RemovePrecursor(tolerance) => spectrum.precursor_filter(*tolerance),
+            Self::RemoveBelow(threshold) => spectrum.low_mz_filter(*threshold),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp, clippy::missing_panics_doc)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        spectrum::{test_spectrum, PeakSpectrum},
+        system::{dalton, e, usize::Charge, Mass},
+    };
+
+    fn mzs(spectrum: &RawSpectrum) -> Vec<f64> {
+        spectrum.spectrum().map(|p| p.mz.value).collect_vec()
+    }
+
+    #[test]
+    fn steps() {
+        let peaks = [
+            (110.0, 50.0),
+            (150.0, 1.0),
+            (180.0, 20.0),
+            (190.0, 30.0),
+            (250.0, 10.0),
+            (501.007_276, 1000.0),
+        ];
+
+        let mut filtered = test_spectrum(&peaks);
+        SpectrumFilter::default()
+            .remove_below(MassOverCharge::new::<mz>(120.0))
+            .apply(&mut filtered);
+        assert_eq!(mzs(&filtered)[0], 150.0);
+
+        let mut filtered = test_spectrum(&peaks);
+        SpectrumFilter::default()
+            .absolute_intensity(5.0)
+            .relative_intensity(0.02)
+            .apply(&mut filtered);
+        assert_eq!(mzs(&filtered), [110.0, 180.0, 190.0, 501.007_276]);
+
+        let mut filtered = test_spectrum(&peaks);
+        SpectrumFilter::default()
+            .top_per_window(MassOverCharge::new::<mz>(100.0), 2)
+            .apply(&mut filtered);
+        assert_eq!(mzs(&filtered), [110.0, 190.0, 250.0, 501.007_276]);
+
+        let mut filtered = test_spectrum(&peaks);
+        filtered.top_x_filter(100.0, 2);
+        assert_eq!(
+            mzs(&filtered)
+                .into_iter()
+                .sorted_by(f64::total_cmp)
+                .collect_vec(),
+            [180.0, 190.0, 250.0, 501.007_276]
+        );
+
+        let mut filtered = test_spectrum(&peaks);
+        SpectrumFilter::default()
+            .dynamic_range(100.0)
+            .apply(&mut filtered);
+        assert_eq!(
+            filtered.spectrum().map(|p| *p.intensity).reduce(f64::max),
+            Some(100.0)
+        );
+
+        let mut filtered = test_spectrum(&peaks);
+        filtered.mass = Some(Mass::new::<dalton>(1000.0));
+        filtered.charge = Some(Charge::new::<e>(2));
+        SpectrumFilter::default()
+            .remove_precursor(Tolerance::new_ppm(10.0))
+            .apply(&mut filtered);
+        assert_eq!(mzs(&filtered), [110.0, 150.0, 180.0, 190.0, 250.0]);
+        assert_eq!(SpectrumFilter::default().steps.len(), 0);
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{system::MassOverCharge, CompoundPeptidoformIon, Fragment, MassMode, Model};
+use crate::{
+    system::{ratio::ppm, MassOverCharge, Ratio},
+    CompoundPeptidoformIon, Fragment, MassMode, Model, Tolerance,
+};
 
 use super::AnnotatedSpectrum;
 
@@ -21,7 +24,8 @@ pub trait AnnotatableSpectrum {
     fn search(&self, query: MassOverCharge, tolerance: Self::Tolerance) -> Option<usize>;
 
     /// Annotate this spectrum with the given peptidoform and given fragments see
-    /// [`crate::CompoundPeptidoform::generate_theoretical_fragments`].
+    /// [`crate::CompoundPeptidoform::generate_theoretical_fragments`]. This does not record the
+    /// deviation of the matches, use [`annotate`] for that.
     fn annotate(
         &self,
         peptide: CompoundPeptidoformIon,
@@ -49,3 +53,158 @@ pub trait AnnotatableSpectrum {
         annotated
     }
 }
+
+/// Annotate a spectrum with the given theoretical fragments. Every fragment is matched to the
+/// closest peak within the tolerance, a single peak can be annotated by multiple fragments. The
+/// deviation of every match (experimental - theoretical) is stored in [`Fragment::deviation`],
+/// in ppm for relative tolerances and in m/z for absolute tolerances. Peaks without any matching
+/// fragment stay in the annotated spectrum as unmatched peaks, see
+/// [`AnnotatedSpectrum::unmatched_peaks`]. Fragments without a formula cannot be matched.
+///
+/// In contrast to [`AnnotatableSpectrum::annotate`] this does not use a [`Model`], so all given
+/// fragments are matched regardless of the mz range of the model.
+///
+/// ```rust
+/// # use rustyms::{*, spectrum::*, system::{usize::Charge, e}};
+/// let model = Model::all();
+/// let spectrum = &rawfile::mgf::open("data/annotated_example.mgf").unwrap()[0];
+/// let peptide = CompoundPeptidoformIon::pro_forma("VAEINPSNGGTTFNEKFKGGKATJ", None).unwrap();
+/// let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+/// let annotated = annotate(spectrum, peptide, &fragments, Tolerance::new_ppm(20.0), MassMode::Monoisotopic);
+/// assert!(annotated.matched_peaks().count() > 0);
+/// assert_eq!(
+///     annotated.matched_peaks().count() + annotated.unmatched_peaks().count(),
+///     annotated.spectrum().len()
+/// );
+/// ```
+pub fn annotate<S: AnnotatableSpectrum + ?Sized>(
+    spectrum: &S,
+    peptide: CompoundPeptidoformIon,
+    theoretical_fragments: &[Fragment],
+    tolerance: Tolerance<MassOverCharge>,
+    mode: MassMode,
+) -> AnnotatedSpectrum {
+    let search_tolerance = tolerance.into();
+    let mut annotated = spectrum.empty_annotated(peptide);
+
+    for fragment in theoretical_fragments {
+        if let Some(mz) = fragment.mz(mode) {
+            // Get the index of the element closest to this value
+            if let Some(index) = spectrum.search(mz, search_tolerance) {
+                let peak = &mut annotated.spectrum[index];
+                let difference = peak.experimental_mz - mz;
+                let deviation = match tolerance {
+                    Tolerance::Relative(_) => Tolerance::Relative(
+                        Ratio::new::<ppm>(difference.value / mz.value * 1e6).into(),
+                    ),
+                    Tolerance::Absolute(_) => Tolerance::Absolute(difference.into()),
+                };
+                peak.annotation.push(Fragment {
+                    deviation: Some(deviation),
+                    ..fragment.clone()
+                });
+            }
+        }
+    }
+
+    annotated
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        spectrum::{PeakSpectrum, RawPeak, RawSpectrum},
+        system::{e, mz, usize::Charge},
+    };
+
+    #[test]
+    fn deviations() {
+        let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDE", None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &Model::none());
+        let precursor = &fragments[0];
+        let theoretical = precursor.mz(MassMode::Monoisotopic).unwrap();
+
+        let mut spectrum = RawSpectrum::default();
+        assert_eq!(
+            annotate(
+                &spectrum,
+                peptide.clone(),
+                &fragments,
+                Tolerance::new_ppm(20.0),
+                MassMode::Monoisotopic,
+            )
+            .spectrum()
+            .len(),
+            0
+        );
+        spectrum.extend([RawPeak::new(theoretical * 1.000_01, 1.0)]);
+
+        let annotated = annotate(
+            &spectrum,
+            peptide.clone(),
+            &fragments,
+            Tolerance::new_ppm(20.0),
+            MassMode::Monoisotopic,
+        );
+        assert_eq!(annotated.unmatched_peaks().count(), 0);
+        let Some(Tolerance::Relative(deviation)) = annotated[0].annotation[0].deviation else {
+            panic!("Expected a relative deviation")
+        };
+        assert!((deviation.get::<ppm>() - 10.0).abs() < 1e-6);
+
+        let annotated = annotate(
+            &spectrum,
+            peptide.clone(),
+            &fragments,
+            Tolerance::new_absolute(MassOverCharge::new::<mz>(0.1)),
+            MassMode::Monoisotopic,
+        );
+        let Some(Tolerance::Absolute(deviation)) = annotated[0].annotation[0].deviation else {
+            panic!("Expected an absolute deviation")
+        };
+        assert!(deviation.value > 0.0);
+
+        let annotated = annotate(
+            &spectrum,
+            peptide,
+            &fragments,
+            Tolerance::new_ppm(5.0),
+            MassMode::Monoisotopic,
+        );
+        assert_eq!(annotated.matched_peaks().count(), 0);
+        assert_eq!(annotated.unmatched_peaks().count(), 1);
+    }
+
+    #[test]
+    fn ion_mobility() {
+        let peptide = CompoundPeptidoformIon::pro_forma("PEPTIDE", None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(1), &Model::none());
+        let theoretical = fragments[0].mz(MassMode::Monoisotopic).unwrap();
+
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend([
+            RawPeak::new(theoretical, 1.0).with_ion_mobility(Some(0.8)),
+            RawPeak::new(theoretical * 2.0, 1.0).with_ion_mobility(Some(1.4)),
+            RawPeak::new(theoretical * 3.0, 1.0),
+        ]);
+        let mut annotated = annotate(
+            &spectrum,
+            peptide,
+            &fragments,
+            Tolerance::new_ppm(20.0),
+            MassMode::Monoisotopic,
+        );
+        let matched = annotated.matched_peaks().collect::<Vec<_>>();
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].ion_mobility, Some(0.8));
+
+        annotated.ion_mobility_filter(..1.0);
+        assert_eq!(annotated.spectrum().len(), 2);
+        assert_eq!(annotated.matched_peaks().count(), 1);
+        assert!(annotated
+            .unmatched_peaks()
+            .all(|peak| peak.ion_mobility.is_none()));
+    }
+}
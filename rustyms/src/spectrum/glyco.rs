@@ -0,0 +1,214 @@
+//! Screen raw spectra for glycan oxonium ions
+
+use std::sync::OnceLock;
+
+use crate::{
+    fragment::DiagnosticPosition,
+    glycan::{BaseSugar, GlycanSubstituent, MonoSaccharide},
+    spectrum::PeakSpectrum,
+    system::f64::MassOverCharge,
+    Chemical, MolecularCharge, MolecularFormula, RawSpectrum, Tolerance,
+};
+
+/// An oxonium ion that was found in a spectrum, see [`RawSpectrum::glyco_triage`]
+#[derive(Clone, Debug, PartialEq)]
+pub struct OxoniumMatch {
+    /// The name of the oxonium ion, eg `HexNAc` or `Neu5Ac-H2O1`
+    pub name: &'static str,
+    /// The theoretical mz of the (singly protonated) oxonium ion
+    pub mz: MassOverCharge,
+    /// The intensity of the most intense peak within the tolerance
+    pub intensity: f64,
+}
+
+/// The result of screening a spectrum for glycan oxonium ions, see [`RawSpectrum::glyco_triage`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GlycoTriage {
+    /// All oxonium ions that were found, in the order of [`oxonium_ions`]
+    pub found: Vec<OxoniumMatch>,
+    /// The summed intensity of all found oxonium ions divided by the total ion current of the
+    /// spectrum
+    pub oxonium_intensity_fraction: f64,
+}
+
+impl GlycoTriage {
+    /// Check if this is likely a glycopeptide spectrum, defined as having the HexNAc oxonium ion
+    /// (204.087) and at least `min_ions` oxonium ions in total (including HexNAc). A `min_ions` of
+    /// 2 or 3 is common to gate glycopeptide searches.
+    pub fn is_glyco(&self, min_ions: usize) -> bool {
+        self.contains("HexNAc") && self.found.len() >= min_ions
+    }
+
+    /// Check if any sialic acid (`Neu5Ac` or `Neu5Gc`) oxonium ion was found
+    pub fn has_sialic_acid(&self) -> bool {
+        self.found.iter().any(|m| m.name.starts_with("Neu"))
+    }
+
+    /// Check if the oxonium ion with the given name was found
+    pub fn contains(&self, name: &str) -> bool {
+        self.found.iter().any(|m| m.name == name)
+    }
+}
+
+/// The oxonium ions that are screened for in [`RawSpectrum::glyco_triage`], as the name and the
+/// neutral formula, sorted on mass. These are the diagnostic ions of `Hex`, `HexNAc`, `Neu5Ac`, and
+/// `Neu5Gc` (see [`MonoSaccharide`]) with the common di- and trisaccharide oxonium ions.
+pub fn oxonium_ions() -> &'static [(String, MolecularFormula)] {
+    OXONIUM_IONS_CELL.get_or_init(|| {
+        let hex = MonoSaccharide::new(BaseSugar::Hexose(None), &[]).with_name("Hex");
+        let hex_nac = MonoSaccharide::new(BaseSugar::Hexose(None), &[GlycanSubstituent::NAcetyl])
+            .with_name("HexNAc");
+        let dhex = MonoSaccharide::new(BaseSugar::Hexose(None), &[GlycanSubstituent::Deoxy])
+            .with_name("dHex");
+        let acetyl_neuraminic = MonoSaccharide::new(
+            BaseSugar::Nonose,
+            &[
+                GlycanSubstituent::Amino,
+                GlycanSubstituent::Acetyl,
+                GlycanSubstituent::Acid,
+            ],
+        )
+        .with_name("Neu5Ac");
+        let glycolyl_neuraminic = MonoSaccharide::new(
+            BaseSugar::Nonose,
+            &[
+                GlycanSubstituent::Amino,
+                GlycanSubstituent::Glycolyl,
+                GlycanSubstituent::Acid,
+            ],
+        )
+        .with_name("Neu5Gc");
+        let mut ions: Vec<(String, MolecularFormula)> =
+            [&hex, &hex_nac, &acetyl_neuraminic, &glycolyl_neuraminic]
+                .into_iter()
+                .flat_map(|sugar| {
+                    sugar
+                        .diagnostic_ions(
+                            0,
+                            0,
+                            DiagnosticPosition::GlycanCompositional(sugar.clone(), None),
+                            true,
+                        )
+                        .into_iter()
+                        .map(move |fragment| {
+                            (
+                                format!(
+                                    "{sugar}{}",
+                                    fragment
+                                        .neutral_loss
+                                        .iter()
+                                        .map(ToString::to_string)
+                                        .collect::<String>()
+                                ),
+                                fragment.formula.unwrap_or_default(),
+                            )
+                        })
+                })
+                .collect();
+        ions.extend([
+            (
+                format!("{dhex}{hex_nac}"),
+                dhex.formula() + hex_nac.formula(),
+            ),
+            (format!("{hex}{hex_nac}"), hex.formula() + hex_nac.formula()),
+            (
+                format!("{hex}{hex_nac}{acetyl_neuraminic}"),
+                hex.formula() + hex_nac.formula() + acetyl_neuraminic.formula(),
+            ),
+        ]);
+        ions.sort_by(|a, b| {
+            a.1.monoisotopic_mass()
+                .value
+                .total_cmp(&b.1.monoisotopic_mass().value)
+        });
+        ions
+    })
+}
+
+static OXONIUM_IONS_CELL: OnceLock<Vec<(String, MolecularFormula)>> = OnceLock::new();
+
+impl RawSpectrum {
+    /// Screen this spectrum for the common glycan oxonium ions (see [`oxonium_ions`]), this is
+    /// cheap and can be used to decide if an expensive glycopeptide search is warranted for this
+    /// spectrum. All oxonium ions are assumed to be singly protonated.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, spectrum::*};
+    /// let spectrum = &rawfile::mgf::open("data/example.mgf").unwrap()[0];
+    /// let triage = spectrum.glyco_triage(Tolerance::new_ppm(20.0));
+    /// if triage.is_glyco(2) {
+    ///     // Run a glycopeptide search
+    /// }
+    /// ```
+    pub fn glyco_triage(&self, tolerance: Tolerance<MassOverCharge>) -> GlycoTriage {
+        let proton = MolecularCharge::proton(1).formula().monoisotopic_mass();
+        let found: Vec<OxoniumMatch> = oxonium_ions()
+            .iter()
+            .filter_map(|(name, formula)| {
+                let mz = MassOverCharge::new::<crate::system::mz>(
+                    (formula.monoisotopic_mass() + proton).value,
+                );
+                let (low, high) = tolerance.bounds(mz);
+                self.binary_search(low, high)
+                    .iter()
+                    .filter(|p| p.mz >= low && p.mz <= high)
+                    .map(|p| *p.intensity)
+                    .max_by(f64::total_cmp)
+                    .map(|intensity| OxoniumMatch {
+                        name: name.as_str(),
+                        mz,
+                        intensity,
+                    })
+            })
+            .collect();
+        let total: f64 = self.spectrum().map(|p| *p.intensity).sum();
+        let oxonium: f64 = found.iter().map(|m| m.intensity).sum();
+        GlycoTriage {
+            oxonium_intensity_fraction: if total > 0.0 { oxonium / total } else { 0.0 },
+            found,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::spectrum::RawPeak;
+
+    fn peak(mz: f64, intensity: f64) -> RawPeak {
+        RawPeak::new(MassOverCharge::new::<crate::system::mz>(mz), intensity)
+    }
+
+    #[test]
+    fn triage() {
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend([
+            peak(138.0550, 10.0),
+            peak(204.0867, 20.0),
+            peak(274.0921, 5.0),
+            peak(500.0, 65.0),
+        ]);
+        let triage = spectrum.glyco_triage(Tolerance::new_ppm(10.0));
+        assert_eq!(
+            triage.found.iter().map(|m| m.name).collect::<Vec<_>>(),
+            ["HexNAc-C1H6O3", "HexNAc", "Neu5Ac-H2O1"]
+        );
+        assert!(triage.is_glyco(3));
+        assert!(!triage.is_glyco(4));
+        assert!(triage.has_sialic_acid());
+        assert!((triage.oxonium_intensity_fraction - 0.35).abs() < 1e-10);
+
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend([peak(138.0550, 10.0), peak(366.1395, 20.0)]);
+        let triage = spectrum.glyco_triage(Tolerance::new_ppm(10.0));
+        assert_eq!(triage.found.len(), 2);
+        assert!(!triage.is_glyco(2));
+        assert!(!triage.has_sialic_acid());
+
+        assert_eq!(
+            RawSpectrum::default().glyco_triage(Tolerance::new_ppm(10.0)),
+            GlycoTriage::default()
+        );
+    }
+}
@@ -1,17 +1,45 @@
 //! Spectrum related code
 
 mod annotated;
+mod de_novo;
+#[cfg(feature = "isotopes")]
+mod deconvolution;
 mod fdr;
+mod filter;
 mod fragmentation;
+mod glyco;
 #[cfg(feature = "mzdata")]
 mod mzdata;
 mod peaks;
+mod processor;
+mod psm_scores;
 mod raw;
 mod scores;
+mod tags;
 
 pub use annotated::*;
+pub use de_novo::*;
+#[cfg(feature = "isotopes")]
+pub use deconvolution::*;
 pub use fdr::*;
+pub use filter::*;
 pub use fragmentation::*;
+pub use glyco::*;
 pub use peaks::*;
+pub use processor::*;
 pub use raw::*;
 pub use scores::*;
+pub use tags::*;
+
+/// Create a raw spectrum with the given (mz, intensity) peaks
+#[cfg(test)]
+fn test_spectrum(peaks: &[(f64, f64)]) -> RawSpectrum {
+    let mut spectrum = RawSpectrum::default();
+    spectrum.extend(peaks.iter().map(|(value, intensity)| {
+        RawPeak::new(
+            crate::system::MassOverCharge::new::<crate::system::mz>(*value),
+            *intensity,
+        )
+    }));
+    spectrum
+}
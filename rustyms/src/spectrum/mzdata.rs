@@ -1,8 +1,8 @@
 use mzdata::{prelude::*, spectrum::RefPeakDataLevel};
 
 use crate::{
-    spectrum::{AnnotatableSpectrum, AnnotatedPeak, AnnotatedSpectrum},
-    system::MassOverCharge,
+    spectrum::{AnnotatableSpectrum, AnnotatedPeak, AnnotatedSpectrum, RawPeak, RawSpectrum},
+    system::{time::min, MassOverCharge, Time},
     CompoundPeptidoformIon,
 };
 
@@ -16,25 +16,27 @@ impl<S: SpectrumLike> AnnotatableSpectrum for S {
             rt: None,
             charge: None,
             mass: None,
+            ion_mobility: self.ion_mobility(),
+            ccs: None,
             peptide,
             spectrum: match self.peaks() {
                 RefPeakDataLevel::Missing | RefPeakDataLevel::RawData(_) => Vec::new(),
                 RefPeakDataLevel::Centroid(data) => data
                     .iter()
                     .map(|p| {
-                        AnnotatedPeak::background(&super::RawPeak {
-                            mz: MassOverCharge::new::<crate::system::mz>(p.mz),
-                            intensity: ordered_float::OrderedFloat(f64::from(p.intensity)),
-                        })
+                        AnnotatedPeak::background(&RawPeak::new(
+                            MassOverCharge::new::<crate::system::mz>(p.mz),
+                            f64::from(p.intensity),
+                        ))
                     })
                     .collect(),
                 RefPeakDataLevel::Deconvoluted(data) => data
                     .iter()
                     .map(|p| {
-                        AnnotatedPeak::background(&super::RawPeak {
-                            mz: MassOverCharge::new::<crate::system::mz>(p.neutral_mass), // TODO: This is M (not MH+) which is not very well supported in the current matching
-                            intensity: ordered_float::OrderedFloat(f64::from(p.intensity)),
-                        })
+                        AnnotatedPeak::background(&RawPeak::new(
+                            MassOverCharge::new::<crate::system::mz>(p.neutral_mass), // TODO: This is M (not MH+) which is not very well supported in the current matching
+                            f64::from(p.intensity),
+                        ))
                     })
                     .collect(),
             },
@@ -62,3 +64,48 @@ impl From<crate::Tolerance<MassOverCharge>> for Tolerance {
         }
     }
 }
+
+/// Convert any mzdata spectrum into a raw spectrum. Profile data is kept as is, use
+/// [`RawSpectrum::centroid`] to turn it into a peak list before annotation. For deconvoluted
+/// spectra the neutral masses are used as mz. The ion mobility of the spectrum (eg for timsTOF
+/// data) is stored as precursor ion mobility, and if the raw data contains an ion mobility array
+/// this is stored on the peaks.
+impl<S: SpectrumLike> From<&S> for RawSpectrum {
+    fn from(value: &S) -> Self {
+        let mut spectrum = Self::default();
+        spectrum.title = value.id().to_string();
+        spectrum.num_scans = value.description().acquisition.scans.len() as u64;
+        spectrum.rt = Some(Time::new::<min>(value.start_time()));
+        spectrum.ion_mobility = value.ion_mobility();
+        let peak = |mz: f64, intensity: f32| {
+            RawPeak::new(
+                MassOverCharge::new::<crate::system::mz>(mz),
+                f64::from(intensity),
+            )
+        };
+        match value.peaks() {
+            RefPeakDataLevel::Missing => (),
+            RefPeakDataLevel::RawData(data) => {
+                if let (Ok(mzs), Ok(intensities)) = (data.mzs(), data.intensities()) {
+                    let mobilities = data.ion_mobility().ok().map(|(array, _)| array);
+                    spectrum.extend(mzs.iter().zip(intensities.iter()).enumerate().map(
+                        |(index, (mz, intensity))| {
+                            peak(*mz, *intensity).with_ion_mobility(
+                                mobilities
+                                    .as_ref()
+                                    .and_then(|array| array.get(index).copied()),
+                            )
+                        },
+                    ));
+                }
+            }
+            RefPeakDataLevel::Centroid(data) => {
+                spectrum.extend(data.iter().map(|p| peak(p.mz, p.intensity)));
+            }
+            RefPeakDataLevel::Deconvoluted(data) => {
+                spectrum.extend(data.iter().map(|p| peak(p.neutral_mass, p.intensity)));
+            }
+        }
+        spectrum
+    }
+}
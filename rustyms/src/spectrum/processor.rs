@@ -0,0 +1,231 @@
+//! Composable preprocessing of raw spectra
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    spectrum::{FilterStep, SpectrumFilter},
+    system::{f64::MassOverCharge, usize::Charge},
+    RawSpectrum, Tolerance,
+};
+
+/// A pipeline of preprocessing steps that are applied in order to raw spectra. This combines
+/// centroiding, denoising (see [`SpectrumFilter`]), deisotoping, and normalisation. As all raw
+/// file readers (MGF, and mzML, Thermo RAW, and others with the `mzdata` feature) result in
+/// [`RawSpectrum`]s the same processor can be used for all input formats. The default processor
+/// does not do anything.
+///
+/// ```rust
+/// # use rustyms::{spectrum::*, system::{e, mz, usize::Charge, MassOverCharge}, *};
+/// let processor = SpectrumProcessor::default()
+///     .filter(&SpectrumFilter::default().relative_intensity(0.01))
+///     .deisotope(Tolerance::new_ppm(20.0), Charge::new::<e>(4), true)
+///     .normalise(Normalisation::Maximum);
+/// let spectra = processor
+///     .process(rawfile::mgf::open("data/example.mgf").unwrap())
+///     .collect::<Vec<_>>();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct SpectrumProcessor {
+    /// The steps in this pipeline, applied in order
+    pub steps: Vec<ProcessingStep>,
+}
+
+/// A single step in a [`SpectrumProcessor`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ProcessingStep {
+    /// Centroid a profile mode spectrum with the given maximal gap, see
+    /// [`RawSpectrum::centroid`]
+    Centroid(MassOverCharge),
+    /// Filter the peaks, see [`FilterStep`]
+    Filter(FilterStep),
+    /// Deisotope the spectrum, see [`RawSpectrum::deisotope`]
+    Deisotope {
+        /// The tolerance for finding isotope peaks
+        tolerance: Tolerance<MassOverCharge>,
+        /// The highest charge that is considered
+        max_charge: Charge,
+        /// If the deisotoped peaks are moved to the m/z they would have with a single charge
+        single_charge: bool,
+    },
+    /// Normalise the intensities, see [`RawSpectrum::normalise`]
+    Normalise(Normalisation),
+}
+
+/// The ways of normalising the intensities of a spectrum, see [`RawSpectrum::normalise`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Normalisation {
+    /// Divide all intensities by the intensity of the most intense peak
+    Maximum,
+    /// Divide all intensities by the total intensity
+    Total,
+    /// Take the square root of all intensities, lowering the dominance of very intense peaks
+    SquareRoot,
+    /// Take the natural logarithm of all intensities plus one
+    Logarithm,
+}
+
+impl SpectrumProcessor {
+    /// Add a step to this pipeline
+    #[must_use]
+    pub fn step(mut self, step: ProcessingStep) -> Self {
+        self.steps.push(step);
+        self
+    }
+
+    /// Centroid the spectrum, see [`ProcessingStep::Centroid`]
+    #[must_use]
+    pub fn centroid(self, maximal_gap: MassOverCharge) -> Self {
+        self.step(ProcessingStep::Centroid(maximal_gap))
+    }
+
+    /// Add all steps from the given filter, see [`ProcessingStep::Filter`]
+    #[must_use]
+    pub fn filter(mut self, filter: &SpectrumFilter) -> Self {
+        self.steps
+            .extend(filter.steps.iter().cloned().map(ProcessingStep::Filter));
+        self
+    }
+
+    /// Deisotope the spectrum, see [`ProcessingStep::Deisotope`]
+    #[must_use]
+    pub fn deisotope(
+        self,
+        tolerance: Tolerance<MassOverCharge>,
+        max_charge: Charge,
+        single_charge: bool,
+    ) -> Self {
+        self.step(ProcessingStep::Deisotope {
+            tolerance,
+            max_charge,
+            single_charge,
+        })
+    }
+
+    /// Normalise the intensities, see [`ProcessingStep::Normalise`]
+    #[must_use]
+    pub fn normalise(self, normalisation: Normalisation) -> Self {
+        self.step(ProcessingStep::Normalise(normalisation))
+    }
+
+    /// Apply all steps, in order, to the given spectrum
+    pub fn apply(&self, spectrum: &mut RawSpectrum) {
+        for step in &self.steps {
+            step.apply(spectrum);
+        }
+    }
+
+    /// Apply all steps to all given spectra
+    pub fn process<'a>(
+        &'a self,
+        spectra: impl IntoIterator<Item = RawSpectrum> + 'a,
+    ) -> impl Iterator<Item = RawSpectrum> + 'a {
+        spectra.into_iter().map(|mut spectrum| {
+            self.apply(&mut spectrum);
+            spectrum
+        })
+    }
+}
+
+impl From<SpectrumFilter> for SpectrumProcessor {
+    fn from(value: SpectrumFilter) -> Self {
+        Self::default().filter(&value)
+    }
+}
+
+impl ProcessingStep {
+    /// Apply this step to the given spectrum
+    pub fn apply(&self, spectrum: &mut RawSpectrum) {
+        match self {
+            Self::Centroid(gap) => spectrum.centroid(*gap),
+            Self::Filter(step) => step.apply(spectrum),
+            Self::Deisotope {
+                tolerance,
+                max_charge,
+                single_charge,
+            } => spectrum.deisotope(*tolerance, *max_charge, *single_charge),
+            Self::Normalise(normalisation) => spectrum.normalise(*normalisation),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc, clippy::float_cmp)]
+mod tests {
+    use itertools::Itertools;
+
+    use super::*;
+    use crate::{
+        spectrum::{test_spectrum, PeakSpectrum},
+        system::e,
+    };
+
+    fn peaks(spectrum: &RawSpectrum) -> Vec<(f64, f64)> {
+        spectrum
+            .spectrum()
+            .map(|p| (p.mz.value, *p.intensity))
+            .collect_vec()
+    }
+
+    #[test]
+    fn deisotope() {
+        let raw = test_spectrum(&[
+            // Singly charged envelope
+            (300.0, 100.0),
+            (301.003_355, 50.0),
+            (302.006_71, 10.0),
+            // Doubly charged envelope
+            (500.0, 100.0),
+            (500.501_677_5, 120.0),
+            (501.003_355, 40.0),
+            // Lone peak
+            (700.0, 30.0),
+        ]);
+        let mut deisotoped = raw.clone();
+        deisotoped.deisotope(Tolerance::new_ppm(10.0), Charge::new::<e>(3), false);
+        assert_eq!(
+            peaks(&deisotoped),
+            [(300.0, 160.0), (500.0, 260.0), (700.0, 30.0)]
+        );
+        let mut single = raw;
+        single.deisotope(Tolerance::new_ppm(10.0), Charge::new::<e>(3), true);
+        let single = peaks(&single);
+        assert_eq!(single.len(), 3);
+        assert!((single[0].0 - 300.0).abs() < 1e-9);
+        assert!((single[1].0 - 700.0).abs() < 1e-9);
+        assert!((single[2].0 - 998.992_724).abs() < 0.001);
+    }
+
+    #[test]
+    fn pipeline() {
+        let raw = test_spectrum(&[
+            (100.0, 4.0),
+            (200.0, 16.0),
+            (201.003_355, 4.0),
+            (300.0, 0.5),
+        ]);
+        let processor = SpectrumProcessor::default()
+            .filter(&SpectrumFilter::default().absolute_intensity(1.0))
+            .deisotope(Tolerance::new_ppm(10.0), Charge::new::<e>(1), false)
+            .normalise(Normalisation::Maximum);
+        let processed = processor.process([raw.clone()]).collect_vec();
+        assert_eq!(peaks(&processed[0]), [(100.0, 0.2), (200.0, 1.0)]);
+
+        let mut normalised = raw.clone();
+        normalised.normalise(Normalisation::Total);
+        assert!((peaks(&normalised).iter().map(|p| p.1).sum::<f64>() - 1.0).abs() < 1e-9);
+        let mut normalised = raw.clone();
+        normalised.normalise(Normalisation::SquareRoot);
+        assert_eq!(peaks(&normalised)[1].1, 4.0);
+        let mut normalised = raw.clone();
+        normalised.normalise(Normalisation::Logarithm);
+        assert!((peaks(&normalised)[0].1 - 5.0_f64.ln()).abs() < 1e-9);
+
+        let mut filtered = raw.clone();
+        SpectrumProcessor::from(SpectrumFilter::default().absolute_intensity(1.0))
+            .apply(&mut filtered);
+        assert_eq!(filtered.spectrum().len(), 3);
+        let mut unchanged = raw.clone();
+        SpectrumProcessor::default().apply(&mut unchanged);
+        assert_eq!(unchanged, raw);
+    }
+}
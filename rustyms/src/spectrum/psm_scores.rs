@@ -0,0 +1,289 @@
+//! Peptide spectrum match scores, to rank candidate peptidoforms for a spectrum
+
+use std::collections::HashSet;
+
+use itertools::Itertools;
+
+use crate::{
+    fragment::{Fragment, FragmentKind},
+    spectrum::{AnnotatedSpectrum, Recovered},
+};
+
+/// The maximal number of peaks per 100 m/z window that is tried for the binomial score
+const BINOMIAL_MAX_PEAKS_PER_WINDOW: usize = 12;
+
+impl AnnotatedSpectrum {
+    /// The X!Tandem hyperscore for this annotated spectrum. This is defined as the log10 of the
+    /// sum of the intensities of all peaks annotated as b or y ion (normalised to the most intense
+    /// peak being 100), multiplied by the factorial of the number of found b ions and the
+    /// factorial of the number of found y ions. Only fragments without neutral losses are
+    /// considered. If no b or y ions are found the score is 0.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, spectrum::AnnotatableSpectrum, system::{usize::Charge, e}};
+    /// let model = Model::all();
+    /// let spectrum = &rawfile::mgf::open("data/annotated_example.mgf").unwrap()[0];
+    /// let peptide = CompoundPeptidoformIon::pro_forma("VAEINPSNGGTTFNEKFKGGKATJ", None).unwrap();
+    /// let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+    /// let annotated = spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic);
+    /// assert!(annotated.hyperscore() > 0.0);
+    /// assert!(annotated.intensity_explained() > 0.0);
+    /// ```
+    pub fn hyperscore(&self) -> f64 {
+        let max_intensity = self
+            .spectrum
+            .iter()
+            .map(|p| *p.intensity)
+            .fold(0.0, f64::max);
+        if max_intensity == 0.0 {
+            return 0.0;
+        }
+        let is_b_or_y = |f: &&Fragment| {
+            f.neutral_loss.is_empty() && matches!(f.ion.kind(), FragmentKind::b | FragmentKind::y)
+        };
+        let intensity: f64 = self
+            .spectrum
+            .iter()
+            .filter(|p| p.annotation.iter().any(|f| is_b_or_y(&f)))
+            .map(|p| *p.intensity / max_intensity * 100.0)
+            .sum();
+        let count = |kind: FragmentKind| {
+            self.spectrum
+                .iter()
+                .flat_map(|p| &p.annotation)
+                .filter(|f| is_b_or_y(f) && f.ion.kind() == kind)
+                .map(|f| {
+                    (
+                        &f.ion,
+                        f.charge,
+                        f.peptidoform_ion_index,
+                        f.peptidoform_index,
+                    )
+                })
+                .unique()
+                .count()
+        };
+        if intensity == 0.0 {
+            0.0
+        } else {
+            intensity.log10()
+                + (ln_factorial(count(FragmentKind::b)) + ln_factorial(count(FragmentKind::y)))
+                    / std::f64::consts::LN_10
+        }
+    }
+
+    /// An Andromeda like binomial score. For every number of peaks `q` (1 to 12) the spectrum is
+    /// filtered to only keep the `q` most intense peaks per 100 m/z window. The probability of
+    /// finding at least the number of matched peaks at random is then calculated with a
+    /// binomial distribution with the number of theoretical fragments as number of trials and
+    /// `q / 100` as the probability of a random match. The score is the highest `-10 log10` of
+    /// this probability over all `q`.
+    ///
+    /// The theoretical fragments should be the same fragments as used for annotating this
+    /// spectrum, and should be limited to the m/z range of the spectrum to give a meaningful
+    /// number of trials.
+    pub fn binomial_score(&self, theoretical_fragments: &[Fragment]) -> f64 {
+        let trials = theoretical_fragments.len();
+        if trials == 0 {
+            return 0.0;
+        }
+        let ln_factorials = (0..=trials).map(ln_factorial).collect_vec();
+        // For every peak the rank in intensity within its 100 m/z window (0 is most intense)
+        let mut windows: Vec<(i64, f64, usize)> = self
+            .spectrum
+            .iter()
+            .enumerate()
+            .map(|(index, p)| {
+                (
+                    (p.experimental_mz.value / 100.0).floor() as i64,
+                    *p.intensity,
+                    index,
+                )
+            })
+            .collect();
+        windows.sort_unstable_by(|a, b| a.0.cmp(&b.0).then(b.1.total_cmp(&a.1)));
+        let mut rank = vec![0; self.spectrum.len()];
+        for (_, group) in &windows.iter().chunk_by(|w| w.0) {
+            for (r, (_, _, index)) in group.enumerate() {
+                rank[*index] = r;
+            }
+        }
+
+        (1..=BINOMIAL_MAX_PEAKS_PER_WINDOW)
+            .map(|q| {
+                let found = self
+                    .spectrum
+                    .iter()
+                    .zip(&rank)
+                    .filter(|(p, r)| **r < q && !p.annotation.is_empty())
+                    .count()
+                    .min(trials);
+                let p = q as f64 / 100.0;
+                let probability: f64 = (found..=trials)
+                    .map(|k| {
+                        ((trials - k) as f64)
+                            .mul_add(
+                                (1.0 - p).ln(),
+                                (k as f64).mul_add(
+                                    p.ln(),
+                                    ln_factorials[trials]
+                                        - ln_factorials[k]
+                                        - ln_factorials[trials - k],
+                                ),
+                            )
+                            .exp()
+                    })
+                    .sum();
+                -10.0 * probability.min(1.0).log10()
+            })
+            .fold(0.0, f64::max)
+    }
+
+    /// The fraction of the total intensity that is annotated, 0 for an empty spectrum
+    pub fn intensity_explained(&self) -> f64 {
+        let (annotated, total) = self
+            .spectrum
+            .iter()
+            .fold((0.0, 0.0), |(annotated, total), p| {
+                (
+                    annotated
+                        + if p.annotation.is_empty() {
+                            0.0
+                        } else {
+                            *p.intensity
+                        },
+                    total + *p.intensity,
+                )
+            });
+        if total == 0.0 {
+            0.0
+        } else {
+            annotated / total
+        }
+    }
+
+    /// The sequence coverage for every backbone ion series (a, b, c, x, y, and z) for the given
+    /// peptidoform. This is the number of positions in the peptide that are covered by at least
+    /// one fragment of that series, out of the total number of positions in the peptide. Returns
+    /// an empty list if the peptidoform does not exist.
+    pub fn ion_series_coverage(
+        &self,
+        peptidoform_ion_index: usize,
+        peptidoform_index: usize,
+    ) -> Vec<(FragmentKind, Recovered<u32>)> {
+        let Some(peptidoform) = self
+            .peptide
+            .peptidoform_ions()
+            .get(peptidoform_ion_index)
+            .and_then(|p| p.peptidoforms().get(peptidoform_index))
+        else {
+            return Vec::new();
+        };
+        [
+            FragmentKind::a,
+            FragmentKind::b,
+            FragmentKind::c,
+            FragmentKind::x,
+            FragmentKind::y,
+            FragmentKind::z,
+        ]
+        .into_iter()
+        .map(|kind| {
+            let covered = self
+                .spectrum
+                .iter()
+                .flat_map(|p| &p.annotation)
+                .filter(|f| {
+                    f.ion.kind() == kind
+                        && f.peptidoform_ion_index == Some(peptidoform_ion_index)
+                        && f.peptidoform_index == Some(peptidoform_index)
+                })
+                .filter_map(|f| f.ion.position().map(|p| p.sequence_index))
+                .collect::<HashSet<_>>()
+                .len();
+            (
+                kind,
+                Recovered {
+                    found: covered as u32,
+                    total: peptidoform.len() as u32,
+                },
+            )
+        })
+        .collect()
+    }
+}
+
+/// The natural log of the factorial of the given number
+fn ln_factorial(n: usize) -> f64 {
+    (2..=n).map(|i| (i as f64).ln()).sum()
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        spectrum::AnnotatableSpectrum,
+        system::{e, usize::Charge},
+        CompoundPeptidoformIon, MassMode, Model,
+    };
+
+    #[test]
+    fn rank_candidates() {
+        let model = Model::all();
+        let spectrum = crate::rawfile::mgf::open("data/annotated_example.mgf")
+            .unwrap()
+            .remove(0);
+        let score = |sequence: &str| {
+            let peptide = CompoundPeptidoformIon::pro_forma(sequence, None).unwrap();
+            let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+            let annotated = spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic);
+            let fragments = fragments
+                .into_iter()
+                .filter(|f| {
+                    f.mz(MassMode::Monoisotopic)
+                        .is_some_and(|mz| model.mz_range.contains(&mz))
+                })
+                .collect::<Vec<_>>();
+            (
+                annotated.hyperscore(),
+                annotated.binomial_score(&fragments),
+                annotated.intensity_explained(),
+                annotated.ion_series_coverage(0, 0),
+            )
+        };
+        let correct = score("VAEINPSNGGTTFNEKFKGGKATJ");
+        let wrong = score("JTAKGGKFKENFTTGGNSPNIEAV");
+        assert!(correct.0 > wrong.0, "{} > {}", correct.0, wrong.0);
+        assert!(correct.1 > wrong.1, "{} > {}", correct.1, wrong.1);
+        assert!(correct.2 > wrong.2, "{} > {}", correct.2, wrong.2);
+        assert!((0.0..=1.0).contains(&correct.2));
+        let coverage = |c: &[(FragmentKind, Recovered<u32>)], kind| {
+            c.iter().find(|(k, _)| *k == kind).unwrap().1.found
+        };
+        assert!(coverage(&correct.3, FragmentKind::y) > coverage(&wrong.3, FragmentKind::y));
+        assert_eq!(correct.3.len(), 6);
+        assert!(correct
+            .3
+            .iter()
+            .all(|(_, r)| r.found <= r.total && r.total == 24));
+    }
+
+    #[test]
+    fn binomial_counts_peaks() {
+        let model = Model::all();
+        let spectrum = crate::rawfile::mgf::open("data/annotated_example.mgf")
+            .unwrap()
+            .remove(0);
+        let peptide = CompoundPeptidoformIon::pro_forma("VAEINPSNGGTTFNEKFKGGKATJ", None).unwrap();
+        let fragments = peptide.generate_theoretical_fragments(Charge::new::<e>(4), &model);
+        let mut annotated = spectrum.annotate(peptide, &fragments, &model, MassMode::Monoisotopic);
+        let score = annotated.binomial_score(&fragments);
+        // A peak annotated with multiple fragments is still only a single matched peak
+        for peak in &mut annotated.spectrum {
+            let annotation = peak.annotation.clone();
+            peak.annotation.extend(annotation);
+        }
+        assert!((annotated.binomial_score(&fragments) - score).abs() < 1e-10);
+    }
+}
@@ -1,20 +1,25 @@
 //! Raw spectra (not annotated)
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, ops::RangeBounds, path::PathBuf};
 
 use itertools::Itertools;
 use ordered_float::OrderedFloat;
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    spectrum::{AnnotatableSpectrum, AnnotatedPeak, PeakSpectrum},
+    spectrum::{AnnotatableSpectrum, AnnotatedPeak, Normalisation, PeakSpectrum},
     system::{
         f64::{Mass, MassOverCharge, Ratio, Time},
         usize::Charge,
     },
-    AnnotatedSpectrum, CompoundPeptidoformIon, Tolerance, WithinTolerance,
+    AnnotatedSpectrum, Chemical, CompoundPeptidoformIon, MolecularCharge, Tolerance,
+    WithinTolerance,
 };
 
+/// The mass difference between consecutive isotope peaks, the mass difference between
+/// <sup>13</sup>C and <sup>12</sup>C
+pub const ISOTOPE_SPACING: f64 = 1.003_355;
+
 /// A raw spectrum (meaning not annotated yet)
 #[derive(Default, Clone, PartialEq, PartialOrd, Debug, Serialize, Deserialize)]
 pub struct RawSpectrum {
@@ -30,12 +35,16 @@ pub struct RawSpectrum {
     pub mass: Option<Mass>,
     /// The found precursor intensity
     pub intensity: Option<f64>,
+    /// The found precursor ion mobility, for timsTOF data this is the inverse reduced mobility (1/K0 in V·s/cm²)
+    pub ion_mobility: Option<f64>,
+    /// The found precursor collisional cross section (in Å²)
+    pub ccs: Option<f64>,
     /// The peaks of which this spectrum consists
     spectrum: Vec<RawPeak>,
     /// MGF: if present the SEQUENCE line
     pub sequence: Option<String>,
     /// MGF TITLE: if present the raw file where this mgf was made from
-    pub raw_file: Option<String>,
+    pub raw_file: Option<PathBuf>,
     /// MGF TITLE: if present the raw file scan number
     pub raw_scan_number: Option<usize>,
     /// MGF TITLE: index number
@@ -77,9 +86,40 @@ impl RawSpectrum {
         self.spectrum.shrink_to_fit();
     }
 
+    /// Filter the spectrum to retain all peaks with an ion mobility within the given range. Peaks
+    /// without a known ion mobility are retained.
+    pub fn ion_mobility_filter(&mut self, range: impl RangeBounds<f64>) {
+        self.spectrum
+            .retain(|p| p.ion_mobility.map_or(true, |im| range.contains(&im)));
+        self.spectrum.shrink_to_fit();
+    }
+
     /// Filter a spectrum by dividing it in windows and within each window only retain the `top` number of peaks.
-    #[allow(clippy::missing_panics_doc)] // Cannot panic as it checks with peek first
+    /// Note that this retains the `top` peaks with the highest mz within each window, to retain
+    /// the most intense peaks instead use [`Self::top_x_intensity_filter`].
     pub fn top_x_filter(&mut self, window_size: f64, top: usize) {
+        self.spectrum =
+            self.windowed_top(window_size, top, |a, b| a.mz.value.total_cmp(&b.mz.value));
+    }
+
+    /// Filter a spectrum by dividing it in windows and within each window only retain the `top`
+    /// most intense peaks.
+    pub fn top_x_intensity_filter(&mut self, window_size: f64, top: usize) {
+        let mut new_spectrum =
+            self.windowed_top(window_size, top, |a, b| a.intensity.total_cmp(&b.intensity));
+        new_spectrum.sort_unstable();
+        self.spectrum = new_spectrum;
+    }
+
+    /// Divide the spectrum in windows and within each window retain the `top` largest peaks
+    /// according to the given comparison.
+    #[allow(clippy::missing_panics_doc)] // Cannot panic as it checks with peek first
+    fn windowed_top(
+        &self,
+        window_size: f64,
+        top: usize,
+        compare: impl Fn(&RawPeak, &RawPeak) -> Ordering,
+    ) -> Vec<RawPeak> {
         let mut new_spectrum = Vec::with_capacity(
             self.spectrum
                 .last()
@@ -99,17 +139,330 @@ impl RawSpectrum {
                     break;
                 }
             }
-            new_spectrum.extend(
-                peaks
-                    .iter()
-                    .cloned()
-                    .k_largest_by(top, |a, b| a.mz.value.total_cmp(&b.mz.value)),
-            );
+            new_spectrum.extend(peaks.iter().cloned().k_largest_by(top, &compare));
             peaks.clear();
             window += 1;
         }
+        new_spectrum
+    }
 
-        self.spectrum = new_spectrum;
+    /// Limit the ratio between the most and least intense peak to `ratio`, by lowering the
+    /// intensity of the most intense peaks to `ratio` times the intensity of the least intense
+    /// peak (ignoring peaks without intensity).
+    pub fn dynamic_range_filter(&mut self, ratio: f64) {
+        if let Some(minimum) = self
+            .spectrum
+            .iter()
+            .map(|p| *p.intensity)
+            .filter(|i| *i > 0.0)
+            .reduce(f64::min)
+        {
+            let maximum = OrderedFloat(minimum * ratio);
+            for peak in &mut self.spectrum {
+                peak.intensity = peak.intensity.min(maximum);
+            }
+        }
+    }
+
+    /// Remove all peaks within the tolerance of the (protonated) precursor for all charge states up
+    /// to the precursor charge. This does not do anything if the precursor mass or charge are not
+    /// known.
+    pub fn precursor_filter(&mut self, tolerance: Tolerance<MassOverCharge>) {
+        if let (Some(mass), Some(charge)) = (self.mass, self.charge) {
+            let precursors = (1..=isize::try_from(charge.value).unwrap_or(isize::MAX))
+                .map(|z| {
+                    MassOverCharge::new::<crate::system::mz>(
+                        (mass + MolecularCharge::proton(z).formula().monoisotopic_mass()).value
+                            / z as f64,
+                    )
+                })
+                .collect_vec();
+            self.spectrum
+                .retain(|p| !precursors.iter().any(|m| tolerance.within(m, &p.mz)));
+            self.spectrum.shrink_to_fit();
+        }
+    }
+
+    /// Remove all peaks below the given mz, eg to remove the immonium ion region
+    pub fn low_mz_filter(&mut self, threshold: MassOverCharge) {
+        self.spectrum.retain(|p| p.mz >= threshold);
+        self.spectrum.shrink_to_fit();
+    }
+
+    /// Centroid a profile mode spectrum. Every local maximum in intensity is turned into a single
+    /// peak with the apex intensity at the intensity weighted average mz of the surrounding points,
+    /// where the surrounding points are all points that have a monotonically decreasing intensity
+    /// going outward from the apex and that are at most `maximal_gap` away from the previous point.
+    /// A point in a valley between two maxima is only assigned to the lower mz peak. Points with an
+    /// intensity of zero (or less) are never part of a peak. Calling this on an already centroided
+    /// spectrum with a gap smaller than the distance between peaks leaves it unchanged.
+    pub fn centroid(&mut self, maximal_gap: MassOverCharge) {
+        self.spectrum = centroid_profile(&self.spectrum, maximal_gap);
+    }
+
+    /// Deisotope a centroided spectrum. Going from low to high m/z every peak is taken as a
+    /// possible monoisotopic peak, and for every charge from the highest to 1 the following
+    /// isotope peaks (spaced 1.003355/charge m/z apart, within the tolerance) are searched. The
+    /// first isotope peak can be up to twice as intense as the monoisotopic peak, all following
+    /// isotope peaks have to be less intense than the previous peak. For the highest charge with
+    /// at least one isotope peak the isotope peaks are removed and their intensity is added to the
+    /// monoisotopic peak. If `single_charge` is set the monoisotopic peaks are also moved to the
+    /// m/z they would have with a single charge (with a proton as charge carrier), this does not
+    /// change peaks without isotope peaks as their charge is not known.
+    pub fn deisotope(
+        &mut self,
+        tolerance: Tolerance<MassOverCharge>,
+        max_charge: Charge,
+        single_charge: bool,
+    ) {
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        let peaks = &self.spectrum;
+        let mut isotope = vec![false; peaks.len()];
+        let mut result = Vec::with_capacity(peaks.len());
+        for index in 0..peaks.len() {
+            if isotope[index] {
+                continue;
+            }
+            let mut envelope = None;
+            for charge in (1..=max_charge.value.max(1)).rev() {
+                let spacing = ISOTOPE_SPACING / charge as f64;
+                let mut chain = Vec::new();
+                let mut previous = index;
+                loop {
+                    let expected = MassOverCharge::new::<crate::system::mz>(
+                        peaks[previous].mz.value + spacing,
+                    );
+                    let upper = tolerance.bounds(expected).1;
+                    let limit = if chain.is_empty() {
+                        *peaks[previous].intensity * 2.0
+                    } else {
+                        *peaks[previous].intensity
+                    };
+                    let next = (previous + 1..peaks.len())
+                        .take_while(|i| peaks[*i].mz <= upper)
+                        .filter(|i| !isotope[*i] && tolerance.within(&expected, &peaks[*i].mz))
+                        .max_by(|a, b| peaks[*a].intensity.cmp(&peaks[*b].intensity))
+                        .filter(|i| *peaks[*i].intensity <= limit);
+                    match next {
+                        Some(next) => {
+                            chain.push(next);
+                            previous = next;
+                        }
+                        None => break,
+                    }
+                }
+                if !chain.is_empty() {
+                    envelope = Some((charge, chain));
+                    break;
+                }
+            }
+            let mut peak = peaks[index].clone();
+            if let Some((charge, chain)) = envelope {
+                for i in chain {
+                    isotope[i] = true;
+                    peak.intensity += peaks[i].intensity;
+                }
+                if single_charge {
+                    peak.mz = MassOverCharge::new::<crate::system::mz>(
+                        (peak.mz.value - proton).mul_add(charge as f64, proton),
+                    );
+                }
+            }
+            result.push(peak);
+        }
+        result.sort_unstable();
+        self.spectrum = result;
+    }
+
+    /// Normalise the intensities of all peaks, see [`Normalisation`] for the options.
+    pub fn normalise(&mut self, normalisation: Normalisation) {
+        let factor = match normalisation {
+            Normalisation::Maximum => self
+                .spectrum
+                .iter()
+                .map(|p| *p.intensity)
+                .reduce(f64::max)
+                .unwrap_or_default(),
+            Normalisation::Total => self.spectrum.iter().map(|p| *p.intensity).sum(),
+            Normalisation::SquareRoot | Normalisation::Logarithm => 1.0,
+        };
+        for peak in &mut self.spectrum {
+            peak.intensity = OrderedFloat(match normalisation {
+                Normalisation::Maximum | Normalisation::Total => {
+                    if factor == 0.0 {
+                        *peak.intensity
+                    } else {
+                        *peak.intensity / factor
+                    }
+                }
+                Normalisation::SquareRoot => peak.intensity.0.max(0.0).sqrt(),
+                Normalisation::Logarithm => peak.intensity.0.max(0.0).ln_1p(),
+            });
+        }
+    }
+
+    /// Merge multiple spectra (eg repeated scans of the same precursor) into a single consensus
+    /// spectrum. All peaks are clustered in mz, a peak joins a cluster if it is within the given
+    /// tolerance of the intensity weighted average mz of that cluster. Each cluster results in a
+    /// single peak at the intensity weighted average mz (and ion mobility and CCS if known) with
+    /// the summed intensity.
+    ///
+    /// The metadata is taken from the first spectrum, except for the number of scans and the
+    /// precursor intensity which are summed and the retention time which is averaged.
+    pub fn merge<'a>(
+        spectra: impl IntoIterator<Item = &'a Self>,
+        tolerance: Tolerance<MassOverCharge>,
+    ) -> Self {
+        let mut spectra = spectra.into_iter();
+        let Some(first) = spectra.next() else {
+            return Self::default();
+        };
+        let mut merged = first.clone();
+        let mut peaks = first.spectrum.clone();
+        let mut rts = first.rt.into_iter().collect_vec();
+        for spectrum in spectra {
+            merged.num_scans += spectrum.num_scans;
+            merged.intensity = match (merged.intensity, spectrum.intensity) {
+                (Some(a), Some(b)) => Some(a + b),
+                (a, b) => a.or(b),
+            };
+            rts.extend(spectrum.rt);
+            peaks.extend(spectrum.spectrum.iter().cloned());
+        }
+        merged.rt = (!rts.is_empty())
+            .then(|| rts.iter().fold(Time::default(), |acc, rt| acc + *rt) / rts.len() as f64);
+        peaks.sort_unstable();
+
+        merged.spectrum = Vec::with_capacity(peaks.len());
+        let mut cluster: Option<WeightedPeak> = None;
+        for peak in peaks {
+            if let Some(current) = &mut cluster {
+                if tolerance.within(&current.mz(), &peak.mz) {
+                    current.add(&peak);
+                    continue;
+                }
+                merged
+                    .spectrum
+                    .push(current.finish(OrderedFloat(current.intensity)));
+            }
+            let mut current = WeightedPeak::default();
+            current.add(&peak);
+            cluster = Some(current);
+        }
+        merged
+            .spectrum
+            .extend(cluster.map(|c| c.finish(OrderedFloat(c.intensity))));
+        merged
+    }
+
+    /// Average multiple spectra into a single consensus spectrum. This is identical to
+    /// [`Self::merge`] except that the peak intensities are divided by the number of spectra.
+    pub fn average<'a>(
+        spectra: impl IntoIterator<Item = &'a Self>,
+        tolerance: Tolerance<MassOverCharge>,
+    ) -> Self {
+        let mut count = 0;
+        let mut merged = Self::merge(spectra.into_iter().inspect(|_| count += 1), tolerance);
+        for peak in &mut merged.spectrum {
+            peak.intensity /= f64::from(count);
+        }
+        merged
+    }
+}
+
+/// Centroid the given profile data, see [`RawSpectrum::centroid`]. The data has to be sorted on mz.
+fn centroid_profile(profile: &[RawPeak], maximal_gap: MassOverCharge) -> Vec<RawPeak> {
+    let mut centroided = Vec::new();
+    let mut previous_end = None;
+    for apex in 0..profile.len() {
+        let intensity = profile[apex].intensity;
+        // The first point of a plateau is taken as the apex
+        if *intensity <= 0.0
+            || (apex > 0
+                && profile[apex - 1].intensity >= intensity
+                && profile[apex].mz - profile[apex - 1].mz <= maximal_gap)
+            || (apex + 1 < profile.len()
+                && profile[apex + 1].intensity > intensity
+                && profile[apex + 1].mz - profile[apex].mz <= maximal_gap)
+        {
+            continue;
+        }
+        let mut start = apex;
+        while start > previous_end.map_or(0, |e| e + 1)
+            && *profile[start - 1].intensity > 0.0
+            && profile[start].mz - profile[start - 1].mz <= maximal_gap
+            && profile[start - 1].intensity < profile[start].intensity
+        {
+            start -= 1;
+        }
+        let mut end = apex;
+        while end + 1 < profile.len()
+            && *profile[end + 1].intensity > 0.0
+            && profile[end + 1].mz - profile[end].mz <= maximal_gap
+            && profile[end + 1].intensity <= profile[end].intensity
+        {
+            end += 1;
+        }
+        previous_end = Some(end);
+        let mut weighted = WeightedPeak::default();
+        for point in &profile[start..=end] {
+            weighted.add(point);
+        }
+        centroided.push(weighted.finish(intensity));
+    }
+    centroided
+}
+
+/// Intensity weighted averages of the properties of a group of peaks
+#[derive(Clone, Copy, Debug, Default)]
+struct WeightedPeak {
+    mz: f64,
+    intensity: f64,
+    /// The unweighted sum of mz and number of peaks, used if the total intensity is zero
+    unweighted: (f64, usize),
+    ion_mobility: (f64, f64),
+    ccs: (f64, f64),
+}
+
+impl WeightedPeak {
+    /// Add a peak to this group
+    fn add(&mut self, peak: &RawPeak) {
+        self.mz = peak.mz.value.mul_add(*peak.intensity, self.mz);
+        self.intensity += *peak.intensity;
+        self.unweighted.0 += peak.mz.value;
+        self.unweighted.1 += 1;
+        for (value, sum) in [
+            (peak.ion_mobility, &mut self.ion_mobility),
+            (peak.ccs, &mut self.ccs),
+        ] {
+            if let Some(value) = value {
+                sum.0 = value.mul_add(*peak.intensity, sum.0);
+                sum.1 += *peak.intensity;
+            }
+        }
+    }
+
+    /// The intensity weighted average mz, or the plain average if all peaks have zero intensity
+    fn mz(&self) -> MassOverCharge {
+        MassOverCharge::new::<crate::system::mz>(if self.intensity == 0.0 {
+            self.unweighted.0 / self.unweighted.1 as f64
+        } else {
+            self.mz / self.intensity
+        })
+    }
+
+    /// Create a single peak with the weighted properties and the given intensity
+    fn finish(&self, intensity: OrderedFloat<f64>) -> RawPeak {
+        let average = |(weighted, total): (f64, f64)| (total > 0.0).then(|| weighted / total);
+        RawPeak {
+            mz: self.mz(),
+            intensity,
+            ion_mobility: average(self.ion_mobility),
+            ccs: average(self.ccs),
+        }
     }
 }
 
@@ -123,6 +476,8 @@ impl AnnotatableSpectrum for RawSpectrum {
             rt: self.rt,
             charge: self.charge,
             mass: self.mass,
+            ion_mobility: self.ion_mobility,
+            ccs: self.ccs,
             peptide,
             spectrum: self
                 .spectrum
@@ -133,6 +488,9 @@ impl AnnotatableSpectrum for RawSpectrum {
     }
 
     fn search(&self, query: MassOverCharge, tolerance: Self::Tolerance) -> Option<usize> {
+        if self.spectrum.is_empty() {
+            return None;
+        }
         let index = self
             .spectrum
             .binary_search_by(|p| p.mz.value.total_cmp(&query.value))
@@ -221,13 +579,18 @@ impl PeakSpectrum for RawSpectrum {
     }
 }
 
-/// A raw peak
+/// A raw peak, construct it with [`RawPeak::new`] and optionally add ion mobility information with [`RawPeak::with_ion_mobility`] and [`RawPeak::with_ccs`].
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct RawPeak {
     /// The mz value of this peak
     pub mz: MassOverCharge,
     /// The intensity of this peak
     pub intensity: OrderedFloat<f64>,
+    /// The ion mobility of this peak, for timsTOF data this is the inverse reduced mobility (1/K0 in V·s/cm²)
+    pub ion_mobility: Option<f64>,
+    /// The collisional cross section of this peak (in Å²)
+    pub ccs: Option<f64>,
 }
 
 impl PartialOrd for RawPeak {
@@ -248,14 +611,170 @@ impl PartialEq for RawPeak {
     fn eq(&self, other: &Self) -> bool {
         self.mz.value.total_cmp(&other.mz.value) == Ordering::Equal
             && self.intensity.total_cmp(&other.intensity) == Ordering::Equal
+            && self.ion_mobility.map(OrderedFloat) == other.ion_mobility.map(OrderedFloat)
+            && self.ccs.map(OrderedFloat) == other.ccs.map(OrderedFloat)
     }
 }
 
 impl Eq for RawPeak {}
 
 impl RawPeak {
+    /// Create a new peak without any ion mobility information
+    pub const fn new(mz: MassOverCharge, intensity: f64) -> Self {
+        Self {
+            mz,
+            intensity: OrderedFloat(intensity),
+            ion_mobility: None,
+            ccs: None,
+        }
+    }
+
+    /// Set the ion mobility of this peak (for timsTOF data the inverse reduced mobility, 1/K0 in V·s/cm²)
+    #[must_use]
+    pub const fn with_ion_mobility(self, ion_mobility: Option<f64>) -> Self {
+        Self {
+            ion_mobility,
+            ..self
+        }
+    }
+
+    /// Set the collisional cross section of this peak (in Å²)
+    #[must_use]
+    pub const fn with_ccs(self, ccs: Option<f64>) -> Self {
+        Self { ccs, ..self }
+    }
+
     /// Determine the ppm error for the given fragment
     pub fn ppm(&self, mz: MassOverCharge) -> Ratio {
         self.mz.ppm(mz)
     }
 }
+
+#[cfg(test)]
+#[allow(
+    clippy::float_cmp,
+    clippy::missing_panics_doc,
+    clippy::suboptimal_flops
+)]
+mod tests {
+    use super::*;
+
+    fn peak(mz: f64, intensity: f64) -> RawPeak {
+        RawPeak::new(MassOverCharge::new::<crate::system::mz>(mz), intensity)
+    }
+
+    #[test]
+    fn merge() {
+        let mut a = RawSpectrum {
+            num_scans: 1,
+            rt: Some(Time::new::<crate::system::time::min>(10.0)),
+            ..RawSpectrum::default()
+        };
+        a.extend([peak(100.0, 10.0), peak(200.0, 4.0)]);
+        let mut b = RawSpectrum {
+            num_scans: 1,
+            rt: Some(Time::new::<crate::system::time::min>(12.0)),
+            ..RawSpectrum::default()
+        };
+        b.extend([peak(100.001, 30.0), peak(150.0, 2.0), peak(200.5, 4.0)]);
+        let tolerance = Tolerance::new_ppm(20.0);
+
+        let merged = RawSpectrum::merge([&a, &b], tolerance);
+        assert_eq!(merged.num_scans, 2);
+        assert!((merged.rt.unwrap().get::<crate::system::time::min>() - 11.0).abs() < 1e-10);
+        let peaks = merged.spectrum().collect_vec();
+        assert_eq!(peaks.len(), 4);
+        assert!((peaks[0].mz.value - 100.000_75).abs() < 1e-10);
+        assert_eq!(*peaks[0].intensity, 40.0);
+        assert_eq!(*peaks[1].intensity, 2.0);
+
+        let averaged = RawSpectrum::average([&a, &b], tolerance);
+        assert_eq!(*averaged.spectrum().next().unwrap().intensity, 20.0);
+        assert_eq!(RawSpectrum::merge([], tolerance), RawSpectrum::default());
+    }
+
+    #[test]
+    fn merge_zero_intensity() {
+        let mut a = RawSpectrum::default();
+        a.extend([peak(100.0, 0.0), peak(200.0, 0.0)]);
+        let mut b = RawSpectrum::default();
+        b.extend([peak(100.001, 0.0)]);
+        let tolerance = Tolerance::new_ppm(20.0);
+        for merged in [
+            RawSpectrum::merge([&a, &b], tolerance),
+            RawSpectrum::average([&a, &b], tolerance),
+        ] {
+            let peaks = merged.spectrum().collect_vec();
+            assert_eq!(peaks.len(), 2);
+            assert!((peaks[0].mz.value - 100.000_5).abs() < 1e-10);
+            assert_eq!(*peaks[0].intensity, 0.0);
+            assert_eq!(peaks[1].mz.value, 200.0);
+        }
+    }
+
+    #[test]
+    fn ion_mobility() {
+        let mobile = |mz: f64, intensity: f64, mobility: f64| {
+            peak(mz, intensity).with_ion_mobility(Some(mobility))
+        };
+        let mut spectrum = RawSpectrum {
+            ion_mobility: Some(0.9),
+            ..RawSpectrum::default()
+        };
+        spectrum.extend([
+            mobile(100.0, 10.0, 0.8),
+            mobile(100.001, 30.0, 1.2),
+            mobile(200.0, 5.0, 1.5),
+            peak(300.0, 7.0),
+        ]);
+        let merged = RawSpectrum::merge([&spectrum], Tolerance::new_ppm(20.0));
+        assert_eq!(merged.ion_mobility, Some(0.9));
+        let first = merged.spectrum().next().unwrap();
+        assert!((first.ion_mobility.unwrap() - 1.1).abs() < 1e-10);
+        assert_eq!(merged.spectrum().last().unwrap().ion_mobility, None);
+
+        spectrum.ion_mobility_filter(0.7..1.0);
+        assert_eq!(
+            spectrum.spectrum().map(|p| p.mz.value).collect_vec(),
+            [100.0, 300.0]
+        );
+        let annotated =
+            spectrum.empty_annotated(CompoundPeptidoformIon::pro_forma("PEPTIDE", None).unwrap());
+        assert_eq!(annotated.ion_mobility, Some(0.9));
+        assert_eq!(annotated.spectrum().next().unwrap().ion_mobility, Some(0.8));
+    }
+
+    #[test]
+    fn centroid() {
+        let mut spectrum = RawSpectrum::default();
+        spectrum.extend([
+            peak(99.9, 0.0),
+            peak(100.0, 10.0),
+            peak(100.1, 20.0),
+            peak(100.2, 10.0),
+            peak(100.3, 0.0),
+            peak(200.0, 5.0),
+            peak(200.1, 5.0),
+            peak(200.2, 2.0),
+            peak(200.3, 4.0),
+            peak(200.4, 1.0),
+            peak(300.0, 7.0),
+        ]);
+        spectrum.centroid(MassOverCharge::new::<crate::system::mz>(0.15));
+        let peaks = spectrum.spectrum().collect_vec();
+        assert_eq!(peaks.len(), 4);
+        assert!((peaks[0].mz.value - 100.1).abs() < 1e-10);
+        assert_eq!(*peaks[0].intensity, 20.0);
+        assert!(
+            (peaks[1].mz.value - (200.0 * 5.0 + 200.1 * 5.0 + 200.2 * 2.0) / 12.0).abs() < 1e-10
+        );
+        assert_eq!(*peaks[1].intensity, 5.0);
+        assert!((peaks[2].mz.value - (200.3 * 4.0 + 200.4 * 1.0) / 5.0).abs() < 1e-10);
+        assert_eq!(*peaks[3].intensity, 7.0);
+
+        // Centroiding a centroided spectrum does not change it
+        let before = spectrum.clone();
+        spectrum.centroid(MassOverCharge::new::<crate::system::mz>(0.15));
+        assert_eq!(before, spectrum);
+    }
+}
@@ -0,0 +1,341 @@
+//! Generate sequence tags directly from spectra
+
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    fragment::FragmentKind,
+    spectrum::PeakSpectrum,
+    system::{da, Mass},
+    Chemical, MolecularCharge, Peptidoform, RawSpectrum, SemiAmbiguous, SequenceElement, Tolerance,
+    WithinTolerance,
+};
+
+/// The settings for sequence tag generation, see [`RawSpectrum::sequence_tags`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceTagSettings {
+    /// The tolerance for matching the mass difference between two peaks to a building block.
+    ///
+    /// Default: 0.02 Da.
+    pub tolerance: Tolerance<Mass>,
+    /// The minimal number of residues in a tag (inclusive).
+    ///
+    /// Default: 2.
+    pub min_length: usize,
+    /// The maximal number of residues in a tag (inclusive).
+    ///
+    /// Default: 5.
+    pub max_length: usize,
+    /// The maximal number of tags that are returned, the tags with the highest score are kept.
+    ///
+    /// Default: 50.
+    pub max_tags: usize,
+}
+
+impl Default for SequenceTagSettings {
+    fn default() -> Self {
+        Self {
+            tolerance: Tolerance::new_absolute(da(0.02)),
+            min_length: 2,
+            max_length: 5,
+            max_tags: 50,
+        }
+    }
+}
+
+impl SequenceTagSettings {
+    /// Set the tolerance
+    #[must_use]
+    pub fn tolerance(self, tolerance: Tolerance<Mass>) -> Self {
+        Self { tolerance, ..self }
+    }
+
+    /// Set the minimal and maximal number of residues in a tag (both inclusive)
+    #[must_use]
+    pub const fn length(self, min_length: usize, max_length: usize) -> Self {
+        Self {
+            min_length,
+            max_length,
+            ..self
+        }
+    }
+
+    /// Set the maximal number of tags
+    #[must_use]
+    pub const fn max_tags(self, max_tags: usize) -> Self {
+        Self { max_tags, ..self }
+    }
+}
+
+/// A sequence tag as found by [`RawSpectrum::sequence_tags`]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceTag {
+    /// The sequence of the tag, in N to C terminal order
+    pub sequence: Peptidoform<SemiAmbiguous>,
+    /// The ion series the peaks of this tag are interpreted as, either [`FragmentKind::b`] or
+    /// [`FragmentKind::y`]
+    pub series: FragmentKind,
+    /// The mass of all residues (including modifications) N terminal of this tag. For a b series
+    /// this is always known, for a y series this is only known if the precursor mass is known.
+    pub n_flank: Option<Mass>,
+    /// The mass of all residues (including modifications) C terminal of this tag. For a y series
+    /// this is always known, for a b series this is only known if the precursor mass is known.
+    pub c_flank: Option<Mass>,
+    /// The indices of the peaks (in the spectrum) that support this tag, in order of m/z
+    pub peaks: Vec<usize>,
+    /// The score, the sum of the intensities of the supporting peaks relative to the most intense
+    /// peak in the spectrum
+    pub score: f64,
+}
+
+impl RawSpectrum {
+    /// Extract short sequence tags from this spectrum. This builds a spectrum graph where every
+    /// peak is a node, and two peaks are connected if their mass difference matches the mass of
+    /// one of the given building blocks. All paths through this graph within the length limits
+    /// of the settings are returned as tags. All peaks are assumed to be singly charged (with a
+    /// proton as charge carrier), so consider deconvoluting the spectrum first.
+    ///
+    /// Because it is not known if a path is formed by b or y ions every path results in two tags,
+    /// one for each interpretation, with the flanking masses calculated accordingly. The flanking
+    /// masses allow database filtering, a peptide matches a tag if it contains the tag with the
+    /// flanking masses N and C terminal of it. The C terminal flank of a b series and the N
+    /// terminal flank of a y series are based on the precursor mass, so are only available if
+    /// that is known. Interpretations with a flanking mass that is more negative than the
+    /// tolerance are left out. The tags are sorted on descending score and only the best tags are
+    /// kept.
+    ///
+    /// ```rust
+    /// # use rustyms::{*, spectrum::*};
+    /// # let spectrum = RawSpectrum::default();
+    /// let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+    /// let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default());
+    /// for tag in tags {
+    ///     println!("{:?} {} {:?} {:?}", tag.series, tag.sequence, tag.n_flank, tag.c_flank);
+    /// }
+    /// ```
+    pub fn sequence_tags(
+        &self,
+        building_blocks: &[(SequenceElement<SemiAmbiguous>, Mass)],
+        settings: &SequenceTagSettings,
+    ) -> Vec<SequenceTag> {
+        let peaks = self.spectrum().collect_vec();
+        let Some(heaviest) = building_blocks
+            .iter()
+            .map(|(_, m)| *m)
+            .max_by(|a, b| a.value.total_cmp(&b.value))
+        else {
+            return Vec::new();
+        };
+        let limit = settings.tolerance.bounds(heaviest).1;
+
+        // Build the spectrum graph, edges go from lower to higher m/z
+        let edges = (0..peaks.len())
+            .map(|from| {
+                let mut edges = Vec::new();
+                for to in from + 1..peaks.len() {
+                    let difference = da(peaks[to].mz.value - peaks[from].mz.value);
+                    if difference > limit {
+                        break;
+                    }
+                    for (block, (_, mass)) in building_blocks.iter().enumerate() {
+                        if settings.tolerance.within(mass, &difference) {
+                            edges.push((to, block));
+                        }
+                    }
+                }
+                edges
+            })
+            .collect_vec();
+
+        // Find all paths within the length limits
+        let mut paths = Vec::new();
+        let mut path = Vec::new();
+        for start in 0..peaks.len() {
+            find_paths(
+                &edges,
+                start,
+                &mut path,
+                (settings.min_length.max(1), settings.max_length),
+                &mut paths,
+            );
+        }
+
+        // Create the tags for both interpretations of every path
+        let max_intensity = peaks
+            .iter()
+            .map(|p| *p.intensity)
+            .fold(0.0, f64::max)
+            .max(f64::MIN_POSITIVE);
+        let proton = MolecularCharge::proton(1).formula().monoisotopic_mass();
+        let water = molecular_formula!(H 2 O 1).monoisotopic_mass();
+        let slack = settings.tolerance.bounds(da(0.0)).0;
+        let possible = |flank: Option<Mass>| flank.map_or(true, |flank| flank >= slack);
+        let mut tags = Vec::new();
+        for (start, steps) in paths {
+            let peak_indices = std::iter::once(start)
+                .chain(steps.iter().map(|(peak, _)| *peak))
+                .collect_vec();
+            let score = peak_indices
+                .iter()
+                .map(|i| *peaks[*i].intensity / max_intensity)
+                .sum::<f64>();
+            let first = da(peaks[start].mz.value) - proton;
+            let last = da(peaks[*peak_indices.last().unwrap_or(&start)].mz.value) - proton;
+            let sequence = steps
+                .iter()
+                .map(|(_, block)| building_blocks[*block].0.clone())
+                .collect_vec();
+
+            let b = (Some(first), self.mass.map(|m| m - water - last));
+            if possible(b.0) && possible(b.1) {
+                tags.push(SequenceTag {
+                    sequence: Peptidoform::new(sequence.clone()),
+                    series: FragmentKind::b,
+                    n_flank: b.0,
+                    c_flank: b.1,
+                    peaks: peak_indices.clone(),
+                    score,
+                });
+            }
+            let y = (self.mass.map(|m| m - last), Some(first - water));
+            if possible(y.0) && possible(y.1) {
+                tags.push(SequenceTag {
+                    sequence: Peptidoform::new(sequence.into_iter().rev()),
+                    series: FragmentKind::y,
+                    n_flank: y.0,
+                    c_flank: y.1,
+                    peaks: peak_indices,
+                    score,
+                });
+            }
+        }
+        tags.sort_by(|a, b| b.score.total_cmp(&a.score));
+        tags.truncate(settings.max_tags);
+        tags
+    }
+}
+
+/// Find all paths starting at the given peak with a number of steps within the given length
+/// limits. Every path is given as the start peak and the list of steps (peak and building block).
+fn find_paths(
+    edges: &[Vec<(usize, usize)>],
+    from: usize,
+    path: &mut Vec<(usize, usize)>,
+    length: (usize, usize),
+    paths: &mut Vec<(usize, Vec<(usize, usize)>)>,
+) {
+    let current = path.last().map_or(from, |(peak, _)| *peak);
+    if path.len() >= length.1 {
+        return;
+    }
+    for edge in &edges[current] {
+        path.push(*edge);
+        if path.len() >= length.0 {
+            paths.push((from, path.clone()));
+        }
+        find_paths(edges, from, path, length, paths);
+        path.pop();
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::missing_panics_doc)]
+mod tests {
+    use super::*;
+    use crate::{
+        building_blocks,
+        spectrum::RawPeak,
+        system::{mz, MassOverCharge},
+        AminoAcid, MultiChemical,
+    };
+
+    /// Create a spectrum with the given m/z values (with intensity 1) and precursor mass
+    fn create(mzs: &[f64], mass: Option<f64>) -> RawSpectrum {
+        let mut spectrum = RawSpectrum::default();
+        spectrum.mass = mass.map(da);
+        spectrum.extend(
+            mzs.iter()
+                .map(|m| RawPeak::new(MassOverCharge::new::<mz>(*m), 1.0)),
+        );
+        spectrum
+    }
+
+    #[test]
+    fn tags() {
+        let peptide = Peptidoform::pro_forma("AGWEDK", None)
+            .unwrap()
+            .into_unambiguous()
+            .unwrap();
+        let mass = peptide.formula().monoisotopic_mass().value;
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        // b2 to b5: AG|WED|K
+        let mut b = proton;
+        let mut mzs = Vec::new();
+        for aa in peptide.sequence() {
+            b += aa.aminoacid.aminoacid().formulas()[0]
+                .monoisotopic_mass()
+                .value;
+            mzs.push(b);
+        }
+        let spectrum = create(&mzs[1..5], Some(mass));
+        let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+        let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default());
+        let best = &tags[0];
+        assert_eq!(best.peaks, [0, 1, 2, 3]);
+        let b_tag = tags
+            .iter()
+            .find(|t| t.series == FragmentKind::b && t.peaks.len() == 4)
+            .unwrap();
+        assert_eq!(b_tag.sequence.to_string(), "WED");
+        let ag = molecular_formula!(C 5 H 8 N 2 O 2).monoisotopic_mass();
+        assert!((b_tag.n_flank.unwrap() - ag).value.abs() < 0.001);
+        let k = AminoAcid::Lysine.formulas()[0].monoisotopic_mass();
+        assert!((b_tag.c_flank.unwrap() - k).value.abs() < 0.001);
+        // The y interpretation is not possible as the C terminal flank would be negative
+        assert!(tags
+            .iter()
+            .all(|t| t.series == FragmentKind::b || t.c_flank.unwrap().value > -0.02));
+        // Length limits
+        let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default().length(3, 3));
+        assert!(tags.iter().all(|t| t.sequence.len() == 3));
+        assert!(!tags.is_empty());
+        let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default().max_tags(2));
+        assert_eq!(tags.len(), 2);
+    }
+
+    #[test]
+    fn y_series() {
+        let proton = MolecularCharge::proton(1)
+            .formula()
+            .monoisotopic_mass()
+            .value;
+        let water = molecular_formula!(H 2 O 1).monoisotopic_mass().value;
+        let glycine = AminoAcid::Glycine.formulas()[0].monoisotopic_mass().value;
+        let serine = AminoAcid::Serine.formulas()[0].monoisotopic_mass().value;
+        // y1 of an R, then + S + G, without precursor mass
+        let arginine = AminoAcid::Arginine.formulas()[0].monoisotopic_mass().value;
+        let y1 = arginine + water + proton;
+        let spectrum = create(&[y1, y1 + serine, y1 + serine + glycine], None);
+        let (_, blocks, _) = building_blocks(AminoAcid::UNIQUE_MASS_AMINO_ACIDS, &[], &[]);
+        let tags = spectrum.sequence_tags(&blocks, &SequenceTagSettings::default());
+        let y_tag = tags
+            .iter()
+            .find(|t| t.series == FragmentKind::y && t.peaks.len() == 3)
+            .unwrap();
+        assert_eq!(y_tag.sequence.to_string(), "GS");
+        assert_eq!(y_tag.n_flank, None);
+        assert!((y_tag.c_flank.unwrap().value - arginine).abs() < 0.001);
+        let b_tag = tags
+            .iter()
+            .find(|t| t.series == FragmentKind::b && t.peaks.len() == 3)
+            .unwrap();
+        assert_eq!(b_tag.sequence.to_string(), "SG");
+        assert_eq!(b_tag.c_flank, None);
+        assert!(create(&[], None)
+            .sequence_tags(&blocks, &SequenceTagSettings::default())
+            .is_empty());
+    }
+}
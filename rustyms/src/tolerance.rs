@@ -2,11 +2,10 @@ use std::{fmt::Display, str::FromStr};
 
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
-use uom::fmt::DisplayStyle;
 
 use crate::{
     system::{da, Mass, MassOverCharge, OrderedMass, OrderedRatio, Ratio},
-    Multi,
+    Multi, Precision,
 };
 
 /// A tolerance around a given unit for searching purposes
@@ -85,13 +84,12 @@ impl Display for Tolerance<Mass> {
             "{}",
             match self {
                 Self::Absolute(value) => format!(
-                    "{}",
-                    value.into_format_args(crate::system::mass::dalton, DisplayStyle::Abbreviation)
+                    "{} Da",
+                    Precision::global().format_mass(value.get::<crate::system::mass::dalton>())
                 ),
                 Self::Relative(tolerance) => format!(
-                    "{}",
-                    tolerance
-                        .into_format_args(crate::system::ratio::ppm, DisplayStyle::Abbreviation)
+                    "{} ppm",
+                    Precision::global().format_ppm(tolerance.get::<crate::system::ratio::ppm>())
                 ),
             }
         )